@@ -0,0 +1,122 @@
+/*
+ * C interface of bioristor-lib, enabled by the `ffi` feature.
+ *
+ * The layout of the structures mirrors the `#[repr(C)]` types of the `ffi`
+ * and `params` modules of the library: keep them in sync.
+ */
+
+#ifndef BIORISTOR_H
+#define BIORISTOR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes returned by the functions. */
+typedef enum {
+    BIORISTOR_OK = 0,
+    BIORISTOR_NULL_POINTER = 1,
+    BIORISTOR_INVALID_ALGORITHM = 2,
+    BIORISTOR_NO_SOLUTION = 3,
+    BIORISTOR_NOT_SOLVED = 4,
+} bioristor_status_t;
+
+/* Algorithms solving the equation model. */
+#define BIORISTOR_ALGORITHM_ADAPTIVE ((uint32_t)0)
+#define BIORISTOR_ALGORITHM_ADAPTIVE2 ((uint32_t)1)
+#define BIORISTOR_ALGORITHM_BRUTE_FORCE ((uint32_t)2)
+#define BIORISTOR_ALGORITHM_GRADIENT_DESCENT ((uint32_t)3)
+#define BIORISTOR_ALGORITHM_NEWTON ((uint32_t)4)
+#define BIORISTOR_ALGORITHM_NEURAL_NETWORK ((uint32_t)5)
+
+/* Output currents of the device [Ampere]. */
+typedef struct {
+    float i_ds_off;
+    float i_ds_on;
+    float i_gs_on;
+} bioristor_currents_t;
+
+/* Parameters of the mathematical model. */
+typedef struct {
+    /* Modulation function: a * x + b * ln(x) + c. */
+    float mod_params[3];
+    /* Resistance of the dry channel [Ohm]. */
+    float r_dry;
+    /* Inverse of the stem resistance: a + b * x^0.955. */
+    float res_params[2];
+    /* Input voltages [Volt]. */
+    float v_ds;
+    float v_gs;
+} bioristor_model_params_t;
+
+/* Dependent variables of the model. */
+typedef struct {
+    float concentration;
+    float resistance;
+    float saturation;
+} bioristor_variables_t;
+
+/* Parameters of the algorithms, see bioristor_solver_params_default(). */
+typedef struct {
+    float concentration_init;
+    float concentration_max;
+    float concentration_min;
+    size_t concentration_steps;
+    float grad_tolerance;
+    float learning_rate_init;
+    size_t max_iterations;
+    float min_range_width;
+    float reduction_factor;
+    float resistance_max;
+    float resistance_min;
+    size_t resistance_steps;
+    float saturation_max;
+    float saturation_min;
+    size_t saturation_steps;
+    float tolerance;
+} bioristor_solver_params_t;
+
+/* State of a model, allocated by the caller. Do not access the fields. */
+typedef struct {
+    bioristor_currents_t currents;
+    float loss;
+    bioristor_model_params_t params;
+    bioristor_variables_t result;
+    bioristor_status_t status;
+} bioristor_model_t;
+
+bioristor_status_t bioristor_model_init(bioristor_model_t *model,
+                                        const bioristor_model_params_t *params,
+                                        const bioristor_currents_t *currents);
+
+bioristor_status_t bioristor_model_set_currents(bioristor_model_t *model,
+                                                const bioristor_currents_t *currents);
+
+bioristor_status_t bioristor_solver_params_default(bioristor_solver_params_t *params);
+
+/* `params` can be NULL to use the defaults. */
+bioristor_status_t bioristor_solve(bioristor_model_t *model,
+                                   uint32_t algorithm,
+                                   const bioristor_solver_params_t *params);
+
+/* `loss` can be NULL if not needed. */
+bioristor_status_t bioristor_result(const bioristor_model_t *model,
+                                    bioristor_variables_t *result,
+                                    float *loss);
+
+bioristor_status_t bioristor_error(const bioristor_model_t *model);
+
+/*
+ * Called on a panic of the library when it is built with the
+ * `ffi-panic-handler` feature: it must be defined by the firmware.
+ */
+void bioristor_panic(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BIORISTOR_H */
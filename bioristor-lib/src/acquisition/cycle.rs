@@ -0,0 +1,298 @@
+use crate::params::Currents;
+
+use super::{AveragingSampler, CurrentSource, GateDriver};
+
+/// The parameters of the [`MeasurementCycle`].
+///
+/// The times are expressed in the same unit of the timestamps passed to
+/// [`MeasurementCycle::tick`], e.g. milliseconds.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CycleParams {
+    /// The time to wait after switching the gate off before sampling.
+    pub off_settle_time: u64,
+
+    /// The time to wait after switching the gate on before sampling.
+    pub on_settle_time: u64,
+}
+
+/// The errors of the [`MeasurementCycle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CycleError<S, G> {
+    /// The current source failed.
+    Source(S),
+
+    /// The gate driver failed.
+    Gate(G),
+}
+
+/// The state of the [`MeasurementCycle`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CycleState {
+    /// No measurement in progress.
+    Idle,
+
+    /// The gate is off, waiting for the currents to settle.
+    SettlingOff {
+        /// The time the gate was switched off.
+        since: u64,
+    },
+
+    /// The gate is on, waiting for the currents to settle.
+    SettlingOn {
+        /// The time the gate was switched on.
+        since: u64,
+
+        /// The drain-source current sampled when the gate was off [Ampere].
+        i_ds_off: f32,
+    },
+}
+
+/// State machine acquiring the currents of the device with consistent timing
+/// relative to the gate switching.
+///
+/// A measurement goes through the following steps:
+/// 1. the gate is switched off and the currents are left to settle;
+/// 2. `i_ds_off` is sampled and the gate is switched on;
+/// 3. after the currents have settled again, `i_ds_on` and `i_gs_on` are
+///    sampled, the gate is switched off and the currents are returned, ready
+///    to be solved.
+///
+/// The state machine never blocks: it is driven by periodically calling
+/// [`MeasurementCycle::tick`], e.g. from the main loop or a timer interrupt.
+///
+/// # Type parameters
+///
+/// * `S` - The type of the current source.
+/// * `G` - The type of the gate driver.
+/// * `N` - The number of conversions averaged for every sample.
+pub struct MeasurementCycle<S: CurrentSource, G: GateDriver, const N: usize> {
+    /// The driver of the gate.
+    gate: G,
+
+    /// The parameters of the cycle.
+    params: CycleParams,
+
+    /// The sampler of the currents.
+    sampler: AveragingSampler<S, N>,
+
+    /// The current state.
+    state: CycleState,
+}
+
+impl<S: CurrentSource, G: GateDriver, const N: usize> MeasurementCycle<S, G, N> {
+    /// Creates a new idle state machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the cycle.
+    /// * `sampler` - The sampler of the currents.
+    /// * `gate` - The driver of the gate.
+    pub fn new(params: CycleParams, sampler: AveragingSampler<S, N>, gate: G) -> Self {
+        Self {
+            gate,
+            params,
+            sampler,
+            state: CycleState::Idle,
+        }
+    }
+
+    /// Returns the current state.
+    #[inline]
+    pub fn state(&self) -> CycleState {
+        self.state
+    }
+
+    /// Returns whether a measurement is in progress.
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        self.state != CycleState::Idle
+    }
+
+    /// Aborts the measurement in progress, if any, and switches the gate off.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the gate has been switched off.
+    /// * `Err(error)` - If the gate driver failed.
+    pub fn abort(&mut self) -> Result<(), G::Error> {
+        self.state = CycleState::Idle;
+        self.gate.set_gate(false)
+    }
+
+    /// Releases the sampler and the gate.
+    pub fn release(self) -> (AveragingSampler<S, N>, G) {
+        (self.sampler, self.gate)
+    }
+
+    /// Advances the state machine.
+    ///
+    /// A new measurement is started as soon as the previous one is completed,
+    /// so this method can simply be called periodically.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, from a monotonic clock.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(currents))` - If the measurement has been completed.
+    /// * `Ok(None)` - If the measurement is still in progress.
+    /// * `Err(error)` - If a conversion or the gate driver failed, in which
+    ///   case the measurement is aborted.
+    pub fn tick(&mut self, now: u64) -> Result<Option<Currents>, CycleError<S::Error, G::Error>> {
+        let res = self.step(now);
+        if res.is_err() {
+            // The original error is more relevant than a failure while aborting.
+            let _ = self.abort();
+        }
+        res
+    }
+
+    /// Advances the state machine, without aborting on errors.
+    fn step(&mut self, now: u64) -> Result<Option<Currents>, CycleError<S::Error, G::Error>> {
+        match self.state {
+            CycleState::Idle => {
+                self.gate.set_gate(false).map_err(CycleError::Gate)?;
+                self.state = CycleState::SettlingOff { since: now };
+            }
+            CycleState::SettlingOff { since } => {
+                if now.saturating_sub(since) >= self.params.off_settle_time {
+                    let i_ds_off = self.sampler.sample_off().map_err(CycleError::Source)?;
+                    self.gate.set_gate(true).map_err(CycleError::Gate)?;
+                    self.state = CycleState::SettlingOn {
+                        since: now,
+                        i_ds_off,
+                    };
+                }
+            }
+            CycleState::SettlingOn { since, i_ds_off } => {
+                if now.saturating_sub(since) >= self.params.on_settle_time {
+                    let (i_ds_on, i_gs_on) =
+                        self.sampler.sample_on().map_err(CycleError::Source)?;
+                    self.abort().map_err(CycleError::Gate)?;
+                    return Ok(Some(Currents {
+                        i_ds_off,
+                        i_ds_on,
+                        i_gs_on,
+                    }));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use crate::acquisition::{Channel, ChannelCalibration, SamplerParams};
+
+    use super::*;
+
+    /// Current source returning fixed values per channel, depending on the
+    /// state of the gate, and failing when requested.
+    struct CurrentSourceMock<'a> {
+        gate: &'a Cell<bool>,
+        fail: &'a Cell<bool>,
+    }
+
+    impl CurrentSource for CurrentSourceMock<'_> {
+        type Error = ();
+
+        fn read(&mut self, channel: Channel) -> Result<u16, Self::Error> {
+            if self.fail.get() {
+                return Err(());
+            }
+            match (channel, self.gate.get()) {
+                (Channel::DrainSource, false) => Ok(10),
+                (Channel::DrainSource, true) => Ok(20),
+                (Channel::GateSource, _) => Ok(1),
+            }
+        }
+    }
+
+    const PARAMS: CycleParams = CycleParams {
+        off_settle_time: 10,
+        on_settle_time: 5,
+    };
+
+    const SAMPLER_PARAMS: SamplerParams = SamplerParams {
+        drain_source: ChannelCalibration {
+            gain: 1.0,
+            offset: 0.0,
+        },
+        gate_source: ChannelCalibration {
+            gain: 1.0,
+            offset: 0.0,
+        },
+    };
+
+    #[test]
+    fn test_measurement_cycle() {
+        let gate = Cell::new(true);
+        let fail = Cell::new(false);
+        let sampler = AveragingSampler::<_, 1>::new(
+            SAMPLER_PARAMS,
+            CurrentSourceMock {
+                gate: &gate,
+                fail: &fail,
+            },
+        );
+        let mut cycle = MeasurementCycle::new(PARAMS, sampler, |on| gate.set(on));
+
+        assert_eq!(cycle.tick(0), Ok(None));
+        assert_eq!(cycle.state(), CycleState::SettlingOff { since: 0 });
+        assert!(!gate.get());
+
+        assert_eq!(cycle.tick(9), Ok(None));
+        assert_eq!(cycle.tick(10), Ok(None));
+        assert_eq!(
+            cycle.state(),
+            CycleState::SettlingOn {
+                since: 10,
+                i_ds_off: 10.0
+            }
+        );
+        assert!(gate.get());
+
+        assert_eq!(cycle.tick(14), Ok(None));
+        assert_eq!(
+            cycle.tick(15),
+            Ok(Some(Currents {
+                i_ds_off: 10.0,
+                i_ds_on: 20.0,
+                i_gs_on: 1.0,
+            }))
+        );
+        assert!(!gate.get());
+        assert!(!cycle.is_busy());
+    }
+
+    #[test]
+    fn test_measurement_cycle_error() {
+        let gate = Cell::new(false);
+        let fail = Cell::new(false);
+        let sampler = AveragingSampler::<_, 1>::new(
+            SAMPLER_PARAMS,
+            CurrentSourceMock {
+                gate: &gate,
+                fail: &fail,
+            },
+        );
+        let mut cycle = MeasurementCycle::new(PARAMS, sampler, |on| gate.set(on));
+
+        assert_eq!(cycle.tick(0), Ok(None));
+        assert_eq!(cycle.tick(10), Ok(None));
+        assert!(gate.get());
+
+        fail.set(true);
+        assert_eq!(cycle.tick(20), Err(CycleError::Source(())));
+        assert_eq!(cycle.state(), CycleState::Idle);
+        assert!(!gate.get());
+    }
+}
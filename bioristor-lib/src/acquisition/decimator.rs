@@ -0,0 +1,201 @@
+use crate::params::Currents;
+
+/// The statistic summarizing the samples of a [`Decimator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Summary {
+    /// The arithmetic mean, which reduces the white noise the most.
+    Mean,
+
+    /// The median, which rejects spikes and outliers, e.g. caused by the
+    /// switching of other loads.
+    Median,
+}
+
+impl Summary {
+    /// Summarizes the given values, reordering them.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The values, not empty.
+    ///
+    /// # Returns
+    ///
+    /// The statistic of the values.
+    fn apply(self, values: &mut [f32]) -> f32 {
+        match self {
+            Summary::Mean => values.iter().sum::<f32>() / values.len() as f32,
+            Summary::Median => {
+                values.sort_unstable_by(f32::total_cmp);
+                let mid = values.len() / 2;
+                if values.len().is_multiple_of(2) {
+                    (values[mid - 1] + values[mid]) / 2.0
+                } else {
+                    values[mid]
+                }
+            }
+        }
+    }
+}
+
+/// Decouples the sampling rate of the currents from the solve rate.
+///
+/// The currents are acquired at a fast rate, e.g. from the
+/// [`MeasurementCycle`](super::MeasurementCycle), and every `N` samples are
+/// summarized into a single [`Currents`] to be solved. Each current is
+/// summarized separately, so that the samples taken with the gate off and
+/// the ones taken with the gate on are never mixed.
+///
+/// # Type parameters
+///
+/// * `N` - The number of samples summarized for every solve.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::acquisition::{Decimator, Summary};
+/// use bioristor_lib::params::Currents;
+///
+/// let mut decimator = Decimator::<4>::new(Summary::Median);
+/// let currents = Currents {
+///     i_ds_off: -1.25e-3,
+///     i_ds_on: -0.85e-3,
+///     i_gs_on: 2.4e-6,
+/// };
+/// for _ in 0..3 {
+///     assert_eq!(decimator.push(currents), None);
+/// }
+/// assert_eq!(decimator.push(currents), Some(currents));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Decimator<const N: usize> {
+    /// The buffered drain-source currents with the gate off [Ampere].
+    i_ds_off: [f32; N],
+
+    /// The buffered drain-source currents with the gate on [Ampere].
+    i_ds_on: [f32; N],
+
+    /// The buffered gate-source currents with the gate on [Ampere].
+    i_gs_on: [f32; N],
+
+    /// The number of buffered samples.
+    len: usize,
+
+    /// The statistic summarizing the samples.
+    summary: Summary,
+}
+
+impl<const N: usize> Decimator<N> {
+    /// Creates a new empty decimator.
+    ///
+    /// # Arguments
+    ///
+    /// * `summary` - The statistic summarizing the samples.
+    ///
+    /// # Panics
+    ///
+    /// If `N` is zero.
+    pub fn new(summary: Summary) -> Self {
+        assert!(N > 0, "at least one sample per solve is required");
+        Self {
+            i_ds_off: [0.0; N],
+            i_ds_on: [0.0; N],
+            i_gs_on: [0.0; N],
+            len: 0,
+            summary,
+        }
+    }
+
+    /// Returns the number of buffered samples.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether no sample is buffered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Discards the buffered samples, e.g. after a failed acquisition.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    /// Buffers a new sample.
+    ///
+    /// # Arguments
+    ///
+    /// * `currents` - The sampled currents.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(currents)` - The summary of the last `N` samples, after which
+    ///   the buffer is emptied.
+    /// * `None` - If less than `N` samples have been buffered.
+    pub fn push(&mut self, currents: Currents) -> Option<Currents> {
+        self.i_ds_off[self.len] = currents.i_ds_off;
+        self.i_ds_on[self.len] = currents.i_ds_on;
+        self.i_gs_on[self.len] = currents.i_gs_on;
+        self.len += 1;
+        if self.len < N {
+            return None;
+        }
+
+        self.len = 0;
+        Some(Currents {
+            i_ds_off: self.summary.apply(&mut self.i_ds_off),
+            i_ds_on: self.summary.apply(&mut self.i_ds_on),
+            i_gs_on: self.summary.apply(&mut self.i_gs_on),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn currents(i_ds_off: f32) -> Currents {
+        Currents {
+            i_ds_off,
+            i_ds_on: 2.0 * i_ds_off,
+            i_gs_on: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_decimator_mean() {
+        let mut decimator = Decimator::<4>::new(Summary::Mean);
+        for value in [1.0, 2.0, 3.0] {
+            assert_eq!(decimator.push(currents(value)), None);
+        }
+        assert_eq!(decimator.len(), 3);
+        assert_eq!(decimator.push(currents(10.0)), Some(currents(4.0)));
+        assert!(decimator.is_empty());
+    }
+
+    #[test]
+    fn test_decimator_median() {
+        let mut decimator = Decimator::<3>::new(Summary::Median);
+        decimator.push(currents(1.0));
+        decimator.push(currents(100.0));
+        assert_eq!(decimator.push(currents(2.0)), Some(currents(2.0)));
+
+        let mut decimator = Decimator::<4>::new(Summary::Median);
+        for value in [4.0, 1.0, 100.0] {
+            decimator.push(currents(value));
+        }
+        assert_eq!(decimator.push(currents(2.0)), Some(currents(3.0)));
+    }
+
+    #[test]
+    fn test_decimator_reset() {
+        let mut decimator = Decimator::<2>::new(Summary::Mean);
+        decimator.push(currents(100.0));
+        decimator.reset();
+        decimator.push(currents(1.0));
+        assert_eq!(decimator.push(currents(3.0)), Some(currents(2.0)));
+    }
+}
@@ -0,0 +1,305 @@
+use super::SamplerParams;
+
+/// Common interface for a DMA channel transferring the conversions of an ADC
+/// into a buffer.
+///
+/// The buffer is usually a `&'static mut [u16; N]`, owned by the transfer
+/// while the DMA is writing to it and given back when the transfer is
+/// completed.
+///
+/// # Type parameters
+///
+/// * `B` - The type of the buffers.
+pub trait DmaTransfer<B> {
+    /// The error returned by the hardware.
+    type Error;
+
+    /// Starts a transfer filling the given buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - The buffer to be filled.
+    fn start(&mut self, buffer: B);
+
+    /// Checks whether the current transfer is completed.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((buffer, Ok(())))` - If the buffer has been filled.
+    /// * `Some((buffer, Err(error)))` - If the transfer failed, the content of
+    ///   the buffer is not valid.
+    /// * `None` - If the transfer is still in progress.
+    fn poll(&mut self) -> Option<(B, Result<(), Self::Error>)>;
+}
+
+/// Double-buffered sampling front-end over a [`DmaTransfer`].
+///
+/// While the CPU processes a buffer, e.g. running the solver on the currents
+/// extracted from it, the DMA fills the other one, so that acquisition and
+/// computation overlap. A new transfer is started as soon as the previous
+/// one is completed, hence [`poll`](DoubleBuffer::poll) should be called
+/// often, e.g. from the DMA interrupt.
+///
+/// If a buffer is filled while the previous one has not been read yet, the
+/// previous one is overwritten by the next transfer and an overrun is
+/// counted, so that the newest samples are always available.
+///
+/// # Type parameters
+///
+/// * `D` - The type of the DMA transfer.
+/// * `B` - The type of the buffers.
+pub struct DoubleBuffer<D: DmaTransfer<B>, B> {
+    /// The DMA transfer.
+    dma: D,
+
+    /// The number of failed transfers.
+    errors: u32,
+
+    /// The number of buffers overwritten before being read.
+    overruns: u32,
+
+    /// The buffer filled by the last transfer, not read yet.
+    ready: Option<B>,
+
+    /// The buffer available for the next transfer.
+    spare: Option<B>,
+}
+
+impl<D: DmaTransfer<B>, B: AsRef<[u16]>> DoubleBuffer<D, B> {
+    /// Creates a new front-end and starts the first transfer.
+    ///
+    /// # Arguments
+    ///
+    /// * `dma` - The DMA transfer.
+    /// * `first` - The buffer filled by the first transfer.
+    /// * `second` - The buffer filled while the first one is processed.
+    pub fn new(mut dma: D, first: B, second: B) -> Self {
+        dma.start(first);
+        Self {
+            dma,
+            errors: 0,
+            overruns: 0,
+            ready: None,
+            spare: Some(second),
+        }
+    }
+
+    /// Returns the number of failed transfers.
+    #[inline]
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// Returns the number of buffers overwritten before being read.
+    #[inline]
+    pub fn overruns(&self) -> u32 {
+        self.overruns
+    }
+
+    /// Returns whether a filled buffer is ready to be read.
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        self.ready.is_some()
+    }
+
+    /// Releases the DMA transfer.
+    pub fn release(self) -> D {
+        self.dma
+    }
+
+    /// Checks whether the current transfer is completed and, if so, starts
+    /// the next one.
+    ///
+    /// # Returns
+    ///
+    /// Whether a filled buffer is ready to be read.
+    pub fn poll(&mut self) -> bool {
+        let Some((buffer, result)) = self.dma.poll() else {
+            return self.is_ready();
+        };
+
+        if result.is_err() {
+            // Discard the content and fill the same buffer again.
+            self.errors = self.errors.wrapping_add(1);
+            self.dma.start(buffer);
+            return self.is_ready();
+        }
+
+        let next = match self.spare.take() {
+            Some(spare) => spare,
+            None => {
+                self.overruns = self.overruns.wrapping_add(1);
+                match self.ready.take() {
+                    Some(ready) => ready,
+                    // Both the buffers are in use, cannot happen.
+                    None => {
+                        self.dma.start(buffer);
+                        return false;
+                    }
+                }
+            }
+        };
+        self.dma.start(next);
+        self.ready = Some(buffer);
+        true
+    }
+
+    /// Processes the filled buffer, if any, while the other one is being
+    /// filled by the DMA.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The function processing the samples.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(result)` - The result of `f`.
+    /// * `None` - If no filled buffer is ready.
+    pub fn read<R>(&mut self, f: impl FnOnce(&[u16]) -> R) -> Option<R> {
+        let buffer = self.ready.take()?;
+        let result = f(buffer.as_ref());
+        self.spare = Some(buffer);
+        Some(result)
+    }
+}
+
+/// Converts the raw samples of the two channels, interleaved by the ADC scan
+/// sequence, to the current waveforms.
+///
+/// # Arguments
+///
+/// * `params` - The calibration of the channels.
+/// * `raw` - The raw samples, alternating drain-source and gate-source.
+/// * `i_ds` - The buffer where the drain-source current is written [Ampere].
+/// * `i_gs` - The buffer where the gate-source current is written [Ampere].
+///
+/// # Returns
+///
+/// The number of samples written to each waveform, limited by the shortest
+/// buffer.
+pub fn deinterleave(
+    params: &SamplerParams,
+    raw: &[u16],
+    i_ds: &mut [f32],
+    i_gs: &mut [f32],
+) -> usize {
+    let mut n = 0;
+    for ((pair, ds), gs) in raw.chunks_exact(2).zip(i_ds).zip(i_gs) {
+        *ds = params.drain_source.convert(pair[0] as f32);
+        *gs = params.gate_source.convert(pair[1] as f32);
+        n += 1;
+    }
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::acquisition::ChannelCalibration;
+
+    use super::*;
+
+    /// DMA filling every buffer with the index of the transfer.
+    struct DmaMock {
+        active: Option<&'static mut [u16; 4]>,
+        completed: bool,
+        fail: bool,
+        transfers: u16,
+    }
+
+    impl DmaMock {
+        fn new() -> Self {
+            Self {
+                active: None,
+                completed: false,
+                fail: false,
+                transfers: 0,
+            }
+        }
+    }
+
+    impl DmaTransfer<&'static mut [u16; 4]> for DmaMock {
+        type Error = ();
+
+        fn start(&mut self, buffer: &'static mut [u16; 4]) {
+            assert!(self.active.is_none());
+            self.active = Some(buffer);
+            self.completed = false;
+        }
+
+        fn poll(&mut self) -> Option<(&'static mut [u16; 4], Result<(), ()>)> {
+            if !self.completed {
+                return None;
+            }
+            let buffer = self.active.take()?;
+            if self.fail {
+                return Some((buffer, Err(())));
+            }
+            self.transfers += 1;
+            buffer.fill(self.transfers);
+            Some((buffer, Ok(())))
+        }
+    }
+
+    fn complete(buffer: &mut DoubleBuffer<DmaMock, &'static mut [u16; 4]>) -> bool {
+        buffer.dma.completed = true;
+        buffer.poll()
+    }
+
+    #[test]
+    fn test_double_buffer() {
+        let first = leak([0; 4]);
+        let second = leak([0; 4]);
+        let mut buffer = DoubleBuffer::new(DmaMock::new(), first, second);
+        assert!(!buffer.poll());
+        assert_eq!(buffer.read(|_| ()), None);
+
+        // A new transfer starts while the buffer is processed.
+        assert!(complete(&mut buffer));
+        assert!(buffer.dma.active.is_some());
+        assert_eq!(buffer.read(|samples| samples[0]), Some(1));
+        assert!(!buffer.is_ready());
+
+        // The buffer not read is overwritten.
+        assert!(complete(&mut buffer));
+        assert!(complete(&mut buffer));
+        assert_eq!(buffer.overruns(), 1);
+        assert_eq!(buffer.read(|samples| samples[0]), Some(3));
+
+        // Failed transfers are retried.
+        buffer.dma.fail = true;
+        assert!(!complete(&mut buffer));
+        assert_eq!(buffer.errors(), 1);
+        assert!(buffer.dma.active.is_some());
+        buffer.dma.fail = false;
+        assert!(complete(&mut buffer));
+        assert_eq!(buffer.read(|samples| samples[0]), Some(4));
+    }
+
+    #[test]
+    fn test_deinterleave() {
+        let params = SamplerParams {
+            drain_source: ChannelCalibration {
+                gain: 2.0,
+                offset: 0.0,
+            },
+            gate_source: ChannelCalibration {
+                gain: 1.0,
+                offset: -1.0,
+            },
+        };
+        let mut i_ds = [0.0; 3];
+        let mut i_gs = [0.0; 3];
+        assert_eq!(
+            deinterleave(&params, &[1, 2, 3, 4, 5], &mut i_ds, &mut i_gs),
+            2
+        );
+        assert_eq!(i_ds, [2.0, 6.0, 0.0]);
+        assert_eq!(i_gs, [1.0, 3.0, 0.0]);
+    }
+
+    /// Leaks a buffer, as a `static` buffer of the firmware.
+    fn leak(buffer: [u16; 4]) -> &'static mut [u16; 4] {
+        extern crate std;
+        std::boxed::Box::leak(std::boxed::Box::new(buffer))
+    }
+}
@@ -0,0 +1,199 @@
+use core::convert::Infallible;
+
+use embedded_hal::{digital::OutputPin, pwm::SetDutyCycle};
+
+/// Common interface for the hardware driving the gate of the device.
+pub trait GateDriver {
+    /// The error returned by the hardware.
+    type Error;
+
+    /// Switches the gate on or off.
+    ///
+    /// # Arguments
+    ///
+    /// * `on` - Whether the gate voltage must be applied.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the gate has been switched.
+    /// * `Err(error)` - If the hardware failed.
+    fn set_gate(&mut self, on: bool) -> Result<(), Self::Error>;
+
+    /// Sets the voltage applied when the gate is on.
+    ///
+    /// Drivers with a fixed gate voltage ignore it, which is the default.
+    ///
+    /// # Arguments
+    ///
+    /// * `v_gs` - The gate-source voltage [Volt].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the voltage has been set.
+    /// * `Err(error)` - If the hardware failed.
+    #[inline]
+    fn set_voltage(&mut self, v_gs: f32) -> Result<(), Self::Error> {
+        let _ = v_gs;
+        Ok(())
+    }
+}
+
+/// Plain functions can be used as gate drivers with a fixed voltage.
+impl<F: FnMut(bool)> GateDriver for F {
+    type Error = Infallible;
+
+    #[inline]
+    fn set_gate(&mut self, on: bool) -> Result<(), Self::Error> {
+        self(on);
+        Ok(())
+    }
+}
+
+/// Gate driver switching a fixed gate voltage through a digital output.
+pub struct PinGate<P: OutputPin> {
+    pin: P,
+}
+
+impl<P: OutputPin> PinGate<P> {
+    /// Creates a new driver.
+    ///
+    /// # Arguments
+    ///
+    /// * `pin` - The output pin, driven high when the gate is on.
+    pub fn new(pin: P) -> Self {
+        Self { pin }
+    }
+
+    /// Releases the output pin.
+    pub fn release(self) -> P {
+        self.pin
+    }
+}
+
+impl<P: OutputPin> GateDriver for PinGate<P> {
+    type Error = P::Error;
+
+    #[inline]
+    fn set_gate(&mut self, on: bool) -> Result<(), Self::Error> {
+        self.pin.set_state(on.into())
+    }
+}
+
+/// Gate driver generating the gate voltage with a low-pass filtered PWM
+/// output, acting as a DAC.
+pub struct PwmGate<P: SetDutyCycle> {
+    /// The duty cycle applied when the gate is on.
+    duty: u16,
+
+    /// The voltage corresponding to a duty cycle of 100% [Volt].
+    full_scale: f32,
+
+    /// Whether the gate is on.
+    on: bool,
+
+    /// The PWM channel.
+    pwm: P,
+}
+
+impl<P: SetDutyCycle> PwmGate<P> {
+    /// Creates a new driver, with the gate off.
+    ///
+    /// # Arguments
+    ///
+    /// * `pwm` - The PWM channel.
+    /// * `full_scale` - The voltage corresponding to a duty cycle of 100%
+    ///   [Volt].
+    /// * `v_gs` - The gate-source voltage applied when the gate is on [Volt].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(driver)` - The new driver.
+    /// * `Err(error)` - If the PWM channel failed.
+    pub fn new(pwm: P, full_scale: f32, v_gs: f32) -> Result<Self, P::Error> {
+        let mut gate = Self {
+            duty: 0,
+            full_scale,
+            on: false,
+            pwm,
+        };
+        gate.set_voltage(v_gs)?;
+        gate.set_gate(false)?;
+        Ok(gate)
+    }
+
+    /// Releases the PWM channel.
+    pub fn release(self) -> P {
+        self.pwm
+    }
+}
+
+impl<P: SetDutyCycle> GateDriver for PwmGate<P> {
+    type Error = P::Error;
+
+    fn set_gate(&mut self, on: bool) -> Result<(), Self::Error> {
+        self.on = on;
+        self.pwm.set_duty_cycle(if on { self.duty } else { 0 })
+    }
+
+    fn set_voltage(&mut self, v_gs: f32) -> Result<(), Self::Error> {
+        let max = self.pwm.max_duty_cycle();
+        let fraction = (v_gs / self.full_scale).clamp(0.0, 1.0);
+        self.duty = (fraction * max as f32 + 0.5) as u16;
+
+        if self.on {
+            self.pwm.set_duty_cycle(self.duty)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal::pwm::ErrorType;
+
+    use super::*;
+
+    struct PwmMock {
+        duty: u16,
+    }
+
+    impl ErrorType for PwmMock {
+        type Error = Infallible;
+    }
+
+    impl SetDutyCycle for PwmMock {
+        fn max_duty_cycle(&self) -> u16 {
+            1000
+        }
+
+        fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+            self.duty = duty;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_pwm_gate() {
+        let mut gate = PwmGate::new(PwmMock { duty: 1 }, 3.3, 0.66).unwrap();
+        assert_eq!(gate.pwm.duty, 0);
+
+        gate.set_gate(true).unwrap();
+        assert_eq!(gate.pwm.duty, 200);
+
+        gate.set_voltage(5.0).unwrap();
+        assert_eq!(gate.pwm.duty, 1000);
+
+        gate.set_gate(false).unwrap();
+        gate.set_voltage(1.65).unwrap();
+        assert_eq!(gate.release().duty, 0);
+    }
+
+    #[test]
+    fn test_function_gate() {
+        let mut state = false;
+        let mut gate = |on| state = on;
+        gate.set_gate(true).unwrap();
+        gate.set_voltage(1.0).unwrap();
+        assert!(state);
+    }
+}
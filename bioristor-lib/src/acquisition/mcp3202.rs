@@ -0,0 +1,82 @@
+use embedded_hal::spi::SpiDevice;
+
+use super::{Channel, CurrentSource};
+
+/// Driver of the Microchip MCP3202 12-bit, 2-channel ADC over SPI.
+///
+/// The drain-source current is expected on channel 0 and the gate-source
+/// current on channel 1, both in single-ended mode.
+pub struct Mcp3202<SPI> {
+    spi: SPI,
+}
+
+impl<SPI: SpiDevice> Mcp3202<SPI> {
+    /// Creates a new driver.
+    ///
+    /// # Arguments
+    ///
+    /// * `spi` - The SPI device the ADC is connected to, at most 1.8 MHz
+    ///   when powered at 5 V.
+    pub fn new(spi: SPI) -> Self {
+        Self { spi }
+    }
+
+    /// Releases the SPI device.
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+impl<SPI: SpiDevice> CurrentSource for Mcp3202<SPI> {
+    type Error = SPI::Error;
+
+    fn read(&mut self, channel: Channel) -> Result<u16, Self::Error> {
+        // Start bit, then single-ended mode, channel selection and MSB first.
+        let config = match channel {
+            Channel::DrainSource => 0b1010_0000,
+            Channel::GateSource => 0b1110_0000,
+        };
+        let mut buf = [0x01, config, 0x00];
+        self.spi.transfer_in_place(&mut buf)?;
+
+        Ok(((buf[1] as u16 & 0x0F) << 8) | buf[2] as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal::spi::{ErrorType, Operation};
+
+    use super::*;
+
+    /// SPI device answering with a fixed conversion result.
+    struct SpiDeviceMock {
+        sent: [u8; 3],
+    }
+
+    impl ErrorType for SpiDeviceMock {
+        type Error = core::convert::Infallible;
+    }
+
+    impl SpiDevice for SpiDeviceMock {
+        fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+            for operation in operations {
+                if let Operation::TransferInPlace(buf) = operation {
+                    self.sent.copy_from_slice(buf);
+                    buf.copy_from_slice(&[0xFF, 0xEA, 0xBC]);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_mcp3202() {
+        let mut adc = Mcp3202::new(SpiDeviceMock { sent: [0; 3] });
+
+        assert_eq!(adc.read(Channel::DrainSource), Ok(0x0ABC));
+        assert_eq!(adc.spi.sent, [0x01, 0xA0, 0x00]);
+        assert_eq!(adc.read(Channel::GateSource), Ok(0x0ABC));
+        assert_eq!(adc.release().sent, [0x01, 0xE0, 0x00]);
+    }
+}
@@ -0,0 +1,46 @@
+mod cycle;
+mod decimator;
+mod dma;
+mod gate;
+mod mcp3202;
+mod sampler;
+
+pub use cycle::*;
+pub use decimator::*;
+pub use dma::*;
+pub use gate::*;
+pub use mcp3202::*;
+pub use sampler::*;
+
+/// The current channels of the Bioristor device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Channel {
+    /// The current between drain and source.
+    DrainSource,
+
+    /// The current between gate and source.
+    GateSource,
+}
+
+/// Common interface for the hardware acquiring the currents of the device.
+///
+/// Implementations only provide the raw conversions of an ADC, so that the
+/// oversampling and the conversion to physical units are shared by all the
+/// boards through the [`AveragingSampler`].
+pub trait CurrentSource {
+    /// The error returned by the hardware.
+    type Error;
+
+    /// Performs a single conversion of the given channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel to be converted.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(raw)` - The raw value of the conversion.
+    /// * `Err(error)` - If the conversion failed.
+    fn read(&mut self, channel: Channel) -> Result<u16, Self::Error>;
+}
@@ -0,0 +1,234 @@
+use crate::params::Currents;
+
+use super::{Channel, CurrentSource};
+
+/// The linear conversion from the raw value of an ADC channel to a current.
+///
+/// The current is computed as:
+/// ```text
+/// gain * raw + offset
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChannelCalibration {
+    /// The current corresponding to one unit of the raw value [Ampere].
+    pub gain: f32,
+
+    /// The current corresponding to a raw value of zero [Ampere].
+    pub offset: f32,
+}
+
+impl ChannelCalibration {
+    /// Converts a raw value to a current.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - The raw value, possibly averaged over several conversions.
+    ///
+    /// # Returns
+    ///
+    /// The current [Ampere].
+    #[inline]
+    pub fn convert(&self, raw: f32) -> f32 {
+        self.gain * raw + self.offset
+    }
+}
+
+/// The parameters of the [`AveragingSampler`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SamplerParams {
+    /// The calibration of the drain-source channel.
+    pub drain_source: ChannelCalibration,
+
+    /// The calibration of the gate-source channel.
+    pub gate_source: ChannelCalibration,
+}
+
+/// Sampler that oversamples a [`CurrentSource`] and converts the average of
+/// the raw values to currents.
+///
+/// # Type parameters
+///
+/// * `S` - The type of the current source.
+/// * `N` - The number of conversions averaged for every sample.
+pub struct AveragingSampler<S: CurrentSource, const N: usize> {
+    /// The parameters of the sampler.
+    params: SamplerParams,
+
+    /// The source of the raw values.
+    source: S,
+}
+
+impl<S: CurrentSource, const N: usize> AveragingSampler<S, N> {
+    /// Creates a new sampler.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the sampler.
+    /// * `source` - The source of the raw values.
+    ///
+    /// # Panics
+    ///
+    /// If `N` is zero.
+    pub fn new(params: SamplerParams, source: S) -> Self {
+        assert!(N > 0, "at least one conversion per sample is required");
+        Self { params, source }
+    }
+
+    /// Returns the parameters of the sampler.
+    #[inline]
+    pub fn params(&self) -> &SamplerParams {
+        &self.params
+    }
+
+    /// Releases the current source.
+    pub fn release(self) -> S {
+        self.source
+    }
+
+    /// Samples the current of the given channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel to be sampled.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(current)` - The average current over `N` conversions [Ampere].
+    /// * `Err(error)` - If a conversion failed.
+    pub fn sample(&mut self, channel: Channel) -> Result<f32, S::Error> {
+        let mut sum = 0u32;
+        for _ in 0..N {
+            sum += self.source.read(channel)? as u32;
+        }
+        let raw = sum as f32 / N as f32;
+
+        Ok(match channel {
+            Channel::DrainSource => self.params.drain_source.convert(raw),
+            Channel::GateSource => self.params.gate_source.convert(raw),
+        })
+    }
+
+    /// Samples the currents when the gate is off.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(i_ds_off)` - The drain-source current [Ampere].
+    /// * `Err(error)` - If a conversion failed.
+    pub fn sample_off(&mut self) -> Result<f32, S::Error> {
+        self.sample(Channel::DrainSource)
+    }
+
+    /// Samples the currents when the gate is on.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((i_ds_on, i_gs_on))` - The drain-source and gate-source currents
+    ///   [Ampere].
+    /// * `Err(error)` - If a conversion failed.
+    pub fn sample_on(&mut self) -> Result<(f32, f32), S::Error> {
+        Ok((
+            self.sample(Channel::DrainSource)?,
+            self.sample(Channel::GateSource)?,
+        ))
+    }
+
+    /// Samples all the currents of the device, switching the gate through the
+    /// given function.
+    ///
+    /// # Arguments
+    ///
+    /// * `set_gate` - The function switching the gate on (`true`) or off
+    ///   (`false`), including any settling time.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(currents)` - The currents of the device.
+    /// * `Err(error)` - If a conversion failed.
+    pub fn currents(&mut self, mut set_gate: impl FnMut(bool)) -> Result<Currents, S::Error> {
+        set_gate(false);
+        let i_ds_off = self.sample_off()?;
+
+        set_gate(true);
+        let (i_ds_on, i_gs_on) = self.sample_on()?;
+        set_gate(false);
+
+        Ok(Currents {
+            i_ds_off,
+            i_ds_on,
+            i_gs_on,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use super::*;
+
+    /// Current source returning fixed values per channel, depending on the
+    /// state of the gate.
+    struct CurrentSourceMock<'a> {
+        gate: &'a Cell<bool>,
+        reads: usize,
+    }
+
+    impl CurrentSource for CurrentSourceMock<'_> {
+        type Error = ();
+
+        fn read(&mut self, channel: Channel) -> Result<u16, Self::Error> {
+            self.reads += 1;
+            let jitter = (self.reads % 2) as u16;
+            match (channel, self.gate.get()) {
+                (Channel::DrainSource, false) => Ok(1000 + jitter),
+                (Channel::DrainSource, true) => Ok(2000 + jitter),
+                (Channel::GateSource, true) => Ok(100 + jitter),
+                (Channel::GateSource, false) => Err(()),
+            }
+        }
+    }
+
+    const PARAMS: SamplerParams = SamplerParams {
+        drain_source: ChannelCalibration {
+            gain: 1e-6,
+            offset: 0.0,
+        },
+        gate_source: ChannelCalibration {
+            gain: 1e-7,
+            offset: -1e-6,
+        },
+    };
+
+    #[test]
+    fn test_sample() {
+        let gate = Cell::new(false);
+        let source = CurrentSourceMock {
+            gate: &gate,
+            reads: 0,
+        };
+        let mut sampler = AveragingSampler::<_, 4>::new(PARAMS, source);
+
+        let i_ds_off = sampler.sample_off().unwrap();
+        assert!((i_ds_off - 1000.5e-6).abs() < 1e-9);
+        assert_eq!(sampler.sample(Channel::GateSource), Err(()));
+        assert_eq!(sampler.release().reads, 5);
+    }
+
+    #[test]
+    fn test_currents() {
+        let gate = Cell::new(true);
+        let source = CurrentSourceMock {
+            gate: &gate,
+            reads: 0,
+        };
+        let mut sampler = AveragingSampler::<_, 2>::new(PARAMS, source);
+
+        let currents = sampler.currents(|on| gate.set(on)).unwrap();
+        assert!(!gate.get());
+        assert!((currents.i_ds_off - 1000.5e-6).abs() < 1e-9);
+        assert!((currents.i_ds_on - 2000.5e-6).abs() < 1e-9);
+        assert!((currents.i_gs_on - 9.05e-6).abs() < 1e-9);
+    }
+}
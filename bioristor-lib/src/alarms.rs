@@ -0,0 +1,463 @@
+use crate::params::Variables;
+
+/// The quantities that can be monitored by an [`Alarm`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Quantity {
+    /// The concentration of ions in the electrolyte [Molarity].
+    Concentration,
+
+    /// The water saturation.
+    Saturation,
+}
+
+impl Quantity {
+    /// Returns the value of the quantity in the given variables.
+    #[inline]
+    pub fn get(self, variables: &Variables) -> f32 {
+        match self {
+            Quantity::Concentration => variables.concentration,
+            Quantity::Saturation => variables.saturation,
+        }
+    }
+}
+
+/// The side of the threshold that raises an [`Alarm`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    /// The alarm is raised when the value is above the threshold.
+    Above,
+
+    /// The alarm is raised when the value is below the threshold.
+    Below,
+}
+
+/// The parameters of an [`Alarm`].
+///
+/// The times are expressed in the same unit of the timestamps passed to
+/// [`Alarm::update`], e.g. milliseconds.
+///
+/// # Example
+///
+/// Salinity above 50 mM for more than 10 minutes, with timestamps in
+/// milliseconds:
+/// ```
+/// use bioristor_lib::alarms::{AlarmParams, Direction, Quantity};
+///
+/// let params = AlarmParams {
+///     direction: Direction::Above,
+///     hold_time: 10 * 60 * 1000,
+///     hysteresis: 5e-3,
+///     quantity: Quantity::Concentration,
+///     threshold: 50e-3,
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AlarmParams {
+    /// The side of the threshold that raises the alarm.
+    pub direction: Direction,
+
+    /// The time the value must stay beyond the threshold before the alarm is
+    /// raised.
+    pub hold_time: u64,
+
+    /// The distance from the threshold the value must come back within for
+    /// the alarm to be cleared, which avoids chattering around the threshold.
+    pub hysteresis: f32,
+
+    /// The monitored quantity.
+    pub quantity: Quantity,
+
+    /// The threshold of the alarm.
+    pub threshold: f32,
+}
+
+/// The state of an [`Alarm`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AlarmState {
+    /// The value is within the threshold.
+    Normal,
+
+    /// The value is beyond the threshold, but not for long enough.
+    Pending {
+        /// The time the value went beyond the threshold.
+        since: u64,
+    },
+
+    /// The alarm has been raised.
+    Active,
+}
+
+/// The transitions of an [`Alarm`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AlarmEvent {
+    /// The alarm has been raised.
+    Raised {
+        /// The index of the alarm in the [`Alarms`], zero for a single alarm.
+        index: usize,
+
+        /// The value of the quantity that raised the alarm.
+        value: f32,
+    },
+
+    /// The alarm has been cleared.
+    Cleared {
+        /// The index of the alarm in the [`Alarms`], zero for a single alarm.
+        index: usize,
+
+        /// The value of the quantity that cleared the alarm.
+        value: f32,
+    },
+}
+
+/// Alarm on a threshold of a quantity, with hysteresis and hold time.
+///
+/// The alarm is raised when the value stays beyond the threshold for the hold
+/// time, and it is cleared as soon as the value comes back within the
+/// threshold by more than the hysteresis.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Alarm {
+    /// The parameters of the alarm.
+    params: AlarmParams,
+
+    /// The current state.
+    state: AlarmState,
+}
+
+impl Alarm {
+    /// Creates a new alarm in the normal state.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the alarm.
+    pub fn new(params: AlarmParams) -> Self {
+        Self {
+            params,
+            state: AlarmState::Normal,
+        }
+    }
+
+    /// Returns the parameters of the alarm.
+    #[inline]
+    pub fn params(&self) -> &AlarmParams {
+        &self.params
+    }
+
+    /// Returns the state of the alarm.
+    #[inline]
+    pub fn state(&self) -> AlarmState {
+        self.state
+    }
+
+    /// Returns whether the alarm has been raised.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.state == AlarmState::Active
+    }
+
+    /// Resets the alarm to the normal state, without generating events.
+    pub fn reset(&mut self) {
+        self.state = AlarmState::Normal;
+    }
+
+    /// Updates the alarm with a new solution of the model.
+    ///
+    /// Non-finite values are ignored, so that a failed solution neither
+    /// raises nor clears the alarm.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, non-decreasing between calls.
+    /// * `variables` - The solution of the model.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(event)` - If the alarm has been raised or cleared, with an
+    ///   index of zero.
+    /// * `None` - If the alarm did not change.
+    pub fn update(&mut self, now: u64, variables: &Variables) -> Option<AlarmEvent> {
+        let value = self.params.quantity.get(variables);
+        if !value.is_finite() {
+            return None;
+        }
+
+        let (beyond, cleared) = match self.params.direction {
+            Direction::Above => (
+                value > self.params.threshold,
+                value < self.params.threshold - self.params.hysteresis,
+            ),
+            Direction::Below => (
+                value < self.params.threshold,
+                value > self.params.threshold + self.params.hysteresis,
+            ),
+        };
+
+        match self.state {
+            AlarmState::Normal if beyond => {
+                self.state = AlarmState::Pending { since: now };
+                self.raise(now, value)
+            }
+            AlarmState::Pending { .. } if !beyond => {
+                self.state = AlarmState::Normal;
+                None
+            }
+            AlarmState::Pending { .. } => self.raise(now, value),
+            AlarmState::Active if cleared => {
+                self.state = AlarmState::Normal;
+                Some(AlarmEvent::Cleared { index: 0, value })
+            }
+            _ => None,
+        }
+    }
+
+    /// Raises the pending alarm if the hold time has elapsed.
+    fn raise(&mut self, now: u64, value: f32) -> Option<AlarmEvent> {
+        match self.state {
+            AlarmState::Pending { since } if now.saturating_sub(since) >= self.params.hold_time => {
+                self.state = AlarmState::Active;
+                Some(AlarmEvent::Raised { index: 0, value })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Set of alarms updated together with every solution of the model.
+///
+/// The events are delivered to a callback, that can e.g. drive a GPIO or send
+/// a message, while [`Alarms::flags`] summarizes the active alarms as bit
+/// flags, ready to be sent with the telemetry.
+///
+/// # Type parameters
+///
+/// * `N` - The number of alarms, at most 32.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::alarms::{AlarmEvent, AlarmParams, Alarms, Direction, Quantity};
+/// use bioristor_lib::params::Variables;
+///
+/// let mut alarms = Alarms::new([
+///     AlarmParams {
+///         direction: Direction::Above,
+///         hold_time: 10 * 60 * 1000,
+///         hysteresis: 5e-3,
+///         quantity: Quantity::Concentration,
+///         threshold: 50e-3,
+///     },
+///     AlarmParams {
+///         direction: Direction::Below,
+///         hold_time: 0,
+///         hysteresis: 0.05,
+///         quantity: Quantity::Saturation,
+///         threshold: 0.2,
+///     },
+/// ]);
+///
+/// let variables = Variables {
+///     concentration: 60e-3,
+///     resistance: 40.0,
+///     saturation: 0.1,
+/// };
+/// let mut raised = 0;
+/// alarms.update(0, &variables, |event| {
+///     if let AlarmEvent::Raised { .. } = event {
+///         raised += 1;
+///     }
+/// });
+/// assert_eq!(raised, 1);
+/// assert_eq!(alarms.flags(), 0b10);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Alarms<const N: usize> {
+    /// The alarms.
+    alarms: [Alarm; N],
+}
+
+impl<const N: usize> Alarms<N> {
+    /// Creates a new set of alarms in the normal state.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the alarms.
+    ///
+    /// # Panics
+    ///
+    /// If `N` is greater than 32.
+    pub fn new(params: [AlarmParams; N]) -> Self {
+        assert!(N <= 32, "at most 32 alarms are supported");
+        Self {
+            alarms: params.map(Alarm::new),
+        }
+    }
+
+    /// Returns the alarms.
+    #[inline]
+    pub fn alarms(&self) -> &[Alarm; N] {
+        &self.alarms
+    }
+
+    /// Returns the active alarms as bit flags, where bit `i` is set if the
+    /// alarm with index `i` is active.
+    pub fn flags(&self) -> u32 {
+        self.alarms
+            .iter()
+            .enumerate()
+            .filter(|(_, alarm)| alarm.is_active())
+            .fold(0, |flags, (i, _)| flags | 1 << i)
+    }
+
+    /// Resets all the alarms to the normal state, without generating events.
+    pub fn reset(&mut self) {
+        self.alarms.iter_mut().for_each(Alarm::reset);
+    }
+
+    /// Updates all the alarms with a new solution of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time, non-decreasing between calls.
+    /// * `variables` - The solution of the model.
+    /// * `on_event` - The callback receiving the events, in the order of the
+    ///   alarms.
+    pub fn update(
+        &mut self,
+        now: u64,
+        variables: &Variables,
+        mut on_event: impl FnMut(AlarmEvent),
+    ) {
+        for (i, alarm) in self.alarms.iter_mut().enumerate() {
+            match alarm.update(now, variables) {
+                Some(AlarmEvent::Raised { value, .. }) => {
+                    on_event(AlarmEvent::Raised { index: i, value })
+                }
+                Some(AlarmEvent::Cleared { value, .. }) => {
+                    on_event(AlarmEvent::Cleared { index: i, value })
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALINITY: AlarmParams = AlarmParams {
+        direction: Direction::Above,
+        hold_time: 10,
+        hysteresis: 5e-3,
+        quantity: Quantity::Concentration,
+        threshold: 50e-3,
+    };
+
+    const DRYNESS: AlarmParams = AlarmParams {
+        direction: Direction::Below,
+        hold_time: 0,
+        hysteresis: 0.05,
+        quantity: Quantity::Saturation,
+        threshold: 0.2,
+    };
+
+    fn variables(concentration: f32, saturation: f32) -> Variables {
+        Variables {
+            concentration,
+            resistance: 40.0,
+            saturation,
+        }
+    }
+
+    #[test]
+    fn test_alarm_hold_time() {
+        let mut alarm = Alarm::new(SALINITY);
+
+        assert_eq!(alarm.update(0, &variables(60e-3, 1.0)), None);
+        assert_eq!(alarm.state(), AlarmState::Pending { since: 0 });
+
+        // Going back within the threshold restarts the hold time.
+        assert_eq!(alarm.update(5, &variables(40e-3, 1.0)), None);
+        assert_eq!(alarm.state(), AlarmState::Normal);
+
+        assert_eq!(alarm.update(6, &variables(60e-3, 1.0)), None);
+        assert_eq!(alarm.update(15, &variables(60e-3, 1.0)), None);
+        assert_eq!(
+            alarm.update(16, &variables(70e-3, 1.0)),
+            Some(AlarmEvent::Raised {
+                index: 0,
+                value: 70e-3
+            })
+        );
+        assert!(alarm.is_active());
+        assert_eq!(alarm.update(17, &variables(70e-3, 1.0)), None);
+    }
+
+    #[test]
+    fn test_alarm_hysteresis() {
+        let mut alarm = Alarm::new(DRYNESS);
+
+        assert_eq!(
+            alarm.update(0, &variables(1e-2, 0.1)),
+            Some(AlarmEvent::Raised {
+                index: 0,
+                value: 0.1
+            })
+        );
+        assert_eq!(alarm.update(1, &variables(1e-2, 0.22)), None);
+        assert_eq!(alarm.update(2, &variables(1e-2, f32::NAN)), None);
+        assert!(alarm.is_active());
+        assert_eq!(
+            alarm.update(3, &variables(1e-2, 0.3)),
+            Some(AlarmEvent::Cleared {
+                index: 0,
+                value: 0.3
+            })
+        );
+        assert_eq!(alarm.state(), AlarmState::Normal);
+    }
+
+    #[test]
+    fn test_alarms() {
+        let mut alarms = Alarms::new([SALINITY, DRYNESS]);
+        let mut events = [None; 4];
+        let mut count = 0;
+        let mut on_event = |event| {
+            events[count] = Some(event);
+            count += 1;
+        };
+
+        alarms.update(0, &variables(60e-3, 0.1), &mut on_event);
+        assert_eq!(alarms.flags(), 0b10);
+        alarms.update(10, &variables(60e-3, 0.5), &mut on_event);
+        assert_eq!(alarms.flags(), 0b01);
+
+        assert_eq!(
+            events,
+            [
+                Some(AlarmEvent::Raised {
+                    index: 1,
+                    value: 0.1
+                }),
+                Some(AlarmEvent::Raised {
+                    index: 0,
+                    value: 60e-3
+                }),
+                Some(AlarmEvent::Cleared {
+                    index: 1,
+                    value: 0.5
+                }),
+                None,
+            ]
+        );
+
+        alarms.reset();
+        assert_eq!(alarms.flags(), 0);
+    }
+}
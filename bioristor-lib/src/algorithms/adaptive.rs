@@ -1,7 +1,21 @@
+use core::mem::size_of;
+
+#[cfg(feature = "async")]
+use crate::algorithms::cooperative::YieldEvery;
+#[cfg(feature = "system")]
+use crate::models::SystemModel;
 use crate::{
-    algorithms::Algorithm,
+    algorithms::{
+        check_boundary, checked_solution, chunks,
+        cooperative::{complete, Checkpoint},
+        evaluate_chunk,
+        footprint::CHUNK_BUFFERS,
+        positive_concentration,
+        validation::{check_non_zero, check_range},
+        Algorithm, Footprint, Monitor, ParamsError, SaturationPolicy, SolveEvent, Termination,
+    },
     losses::Loss,
-    models::{EquationModel, Model, SystemModel},
+    models::{EquationModel, Model},
     params::Variables,
     utils::{BestOrderedList, FloatRange},
 };
@@ -19,6 +33,10 @@ pub struct AdaptiveParams {
     /// The maximum number of iterations.
     pub max_iterations: usize,
 
+    /// What to do with a solution whose saturation is outside the physical
+    /// interval.
+    pub saturation_policy: SaturationPolicy,
+
     /// The range of water saturation to search.
     pub saturation_range: FloatRange,
 
@@ -26,6 +44,52 @@ pub struct AdaptiveParams {
     pub resistance_range: FloatRange,
 }
 
+impl AdaptiveParams {
+    /// Checks that the parameters are valid: there is at least one step and
+    /// one iteration and the ranges are finite and not empty.
+    ///
+    /// The initial guess is not checked, since a zero or non-finite one is
+    /// corrected by the algorithm, see [`initial_support`](Self::initial_support).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the parameters are valid.
+    /// * `Err(error)` - The first parameter that is not valid.
+    pub fn validate(&self) -> Result<(), ParamsError> {
+        check_non_zero("concentration_steps", self.concentration_steps)?;
+        check_non_zero("max_iterations", self.max_iterations)?;
+        check_range("saturation_range", &self.saturation_range)?;
+        check_range("resistance_range", &self.resistance_range)
+    }
+
+    /// The initial guess used in place of a zero or non-finite one, in the
+    /// middle of the concentrations usually found in the sap [Molarity].
+    pub const DEFAULT_CONCENTRATION_INIT: f32 = 1e-2;
+
+    /// Returns the initial support of the search, i.e. the center of the
+    /// first range of concentrations.
+    ///
+    /// The range spans a decade on both sides of the support, so a zero
+    /// support gives an empty range forever, and a negative one an inverted
+    /// range: a negative guess is replaced by its magnitude, while a zero or
+    /// non-finite one is replaced by [`Self::DEFAULT_CONCENTRATION_INIT`].
+    ///
+    /// # Returns
+    ///
+    /// * `(support, corrected)` - The support and whether the initial guess
+    ///   had to be corrected.
+    pub fn initial_support(&self) -> (f32, bool) {
+        let init = self.concentration_init;
+        if init > 0.0 && init.is_finite() {
+            (init, false)
+        } else if init < 0.0 && init.is_finite() {
+            (-init, true)
+        } else {
+            (Self::DEFAULT_CONCENTRATION_INIT, true)
+        }
+    }
+}
+
 /// Implementation of the adaptive algorithm for the equation model.
 ///
 /// # Type parameters
@@ -33,7 +97,8 @@ pub struct AdaptiveParams {
 /// * `M` - The model to be solved.
 /// * `L` - The loss function to be used.
 /// * `MINIMA` - The number of minima over which the algorithm will average and
-///     finds the optimal values for the variables.
+///   finds the optimal values for the variables.
+///   It must be greater than zero, otherwise the algorithm does not compile.
 pub struct AdaptiveEquation<M: Model, L: Loss, const MINIMA: usize> {
     /// The parameters of the algorithm.
     params: AdaptiveParams,
@@ -44,55 +109,76 @@ pub struct AdaptiveEquation<M: Model, L: Loss, const MINIMA: usize> {
     _t: core::marker::PhantomData<L>,
 }
 
-impl<M, L, const MINIMA: usize> Algorithm<AdaptiveParams, M> for AdaptiveEquation<M, L, MINIMA>
+impl<M, L, const MINIMA: usize> AdaptiveEquation<M, L, MINIMA>
 where
     M: EquationModel,
     L: Loss<ModelOutput = f32>,
 {
-    /// Create a new instance of the adaptive algorithm.
-    ///
-    /// # Arguments
-    ///
-    /// * `params` - The parameters of the algorithm.
-    /// * `model` - The model to be solved by the algorithm.
-    fn new(params: AdaptiveParams, model: M) -> Self {
-        Self {
-            params,
-            model,
-            _t: core::marker::PhantomData,
-        }
+    /// Returns the static memory required by the algorithm, see [`Footprint`].
+    pub const fn footprint() -> Footprint {
+        Footprint::new(
+            size_of::<Self>(),
+            size_of::<BestOrderedList<f32, MINIMA>>() + CHUNK_BUFFERS,
+        )
     }
 
-    /// Tries to solve the model for the given parameters using the adaptive
-    /// algorithm and returns the best solution found.
-    ///
-    /// # Returns
-    ///
-    /// * `Some((vars, loss))` - The variables and the loss of the solution.
-    /// * `None` - If the algorithm could not find a solution.
-    fn run(&self) -> Option<(Variables, f32)> {
+    /// Solves the model, notifying the checkpoint after every evaluation of
+    /// the model.
+    async fn solve<O: Monitor, C: Checkpoint>(
+        &self,
+        monitor: &mut O,
+        mut checkpoint: C,
+    ) -> Option<(Variables, f32)> {
         // Best solutions found with their error.
         let mut best_list = BestOrderedList::<f32, MINIMA>::new();
 
-        let mut support = self.params.concentration_init;
+        let (mut support, corrected) = self.params.initial_support();
+        if corrected {
+            monitor.event(SolveEvent::InitCorrected);
+        }
+
+        // The index of the best concentration in the grid of the iteration.
+        let mut best_index: Option<(usize, f32)> = None;
 
+        let mut termination = Termination::Exhausted;
         for _ in 0..self.params.max_iterations {
             best_list.clear();
+            best_index = None;
 
             let c_start = support / 10.0;
             let c_end = support * 10.0;
 
             // Perform a brute-force search.
             let range = FloatRange::new(c_start, c_end, self.params.concentration_steps);
-            for concentration in range {
-                // Evaluate the model for the given concentration.
-                let error = L::evaluate(self.model.value(concentration));
+            let concentrations = range.into_iter().map(positive_concentration);
+            let mut index = 0;
+            for (chunk, len) in chunks(concentrations) {
+                // Evaluate the model for a chunk of concentrations.
+                let values = evaluate_chunk(&self.model, monitor, &chunk, len);
+                for (&concentration, &value) in chunk.iter().zip(&values).take(len) {
+                    let error = L::evaluate(value);
+                    if C::SUSPENDS {
+                        checkpoint.evaluated().await;
+                    }
 
-                // Add the solution to the best solutions.
-                best_list.add_solution((concentration, error));
+                    // Add the solution to the best solutions.
+                    if error.is_finite() {
+                        best_list.add_solution((concentration, error));
+                        if best_index.is_none_or(|(_, best)| error < best) {
+                            best_index = Some((index, error));
+                        }
+                    } else {
+                        monitor.event(SolveEvent::NonFiniteCandidate);
+                    }
+                    index += 1;
+                }
             }
 
-            let mean = best_list.mean_concentration();
+            let Some(mean) = best_list.mean_concentration() else {
+                // No concentration of the grid gave a finite error.
+                termination = Termination::Diverged;
+                break;
+            };
             let center = (mean - c_start) / (c_end - c_start);
 
             if center > 0.5 {
@@ -100,27 +186,163 @@ where
             } else {
                 support *= 0.5;
             }
+
+            if !monitor.iteration() {
+                termination = Termination::Stopped;
+                break;
+            }
         }
+        monitor.termination(termination);
+        check_boundary(
+            monitor,
+            best_index.map(|(index, _)| index),
+            self.params.concentration_steps,
+        );
+
+        let best = best_list.best()?;
+        let vars = Variables {
+            concentration: best,
+            resistance: self.model.resistance(best),
+            saturation: self.model.saturation(best),
+        };
+        let loss = L::evaluate(monitor.evaluation(|| self.model.value(best)));
+        checked_solution(monitor, self.params.saturation_policy, vars, loss)
+    }
+}
 
-        let best = best_list.best();
-        Some((
-            Variables {
-                concentration: best,
-                resistance: self.model.resistance(best),
-                saturation: self.model.saturation(best),
-            },
-            L::evaluate(self.model.value(best)),
-        ))
+impl<M, L, const MINIMA: usize> Algorithm<AdaptiveParams, M> for AdaptiveEquation<M, L, MINIMA>
+where
+    M: EquationModel,
+    L: Loss<ModelOutput = f32>,
+{
+    /// Create a new instance of the adaptive algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the algorithm.
+    /// * `model` - The model to be solved by the algorithm.
+    fn new(params: AdaptiveParams, model: M) -> Self {
+        Self {
+            params,
+            model,
+            _t: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns a reference to the model solved by the algorithm.
+    #[inline]
+    fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Tries to solve the model for the given parameters using the adaptive
+    /// algorithm and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    fn run_with<O: Monitor>(&self, monitor: &mut O) -> Option<(Variables, f32)> {
+        complete(self.solve(monitor, ()))
+    }
+
+    /// Tries to solve the model for the given parameters using the adaptive
+    /// algorithm, yielding to the executor every `yield_every` evaluations of
+    /// the model, and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    /// * `yield_every` - The number of evaluations between two yields.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    #[cfg(feature = "async")]
+    fn run_async<O: Monitor>(
+        &self,
+        monitor: &mut O,
+        yield_every: usize,
+    ) -> impl core::future::Future<Output = Option<(Variables, f32)>> {
+        self.solve(monitor, YieldEvery::new(yield_every))
+    }
+}
+
+/// The factor by which the ranges of resistance and saturation searched by
+/// [`AdaptiveSystem`] are reduced, once the best solutions stop moving.
+#[cfg(feature = "system")]
+const RANGE_REDUCTION: f32 = 0.5;
+
+/// Moves a range to a center, without leaving the bounds of the initial
+/// range.
+///
+/// The range is widened by the inverse of [`RANGE_REDUCTION`] if the center
+/// is on one of its edges, other than the bounds, since the optimum may lie
+/// beyond it. It is shrunk by [`RANGE_REDUCTION`] only if the center has
+/// moved by at most a step of the grid since the previous iteration, and it
+/// keeps its width otherwise.
+///
+/// # Arguments
+///
+/// * `range` - The range searched in the last iteration.
+/// * `center` - The center of the new range.
+/// * `previous` - The center of the range searched in the last iteration, if
+///   any.
+/// * `bounds` - The initial range.
+///
+/// # Returns
+///
+/// The range to be searched in the next iteration, with the same steps.
+#[cfg(feature = "system")]
+fn refine(
+    range: &FloatRange,
+    center: f32,
+    previous: Option<f32>,
+    bounds: &FloatRange,
+) -> FloatRange {
+    let width = range.end - range.start;
+    let step = width / range.steps as f32;
+
+    // The end is exclusive, so the last point of the grid is a step before.
+    let on_edge = (center - range.start < 0.5 * step && range.start > bounds.start)
+        || (range.end - center < 1.5 * step && range.end < bounds.end);
+    let width = if on_edge {
+        width / RANGE_REDUCTION
+    } else if previous.is_some_and(|previous| (center - previous).abs() <= step) {
+        width * RANGE_REDUCTION
+    } else {
+        width
     }
+    .min(bounds.end - bounds.start);
+
+    // The range is shifted, rather than cut, to stay within the bounds.
+    let start = (center - 0.5 * width)
+        .min(bounds.end - width)
+        .max(bounds.start);
+    FloatRange::new(start, start + width, range.steps)
 }
 
 /// Implementation of the adaptive algorithm for the system model.
 ///
+/// Like the concentration, whose support is moved towards the best solutions
+/// of every iteration, the ranges of resistance and saturation are moved to
+/// the mean of the best solutions, and shrunk once it stops moving, so that a
+/// coarse grid is enough to reach a fine resolution.
+///
 /// # Type parameters
 ///
 /// * `M` - The type of the model.
 /// * `L` - The type of the loss.
 /// * `MINIMA` - The number of minima to keep track of.
+///   It must be greater than zero, otherwise the algorithm does not compile.
+#[cfg(feature = "system")]
 pub struct AdaptiveSystem<M: Model, L: Loss, const MINIMA: usize> {
     /// The parameters of the algorithm.
     params: AdaptiveParams,
@@ -131,87 +353,214 @@ pub struct AdaptiveSystem<M: Model, L: Loss, const MINIMA: usize> {
     _t: core::marker::PhantomData<L>,
 }
 
-impl<M, L, const MINIMA: usize> Algorithm<AdaptiveParams, M> for AdaptiveSystem<M, L, MINIMA>
+#[cfg(feature = "system")]
+impl<M, L, const MINIMA: usize> AdaptiveSystem<M, L, MINIMA>
 where
     M: SystemModel,
     L: Loss<ModelOutput = [(f32, f32); 3]>,
 {
-    /// Create a new instance of the adaptive algorithm.
-    ///
-    /// # Arguments
-    ///
-    /// * `params` - The parameters of the algorithm.
-    /// * `model` - The model to be solved by the algorithm.
-    fn new(params: AdaptiveParams, model: M) -> Self {
-        Self {
-            params,
-            model,
-            _t: core::marker::PhantomData,
-        }
+    /// Returns the static memory required by the algorithm, see [`Footprint`].
+    pub const fn footprint() -> Footprint {
+        Footprint::new(
+            size_of::<Self>(),
+            size_of::<BestOrderedList<Variables, MINIMA>>(),
+        )
     }
 
-    /// Tries to solve the model for the given parameters using the adaptive
-    /// algorithm and returns the best solution found.
-    ///
-    /// # Returns
-    ///
-    /// * `Some((vars, loss))` - The variables and the loss of the solution.
-    /// * `None` - If the algorithm could not find a solution.
-    fn run(&self) -> Option<(Variables, f32)> {
+    /// Solves the model, notifying the checkpoint after every evaluation of
+    /// the model.
+    async fn solve<O: Monitor, C: Checkpoint>(
+        &self,
+        monitor: &mut O,
+        mut checkpoint: C,
+    ) -> Option<(Variables, f32)> {
         let mut best = BestOrderedList::<Variables, MINIMA>::new();
 
-        let mut support = self.params.concentration_init;
+        let mut resistance_range = self.params.resistance_range.clone();
+        let mut saturation_range = self.params.saturation_range.clone();
 
+        let (mut support, corrected) = self.params.initial_support();
+        if corrected {
+            monitor.event(SolveEvent::InitCorrected);
+        }
+
+        // The index of the best concentration in the grid of the iteration.
+        let mut best_index: Option<(usize, f32)> = None;
+
+        // The best solution of the previous iteration.
+        let mut previous: Option<Variables> = None;
+
+        let mut termination = Termination::Exhausted;
         for _ in 0..self.params.max_iterations {
             best.clear();
+            best_index = None;
 
             let c_start = support / 10.0;
             let c_end = support * 10.0;
 
-            for c in FloatRange::new(c_start, c_end, self.params.concentration_steps) {
-                for s in self.params.saturation_range.clone() {
-                    for r in self.params.resistance_range.clone() {
+            let range = FloatRange::new(c_start, c_end, self.params.concentration_steps);
+            let concentrations = range.into_iter().map(positive_concentration);
+            for (index, c) in concentrations.enumerate() {
+                // The terms depending only on the concentration are computed
+                // once.
+                let value = self.model.value_at(c);
+                for s in saturation_range.clone() {
+                    for r in resistance_range.clone() {
                         // Evaluate the model for the given variables.
                         let vars = Variables {
                             concentration: c,
                             resistance: r,
                             saturation: s,
                         };
-                        let error = L::evaluate(self.model.value(vars));
+                        let error = L::evaluate(monitor.evaluation(|| value(r, s)));
+                        if C::SUSPENDS {
+                            checkpoint.evaluated().await;
+                        }
 
                         // Add the solution to the best solutions.
-                        best.add_solution((vars, error));
+                        if error.is_finite() {
+                            best.add_solution((vars, error));
+                            if best_index.is_none_or(|(_, best)| error < best) {
+                                best_index = Some((index, error));
+                            }
+                        } else {
+                            monitor.event(SolveEvent::NonFiniteCandidate);
+                        }
                     }
                 }
             }
 
-            let mean = best.mean_concentration();
-            let center = (mean - c_start) / (c_end - c_start);
+            let Some((mean, _)) = best.best() else {
+                // No variables of the grid gave a finite error.
+                termination = Termination::Diverged;
+                break;
+            };
+            let center = (mean.concentration - c_start) / (c_end - c_start);
 
             if center > 0.5 {
                 support *= 2.0;
             } else {
                 support *= 0.5;
             }
+
+            resistance_range = refine(
+                &resistance_range,
+                mean.resistance,
+                previous.map(|vars| vars.resistance),
+                &self.params.resistance_range,
+            );
+            saturation_range = refine(
+                &saturation_range,
+                mean.saturation,
+                previous.map(|vars| vars.saturation),
+                &self.params.saturation_range,
+            );
+            previous = Some(mean);
+
+            if !monitor.iteration() {
+                termination = Termination::Stopped;
+                break;
+            }
+        }
+        monitor.termination(termination);
+        check_boundary(
+            monitor,
+            best_index.map(|(index, _)| index),
+            self.params.concentration_steps,
+        );
+
+        let (vars, loss) = best.best()?;
+        checked_solution(monitor, self.params.saturation_policy, vars, loss)
+    }
+}
+
+#[cfg(feature = "system")]
+impl<M, L, const MINIMA: usize> Algorithm<AdaptiveParams, M> for AdaptiveSystem<M, L, MINIMA>
+where
+    M: SystemModel,
+    L: Loss<ModelOutput = [(f32, f32); 3]>,
+{
+    /// Create a new instance of the adaptive algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the algorithm.
+    /// * `model` - The model to be solved by the algorithm.
+    fn new(params: AdaptiveParams, model: M) -> Self {
+        Self {
+            params,
+            model,
+            _t: core::marker::PhantomData,
         }
+    }
+
+    /// Returns a reference to the model solved by the algorithm.
+    #[inline]
+    fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Tries to solve the model for the given parameters using the adaptive
+    /// algorithm and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    fn run_with<O: Monitor>(&self, monitor: &mut O) -> Option<(Variables, f32)> {
+        complete(self.solve(monitor, ()))
+    }
 
-        Some(best.best())
+    /// Tries to solve the model for the given parameters using the adaptive
+    /// algorithm, yielding to the executor every `yield_every` evaluations of
+    /// the model, and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    /// * `yield_every` - The number of evaluations between two yields.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    #[cfg(feature = "async")]
+    fn run_async<O: Monitor>(
+        &self,
+        monitor: &mut O,
+        yield_every: usize,
+    ) -> impl core::future::Future<Output = Option<(Variables, f32)>> {
+        self.solve(monitor, YieldEvery::new(yield_every))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        losses::{Absolute, SumRelative},
-        models::{Model, SystemModel},
+        algorithms::SolveReport,
+        losses::Absolute,
+        models::Model,
         params::{Currents, ModelParams},
     };
+    #[cfg(feature = "system")]
+    use crate::{
+        losses::{MeanRelative, SumRelative},
+        models::SystemModel,
+    };
 
     use super::*;
 
     struct EquationModelMock;
 
     impl Model for EquationModelMock {
+        type Params = ModelParams;
+
         fn new(_: ModelParams, _: Currents) -> Self {
             Self
         }
@@ -243,9 +592,13 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "system")]
     struct SystemModelMock;
 
+    #[cfg(feature = "system")]
     impl Model for SystemModelMock {
+        type Params = ModelParams;
+
         fn new(_: ModelParams, _: Currents) -> Self {
             Self
         }
@@ -259,6 +612,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "system")]
     impl SystemModel for SystemModelMock {
         fn value(&self, vars: Variables) -> [(f32, f32); 3] {
             [
@@ -279,6 +633,7 @@ mod tests {
             concentration_init: 1.0,
             concentration_steps: 500,
             max_iterations: 10,
+            saturation_policy: SaturationPolicy::Keep,
             saturation_range: FloatRange::new(0.0, 10.0, 10),
             resistance_range: FloatRange::new(0.0, 10.0, 10),
         };
@@ -293,23 +648,166 @@ mod tests {
         assert!(error.abs() < 1e-3);
     }
 
+    #[test]
+    fn test_adaptive_equation_degenerate_init() {
+        for concentration_init in [0.0, -1.0, f32::NAN] {
+            let params = AdaptiveParams {
+                concentration_init,
+                concentration_steps: 500,
+                max_iterations: 20,
+                saturation_policy: SaturationPolicy::Keep,
+                saturation_range: FloatRange::new(0.0, 10.0, 10),
+                resistance_range: FloatRange::new(0.0, 10.0, 10),
+            };
+            let algorithm = AdaptiveEquation::<_, Absolute, 5>::new(params, EquationModelMock);
+            let mut report = SolveReport::new();
+            let (variables, _) = algorithm.run_with(&mut report).unwrap();
+
+            assert!(
+                (variables.concentration - 2.0).abs() < 1e-2,
+                "{concentration_init}: {}",
+                variables.concentration
+            );
+            assert!(report.init_corrected);
+        }
+    }
+
+    #[cfg(feature = "system")]
     #[test]
     fn test_adaptive_system() {
         let params = AdaptiveParams {
             concentration_init: 0.0,
             concentration_steps: 10,
             max_iterations: 10,
+            saturation_policy: SaturationPolicy::Keep,
             saturation_range: FloatRange::new(0.0, 10.0, 10),
             resistance_range: FloatRange::new(0.0, 10.0, 10),
         };
         let model = SystemModelMock;
 
         let algorithm = AdaptiveSystem::<_, SumRelative, 5>::new(params, model);
-        let (vars, error) = algorithm.run().unwrap();
-
-        assert_eq!(vars.concentration, 0.0);
+        let mut report = SolveReport::new();
+        let (vars, error) = algorithm.run_with(&mut report).unwrap();
+
+        // The zero initial guess is corrected, so the concentration is
+        // searched in a valid range, down towards zero.
+        assert!(report.init_corrected);
+        assert!(vars.concentration > 0.0);
+        assert!(vars.concentration < 1e-2);
         assert_eq!(vars.resistance, 0.0);
         assert_eq!(vars.saturation, 0.0);
-        assert_eq!(error, 0.0);
+        assert!(error <= 1.0);
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn test_adaptive_system_refine() {
+        /// Model whose solution is inside the ranges, between the points of
+        /// their initial grids.
+        struct InteriorModelMock;
+
+        impl Model for InteriorModelMock {
+            type Params = ModelParams;
+
+            fn new(_: ModelParams, _: Currents) -> Self {
+                Self
+            }
+
+            fn params(&self) -> &ModelParams {
+                unimplemented!()
+            }
+
+            fn currents(&self) -> &Currents {
+                unimplemented!()
+            }
+        }
+
+        impl SystemModel for InteriorModelMock {
+            fn value(&self, vars: Variables) -> [(f32, f32); 3] {
+                [
+                    (2e-2, vars.concentration),
+                    (3.3, vars.resistance),
+                    (0.45, vars.saturation),
+                ]
+            }
+
+            fn jacobian(&self, _: Variables) -> nalgebra::Matrix3<f32> {
+                unimplemented!()
+            }
+        }
+
+        let params = AdaptiveParams {
+            concentration_init: 1e-2,
+            concentration_steps: 100,
+            max_iterations: 10,
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 10),
+            resistance_range: FloatRange::new(0.0, 10.0, 10),
+        };
+        let algorithm = AdaptiveSystem::<_, MeanRelative, 1>::new(params, InteriorModelMock);
+        let (vars, _) = algorithm.run().unwrap();
+
+        // The initial grids have a resolution of 1 and 0.1.
+        assert!((vars.concentration - 2e-2).abs() < 1e-3, "{vars:?}");
+        assert!((vars.resistance - 3.3).abs() < 1e-1, "{vars:?}");
+        assert!((vars.saturation - 0.45).abs() < 1e-2, "{vars:?}");
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn test_adaptive_system_refine_far_init() {
+        /// Model whose best resistance and saturation at a concentration are
+        /// proportional to the inverse of the concentration, so they move
+        /// while the support of the concentration moves to the solution.
+        struct CoupledModelMock;
+
+        impl Model for CoupledModelMock {
+            type Params = ModelParams;
+
+            fn new(_: ModelParams, _: Currents) -> Self {
+                Self
+            }
+
+            fn params(&self) -> &ModelParams {
+                unimplemented!()
+            }
+
+            fn currents(&self) -> &Currents {
+                unimplemented!()
+            }
+        }
+
+        impl SystemModel for CoupledModelMock {
+            fn value(&self, vars: Variables) -> [(f32, f32); 3] {
+                let scale = vars.concentration / 2e-2;
+                [
+                    (2e-2, vars.concentration),
+                    (3.3, vars.resistance * scale),
+                    (0.45, vars.saturation * scale),
+                ]
+            }
+
+            fn jacobian(&self, _: Variables) -> nalgebra::Matrix3<f32> {
+                unimplemented!()
+            }
+        }
+
+        // The initial support of the concentration does not contain the
+        // solution, and the best resistance and saturation of the first
+        // iterations are at a fifth of the solution.
+        let params = AdaptiveParams {
+            concentration_init: 1.0,
+            concentration_steps: 100,
+            max_iterations: 15,
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 10),
+            resistance_range: FloatRange::new(0.0, 10.0, 10),
+        };
+        let algorithm = AdaptiveSystem::<_, MeanRelative, 1>::new(params, CoupledModelMock);
+        let (vars, _) = algorithm.run().unwrap();
+
+        assert!((vars.concentration - 2e-2).abs() < 1e-3, "{vars:?}");
+        assert!((vars.resistance - 3.3).abs() < 1e-1, "{vars:?}");
+        assert!((vars.saturation - 0.45).abs() < 1e-2, "{vars:?}");
     }
 }
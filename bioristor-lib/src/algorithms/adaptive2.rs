@@ -1,5 +1,17 @@
+use core::mem::size_of;
+
+#[cfg(feature = "async")]
+use crate::algorithms::cooperative::YieldEvery;
 use crate::{
-    algorithms::Algorithm,
+    algorithms::{
+        check_boundary, checked_solution, chunks,
+        cooperative::{complete, Checkpoint},
+        evaluate_chunk,
+        footprint::CHUNK_BUFFERS,
+        positive_concentration,
+        validation::{check_non_negative, check_non_zero, check_positive, check_range},
+        Algorithm, Footprint, Monitor, ParamsError, SaturationPolicy, SolveEvent, Termination,
+    },
     losses::Loss,
     models::{EquationModel, Model},
     params::Variables,
@@ -16,6 +28,11 @@ pub struct Adaptive2Params {
     /// The maximum number of iterations.
     pub max_iterations: usize,
 
+    /// The width of the range of concentrations below which the algorithm
+    /// stops, e.g. the resolution of the concentration allowed by the ADC.
+    /// Zero disables the criterion.
+    pub min_range_width: f32,
+
     /// The factor by which the range of concentrations is reduced after each
     /// iteration.
     pub reduction_factor: f32,
@@ -23,6 +40,10 @@ pub struct Adaptive2Params {
     /// The range of wet drain-source resistance to search.
     pub resistance_range: FloatRange,
 
+    /// What to do with a solution whose saturation is outside the physical
+    /// interval.
+    pub saturation_policy: SaturationPolicy,
+
     /// The range of water saturation to search.
     pub saturation_range: FloatRange,
 
@@ -30,6 +51,29 @@ pub struct Adaptive2Params {
     pub tolerance: f32,
 }
 
+impl Adaptive2Params {
+    /// Checks that the parameters are valid: the ranges are finite and not
+    /// empty, there is at least one iteration, the minimum width of the range
+    /// is not negative, the reduction factor is in the open interval `(0, 1)`
+    /// and the tolerance is positive.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the parameters are valid.
+    /// * `Err(error)` - The first parameter that is not valid.
+    pub fn validate(&self) -> Result<(), ParamsError> {
+        check_range("concentration_range", &self.concentration_range)?;
+        check_non_zero("max_iterations", self.max_iterations)?;
+        check_non_negative("min_range_width", self.min_range_width)?;
+        if !(self.reduction_factor > 0.0 && self.reduction_factor < 1.0) {
+            return Err(ParamsError::InvalidReductionFactor("reduction_factor"));
+        }
+        check_range("resistance_range", &self.resistance_range)?;
+        check_range("saturation_range", &self.saturation_range)?;
+        check_positive("tolerance", self.tolerance)
+    }
+}
+
 /// Implementation of the adaptive algorithm v2 for the equation model.
 ///
 /// # Type parameters
@@ -37,7 +81,8 @@ pub struct Adaptive2Params {
 /// * `M` - The model to be solved.
 /// * `L` - The loss function to be used.
 /// * `MINIMA` - The number of minima over which the algorithm will average and
-///     finds the optimal values for the variables.
+///   finds the optimal values for the variables.
+///   It must be greater than zero, otherwise the algorithm does not compile.
 pub struct Adaptive2Equation<M: Model, L: Loss, const MINIMA: usize> {
     /// The parameters of the algorithm.
     params: Adaptive2Params,
@@ -48,33 +93,26 @@ pub struct Adaptive2Equation<M: Model, L: Loss, const MINIMA: usize> {
     _t: core::marker::PhantomData<L>,
 }
 
-impl<M, L, const MINIMA: usize> Algorithm<Adaptive2Params, M> for Adaptive2Equation<M, L, MINIMA>
+impl<M, L, const MINIMA: usize> Adaptive2Equation<M, L, MINIMA>
 where
     M: EquationModel,
     L: Loss<ModelOutput = f32>,
 {
-    /// Create a new instance of the adaptive algorithm v2.
-    ///
-    /// # Arguments
-    ///
-    /// * `params` - The parameters of the algorithm.
-    /// * `model` - The model to be solved by the algorithm.
-    fn new(params: Adaptive2Params, model: M) -> Self {
-        Self {
-            params,
-            model,
-            _t: core::marker::PhantomData,
-        }
+    /// Returns the static memory required by the algorithm, see [`Footprint`].
+    pub const fn footprint() -> Footprint {
+        Footprint::new(
+            size_of::<Self>(),
+            size_of::<BestOrderedList<f32, MINIMA>>() + CHUNK_BUFFERS,
+        )
     }
 
-    /// Tries to solve the model for the given parameters using the adaptive
-    /// algorithm and returns the best solution found.
-    ///
-    /// # Returns
-    ///
-    /// * `Some((vars, loss))` - The variables and the loss of the solution.
-    /// * `None` - If the algorithm could not find a solution.
-    fn run(&self) -> Option<(Variables, f32)> {
+    /// Solves the model, notifying the checkpoint after every evaluation of
+    /// the model.
+    async fn solve<O: Monitor, C: Checkpoint>(
+        &self,
+        monitor: &mut O,
+        mut checkpoint: C,
+    ) -> Option<(Variables, f32)> {
         // Best solutions found with their error.
         let mut best_list = BestOrderedList::<f32, MINIMA>::new();
 
@@ -86,21 +124,48 @@ where
 
         let mut error = f32::INFINITY;
 
+        // The index of the best concentration in the grid of the iteration.
+        let mut best_index: Option<(usize, f32)> = None;
+
         let mut iteration = 0;
+        let mut termination = None;
         while iteration < self.params.max_iterations && error > self.params.tolerance {
             best_list.clear();
-
-            // Perform a brute-force search.
-            for concentration in range {
-                // Evaluate the model for the given concentration.
-                let err = L::evaluate(self.model.value(concentration));
-
-                // Add the solution to the best solutions.
-                best_list.add_solution((concentration, err));
+            best_index = None;
+
+            // Perform a brute-force search, evaluating the model in chunks.
+            let concentrations = range.into_iter().map(positive_concentration);
+            let mut index = 0;
+            for (chunk, len) in chunks(concentrations) {
+                let values = evaluate_chunk(&self.model, monitor, &chunk, len);
+                for (&concentration, &value) in chunk.iter().zip(&values).take(len) {
+                    let err = L::evaluate(value);
+                    if C::SUSPENDS {
+                        checkpoint.evaluated().await;
+                    }
+
+                    // Add the solution to the best solutions.
+                    if err.is_finite() {
+                        best_list.add_solution((concentration, err));
+                        if best_index.is_none_or(|(_, best)| err < best) {
+                            best_index = Some((index, err));
+                        }
+                    } else {
+                        monitor.event(SolveEvent::NonFiniteCandidate);
+                    }
+                    index += 1;
+                }
             }
 
-            let mean = best_list.mean_concentration();
-            error = L::evaluate(self.model.value(mean));
+            let Some(mean) = best_list.mean_concentration() else {
+                // No concentration of the grid gave a finite error.
+                termination = Some(Termination::Diverged);
+                break;
+            };
+            error = L::evaluate(monitor.evaluation(|| self.model.value(mean)));
+            if C::SUSPENDS {
+                checkpoint.evaluated().await;
+            }
 
             range_semi_width *= self.params.reduction_factor;
             range = FloatRange::new(
@@ -110,23 +175,107 @@ where
             );
 
             iteration += 1;
+
+            if !monitor.iteration() {
+                termination = Some(Termination::Stopped);
+                break;
+            }
+
+            // The range is narrower than the resolution of the concentration.
+            if range.end - range.start < self.params.min_range_width {
+                termination = Some(Termination::Converged);
+                break;
+            }
+        }
+        monitor.termination(termination.unwrap_or(if !error.is_finite() {
+            Termination::Diverged
+        } else if error <= self.params.tolerance {
+            Termination::Converged
+        } else {
+            Termination::MaxIterations
+        }));
+        check_boundary(monitor, best_index.map(|(index, _)| index), range_steps);
+
+        let best = best_list.best()?;
+        let vars = Variables {
+            concentration: best,
+            resistance: self.model.resistance(best),
+            saturation: self.model.saturation(best),
+        };
+        let loss = L::evaluate(monitor.evaluation(|| self.model.value(best)));
+        checked_solution(monitor, self.params.saturation_policy, vars, loss)
+    }
+}
+
+impl<M, L, const MINIMA: usize> Algorithm<Adaptive2Params, M> for Adaptive2Equation<M, L, MINIMA>
+where
+    M: EquationModel,
+    L: Loss<ModelOutput = f32>,
+{
+    /// Create a new instance of the adaptive algorithm v2.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the algorithm.
+    /// * `model` - The model to be solved by the algorithm.
+    fn new(params: Adaptive2Params, model: M) -> Self {
+        Self {
+            params,
+            model,
+            _t: core::marker::PhantomData,
         }
+    }
 
-        let best = best_list.best();
-        Some((
-            Variables {
-                concentration: best,
-                resistance: self.model.resistance(best),
-                saturation: self.model.saturation(best),
-            },
-            L::evaluate(self.model.value(best)),
-        ))
+    /// Returns a reference to the model solved by the algorithm.
+    #[inline]
+    fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Tries to solve the model for the given parameters using the adaptive
+    /// algorithm and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    fn run_with<O: Monitor>(&self, monitor: &mut O) -> Option<(Variables, f32)> {
+        complete(self.solve(monitor, ()))
+    }
+
+    /// Tries to solve the model for the given parameters using the adaptive
+    /// algorithm, yielding to the executor every `yield_every` evaluations of
+    /// the model, and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    /// * `yield_every` - The number of evaluations between two yields.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    #[cfg(feature = "async")]
+    fn run_async<O: Monitor>(
+        &self,
+        monitor: &mut O,
+        yield_every: usize,
+    ) -> impl core::future::Future<Output = Option<(Variables, f32)>> {
+        self.solve(monitor, YieldEvery::new(yield_every))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
+        algorithms::SolveReport,
         losses::Absolute,
         models::{EquationModel, Model},
         params::{Currents, ModelParams},
@@ -137,6 +286,8 @@ mod tests {
     struct EquationModelMock;
 
     impl Model for EquationModelMock {
+        type Params = ModelParams;
+
         fn new(_: ModelParams, _: Currents) -> Self {
             Self
         }
@@ -173,8 +324,10 @@ mod tests {
         let params = Adaptive2Params {
             concentration_range: FloatRange::new(0.0, 10.0, 10),
             max_iterations: 10,
+            min_range_width: 0.0,
             reduction_factor: 0.5,
             resistance_range: FloatRange::new(0.0, 10.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
             saturation_range: FloatRange::new(0.0, 10.0, 10),
             tolerance: 1e-3,
         };
@@ -188,4 +341,97 @@ mod tests {
         assert!((variables.saturation - 2.0).abs() < 1e-3);
         assert!(error.abs() < 1e-3);
     }
+
+    #[test]
+    fn test_adaptive2_equation_min_range_width() {
+        let params = Adaptive2Params {
+            concentration_range: FloatRange::new(0.3, 9.3, 10),
+            max_iterations: 10,
+            min_range_width: 2.0,
+            reduction_factor: 0.5,
+            resistance_range: FloatRange::new(0.0, 10.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 10.0, 10),
+            tolerance: 1e-9,
+        };
+        let algorithm = Adaptive2Equation::<_, Absolute, 1>::new(params.clone(), EquationModelMock);
+        let mut report = SolveReport::new();
+
+        // The width of the range is 4.5, 2.25 and then 1.125.
+        assert!(algorithm.run_with(&mut report).is_some());
+        assert_eq!(report.iterations, 3);
+        assert_eq!(report.diagnostics.termination, Some(Termination::Converged));
+
+        // Without the criterion all the iterations are performed.
+        let params = Adaptive2Params {
+            min_range_width: 0.0,
+            ..params
+        };
+        let algorithm = Adaptive2Equation::<_, Absolute, 1>::new(params, EquationModelMock);
+        let mut report = SolveReport::new();
+        assert!(algorithm.run_with(&mut report).is_some());
+        assert_eq!(report.iterations, 10);
+        assert_eq!(
+            report.diagnostics.termination,
+            Some(Termination::MaxIterations)
+        );
+    }
+
+    #[test]
+    fn test_adaptive2_equation_no_solution() {
+        /// Model undefined everywhere, so no candidate is ever added.
+        struct UndefinedModelMock;
+
+        impl Model for UndefinedModelMock {
+            type Params = ModelParams;
+
+            fn new(_: ModelParams, _: Currents) -> Self {
+                Self
+            }
+
+            fn params(&self) -> &ModelParams {
+                unimplemented!()
+            }
+
+            fn currents(&self) -> &Currents {
+                unimplemented!()
+            }
+        }
+
+        impl EquationModel for UndefinedModelMock {
+            fn value(&self, _: f32) -> f32 {
+                f32::NAN
+            }
+
+            fn gradient(&self, _: f32) -> f32 {
+                unimplemented!()
+            }
+
+            fn resistance(&self, concentration: f32) -> f32 {
+                concentration
+            }
+
+            fn saturation(&self, concentration: f32) -> f32 {
+                concentration
+            }
+        }
+
+        let params = Adaptive2Params {
+            concentration_range: FloatRange::new(0.0, 10.0, 10),
+            max_iterations: 10,
+            min_range_width: 0.0,
+            reduction_factor: 0.5,
+            resistance_range: FloatRange::new(0.0, 10.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 10.0, 10),
+            tolerance: 1e-3,
+        };
+        let algorithm = Adaptive2Equation::<_, Absolute, 5>::new(params, UndefinedModelMock);
+        let mut report = SolveReport::new();
+
+        assert_eq!(algorithm.run_with(&mut report), None);
+        assert_eq!(report.iterations, 0);
+        assert_eq!(report.non_finite_candidates, 10);
+        assert_eq!(report.diagnostics.termination, Some(Termination::Diverged));
+    }
 }
@@ -1,7 +1,26 @@
+use core::mem::size_of;
+
+#[cfg(feature = "std")]
+use rayon::prelude::*;
+
+#[cfg(feature = "async")]
+use crate::algorithms::cooperative::YieldEvery;
+#[cfg(feature = "std")]
+use crate::algorithms::CHUNK;
+#[cfg(feature = "system")]
+use crate::models::SystemModel;
 use crate::{
-    algorithms::Algorithm,
+    algorithms::{
+        check_boundary, checked_solution, chunks,
+        cooperative::{complete, Checkpoint},
+        evaluate_chunk,
+        footprint::CHUNK_BUFFERS,
+        positive_concentration,
+        validation::check_range,
+        Algorithm, Footprint, Monitor, ParamsError, SaturationPolicy, SolveEvent, Termination,
+    },
     losses::Loss,
-    models::{EquationModel, Model, SystemModel},
+    models::{EquationModel, Model},
     params::Variables,
     utils::FloatRange,
 };
@@ -16,10 +35,130 @@ pub struct BruteForceParams {
     /// The range of wet drain-source resistance to search.
     pub resistance_range: FloatRange,
 
+    /// What to do with a solution whose saturation is outside the physical
+    /// interval.
+    pub saturation_policy: SaturationPolicy,
+
     /// The range of water saturation to search.
     pub saturation_range: FloatRange,
 }
 
+impl BruteForceParams {
+    /// Checks that the parameters are valid: the ranges are finite, not empty
+    /// and with at least one step.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the parameters are valid.
+    /// * `Err(error)` - The first parameter that is not valid.
+    pub fn validate(&self) -> Result<(), ParamsError> {
+        check_range("concentration_range", &self.concentration_range)?;
+        check_range("resistance_range", &self.resistance_range)?;
+        check_range("saturation_range", &self.saturation_range)
+    }
+
+    /// Splits the range of concentrations into `parts` disjoint chunks and
+    /// returns the parameters searching the chunk with the given index.
+    ///
+    /// The chunks can be solved independently, e.g. one per core, and their
+    /// results combined with [`merge`] to obtain the same solution of the
+    /// whole grid.
+    ///
+    /// # Arguments
+    ///
+    /// * `parts` - The number of chunks, must be greater than zero.
+    /// * `index` - The index of the chunk, from `0` to `parts - 1`.
+    ///
+    /// # Returns
+    ///
+    /// The parameters of the chunk.
+    pub fn split(&self, parts: usize, index: usize) -> Self {
+        Self {
+            concentration_range: self.concentration_range.split(parts, index),
+            ..self.clone()
+        }
+    }
+}
+
+/// Combines the results of the brute force algorithm over the chunks of a
+/// grid returned by [`BruteForceParams::split`].
+///
+/// # Arguments
+///
+/// * `results` - The results of the chunks, in the order of their indices.
+///
+/// # Returns
+///
+/// * `Some((vars, loss))` - The solution with the lowest loss; on ties, the
+///   one of the first chunk, as when solving the whole grid.
+/// * `None` - If no chunk has found a solution.
+pub fn merge(
+    results: impl IntoIterator<Item = Option<(Variables, f32)>>,
+) -> Option<(Variables, f32)> {
+    results
+        .into_iter()
+        .flatten()
+        .fold(None, |best, (vars, error)| match best {
+            Some((_, best_error)) if error >= best_error => best,
+            _ => Some((vars, error)),
+        })
+}
+
+/// The number of concentrations evaluated by a task of the parallel runs of
+/// the equation model, a multiple of [`CHUNK`] so that the concentrations are
+/// batched as in the sequential run.
+#[cfg(feature = "std")]
+const PAR_EQUATION_CHUNK: usize = 256 * CHUNK;
+
+/// Splits the grid of concentrations into the chunks evaluated by the tasks
+/// of a parallel run.
+///
+/// Unlike [`FloatRange::split`], every chunk continues the iteration of the
+/// whole grid, so that its concentrations have the same bits of the
+/// sequential run.
+///
+/// # Arguments
+///
+/// * `range` - The grid of concentrations.
+/// * `len` - The number of concentrations of every chunk but the last one.
+///
+/// # Returns
+///
+/// The index of the first concentration of every chunk and its iterator.
+#[cfg(feature = "std")]
+fn par_chunks(
+    range: &FloatRange,
+    len: usize,
+) -> std::vec::Vec<(usize, impl Iterator<Item = f32> + Send)> {
+    let mut iter = range.clone().into_iter();
+    (0..range.steps)
+        .step_by(len)
+        .map(|first| {
+            let chunk = iter.clone().take(len);
+            iter.nth(len - 1);
+            (first, chunk)
+        })
+        .collect()
+}
+
+/// Returns the better of two candidate solutions of a parallel run: the one
+/// with the lowest loss and, on ties, the first of the grid, as in the
+/// sequential run.
+///
+/// # Arguments
+///
+/// * `a` - The index in the grid of concentrations, the solution and its
+///   loss.
+/// * `b` - The same of the other candidate.
+#[cfg(feature = "std")]
+fn par_best<T>(a: (usize, T, f32), b: (usize, T, f32)) -> (usize, T, f32) {
+    if b.2 < a.2 || (b.2 == a.2 && b.0 < a.0) {
+        b
+    } else {
+        a
+    }
+}
+
 /// Implementation of the brute force algorithm for the equation model.
 ///
 /// # Type parameters
@@ -36,6 +175,72 @@ pub struct BruteForceEquation<M: Model, L: Loss> {
     _t: core::marker::PhantomData<L>,
 }
 
+impl<M, L> BruteForceEquation<M, L>
+where
+    M: EquationModel,
+    L: Loss<ModelOutput = f32>,
+{
+    /// Returns the static memory required by the algorithm, see [`Footprint`].
+    pub const fn footprint() -> Footprint {
+        Footprint::new(size_of::<Self>(), CHUNK_BUFFERS)
+    }
+
+    /// Solves the model, notifying the checkpoint after every evaluation of
+    /// the model.
+    async fn solve<O: Monitor, C: Checkpoint>(
+        &self,
+        monitor: &mut O,
+        mut checkpoint: C,
+    ) -> Option<(Variables, f32)> {
+        let mut best: Option<(usize, f32, f32)> = None;
+
+        let mut termination = Termination::Exhausted;
+        let concentrations = self
+            .params
+            .concentration_range
+            .clone()
+            .into_iter()
+            .map(positive_concentration);
+        let mut index = 0;
+        'grid: for (chunk, len) in chunks(concentrations) {
+            let values = evaluate_chunk(&self.model, monitor, &chunk, len);
+            for (&concentration, &value) in chunk.iter().zip(&values).take(len) {
+                let error = L::evaluate(value);
+                if C::SUSPENDS {
+                    checkpoint.evaluated().await;
+                }
+
+                match best {
+                    _ if !error.is_finite() => monitor.event(SolveEvent::NonFiniteCandidate),
+                    Some((_, _, best_error)) if error < best_error => {
+                        best = Some((index, concentration, error));
+                    }
+                    None => {
+                        best = Some((index, concentration, error));
+                    }
+                    _ => (),
+                }
+                index += 1;
+
+                if !monitor.iteration() {
+                    termination = Termination::Stopped;
+                    break 'grid;
+                }
+            }
+        }
+        monitor.termination(termination);
+
+        let (index, concentration, error) = best?;
+        check_boundary(monitor, Some(index), self.params.concentration_range.steps);
+        let vars = Variables {
+            concentration,
+            resistance: self.model.resistance(concentration),
+            saturation: self.model.saturation(concentration),
+        };
+        checked_solution(monitor, self.params.saturation_policy, vars, error)
+    }
+}
+
 impl<M, L> Algorithm<BruteForceParams, M> for BruteForceEquation<M, L>
 where
     M: EquationModel,
@@ -55,40 +260,101 @@ where
         }
     }
 
+    /// Returns a reference to the model solved by the algorithm.
+    #[inline]
+    fn model(&self) -> &M {
+        &self.model
+    }
+
     /// Tries to solve the model for the given parameters using the brute force
     /// algorithm and returns the best solution found.
     ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    ///
     /// # Returns
     ///
     /// * `Some((vars, loss))` - The variables and the loss of the solution.
     /// * `None` - If the algorithm could not find a solution.
-    fn run(&self) -> Option<(Variables, f32)> {
-        let mut best: Option<(f32, f32)> = None;
-
-        for concentration in self.params.concentration_range.clone() {
-            let error = L::evaluate(self.model.value(concentration));
+    fn run_with<O: Monitor>(&self, monitor: &mut O) -> Option<(Variables, f32)> {
+        complete(self.solve(monitor, ()))
+    }
 
-            match best {
-                Some((_, best_error)) if error < best_error => {
-                    best = Some((concentration, error));
-                }
-                None => {
-                    best = Some((concentration, error));
-                }
-                _ => (),
-            }
-        }
+    /// Tries to solve the model for the given parameters using the brute force
+    /// algorithm, yielding to the executor every `yield_every` evaluations of
+    /// the model, and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    /// * `yield_every` - The number of evaluations between two yields.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    #[cfg(feature = "async")]
+    fn run_async<O: Monitor>(
+        &self,
+        monitor: &mut O,
+        yield_every: usize,
+    ) -> impl core::future::Future<Output = Option<(Variables, f32)>> {
+        self.solve(monitor, YieldEvery::new(yield_every))
+    }
+}
 
-        best.map(|(concentration, error)| {
-            (
-                Variables {
-                    concentration,
-                    resistance: self.model.resistance(concentration),
-                    saturation: self.model.saturation(concentration),
-                },
-                error,
-            )
-        })
+#[cfg(feature = "std")]
+impl<M, L> BruteForceEquation<M, L>
+where
+    M: EquationModel + Sync,
+    L: Loss<ModelOutput = f32>,
+{
+    /// Tries to solve the model for the given parameters using the brute force
+    /// algorithm on all the cores of the host, and returns the best solution
+    /// found.
+    ///
+    /// The grid is split in chunks evaluated in parallel with `rayon`, whose
+    /// best solutions are then merged, so the solution is the same of
+    /// [`Algorithm::run`], bit by bit. No monitor is notified.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    pub fn run_par(&self) -> Option<(Variables, f32)> {
+        // Only the model is shared between the threads.
+        let model = &self.model;
+        let (_, concentration, error) =
+            par_chunks(&self.params.concentration_range, PAR_EQUATION_CHUNK)
+                .into_par_iter()
+                .filter_map(|(first, concentrations)| {
+                    let mut best = None;
+                    let mut index = first;
+                    for (chunk, len) in chunks(concentrations.map(positive_concentration)) {
+                        let values = evaluate_chunk(model, &mut (), &chunk, len);
+                        for (&concentration, &value) in chunk.iter().zip(&values).take(len) {
+                            let error = L::evaluate(value);
+                            if error.is_finite() {
+                                let candidate = (index, concentration, error);
+                                best =
+                                    Some(best.map_or(candidate, |best| par_best(best, candidate)));
+                            }
+                            index += 1;
+                        }
+                    }
+                    best
+                })
+                .reduce_with(par_best)?;
+
+        let vars = Variables {
+            concentration,
+            resistance: self.model.resistance(concentration),
+            saturation: self.model.saturation(concentration),
+        };
+        checked_solution(&mut (), self.params.saturation_policy, vars, error)
     }
 }
 
@@ -98,6 +364,7 @@ where
 ///
 /// * `M` - The type of the model.
 /// * `L` - The type of the loss.
+#[cfg(feature = "system")]
 pub struct BruteForceSystem<M: Model, L: Loss> {
     /// The parameters of the algorithm.
     params: BruteForceParams,
@@ -108,6 +375,76 @@ pub struct BruteForceSystem<M: Model, L: Loss> {
     _t: core::marker::PhantomData<L>,
 }
 
+#[cfg(feature = "system")]
+impl<M, L> BruteForceSystem<M, L>
+where
+    M: SystemModel,
+    L: Loss<ModelOutput = [(f32, f32); 3]>,
+{
+    /// Returns the static memory required by the algorithm, see [`Footprint`].
+    pub const fn footprint() -> Footprint {
+        Footprint::new(size_of::<Self>(), 0)
+    }
+
+    /// Solves the model, notifying the checkpoint after every evaluation of
+    /// the model.
+    async fn solve<O: Monitor, C: Checkpoint>(
+        &self,
+        monitor: &mut O,
+        mut checkpoint: C,
+    ) -> Option<(Variables, f32)> {
+        let mut best: Option<(usize, Variables, f32)> = None;
+
+        let mut termination = Termination::Exhausted;
+        for (index, c) in self
+            .params
+            .concentration_range
+            .clone()
+            .into_iter()
+            .map(positive_concentration)
+            .enumerate()
+        {
+            // The terms depending only on the concentration are computed once.
+            let value = self.model.value_at(c);
+            for r in self.params.resistance_range.clone() {
+                for s in self.params.saturation_range.clone() {
+                    let vars = Variables {
+                        concentration: c,
+                        resistance: r,
+                        saturation: s,
+                    };
+
+                    let error = L::evaluate(monitor.evaluation(|| value(r, s)));
+                    if C::SUSPENDS {
+                        checkpoint.evaluated().await;
+                    }
+
+                    if !error.is_finite() {
+                        monitor.event(SolveEvent::NonFiniteCandidate);
+                    } else if let Some((_, _, best_error)) = best {
+                        if error < best_error {
+                            best = Some((index, vars, error));
+                        }
+                    } else {
+                        best = Some((index, vars, error));
+                    }
+                }
+            }
+
+            if !monitor.iteration() {
+                termination = Termination::Stopped;
+                break;
+            }
+        }
+        monitor.termination(termination);
+
+        let (index, vars, error) = best?;
+        check_boundary(monitor, Some(index), self.params.concentration_range.steps);
+        checked_solution(monitor, self.params.saturation_policy, vars, error)
+    }
+}
+
+#[cfg(feature = "system")]
 impl<M, L> Algorithm<BruteForceParams, M> for BruteForceSystem<M, L>
 where
     M: SystemModel,
@@ -127,55 +464,120 @@ where
         }
     }
 
+    /// Returns a reference to the model solved by the algorithm.
+    #[inline]
+    fn model(&self) -> &M {
+        &self.model
+    }
+
     /// Tries to solve the model for the given parameters using the brute force
     /// algorithm and returns the best solution found.
     ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    ///
     /// # Returns
     ///
     /// * `Some((vars, loss))` - The variables and the loss of the solution.
     /// * `None` - If the algorithm could not find a solution.
-    fn run(&self) -> Option<(Variables, f32)> {
-        let mut best: Option<(Variables, f32)> = None;
-
-        for c in self.params.concentration_range.clone() {
-            for r in self.params.resistance_range.clone() {
-                for s in self.params.saturation_range.clone() {
-                    let vars = Variables {
-                        concentration: c,
-                        resistance: r,
-                        saturation: s,
-                    };
+    fn run_with<O: Monitor>(&self, monitor: &mut O) -> Option<(Variables, f32)> {
+        complete(self.solve(monitor, ()))
+    }
 
-                    let error = L::evaluate(self.model.value(vars));
+    /// Tries to solve the model for the given parameters using the brute force
+    /// algorithm, yielding to the executor every `yield_every` evaluations of
+    /// the model, and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    /// * `yield_every` - The number of evaluations between two yields.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    #[cfg(feature = "async")]
+    fn run_async<O: Monitor>(
+        &self,
+        monitor: &mut O,
+        yield_every: usize,
+    ) -> impl core::future::Future<Output = Option<(Variables, f32)>> {
+        self.solve(monitor, YieldEvery::new(yield_every))
+    }
+}
 
-                    if let Some((_, best_error)) = best {
-                        if error < best_error {
-                            best = Some((vars, error));
+#[cfg(all(feature = "std", feature = "system"))]
+impl<M, L> BruteForceSystem<M, L>
+where
+    M: SystemModel + Sync,
+    L: Loss<ModelOutput = [(f32, f32); 3]>,
+{
+    /// Tries to solve the model for the given parameters using the brute force
+    /// algorithm on all the cores of the host, and returns the best solution
+    /// found.
+    ///
+    /// Every concentration of the grid is evaluated in parallel with `rayon`
+    /// over the ranges of resistance and saturation, and the best solutions
+    /// are then merged, so the solution is the same of [`Algorithm::run`],
+    /// bit by bit. No monitor is notified.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    pub fn run_par(&self) -> Option<(Variables, f32)> {
+        // Only the model and the parameters are shared between the threads.
+        let (model, params) = (&self.model, &self.params);
+        let (_, vars, error) = par_chunks(&params.concentration_range, 1)
+            .into_par_iter()
+            .filter_map(|(index, mut concentrations)| {
+                let c = positive_concentration(concentrations.next()?);
+                let value = model.value_at(c);
+                let mut best = None;
+                for r in params.resistance_range.clone() {
+                    for s in params.saturation_range.clone() {
+                        let vars = Variables {
+                            concentration: c,
+                            resistance: r,
+                            saturation: s,
+                        };
+                        let error = L::evaluate(value(r, s));
+                        if error.is_finite() {
+                            let candidate = (index, vars, error);
+                            best = Some(best.map_or(candidate, |best| par_best(best, candidate)));
                         }
-                    } else {
-                        best = Some((vars, error));
                     }
                 }
-            }
-        }
+                best
+            })
+            .reduce_with(par_best)?;
 
-        best
+        checked_solution(&mut (), self.params.saturation_policy, vars, error)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        losses::{Absolute, SumRelative},
-        models::{Model, SystemModel},
+        algorithms::SolveReport,
+        losses::Absolute,
+        models::Model,
         params::{Currents, ModelParams},
     };
+    #[cfg(feature = "system")]
+    use crate::{algorithms::DEFAULT_CONCENTRATION_MIN, losses::SumRelative, models::SystemModel};
 
     use super::*;
 
     struct EquationModelMock;
 
     impl Model for EquationModelMock {
+        type Params = ModelParams;
+
         fn new(_: ModelParams, _: Currents) -> Self {
             Self
         }
@@ -207,9 +609,13 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "system")]
     struct SystemModelMock;
 
+    #[cfg(feature = "system")]
     impl Model for SystemModelMock {
+        type Params = ModelParams;
+
         fn new(_: ModelParams, _: Currents) -> Self {
             Self
         }
@@ -223,6 +629,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "system")]
     impl SystemModel for SystemModelMock {
         fn value(&self, vars: Variables) -> [(f32, f32); 3] {
             [
@@ -242,6 +649,7 @@ mod tests {
         let params = BruteForceParams {
             concentration_range: FloatRange::new(0.0, 10.0, 10),
             resistance_range: FloatRange::new(0.0, 1.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
             saturation_range: FloatRange::new(0.0, 1.0, 10),
         };
         let model = EquationModelMock;
@@ -255,11 +663,63 @@ mod tests {
         assert!(error.abs() < 1e-6);
     }
 
+    #[test]
+    fn test_brute_force_equation_boundary() {
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(0.0, 10.0, 10),
+            resistance_range: FloatRange::new(0.0, 1.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 10),
+        };
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params, EquationModelMock);
+        let mut report = SolveReport::new();
+        algorithm.run_with(&mut report).unwrap();
+        assert!(!report.boundary_hit);
+
+        // The range ends before the optimum.
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(0.0, 1.0, 10),
+            resistance_range: FloatRange::new(0.0, 1.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 10),
+        };
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params, EquationModelMock);
+        let mut report = SolveReport::new();
+        let (vars, _) = algorithm.run_with(&mut report).unwrap();
+        assert!((vars.concentration - 0.9).abs() < 1e-6);
+        assert!(report.boundary_hit);
+    }
+
+    #[test]
+    fn test_brute_force_equation_saturation_policy() {
+        // The optimum of the mock has a saturation of 2.
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(0.0, 10.0, 10),
+            resistance_range: FloatRange::new(0.0, 1.0, 10),
+            saturation_policy: SaturationPolicy::Clamp,
+            saturation_range: FloatRange::new(0.0, 1.0, 10),
+        };
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params.clone(), EquationModelMock);
+        let (vars, error) = algorithm.run().unwrap();
+        assert!((vars.concentration - 2.0).abs() < 1e-6);
+        assert_eq!(vars.saturation, 1.0);
+        assert!(error.abs() < 1e-6);
+
+        let params = BruteForceParams {
+            saturation_policy: SaturationPolicy::Reject,
+            ..params
+        };
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params, EquationModelMock);
+        assert_eq!(algorithm.run(), None);
+    }
+
+    #[cfg(feature = "system")]
     #[test]
     fn test_brute_force_system() {
         let params = BruteForceParams {
             concentration_range: FloatRange::new(0.0, 1.0, 10),
             resistance_range: FloatRange::new(0.0, 1.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
             saturation_range: FloatRange::new(0.0, 1.0, 10),
         };
         let model = SystemModelMock;
@@ -267,9 +727,289 @@ mod tests {
         let algorithm = BruteForceSystem::<_, SumRelative>::new(params, model);
         let (vars, error) = algorithm.run().unwrap();
 
-        assert_eq!(vars.concentration, 0.0);
+        // The concentration is clamped to the lower bound.
+        assert_eq!(vars.concentration, DEFAULT_CONCENTRATION_MIN);
         assert_eq!(vars.resistance, 0.0);
         assert_eq!(vars.saturation, 0.0);
-        assert_eq!(error, 0.0);
+        assert!(error < 1e-2);
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn test_brute_force_system_saturation_policy() {
+        // The mock is solved by the lowest saturation of the range.
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(0.0, 1.0, 10),
+            resistance_range: FloatRange::new(0.0, 1.0, 10),
+            saturation_policy: SaturationPolicy::Clamp,
+            saturation_range: FloatRange::new(1.5, 2.0, 10),
+        };
+        let algorithm = BruteForceSystem::<_, SumRelative>::new(params.clone(), SystemModelMock);
+        let (vars, _) = algorithm.run().unwrap();
+        assert_eq!(vars.resistance, 0.0);
+        assert_eq!(vars.saturation, 1.0);
+
+        let params = BruteForceParams {
+            saturation_policy: SaturationPolicy::Reject,
+            ..params
+        };
+        let algorithm = BruteForceSystem::<_, SumRelative>::new(params, SystemModelMock);
+        assert_eq!(algorithm.run(), None);
+    }
+
+    #[test]
+    fn test_brute_force_equation_non_finite() {
+        /// Model undefined below one, like the logarithm of the modulation.
+        struct PartialModelMock;
+
+        impl Model for PartialModelMock {
+            type Params = ModelParams;
+
+            fn new(_: ModelParams, _: Currents) -> Self {
+                Self
+            }
+
+            fn params(&self) -> &ModelParams {
+                unimplemented!()
+            }
+
+            fn currents(&self) -> &Currents {
+                unimplemented!()
+            }
+        }
+
+        impl EquationModel for PartialModelMock {
+            fn value(&self, concentration: f32) -> f32 {
+                if concentration < 1.0 {
+                    f32::NAN
+                } else {
+                    (concentration - 2.0).powi(2)
+                }
+            }
+
+            fn gradient(&self, _: f32) -> f32 {
+                unimplemented!()
+            }
+
+            fn resistance(&self, concentration: f32) -> f32 {
+                concentration
+            }
+
+            fn saturation(&self, concentration: f32) -> f32 {
+                concentration
+            }
+        }
+
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(0.0, 10.0, 10),
+            resistance_range: FloatRange::new(0.0, 1.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 10),
+        };
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params.clone(), PartialModelMock);
+        let mut report = SolveReport::new();
+        let (vars, error) = algorithm.run_with(&mut report).unwrap();
+        assert!((vars.concentration - 2.0).abs() < 1e-6);
+        assert!(error.abs() < 1e-6);
+        assert_eq!(report.non_finite_candidates, 1);
+
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(0.0, 1.0, 10),
+            ..params
+        };
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params, PartialModelMock);
+        let mut report = SolveReport::new();
+        assert_eq!(algorithm.run_with(&mut report), None);
+        assert_eq!(report.non_finite_candidates, 10);
+    }
+
+    #[test]
+    fn test_brute_force_equation_stopped() {
+        struct StopAfter(usize);
+
+        impl Monitor for StopAfter {
+            fn iteration(&mut self) -> bool {
+                self.0 -= 1;
+                self.0 > 0
+            }
+        }
+
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(0.0, 10.0, 10),
+            resistance_range: FloatRange::new(0.0, 1.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 10),
+        };
+        let model = EquationModelMock;
+
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params, model);
+        let (vars, error) = algorithm.run_with(&mut StopAfter(2)).unwrap();
+
+        assert!((vars.concentration - 1.0).abs() < 1e-6);
+        assert!((error - 1.0).abs() < 1e-6);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_brute_force_equation_async() {
+        use crate::algorithms::step;
+
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(0.0, 10.0, 10),
+            resistance_range: FloatRange::new(0.0, 1.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 10),
+        };
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params, EquationModelMock);
+
+        // Step the solver until completion, counting the yields.
+        let mut monitor = ();
+        let mut solver = core::pin::pin!(algorithm.run_async(&mut monitor, 3));
+        let mut yields = 0;
+        let result = loop {
+            match step(solver.as_mut()) {
+                Some(result) => break result,
+                None => yields += 1,
+            }
+        };
+
+        assert_eq!(result, algorithm.run());
+        assert_eq!(yields, 3);
+    }
+
+    #[test]
+    fn test_brute_force_split_merge() {
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(0.0, 10.0, 100),
+            resistance_range: FloatRange::new(0.0, 1.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 10),
+        };
+
+        let chunks = [0, 1, 2].map(|index| {
+            BruteForceEquation::<_, Absolute>::new(params.split(3, index), EquationModelMock).run()
+        });
+        let (vars, error) = merge(chunks).unwrap();
+
+        let (expected, expected_error) =
+            BruteForceEquation::<_, Absolute>::new(params, EquationModelMock)
+                .run()
+                .unwrap();
+        assert!((vars.concentration - expected.concentration).abs() < 1e-5);
+        assert!((error - expected_error).abs() < 1e-5);
+        assert_eq!(merge([None, None]), None);
+    }
+
+    #[cfg(all(feature = "std", feature = "system"))]
+    #[test]
+    fn test_brute_force_par() {
+        use crate::{
+            models::{Equation, System},
+            params::{ModulationParams, StemResistanceInvParams, Voltages},
+        };
+
+        let model_params = ModelParams {
+            mod_params: ModulationParams(0.0, -0.01463, -0.32),
+            r_dry: 38.2,
+            res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+            voltages: Voltages {
+                v_ds: -0.05,
+                v_gs: 0.5,
+            },
+        };
+        let currents = Currents {
+            i_ds_on: -0.0026829,
+            i_ds_off: -0.0030365,
+            i_gs_on: 1.169828e-6,
+        };
+
+        // The grid spans several chunks, the last of which is partial.
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(1e-4, 1e-1, 10 * PAR_EQUATION_CHUNK + 3),
+            resistance_range: FloatRange::new(10.0, 100.0, 20),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 20),
+        };
+        let algorithm = BruteForceEquation::<_, Absolute>::new(
+            params.clone(),
+            Equation::new(model_params.clone(), currents),
+        );
+        let solution = algorithm.run().unwrap();
+        let par_solution = algorithm.run_par().unwrap();
+        assert_eq!(par_solution.0, solution.0);
+        assert_eq!(par_solution.1.to_bits(), solution.1.to_bits());
+
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(1e-4, 1e-1, 100),
+            ..params
+        };
+        let algorithm =
+            BruteForceSystem::<_, SumRelative>::new(params, System::new(model_params, currents));
+        let solution = algorithm.run().unwrap();
+        let par_solution = algorithm.run_par().unwrap();
+        assert_eq!(par_solution.0, solution.0);
+        assert_eq!(par_solution.1.to_bits(), solution.1.to_bits());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_brute_force_par_ties() {
+        /// Model with the same value at every concentration.
+        struct FlatModelMock;
+
+        impl Model for FlatModelMock {
+            type Params = ModelParams;
+
+            fn new(_: ModelParams, _: Currents) -> Self {
+                Self
+            }
+
+            fn params(&self) -> &ModelParams {
+                unimplemented!()
+            }
+
+            fn currents(&self) -> &Currents {
+                unimplemented!()
+            }
+        }
+
+        impl EquationModel for FlatModelMock {
+            fn value(&self, _: f32) -> f32 {
+                1.0
+            }
+
+            fn gradient(&self, _: f32) -> f32 {
+                0.0
+            }
+
+            fn resistance(&self, concentration: f32) -> f32 {
+                concentration
+            }
+
+            fn saturation(&self, concentration: f32) -> f32 {
+                concentration
+            }
+        }
+
+        // On ties, the first concentration of the grid is the solution, as in
+        // the sequential run.
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(1.0, 2.0, 4 * PAR_EQUATION_CHUNK),
+            resistance_range: FloatRange::new(0.0, 1.0, 1),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 1),
+        };
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params, FlatModelMock);
+        assert_eq!(algorithm.run_par(), algorithm.run());
+        assert_eq!(algorithm.run_par().unwrap().0.concentration, 1.0);
+
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(1.0, 2.0, 0),
+            resistance_range: FloatRange::new(0.0, 1.0, 1),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 1),
+        };
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params, FlatModelMock);
+        assert_eq!(algorithm.run_par(), None);
     }
 }
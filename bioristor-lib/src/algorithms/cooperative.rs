@@ -0,0 +1,155 @@
+use core::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+/// Point where a solver can suspend its execution to let other tasks run.
+///
+/// The algorithms are implemented once as futures that notify the checkpoint
+/// after every evaluation of the model: the blocking API uses the no-op
+/// checkpoint `()`, which never suspends, while [`run_async`] yields to the
+/// executor periodically.
+///
+/// The notification must be guarded by [`Checkpoint::SUSPENDS`]: an `.await`
+/// in the innermost loop, even on a future that is always ready, keeps the
+/// locals of the solver in the state of the future instead of registers and
+/// slows down the brute force considerably.
+///
+/// [`run_async`]: super::Algorithm::run_async
+pub(crate) trait Checkpoint {
+    /// Whether the checkpoint can suspend the solver.
+    const SUSPENDS: bool;
+
+    /// Called after every evaluation of the model.
+    async fn evaluated(&mut self);
+}
+
+/// Checkpoint that never suspends the solver.
+impl Checkpoint for () {
+    const SUSPENDS: bool = false;
+
+    #[inline(always)]
+    async fn evaluated(&mut self) {}
+}
+
+/// Checkpoint that yields to the executor every `every` evaluations.
+#[cfg(feature = "async")]
+pub(crate) struct YieldEvery {
+    /// The number of evaluations since the last yield.
+    count: usize,
+
+    /// The number of evaluations between two yields.
+    every: usize,
+}
+
+#[cfg(feature = "async")]
+impl YieldEvery {
+    /// Creates a new checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `every` - The number of evaluations between two yields; zero is
+    ///   treated as one.
+    pub(crate) fn new(every: usize) -> Self {
+        Self {
+            count: 0,
+            every: every.max(1),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Checkpoint for YieldEvery {
+    const SUSPENDS: bool = true;
+
+    #[inline]
+    async fn evaluated(&mut self) {
+        self.count += 1;
+        if self.count >= self.every {
+            self.count = 0;
+            yield_now().await;
+        }
+    }
+}
+
+/// Future returned by [`yield_now`].
+#[cfg(feature = "async")]
+#[must_use = "futures do nothing unless awaited"]
+pub struct YieldNow {
+    /// Whether the task has already yielded.
+    yielded: bool,
+}
+
+#[cfg(feature = "async")]
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// Yields the execution to the executor once, rescheduling the current task
+/// immediately, so that the other ready tasks can run.
+///
+/// The future works with any executor, e.g. `embassy-executor`.
+#[cfg(feature = "async")]
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+/// Advances a solver returned by [`run_async`] until its next yield, without
+/// an executor.
+///
+/// This allows to run the solver by steps of `yield_every` evaluations of the
+/// model from a super-loop or from the idle task of a scheduler, interleaving
+/// it with other work. The solver must not be stepped again once it has
+/// returned its result.
+///
+/// # Arguments
+///
+/// * `solver` - The pinned future returned by [`run_async`].
+///
+/// # Returns
+///
+/// * `Some(result)` - The result of the solver, if it has completed.
+/// * `None` - If the solver has yielded and must be stepped again.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut monitor = ();
+/// let mut solver = core::pin::pin!(algorithm.run_async(&mut monitor, 100));
+/// let result = loop {
+///     if let Some(result) = step(solver.as_mut()) {
+///         break result;
+///     }
+///     // Serve the other tasks.
+/// };
+/// ```
+///
+/// [`run_async`]: super::Algorithm::run_async
+#[cfg(feature = "async")]
+pub fn step<F: Future>(solver: core::pin::Pin<&mut F>) -> Option<F::Output> {
+    match solver.poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    }
+}
+
+/// Runs to completion a solver that never suspends its execution, i.e. one
+/// using the no-op checkpoint.
+#[inline(always)]
+pub(crate) fn complete<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("the solver has been suspended"),
+    }
+}
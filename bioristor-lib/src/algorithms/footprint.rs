@@ -0,0 +1,147 @@
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+use core::mem::size_of;
+
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+use crate::algorithms::CHUNK;
+
+/// The static memory required by an algorithm, in bytes.
+///
+/// It is returned by the `footprint` associated function of every
+/// algorithm, which is `const`, so that a configuration exceeding the RAM of
+/// a part is rejected at compile time instead of overflowing the stack at
+/// runtime. The call frames and the scalar variables are not included,
+/// since their size depends on the compiler and on the optimizations, so a
+/// margin should be reserved for them.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::{
+///     algorithms::{AdaptiveEquation, Footprint},
+///     losses::Absolute,
+///     models::Equation,
+/// };
+///
+/// const FOOTPRINT: Footprint = AdaptiveEquation::<Equation, Absolute, 10>::footprint();
+///
+/// // Fails the build if the algorithm does not fit in 1 KB.
+/// const _: () = assert!(FOOTPRINT.total() <= 1024);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Footprint {
+    /// The size of the arrays allocated on the stack by a run of the
+    /// algorithm, e.g. the list of the best solutions, the chunks of the
+    /// grids and the layers of the neural networks.
+    pub buffers: usize,
+
+    /// The size of an instance of the algorithm, including the parameters
+    /// and the model.
+    pub instance: usize,
+}
+
+impl Footprint {
+    /// Returns the footprint of an algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance` - The size of an instance of the algorithm.
+    /// * `buffers` - The size of the arrays allocated by a run.
+    #[cfg(any(
+        feature = "adaptive",
+        feature = "adaptive2",
+        feature = "brute-force",
+        feature = "gradient-descent",
+        feature = "neural-network",
+        feature = "newton"
+    ))]
+    pub(crate) const fn new(instance: usize, buffers: usize) -> Self {
+        Self { buffers, instance }
+    }
+
+    /// Returns the total memory required by the algorithm, in bytes.
+    pub const fn total(&self) -> usize {
+        self.buffers + self.instance
+    }
+}
+
+/// The size of the chunk of concentrations and of the chunk of values of
+/// the model used by the grid algorithms of the equation model, see
+/// [`chunks`](crate::algorithms::chunks).
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+pub(crate) const CHUNK_BUFFERS: usize = 2 * size_of::<[f32; CHUNK]>();
+
+#[cfg(all(
+    test,
+    feature = "adaptive",
+    feature = "adaptive2",
+    feature = "brute-force",
+    feature = "gradient-descent",
+    feature = "neural-network",
+    feature = "newton",
+    feature = "system"
+))]
+mod tests {
+    use crate::{
+        algorithms::{
+            Adaptive2Equation, AdaptiveEquation, AdaptiveSystem, BruteForceEquation,
+            GradientDescentEquation, NeuralNetworkEquation, NewtonEquation,
+        },
+        losses::{Absolute, MeanRelative},
+        models::{Equation, System},
+        params::Variables,
+        utils::BestOrderedList,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_footprint_minima() {
+        // Every further minimum is an entry of the list of the best solutions.
+        let entry = size_of::<(f32, f32)>();
+        assert_eq!(
+            AdaptiveEquation::<Equation, Absolute, 10>::footprint().buffers
+                - AdaptiveEquation::<Equation, Absolute, 1>::footprint().buffers,
+            9 * entry
+        );
+        assert_eq!(
+            Adaptive2Equation::<Equation, Absolute, 10>::footprint().buffers
+                - Adaptive2Equation::<Equation, Absolute, 1>::footprint().buffers,
+            9 * entry
+        );
+        assert_eq!(
+            AdaptiveSystem::<System, MeanRelative, 10>::footprint().buffers,
+            size_of::<BestOrderedList<Variables, 10>>()
+        );
+    }
+
+    #[test]
+    fn test_footprint_buffers() {
+        assert_eq!(
+            BruteForceEquation::<Equation, Absolute>::footprint().buffers,
+            CHUNK_BUFFERS
+        );
+        assert_eq!(
+            GradientDescentEquation::<Equation, Absolute>::footprint().buffers,
+            0
+        );
+        assert_eq!(NewtonEquation::<Equation, Absolute>::footprint().buffers, 0);
+
+        // The weights of the larger network are copied to the stack.
+        let small = NeuralNetworkEquation::<Equation, Absolute, 0>::footprint();
+        let large = NeuralNetworkEquation::<Equation, Absolute, 1>::footprint();
+        assert_eq!(small.instance, large.instance);
+        assert!(large.buffers > size_of::<[f32; 64 * 32]>());
+        assert!(small.buffers < large.buffers);
+    }
+
+    #[test]
+    fn test_footprint_total() {
+        let footprint = BruteForceEquation::<Equation, Absolute>::footprint();
+        assert_eq!(footprint.total(), footprint.instance + footprint.buffers);
+        assert_eq!(
+            footprint.instance,
+            size_of::<BruteForceEquation<Equation, Absolute>>()
+        );
+    }
+}
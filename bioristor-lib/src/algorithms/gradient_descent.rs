@@ -1,8 +1,18 @@
+use core::mem::size_of;
+
 #[allow(unused_imports)]
 use micromath::F32Ext;
 
+#[cfg(feature = "async")]
+use crate::algorithms::cooperative::YieldEvery;
 use crate::{
-    algorithms::Algorithm,
+    algorithms::{
+        checked_solution,
+        cooperative::{complete, Checkpoint},
+        iterative_termination, positive_concentration,
+        validation::{check_non_zero, check_positive},
+        Algorithm, Footprint, Monitor, ParamsError, SaturationPolicy, SolveEvent, Termination,
+    },
     losses::Loss,
     models::{EquationModel, Model},
     params::Variables,
@@ -26,10 +36,32 @@ pub struct GradientDescentParams {
     /// The maximum number of iterations.
     pub max_iterations: usize,
 
+    /// What to do with a solution whose saturation is outside the physical
+    /// interval.
+    pub saturation_policy: SaturationPolicy,
+
     /// The error tolerance at which the algorithm stops.
     pub tolerance: f32,
 }
 
+impl GradientDescentParams {
+    /// Checks that the parameters are valid: the initial guess, the learning
+    /// rate and the tolerances are positive and there is at least one
+    /// iteration.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the parameters are valid.
+    /// * `Err(error)` - The first parameter that is not valid.
+    pub fn validate(&self) -> Result<(), ParamsError> {
+        check_positive("concentration_init", self.concentration_init)?;
+        check_positive("grad_tolerance", self.grad_tolerance)?;
+        check_positive("learning_rate_init", self.learning_rate_init)?;
+        check_non_zero("max_iterations", self.max_iterations)?;
+        check_positive("tolerance", self.tolerance)
+    }
+}
+
 /// Implementation of the gradient descent algorithm for the equation model.
 ///
 /// # Type parameters
@@ -46,56 +78,57 @@ pub struct GradientDescentEquation<M: Model, L: Loss> {
     _t: core::marker::PhantomData<L>,
 }
 
-impl<M, L> Algorithm<GradientDescentParams, M> for GradientDescentEquation<M, L>
+impl<M: Model, L: Loss> GradientDescentEquation<M, L> {
+    /// Returns the static memory required by the algorithm, see [`Footprint`].
+    pub const fn footprint() -> Footprint {
+        Footprint::new(size_of::<Self>(), 0)
+    }
+}
+
+impl<M, L> GradientDescentEquation<M, L>
 where
     M: EquationModel,
     L: Loss<ModelOutput = f32>,
 {
-    /// Create a new instance of the gradient descent algorithm.
-    ///
-    /// # Arguments
-    ///
-    /// * `params` - The parameters of the algorithm.
-    /// * `model` - The model to be solved by the algorithm.
-    fn new(params: GradientDescentParams, model: M) -> Self {
-        Self {
-            params,
-            model,
-            _t: core::marker::PhantomData,
-        }
-    }
-
-    /// Tries to solve the model for the given parameters using the gradient
-    /// descent algorithm and returns the best solution found.
-    ///
-    /// # Returns
-    ///
-    /// * `Some((vars, loss))` - The variables and the loss of the solution.
-    /// * `None` - If the algorithm could not find a solution.
-    fn run(&self) -> Option<(Variables, f32)> {
+    /// Solves the model, notifying the checkpoint after every evaluation of
+    /// the model.
+    async fn solve<O: Monitor, C: Checkpoint>(
+        &self,
+        monitor: &mut O,
+        mut checkpoint: C,
+    ) -> Option<(Variables, f32)> {
         // The search for the minima of the squared function f²(x) is equivalent
         // to the search for the zeros in the initial function f(x).
-        let gradient = |x: f32| -> f32 {
-            let f = self.model.value(x);
-            let df = self.model.gradient(x);
-            2.0 * f * df
+        let gradient = |monitor: &mut O, x: f32| -> f32 {
+            monitor.evaluation(|| {
+                let f = self.model.value(x);
+                let df = self.model.gradient(x);
+                2.0 * f * df
+            })
         };
 
         // Initialize variable with starting point.
-        let mut c = self.params.concentration_init;
+        let mut c = positive_concentration(self.params.concentration_init);
         let mut c_prev;
 
-        let mut grad = gradient(c);
+        let mut grad = gradient(monitor, c);
+        if C::SUSPENDS {
+            checkpoint.evaluated().await;
+        }
         let mut grad_prev;
 
         let mut learning_rate = self.params.learning_rate_init;
 
         // Initialize error with loss at starting point.
-        let mut error = L::evaluate(self.model.value(c));
+        let mut error = L::evaluate(monitor.evaluation(|| self.model.value(c)));
+        if C::SUSPENDS {
+            checkpoint.evaluated().await;
+        }
 
         // Loop until the maximum number of iterations is reached, the error
         // subceeds a certain tolerance, or the gradient becomes too small.
         let mut iterations = 0;
+        let mut termination = None;
         while iterations < self.params.max_iterations
             && error > self.params.tolerance
             && grad.abs() > self.params.grad_tolerance
@@ -105,31 +138,124 @@ where
             grad_prev = grad;
 
             // Update variable based on gradient and learning rate.
-            c -= learning_rate * grad;
-            grad = gradient(c);
+            c = positive_concentration(c - learning_rate * grad);
+            grad = gradient(monitor, c);
+            if C::SUSPENDS {
+                checkpoint.evaluated().await;
+            }
+
+            // Update learning rate using the Barzilai–Borwein method, which is
+            // not defined when the gradient did not change, e.g. on a plateau
+            // or when the step is below the resolution of the iterate.
+            let grad_diff = grad - grad_prev;
+            let bb_rate = ((c - c_prev) * grad_diff).abs() / grad_diff.powi(2);
+            learning_rate = if bb_rate.is_finite() && bb_rate > 0.0 {
+                bb_rate
+            } else {
+                monitor.event(SolveEvent::LearningRateFallback);
+                self.params.learning_rate_init
+            };
+
+            error = L::evaluate(monitor.evaluation(|| self.model.value(c)));
+            if C::SUSPENDS {
+                checkpoint.evaluated().await;
+            }
+            monitor.step(error, grad, c - c_prev);
 
-            // Update learning rate using the Barzilai–Borwein method.
-            learning_rate = ((c - c_prev) * (grad - grad_prev)).abs() / (grad - grad_prev).powi(2);
+            iterations += 1;
 
-            error = L::evaluate(self.model.value(c));
+            if !monitor.iteration() {
+                termination = Some(Termination::Stopped);
+                break;
+            }
+        }
+        monitor.termination(termination.unwrap_or_else(|| {
+            iterative_termination(
+                error,
+                self.params.tolerance,
+                grad,
+                self.params.grad_tolerance,
+            )
+        }));
+
+        let vars = Variables {
+            concentration: c,
+            resistance: self.model.resistance(c),
+            saturation: self.model.saturation(c),
+        };
+        checked_solution(monitor, self.params.saturation_policy, vars, error)
+    }
+}
 
-            iterations += 1;
+impl<M, L> Algorithm<GradientDescentParams, M> for GradientDescentEquation<M, L>
+where
+    M: EquationModel,
+    L: Loss<ModelOutput = f32>,
+{
+    /// Create a new instance of the gradient descent algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the algorithm.
+    /// * `model` - The model to be solved by the algorithm.
+    fn new(params: GradientDescentParams, model: M) -> Self {
+        Self {
+            params,
+            model,
+            _t: core::marker::PhantomData,
         }
+    }
 
-        Some((
-            Variables {
-                concentration: c,
-                resistance: self.model.resistance(c),
-                saturation: self.model.saturation(c),
-            },
-            error,
-        ))
+    /// Returns a reference to the model solved by the algorithm.
+    #[inline]
+    fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Tries to solve the model for the given parameters using the gradient
+    /// descent algorithm and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    fn run_with<O: Monitor>(&self, monitor: &mut O) -> Option<(Variables, f32)> {
+        complete(self.solve(monitor, ()))
+    }
+
+    /// Tries to solve the model for the given parameters using the gradient
+    /// descent algorithm, yielding to the executor every `yield_every`
+    /// evaluations of the model, and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    /// * `yield_every` - The number of evaluations between two yields.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    #[cfg(feature = "async")]
+    fn run_async<O: Monitor>(
+        &self,
+        monitor: &mut O,
+        yield_every: usize,
+    ) -> impl core::future::Future<Output = Option<(Variables, f32)>> {
+        self.solve(monitor, YieldEvery::new(yield_every))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
+        algorithms::SolveReport,
         losses::Absolute,
         models::Model,
         params::{Currents, ModelParams},
@@ -140,6 +266,8 @@ mod tests {
     struct EquationModelMock;
 
     impl Model for EquationModelMock {
+        type Params = ModelParams;
+
         fn new(_: ModelParams, _: Currents) -> Self {
             Self
         }
@@ -171,6 +299,69 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_gradient_descent_equation_fallback() {
+        /// Model with a constant gradient of the squared value.
+        struct PlateauModelMock;
+
+        impl Model for PlateauModelMock {
+            type Params = ModelParams;
+
+            fn new(_: ModelParams, _: Currents) -> Self {
+                Self
+            }
+
+            fn params(&self) -> &ModelParams {
+                unimplemented!()
+            }
+
+            fn currents(&self) -> &Currents {
+                unimplemented!()
+            }
+        }
+
+        impl EquationModel for PlateauModelMock {
+            fn value(&self, _: f32) -> f32 {
+                1.0
+            }
+
+            fn gradient(&self, _: f32) -> f32 {
+                0.5
+            }
+
+            fn resistance(&self, concentration: f32) -> f32 {
+                concentration
+            }
+
+            fn saturation(&self, concentration: f32) -> f32 {
+                concentration
+            }
+        }
+
+        let params = GradientDescentParams {
+            concentration_init: 1.0,
+            grad_tolerance: 1e-9,
+            learning_rate_init: 0.1,
+            max_iterations: 5,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-6,
+        };
+        let algorithm = GradientDescentEquation::<_, Absolute>::new(params, PlateauModelMock);
+        let mut report = SolveReport::new();
+        let (variables, _) = algorithm.run_with(&mut report).unwrap();
+
+        assert!((variables.concentration - 0.5).abs() < 1e-6);
+        assert_eq!(report.learning_rate_fallbacks, 5);
+
+        // The run gave up, with a constant gradient and step.
+        let diagnostics = &report.diagnostics;
+        assert_eq!(diagnostics.termination, Some(Termination::MaxIterations));
+        assert!(!diagnostics.converged());
+        assert_eq!(diagnostics.gradient, Some(1.0));
+        assert_eq!(diagnostics.losses, 5);
+        assert!((diagnostics.step.unwrap() - 0.1).abs() < 1e-6);
+    }
+
     #[test]
     fn test_gradient_descent_equation() {
         let params = GradientDescentParams {
@@ -178,6 +369,7 @@ mod tests {
             grad_tolerance: 1e-9,
             learning_rate_init: 0.2,
             max_iterations: 100,
+            saturation_policy: SaturationPolicy::Keep,
             tolerance: 1e-6,
         };
         let model = EquationModelMock;
@@ -1,20 +1,154 @@
+#[cfg(feature = "adaptive")]
 mod adaptive;
+#[cfg(feature = "adaptive2")]
 mod adaptive2;
+#[cfg(feature = "brute-force")]
 mod brute_force;
+#[cfg_attr(
+    not(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force")),
+    allow(dead_code)
+)]
+mod cooperative;
+mod footprint;
+#[cfg(feature = "gradient-descent")]
 mod gradient_descent;
+mod monitor;
+#[cfg(feature = "neural-network")]
 mod neural_network;
+#[cfg(feature = "newton")]
 mod newton;
+mod presets;
+mod report;
+mod validation;
 
+#[cfg(feature = "adaptive")]
 pub use adaptive::*;
+#[cfg(feature = "adaptive2")]
 pub use adaptive2::*;
+#[cfg(feature = "brute-force")]
 pub use brute_force::*;
+#[cfg(feature = "async")]
+pub use cooperative::{step, yield_now, YieldNow};
+pub use footprint::Footprint;
+#[cfg(feature = "gradient-descent")]
 pub use gradient_descent::*;
+pub use monitor::*;
+#[cfg(feature = "neural-network")]
 pub use neural_network::*;
+#[cfg(feature = "newton")]
 pub use newton::*;
+pub use presets::CpuClass;
+pub use report::*;
+pub use validation::ParamsError;
 
+use core::sync::atomic::{AtomicU32, Ordering};
+
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+use crate::models::EquationModel;
 use crate::models::Model;
 use crate::params::Variables;
 
+/// The default lower bound of the concentration evaluated by the algorithms
+/// [Molarity].
+pub const DEFAULT_CONCENTRATION_MIN: f32 = 1e-9;
+
+/// The lower bound of the concentration, as the bits of an `f32`.
+static CONCENTRATION_MIN: AtomicU32 = AtomicU32::new(DEFAULT_CONCENTRATION_MIN.to_bits());
+
+/// Returns the lower bound of the concentration evaluated by the algorithms.
+///
+/// The modulation of the models is a function of the logarithm of the
+/// concentration, which is not defined for non-positive values, so every
+/// grid and every iterative update is clamped to this bound.
+#[inline]
+pub fn concentration_min() -> f32 {
+    f32::from_bits(CONCENTRATION_MIN.load(Ordering::Relaxed))
+}
+
+/// Sets the lower bound of the concentration evaluated by the algorithms, see
+/// [`concentration_min`].
+///
+/// # Arguments
+///
+/// * `min` - The lower bound [Molarity].
+///
+/// # Panics
+///
+/// If the bound is not positive and finite.
+pub fn set_concentration_min(min: f32) {
+    assert!(
+        min > 0.0 && min.is_finite(),
+        "the lower bound of the concentration must be positive"
+    );
+    CONCENTRATION_MIN.store(min.to_bits(), Ordering::Relaxed);
+}
+
+/// Clamps a concentration to the lower bound, see [`concentration_min`].
+///
+/// A NaN is returned unchanged, so that it is still reported by
+/// [`checked_solution`].
+#[inline]
+#[cfg(any(
+    feature = "adaptive",
+    feature = "adaptive2",
+    feature = "brute-force",
+    feature = "gradient-descent",
+    feature = "neural-network",
+    feature = "newton"
+))]
+pub(crate) fn positive_concentration(concentration: f32) -> f32 {
+    let min = concentration_min();
+    if concentration < min {
+        min
+    } else {
+        concentration
+    }
+}
+
+/// What the algorithms do with a solution whose saturation is outside the
+/// physical `[0, 1]` interval, which is common with the equation model on
+/// noisy currents.
+///
+/// Every algorithm applies the policy set in its parameters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SaturationPolicy {
+    /// The solution is returned unchanged.
+    #[default]
+    Keep,
+
+    /// The saturation is clamped to the interval.
+    Clamp,
+
+    /// The solution is rejected, so the algorithm returns no solution.
+    Reject,
+}
+
+impl SaturationPolicy {
+    /// Applies the policy to a solution.
+    ///
+    /// # Arguments
+    ///
+    /// * `vars` - The variables of the solution.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(vars)` - The variables of the solution, with the saturation
+    ///   clamped if required.
+    /// * `None` - If the solution is rejected.
+    pub fn apply(self, mut vars: Variables) -> Option<Variables> {
+        if (0.0..=1.0).contains(&vars.saturation) {
+            return Some(vars);
+        }
+        match self {
+            SaturationPolicy::Keep => {}
+            SaturationPolicy::Clamp => vars.saturation = vars.saturation.clamp(0.0, 1.0),
+            SaturationPolicy::Reject => return None,
+        }
+        Some(vars)
+    }
+}
+
 /// Common interface for algorithm implementations.
 ///
 /// # Type parameters
@@ -30,6 +164,14 @@ pub trait Algorithm<P: Sized, M: Model> {
     /// * `model` - The model to be solved by the algorithm.
     fn new(params: P, model: M) -> Self;
 
+    /// Returns a reference to the model solved by the algorithm.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the model, e.g. to read the counters of a
+    /// [`CountingModel`](crate::models::CountingModel) after a solve.
+    fn model(&self) -> &M;
+
     /// Tries to solve the model for the given parameters using this algorithm
     /// and returns the best solution found.
     ///
@@ -37,5 +179,315 @@ pub trait Algorithm<P: Sized, M: Model> {
     ///
     /// * `Some((vars, loss))` - The variables and the loss of the solution.
     /// * `None` - If the algorithm could not find a solution.
-    fn run(&self) -> Option<(Variables, f32)>;
+    #[inline]
+    fn run(&self) -> Option<(Variables, f32)> {
+        self.run_with(&mut ())
+    }
+
+    /// Tries to solve the model for the given parameters using this algorithm,
+    /// notifying the given monitor at the end of every iteration, and returns
+    /// the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor of the execution, that can stop the algorithm
+    ///   early.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    fn run_with<O: Monitor>(&self, monitor: &mut O) -> Option<(Variables, f32)>;
+
+    /// Tries to solve the model for the given parameters using this algorithm,
+    /// yielding to the executor every `yield_every` evaluations of the model,
+    /// and returns the best solution found.
+    ///
+    /// This allows the solver to share a single-core executor with other
+    /// tasks, e.g. radio and sensors, without starving them. The algorithms
+    /// of the library yield every `yield_every` evaluations; the default
+    /// implementation, for the algorithms defined elsewhere, runs
+    /// [`run_with`](Algorithm::run_with) to completion and yields once at the
+    /// end.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor of the execution, that can stop the algorithm
+    ///   early.
+    /// * `yield_every` - The number of evaluations between two yields.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    #[cfg(feature = "async")]
+    fn run_async<O: Monitor>(
+        &self,
+        monitor: &mut O,
+        yield_every: usize,
+    ) -> impl core::future::Future<Output = Option<(Variables, f32)>> {
+        let _ = yield_every;
+        async move {
+            let result = self.run_with(monitor);
+            yield_now().await;
+            result
+        }
+    }
+}
+
+/// Checks that the solution found by an algorithm is finite, and applies the
+/// policy of the algorithm to its saturation.
+///
+/// # Arguments
+///
+/// * `monitor` - The monitor notified with [`SolveEvent::NonFiniteSolution`]
+///   if the solution is not finite, or with
+///   [`SolveEvent::SaturationOutOfRange`] if the saturation is outside the
+///   physical interval.
+/// * `policy` - The policy applied to the saturation of the solution.
+/// * `vars` - The variables of the solution.
+/// * `loss` - The loss of the solution.
+///
+/// # Returns
+///
+/// * `Some((vars, loss))` - If all the values are finite and the solution is
+///   not rejected by the policy.
+/// * `None` - Otherwise.
+#[cfg(any(
+    feature = "adaptive",
+    feature = "adaptive2",
+    feature = "brute-force",
+    feature = "gradient-descent",
+    feature = "neural-network",
+    feature = "newton"
+))]
+pub(crate) fn checked_solution<O: Monitor>(
+    monitor: &mut O,
+    policy: SaturationPolicy,
+    vars: Variables,
+    loss: f32,
+) -> Option<(Variables, f32)> {
+    if !(vars.concentration.is_finite()
+        && vars.resistance.is_finite()
+        && vars.saturation.is_finite()
+        && loss.is_finite())
+    {
+        monitor.event(SolveEvent::NonFiniteSolution);
+        return None;
+    }
+
+    if !(0.0..=1.0).contains(&vars.saturation) {
+        monitor.event(SolveEvent::SaturationOutOfRange);
+    }
+    policy.apply(vars).map(|vars| (vars, loss))
+}
+
+/// Number of concentrations of a grid evaluated together by the equation
+/// algorithms, see [`EquationModel::value_batch`], as many as the SIMD lanes
+/// used by the equation model on the host.
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+pub(crate) const CHUNK: usize = 8;
+
+/// Groups the concentrations of a grid in chunks of [`CHUNK`] elements.
+///
+/// # Arguments
+///
+/// * `concentrations` - The concentrations of the grid.
+///
+/// # Returns
+///
+/// An iterator over the chunks and the number of concentrations in each
+/// chunk, which is less than [`CHUNK`] only for the last one.
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+pub(crate) fn chunks(
+    mut concentrations: impl Iterator<Item = f32>,
+) -> impl Iterator<Item = ([f32; CHUNK], usize)> {
+    core::iter::from_fn(move || {
+        let mut chunk = [0.0; CHUNK];
+        let mut len = 0;
+        for (slot, concentration) in chunk.iter_mut().zip(&mut concentrations) {
+            *slot = concentration;
+            len += 1;
+        }
+        (len > 0).then_some((chunk, len))
+    })
+}
+
+/// Evaluates the model on a chunk of concentrations, notifying the monitor
+/// once per concentration.
+///
+/// # Arguments
+///
+/// * `model` - The model to evaluate.
+/// * `monitor` - The monitor to notify.
+/// * `chunk` - The concentrations, of which only the first `len` are valid.
+/// * `len` - The number of valid concentrations.
+///
+/// # Returns
+///
+/// The output values of the model, of which only the first `len` are valid.
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+pub(crate) fn evaluate_chunk<M: EquationModel, O: Monitor>(
+    model: &M,
+    monitor: &mut O,
+    chunk: &[f32; CHUNK],
+    len: usize,
+) -> [f32; CHUNK] {
+    monitor.evaluations(len, || {
+        if len == CHUNK {
+            model.value_batch(chunk)
+        } else {
+            let mut values = [f32::NAN; CHUNK];
+            for (value, concentration) in values.iter_mut().zip(chunk).take(len) {
+                *value = model.value(*concentration);
+            }
+            values
+        }
+    })
+}
+
+/// Notifies the monitor with [`SolveEvent::BoundaryHit`] if the best
+/// concentration of a grid is its first or last point, in which case the
+/// optimum likely lies outside of the grid.
+///
+/// # Arguments
+///
+/// * `monitor` - The monitor to notify.
+/// * `index` - The index of the best concentration in the grid, if any.
+/// * `steps` - The number of concentrations in the grid.
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+pub(crate) fn check_boundary<O: Monitor>(monitor: &mut O, index: Option<usize>, steps: usize) {
+    if let Some(index) = index {
+        if steps > 1 && (index == 0 || index == steps - 1) {
+            monitor.event(SolveEvent::BoundaryHit);
+        }
+    }
+}
+
+/// Returns the reason of the termination of an iterative algorithm whose
+/// loop ended without being interrupted.
+///
+/// # Arguments
+///
+/// * `error` - The loss of the last iterate.
+/// * `tolerance` - The tolerance of the loss.
+/// * `grad` - The gradient of the last iterate.
+/// * `grad_tolerance` - The tolerance of the gradient.
+#[cfg(any(feature = "gradient-descent", feature = "newton"))]
+pub(crate) fn iterative_termination(
+    error: f32,
+    tolerance: f32,
+    grad: f32,
+    grad_tolerance: f32,
+) -> Termination {
+    if !(error.is_finite() && grad.is_finite()) {
+        Termination::Diverged
+    } else if error <= tolerance {
+        Termination::Converged
+    } else if grad.abs() <= grad_tolerance {
+        Termination::GradientVanished
+    } else {
+        Termination::MaxIterations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saturation_policy() {
+        let vars = Variables {
+            concentration: 1e-2,
+            resistance: 40.0,
+            saturation: 1.3,
+        };
+        assert_eq!(SaturationPolicy::Keep.apply(vars), Some(vars));
+        assert_eq!(SaturationPolicy::Reject.apply(vars), None);
+        assert_eq!(
+            SaturationPolicy::Clamp.apply(vars),
+            Some(Variables {
+                saturation: 1.0,
+                ..vars
+            })
+        );
+
+        let vars = Variables {
+            saturation: 0.7,
+            ..vars
+        };
+        assert_eq!(SaturationPolicy::Reject.apply(vars), Some(vars));
+    }
+
+    #[cfg(any(
+        feature = "adaptive",
+        feature = "adaptive2",
+        feature = "brute-force",
+        feature = "gradient-descent",
+        feature = "neural-network",
+        feature = "newton"
+    ))]
+    #[test]
+    fn test_positive_concentration() {
+        assert_eq!(positive_concentration(0.0), DEFAULT_CONCENTRATION_MIN);
+        assert_eq!(positive_concentration(-1.0), DEFAULT_CONCENTRATION_MIN);
+        assert_eq!(positive_concentration(1e-3), 1e-3);
+        assert!(positive_concentration(f32::NAN).is_nan());
+    }
+
+    #[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+    #[test]
+    fn test_chunks() {
+        let mut iter = chunks((1..=10).map(|i| i as f32));
+        assert_eq!(
+            iter.next(),
+            Some(([1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0], 8))
+        );
+        assert_eq!(
+            iter.next(),
+            Some(([9.0, 10.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], 2))
+        );
+        assert_eq!(iter.next(), None);
+
+        assert_eq!(chunks(core::iter::empty()).next(), None);
+    }
+
+    #[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+    #[test]
+    fn test_evaluate_chunk() {
+        use crate::models::{CountingModel, Equation};
+        use crate::params::{Currents, ModelParams, ModulationParams};
+        use crate::params::{StemResistanceInvParams, Voltages};
+
+        let params = ModelParams {
+            mod_params: ModulationParams(0.0, -0.01463, -0.32),
+            r_dry: 38.2,
+            res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+            voltages: Voltages {
+                v_ds: -0.05,
+                v_gs: 0.5,
+            },
+        };
+        let currents = Currents {
+            i_ds_off: -0.003_036_5,
+            i_ds_on: -0.002_682_9,
+            i_gs_on: 1.169_828e-6,
+        };
+        let model = CountingModel::<Equation>::new(params, currents);
+        let mut report = SolveReport::new();
+
+        let chunk = [1e-3, 2e-3, 3e-3, 4e-3, 5e-3, 6e-3, 7e-3, 8e-3];
+        let values = evaluate_chunk(&model, &mut report, &chunk, CHUNK);
+        for (value, concentration) in values.iter().zip(chunk) {
+            // On the host the full chunks are evaluated with SIMD instructions.
+            let expected = model.inner().value(concentration);
+            assert!((value - expected).abs() <= 1e-5 * expected.abs());
+        }
+        let values = evaluate_chunk(&model, &mut report, &chunk, 3);
+        assert_eq!(values[2], model.inner().value(3e-3));
+        assert!(values[3].is_nan());
+
+        assert_eq!(report.evaluations, 11);
+        assert_eq!(model.values(), 11);
+    }
 }
@@ -0,0 +1,473 @@
+#[cfg(feature = "profiler")]
+use profiler::CycleCounter;
+
+/// Observer of the execution of an algorithm.
+///
+/// The algorithms notify the monitor at the end of every iteration, which
+/// allows to time the execution or to stop it early without hard-wiring the
+/// library to a specific hardware timer. All the methods have a default empty
+/// implementation, so that the unit type `()` can be used as a no-op monitor.
+pub trait Monitor {
+    /// Called at the end of every iteration of the algorithm.
+    ///
+    /// What an iteration is depends on the algorithm: a step for iterative
+    /// methods, a refinement of the search range for adaptive methods, or a
+    /// concentration of the grid for brute force methods.
+    ///
+    /// # Returns
+    ///
+    /// * `true` - If the algorithm should continue.
+    /// * `false` - If the algorithm should stop and return the best solution
+    ///   found so far.
+    #[inline]
+    fn iteration(&mut self) -> bool {
+        true
+    }
+
+    /// Called around every evaluation of the model, i.e. every call to its
+    /// value and/or gradient.
+    ///
+    /// The default implementation just calls `f`; monitors can override it
+    /// to count or time the evaluations.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The closure evaluating the model.
+    ///
+    /// # Returns
+    ///
+    /// The result of the closure.
+    #[inline]
+    fn evaluation<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    /// Called around the evaluation of a batch of `count` points of the
+    /// model, evaluated together for speed.
+    ///
+    /// The default implementation notifies [`Monitor::evaluation`] once per
+    /// point, calling `f` within the last notification, so that the monitors
+    /// counting or timing the evaluations need not override it.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The number of points evaluated by the closure.
+    /// * `f` - The closure evaluating the model.
+    ///
+    /// # Returns
+    ///
+    /// The result of the closure.
+    #[inline]
+    fn evaluations<R>(&mut self, count: usize, f: impl FnOnce() -> R) -> R {
+        for _ in 1..count {
+            self.evaluation(|| ());
+        }
+        self.evaluation(f)
+    }
+
+    /// Called when the algorithm meets an anomaly, e.g. a non-finite output
+    /// of the model, so that it can be reported instead of silently giving a
+    /// wrong solution.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The anomaly.
+    #[inline]
+    fn event(&mut self, event: SolveEvent) {
+        let _ = event;
+    }
+
+    /// Called at the end of every step of the iterative algorithms, with the
+    /// state of the new iterate.
+    ///
+    /// # Arguments
+    ///
+    /// * `loss` - The loss of the iterate.
+    /// * `gradient` - The gradient of the iterate.
+    /// * `step` - The size of the step, i.e. the change of the concentration.
+    #[inline]
+    fn step(&mut self, loss: f32, gradient: f32, step: f32) {
+        let _ = (loss, gradient, step);
+    }
+
+    /// Called once when the algorithm terminates.
+    ///
+    /// # Arguments
+    ///
+    /// * `termination` - The reason of the termination.
+    #[inline]
+    fn termination(&mut self, termination: Termination) {
+        let _ = termination;
+    }
+}
+
+/// The reasons for which an algorithm terminates, see
+/// [`Monitor::termination`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Termination {
+    /// The loss subceeded the tolerance.
+    Converged,
+
+    /// The gradient subceeded its tolerance before the loss did.
+    GradientVanished,
+
+    /// The maximum number of iterations was reached before the tolerance.
+    MaxIterations,
+
+    /// The algorithm completed its search, e.g. evaluated the whole grid or
+    /// all the refinements of the range.
+    Exhausted,
+
+    /// The iterates diverged, or were not finite.
+    Diverged,
+
+    /// The monitor stopped the algorithm early.
+    Stopped,
+}
+
+impl Termination {
+    /// Returns the name of the reason, in snake case.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Termination::Converged => "converged",
+            Termination::GradientVanished => "gradient_vanished",
+            Termination::MaxIterations => "max_iterations",
+            Termination::Exhausted => "exhausted",
+            Termination::Diverged => "diverged",
+            Termination::Stopped => "stopped",
+        }
+    }
+}
+
+/// The anomalies met by the algorithms, see [`Monitor::event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SolveEvent {
+    /// The loss of a candidate of a grid search is not finite, so the
+    /// candidate has been skipped.
+    NonFiniteCandidate,
+
+    /// An iterate, or the solution, is not finite, so the algorithm has
+    /// stopped without a solution.
+    NonFiniteSolution,
+
+    /// The adaptive learning rate could not be computed, e.g. because the
+    /// gradient did not change between two iterations, so the initial
+    /// learning rate has been used instead.
+    LearningRateFallback,
+
+    /// The step of an iterative method left the physical range or the
+    /// bracket of the root, or the previous step increased the loss, so a
+    /// bisection of the bracket has been used instead.
+    BracketingStep,
+
+    /// The iterative method diverged without a bracket of the root to fall
+    /// back to, so it has stopped with the best iterate found so far.
+    Diverged,
+
+    /// The initial guess of the concentration was not positive, or not
+    /// finite, so it has been corrected before starting the search.
+    InitCorrected,
+
+    /// The saturation of the solution is outside the physical `[0, 1]`
+    /// interval, see [`SaturationPolicy`](super::SaturationPolicy).
+    SaturationOutOfRange,
+
+    /// The best concentration of a grid search is the first or the last point
+    /// of the grid, so the range of concentrations likely truncates the
+    /// optimum.
+    BoundaryHit,
+}
+
+/// No-op monitor.
+impl Monitor for () {}
+
+/// Monitor that feeds a watchdog every `every` evaluations of the model.
+///
+/// Long runs, e.g. brute force over a fine grid, can take hundreds of
+/// milliseconds and would otherwise trip the independent watchdog of a
+/// deployed device. The feeding function can be any closure, including a
+/// `&mut dyn FnMut()` trait object. All the notifications are forwarded to an
+/// inner monitor, so that the feeder can be combined with other monitors.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::algorithms::{Monitor, SolveReport, WatchdogFeeder};
+///
+/// let mut feeds = 0;
+/// let mut feeder = WatchdogFeeder::new(100, || feeds += 1).with_monitor(SolveReport::new());
+///
+/// // let res = algorithm.run_with(&mut feeder);
+///
+/// let evaluations = feeder.monitor().evaluations;
+/// ```
+pub struct WatchdogFeeder<F: FnMut(), O: Monitor = ()> {
+    /// The number of evaluations since the last feeding.
+    count: usize,
+
+    /// The number of evaluations between two feedings.
+    every: usize,
+
+    /// The function feeding the watchdog.
+    feed: F,
+
+    /// The monitor the notifications are forwarded to.
+    monitor: O,
+}
+
+impl<F: FnMut()> WatchdogFeeder<F> {
+    /// Creates a new feeder.
+    ///
+    /// # Arguments
+    ///
+    /// * `every` - The number of evaluations between two feedings, at least
+    ///   one.
+    /// * `feed` - The function feeding the watchdog.
+    pub fn new(every: usize, feed: F) -> Self {
+        Self {
+            count: 0,
+            every: every.max(1),
+            feed,
+            monitor: (),
+        }
+    }
+}
+
+impl<F: FnMut(), O: Monitor> WatchdogFeeder<F, O> {
+    /// Sets the monitor the notifications are forwarded to.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The inner monitor.
+    pub fn with_monitor<M: Monitor>(self, monitor: M) -> WatchdogFeeder<F, M> {
+        WatchdogFeeder {
+            count: self.count,
+            every: self.every,
+            feed: self.feed,
+            monitor,
+        }
+    }
+
+    /// Returns the inner monitor.
+    #[inline]
+    pub fn monitor(&self) -> &O {
+        &self.monitor
+    }
+
+    /// Releases the inner monitor.
+    pub fn release(self) -> O {
+        self.monitor
+    }
+}
+
+impl<F: FnMut(), O: Monitor> Monitor for WatchdogFeeder<F, O> {
+    #[inline]
+    fn iteration(&mut self) -> bool {
+        self.monitor.iteration()
+    }
+
+    #[inline]
+    fn evaluation<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        self.count += 1;
+        if self.count == self.every {
+            self.count = 0;
+            (self.feed)();
+        }
+        self.monitor.evaluation(f)
+    }
+
+    #[inline]
+    fn event(&mut self, event: SolveEvent) {
+        self.monitor.event(event)
+    }
+
+    #[inline]
+    fn step(&mut self, loss: f32, gradient: f32, step: f32) {
+        self.monitor.step(loss, gradient, step)
+    }
+
+    #[inline]
+    fn termination(&mut self, termination: Termination) {
+        self.monitor.termination(termination)
+    }
+}
+
+/// Monitor that measures the duration of every iteration using a
+/// [`CycleCounter`] and optionally stops the algorithm when a cycle budget
+/// is exhausted.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::algorithms::IterationTimer;
+/// use profiler::MockCounter;
+///
+/// let counter = MockCounter::new(100);
+/// let mut timer = IterationTimer::new(&counter).with_budget(1_000);
+///
+/// // let res = algorithm.run_with(&mut timer);
+///
+/// let max_cycles = timer.max_iteration_cycles();
+/// let exceeded = timer.budget_exceeded();
+/// ```
+#[cfg(feature = "profiler")]
+#[derive(Debug)]
+pub struct IterationTimer<'a, C: CycleCounter> {
+    /// The counter used to measure the time.
+    counter: &'a C,
+
+    /// The value of the counter when the timer was created.
+    start: u64,
+
+    /// The value of the counter at the end of the last iteration.
+    last: u64,
+
+    /// The maximum number of cycles the algorithm is allowed to run.
+    budget: Option<u64>,
+
+    /// The number of iterations completed.
+    iterations: usize,
+
+    /// The number of cycles spent in the slowest iteration.
+    max_iteration_cycles: u64,
+}
+
+#[cfg(feature = "profiler")]
+impl<'a, C: CycleCounter> IterationTimer<'a, C> {
+    /// Creates a new timer and starts measuring.
+    ///
+    /// # Arguments
+    ///
+    /// * `counter` - The counter used to measure the time.
+    pub fn new(counter: &'a C) -> Self {
+        let start = counter.cycles();
+        Self {
+            counter,
+            start,
+            last: start,
+            budget: None,
+            iterations: 0,
+            max_iteration_cycles: 0,
+        }
+    }
+
+    /// Sets the maximum number of cycles the algorithm is allowed to run.
+    ///
+    /// # Arguments
+    ///
+    /// * `cycles` - The cycle budget, measured from the creation of the timer.
+    pub fn with_budget(mut self, cycles: u64) -> Self {
+        self.budget = Some(cycles);
+        self
+    }
+
+    /// Returns the number of iterations completed.
+    #[inline]
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Returns the number of cycles elapsed from the creation of the timer to
+    /// the end of the last iteration.
+    #[inline]
+    pub fn total_cycles(&self) -> u64 {
+        self.last - self.start
+    }
+
+    /// Returns the number of cycles spent in the slowest iteration.
+    #[inline]
+    pub fn max_iteration_cycles(&self) -> u64 {
+        self.max_iteration_cycles
+    }
+
+    /// Returns the average number of cycles spent in an iteration, or zero if
+    /// no iteration has been completed.
+    #[inline]
+    pub fn mean_iteration_cycles(&self) -> u64 {
+        match self.iterations {
+            0 => 0,
+            n => self.total_cycles() / n as u64,
+        }
+    }
+
+    /// Returns whether the algorithm has been stopped because the budget was
+    /// exhausted.
+    #[inline]
+    pub fn budget_exceeded(&self) -> bool {
+        self.budget
+            .is_some_and(|budget| self.total_cycles() >= budget)
+    }
+}
+
+#[cfg(feature = "profiler")]
+impl<C: CycleCounter> Monitor for IterationTimer<'_, C> {
+    #[inline]
+    fn iteration(&mut self) -> bool {
+        let now = self.counter.cycles();
+        self.max_iteration_cycles = self.max_iteration_cycles.max(now - self.last);
+        self.last = now;
+        self.iterations += 1;
+
+        !self.budget_exceeded()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "profiler")]
+    use profiler::MockCounter;
+
+    use crate::algorithms::SolveReport;
+
+    use super::*;
+
+    #[test]
+    fn test_watchdog_feeder() {
+        let mut feeds = 0;
+        let mut feeder = WatchdogFeeder::new(4, || feeds += 1).with_monitor(SolveReport::new());
+        for i in 0..10 {
+            assert_eq!(feeder.evaluation(|| i * 2), i * 2);
+        }
+        assert!(feeder.iteration());
+
+        let report = feeder.release();
+        assert_eq!(report.evaluations, 10);
+        assert_eq!(report.iterations, 1);
+        assert_eq!(feeds, 2);
+    }
+
+    #[cfg(feature = "profiler")]
+    #[test]
+    fn test_iteration_timer() {
+        let counter = MockCounter::new(0);
+        let mut timer = IterationTimer::new(&counter);
+
+        counter.advance(10);
+        assert!(timer.iteration());
+        counter.advance(30);
+        assert!(timer.iteration());
+        counter.advance(20);
+        assert!(timer.iteration());
+
+        assert_eq!(timer.iterations(), 3);
+        assert_eq!(timer.total_cycles(), 60);
+        assert_eq!(timer.max_iteration_cycles(), 30);
+        assert_eq!(timer.mean_iteration_cycles(), 20);
+        assert!(!timer.budget_exceeded());
+    }
+
+    #[cfg(feature = "profiler")]
+    #[test]
+    fn test_iteration_timer_budget() {
+        let counter = MockCounter::new(0);
+        let mut timer = IterationTimer::new(&counter).with_budget(50);
+
+        counter.advance(40);
+        assert!(timer.iteration());
+        counter.advance(40);
+        assert!(!timer.iteration());
+        assert!(timer.budget_exceeded());
+    }
+}
@@ -1,10 +1,27 @@
+use core::mem::{size_of, size_of_val};
+
 use nalgebra::{SMatrix, SVector};
 
-use crate::algorithms::Algorithm;
+#[cfg(feature = "async")]
+use crate::algorithms::cooperative::YieldEvery;
+use crate::algorithms::{
+    checked_solution,
+    cooperative::{complete, Checkpoint},
+    positive_concentration, Algorithm, Footprint, Monitor, SaturationPolicy, Termination,
+};
 use crate::losses::Loss;
 use crate::models::{EquationModel, Model};
 use crate::params::Variables;
 
+/// The parameters of the Neural Network algorithm.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NeuralNetworkParams {
+    /// What to do with a solution whose saturation is outside the physical
+    /// interval.
+    pub saturation_policy: SaturationPolicy,
+}
+
 /// Implementation of the Neural Network algorithm for the equation model.
 ///
 /// # Type parameters
@@ -18,6 +35,9 @@ pub struct NeuralNetworkEquation<M: Model, L: Loss, const TOPOLOGY: usize> {
     /// The model to be solved.
     model: M,
 
+    /// The parameters of the algorithm.
+    params: NeuralNetworkParams,
+
     // Mean and std for input/output standardization
     input_mean: SVector<f32, 4>,
     input_std: SVector<f32, 4>,
@@ -27,36 +47,38 @@ pub struct NeuralNetworkEquation<M: Model, L: Loss, const TOPOLOGY: usize> {
     _t: core::marker::PhantomData<L>,
 }
 
-impl<M, L> Algorithm<(), M> for NeuralNetworkEquation<M, L, 0>
+impl<M: Model, L: Loss> NeuralNetworkEquation<M, L, 0> {
+    /// Returns the static memory required by the algorithm, see [`Footprint`].
+    ///
+    /// The buffers are the input and the weights, the biases and the output
+    /// of every layer, which are copied to the stack.
+    pub const fn footprint() -> Footprint {
+        Footprint::new(
+            size_of::<Self>(),
+            size_of::<SVector<f32, 4>>()
+                + size_of_val(&models::L16_WEIGHT_0)
+                + size_of_val(&models::L16_BIAS_0)
+                + size_of::<SVector<f32, 16>>()
+                + size_of_val(&models::L16_WEIGHT_1)
+                + size_of_val(&models::L16_BIAS_1)
+                + size_of::<SVector<f32, 3>>(),
+        )
+    }
+}
+
+impl<M, L> NeuralNetworkEquation<M, L, 0>
 where
     M: EquationModel,
     L: Loss<ModelOutput = f32>,
 {
-    /// Create a new instance of the Neural Network algorithm.
-    ///
-    /// # Arguments
-    ///
-    /// * `params` - The parameters of the algorithm.
-    /// * `model` - The model to be solved by the algorithm.
-    fn new(_: (), model: M) -> Self {
-        Self {
-            model,
-            input_mean: SVector::<f32, 4>::new(-0.002274, -0.002545, 1.241e-06, 38.94),
-            input_std: SVector::<f32, 4>::new(0.001004, 0.001047, 5.142e-07, 15.5),
-            output_mean: SVector::<f32, 3>::new(0.01102, 21.13, 0.5935),
-            output_std: SVector::<f32, 3>::new(0.01253, 25.15, 0.2052),
-            _t: core::marker::PhantomData,
-        }
-    }
-
-    /// Tries to solve the model for the given parameters using the Neural
-    /// Network algorithm and returns the best solution found.
-    ///
-    /// # Returns
-    ///
-    /// * `Some((vars, loss))` - The variables and the loss of the solution.
-    /// * `None` - If the algorithm could not find a solution.
-    fn run(&self) -> Option<(Variables, f32)> {
+    /// Solves the model, notifying the checkpoint after every layer of the
+    /// network, which costs about as much as an evaluation of the model, and
+    /// after the evaluation of the model.
+    async fn solve<O: Monitor, C: Checkpoint>(
+        &self,
+        monitor: &mut O,
+        mut checkpoint: C,
+    ) -> Option<(Variables, f32)> {
         let mut x = SVector::<f32, 4>::new(
             self.model.currents().i_ds_on,
             self.model.currents().i_ds_off,
@@ -72,6 +94,9 @@ where
         let weight = SMatrix::<f32, 16, 4>::from_row_slice(&models::L16_WEIGHT_0);
         let bias = SVector::<f32, 16>::from_row_slice(&models::L16_BIAS_0);
         let mut x = weight * x + bias;
+        if C::SUSPENDS {
+            checkpoint.evaluated().await;
+        }
 
         // Activation function: ReLU
         x.apply(|x| {
@@ -84,51 +109,62 @@ where
         let weight = SMatrix::<f32, 3, 16>::from_row_slice(&models::L16_WEIGHT_1);
         let bias = SVector::<f32, 3>::from_row_slice(&models::L16_BIAS_1);
         y = weight * x + bias;
+        if C::SUSPENDS {
+            checkpoint.evaluated().await;
+        }
 
         // Output de-standardization
         y = y.component_mul(&self.output_std) + self.output_mean;
 
-        Some((
-            Variables {
-                concentration: y[0],
-                resistance: y[1],
-                saturation: y[2],
-            },
-            L::evaluate(self.model.value(y[0])),
-        ))
+        let vars = Variables {
+            concentration: positive_concentration(y[0]),
+            resistance: y[1],
+            saturation: y[2],
+        };
+        let loss = L::evaluate(monitor.evaluation(|| self.model.value(vars.concentration)));
+        if C::SUSPENDS {
+            checkpoint.evaluated().await;
+        }
+        monitor.termination(Termination::Exhausted);
+        checked_solution(monitor, self.params.saturation_policy, vars, loss)
+    }
+}
+
+impl<M: Model, L: Loss> NeuralNetworkEquation<M, L, 1> {
+    /// Returns the static memory required by the algorithm, see [`Footprint`].
+    ///
+    /// The buffers are the input and the weights, the biases and the output
+    /// of every layer, which are copied to the stack.
+    pub const fn footprint() -> Footprint {
+        Footprint::new(
+            size_of::<Self>(),
+            size_of::<SVector<f32, 4>>()
+                + size_of_val(&models::L64_32_WEIGHT_0)
+                + size_of_val(&models::L64_32_BIAS_0)
+                + size_of::<SVector<f32, 64>>()
+                + size_of_val(&models::L64_32_WEIGHT_1)
+                + size_of_val(&models::L64_32_BIAS_1)
+                + size_of::<SVector<f32, 32>>()
+                + size_of_val(&models::L64_32_WEIGHT_2)
+                + size_of_val(&models::L64_32_BIAS_2)
+                + size_of::<SVector<f32, 3>>(),
+        )
     }
 }
 
-impl<M, L> Algorithm<(), M> for NeuralNetworkEquation<M, L, 1>
+impl<M, L> NeuralNetworkEquation<M, L, 1>
 where
     M: EquationModel,
     L: Loss<ModelOutput = f32>,
 {
-    /// Create a new instance of the Neural Network algorithm.
-    ///
-    /// # Arguments
-    ///
-    /// * `params` - The parameters of the algorithm.
-    /// * `model` - The model to be solved by the algorithm.
-    fn new(_: (), model: M) -> Self {
-        Self {
-            model,
-            input_mean: SVector::<f32, 4>::new(-0.002274, -0.002545, 1.241e-06, 38.94),
-            input_std: SVector::<f32, 4>::new(0.001004, 0.001047, 5.142e-07, 15.5),
-            output_mean: SVector::<f32, 3>::new(0.01102, 21.13, 0.5935),
-            output_std: SVector::<f32, 3>::new(0.01253, 25.15, 0.2052),
-            _t: core::marker::PhantomData,
-        }
-    }
-
-    /// Tries to solve the model for the given parameters using the Neural
-    /// Network algorithm and returns the best solution found.
-    ///
-    /// # Returns
-    ///
-    /// * `Some((vars, loss))` - The variables and the loss of the solution.
-    /// * `None` - If the algorithm could not find a solution.
-    fn run(&self) -> Option<(Variables, f32)> {
+    /// Solves the model, notifying the checkpoint after every layer of the
+    /// network, which costs about as much as an evaluation of the model, and
+    /// after the evaluation of the model.
+    async fn solve<O: Monitor, C: Checkpoint>(
+        &self,
+        monitor: &mut O,
+        mut checkpoint: C,
+    ) -> Option<(Variables, f32)> {
         let mut x = SVector::<f32, 4>::new(
             self.model.currents().i_ds_on,
             self.model.currents().i_ds_off,
@@ -144,6 +180,9 @@ where
         let weight = SMatrix::<f32, 64, 4>::from_row_slice(&models::L64_32_WEIGHT_0);
         let bias = SVector::<f32, 64>::from_row_slice(&models::L64_32_BIAS_0);
         let mut x = weight * x + bias;
+        if C::SUSPENDS {
+            checkpoint.evaluated().await;
+        }
 
         // Activation function: ReLU
         x.apply(|x| {
@@ -156,6 +195,9 @@ where
         let weight = SMatrix::<f32, 32, 64>::from_row_slice(&models::L64_32_WEIGHT_1);
         let bias = SVector::<f32, 32>::from_row_slice(&models::L64_32_BIAS_1);
         let mut x = weight * x + bias;
+        if C::SUSPENDS {
+            checkpoint.evaluated().await;
+        }
 
         // Activation function: ReLU
         x.apply(|x| {
@@ -168,18 +210,164 @@ where
         let weight = SMatrix::<f32, 3, 32>::from_row_slice(&models::L64_32_WEIGHT_2);
         let bias = SVector::<f32, 3>::from_row_slice(&models::L64_32_BIAS_2);
         y = weight * x + bias;
+        if C::SUSPENDS {
+            checkpoint.evaluated().await;
+        }
 
         // Output de-standardization
         y = y.component_mul(&self.output_std) + self.output_mean;
 
-        Some((
-            Variables {
-                concentration: y[0],
-                resistance: y[1],
-                saturation: y[2],
-            },
-            L::evaluate(self.model.value(y[0])),
-        ))
+        let vars = Variables {
+            concentration: positive_concentration(y[0]),
+            resistance: y[1],
+            saturation: y[2],
+        };
+        let loss = L::evaluate(monitor.evaluation(|| self.model.value(vars.concentration)));
+        if C::SUSPENDS {
+            checkpoint.evaluated().await;
+        }
+        monitor.termination(Termination::Exhausted);
+        checked_solution(monitor, self.params.saturation_policy, vars, loss)
+    }
+}
+
+impl<M, L> Algorithm<NeuralNetworkParams, M> for NeuralNetworkEquation<M, L, 0>
+where
+    M: EquationModel,
+    L: Loss<ModelOutput = f32>,
+{
+    /// Create a new instance of the Neural Network algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the algorithm.
+    /// * `model` - The model to be solved by the algorithm.
+    fn new(params: NeuralNetworkParams, model: M) -> Self {
+        Self {
+            model,
+            params,
+            input_mean: SVector::<f32, 4>::new(-0.002274, -0.002545, 1.241e-06, 38.94),
+            input_std: SVector::<f32, 4>::new(0.001004, 0.001047, 5.142e-07, 15.5),
+            output_mean: SVector::<f32, 3>::new(0.01102, 21.13, 0.5935),
+            output_std: SVector::<f32, 3>::new(0.01253, 25.15, 0.2052),
+            _t: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns a reference to the model solved by the algorithm.
+    #[inline]
+    fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Tries to solve the model for the given parameters using the Neural
+    /// Network algorithm and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified around the evaluation of the model;
+    ///   the network is evaluated in a single pass, so there are no iterations.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    fn run_with<O: Monitor>(&self, monitor: &mut O) -> Option<(Variables, f32)> {
+        complete(self.solve(monitor, ()))
+    }
+
+    /// Tries to solve the model for the given parameters using the Neural
+    /// Network algorithm, yielding to the executor every `yield_every`
+    /// evaluations of the model, and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified around the evaluation of the model;
+    ///   the network is evaluated in a single pass, so there are no iterations.
+    /// * `yield_every` - The number of evaluations between two yields, where
+    ///   every layer of the network counts as an evaluation.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    #[cfg(feature = "async")]
+    fn run_async<O: Monitor>(
+        &self,
+        monitor: &mut O,
+        yield_every: usize,
+    ) -> impl core::future::Future<Output = Option<(Variables, f32)>> {
+        self.solve(monitor, YieldEvery::new(yield_every))
+    }
+}
+
+impl<M, L> Algorithm<NeuralNetworkParams, M> for NeuralNetworkEquation<M, L, 1>
+where
+    M: EquationModel,
+    L: Loss<ModelOutput = f32>,
+{
+    /// Create a new instance of the Neural Network algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the algorithm.
+    /// * `model` - The model to be solved by the algorithm.
+    fn new(params: NeuralNetworkParams, model: M) -> Self {
+        Self {
+            model,
+            params,
+            input_mean: SVector::<f32, 4>::new(-0.002274, -0.002545, 1.241e-06, 38.94),
+            input_std: SVector::<f32, 4>::new(0.001004, 0.001047, 5.142e-07, 15.5),
+            output_mean: SVector::<f32, 3>::new(0.01102, 21.13, 0.5935),
+            output_std: SVector::<f32, 3>::new(0.01253, 25.15, 0.2052),
+            _t: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns a reference to the model solved by the algorithm.
+    #[inline]
+    fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Tries to solve the model for the given parameters using the Neural
+    /// Network algorithm and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified around the evaluation of the model;
+    ///   the network is evaluated in a single pass, so there are no iterations.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    fn run_with<O: Monitor>(&self, monitor: &mut O) -> Option<(Variables, f32)> {
+        complete(self.solve(monitor, ()))
+    }
+
+    /// Tries to solve the model for the given parameters using the Neural
+    /// Network algorithm, yielding to the executor every `yield_every`
+    /// evaluations of the model, and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified around the evaluation of the model;
+    ///   the network is evaluated in a single pass, so there are no iterations.
+    /// * `yield_every` - The number of evaluations between two yields, where
+    ///   every layer of the network counts as an evaluation.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    #[cfg(feature = "async")]
+    fn run_async<O: Monitor>(
+        &self,
+        monitor: &mut O,
+        yield_every: usize,
+    ) -> impl core::future::Future<Output = Option<(Variables, f32)>> {
+        self.solve(monitor, YieldEvery::new(yield_every))
     }
 }
 
@@ -730,6 +918,8 @@ mod tests {
     struct EquationModelMock;
 
     impl Model for EquationModelMock {
+        type Params = ModelParams;
+
         fn new(_: ModelParams, _: Currents) -> Self {
             Self
         }
@@ -777,7 +967,8 @@ mod tests {
     fn test_neural_network_l16_equation() {
         let model = EquationModelMock;
 
-        let algorithm = NeuralNetworkEquation::<_, Absolute, 0>::new((), model);
+        let algorithm =
+            NeuralNetworkEquation::<_, Absolute, 0>::new(NeuralNetworkParams::default(), model);
         let (variables, error) = algorithm.run().unwrap();
 
         assert!((variables.concentration - 0.015_984_175).abs() < 1e-6);
@@ -790,7 +981,8 @@ mod tests {
     fn test_neural_network_l64_32_equation() {
         let model = EquationModelMock;
 
-        let algorithm = NeuralNetworkEquation::<_, Absolute, 1>::new((), model);
+        let algorithm =
+            NeuralNetworkEquation::<_, Absolute, 1>::new(NeuralNetworkParams::default(), model);
         let (variables, error) = algorithm.run().unwrap();
 
         assert!((variables.concentration - 0.016_708_508).abs() < 1e-6);
@@ -1,8 +1,18 @@
+use core::mem::size_of;
+
 #[allow(unused_imports)]
 use micromath::F32Ext;
 
+#[cfg(feature = "async")]
+use crate::algorithms::cooperative::YieldEvery;
 use crate::{
-    algorithms::Algorithm,
+    algorithms::{
+        checked_solution, concentration_min,
+        cooperative::{complete, Checkpoint},
+        iterative_termination, positive_concentration,
+        validation::{check_non_zero, check_positive},
+        Algorithm, Footprint, Monitor, ParamsError, SaturationPolicy, SolveEvent, Termination,
+    },
     losses::Loss,
     models::{EquationModel, Model},
     params::Variables,
@@ -21,12 +31,38 @@ pub struct NewtonParams {
     /// The maximum number of iterations.
     pub max_iterations: usize,
 
+    /// What to do with a solution whose saturation is outside the physical
+    /// interval.
+    pub saturation_policy: SaturationPolicy,
+
     /// The error tolerance at which the algorithm stops.
     pub tolerance: f32,
 }
 
+impl NewtonParams {
+    /// Checks that the parameters are valid: the initial guess and the
+    /// tolerances are positive and there is at least one iteration.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the parameters are valid.
+    /// * `Err(error)` - The first parameter that is not valid.
+    pub fn validate(&self) -> Result<(), ParamsError> {
+        check_positive("concentration_init", self.concentration_init)?;
+        check_positive("grad_tolerance", self.grad_tolerance)?;
+        check_non_zero("max_iterations", self.max_iterations)?;
+        check_positive("tolerance", self.tolerance)
+    }
+}
+
 /// Implementation of the Newton's method.
 ///
+/// The method is safeguarded against divergence: as soon as two iterates
+/// give values of opposite sign, the root is bracketed, and every step that
+/// leaves the bracket or the physical range, or follows a step that
+/// increased the loss, is replaced by a bisection of the bracket. Without a
+/// bracket, the method stops with the best iterate found so far.
+///
 /// # Type parameters
 ///
 /// * `M` - The type of the model.
@@ -41,6 +77,126 @@ pub struct NewtonEquation<M: Model, L: Loss> {
     _t: core::marker::PhantomData<L>,
 }
 
+impl<M: Model, L: Loss> NewtonEquation<M, L> {
+    /// Returns the static memory required by the algorithm, see [`Footprint`].
+    pub const fn footprint() -> Footprint {
+        Footprint::new(size_of::<Self>(), 0)
+    }
+}
+
+impl<M, L> NewtonEquation<M, L>
+where
+    M: EquationModel,
+    L: Loss<ModelOutput = f32>,
+{
+    /// Solves the model, notifying the checkpoint after every evaluation of
+    /// the model.
+    async fn solve<O: Monitor, C: Checkpoint>(
+        &self,
+        monitor: &mut O,
+        mut checkpoint: C,
+    ) -> Option<(Variables, f32)> {
+        // Initialize variable and gradient with starting point.
+        let mut c = positive_concentration(self.params.concentration_init);
+        let mut grad = monitor.evaluation(|| self.model.gradient(c));
+        if C::SUSPENDS {
+            checkpoint.evaluated().await;
+        }
+
+        // Initialize the value of the function at starting point.
+        let mut value = monitor.evaluation(|| self.model.value(c));
+        if C::SUSPENDS {
+            checkpoint.evaluated().await;
+        }
+        let mut error = L::evaluate(value);
+
+        // The best iterate, and the bracket of the root as the two iterates
+        // with values of opposite sign, along with the value of the first.
+        let (mut best_c, mut best_error) = (c, error);
+        let mut bracket: Option<(f32, f32, f32)> = None;
+        let mut growing = false;
+        let mut termination = None;
+
+        // Loop until the maximum number of iterations is reached, the error
+        // subceeds a certain tolerance, or the gradient becomes too small.
+        let mut iterations = 0;
+        while iterations < self.params.max_iterations
+            && error > self.params.tolerance
+            && grad.abs() > self.params.grad_tolerance
+        {
+            // Take the Newton step, unless it diverges.
+            let step = c - value / grad;
+            let valid = match bracket {
+                Some((a, _, b)) => step > a.min(b) && step < a.max(b),
+                None => step >= concentration_min() && step.is_finite(),
+            };
+            let prev_c = c;
+            c = match bracket {
+                _ if valid && !growing => step,
+                Some((a, _, b)) => {
+                    monitor.event(SolveEvent::BracketingStep);
+                    0.5 * (a + b)
+                }
+                None => {
+                    monitor.event(SolveEvent::Diverged);
+                    termination = Some(Termination::Diverged);
+                    break;
+                }
+            };
+            grad = monitor.evaluation(|| self.model.gradient(c));
+            if C::SUSPENDS {
+                checkpoint.evaluated().await;
+            }
+
+            // Update the function value and loss.
+            let prev_value = value;
+            let prev_error = error;
+            value = monitor.evaluation(|| self.model.value(c));
+            if C::SUSPENDS {
+                checkpoint.evaluated().await;
+            }
+            error = L::evaluate(value);
+            growing = error >= prev_error;
+            monitor.step(error, grad, c - prev_c);
+
+            // Update the best iterate and the bracket.
+            if error < best_error {
+                (best_c, best_error) = (c, error);
+            }
+            bracket = match bracket {
+                Some((a, fa, _)) if (value < 0.0) != (fa < 0.0) => Some((a, fa, c)),
+                Some((_, _, b)) => Some((c, value, b)),
+                None if (value < 0.0) != (prev_value < 0.0) => Some((prev_c, prev_value, c)),
+                None => None,
+            };
+
+            iterations += 1;
+
+            if !monitor.iteration() {
+                termination = Some(Termination::Stopped);
+                break;
+            }
+        }
+        monitor.termination(termination.unwrap_or_else(|| {
+            iterative_termination(
+                error,
+                self.params.tolerance,
+                grad,
+                self.params.grad_tolerance,
+            )
+        }));
+
+        let c = best_c;
+        let error = best_error;
+        let vars = Variables {
+            concentration: c,
+            resistance: self.model.resistance(c),
+            saturation: self.model.saturation(c),
+        };
+        checked_solution(monitor, self.params.saturation_policy, vars, error)
+    }
+}
+
 impl<M, L> Algorithm<NewtonParams, M> for NewtonEquation<M, L>
 where
     M: EquationModel,
@@ -60,53 +216,55 @@ where
         }
     }
 
+    /// Returns a reference to the model solved by the algorithm.
+    #[inline]
+    fn model(&self) -> &M {
+        &self.model
+    }
+
     /// Tries to solve the model for the given parameters using the Newton's
     /// method and returns the best solution found.
     ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    ///
     /// # Returns
     ///
     /// * `Some((vars, loss))` - The variables and the loss of the solution.
     /// * `None` - If the algorithm could not find a solution.
-    fn run(&self) -> Option<(Variables, f32)> {
-        // Initialize variable and gradient with starting point.
-        let mut c = self.params.concentration_init;
-        let mut grad = self.model.gradient(c);
-
-        // Initialize the value of the function at starting point.
-        let mut value = self.model.value(c);
-        let mut error = L::evaluate(value);
-
-        // Loop until the maximum number of iterations is reached, the error
-        // subceeds a certain tolerance, or the gradient becomes too small.
-        let mut iterations = 0;
-        while iterations < self.params.max_iterations
-            && error > self.params.tolerance
-            && grad.abs() > self.params.grad_tolerance
-        {
-            // Update variable and gradient.
-            c -= value / grad;
-            grad = self.model.gradient(c);
-
-            // Update the function value and loss.
-            value = self.model.value(c);
-            error = L::evaluate(value);
-
-            iterations += 1;
-        }
+    fn run_with<O: Monitor>(&self, monitor: &mut O) -> Option<(Variables, f32)> {
+        complete(self.solve(monitor, ()))
+    }
 
-        Some((
-            Variables {
-                concentration: c,
-                resistance: self.model.resistance(c),
-                saturation: self.model.saturation(c),
-            },
-            error,
-        ))
+    /// Tries to solve the model for the given parameters using the Newton's
+    /// method, yielding to the executor every `yield_every` evaluations of
+    /// the model, and returns the best solution found.
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor` - The monitor notified at the end of every iteration and
+    ///   around every evaluation of the model.
+    /// * `yield_every` - The number of evaluations between two yields.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((vars, loss))` - The variables and the loss of the solution.
+    /// * `None` - If the algorithm could not find a solution.
+    #[cfg(feature = "async")]
+    fn run_async<O: Monitor>(
+        &self,
+        monitor: &mut O,
+        yield_every: usize,
+    ) -> impl core::future::Future<Output = Option<(Variables, f32)>> {
+        self.solve(monitor, YieldEvery::new(yield_every))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::algorithms::SolveReport;
     use crate::losses::Absolute;
     use crate::models::Model;
     use crate::params::{Currents, ModelParams};
@@ -116,6 +274,8 @@ mod tests {
     struct EquationModelMock;
 
     impl Model for EquationModelMock {
+        type Params = ModelParams;
+
         fn new(_: ModelParams, _: Currents) -> Self {
             Self
         }
@@ -147,12 +307,110 @@ mod tests {
         }
     }
 
+    /// Model with a single root at 2, where the Newton's method diverges
+    /// from starting points far from the root.
+    struct ArctanModelMock;
+
+    impl Model for ArctanModelMock {
+        type Params = ModelParams;
+
+        fn new(_: ModelParams, _: Currents) -> Self {
+            Self
+        }
+
+        fn params(&self) -> &ModelParams {
+            unimplemented!()
+        }
+
+        fn currents(&self) -> &Currents {
+            unimplemented!()
+        }
+    }
+
+    impl EquationModel for ArctanModelMock {
+        fn value(&self, x: f32) -> f32 {
+            (x - 2.0).atan()
+        }
+
+        fn gradient(&self, x: f32) -> f32 {
+            1.0 / (1.0 + (x - 2.0).powi(2))
+        }
+
+        fn resistance(&self, x: f32) -> f32 {
+            x
+        }
+
+        fn saturation(&self, x: f32) -> f32 {
+            x
+        }
+    }
+
+    #[test]
+    fn test_newton_equation_bracketing() {
+        let params = NewtonParams {
+            concentration_init: 3.5,
+            grad_tolerance: 1e-6,
+            max_iterations: 30,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-5,
+        };
+        let algorithm = NewtonEquation::<_, Absolute>::new(params, ArctanModelMock);
+        let mut report = SolveReport::new();
+        let (variables, error) = algorithm.run_with(&mut report).unwrap();
+
+        assert!((variables.concentration - 2.0).abs() < 1e-4);
+        assert!(error < 1e-5);
+        assert!(report.bracketing_steps > 0);
+        assert!(!report.diverged);
+        assert!(report.diagnostics.converged());
+        assert_eq!(report.diagnostics.losses, report.iterations);
+    }
+
+    #[test]
+    fn test_newton_equation_diverged() {
+        let params = NewtonParams {
+            concentration_init: 4.0,
+            grad_tolerance: 1e-6,
+            max_iterations: 30,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-5,
+        };
+        let algorithm = NewtonEquation::<_, Absolute>::new(params, ArctanModelMock);
+        let mut report = SolveReport::new();
+        let (variables, error) = algorithm.run_with(&mut report).unwrap();
+
+        // The first step leaves the physical range, so the starting point is
+        // the best iterate.
+        assert_eq!(variables.concentration, 4.0);
+        assert_eq!(error, 2.0f32.atan());
+        assert!(report.diverged);
+        assert_eq!(report.iterations, 0);
+        assert_eq!(report.diagnostics.termination, Some(Termination::Diverged));
+        assert_eq!(report.diagnostics.gradient, None);
+    }
+
+    #[test]
+    fn test_newton_equation_non_finite() {
+        let params = NewtonParams {
+            concentration_init: f32::NAN,
+            grad_tolerance: 1e-6,
+            max_iterations: 20,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-6,
+        };
+        let algorithm = NewtonEquation::<_, Absolute>::new(params, EquationModelMock);
+        let mut report = SolveReport::new();
+        assert_eq!(algorithm.run_with(&mut report), None);
+        assert!(report.non_finite_solution);
+    }
+
     #[test]
     fn test_newton_equation() {
         let params = NewtonParams {
             concentration_init: 0.5,
             grad_tolerance: 1e-6,
             max_iterations: 20,
+            saturation_policy: SaturationPolicy::Keep,
             tolerance: 1e-6,
         };
         let model = EquationModelMock;
@@ -165,4 +423,38 @@ mod tests {
         assert!((variables.saturation - 0.865_474_03).abs() < 1e-6);
         assert!(error.abs() < 1e-6);
     }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_newton_equation_async() {
+        use crate::algorithms::step;
+
+        let params = NewtonParams {
+            concentration_init: 0.5,
+            grad_tolerance: 1e-6,
+            max_iterations: 20,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-6,
+        };
+        let algorithm = NewtonEquation::<_, Absolute>::new(params, EquationModelMock);
+
+        // Step the solver until completion, counting the yields.
+        let mut report = SolveReport::new();
+        let mut yields = 0;
+        let result = {
+            let mut solver = core::pin::pin!(algorithm.run_async(&mut report, 2));
+            loop {
+                match step(solver.as_mut()) {
+                    Some(result) => break result,
+                    None => yields += 1,
+                }
+            }
+        };
+
+        // The value and the gradient are evaluated at every iterate.
+        assert_eq!(result, algorithm.run());
+        assert!(report.iterations > 1);
+        assert_eq!(report.evaluations, 2 * (report.iterations + 1));
+        assert_eq!(yields, report.iterations + 1);
+    }
 }
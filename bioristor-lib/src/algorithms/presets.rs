@@ -0,0 +1,348 @@
+#[cfg(feature = "adaptive2")]
+use crate::algorithms::Adaptive2Params;
+#[cfg(feature = "adaptive")]
+use crate::algorithms::AdaptiveParams;
+#[cfg(feature = "brute-force")]
+use crate::algorithms::BruteForceParams;
+#[cfg(feature = "gradient-descent")]
+use crate::algorithms::GradientDescentParams;
+#[cfg(feature = "newton")]
+use crate::algorithms::NewtonParams;
+#[cfg(any(
+    feature = "adaptive",
+    feature = "adaptive2",
+    feature = "brute-force",
+    feature = "gradient-descent",
+    feature = "newton"
+))]
+use crate::algorithms::SaturationPolicy;
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+use crate::utils::FloatRange;
+
+/// The range of concentrations searched by the presets [Molarity].
+#[cfg(any(feature = "adaptive2", feature = "brute-force"))]
+const CONCENTRATION: (f32, f32) = (1e-4, 1e-1);
+
+/// The range of wet drain-source resistances searched by the presets [Ohm].
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+const RESISTANCE: (f32, f32) = (10.0, 100.0);
+
+/// The range of water saturations searched by the presets.
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+const SATURATION: (f32, f32) = (0.0, 1.0);
+
+/// The sizes of the grids and the numbers of iterations of a class of cores.
+///
+/// The entries of the algorithms disabled by the features are not read.
+#[cfg_attr(
+    not(all(
+        feature = "adaptive",
+        feature = "adaptive2",
+        feature = "brute-force",
+        feature = "gradient-descent",
+        feature = "newton"
+    )),
+    allow(dead_code)
+)]
+struct Table {
+    /// The number of concentrations of every iteration of the adaptive
+    /// algorithm.
+    adaptive_steps: usize,
+
+    /// The number of concentrations of every iteration of the adaptive
+    /// algorithm v2.
+    adaptive2_steps: usize,
+
+    /// The number of concentrations of the brute force algorithm.
+    brute_force_steps: usize,
+
+    /// The maximum number of iterations of the gradient descent.
+    gradient_descent_iterations: usize,
+
+    /// The maximum number of iterations of the adaptive algorithms and of
+    /// the Newton's method.
+    iterations: usize,
+
+    /// The number of resistances and saturations searched by the algorithms
+    /// of the system model.
+    variable_steps: usize,
+}
+
+/// The class of the core running the algorithms, which selects the presets
+/// of their parameters.
+///
+/// The presets of the Cortex-M7 are the settings of the example firmware on
+/// the NUCLEO-F767ZI at 216 MHz. The ones of the other classes scale the
+/// grids and the iterations by the cost of an evaluation of the model on
+/// the class, so that a solution takes a comparable time: the Cortex-M4
+/// runs at a lower clock, the Cortex-M0+ has no floating point unit and the
+/// host is faster than any target. The searched ranges and the tolerances
+/// are the same for every class.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::algorithms::{Adaptive2Params, CpuClass};
+///
+/// const ALG_PARAMS: Adaptive2Params = CpuClass::CortexM4.adaptive2_params();
+/// assert_eq!(ALG_PARAMS.validate(), Ok(()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CpuClass {
+    /// Cortex-M0+ cores, e.g. the RP2040, without a floating point unit.
+    CortexM0Plus,
+
+    /// Cortex-M4F cores, e.g. the STM32L476 and the nRF52840.
+    CortexM4,
+
+    /// Cortex-M7F cores, e.g. the STM32F767.
+    CortexM7,
+
+    /// The host, e.g. the simulator and the tests.
+    Host,
+}
+
+impl CpuClass {
+    /// The class selected by the `cpu-m0plus`, `cpu-m4` and `cpu-m7`
+    /// features, or the host when none of them is enabled.
+    ///
+    /// When more features are enabled, the least powerful class takes
+    /// precedence, so that the presets never exceed the budget of any of
+    /// the enabled classes.
+    pub const SELECTED: CpuClass = if cfg!(feature = "cpu-m0plus") {
+        CpuClass::CortexM0Plus
+    } else if cfg!(feature = "cpu-m4") {
+        CpuClass::CortexM4
+    } else if cfg!(feature = "cpu-m7") {
+        CpuClass::CortexM7
+    } else {
+        CpuClass::Host
+    };
+
+    /// Returns the table of the class.
+    #[cfg(any(
+        feature = "adaptive",
+        feature = "adaptive2",
+        feature = "brute-force",
+        feature = "gradient-descent",
+        feature = "newton"
+    ))]
+    const fn table(self) -> &'static Table {
+        match self {
+            CpuClass::CortexM0Plus => &Table {
+                adaptive_steps: 20,
+                adaptive2_steps: 100,
+                brute_force_steps: 10_000,
+                gradient_descent_iterations: 20,
+                iterations: 8,
+                variable_steps: 20,
+            },
+            CpuClass::CortexM4 => &Table {
+                adaptive_steps: 50,
+                adaptive2_steps: 250,
+                brute_force_steps: 25_000,
+                gradient_descent_iterations: 50,
+                iterations: 10,
+                variable_steps: 50,
+            },
+            CpuClass::CortexM7 => &Table {
+                adaptive_steps: 100,
+                adaptive2_steps: 1_000,
+                brute_force_steps: 100_000,
+                gradient_descent_iterations: 50,
+                iterations: 10,
+                variable_steps: 100,
+            },
+            CpuClass::Host => &Table {
+                adaptive_steps: 1_000,
+                adaptive2_steps: 10_000,
+                brute_force_steps: 1_000_000,
+                gradient_descent_iterations: 1_000,
+                iterations: 20,
+                variable_steps: 500,
+            },
+        }
+    }
+
+    /// Returns the range of resistances searched on the class.
+    #[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+    const fn resistance_range(self) -> FloatRange {
+        FloatRange::new(RESISTANCE.0, RESISTANCE.1, self.table().variable_steps)
+    }
+
+    /// Returns the range of saturations searched on the class.
+    #[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+    const fn saturation_range(self) -> FloatRange {
+        FloatRange::new(SATURATION.0, SATURATION.1, self.table().variable_steps)
+    }
+
+    /// Returns the recommended parameters of the adaptive algorithm.
+    #[cfg(feature = "adaptive")]
+    pub const fn adaptive_params(self) -> AdaptiveParams {
+        AdaptiveParams {
+            concentration_init: 1e-2,
+            concentration_steps: self.table().adaptive_steps,
+            max_iterations: self.table().iterations,
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: self.saturation_range(),
+            resistance_range: self.resistance_range(),
+        }
+    }
+
+    /// Returns the recommended parameters of the adaptive algorithm v2.
+    #[cfg(feature = "adaptive2")]
+    pub const fn adaptive2_params(self) -> Adaptive2Params {
+        Adaptive2Params {
+            concentration_range: FloatRange::new(
+                CONCENTRATION.0,
+                CONCENTRATION.1,
+                self.table().adaptive2_steps,
+            ),
+            max_iterations: self.table().iterations,
+            min_range_width: 0.0,
+            reduction_factor: 0.2,
+            resistance_range: self.resistance_range(),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: self.saturation_range(),
+            tolerance: 1e-15,
+        }
+    }
+
+    /// Returns the recommended parameters of the brute force algorithm.
+    #[cfg(feature = "brute-force")]
+    pub const fn brute_force_params(self) -> BruteForceParams {
+        BruteForceParams {
+            concentration_range: FloatRange::new(
+                CONCENTRATION.0,
+                CONCENTRATION.1,
+                self.table().brute_force_steps,
+            ),
+            resistance_range: self.resistance_range(),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: self.saturation_range(),
+        }
+    }
+
+    /// Returns the recommended parameters of the gradient descent.
+    #[cfg(feature = "gradient-descent")]
+    pub const fn gradient_descent_params(self) -> GradientDescentParams {
+        GradientDescentParams {
+            concentration_init: 1e-2,
+            grad_tolerance: 1e-20,
+            learning_rate_init: 100.0,
+            max_iterations: self.table().gradient_descent_iterations,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-15,
+        }
+    }
+
+    /// Returns the recommended parameters of the Newton's method.
+    #[cfg(feature = "newton")]
+    pub const fn newton_params(self) -> NewtonParams {
+        NewtonParams {
+            concentration_init: 1e-2,
+            grad_tolerance: 1e-9,
+            max_iterations: self.table().iterations,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-15,
+        }
+    }
+}
+
+impl Default for CpuClass {
+    /// Returns the class selected by the features.
+    fn default() -> Self {
+        Self::SELECTED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(
+        feature = "adaptive",
+        feature = "adaptive2",
+        feature = "brute-force",
+        feature = "gradient-descent",
+        feature = "newton"
+    ))]
+    const CLASSES: [CpuClass; 4] = [
+        CpuClass::CortexM0Plus,
+        CpuClass::CortexM4,
+        CpuClass::CortexM7,
+        CpuClass::Host,
+    ];
+
+    #[cfg(any(
+        feature = "adaptive",
+        feature = "adaptive2",
+        feature = "brute-force",
+        feature = "gradient-descent",
+        feature = "newton"
+    ))]
+    #[test]
+    fn test_presets_validate() {
+        for class in CLASSES {
+            #[cfg(feature = "adaptive")]
+            assert_eq!(class.adaptive_params().validate(), Ok(()));
+            #[cfg(feature = "adaptive2")]
+            assert_eq!(class.adaptive2_params().validate(), Ok(()));
+            #[cfg(feature = "brute-force")]
+            assert_eq!(class.brute_force_params().validate(), Ok(()));
+            #[cfg(feature = "gradient-descent")]
+            assert_eq!(class.gradient_descent_params().validate(), Ok(()));
+            #[cfg(feature = "newton")]
+            assert_eq!(class.newton_params().validate(), Ok(()));
+        }
+    }
+
+    #[cfg(all(
+        feature = "adaptive",
+        feature = "adaptive2",
+        feature = "brute-force",
+        feature = "gradient-descent",
+        feature = "newton"
+    ))]
+    #[test]
+    fn test_presets_scale() {
+        // More powerful classes never search coarser grids.
+        for pair in CLASSES.windows(2) {
+            let (low, high) = (pair[0].table(), pair[1].table());
+            assert!(low.adaptive_steps <= high.adaptive_steps);
+            assert!(low.adaptive2_steps <= high.adaptive2_steps);
+            assert!(low.brute_force_steps <= high.brute_force_steps);
+            assert!(low.gradient_descent_iterations <= high.gradient_descent_iterations);
+            assert!(low.iterations <= high.iterations);
+            assert!(low.variable_steps <= high.variable_steps);
+        }
+    }
+
+    #[cfg(all(feature = "adaptive2", feature = "brute-force"))]
+    #[test]
+    fn test_presets_m7() {
+        // The presets of the Cortex-M7 are the settings of the example
+        // firmware.
+        let params = CpuClass::CortexM7.adaptive2_params();
+        assert_eq!(
+            params.concentration_range,
+            FloatRange::new(1e-4, 1e-1, 1_000)
+        );
+        assert_eq!(params.max_iterations, 10);
+        assert_eq!(params.resistance_range, FloatRange::new(10.0, 100.0, 100));
+        assert_eq!(
+            CpuClass::CortexM7.brute_force_params().concentration_range,
+            FloatRange::new(1e-4, 1e-1, 100_000)
+        );
+    }
+
+    #[test]
+    fn test_selected() {
+        assert_eq!(CpuClass::default(), CpuClass::SELECTED);
+        #[cfg(not(any(feature = "cpu-m0plus", feature = "cpu-m4", feature = "cpu-m7")))]
+        assert_eq!(CpuClass::SELECTED, CpuClass::Host);
+        #[cfg(feature = "cpu-m0plus")]
+        assert_eq!(CpuClass::SELECTED, CpuClass::CortexM0Plus);
+    }
+}
@@ -0,0 +1,410 @@
+#[cfg(feature = "instrument")]
+use profiler::CycleCounter;
+
+use super::{Monitor, SolveEvent, Termination};
+#[cfg(feature = "system")]
+use crate::{
+    models::condition_number,
+    params::{Currents, ModelParams, Variables},
+};
+
+/// Diagnostics of the convergence of an algorithm, collected by the
+/// [`SolveReport`], which distinguish the runs that converged from the ones
+/// that gave up.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Diagnostics {
+    /// The condition number of the Jacobian matrix of the system model at
+    /// the solution, if recorded with [`SolveReport::record_condition`].
+    pub condition: Option<f32>,
+
+    /// The magnitude of the gradient at the last step, if the algorithm is
+    /// iterative and took at least a step.
+    pub gradient: Option<f32>,
+
+    /// The number of losses in the history of the steps.
+    pub losses: usize,
+
+    /// The size of the last step, if the algorithm is iterative and took at
+    /// least a step.
+    pub step: Option<f32>,
+
+    /// The reason of the termination, if the algorithm terminated.
+    pub termination: Option<Termination>,
+}
+
+impl Diagnostics {
+    /// The condition number above which a solution is ill-conditioned: the
+    /// relative noise of the currents is amplified more than a thousand times
+    /// in the relative error of the variables.
+    pub const ILL_CONDITIONED: f32 = 1e3;
+
+    /// Returns whether the algorithm converged, i.e. reached the tolerance or
+    /// evaluated the whole search grid.
+    #[inline]
+    pub fn converged(&self) -> bool {
+        matches!(
+            self.termination,
+            Some(Termination::Converged | Termination::Exhausted)
+        )
+    }
+
+    /// Returns whether the recorded condition number exceeds
+    /// [`ILL_CONDITIONED`](Self::ILL_CONDITIONED).
+    #[inline]
+    pub fn is_ill_conditioned(&self) -> bool {
+        self.condition
+            .is_some_and(|condition| condition > Self::ILL_CONDITIONED)
+    }
+}
+
+/// Report of the execution of an algorithm.
+///
+/// The report is itself a [`Monitor`] that counts the iterations and the
+/// evaluations of the model. With the `instrument` feature enabled, the
+/// [`PhaseTimer`] monitor also fills in the number of cycles spent in the
+/// evaluation of the model and in the bookkeeping of the algorithm.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::algorithms::SolveReport;
+///
+/// let mut report = SolveReport::new();
+///
+/// // let res = algorithm.run_with(&mut report);
+///
+/// let evaluations = report.evaluations;
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SolveReport {
+    /// Whether the best concentration of a grid search was on the boundary
+    /// of the grid.
+    pub boundary_hit: bool,
+
+    /// The number of bisection steps used in place of diverging steps.
+    pub bracketing_steps: usize,
+
+    /// The diagnostics of the convergence.
+    pub diagnostics: Diagnostics,
+
+    /// Whether the algorithm stopped early because it diverged.
+    pub diverged: bool,
+
+    /// The number of cycles spent in the evaluation of the model.
+    #[cfg(feature = "instrument")]
+    pub evaluation_cycles: u64,
+
+    /// Whether the initial guess of the concentration had to be corrected.
+    pub init_corrected: bool,
+
+    /// The number of evaluations of the model.
+    pub evaluations: usize,
+
+    /// The number of iterations completed.
+    pub iterations: usize,
+
+    /// The number of times the adaptive learning rate could not be computed
+    /// and the initial one has been used instead.
+    pub learning_rate_fallbacks: usize,
+
+    /// The number of candidates skipped because their loss was not finite.
+    pub non_finite_candidates: usize,
+
+    /// Whether the algorithm stopped because an iterate, or the solution,
+    /// was not finite.
+    pub non_finite_solution: bool,
+
+    /// Whether the saturation of the solution was outside the physical
+    /// `[0, 1]` interval, before the saturation policy was applied.
+    pub saturation_out_of_range: bool,
+
+    /// The number of cycles spent in the whole execution.
+    #[cfg(feature = "instrument")]
+    pub total_cycles: u64,
+}
+
+impl SolveReport {
+    /// Creates a new empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Computes the condition number of the Jacobian matrix of the system
+    /// model at the solution and records it in the diagnostics, see
+    /// [`condition_number`].
+    ///
+    /// # Arguments
+    ///
+    /// * `model_params` - The parameters of the model.
+    /// * `currents` - The measured currents.
+    /// * `vars` - The solution found by the algorithm.
+    ///
+    /// # Returns
+    ///
+    /// The condition number.
+    #[cfg(feature = "system")]
+    pub fn record_condition(
+        &mut self,
+        model_params: &ModelParams,
+        currents: &Currents,
+        vars: &Variables,
+    ) -> f32 {
+        let condition = condition_number(model_params, currents, vars);
+        self.diagnostics.condition = Some(condition);
+        condition
+    }
+
+    /// Returns the number of cycles spent in the algorithm outside of the
+    /// evaluation of the model.
+    #[cfg(feature = "instrument")]
+    #[inline]
+    pub fn bookkeeping_cycles(&self) -> u64 {
+        self.total_cycles.saturating_sub(self.evaluation_cycles)
+    }
+}
+
+impl Monitor for SolveReport {
+    #[inline]
+    fn iteration(&mut self) -> bool {
+        self.iterations += 1;
+        true
+    }
+
+    #[inline]
+    fn evaluation<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        self.evaluations += 1;
+        f()
+    }
+
+    #[inline]
+    fn event(&mut self, event: SolveEvent) {
+        match event {
+            SolveEvent::NonFiniteCandidate => self.non_finite_candidates += 1,
+            SolveEvent::NonFiniteSolution => self.non_finite_solution = true,
+            SolveEvent::LearningRateFallback => self.learning_rate_fallbacks += 1,
+            SolveEvent::BracketingStep => self.bracketing_steps += 1,
+            SolveEvent::Diverged => self.diverged = true,
+            SolveEvent::InitCorrected => self.init_corrected = true,
+            SolveEvent::SaturationOutOfRange => self.saturation_out_of_range = true,
+            SolveEvent::BoundaryHit => self.boundary_hit = true,
+        }
+    }
+
+    #[inline]
+    fn step(&mut self, _loss: f32, gradient: f32, step: f32) {
+        self.diagnostics.gradient = Some(gradient.abs());
+        self.diagnostics.losses += 1;
+        self.diagnostics.step = Some(step.abs());
+    }
+
+    #[inline]
+    fn termination(&mut self, termination: Termination) {
+        self.diagnostics.termination = Some(termination);
+    }
+}
+
+/// Monitor that measures the cycles spent in the evaluation of the model and
+/// in the bookkeeping of the algorithm, and collects them in a [`SolveReport`].
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::algorithms::PhaseTimer;
+/// use profiler::MockCounter;
+///
+/// let counter = MockCounter::new(100);
+/// let mut timer = PhaseTimer::new(&counter);
+///
+/// // let res = algorithm.run_with(&mut timer);
+///
+/// let report = timer.finish();
+/// let bookkeeping = report.bookkeeping_cycles();
+/// ```
+#[cfg(feature = "instrument")]
+#[derive(Debug)]
+pub struct PhaseTimer<'a, C: CycleCounter> {
+    /// The counter used to measure the time.
+    counter: &'a C,
+
+    /// The value of the counter when the timer was created.
+    start: u64,
+
+    /// The report being collected.
+    report: SolveReport,
+}
+
+#[cfg(feature = "instrument")]
+impl<'a, C: CycleCounter> PhaseTimer<'a, C> {
+    /// Creates a new timer and starts measuring.
+    ///
+    /// # Arguments
+    ///
+    /// * `counter` - The counter used to measure the time.
+    pub fn new(counter: &'a C) -> Self {
+        Self {
+            counter,
+            start: counter.cycles(),
+            report: SolveReport::default(),
+        }
+    }
+
+    /// Returns the report collected so far.
+    #[inline]
+    pub fn report(&self) -> &SolveReport {
+        &self.report
+    }
+
+    /// Stops measuring and returns the report.
+    ///
+    /// # Returns
+    ///
+    /// The report, including the cycles spent since the last iteration.
+    pub fn finish(mut self) -> SolveReport {
+        self.report.total_cycles = self.counter.cycles() - self.start;
+        self.report
+    }
+}
+
+#[cfg(feature = "instrument")]
+impl<C: CycleCounter> Monitor for PhaseTimer<'_, C> {
+    #[inline]
+    fn iteration(&mut self) -> bool {
+        self.report.total_cycles = self.counter.cycles() - self.start;
+        self.report.iteration()
+    }
+
+    #[inline]
+    fn evaluation<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        let start = self.counter.cycles();
+        let res = self.report.evaluation(f);
+        let end = self.counter.cycles();
+
+        self.report.evaluation_cycles += end - start;
+        self.report.total_cycles = end - self.start;
+        res
+    }
+
+    #[inline]
+    fn event(&mut self, event: SolveEvent) {
+        self.report.event(event)
+    }
+
+    #[inline]
+    fn step(&mut self, loss: f32, gradient: f32, step: f32) {
+        self.report.step(loss, gradient, step)
+    }
+
+    #[inline]
+    fn termination(&mut self, termination: Termination) {
+        self.report.termination(termination)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "brute-force")]
+    use crate::{
+        algorithms::{Algorithm, BruteForceEquation, BruteForceParams, SaturationPolicy},
+        losses::Absolute,
+        models::{EquationModel, Model},
+        params::{Currents, ModelParams},
+        utils::FloatRange,
+    };
+
+    #[cfg(any(feature = "brute-force", feature = "instrument"))]
+    use super::*;
+
+    #[cfg(feature = "brute-force")]
+    struct EquationModelMock;
+
+    #[cfg(feature = "brute-force")]
+    impl Model for EquationModelMock {
+        type Params = ModelParams;
+
+        fn new(_: ModelParams, _: Currents) -> Self {
+            Self
+        }
+
+        fn params(&self) -> &ModelParams {
+            unimplemented!()
+        }
+
+        fn currents(&self) -> &Currents {
+            unimplemented!()
+        }
+    }
+
+    #[cfg(feature = "brute-force")]
+    impl EquationModel for EquationModelMock {
+        fn value(&self, concentration: f32) -> f32 {
+            (concentration - 2.0).powi(2)
+        }
+
+        fn gradient(&self, concentration: f32) -> f32 {
+            2.0 * (concentration - 2.0)
+        }
+
+        fn resistance(&self, concentration: f32) -> f32 {
+            concentration
+        }
+
+        fn saturation(&self, concentration: f32) -> f32 {
+            concentration
+        }
+    }
+
+    #[cfg(feature = "brute-force")]
+    #[test]
+    fn test_solve_report() {
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(0.0, 10.0, 10),
+            resistance_range: FloatRange::new(0.0, 1.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 10),
+        };
+        let steps = params.concentration_range.clone().into_iter().count();
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params, EquationModelMock);
+
+        let mut report = SolveReport::new();
+        algorithm.run_with(&mut report).unwrap();
+
+        assert_eq!(report.iterations, steps);
+        assert_eq!(report.evaluations, steps);
+
+        // The grid search takes no gradient steps.
+        assert_eq!(
+            report.diagnostics,
+            Diagnostics {
+                condition: None,
+                gradient: None,
+                losses: 0,
+                step: None,
+                termination: Some(Termination::Exhausted),
+            }
+        );
+        assert!(report.diagnostics.converged());
+    }
+
+    #[cfg(feature = "instrument")]
+    #[test]
+    fn test_phase_timer() {
+        let counter = profiler::MockCounter::new(0);
+        let mut timer = PhaseTimer::new(&counter);
+
+        counter.advance(10);
+        timer.evaluation(|| counter.advance(100));
+        counter.advance(20);
+        assert!(timer.iteration());
+        timer.evaluation(|| counter.advance(50));
+        counter.advance(5);
+
+        let report = timer.finish();
+        assert_eq!(report.iterations, 1);
+        assert_eq!(report.evaluations, 2);
+        assert_eq!(report.evaluation_cycles, 150);
+        assert_eq!(report.total_cycles, 185);
+        assert_eq!(report.bookkeeping_cycles(), 35);
+    }
+}
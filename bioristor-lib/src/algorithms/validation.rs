@@ -0,0 +1,313 @@
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+use crate::utils::FloatRange;
+
+/// The errors of the validation of the parameters of the algorithms.
+///
+/// Every variant carries the name of the offending field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParamsError {
+    /// A range is empty, reversed, has no steps or has bounds that are not
+    /// finite.
+    InvalidRange(&'static str),
+
+    /// The reduction factor is not in the open interval `(0, 1)`.
+    InvalidReductionFactor(&'static str),
+
+    /// A width is negative or not finite.
+    Negative(&'static str),
+
+    /// A tolerance, a learning rate or an initial value is not positive and
+    /// finite.
+    NotPositive(&'static str),
+
+    /// A number of steps or iterations is zero.
+    Zero(&'static str),
+}
+
+impl ParamsError {
+    /// Returns the name of the field that is not valid.
+    pub fn field(self) -> &'static str {
+        match self {
+            ParamsError::InvalidRange(field)
+            | ParamsError::InvalidReductionFactor(field)
+            | ParamsError::Negative(field)
+            | ParamsError::NotPositive(field)
+            | ParamsError::Zero(field) => field,
+        }
+    }
+
+    /// Returns the description of the error.
+    pub fn message(self) -> &'static str {
+        match self {
+            ParamsError::InvalidRange(_) => "range must be finite, non-empty and with steps",
+            ParamsError::InvalidReductionFactor(_) => "reduction factor must be in (0, 1)",
+            ParamsError::Negative(_) => "value must be non-negative and finite",
+            ParamsError::NotPositive(_) => "value must be positive and finite",
+            ParamsError::Zero(_) => "value must be greater than zero",
+        }
+    }
+}
+
+/// Checks that a range has finite bounds, with the start before the end, and
+/// at least one step.
+#[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+pub(crate) fn check_range(field: &'static str, range: &FloatRange) -> Result<(), ParamsError> {
+    if range.start.is_finite()
+        && range.end.is_finite()
+        && range.start < range.end
+        && range.steps > 0
+    {
+        Ok(())
+    } else {
+        Err(ParamsError::InvalidRange(field))
+    }
+}
+
+/// Checks that a value is positive and finite.
+#[cfg(any(
+    feature = "adaptive2",
+    feature = "gradient-descent",
+    feature = "newton"
+))]
+pub(crate) fn check_positive(field: &'static str, value: f32) -> Result<(), ParamsError> {
+    if value > 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(ParamsError::NotPositive(field))
+    }
+}
+
+/// Checks that a value is non-negative and finite.
+#[cfg(feature = "adaptive2")]
+pub(crate) fn check_non_negative(field: &'static str, value: f32) -> Result<(), ParamsError> {
+    if value >= 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(ParamsError::Negative(field))
+    }
+}
+
+/// Checks that a count is not zero.
+#[cfg(any(
+    feature = "adaptive",
+    feature = "adaptive2",
+    feature = "gradient-descent",
+    feature = "newton"
+))]
+pub(crate) fn check_non_zero(field: &'static str, value: usize) -> Result<(), ParamsError> {
+    if value > 0 {
+        Ok(())
+    } else {
+        Err(ParamsError::Zero(field))
+    }
+}
+
+#[cfg(all(
+    test,
+    any(
+        feature = "adaptive",
+        feature = "adaptive2",
+        feature = "brute-force",
+        feature = "gradient-descent",
+        feature = "newton"
+    )
+))]
+mod tests {
+    use super::*;
+    #[cfg(feature = "adaptive2")]
+    use crate::algorithms::Adaptive2Params;
+    #[cfg(feature = "adaptive")]
+    use crate::algorithms::AdaptiveParams;
+    #[cfg(feature = "brute-force")]
+    use crate::algorithms::BruteForceParams;
+    #[cfg(feature = "gradient-descent")]
+    use crate::algorithms::GradientDescentParams;
+    #[cfg(feature = "newton")]
+    use crate::algorithms::NewtonParams;
+    use crate::algorithms::SaturationPolicy;
+    #[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+    use crate::utils::FloatRange;
+
+    #[cfg(feature = "adaptive2")]
+    fn adaptive2_params() -> Adaptive2Params {
+        Adaptive2Params {
+            concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+            max_iterations: 10,
+            min_range_width: 0.0,
+            reduction_factor: 0.2,
+            resistance_range: FloatRange::new(10.0, 100.0, 100),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 100),
+            tolerance: 1e-15,
+        }
+    }
+
+    #[cfg(feature = "gradient-descent")]
+    fn gradient_descent_params() -> GradientDescentParams {
+        GradientDescentParams {
+            concentration_init: 1e-2,
+            grad_tolerance: 1e-9,
+            learning_rate_init: 0.1,
+            max_iterations: 10,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-15,
+        }
+    }
+
+    #[cfg(any(feature = "adaptive", feature = "adaptive2", feature = "brute-force"))]
+    #[test]
+    fn test_check_range() {
+        assert_eq!(check_range("r", &FloatRange::new(0.0, 1.0, 1)), Ok(()));
+        for range in [
+            FloatRange::new(0.0, 1.0, 0),
+            FloatRange::new(1.0, 1.0, 10),
+            FloatRange::new(1.0, 0.0, 10),
+            FloatRange::new(f32::NAN, 1.0, 10),
+            FloatRange::new(0.0, f32::INFINITY, 10),
+        ] {
+            assert_eq!(
+                check_range("r", &range),
+                Err(ParamsError::InvalidRange("r"))
+            );
+        }
+    }
+
+    #[cfg(feature = "adaptive2")]
+    #[test]
+    fn test_adaptive2_params_validate() {
+        assert_eq!(adaptive2_params().validate(), Ok(()));
+
+        for reduction_factor in [0.0, 1.0, -0.5, f32::NAN] {
+            let params = Adaptive2Params {
+                reduction_factor,
+                ..adaptive2_params()
+            };
+            assert_eq!(
+                params.validate(),
+                Err(ParamsError::InvalidReductionFactor("reduction_factor"))
+            );
+        }
+
+        let params = Adaptive2Params {
+            max_iterations: 0,
+            ..adaptive2_params()
+        };
+        assert_eq!(params.validate(), Err(ParamsError::Zero("max_iterations")));
+
+        for min_range_width in [-1e-6, f32::INFINITY, f32::NAN] {
+            let params = Adaptive2Params {
+                min_range_width,
+                ..adaptive2_params()
+            };
+            let error = params.validate().unwrap_err();
+            assert_eq!(error, ParamsError::Negative("min_range_width"));
+            assert_eq!(error.message(), "value must be non-negative and finite");
+        }
+
+        let params = Adaptive2Params {
+            concentration_range: FloatRange::new(1e-4, 1e-1, 0),
+            ..adaptive2_params()
+        };
+        let error = params.validate().unwrap_err();
+        assert_eq!(error.field(), "concentration_range");
+        assert_eq!(
+            error.message(),
+            "range must be finite, non-empty and with steps"
+        );
+
+        let params = Adaptive2Params {
+            tolerance: 0.0,
+            ..adaptive2_params()
+        };
+        assert_eq!(
+            params.validate(),
+            Err(ParamsError::NotPositive("tolerance"))
+        );
+    }
+
+    #[cfg(feature = "adaptive")]
+    #[test]
+    fn test_adaptive_params_validate() {
+        let params = AdaptiveParams {
+            // The initial guess is corrected by the algorithm.
+            concentration_init: 0.0,
+            concentration_steps: 100,
+            max_iterations: 10,
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 100),
+            resistance_range: FloatRange::new(10.0, 100.0, 100),
+        };
+        assert_eq!(params.validate(), Ok(()));
+
+        let params = AdaptiveParams {
+            concentration_steps: 0,
+            ..params
+        };
+        assert_eq!(
+            params.validate(),
+            Err(ParamsError::Zero("concentration_steps"))
+        );
+    }
+
+    #[cfg(feature = "brute-force")]
+    #[test]
+    fn test_brute_force_params_validate() {
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+            resistance_range: FloatRange::new(10.0, 100.0, 100),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(1.0, 0.0, 100),
+        };
+        assert_eq!(
+            params.validate(),
+            Err(ParamsError::InvalidRange("saturation_range"))
+        );
+    }
+
+    #[cfg(feature = "gradient-descent")]
+    #[test]
+    fn test_gradient_descent_params_validate() {
+        assert_eq!(gradient_descent_params().validate(), Ok(()));
+
+        let params = GradientDescentParams {
+            learning_rate_init: -0.1,
+            ..gradient_descent_params()
+        };
+        assert_eq!(
+            params.validate(),
+            Err(ParamsError::NotPositive("learning_rate_init"))
+        );
+
+        let params = GradientDescentParams {
+            concentration_init: f32::NAN,
+            ..gradient_descent_params()
+        };
+        assert_eq!(
+            params.validate(),
+            Err(ParamsError::NotPositive("concentration_init"))
+        );
+    }
+
+    #[cfg(feature = "newton")]
+    #[test]
+    fn test_newton_params_validate() {
+        let params = NewtonParams {
+            concentration_init: 1e-2,
+            grad_tolerance: 1e-9,
+            max_iterations: 10,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-15,
+        };
+        assert_eq!(params.validate(), Ok(()));
+
+        let params = NewtonParams {
+            grad_tolerance: 0.0,
+            ..params
+        };
+        assert_eq!(
+            params.validate(),
+            Err(ParamsError::NotPositive("grad_tolerance"))
+        );
+    }
+}
@@ -0,0 +1,439 @@
+//! Host simulator running the algorithms of the library on recorded currents.
+//!
+//! The currents are read from a CSV file whose header contains the columns
+//! `i_ds_off`, `i_ds_on` and `i_gs_on` (any other column is ignored), and the
+//! solutions are written as CSV together with the time spent by the algorithm
+//! on each row, so that a change to the algorithms can be checked against the
+//! archived field datasets before flashing the boards.
+//!
+//! ```text
+//! bioristor-sim [-a ALGORITHM] [-l LOSS] [-i INPUT] [-o OUTPUT]
+//! ```
+//!
+//! The input and the output default to the standard input and output. The
+//! brute force runs on all the cores of the host, with the same solutions of
+//! the sequential run.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use bioristor_lib::{
+    algorithms::{
+        Adaptive2Equation, Adaptive2Params, AdaptiveEquation, AdaptiveParams, AdaptiveSystem,
+        Algorithm, BruteForceEquation, BruteForceParams, BruteForceSystem, GradientDescentEquation,
+        GradientDescentParams, NeuralNetworkEquation, NeuralNetworkParams, NewtonEquation,
+        NewtonParams, SaturationPolicy,
+    },
+    dataset::DatasetReader,
+    losses::{Absolute, Loss, MaxRelative, MeanRelative, SumRelative},
+    models::{Equation, Model, System},
+    params::{
+        Currents, ModelParams, ModulationParams, StemResistanceInvParams, Variables, Voltages,
+    },
+    utils::FloatRange,
+};
+
+const USAGE: &str = "\
+Usage: bioristor-sim [OPTIONS]
+
+Options:
+  -a, --algorithm <NAME>  adaptive, adaptive2, brute-force, gradient-descent,
+                          newton, neural-network or neural-network-2
+                          [default: adaptive2]
+  -l, --loss <NAME>       absolute (equation model), max-relative,
+                          mean-relative or sum-relative (system model)
+                          [default: absolute]
+  -i, --input <FILE>      CSV file of the currents [default: stdin]
+  -o, --output <FILE>     CSV file of the solutions [default: stdout]
+  -h, --help              Print this message";
+
+/// The header of the output.
+const OUTPUT_HEADER: &str =
+    "i_ds_off,i_ds_on,i_gs_on,concentration,resistance,saturation,loss,time_us";
+
+/// The number of minima averaged by the adaptive algorithms.
+const MINIMA: usize = 10;
+
+const ADAPTIVE_PARAMS: AdaptiveParams = AdaptiveParams {
+    concentration_init: 1e-2,
+    concentration_steps: 1_000,
+    max_iterations: 10,
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 100),
+    resistance_range: FloatRange::new(10.0, 100.0, 100),
+};
+
+const ADAPTIVE2_PARAMS: Adaptive2Params = Adaptive2Params {
+    concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+    max_iterations: 10,
+    min_range_width: 0.0,
+    reduction_factor: 0.2,
+    resistance_range: FloatRange::new(10.0, 100.0, 100),
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 100),
+    tolerance: 1e-15,
+};
+
+const BRUTE_FORCE_PARAMS: BruteForceParams = BruteForceParams {
+    concentration_range: FloatRange::new(1e-4, 1e-1, 100_000),
+    resistance_range: FloatRange::new(10.0, 100.0, 100),
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 100),
+};
+
+const GRADIENT_DESCENT_PARAMS: GradientDescentParams = GradientDescentParams {
+    concentration_init: 1e-2,
+    grad_tolerance: 1e-9,
+    learning_rate_init: 0.1,
+    max_iterations: 10,
+    saturation_policy: SaturationPolicy::Keep,
+    tolerance: 1e-15,
+};
+
+const NEWTON_PARAMS: NewtonParams = NewtonParams {
+    concentration_init: 1e-2,
+    grad_tolerance: 1e-9,
+    max_iterations: 10,
+    saturation_policy: SaturationPolicy::Keep,
+    tolerance: 1e-15,
+};
+
+const MODEL_PARAMS: ModelParams = ModelParams {
+    mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    r_dry: 38.2,
+    res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    voltages: Voltages {
+        v_ds: -0.05,
+        v_gs: 0.5,
+    },
+};
+
+/// The options of the command line.
+#[derive(Debug, PartialEq)]
+struct Options {
+    algorithm: String,
+    input: Option<String>,
+    loss: String,
+    output: Option<String>,
+}
+
+/// The solution of a row and the time spent to find it.
+type Solution = (Option<(Variables, f32)>, Duration);
+
+/// A solver for the rows, i.e. a combination of algorithm, loss and model.
+type Solver = fn(Currents) -> Solution;
+
+/// Parses the command line arguments.
+///
+/// # Arguments
+///
+/// * `args` - The arguments, without the name of the program.
+///
+/// # Returns
+///
+/// * `Ok(Some(options))` - The parsed options.
+/// * `Ok(None)` - If the help has been requested.
+/// * `Err(message)` - If the arguments are not valid.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        algorithm: "adaptive2".into(),
+        input: None,
+        loss: "absolute".into(),
+        output: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-a" | "--algorithm" | "-l" | "--loss" | "-i" | "--input" | "-o" | "--output" => args
+                .next()
+                .ok_or_else(|| format!("missing value for `{arg}`"))?,
+            _ => return Err(format!("unexpected argument `{arg}`")),
+        };
+        match arg.as_str() {
+            "-a" | "--algorithm" => options.algorithm = value,
+            "-l" | "--loss" => options.loss = value,
+            "-i" | "--input" => options.input = Some(value),
+            _ => options.output = Some(value),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+/// Runs an algorithm on the given currents and measures its execution time.
+fn solve<A, P, M>(params: &P, currents: Currents) -> Solution
+where
+    A: Algorithm<P, M>,
+    P: Clone,
+    M: Model<Params = ModelParams>,
+{
+    solve_with::<A, P, M>(params, currents, A::run)
+}
+
+/// Runs an algorithm on the given currents with the given method, e.g. the
+/// parallel run of the brute force, and measures its execution time.
+fn solve_with<A, P, M>(
+    params: &P,
+    currents: Currents,
+    run: fn(&A) -> Option<(Variables, f32)>,
+) -> Solution
+where
+    A: Algorithm<P, M>,
+    P: Clone,
+    M: Model<Params = ModelParams>,
+{
+    let algorithm = A::new(params.clone(), M::new(MODEL_PARAMS, currents));
+    let start = Instant::now();
+    let solution = run(&algorithm);
+    (solution, start.elapsed())
+}
+
+/// Returns the solver of a system model with the given loss.
+fn system_solver<L>(algorithm: &str) -> Option<Solver>
+where
+    L: Loss<ModelOutput = [(f32, f32); 3]>,
+{
+    match algorithm {
+        "adaptive" => Some(|currents| {
+            solve::<AdaptiveSystem<System, L, MINIMA>, _, _>(&ADAPTIVE_PARAMS, currents)
+        }),
+        "brute-force" => Some(|currents| {
+            solve_with::<BruteForceSystem<System, L>, _, _>(
+                &BRUTE_FORCE_PARAMS,
+                currents,
+                BruteForceSystem::run_par,
+            )
+        }),
+        _ => None,
+    }
+}
+
+/// Returns the solver of the given combination of algorithm and loss.
+///
+/// # Returns
+///
+/// * `Ok(solver)` - The solver.
+/// * `Err(message)` - If the combination is not supported.
+fn solver(algorithm: &str, loss: &str) -> Result<Solver, String> {
+    let solver: Option<Solver> = match loss {
+        "absolute" => match algorithm {
+            "adaptive" => Some(|currents| {
+                solve::<AdaptiveEquation<Equation, Absolute, MINIMA>, _, _>(
+                    &ADAPTIVE_PARAMS,
+                    currents,
+                )
+            }),
+            "adaptive2" => Some(|currents| {
+                solve::<Adaptive2Equation<Equation, Absolute, MINIMA>, _, _>(
+                    &ADAPTIVE2_PARAMS,
+                    currents,
+                )
+            }),
+            "brute-force" => Some(|currents| {
+                solve_with::<BruteForceEquation<Equation, Absolute>, _, _>(
+                    &BRUTE_FORCE_PARAMS,
+                    currents,
+                    BruteForceEquation::run_par,
+                )
+            }),
+            "gradient-descent" => Some(|currents| {
+                solve::<GradientDescentEquation<Equation, Absolute>, _, _>(
+                    &GRADIENT_DESCENT_PARAMS,
+                    currents,
+                )
+            }),
+            "newton" => Some(|currents| {
+                solve::<NewtonEquation<Equation, Absolute>, _, _>(&NEWTON_PARAMS, currents)
+            }),
+            "neural-network" => Some(|currents| {
+                solve::<NeuralNetworkEquation<Equation, Absolute, 0>, _, _>(
+                    &NeuralNetworkParams::default(),
+                    currents,
+                )
+            }),
+            "neural-network-2" => Some(|currents| {
+                solve::<NeuralNetworkEquation<Equation, Absolute, 1>, _, _>(
+                    &NeuralNetworkParams::default(),
+                    currents,
+                )
+            }),
+            _ => None,
+        },
+        "max-relative" => system_solver::<MaxRelative>(algorithm),
+        "mean-relative" => system_solver::<MeanRelative>(algorithm),
+        "sum-relative" => system_solver::<SumRelative>(algorithm),
+        _ => return Err(format!("unknown loss `{loss}`")),
+    };
+    solver.ok_or_else(|| format!("algorithm `{algorithm}` not available with loss `{loss}`"))
+}
+
+/// Formats a row of the output; the fields of the solution are left empty if
+/// the algorithm did not find one.
+fn format_row(currents: &Currents, (solution, elapsed): &Solution) -> String {
+    let solution = match solution {
+        Some((vars, loss)) => format!(
+            "{},{},{},{}",
+            vars.concentration, vars.resistance, vars.saturation, loss
+        ),
+        None => ",,,".into(),
+    };
+    format!(
+        "{},{},{},{},{}",
+        currents.i_ds_off,
+        currents.i_ds_on,
+        currents.i_gs_on,
+        solution,
+        elapsed.as_micros()
+    )
+}
+
+/// Solves every row of the input and writes the solutions to the output.
+fn simulate(input: impl BufRead, mut output: impl Write, solver: Solver) -> Result<(), String> {
+    let records = DatasetReader::new(input).map_err(|err| err.to_string())?;
+
+    let write_err = |err: io::Error| err.to_string();
+    writeln!(output, "{OUTPUT_HEADER}").map_err(write_err)?;
+    for record in records {
+        let currents = record.map_err(|err| err.to_string())?.currents;
+        writeln!(output, "{}", format_row(&currents, &solver(currents))).map_err(write_err)?;
+    }
+    output.flush().map_err(write_err)
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let solver = solver(&options.algorithm, &options.loss)?;
+
+    let input: Box<dyn BufRead> = match &options.input {
+        Some(path) => Box::new(BufReader::new(
+            File::open(path).map_err(|err| format!("{path}: {err}"))?,
+        )),
+        None => Box::new(io::stdin().lock()),
+    };
+    let output: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).map_err(|err| format!("{path}: {err}"))?,
+        )),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    simulate(input, output, solver)
+}
+
+fn main() -> ExitCode {
+    let result = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => run(options),
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => Err(format!("{err}\n\n{USAGE}")),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(args(&["-a", "newton", "--output", "out.csv"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            options,
+            Options {
+                algorithm: "newton".into(),
+                input: None,
+                loss: "absolute".into(),
+                output: Some("out.csv".into()),
+            }
+        );
+        assert_eq!(parse_args(args(&["-i", "in.csv", "-h"])), Ok(None));
+        assert!(parse_args(args(&["--loss"])).is_err());
+        assert!(parse_args(args(&["in.csv"])).is_err());
+    }
+
+    #[test]
+    fn test_solver() {
+        assert!(solver("adaptive2", "absolute").is_ok());
+        assert!(solver("brute-force", "mean-relative").is_ok());
+        assert!(solver("adaptive2", "mean-relative").is_err());
+        assert!(solver("newton", "unknown").is_err());
+    }
+
+    #[test]
+    fn test_simulate() {
+        let input = "timestamp,i_gs_on,i_ds_on,i_ds_off\n\
+                     0,1.169828e-6,-0.0026829,-0.0030365\n\
+                     \n\
+                     1,1.169828e-6,-0.0026829,-0.0030365\n";
+        let solver: Solver = |_| {
+            let vars = Variables {
+                concentration: 0.01,
+                resistance: 40.0,
+                saturation: 0.5,
+            };
+            (Some((vars, 0.0)), Duration::from_micros(12))
+        };
+
+        let mut output = Vec::new();
+        simulate(input.as_bytes(), &mut output, solver).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some(OUTPUT_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("-0.0030365,-0.0026829,0.000001169828,0.01,40,0.5,0,12")
+        );
+        assert_eq!(lines.count(), 1);
+    }
+
+    #[test]
+    fn test_simulate_invalid() {
+        let solver: Solver = |_| (None, Duration::ZERO);
+        let mut output = Vec::new();
+        assert_eq!(
+            simulate("i_ds_on,i_gs_on\n".as_bytes(), &mut output, solver),
+            Err("missing column `i_ds_off`".into())
+        );
+        assert_eq!(
+            simulate(
+                "i_ds_off,i_ds_on,i_gs_on\n1,2,x\n".as_bytes(),
+                &mut output,
+                solver
+            ),
+            Err("line 2: invalid i_gs_on `x`".into())
+        );
+    }
+
+    #[test]
+    fn test_format_row_no_solution() {
+        let currents = Currents {
+            i_ds_off: 1.0,
+            i_ds_on: 2.0,
+            i_gs_on: 3.0,
+        };
+        assert_eq!(
+            format_row(&currents, &(None, Duration::from_micros(5))),
+            "1,2,3,,,,,5"
+        );
+    }
+}
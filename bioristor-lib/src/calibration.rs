@@ -0,0 +1,548 @@
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::param_store::ParamStore;
+use crate::params::{Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages};
+
+/// The minimum number of reference solutions needed to fit the parameters.
+pub const MIN_POINTS: usize = 3;
+
+/// The measurement of the device dipped in a reference solution.
+///
+/// With the device immersed in the solution the water saturation is one, so
+/// the system model gives the modulation and the inverse of the stem
+/// resistance at the concentration of the solution directly from the
+/// currents:
+/// ```text
+/// modulation = (i_ds_on - i_gs_on) / i_ds_off - 1
+/// stem_resistance_inv = i_gs_on / v_gs
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CalibrationPoint {
+    /// The concentration of the reference solution [Molarity].
+    pub concentration: f32,
+
+    /// The modulation of the channel.
+    pub modulation: f32,
+
+    /// The inverse of the stem resistance [1 / Ohm].
+    pub stem_resistance_inv: f32,
+}
+
+impl CalibrationPoint {
+    /// Computes the calibration point from the measured currents.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentration` - The concentration of the reference solution [Molarity].
+    /// * `currents` - The currents measured in the reference solution.
+    /// * `voltages` - The input voltages of the device.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(point)` - The calibration point.
+    /// * `None` - If the concentration is not positive, or the currents
+    ///   are not valid.
+    pub fn new(concentration: f32, currents: &Currents, voltages: &Voltages) -> Option<Self> {
+        let point = Self {
+            concentration,
+            modulation: (currents.i_ds_on - currents.i_gs_on) / currents.i_ds_off - 1.0,
+            stem_resistance_inv: currents.i_gs_on / voltages.v_gs,
+        };
+        (concentration > 0.0
+            && concentration.is_finite()
+            && point.modulation.is_finite()
+            && point.stem_resistance_inv.is_finite())
+        .then_some(point)
+    }
+}
+
+/// Fits the parameters of the modulation function to the calibration points,
+/// with the least squares method.
+///
+/// # Arguments
+///
+/// * `points` - The calibration points, at least three at different
+///   concentrations.
+///
+/// # Returns
+///
+/// * `Some(params)` - The parameters of the modulation function.
+/// * `None` - If the points are not enough to determine the parameters.
+pub fn fit_modulation(points: &[CalibrationPoint]) -> Option<ModulationParams> {
+    let [a, b, c] = least_squares(points.iter().map(|point| {
+        (
+            [point.concentration, point.concentration.ln(), 1.0],
+            point.modulation,
+        )
+    }))?;
+    Some(ModulationParams(a, b, c))
+}
+
+/// Fits the parameters of the inverse of the stem resistance function to the
+/// calibration points, with the least squares method.
+///
+/// # Arguments
+///
+/// * `points` - The calibration points, at least two at different
+///   concentrations.
+///
+/// # Returns
+///
+/// * `Some(params)` - The parameters of the inverse of the stem resistance.
+/// * `None` - If the points are not enough to determine the parameters.
+pub fn fit_stem_resistance_inv(points: &[CalibrationPoint]) -> Option<StemResistanceInvParams> {
+    let [a, b] = least_squares(points.iter().map(|point| {
+        (
+            [1.0, point.concentration.powf(0.955)],
+            point.stem_resistance_inv,
+        )
+    }))?;
+    Some(StemResistanceInvParams(a, b))
+}
+
+/// Solves a linear least squares problem with the normal equations, which
+/// are solved with Cramer's rule since they have at most three unknowns.
+///
+/// # Arguments
+///
+/// * `rows` - The rows of the problem: the coefficients of the unknowns and
+///   the known term.
+///
+/// # Returns
+///
+/// * `Some(x)` - The solution of the problem.
+/// * `None` - If the problem does not have a unique solution.
+fn least_squares<const N: usize>(rows: impl Iterator<Item = ([f32; N], f32)>) -> Option<[f32; N]>
+where
+    [[f64; N]; N]: Determinant,
+{
+    // Accumulate in double precision, since the coefficients span several
+    // orders of magnitude.
+    let mut ata = [[0.0f64; N]; N];
+    let mut aty = [0.0f64; N];
+    for (row, y) in rows {
+        for i in 0..N {
+            for j in 0..N {
+                ata[i][j] += row[i] as f64 * row[j] as f64;
+            }
+            aty[i] += row[i] as f64 * y as f64;
+        }
+    }
+
+    // The determinant of a Gram matrix is at most the product of its
+    // diagonal, which gives the scale to detect singular problems.
+    let det = ata.determinant();
+    let scale = (0..N).map(|i| ata[i][i]).product::<f64>();
+    if det.is_nan() || det <= 1e-9 * scale {
+        return None;
+    }
+
+    let mut x = [0.0; N];
+    for (k, x) in x.iter_mut().enumerate() {
+        let mut m = ata;
+        for i in 0..N {
+            m[i][k] = aty[i];
+        }
+        *x = (m.determinant() / det) as f32;
+    }
+    x.iter().all(|x| x.is_finite()).then_some(x)
+}
+
+/// A square matrix whose determinant is computed in closed form, which is
+/// only implemented for the sizes of the fits.
+trait Determinant {
+    /// Computes the determinant of the matrix.
+    fn determinant(&self) -> f64;
+}
+
+impl Determinant for [[f64; 2]; 2] {
+    fn determinant(&self) -> f64 {
+        let m = self;
+        m[0][0] * m[1][1] - m[0][1] * m[1][0]
+    }
+}
+
+impl Determinant for [[f64; 3]; 3] {
+    fn determinant(&self) -> f64 {
+        let m = self;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+}
+
+/// The errors of the [`Calibration`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CalibrationError {
+    /// The calibration has not been started.
+    NotStarted,
+
+    /// There is no room for more points.
+    Full,
+
+    /// The concentration or the measured currents are not valid.
+    InvalidPoint,
+
+    /// Less than [`MIN_POINTS`] points have been collected.
+    NotEnoughPoints,
+
+    /// The points do not determine the parameters, e.g. because some of them
+    /// have the same concentration.
+    Singular,
+
+    /// The parameters have not been fitted yet.
+    NotFitted,
+}
+
+impl CalibrationError {
+    /// Returns the description of the error printed by the console.
+    pub fn message(self) -> &'static str {
+        match self {
+            CalibrationError::NotStarted => "calibration not started",
+            CalibrationError::Full => "too many points",
+            CalibrationError::InvalidPoint => "invalid point",
+            CalibrationError::NotEnoughPoints => "not enough points",
+            CalibrationError::Singular => "points do not determine the parameters",
+            CalibrationError::NotFitted => "parameters not fitted",
+        }
+    }
+}
+
+/// The state of a [`Calibration`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CalibrationState {
+    /// No calibration in progress.
+    Idle,
+
+    /// The points are being collected.
+    Collecting,
+
+    /// The parameters have been fitted and can be saved.
+    Fitted(ModelParams),
+}
+
+/// Guided calibration of the parameters of the model with reference
+/// solutions, replacing the manual procedure with a spreadsheet.
+///
+/// The operator dips the device in solutions of known concentration, one at
+/// a time, and a [`CalibrationPoint`] is measured for each one. Once at least
+/// [`MIN_POINTS`] solutions have been measured, the parameters of the
+/// modulation and of the stem resistance are fitted to the points and can be
+/// saved in a [`ParamStore`], while the other parameters are kept.
+///
+/// # Type parameters
+///
+/// * `N` - The maximum number of points.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut calibration = Calibration::<8>::new();
+/// calibration.start();
+/// for concentration in [1e-4, 1e-3, 1e-2, 1e-1] {
+///     wait_for_operator(concentration);
+///     calibration.add_point(concentration, &measure(), &params.voltages)?;
+/// }
+/// calibration.fit(&params)?;
+/// calibration.save(&mut store, &mut params)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Calibration<const N: usize> {
+    /// The number of collected points.
+    len: usize,
+
+    /// The collected points.
+    points: [CalibrationPoint; N],
+
+    /// The state of the calibration.
+    state: CalibrationState,
+}
+
+impl<const N: usize> Calibration<N> {
+    /// Creates a new idle calibration.
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            points: [CalibrationPoint {
+                concentration: 0.0,
+                modulation: 0.0,
+                stem_resistance_inv: 0.0,
+            }; N],
+            state: CalibrationState::Idle,
+        }
+    }
+
+    /// Returns the state of the calibration.
+    #[inline]
+    pub fn state(&self) -> &CalibrationState {
+        &self.state
+    }
+
+    /// Returns the collected points.
+    #[inline]
+    pub fn points(&self) -> &[CalibrationPoint] {
+        &self.points[..self.len]
+    }
+
+    /// Starts a new calibration, discarding the collected points.
+    pub fn start(&mut self) {
+        self.len = 0;
+        self.state = CalibrationState::Collecting;
+    }
+
+    /// Aborts the calibration.
+    pub fn abort(&mut self) {
+        self.len = 0;
+        self.state = CalibrationState::Idle;
+    }
+
+    /// Adds the measurement of a reference solution. Adding a point after
+    /// the parameters have been fitted discards the fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentration` - The concentration of the reference solution [Molarity].
+    /// * `currents` - The currents measured in the reference solution.
+    /// * `voltages` - The input voltages of the device.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(point)` - The added point.
+    /// * `Err(error)` - If the calibration has not been started, it is full
+    ///   or the point is not valid.
+    pub fn add_point(
+        &mut self,
+        concentration: f32,
+        currents: &Currents,
+        voltages: &Voltages,
+    ) -> Result<CalibrationPoint, CalibrationError> {
+        if self.state == CalibrationState::Idle {
+            return Err(CalibrationError::NotStarted);
+        }
+        if self.len == N {
+            return Err(CalibrationError::Full);
+        }
+        let point = CalibrationPoint::new(concentration, currents, voltages)
+            .ok_or(CalibrationError::InvalidPoint)?;
+
+        self.points[self.len] = point;
+        self.len += 1;
+        self.state = CalibrationState::Collecting;
+        Ok(point)
+    }
+
+    /// Fits the parameters to the collected points.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The current parameters, whose voltages and dry resistance
+    ///   are kept.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(params)` - The fitted parameters.
+    /// * `Err(error)` - If the calibration has not been started, or the
+    ///   points are not enough to determine the parameters.
+    pub fn fit(&mut self, params: &ModelParams) -> Result<&ModelParams, CalibrationError> {
+        if self.state == CalibrationState::Idle {
+            return Err(CalibrationError::NotStarted);
+        }
+        if self.len < MIN_POINTS {
+            return Err(CalibrationError::NotEnoughPoints);
+        }
+
+        let points = self.points();
+        let mod_params = fit_modulation(points).ok_or(CalibrationError::Singular)?;
+        let res_params = fit_stem_resistance_inv(points).ok_or(CalibrationError::Singular)?;
+        self.state = CalibrationState::Fitted(ModelParams {
+            mod_params,
+            res_params,
+            ..params.clone()
+        });
+        match &self.state {
+            CalibrationState::Fitted(params) => Ok(params),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Stores the fitted parameters, applies them and ends the calibration.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The storage of the parameters.
+    /// * `params` - The parameters in use, replaced by the fitted ones.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Ok(()))` - If the parameters have been stored and applied.
+    /// * `Ok(Err(CalibrationError::NotFitted))` - If the parameters have not
+    ///   been fitted.
+    /// * `Err(error)` - If the storage failed; the calibration is kept, so
+    ///   that saving can be retried.
+    pub fn save<S: ParamStore>(
+        &mut self,
+        store: &mut S,
+        params: &mut ModelParams,
+    ) -> Result<Result<(), CalibrationError>, S::Error> {
+        let CalibrationState::Fitted(fitted) = &self.state else {
+            return Ok(Err(CalibrationError::NotFitted));
+        };
+
+        store.store(fitted)?;
+        *params = fitted.clone();
+        self.abort();
+        Ok(Ok(()))
+    }
+}
+
+impl<const N: usize> Default for Calibration<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::param_store::Record;
+
+    const PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    /// Store keeping the kind of the last stored record.
+    #[derive(Default)]
+    struct StoreMock {
+        kind: Option<u8>,
+    }
+
+    impl ParamStore for StoreMock {
+        type Error = ();
+
+        fn load<R: Record>(&mut self) -> Result<Option<R>, Self::Error> {
+            Ok(None)
+        }
+
+        fn store<R: Record>(&mut self, _record: &R) -> Result<(), Self::Error> {
+            self.kind = Some(R::KIND);
+            Ok(())
+        }
+    }
+
+    /// Currents of a device with the given parameters, immersed in a solution
+    /// with the given concentration and with wet resistance of 40 Ohm.
+    fn currents(params: &ModelParams, concentration: f32) -> Currents {
+        let resistance = 40.0;
+        let m = params.mod_params.0 * concentration
+            + params.mod_params.1 * concentration.ln()
+            + params.mod_params.2;
+        let r = params.res_params.0 + params.res_params.1 * concentration.powf(0.955);
+        let i_gs_on = params.voltages.v_gs * r;
+        Currents {
+            i_ds_off: params.voltages.v_ds / resistance,
+            i_ds_on: i_gs_on + params.voltages.v_ds * (m + 1.0) / resistance,
+            i_gs_on,
+        }
+    }
+
+    #[test]
+    fn test_calibration_point() {
+        let point = CalibrationPoint::new(1e-2, &currents(&PARAMS, 1e-2), &PARAMS.voltages);
+        let point = point.unwrap();
+        assert!((point.modulation - (-0.01463 * 1e-2f32.ln() - 0.32)).abs() < 1e-4);
+        assert!(
+            (point.stem_resistance_inv - (1.35e-6 + 2.73e-4 * 1e-2f32.powf(0.955))).abs() < 1e-8
+        );
+
+        assert_eq!(
+            CalibrationPoint::new(0.0, &currents(&PARAMS, 1e-2), &PARAMS.voltages),
+            None
+        );
+        let zero = Currents {
+            i_ds_off: 0.0,
+            i_ds_on: 0.0,
+            i_gs_on: 0.0,
+        };
+        assert_eq!(CalibrationPoint::new(1e-2, &zero, &PARAMS.voltages), None);
+    }
+
+    #[test]
+    fn test_calibration() {
+        let mut calibration = Calibration::<8>::new();
+        let mut params = ModelParams {
+            mod_params: ModulationParams(0.0, -0.02, -0.3),
+            res_params: StemResistanceInvParams(1e-6, 3e-4),
+            ..PARAMS
+        };
+        assert_eq!(
+            calibration.add_point(1e-3, &currents(&PARAMS, 1e-3), &params.voltages),
+            Err(CalibrationError::NotStarted)
+        );
+
+        calibration.start();
+        for concentration in [1e-4, 1e-3] {
+            calibration
+                .add_point(
+                    concentration,
+                    &currents(&PARAMS, concentration),
+                    &params.voltages,
+                )
+                .unwrap();
+        }
+        assert_eq!(
+            calibration.fit(&params),
+            Err(CalibrationError::NotEnoughPoints)
+        );
+        for concentration in [1e-2, 1e-1] {
+            calibration
+                .add_point(
+                    concentration,
+                    &currents(&PARAMS, concentration),
+                    &params.voltages,
+                )
+                .unwrap();
+        }
+
+        let fitted = calibration.fit(&params).unwrap();
+        assert!((fitted.mod_params.1 - PARAMS.mod_params.1).abs() < 1e-4);
+        assert!((fitted.mod_params.2 - PARAMS.mod_params.2).abs() < 1e-3);
+        assert!((fitted.res_params.1 - PARAMS.res_params.1).abs() < 1e-6);
+        assert_eq!(fitted.r_dry, params.r_dry);
+
+        let mut store = StoreMock::default();
+        assert_eq!(calibration.save(&mut store, &mut params), Ok(Ok(())));
+        assert_eq!(store.kind, Some(ModelParams::KIND));
+        assert!((params.mod_params.1 - PARAMS.mod_params.1).abs() < 1e-4);
+        assert_eq!(calibration.state(), &CalibrationState::Idle);
+        assert_eq!(
+            calibration.save(&mut store, &mut params),
+            Ok(Err(CalibrationError::NotFitted))
+        );
+    }
+
+    #[test]
+    fn test_calibration_singular() {
+        let mut calibration = Calibration::<3>::new();
+        calibration.start();
+        for _ in 0..3 {
+            calibration
+                .add_point(1e-2, &currents(&PARAMS, 1e-2), &PARAMS.voltages)
+                .unwrap();
+        }
+        assert_eq!(
+            calibration.add_point(1e-2, &currents(&PARAMS, 1e-2), &PARAMS.voltages),
+            Err(CalibrationError::Full)
+        );
+        assert_eq!(calibration.fit(&PARAMS), Err(CalibrationError::Singular));
+    }
+}
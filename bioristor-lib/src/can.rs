@@ -0,0 +1,289 @@
+use embedded_can::{Frame, Id, StandardId};
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::{params::Variables, utils::scale};
+
+/// The identifier of the result frame of the node with address zero, the
+/// frames of the other nodes follow consecutively.
+pub const RESULT_BASE_ID: u16 = 0x380;
+
+/// The size of the payload of a result frame.
+pub const RESULT_FRAME_SIZE: usize = 8;
+
+/// The decades of the concentration covered by the encoding, from 1 nM to
+/// 1 M.
+const CONCENTRATION_DECADES: f32 = 9.0;
+
+/// The resolution of the logarithmic encodings [1/decade].
+const STEPS_PER_DECADE: f32 = 7000.0;
+
+/// The status of the node sent with the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ResultStatus {
+    /// The acquisition of the currents failed, the variables are not valid.
+    AcquisitionError,
+
+    /// The algorithm did not find a solution, the variables are not valid.
+    NoSolution,
+
+    /// The variables are the solution of the algorithm.
+    Ok,
+
+    /// An application-specific status, with a code from 3 to 255.
+    Other(u8),
+}
+
+impl ResultStatus {
+    /// Returns the code of the status.
+    pub fn code(self) -> u8 {
+        match self {
+            Self::Ok => 0,
+            Self::NoSolution => 1,
+            Self::AcquisitionError => 2,
+            Self::Other(code) => code,
+        }
+    }
+
+    /// Returns the status with the given code.
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            0 => Self::Ok,
+            1 => Self::NoSolution,
+            2 => Self::AcquisitionError,
+            code => Self::Other(code),
+        }
+    }
+}
+
+/// The content of a result frame.
+///
+/// The payload is made of 8 bytes, with the scaled integers in little
+/// endian:
+///
+/// | Bytes | Field           | Encoding                                      |
+/// |-------|-----------------|-----------------------------------------------|
+/// | 0-1   | `concentration` | `u16`, `7000 * (log10(c) + 9)`, 1 nM to 1 M   |
+/// | 2     | `saturation`    | `u8`, `250 * s`, from 0 to 1                  |
+/// | 3-4   | `resistance`    | `u16`, `100 * r`, from 0 to 655.35 Ohm        |
+/// | 5-6   | `loss`          | `u16`, `-7000 * log10(loss)`, 1 to 4.4e-10    |
+/// | 7     | `status`        | [`ResultStatus::code`]                        |
+///
+/// Values out of range are clamped to the nearest representable value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResultFrame {
+    /// The loss of the solution.
+    pub loss: f32,
+
+    /// The status of the node.
+    pub status: ResultStatus,
+
+    /// The variables of the solution.
+    pub variables: Variables,
+}
+
+impl ResultFrame {
+    /// Encodes the result in the payload of a frame.
+    ///
+    /// # Returns
+    ///
+    /// The payload of the frame.
+    pub fn encode(&self) -> [u8; RESULT_FRAME_SIZE] {
+        let vars = &self.variables;
+        let concentration =
+            scale((vars.concentration.log10() + CONCENTRATION_DECADES) * STEPS_PER_DECADE);
+        let saturation = scale(vars.saturation * 250.0).min(250) as u8;
+        let resistance = scale(vars.resistance * 100.0);
+        let loss = scale(-self.loss.log10() * STEPS_PER_DECADE);
+
+        let mut payload = [0; RESULT_FRAME_SIZE];
+        payload[0..2].copy_from_slice(&concentration.to_le_bytes());
+        payload[2] = saturation;
+        payload[3..5].copy_from_slice(&resistance.to_le_bytes());
+        payload[5..7].copy_from_slice(&loss.to_le_bytes());
+        payload[7] = self.status.code();
+        payload
+    }
+
+    /// Decodes the payload of a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The payload of the frame.
+    ///
+    /// # Returns
+    ///
+    /// The decoded result, with the precision of the encoding.
+    pub fn decode(payload: &[u8; RESULT_FRAME_SIZE]) -> Self {
+        let concentration = u16::from_le_bytes([payload[0], payload[1]]) as f32;
+        let resistance = u16::from_le_bytes([payload[3], payload[4]]) as f32;
+        let loss = u16::from_le_bytes([payload[5], payload[6]]) as f32;
+
+        Self {
+            loss: 10.0f32.powf(-loss / STEPS_PER_DECADE),
+            status: ResultStatus::from_code(payload[7]),
+            variables: Variables {
+                concentration: 10.0f32
+                    .powf(concentration / STEPS_PER_DECADE - CONCENTRATION_DECADES),
+                resistance: resistance / 100.0,
+                saturation: payload[2] as f32 / 250.0,
+            },
+        }
+    }
+
+    /// Creates a CAN frame carrying the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The address of the node on the network, added to
+    ///   [`RESULT_BASE_ID`].
+    ///
+    /// # Returns
+    ///
+    /// * `Some(frame)` - The frame with a standard identifier.
+    /// * `None` - If the identifier exceeds 11 bits.
+    pub fn to_frame<F: Frame>(&self, node: u8) -> Option<F> {
+        let id = StandardId::new(RESULT_BASE_ID + node as u16)?;
+        F::new(id, &self.encode())
+    }
+
+    /// Decodes a CAN frame carrying a result.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The received frame.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((node, result))` - The address of the sending node and the
+    ///   result.
+    /// * `None` - If the frame does not carry a result.
+    pub fn from_frame<F: Frame>(frame: &F) -> Option<(u8, Self)> {
+        let Id::Standard(id) = frame.id() else {
+            return None;
+        };
+        let node = u8::try_from(id.as_raw().checked_sub(RESULT_BASE_ID)?).ok()?;
+        let payload = frame.data().try_into().ok()?;
+        Some((node, Self::decode(payload)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frame with a standard identifier.
+    #[derive(Debug)]
+    struct FrameMock {
+        data: [u8; 8],
+        id: Id,
+        len: usize,
+    }
+
+    impl Frame for FrameMock {
+        fn new(id: impl Into<Id>, data: &[u8]) -> Option<Self> {
+            let mut frame = Self {
+                data: [0; 8],
+                id: id.into(),
+                len: data.len(),
+            };
+            frame.data.get_mut(..data.len())?.copy_from_slice(data);
+            Some(frame)
+        }
+
+        fn new_remote(_id: impl Into<Id>, _dlc: usize) -> Option<Self> {
+            None
+        }
+
+        fn is_extended(&self) -> bool {
+            matches!(self.id, Id::Extended(_))
+        }
+
+        fn is_remote_frame(&self) -> bool {
+            false
+        }
+
+        fn id(&self) -> Id {
+            self.id
+        }
+
+        fn dlc(&self) -> usize {
+            self.len
+        }
+
+        fn data(&self) -> &[u8] {
+            &self.data[..self.len]
+        }
+    }
+
+    const RESULT: ResultFrame = ResultFrame {
+        loss: 1e-6,
+        status: ResultStatus::Ok,
+        variables: Variables {
+            concentration: 2.5e-3,
+            resistance: 42.37,
+            saturation: 0.62,
+        },
+    };
+
+    fn assert_relative_eq(a: f32, b: f32, tolerance: f32) {
+        assert!((a - b).abs() <= tolerance * b.abs(), "{a} != {b}");
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let decoded = ResultFrame::decode(&RESULT.encode());
+        assert_relative_eq(decoded.loss, RESULT.loss, 1e-3);
+        assert_eq!(decoded.status, RESULT.status);
+        assert_relative_eq(
+            decoded.variables.concentration,
+            RESULT.variables.concentration,
+            1e-3,
+        );
+        assert_relative_eq(
+            decoded.variables.resistance,
+            RESULT.variables.resistance,
+            1e-3,
+        );
+        assert_relative_eq(
+            decoded.variables.saturation,
+            RESULT.variables.saturation,
+            1e-3,
+        );
+    }
+
+    #[test]
+    fn test_encode_clamped() {
+        let result = ResultFrame {
+            loss: 0.0,
+            status: ResultStatus::Other(200),
+            variables: Variables {
+                concentration: f32::NAN,
+                resistance: -1.0,
+                saturation: 2.0,
+            },
+        };
+        assert_eq!(
+            result.encode(),
+            [0x00, 0x00, 250, 0x00, 0x00, 0xFF, 0xFF, 200]
+        );
+    }
+
+    #[test]
+    fn test_frame() {
+        let frame: FrameMock = RESULT.to_frame(5).unwrap();
+        assert_eq!(frame.id, Id::Standard(StandardId::new(0x385).unwrap()));
+        assert_eq!(frame.data(), &RESULT.encode());
+
+        let (node, result) = ResultFrame::from_frame(&frame).unwrap();
+        assert_eq!(node, 5);
+        assert_eq!(result, ResultFrame::decode(&RESULT.encode()));
+
+        let other = FrameMock::new(StandardId::new(0x100).unwrap(), &[0; 8]).unwrap();
+        assert_eq!(ResultFrame::from_frame(&other), None);
+        let short = FrameMock::new(StandardId::new(0x380).unwrap(), &[0; 4]).unwrap();
+        assert_eq!(ResultFrame::from_frame(&short), None);
+    }
+}
@@ -0,0 +1,915 @@
+use core::fmt;
+
+use embedded_io::{Read, Write, WriteFmtError};
+
+#[cfg(feature = "calibration")]
+use crate::calibration::{Calibration, CalibrationError, CalibrationState, MIN_POINTS};
+#[cfg(feature = "calibration")]
+use crate::param_store::ParamStore;
+#[cfg(feature = "calibration")]
+use crate::params::Currents;
+use crate::params::{ModelParams, Variables};
+
+/// The maximum number of reference solutions of a calibration.
+#[cfg(feature = "calibration")]
+pub const MAX_CALIBRATION_POINTS: usize = 8;
+
+/// The fields of the [`ModelParams`] that can be read and written from the
+/// console.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Field {
+    /// The first parameter of the modulation function.
+    ModA,
+    /// The second parameter of the modulation function.
+    ModB,
+    /// The third parameter of the modulation function.
+    ModC,
+    /// The resistance of the dry PEDOT channel.
+    RDry,
+    /// The first parameter of the inverse of stem resistance function.
+    ResA,
+    /// The second parameter of the inverse of stem resistance function.
+    ResB,
+    /// The drain-source voltage.
+    VDs,
+    /// The gate-source voltage.
+    VGs,
+}
+
+impl Field {
+    /// All the fields, in the order they are listed by the console.
+    pub const ALL: [Field; 8] = [
+        Field::ModA,
+        Field::ModB,
+        Field::ModC,
+        Field::RDry,
+        Field::ResA,
+        Field::ResB,
+        Field::VDs,
+        Field::VGs,
+    ];
+
+    /// Returns the name of the field used by the console.
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::ModA => "mod_a",
+            Field::ModB => "mod_b",
+            Field::ModC => "mod_c",
+            Field::RDry => "r_dry",
+            Field::ResA => "res_a",
+            Field::ResB => "res_b",
+            Field::VDs => "v_ds",
+            Field::VGs => "v_gs",
+        }
+    }
+
+    /// Returns the field with the given name, if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.name() == name)
+    }
+
+    /// Returns the value of the field in the given parameters.
+    pub fn get(self, params: &ModelParams) -> f32 {
+        match self {
+            Field::ModA => params.mod_params.0,
+            Field::ModB => params.mod_params.1,
+            Field::ModC => params.mod_params.2,
+            Field::RDry => params.r_dry,
+            Field::ResA => params.res_params.0,
+            Field::ResB => params.res_params.1,
+            Field::VDs => params.voltages.v_ds,
+            Field::VGs => params.voltages.v_gs,
+        }
+    }
+
+    /// Sets the value of the field in the given parameters.
+    pub fn set(self, params: &mut ModelParams, value: f32) {
+        let field = match self {
+            Field::ModA => &mut params.mod_params.0,
+            Field::ModB => &mut params.mod_params.1,
+            Field::ModC => &mut params.mod_params.2,
+            Field::RDry => &mut params.r_dry,
+            Field::ResA => &mut params.res_params.0,
+            Field::ResB => &mut params.res_params.1,
+            Field::VDs => &mut params.voltages.v_ds,
+            Field::VGs => &mut params.voltages.v_gs,
+        };
+        *field = value;
+    }
+}
+
+/// The algorithms that can be selected from the console.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AlgorithmKind {
+    /// The adaptive algorithm.
+    Adaptive,
+    /// The adaptive algorithm with mean-based refinement.
+    #[default]
+    Adaptive2,
+    /// The brute force algorithm.
+    BruteForce,
+    /// The gradient descent algorithm.
+    GradientDescent,
+    /// The neural network.
+    NeuralNetwork,
+    /// The Newton's method.
+    Newton,
+}
+
+impl AlgorithmKind {
+    /// All the algorithms, in the order they are listed by the console.
+    pub const ALL: [AlgorithmKind; 6] = [
+        AlgorithmKind::Adaptive,
+        AlgorithmKind::Adaptive2,
+        AlgorithmKind::BruteForce,
+        AlgorithmKind::GradientDescent,
+        AlgorithmKind::NeuralNetwork,
+        AlgorithmKind::Newton,
+    ];
+
+    /// Returns the name of the algorithm used by the console.
+    pub fn name(self) -> &'static str {
+        match self {
+            AlgorithmKind::Adaptive => "adaptive",
+            AlgorithmKind::Adaptive2 => "adaptive2",
+            AlgorithmKind::BruteForce => "brute_force",
+            AlgorithmKind::GradientDescent => "gradient_descent",
+            AlgorithmKind::NeuralNetwork => "neural_network",
+            AlgorithmKind::Newton => "newton",
+        }
+    }
+
+    /// Returns the algorithm with the given name, if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == name)
+    }
+}
+
+/// The steps of the calibration, see [`Calibration`].
+#[cfg(feature = "calibration")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CalibrationCommand {
+    /// `cal abort`: aborts the calibration.
+    Abort,
+    /// `cal fit`: fits the parameters to the measured points.
+    Fit,
+    /// `cal point <concentration>`: measures the reference solution with the
+    /// given concentration.
+    Point(f32),
+    /// `cal save`: stores and applies the fitted parameters.
+    Save,
+    /// `cal start`: starts a new calibration.
+    Start,
+}
+
+/// The commands accepted by the console, one per line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Command {
+    /// `algo [name]`: selects the algorithm, or prints the selected one.
+    Algorithm(Option<AlgorithmKind>),
+    /// `cal start|point <concentration>|fit|save|abort`: guides the
+    /// calibration of the model parameters.
+    #[cfg(feature = "calibration")]
+    Calibrate(CalibrationCommand),
+    /// `get <field>`: prints the value of a field of the model parameters.
+    Get(Field),
+    /// `help`: lists the commands, fields and algorithms.
+    Help,
+    /// `set <field> <value>`: sets the value of a field of the model parameters.
+    Set(Field, f32),
+    /// `solve`: runs the selected algorithm.
+    Solve,
+    /// `stream on|off`: enables or disables the streaming of the results.
+    Stream(bool),
+}
+
+/// The errors of the parsing of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParseError {
+    /// A required argument is missing.
+    MissingArgument,
+    /// The command is unknown.
+    UnknownCommand,
+    /// The algorithm is unknown.
+    UnknownAlgorithm,
+    /// The field is unknown.
+    UnknownField,
+    /// An argument has an invalid value.
+    InvalidValue,
+    /// The line has too many arguments.
+    TooManyArguments,
+}
+
+impl ParseError {
+    /// Returns the description of the error printed by the console.
+    pub fn message(self) -> &'static str {
+        match self {
+            ParseError::MissingArgument => "missing argument",
+            ParseError::UnknownCommand => "unknown command",
+            ParseError::UnknownAlgorithm => "unknown algorithm",
+            ParseError::UnknownField => "unknown field",
+            ParseError::InvalidValue => "invalid value",
+            ParseError::TooManyArguments => "too many arguments",
+        }
+    }
+}
+
+impl Command {
+    /// Parses a line of the console.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line, without the line terminator.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(command)` - The parsed command.
+    /// * `Err(error)` - If the line is not a valid command.
+    pub fn parse(line: &str) -> Result<Self, ParseError> {
+        let mut words = line.split_whitespace();
+        let command = words.next().ok_or(ParseError::UnknownCommand)?;
+
+        let field = |word: Option<&str>| {
+            Field::from_name(word.ok_or(ParseError::MissingArgument)?)
+                .ok_or(ParseError::UnknownField)
+        };
+
+        let command = match command {
+            "algo" => Command::Algorithm(match words.next() {
+                Some(name) => {
+                    Some(AlgorithmKind::from_name(name).ok_or(ParseError::UnknownAlgorithm)?)
+                }
+                None => None,
+            }),
+            #[cfg(feature = "calibration")]
+            "cal" => Command::Calibrate(match words.next() {
+                Some("abort") => CalibrationCommand::Abort,
+                Some("fit") => CalibrationCommand::Fit,
+                Some("point") => CalibrationCommand::Point(
+                    words
+                        .next()
+                        .ok_or(ParseError::MissingArgument)?
+                        .parse()
+                        .map_err(|_| ParseError::InvalidValue)?,
+                ),
+                Some("save") => CalibrationCommand::Save,
+                Some("start") => CalibrationCommand::Start,
+                Some(_) => return Err(ParseError::InvalidValue),
+                None => return Err(ParseError::MissingArgument),
+            }),
+            "get" => Command::Get(field(words.next())?),
+            "help" => Command::Help,
+            "set" => {
+                let field = field(words.next())?;
+                let value = words
+                    .next()
+                    .ok_or(ParseError::MissingArgument)?
+                    .parse()
+                    .map_err(|_| ParseError::InvalidValue)?;
+                Command::Set(field, value)
+            }
+            "solve" => Command::Solve,
+            "stream" => Command::Stream(match words.next() {
+                Some("on") => true,
+                Some("off") => false,
+                Some(_) => return Err(ParseError::InvalidValue),
+                None => return Err(ParseError::MissingArgument),
+            }),
+            _ => return Err(ParseError::UnknownCommand),
+        };
+
+        match words.next() {
+            Some(_) => Err(ParseError::TooManyArguments),
+            None => Ok(command),
+        }
+    }
+}
+
+/// The actions requested by the console to the application.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Request {
+    /// Run the given algorithm and call [`Console::report`] with the result.
+    Solve(AlgorithmKind),
+    /// Measure the currents of the device dipped in the reference solution
+    /// with the given concentration, and call
+    /// [`Console::add_calibration_point`] with them.
+    #[cfg(feature = "calibration")]
+    Measure(f32),
+    /// Call [`Console::save_calibration`] with the storage of the parameters.
+    #[cfg(feature = "calibration")]
+    SaveCalibration,
+}
+
+/// Line-based console over a serial port, to tune the model parameters and
+/// trigger the algorithm without reflashing the device.
+///
+/// The console answers every command with a single line: `OK`, the requested
+/// value, or `ERR` followed by a description of the error. The execution of
+/// the algorithm is left to the application, which is notified through the
+/// return value of [`Console::poll`] and prints the result with
+/// [`Console::report`].
+///
+/// With the `calibration` feature, the `cal` commands guide the operator
+/// through the calibration of the model parameters with reference solutions,
+/// printing the instructions on lines starting with `CAL`. The measurements
+/// and the storage of the parameters are left to the application as well.
+///
+/// # Type parameters
+///
+/// * `IO` - The type of the serial port.
+/// * `N` - The maximum length of a line, longer lines are discarded.
+pub struct Console<IO: Read + Write, const N: usize> {
+    /// The selected algorithm.
+    algorithm: AlgorithmKind,
+
+    /// The calibration in progress.
+    #[cfg(feature = "calibration")]
+    calibration: Calibration<MAX_CALIBRATION_POINTS>,
+
+    /// The bytes read from the serial port and not processed yet.
+    input: [u8; 16],
+
+    /// The number of bytes in `input`.
+    input_len: usize,
+
+    /// The position of the next byte to process in `input`.
+    input_pos: usize,
+
+    /// The serial port.
+    io: IO,
+
+    /// The line being received.
+    line: [u8; N],
+
+    /// The length of the line being received.
+    len: usize,
+
+    /// Whether the line being received is too long and must be discarded.
+    overflow: bool,
+
+    /// Whether the results are streamed.
+    streaming: bool,
+}
+
+impl<IO: Read + Write, const N: usize> Console<IO, N> {
+    /// Creates a new console.
+    ///
+    /// # Arguments
+    ///
+    /// * `io` - The serial port.
+    pub fn new(io: IO) -> Self {
+        Self {
+            algorithm: AlgorithmKind::default(),
+            #[cfg(feature = "calibration")]
+            calibration: Calibration::new(),
+            input: [0; 16],
+            input_len: 0,
+            input_pos: 0,
+            io,
+            line: [0; N],
+            len: 0,
+            overflow: false,
+            streaming: false,
+        }
+    }
+
+    /// Returns the selected algorithm.
+    #[inline]
+    pub fn algorithm(&self) -> AlgorithmKind {
+        self.algorithm
+    }
+
+    /// Returns whether the results must be streamed, i.e. the application
+    /// should run the algorithm continuously and report every result.
+    #[inline]
+    pub fn is_streaming(&self) -> bool {
+        self.streaming
+    }
+
+    /// Releases the serial port.
+    pub fn release(self) -> IO {
+        self.io
+    }
+
+    /// Reads the available input and executes the received commands.
+    ///
+    /// This blocks until some input is available, as [`Read::read`] does.
+    /// The execution stops at the first command that needs an action of the
+    /// application, and the rest of the input is executed by the next call.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The model parameters, modified by the `set` command.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(request))` - If a command needs an action of the
+    ///   application, e.g. the `solve` command.
+    /// * `Ok(None)` - If no action has been requested.
+    /// * `Err(error)` - If the serial port failed.
+    pub fn poll(&mut self, params: &mut ModelParams) -> Result<Option<Request>, IO::Error> {
+        if self.input_pos == self.input_len {
+            self.input_len = self.io.read(&mut self.input)?;
+            self.input_pos = 0;
+        }
+
+        while self.input_pos < self.input_len {
+            let byte = self.input[self.input_pos];
+            self.input_pos += 1;
+            if byte != b'\n' && byte != b'\r' {
+                if self.len < N {
+                    self.line[self.len] = byte;
+                    self.len += 1;
+                } else {
+                    self.overflow = true;
+                }
+                continue;
+            }
+
+            let (len, overflow) = (self.len, self.overflow);
+            self.len = 0;
+            self.overflow = false;
+
+            if overflow {
+                self.io.write_all(b"ERR line too long\r\n")?;
+            } else if len > 0 {
+                let line = self.line;
+                match core::str::from_utf8(&line[..len]) {
+                    Ok(line) => {
+                        if let Some(request) = self.execute(line, params)? {
+                            return Ok(Some(request));
+                        }
+                    }
+                    Err(_) => self.io.write_all(b"ERR invalid encoding\r\n")?,
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Prints the result of the algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The result returned by the algorithm.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the result has been printed.
+    /// * `Err(error)` - If the serial port failed.
+    pub fn report(&mut self, result: Option<(Variables, f32)>) -> Result<(), IO::Error> {
+        match result {
+            Some((vars, loss)) => self.print(format_args!(
+                "RESULT {} {} {} {}\r\n",
+                vars.concentration, vars.resistance, vars.saturation, loss
+            )),
+            None => self.io.write_all(b"RESULT none\r\n"),
+        }
+    }
+
+    /// Adds the measurement requested by [`Request::Measure`] to the
+    /// calibration, and prints the next instructions.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentration` - The concentration of the reference solution [Molarity].
+    /// * `currents` - The currents measured in the reference solution.
+    /// * `params` - The model parameters.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the outcome has been printed.
+    /// * `Err(error)` - If the serial port failed.
+    #[cfg(feature = "calibration")]
+    pub fn add_calibration_point(
+        &mut self,
+        concentration: f32,
+        currents: &Currents,
+        params: &ModelParams,
+    ) -> Result<(), IO::Error> {
+        let point = match self
+            .calibration
+            .add_point(concentration, currents, &params.voltages)
+        {
+            Ok(point) => point,
+            Err(error) => return self.print(format_args!("ERR {}\r\n", error.message())),
+        };
+
+        let count = self.calibration.points().len();
+        self.print(format_args!(
+            "CAL point {} modulation {} stem_resistance_inv {}\r\n",
+            count, point.modulation, point.stem_resistance_inv
+        ))?;
+        if count < MIN_POINTS {
+            self.io.write_all(
+                b"CAL rinse the sensor, dip it in the next solution and send: cal point <concentration>\r\n",
+            )
+        } else {
+            self.io.write_all(
+                b"CAL dip the sensor in another solution and send: cal point <concentration>, or send: cal fit\r\n",
+            )
+        }
+    }
+
+    /// Stores and applies the fitted parameters, as requested by
+    /// [`Request::SaveCalibration`].
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The storage of the parameters.
+    /// * `params` - The model parameters, replaced by the fitted ones.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the outcome has been printed.
+    /// * `Err(error)` - If the serial port failed.
+    #[cfg(feature = "calibration")]
+    pub fn save_calibration<S: ParamStore>(
+        &mut self,
+        store: &mut S,
+        params: &mut ModelParams,
+    ) -> Result<(), IO::Error> {
+        match self.calibration.save(store, params) {
+            Ok(Ok(())) => self.io.write_all(b"OK\r\n"),
+            Ok(Err(error)) => self.print(format_args!("ERR {}\r\n", error.message())),
+            Err(_) => self.io.write_all(b"ERR storage failed\r\n"),
+        }
+    }
+
+    /// Executes a calibration command.
+    ///
+    /// # Returns
+    ///
+    /// The action requested to the application, if any.
+    #[cfg(feature = "calibration")]
+    fn calibrate(
+        &mut self,
+        command: CalibrationCommand,
+        params: &ModelParams,
+    ) -> Result<Option<Request>, IO::Error> {
+        let error = match command {
+            CalibrationCommand::Abort => {
+                self.calibration.abort();
+                self.io.write_all(b"OK\r\n")?;
+                return Ok(None);
+            }
+            CalibrationCommand::Fit => match self.calibration.fit(params) {
+                Ok(fitted) => {
+                    let fitted = fitted.clone();
+                    self.io.write_all(b"CAL")?;
+                    for field in Field::ALL {
+                        self.print(format_args!(" {} {}", field.name(), field.get(&fitted)))?;
+                    }
+                    self.io
+                        .write_all(b"\r\nCAL send: cal save to store the parameters\r\n")?;
+                    return Ok(None);
+                }
+                Err(error) => error,
+            },
+            CalibrationCommand::Point(concentration) => {
+                if *self.calibration.state() == CalibrationState::Idle {
+                    CalibrationError::NotStarted
+                } else if self.calibration.points().len() == MAX_CALIBRATION_POINTS {
+                    CalibrationError::Full
+                } else {
+                    return Ok(Some(Request::Measure(concentration)));
+                }
+            }
+            CalibrationCommand::Save => match self.calibration.state() {
+                CalibrationState::Fitted(_) => return Ok(Some(Request::SaveCalibration)),
+                _ => CalibrationError::NotFitted,
+            },
+            CalibrationCommand::Start => {
+                self.calibration.start();
+                self.io.write_all(
+                    b"CAL dip the sensor in a reference solution and send: cal point <concentration>\r\n",
+                )?;
+                return Ok(None);
+            }
+        };
+
+        self.print(format_args!("ERR {}\r\n", error.message()))?;
+        Ok(None)
+    }
+
+    /// Executes a line of the console.
+    ///
+    /// # Returns
+    ///
+    /// The action requested to the application, if any.
+    fn execute(
+        &mut self,
+        line: &str,
+        params: &mut ModelParams,
+    ) -> Result<Option<Request>, IO::Error> {
+        let command = match Command::parse(line) {
+            Ok(command) => command,
+            Err(error) => {
+                self.print(format_args!("ERR {}\r\n", error.message()))?;
+                return Ok(None);
+            }
+        };
+
+        match command {
+            Command::Algorithm(Some(algorithm)) => {
+                self.algorithm = algorithm;
+                self.io.write_all(b"OK\r\n")?;
+            }
+            Command::Algorithm(None) => {
+                self.print(format_args!("{}\r\n", self.algorithm.name()))?;
+            }
+            #[cfg(feature = "calibration")]
+            Command::Calibrate(command) => return self.calibrate(command, params),
+            Command::Get(field) => {
+                self.print(format_args!("{} {}\r\n", field.name(), field.get(params)))?;
+            }
+            Command::Help => {
+                self.io
+                    .write_all(b"commands: algo [name], get <field>, set <field> <value>, solve, stream on|off\r\n")?;
+                #[cfg(feature = "calibration")]
+                self.io
+                    .write_all(b"calibration: cal start, cal point <concentration>, cal fit, cal save, cal abort\r\n")?;
+                self.io.write_all(b"fields:")?;
+                for field in Field::ALL {
+                    self.print(format_args!(" {}", field.name()))?;
+                }
+                self.io.write_all(b"\r\nalgorithms:")?;
+                for algorithm in AlgorithmKind::ALL {
+                    self.print(format_args!(" {}", algorithm.name()))?;
+                }
+                self.io.write_all(b"\r\n")?;
+            }
+            Command::Set(field, value) => {
+                field.set(params, value);
+                self.io.write_all(b"OK\r\n")?;
+            }
+            Command::Solve => return Ok(Some(Request::Solve(self.algorithm))),
+            Command::Stream(enabled) => {
+                self.streaming = enabled;
+                self.io.write_all(b"OK\r\n")?;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Prints formatted text.
+    fn print(&mut self, args: fmt::Arguments) -> Result<(), IO::Error> {
+        match self.io.write_fmt(args) {
+            Ok(()) => Ok(()),
+            Err(WriteFmtError::Other(error)) => Err(error),
+            // Formatting strings and numbers cannot fail.
+            Err(WriteFmtError::FmtError) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use embedded_io::ErrorType;
+
+    use crate::params::{ModulationParams, StemResistanceInvParams, Voltages};
+
+    use super::*;
+
+    /// Serial port reading from a fixed input and writing to a buffer.
+    struct SerialMock {
+        input: &'static [u8],
+        output: [u8; 1024],
+        written: usize,
+    }
+
+    impl SerialMock {
+        fn new(input: &'static [u8]) -> Self {
+            Self {
+                input,
+                output: [0; 1024],
+                written: 0,
+            }
+        }
+
+        fn output(&self) -> &str {
+            core::str::from_utf8(&self.output[..self.written]).unwrap()
+        }
+    }
+
+    impl ErrorType for SerialMock {
+        type Error = Infallible;
+    }
+
+    impl Read for SerialMock {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let n = buf.len().min(self.input.len());
+            buf[..n].copy_from_slice(&self.input[..n]);
+            self.input = &self.input[n..];
+            Ok(n)
+        }
+    }
+
+    impl Write for SerialMock {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.output[self.written..self.written + buf.len()].copy_from_slice(buf);
+            self.written += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    const PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(1.0, 2.0, 3.0),
+        r_dry: 100.0,
+        res_params: StemResistanceInvParams(4.0, 5.0),
+        voltages: Voltages {
+            v_ds: 0.1,
+            v_gs: 0.5,
+        },
+    };
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Command::parse("solve"), Ok(Command::Solve));
+        assert_eq!(
+            Command::parse(" set  r_dry 12.5 "),
+            Ok(Command::Set(Field::RDry, 12.5))
+        );
+        assert_eq!(
+            Command::parse("algo newton"),
+            Ok(Command::Algorithm(Some(AlgorithmKind::Newton)))
+        );
+        assert_eq!(Command::parse("algo"), Ok(Command::Algorithm(None)));
+        assert_eq!(Command::parse("stream on"), Ok(Command::Stream(true)));
+        assert_eq!(Command::parse("get"), Err(ParseError::MissingArgument));
+        assert_eq!(Command::parse("get foo"), Err(ParseError::UnknownField));
+        assert_eq!(Command::parse("set v_ds x"), Err(ParseError::InvalidValue));
+        assert_eq!(
+            Command::parse("solve now"),
+            Err(ParseError::TooManyArguments)
+        );
+        assert_eq!(Command::parse("run"), Err(ParseError::UnknownCommand));
+    }
+
+    #[test]
+    fn test_fields() {
+        let mut params = PARAMS;
+        for (i, field) in Field::ALL.into_iter().enumerate() {
+            assert_eq!(Field::from_name(field.name()), Some(field));
+            field.set(&mut params, i as f32);
+            assert_eq!(field.get(&params), i as f32);
+        }
+    }
+
+    #[test]
+    fn test_console() {
+        let io = SerialMock::new(b"set v_gs 0.75\r\nget v_gs\nalgo newton\nbogus\nsolve\n");
+        let mut console = Console::<_, 32>::new(io);
+        let mut params = PARAMS;
+
+        let mut solve = None;
+        for _ in 0..4 {
+            solve = solve.or(console.poll(&mut params).unwrap());
+        }
+        assert_eq!(solve, Some(Request::Solve(AlgorithmKind::Newton)));
+        assert_eq!(params.voltages.v_gs, 0.75);
+
+        console
+            .report(Some((
+                Variables {
+                    concentration: 0.5,
+                    resistance: 2.0,
+                    saturation: 0.25,
+                },
+                0.125,
+            )))
+            .unwrap();
+        assert_eq!(
+            console.release().output(),
+            "OK\r\nv_gs 0.75\r\nOK\r\nERR unknown command\r\nRESULT 0.5 2 0.25 0.125\r\n"
+        );
+    }
+
+    #[test]
+    fn test_console_overflow() {
+        let io = SerialMock::new(b"set r_dry 1234567890\nstream on\n");
+        let mut console = Console::<_, 16>::new(io);
+        let mut params = PARAMS;
+
+        for _ in 0..2 {
+            assert_eq!(console.poll(&mut params).unwrap(), None);
+        }
+        assert!(console.is_streaming());
+        assert_eq!(params.r_dry, PARAMS.r_dry);
+        assert_eq!(console.release().output(), "ERR line too long\r\nOK\r\n");
+    }
+
+    #[cfg(feature = "calibration")]
+    #[test]
+    fn test_parse_calibration() {
+        assert_eq!(
+            Command::parse("cal point 1e-3"),
+            Ok(Command::Calibrate(CalibrationCommand::Point(1e-3)))
+        );
+        assert_eq!(
+            Command::parse("cal start"),
+            Ok(Command::Calibrate(CalibrationCommand::Start))
+        );
+        assert_eq!(Command::parse("cal"), Err(ParseError::MissingArgument));
+        assert_eq!(
+            Command::parse("cal point"),
+            Err(ParseError::MissingArgument)
+        );
+        assert_eq!(Command::parse("cal go"), Err(ParseError::InvalidValue));
+    }
+
+    #[cfg(feature = "calibration")]
+    #[test]
+    fn test_console_calibration() {
+        use crate::param_store::Record;
+
+        /// Store counting the stored records.
+        #[derive(Default)]
+        struct StoreMock {
+            stored: usize,
+        }
+
+        impl ParamStore for StoreMock {
+            type Error = ();
+
+            fn load<R: Record>(&mut self) -> Result<Option<R>, Self::Error> {
+                Ok(None)
+            }
+
+            fn store<R: Record>(&mut self, _record: &R) -> Result<(), Self::Error> {
+                self.stored += 1;
+                Ok(())
+            }
+        }
+
+        let io = SerialMock::new(
+            b"cal point 1e-4\ncal start\ncal point 1e-4\ncal point 1e-3\ncal fit\ncal point 1e-2\ncal fit\ncal save\n",
+        );
+        let mut console = Console::<_, 32>::new(io);
+        let mut store = StoreMock::default();
+        let mut params = PARAMS;
+
+        // Device with modulation -0.3 - 0.015 * ln(x) and inverse of stem
+        // resistance 1e-6 + 3e-4 * x^0.955, dipped in a solution with wet
+        // resistance of 40 Ohm.
+        let currents = |concentration: f32| {
+            let m = -0.3 - 0.015 * concentration.ln();
+            let r = 1e-6 + 3e-4 * concentration.powf(0.955);
+            let i_gs_on = PARAMS.voltages.v_gs * r;
+            Currents {
+                i_ds_off: PARAMS.voltages.v_ds / 40.0,
+                i_ds_on: i_gs_on + PARAMS.voltages.v_ds * (m + 1.0) / 40.0,
+                i_gs_on,
+            }
+        };
+
+        for _ in 0..16 {
+            match console.poll(&mut params).unwrap() {
+                Some(Request::Measure(concentration)) => console
+                    .add_calibration_point(concentration, &currents(concentration), &params)
+                    .unwrap(),
+                Some(Request::SaveCalibration) => {
+                    console.save_calibration(&mut store, &mut params).unwrap()
+                }
+                request => assert_eq!(request, None),
+            }
+        }
+        assert_eq!(store.stored, 1);
+        assert!((params.mod_params.1 + 0.015).abs() < 1e-3);
+        assert!((params.mod_params.2 + 0.3).abs() < 1e-2);
+        assert!((params.res_params.1 - 3e-4).abs() < 1e-5);
+        assert_eq!(params.r_dry, PARAMS.r_dry);
+
+        let io = console.release();
+        let mut lines = io.output().lines();
+        assert_eq!(lines.next(), Some("ERR calibration not started"));
+        assert!(lines.next().unwrap().starts_with("CAL dip"));
+        assert!(lines.next().unwrap().starts_with("CAL point 1 "));
+        assert!(lines.next().unwrap().starts_with("CAL rinse"));
+        assert!(lines.next().unwrap().starts_with("CAL point 2 "));
+        assert!(lines.next().unwrap().starts_with("CAL rinse"));
+        assert_eq!(lines.next(), Some("ERR not enough points"));
+        assert!(lines.next().unwrap().starts_with("CAL point 3 "));
+        assert!(lines.next().unwrap().ends_with("cal fit"));
+        assert!(lines.next().unwrap().starts_with("CAL mod_a "));
+        assert!(lines.next().unwrap().ends_with("store the parameters"));
+        assert_eq!(lines.next(), Some("OK"));
+        assert_eq!(lines.next(), None);
+    }
+}
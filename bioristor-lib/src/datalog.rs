@@ -0,0 +1,409 @@
+use embedded_storage::nor_flash::NorFlash;
+
+use crate::params::Variables;
+use crate::record::{MeasurementRecord, RECORD_SIZE};
+
+/// The errors of the [`DataLog`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DataLogError<E> {
+    /// The flash memory failed.
+    Flash(E),
+
+    /// The region is not aligned to the sectors, or a sector cannot fit a
+    /// record.
+    InvalidRegion,
+}
+
+/// Log of solutions stored in a region of flash memory.
+///
+/// The region is made of several sectors used as a circular buffer: records
+/// are appended to the current sector and, when it is full, the oldest sector
+/// is erased and reused. This spreads the erase cycles evenly across all the
+/// sectors, and the log always retains at least the records of all the
+/// sectors but one.
+///
+/// Every record is a [`MeasurementRecord`], carrying a sequence number and a
+/// CRC, so that the position of
+/// the newest record is found again after a reset, and records corrupted by a
+/// power loss during a write are skipped.
+pub struct DataLog<F: NorFlash> {
+    /// The flash memory.
+    flash: F,
+
+    /// The address of the slot where the next record is written.
+    next: u32,
+
+    /// The address of the first sector of the region.
+    offset: u32,
+
+    /// The number of sectors of the region.
+    sectors: u32,
+
+    /// The sequence number of the next record.
+    sequence: u32,
+}
+
+impl<F: NorFlash> DataLog<F> {
+    /// The number of records that fit in a sector.
+    const RECORDS_PER_SECTOR: u32 = (F::ERASE_SIZE / RECORD_SIZE) as u32;
+
+    /// Opens the log stored in the given region, locating the newest record.
+    ///
+    /// # Arguments
+    ///
+    /// * `flash` - The flash memory.
+    /// * `offset` - The address of the first sector of the region.
+    /// * `sectors` - The number of sectors of the region, at least two.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(log)` - The log.
+    /// * `Err(error)` - If the region is invalid or the flash memory failed.
+    pub fn mount(mut flash: F, offset: u32, sectors: u32) -> Result<Self, DataLogError<F::Error>> {
+        if sectors < 2
+            || !(offset as usize).is_multiple_of(F::ERASE_SIZE)
+            || !RECORD_SIZE.is_multiple_of(F::WRITE_SIZE)
+            || Self::RECORDS_PER_SECTOR == 0
+            || offset as usize + sectors as usize * F::ERASE_SIZE > flash.capacity()
+        {
+            return Err(DataLogError::InvalidRegion);
+        }
+
+        // Find the sector containing the newest record, by looking at the
+        // first record of every sector.
+        let mut newest: Option<(u32, u32)> = None;
+        for sector in 0..sectors {
+            let address = offset + sector * F::ERASE_SIZE as u32;
+            if let Some(record) = Self::read_slot(&mut flash, address)? {
+                if newest.is_none_or(|(_, sequence)| record.sequence > sequence) {
+                    newest = Some((sector, record.sequence));
+                }
+            }
+        }
+
+        let mut log = Self {
+            flash,
+            next: offset,
+            offset,
+            sectors,
+            sequence: 0,
+        };
+
+        // Find the first free slot after the newest record.
+        if let Some((sector, _)) = newest {
+            let start = log.sector_address(sector);
+            log.next = log.sector_address((sector + 1) % sectors);
+            for slot in 0..Self::RECORDS_PER_SECTOR {
+                let address = start + slot * RECORD_SIZE as u32;
+                let mut bytes = [0; RECORD_SIZE];
+                log.flash
+                    .read(address, &mut bytes)
+                    .map_err(DataLogError::Flash)?;
+
+                if bytes.iter().all(|&b| b == 0xFF) {
+                    log.next = address;
+                    break;
+                }
+                if let Ok(record) = MeasurementRecord::from_bytes(&bytes) {
+                    log.sequence = record.sequence.wrapping_add(1);
+                }
+            }
+        }
+
+        Ok(log)
+    }
+
+    /// Returns the sequence number of the next record.
+    #[inline]
+    pub fn next_sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    /// Returns the maximum number of records stored in the log.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        (self.sectors * Self::RECORDS_PER_SECTOR) as usize
+    }
+
+    /// Releases the flash memory.
+    pub fn release(self) -> F {
+        self.flash
+    }
+
+    /// Appends a solution to the log.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - The time of the measurement, in seconds since the Unix
+    ///   epoch, e.g. from a [`Timestamp`](crate::timestamp::Timestamp).
+    /// * `variables` - The variables of the solution.
+    /// * `loss` - The loss of the solution.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(sequence)` - The sequence number of the record.
+    /// * `Err(error)` - If the flash memory failed.
+    pub fn append(
+        &mut self,
+        timestamp: u64,
+        variables: Variables,
+        loss: f32,
+    ) -> Result<u32, DataLogError<F::Error>> {
+        let sector_offset = (self.next - self.offset) % F::ERASE_SIZE as u32;
+        if sector_offset == 0 {
+            // Entering a new sector: erase it, dropping the oldest records.
+            self.flash
+                .erase(self.next, self.next + F::ERASE_SIZE as u32)
+                .map_err(DataLogError::Flash)?;
+        }
+
+        let record = MeasurementRecord {
+            loss,
+            sequence: self.sequence,
+            timestamp,
+            variables,
+        };
+        self.flash
+            .write(self.next, &record.to_bytes())
+            .map_err(DataLogError::Flash)?;
+
+        self.sequence = self.sequence.wrapping_add(1);
+        let sector = (self.next - self.offset) / F::ERASE_SIZE as u32;
+        if sector_offset / RECORD_SIZE as u32 + 1 == Self::RECORDS_PER_SECTOR {
+            // The sector is full, move to the next one.
+            self.next = self.sector_address((sector + 1) % self.sectors);
+        } else {
+            self.next += RECORD_SIZE as u32;
+        }
+
+        Ok(record.sequence)
+    }
+
+    /// Reads all the valid records, from the oldest to the newest.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The function called for every record.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If all the records have been read.
+    /// * `Err(error)` - If the flash memory failed.
+    pub fn for_each(
+        &mut self,
+        mut f: impl FnMut(MeasurementRecord),
+    ) -> Result<(), DataLogError<F::Error>> {
+        // The oldest records are in the sector following the current one, or in
+        // the current one if it has not been written yet.
+        let current = (self.next - self.offset) / F::ERASE_SIZE as u32;
+        let partial = !(self.next - self.offset).is_multiple_of(F::ERASE_SIZE as u32);
+        let first = if partial { current + 1 } else { current };
+
+        for i in 0..self.sectors {
+            let start = self.sector_address((first + i) % self.sectors);
+            let last = i + 1 == self.sectors;
+            for slot in 0..Self::RECORDS_PER_SECTOR {
+                let address = start + slot * RECORD_SIZE as u32;
+                if last && partial && address >= self.next {
+                    break;
+                }
+                if let Some(record) = Self::read_slot(&mut self.flash, address)? {
+                    f(record);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the address of the given sector of the region.
+    #[inline]
+    fn sector_address(&self, sector: u32) -> u32 {
+        self.offset + sector * F::ERASE_SIZE as u32
+    }
+
+    /// Reads the record stored in the given slot, if valid.
+    fn read_slot(
+        flash: &mut F,
+        address: u32,
+    ) -> Result<Option<MeasurementRecord>, DataLogError<F::Error>> {
+        let mut bytes = [0; RECORD_SIZE];
+        flash
+            .read(address, &mut bytes)
+            .map_err(DataLogError::Flash)?;
+        Ok(MeasurementRecord::from_bytes(&bytes).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_storage::nor_flash::{ErrorType, NorFlashErrorKind, ReadNorFlash};
+
+    use super::*;
+
+    const SECTOR_SIZE: usize = 128;
+    const SECTORS: usize = 3;
+
+    /// Flash memory counting the erase cycles of every sector.
+    struct FlashMock {
+        data: [u8; SECTOR_SIZE * SECTORS],
+        erases: [u32; SECTORS],
+    }
+
+    impl FlashMock {
+        fn new() -> Self {
+            Self {
+                data: [0xFF; SECTOR_SIZE * SECTORS],
+                erases: [0; SECTORS],
+            }
+        }
+    }
+
+    impl ErrorType for FlashMock {
+        type Error = NorFlashErrorKind;
+    }
+
+    impl ReadNorFlash for FlashMock {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    impl NorFlash for FlashMock {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = SECTOR_SIZE;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            self.data[from as usize..to as usize].fill(0xFF);
+            self.erases[from as usize / SECTOR_SIZE] += 1;
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            for (cell, byte) in self.data[offset..offset + bytes.len()]
+                .iter_mut()
+                .zip(bytes)
+            {
+                // Programming can only clear bits.
+                *cell &= byte;
+            }
+            Ok(())
+        }
+    }
+
+    fn variables(i: u32) -> Variables {
+        Variables {
+            concentration: i as f32,
+            resistance: 2.0 * i as f32,
+            saturation: 0.5,
+        }
+    }
+
+    fn append(
+        log: &mut DataLog<FlashMock>,
+        i: u32,
+    ) -> Result<u32, DataLogError<NorFlashErrorKind>> {
+        log.append(1_700_000_000 + i as u64, variables(i), 0.0)
+    }
+
+    fn read_sequences(log: &mut DataLog<FlashMock>) -> ([u32; 16], usize) {
+        let mut sequences = [0; 16];
+        let mut n = 0;
+        log.for_each(|record| {
+            assert_eq!(record.timestamp, 1_700_000_000 + record.sequence as u64);
+            assert_eq!(record.variables, variables(record.sequence));
+            sequences[n] = record.sequence;
+            n += 1;
+        })
+        .unwrap();
+        (sequences, n)
+    }
+
+    #[test]
+    fn test_record() {
+        let record = MeasurementRecord {
+            loss: 1e-3,
+            sequence: 7,
+            timestamp: 1_700_000_007,
+            variables: variables(7),
+        };
+        let mut bytes = record.to_bytes();
+        assert_eq!(MeasurementRecord::from_bytes(&bytes), Ok(record));
+
+        bytes[5] ^= 0x10;
+        assert!(MeasurementRecord::from_bytes(&bytes).is_err());
+        assert!(MeasurementRecord::from_bytes(&[0xFF; RECORD_SIZE]).is_err());
+    }
+
+    #[test]
+    fn test_datalog() {
+        let mut log = DataLog::mount(FlashMock::new(), 0, SECTORS as u32).unwrap();
+        assert_eq!(log.capacity(), 12);
+        for i in 0..12 {
+            assert_eq!(append(&mut log, i), Ok(i));
+        }
+
+        // The position is recovered after a reset.
+        let mut log = DataLog::mount(log.release(), 0, SECTORS as u32).unwrap();
+        assert_eq!(log.next_sequence(), 12);
+        let (sequences, n) = read_sequences(&mut log);
+        assert_eq!(
+            &sequences[..n],
+            &core::array::from_fn::<u32, 12, _>(|i| i as u32)
+        );
+
+        // The oldest sector is reused when the log is full.
+        for i in 12..20 {
+            assert_eq!(append(&mut log, i), Ok(i));
+        }
+        let mut log = DataLog::mount(log.release(), 0, SECTORS as u32).unwrap();
+        assert_eq!(log.next_sequence(), 20);
+        let (sequences, n) = read_sequences(&mut log);
+        assert_eq!(
+            &sequences[..n],
+            &core::array::from_fn::<u32, 12, _>(|i| i as u32 + 8)
+        );
+
+        append(&mut log, 20).unwrap();
+        assert_eq!(log.release().erases, [2, 2, 2]);
+    }
+
+    #[test]
+    fn test_datalog_corrupted() {
+        let mut log = DataLog::mount(FlashMock::new(), 0, SECTORS as u32).unwrap();
+        for i in 0..3 {
+            append(&mut log, i).unwrap();
+        }
+
+        // Simulate a power loss while writing the second record.
+        let mut flash = log.release();
+        flash.data[RECORD_SIZE + 17] = 0x00;
+
+        let mut log = DataLog::mount(flash, 0, SECTORS as u32).unwrap();
+        assert_eq!(log.next_sequence(), 3);
+        let (sequences, n) = read_sequences(&mut log);
+        assert_eq!(&sequences[..n], &[0, 2]);
+    }
+
+    #[test]
+    fn test_datalog_invalid_region() {
+        assert!(matches!(
+            DataLog::mount(FlashMock::new(), 0, 1),
+            Err(DataLogError::InvalidRegion)
+        ));
+        assert!(matches!(
+            DataLog::mount(FlashMock::new(), SECTOR_SIZE as u32, SECTORS as u32),
+            Err(DataLogError::InvalidRegion)
+        ));
+    }
+}
@@ -0,0 +1,311 @@
+use std::fmt;
+use std::io::{self, BufRead, Lines};
+use std::iter::Enumerate;
+
+use crate::params::Currents;
+
+/// The columns of the currents, which every dataset must contain.
+const CURRENT_COLUMNS: [&str; 3] = ["i_ds_off", "i_ds_on", "i_gs_on"];
+
+/// A row of a dataset exported by the lab.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetRecord {
+    /// The measured currents.
+    pub currents: Currents,
+
+    /// The line of the row in the file, starting from one.
+    pub line: usize,
+
+    /// The temperature of the plant, if the dataset contains it [°C].
+    pub temperature: Option<f32>,
+
+    /// The time of the measurement, if the dataset contains it [s].
+    pub timestamp: Option<f64>,
+}
+
+/// The errors of the [`DatasetReader`].
+#[derive(Debug)]
+pub enum DatasetError {
+    /// The file could not be read.
+    Io(io::Error),
+
+    /// The file does not contain the header.
+    Empty,
+
+    /// The header does not contain a mandatory column.
+    MissingColumn(&'static str),
+
+    /// A row does not contain a field.
+    MissingField {
+        /// The line of the row, starting from one.
+        line: usize,
+
+        /// The column of the field.
+        column: &'static str,
+    },
+
+    /// A field of a row is not a number.
+    InvalidField {
+        /// The line of the row, starting from one.
+        line: usize,
+
+        /// The column of the field.
+        column: &'static str,
+
+        /// The content of the field.
+        value: String,
+    },
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::Io(err) => write!(f, "{err}"),
+            DatasetError::Empty => write!(f, "empty input"),
+            DatasetError::MissingColumn(column) => write!(f, "missing column `{column}`"),
+            DatasetError::MissingField { line, column } => {
+                write!(f, "line {line}: missing field `{column}`")
+            }
+            DatasetError::InvalidField {
+                line,
+                column,
+                value,
+            } => write!(f, "line {line}: invalid {column} `{value}`"),
+        }
+    }
+}
+
+impl std::error::Error for DatasetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DatasetError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DatasetError {
+    fn from(err: io::Error) -> Self {
+        DatasetError::Io(err)
+    }
+}
+
+/// The indexes of the known columns in the header.
+#[derive(Debug, Clone)]
+struct Columns {
+    /// The columns of the currents, in the order of [`CURRENT_COLUMNS`].
+    currents: [usize; 3],
+
+    /// The column of the temperature.
+    temperature: Option<usize>,
+
+    /// The column of the timestamp.
+    timestamp: Option<usize>,
+}
+
+/// Reader of the CSV datasets exported by the lab, shared by the simulator
+/// and the tests.
+///
+/// The first line is the header, which must contain the columns `i_ds_off`,
+/// `i_ds_on` and `i_gs_on` and can contain the columns `timestamp` and
+/// `temperature`, in any order; any other column is ignored, as well as the
+/// empty lines. The rows are returned as an iterator, so that an invalid row
+/// can be reported with its line without giving up the whole dataset.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::dataset::DatasetReader;
+///
+/// let csv = "timestamp,i_ds_on,i_ds_off,i_gs_on,temperature\n\
+///            0,-0.0026829,-0.0030365,1.169828e-6,21.5\n";
+/// let mut reader = DatasetReader::new(csv.as_bytes()).unwrap();
+/// let record = reader.next().unwrap().unwrap();
+/// assert_eq!(record.currents.i_ds_off, -0.0030365);
+/// assert_eq!(record.temperature, Some(21.5));
+/// ```
+pub struct DatasetReader<R: BufRead> {
+    /// The columns of the header.
+    columns: Columns,
+
+    /// The remaining lines, with their indexes.
+    lines: Enumerate<Lines<R>>,
+}
+
+impl<R: BufRead> DatasetReader<R> {
+    /// Creates a new reader, parsing the header.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The source of the CSV.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(reader)` - The reader of the rows.
+    /// * `Err(error)` - If the header is missing or does not contain the
+    ///   columns of the currents.
+    pub fn new(reader: R) -> Result<Self, DatasetError> {
+        let mut lines = reader.lines().enumerate();
+        let header = match lines.next() {
+            Some((_, header)) => header?,
+            None => return Err(DatasetError::Empty),
+        };
+
+        let names: Vec<&str> = header.split(',').map(str::trim).collect();
+        let index = |name: &str| names.iter().position(|&column| column == name);
+        let mut currents = [0; 3];
+        for (column, name) in currents.iter_mut().zip(CURRENT_COLUMNS) {
+            *column = index(name).ok_or(DatasetError::MissingColumn(name))?;
+        }
+
+        Ok(Self {
+            columns: Columns {
+                currents,
+                temperature: index("temperature"),
+                timestamp: index("timestamp"),
+            },
+            lines,
+        })
+    }
+
+    /// Parses a row.
+    fn parse(&self, line: usize, row: &str) -> Result<DatasetRecord, DatasetError> {
+        let fields: Vec<&str> = row.split(',').map(str::trim).collect();
+        let field = |index: usize, column: &'static str| {
+            fields
+                .get(index)
+                .copied()
+                .ok_or(DatasetError::MissingField { line, column })
+        };
+        let invalid = |column: &'static str, value: &str| DatasetError::InvalidField {
+            line,
+            column,
+            value: value.into(),
+        };
+
+        let mut currents = [0.0; 3];
+        for ((current, &index), column) in currents
+            .iter_mut()
+            .zip(&self.columns.currents)
+            .zip(CURRENT_COLUMNS)
+        {
+            let value = field(index, column)?;
+            *current = value.parse().map_err(|_| invalid(column, value))?;
+        }
+        let [i_ds_off, i_ds_on, i_gs_on] = currents;
+
+        // The optional fields can be left empty.
+        let temperature = match self.columns.temperature.map(|i| field(i, "temperature")) {
+            Some(Ok(value)) if !value.is_empty() => {
+                Some(value.parse().map_err(|_| invalid("temperature", value))?)
+            }
+            _ => None,
+        };
+        let timestamp = match self.columns.timestamp.map(|i| field(i, "timestamp")) {
+            Some(Ok(value)) if !value.is_empty() => {
+                Some(value.parse().map_err(|_| invalid("timestamp", value))?)
+            }
+            _ => None,
+        };
+
+        Ok(DatasetRecord {
+            currents: Currents {
+                i_ds_off,
+                i_ds_on,
+                i_gs_on,
+            },
+            line,
+            temperature,
+            timestamp,
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for DatasetReader<R> {
+    type Item = Result<DatasetRecord, DatasetError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (index, line) = self.lines.next()?;
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err.into())),
+            };
+            if !line.trim().is_empty() {
+                return Some(self.parse(index + 1, &line));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_reader() {
+        let csv = "timestamp, i_gs_on, i_ds_on, i_ds_off, temperature, notes\n\
+                   0.5,1e-6,-2e-3,-3e-3,20.5,ok\n\
+                   \n\
+                   1.5,2e-6,-4e-3,-6e-3,,\n";
+        let records: Vec<_> = DatasetReader::new(csv.as_bytes())
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            records,
+            [
+                DatasetRecord {
+                    currents: Currents {
+                        i_ds_off: -3e-3,
+                        i_ds_on: -2e-3,
+                        i_gs_on: 1e-6,
+                    },
+                    line: 2,
+                    temperature: Some(20.5),
+                    timestamp: Some(0.5),
+                },
+                DatasetRecord {
+                    currents: Currents {
+                        i_ds_off: -6e-3,
+                        i_ds_on: -4e-3,
+                        i_gs_on: 2e-6,
+                    },
+                    line: 4,
+                    temperature: None,
+                    timestamp: Some(1.5),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dataset_reader_errors() {
+        assert!(matches!(
+            DatasetReader::new("".as_bytes()),
+            Err(DatasetError::Empty)
+        ));
+        assert!(matches!(
+            DatasetReader::new("i_ds_on,i_gs_on\n".as_bytes()),
+            Err(DatasetError::MissingColumn("i_ds_off"))
+        ));
+
+        let csv = "i_ds_off,i_ds_on,i_gs_on\n1,2,x\n1,2\n1,2,3\n";
+        let mut reader = DatasetReader::new(csv.as_bytes()).unwrap();
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "line 2: invalid i_gs_on `x`");
+        assert!(matches!(
+            reader.next(),
+            Some(Err(DatasetError::MissingField {
+                line: 3,
+                column: "i_gs_on"
+            }))
+        ));
+        // The following rows are still read.
+        assert!(matches!(
+            reader.next(),
+            Some(Ok(DatasetRecord { line: 4, .. }))
+        ));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,376 @@
+use core::fmt::{self, Write};
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::params::Variables;
+
+/// The maximum length of a [`Label`], a full line of a 128 pixels wide
+/// display with the 6x10 font.
+pub const LABEL_LEN: usize = 21;
+
+/// The height of a line of text [pixel].
+const LINE_HEIGHT: i32 = 10;
+
+/// The height of a bar [pixel].
+const BAR_HEIGHT: u32 = 6;
+
+/// A short text formatted without allocations, truncated to [`LABEL_LEN`]
+/// bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Label {
+    /// The text.
+    buf: [u8; LABEL_LEN],
+
+    /// The length of the text.
+    len: usize,
+}
+
+impl Label {
+    /// Creates an empty label.
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; LABEL_LEN],
+            len: 0,
+        }
+    }
+
+    /// Returns the text of the label.
+    pub fn as_str(&self) -> &str {
+        // Only whole characters are written, see `write_str`.
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+}
+
+impl Default for Label {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for Label {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            let end = self.len + c.len_utf8();
+            if end > LABEL_LEN {
+                return Err(fmt::Error);
+            }
+            c.encode_utf8(&mut self.buf[self.len..end]);
+            self.len = end;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a concentration with three significant digits, in the unit that
+/// keeps the value between 1 and 1000.
+///
+/// # Arguments
+///
+/// * `concentration` - The concentration [Molarity].
+///
+/// # Returns
+///
+/// The formatted concentration, e.g. `"12.3 mM"`, or `"---"` if the value is
+/// not a valid concentration.
+///
+/// # Examples
+///
+/// ```
+/// use bioristor_lib::display::format_concentration;
+///
+/// assert_eq!(format_concentration(0.0123).as_str(), "12.3 mM");
+/// assert_eq!(format_concentration(4.5e-5).as_str(), "45.0 uM");
+/// ```
+pub fn format_concentration(concentration: f32) -> Label {
+    let mut label = Label::new();
+    if !concentration.is_finite() || concentration < 0.0 {
+        label.write_str("---").ok();
+        return label;
+    }
+
+    let (value, unit) = match concentration {
+        c if c >= 1.0 => (c, "M"),
+        c if c >= 1e-3 => (c * 1e3, "mM"),
+        c if c >= 1e-6 => (c * 1e6, "uM"),
+        c => (c * 1e9, "nM"),
+    };
+    let decimals = match value {
+        v if v >= 100.0 => 0,
+        v if v >= 10.0 => 1,
+        _ => 2,
+    };
+    write!(label, "{value:.decimals$} {unit}").ok();
+    label
+}
+
+/// Formats a saturation as a percentage.
+///
+/// # Arguments
+///
+/// * `saturation` - The saturation [dimensionless].
+///
+/// # Returns
+///
+/// The formatted saturation, e.g. `"45 %"`, or `"---"` if the value is not
+/// finite.
+pub fn format_saturation(saturation: f32) -> Label {
+    let mut label = Label::new();
+    if saturation.is_finite() {
+        write!(label, "{:.0} %", saturation.clamp(0.0, 1.0) * 100.0).ok();
+    } else {
+        label.write_str("---").ok();
+    }
+    label
+}
+
+/// The layout of the results on a monochrome display, such as the 128x64
+/// SSD1306.
+///
+/// The concentration and the saturation are drawn as text followed by a bar;
+/// the bar of the concentration is in logarithmic scale over the given
+/// range. The last line is either `OK` or the error, highlighted in reverse
+/// video.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::display::ResultsView;
+/// use bioristor_lib::params::Variables;
+/// use embedded_graphics::{mock_display::MockDisplay, pixelcolor::BinaryColor};
+///
+/// let view = ResultsView {
+///     concentration_max: 1e-1,
+///     concentration_min: 1e-4,
+/// };
+/// let result = Variables {
+///     concentration: 1.2e-3,
+///     resistance: 40.0,
+///     saturation: 0.5,
+/// };
+///
+/// let mut display = MockDisplay::<BinaryColor>::new();
+/// display.set_allow_out_of_bounds_drawing(true);
+/// display.set_allow_overdraw(true);
+/// view.draw(&mut display, Some(&result), None).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResultsView {
+    /// The concentration at the right end of the bar [Molarity].
+    pub concentration_max: f32,
+
+    /// The concentration at the left end of the bar [Molarity].
+    pub concentration_min: f32,
+}
+
+impl ResultsView {
+    /// Clears the display and draws the results.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The display.
+    /// * `result` - The latest solution, if any.
+    /// * `error` - A short description of the latest error, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the results have been drawn.
+    /// * `Err(error)` - If the display failed.
+    pub fn draw<D: DrawTarget<Color = BinaryColor>>(
+        &self,
+        target: &mut D,
+        result: Option<&Variables>,
+        error: Option<&str>,
+    ) -> Result<(), D::Error> {
+        target.clear(BinaryColor::Off)?;
+
+        let width = target.bounding_box().size.width;
+        let (concentration, saturation) = result.map_or((f32::NAN, f32::NAN), |vars| {
+            (vars.concentration, vars.saturation)
+        });
+
+        let mut label = Label::new();
+        write!(
+            label,
+            "Conc {}",
+            format_concentration(concentration).as_str()
+        )
+        .ok();
+        draw_text(target, label.as_str(), 0, BinaryColor::On)?;
+        draw_bar(
+            target,
+            LINE_HEIGHT + 1,
+            width,
+            self.concentration_fraction(concentration),
+        )?;
+
+        let mut label = Label::new();
+        write!(label, "Sat  {}", format_saturation(saturation).as_str()).ok();
+        draw_text(target, label.as_str(), 2 * LINE_HEIGHT + 2, BinaryColor::On)?;
+        draw_bar(
+            target,
+            3 * LINE_HEIGHT + 3,
+            width,
+            saturation_fraction(saturation),
+        )?;
+
+        let status_y = 4 * LINE_HEIGHT + 6;
+        match error {
+            Some(error) => {
+                Rectangle::new(
+                    Point::new(0, status_y),
+                    Size::new(width, LINE_HEIGHT as u32),
+                )
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(target)?;
+                draw_text(target, error, status_y, BinaryColor::Off)
+            }
+            None => draw_text(target, "OK", status_y, BinaryColor::On),
+        }
+    }
+
+    /// Returns the filled fraction of the bar of the concentration.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentration` - The concentration [Molarity].
+    ///
+    /// # Returns
+    ///
+    /// The fraction in logarithmic scale, between 0 and 1; zero if either the
+    /// concentration or the range is not valid.
+    pub fn concentration_fraction(&self, concentration: f32) -> f32 {
+        if concentration <= 0.0 || self.concentration_min <= 0.0 {
+            return 0.0;
+        }
+        let span = (self.concentration_max / self.concentration_min).ln();
+        let fraction = (concentration / self.concentration_min).ln() / span;
+        if fraction.is_finite() {
+            fraction.clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Returns the filled fraction of the bar of the saturation, between 0 and 1.
+fn saturation_fraction(saturation: f32) -> f32 {
+    if saturation.is_finite() {
+        saturation.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// Draws a line of text with its top at the given height.
+fn draw_text<D: DrawTarget<Color = BinaryColor>>(
+    target: &mut D,
+    text: &str,
+    y: i32,
+    color: BinaryColor,
+) -> Result<(), D::Error> {
+    Text::with_baseline(
+        text,
+        Point::new(0, y),
+        MonoTextStyle::new(&FONT_6X10, color),
+        Baseline::Top,
+    )
+    .draw(target)?;
+    Ok(())
+}
+
+/// Draws the outline of a bar spanning the width of the display, filled up
+/// to the given fraction.
+fn draw_bar<D: DrawTarget<Color = BinaryColor>>(
+    target: &mut D,
+    y: i32,
+    width: u32,
+    fraction: f32,
+) -> Result<(), D::Error> {
+    Rectangle::new(Point::new(0, y), Size::new(width, BAR_HEIGHT))
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+        .draw(target)?;
+
+    let filled = (fraction * width as f32) as u32;
+    Rectangle::new(Point::new(0, y), Size::new(filled, BAR_HEIGHT))
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::mock_display::MockDisplay;
+
+    use super::*;
+
+    const VIEW: ResultsView = ResultsView {
+        concentration_max: 1e-1,
+        concentration_min: 1e-4,
+    };
+
+    #[test]
+    fn test_format_concentration() {
+        assert_eq!(format_concentration(2.5).as_str(), "2.50 M");
+        assert_eq!(format_concentration(0.123).as_str(), "123 mM");
+        assert_eq!(format_concentration(1e-3).as_str(), "1.00 mM");
+        assert_eq!(format_concentration(4.56e-7).as_str(), "456 nM");
+        assert_eq!(format_concentration(-1.0).as_str(), "---");
+        assert_eq!(format_concentration(f32::NAN).as_str(), "---");
+    }
+
+    #[test]
+    fn test_format_saturation() {
+        assert_eq!(format_saturation(0.456).as_str(), "46 %");
+        assert_eq!(format_saturation(1.5).as_str(), "100 %");
+        assert_eq!(format_saturation(f32::INFINITY).as_str(), "---");
+    }
+
+    #[test]
+    fn test_label_truncated() {
+        let mut label = Label::new();
+        assert!(label.write_str("Concentration 123.45 mM").is_err());
+        assert_eq!(label.as_str().len(), LABEL_LEN);
+    }
+
+    #[test]
+    fn test_concentration_fraction() {
+        assert_eq!(VIEW.concentration_fraction(1e-4), 0.0);
+        assert!((VIEW.concentration_fraction(1e-2) - 2.0 / 3.0).abs() < 1e-3);
+        assert_eq!(VIEW.concentration_fraction(1.0), 1.0);
+        assert_eq!(VIEW.concentration_fraction(0.0), 0.0);
+        assert_eq!(VIEW.concentration_fraction(f32::NAN), 0.0);
+    }
+
+    #[test]
+    fn test_draw() {
+        let result = Variables {
+            concentration: 1e-1,
+            resistance: 40.0,
+            saturation: 1.0,
+        };
+
+        let mut display = MockDisplay::<BinaryColor>::new();
+        display.set_allow_out_of_bounds_drawing(true);
+        display.set_allow_overdraw(true);
+        VIEW.draw(&mut display, Some(&result), None).unwrap();
+
+        // The bars are full.
+        let bar = Point::new(32, LINE_HEIGHT + 1 + BAR_HEIGHT as i32 / 2);
+        assert_eq!(display.get_pixel(bar), Some(BinaryColor::On));
+        let status = Point::new(63, 4 * LINE_HEIGHT + 6);
+        assert_eq!(display.get_pixel(status), Some(BinaryColor::Off));
+
+        // The error is drawn in reverse video.
+        VIEW.draw(&mut display, None, Some("ADC")).unwrap();
+        assert_eq!(display.get_pixel(status), Some(BinaryColor::On));
+        assert_eq!(display.get_pixel(bar), Some(BinaryColor::Off));
+    }
+}
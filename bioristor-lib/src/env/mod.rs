@@ -0,0 +1,227 @@
+mod sht3x;
+
+pub use sht3x::*;
+
+use crate::models::Model;
+use crate::params::{Currents, ModelParams};
+
+/// The offset between the Celsius and the Kelvin scales.
+const ZERO_CELSIUS: f32 = 273.15;
+
+/// The ambient conditions measured around the plant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ambient {
+    /// The relative humidity [%].
+    pub humidity: f32,
+
+    /// The temperature [Celsius].
+    pub temperature: f32,
+}
+
+/// Common interface for the sensors of the ambient conditions.
+pub trait AmbientSensor {
+    /// The error returned by the sensor.
+    type Error;
+
+    /// Performs a measurement of the ambient conditions.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ambient)` - The ambient conditions.
+    /// * `Err(error)` - If the measurement failed.
+    fn read(&mut self) -> Result<Ambient, Self::Error>;
+}
+
+/// The parameters of the temperature compensation of the model.
+///
+/// The parameters of the model are calibrated at a reference temperature.
+/// At a different temperature, the resistance of the dry channel changes
+/// linearly, and the logarithmic term of the modulation scales with the
+/// absolute temperature as in the Nernst equation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CompensationParams {
+    /// The relative change of the resistance of the dry channel per degree
+    /// [1/Celsius].
+    pub r_dry_coefficient: f32,
+
+    /// The temperature at which the model has been calibrated [Celsius].
+    pub reference_temperature: f32,
+}
+
+impl CompensationParams {
+    /// Computes the parameters of the model at the given temperature.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the model at the reference temperature.
+    /// * `temperature` - The temperature [Celsius].
+    ///
+    /// # Returns
+    ///
+    /// The compensated parameters of the model.
+    pub fn compensate(&self, params: &ModelParams, temperature: f32) -> ModelParams {
+        let delta = temperature - self.reference_temperature;
+        let ratio = (temperature + ZERO_CELSIUS) / (self.reference_temperature + ZERO_CELSIUS);
+
+        let mut compensated = params.clone();
+        compensated.r_dry *= 1.0 + self.r_dry_coefficient * delta;
+        compensated.mod_params.1 *= ratio;
+        compensated
+    }
+}
+
+/// Compensation of the model with the temperature read from an ambient
+/// sensor at every measurement cycle.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut compensator = EnvCompensator::new(sensor, COMPENSATION, MODEL_PARAMS);
+///
+/// if let Some(currents) = cycle.tick(now)? {
+///     let model: Equation = compensator.model(currents)?;
+///     let res = Adaptive2Equation::new(ALG_PARAMS, model).run();
+/// }
+/// ```
+pub struct EnvCompensator<S: AmbientSensor> {
+    /// The last ambient conditions measured.
+    ambient: Option<Ambient>,
+
+    /// The parameters of the compensation.
+    compensation: CompensationParams,
+
+    /// The parameters of the model at the reference temperature.
+    reference: ModelParams,
+
+    /// The ambient sensor.
+    sensor: S,
+}
+
+impl<S: AmbientSensor> EnvCompensator<S> {
+    /// Creates a new compensator.
+    ///
+    /// # Arguments
+    ///
+    /// * `sensor` - The ambient sensor.
+    /// * `compensation` - The parameters of the compensation.
+    /// * `reference` - The parameters of the model at the reference
+    ///   temperature.
+    pub fn new(sensor: S, compensation: CompensationParams, reference: ModelParams) -> Self {
+        Self {
+            ambient: None,
+            compensation,
+            reference,
+            sensor,
+        }
+    }
+
+    /// Returns the last ambient conditions measured, if any.
+    #[inline]
+    pub fn ambient(&self) -> Option<Ambient> {
+        self.ambient
+    }
+
+    /// Releases the ambient sensor.
+    pub fn release(self) -> S {
+        self.sensor
+    }
+
+    /// Reads the ambient sensor and computes the compensated parameters of
+    /// the model.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(params)` - The parameters at the current temperature.
+    /// * `Err(error)` - If the sensor failed.
+    pub fn params(&mut self) -> Result<ModelParams, S::Error> {
+        let ambient = self.sensor.read()?;
+        self.ambient = Some(ambient);
+        Ok(self
+            .compensation
+            .compensate(&self.reference, ambient.temperature))
+    }
+
+    /// Reads the ambient sensor and creates the model of the measured
+    /// currents with the compensated parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `currents` - The currents measured in the current cycle.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(model)` - The compensated model.
+    /// * `Err(error)` - If the sensor failed.
+    pub fn model<M: Model<Params = ModelParams>>(
+        &mut self,
+        currents: Currents,
+    ) -> Result<M, S::Error> {
+        Ok(M::new(self.params()?, currents))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::Equation;
+    use crate::params::{ModulationParams, StemResistanceInvParams, Voltages};
+
+    use super::*;
+
+    /// Sensor returning a fixed temperature.
+    struct SensorMock(f32);
+
+    impl AmbientSensor for SensorMock {
+        type Error = ();
+
+        fn read(&mut self) -> Result<Ambient, Self::Error> {
+            Ok(Ambient {
+                humidity: 60.0,
+                temperature: self.0,
+            })
+        }
+    }
+
+    const PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    const COMPENSATION: CompensationParams = CompensationParams {
+        r_dry_coefficient: 0.01,
+        reference_temperature: 25.0,
+    };
+
+    #[test]
+    fn test_compensate() {
+        assert_eq!(COMPENSATION.compensate(&PARAMS, 25.0), PARAMS);
+
+        let params = COMPENSATION.compensate(&PARAMS, 35.0);
+        assert!((params.r_dry - 38.2 * 1.1).abs() < 1e-4);
+        assert!((params.mod_params.1 + 0.01463 * 308.15 / 298.15).abs() < 1e-7);
+        assert_eq!(params.mod_params.0, PARAMS.mod_params.0);
+        assert_eq!(params.res_params, PARAMS.res_params);
+    }
+
+    #[test]
+    fn test_env_compensator() {
+        let mut compensator = EnvCompensator::new(SensorMock(15.0), COMPENSATION, PARAMS);
+        assert_eq!(compensator.ambient(), None);
+
+        let currents = Currents {
+            i_ds_off: -0.0030365,
+            i_ds_on: -0.0026829,
+            i_gs_on: 1.169828e-6,
+        };
+        let model: Equation = compensator.model(currents).unwrap();
+        assert_eq!(model.params(), &COMPENSATION.compensate(&PARAMS, 15.0));
+        assert_eq!(model.currents(), &currents);
+        assert_eq!(compensator.ambient().map(|a| a.temperature), Some(15.0));
+    }
+}
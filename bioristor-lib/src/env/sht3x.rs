@@ -0,0 +1,169 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+
+use super::{Ambient, AmbientSensor};
+
+/// The default I2C address of the SHT3x, with the ADDR pin low.
+pub const SHT3X_ADDRESS: u8 = 0x44;
+
+/// The single-shot measurement command, with high repeatability and clock
+/// stretching disabled.
+const MEASURE_COMMAND: [u8; 2] = [0x24, 0x00];
+
+/// The maximum duration of a measurement with high repeatability [ms].
+const MEASURE_TIME_MS: u32 = 16;
+
+/// The errors of the [`Sht3x`] driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Sht3xError<E> {
+    /// The CRC of the received data does not match.
+    Crc,
+
+    /// The I2C bus failed.
+    I2c(E),
+}
+
+/// Driver of the Sensirion SHT3x temperature and humidity sensor over I2C.
+pub struct Sht3x<I2C, D> {
+    /// The I2C address of the sensor.
+    address: u8,
+
+    /// The delay used to wait for the end of the measurement.
+    delay: D,
+
+    /// The I2C bus the sensor is connected to.
+    i2c: I2C,
+}
+
+impl<I2C: I2c, D: DelayNs> Sht3x<I2C, D> {
+    /// Creates a new driver.
+    ///
+    /// # Arguments
+    ///
+    /// * `i2c` - The I2C bus the sensor is connected to.
+    /// * `delay` - The delay used to wait for the end of the measurement.
+    /// * `address` - The I2C address of the sensor, usually
+    ///   [`SHT3X_ADDRESS`].
+    pub fn new(i2c: I2C, delay: D, address: u8) -> Self {
+        Self {
+            address,
+            delay,
+            i2c,
+        }
+    }
+
+    /// Releases the I2C bus and the delay.
+    pub fn release(self) -> (I2C, D) {
+        (self.i2c, self.delay)
+    }
+}
+
+impl<I2C: I2c, D: DelayNs> AmbientSensor for Sht3x<I2C, D> {
+    type Error = Sht3xError<I2C::Error>;
+
+    fn read(&mut self) -> Result<Ambient, Self::Error> {
+        self.i2c
+            .write(self.address, &MEASURE_COMMAND)
+            .map_err(Sht3xError::I2c)?;
+        self.delay.delay_ms(MEASURE_TIME_MS);
+
+        let mut buf = [0; 6];
+        self.i2c
+            .read(self.address, &mut buf)
+            .map_err(Sht3xError::I2c)?;
+        if crc8(&buf[0..2]) != buf[2] || crc8(&buf[3..5]) != buf[5] {
+            return Err(Sht3xError::Crc);
+        }
+
+        let temperature = u16::from_be_bytes([buf[0], buf[1]]) as f32;
+        let humidity = u16::from_be_bytes([buf[3], buf[4]]) as f32;
+        Ok(Ambient {
+            humidity: 100.0 * humidity / 65535.0,
+            temperature: -45.0 + 175.0 * temperature / 65535.0,
+        })
+    }
+}
+
+/// Computes the CRC-8 of the SHT3x, with polynomial `0x31` and initial value
+/// `0xFF`.
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0xFF;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x31
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal::i2c::{ErrorType, Operation};
+
+    use super::*;
+
+    /// I2C bus answering with a fixed measurement.
+    struct I2cMock {
+        response: [u8; 6],
+        written: [u8; 2],
+    }
+
+    impl ErrorType for I2cMock {
+        type Error = core::convert::Infallible;
+    }
+
+    impl I2c for I2cMock {
+        fn transaction(
+            &mut self,
+            address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            assert_eq!(address, SHT3X_ADDRESS);
+            for operation in operations {
+                match operation {
+                    Operation::Write(buf) => self.written.copy_from_slice(buf),
+                    Operation::Read(buf) => buf.copy_from_slice(&self.response),
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Delay returning immediately.
+    struct DelayMock;
+
+    impl DelayNs for DelayMock {
+        fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    #[test]
+    fn test_crc8() {
+        assert_eq!(crc8(&[0xBE, 0xEF]), 0x92);
+    }
+
+    #[test]
+    fn test_sht3x() {
+        let mut response = [0x66, 0x66, 0, 0x80, 0x00, 0];
+        response[2] = crc8(&response[0..2]);
+        response[5] = crc8(&response[3..5]);
+        let i2c = I2cMock {
+            response,
+            written: [0; 2],
+        };
+        let mut sensor = Sht3x::new(i2c, DelayMock, SHT3X_ADDRESS);
+
+        let ambient = sensor.read().unwrap();
+        assert!((ambient.temperature - 25.0).abs() < 1e-3);
+        assert!((ambient.humidity - 50.0).abs() < 1e-2);
+        assert_eq!(sensor.i2c.written, MEASURE_COMMAND);
+
+        sensor.i2c.response[1] ^= 0x01;
+        assert_eq!(sensor.read(), Err(Sht3xError::Crc));
+    }
+}
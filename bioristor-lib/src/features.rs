@@ -0,0 +1,234 @@
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::{params::Currents, utils::fast_math};
+
+/// The parameters of the extraction of the currents.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FeatureParams {
+    /// The minimum number of settled samples required in every segment.
+    /// The last `min_samples` samples are also used to estimate the
+    /// steady-state value.
+    pub min_samples: usize,
+
+    /// The number of standard deviations from the mean beyond which a sample
+    /// is considered an outlier.
+    pub outlier_threshold: f32,
+
+    /// The tolerance around the steady-state value, relative to the value
+    /// itself, within which a sample is considered settled.
+    pub settle_tolerance: f32,
+
+    /// The number of consecutive samples out of tolerance that mark the end of
+    /// the transient; shorter runs are considered outliers.
+    pub settle_window: usize,
+}
+
+/// The errors of the extraction of the currents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FeatureError {
+    /// The waveforms are inconsistent with the given gate switching index.
+    InvalidWaveform,
+
+    /// A segment does not settle within the waveform.
+    NotSettled,
+
+    /// A segment is shorter than the minimum number of samples.
+    TooShort,
+}
+
+/// Extracts the currents of the device from the waveforms sampled during a
+/// gate pulse.
+///
+/// The samples before the gate is switched on are used to compute `i_ds_off`,
+/// the following ones to compute `i_ds_on` and `i_gs_on`. For every segment,
+/// the initial transient is discarded and the outliers are rejected, see
+/// [`steady_state`].
+///
+/// # Arguments
+///
+/// * `params` - The parameters of the extraction.
+/// * `i_ds` - The drain-source current waveform [Ampere].
+/// * `i_gs` - The gate-source current waveform, with the same length and
+///   timing of `i_ds` [Ampere].
+/// * `gate_on` - The index of the first sample after the gate has been
+///   switched on.
+///
+/// # Returns
+///
+/// * `Ok(currents)` - The steady-state currents.
+/// * `Err(error)` - If the currents cannot be extracted from the waveforms.
+pub fn extract_currents(
+    params: &FeatureParams,
+    i_ds: &[f32],
+    i_gs: &[f32],
+    gate_on: usize,
+) -> Result<Currents, FeatureError> {
+    if i_ds.len() != i_gs.len() || gate_on > i_ds.len() {
+        return Err(FeatureError::InvalidWaveform);
+    }
+
+    Ok(Currents {
+        i_ds_off: steady_state(params, &i_ds[..gate_on])?,
+        i_ds_on: steady_state(params, &i_ds[gate_on..])?,
+        i_gs_on: steady_state(params, &i_gs[gate_on..])?,
+    })
+}
+
+/// Computes the steady-state value of a segment of a waveform.
+///
+/// The steady-state value is first estimated from the last samples of the
+/// segment; then the segment is scanned backwards until a run of
+/// `settle_window` samples out of tolerance is found, which marks the end of
+/// the transient. The result is the mean of the settled samples, excluding
+/// the outliers.
+///
+/// # Arguments
+///
+/// * `params` - The parameters of the extraction.
+/// * `segment` - The samples of the segment.
+///
+/// # Returns
+///
+/// * `Ok(value)` - The steady-state value.
+/// * `Err(error)` - If the segment is too short or does not settle.
+pub fn steady_state(params: &FeatureParams, segment: &[f32]) -> Result<f32, FeatureError> {
+    let min_samples = params.min_samples.max(1);
+    if segment.len() < min_samples {
+        return Err(FeatureError::TooShort);
+    }
+
+    // Estimate the steady-state value from the tail of the segment.
+    let tail = &segment[segment.len() - min_samples..];
+    let reference = clipped_mean(tail, params.outlier_threshold);
+    let band =
+        (params.settle_tolerance * reference.abs()).max(params.outlier_threshold * noise(tail));
+
+    // Find the end of the transient.
+    let mut start = 0;
+    let mut run = 0;
+    for (i, &x) in segment.iter().enumerate().rev() {
+        if (x - reference).abs() > band {
+            run += 1;
+            if run >= params.settle_window.max(1) {
+                start = i + run;
+                break;
+            }
+        } else {
+            run = 0;
+        }
+    }
+
+    if segment.len() - start < min_samples {
+        return Err(FeatureError::NotSettled);
+    }
+
+    Ok(clipped_mean(&segment[start..], params.outlier_threshold))
+}
+
+/// Estimates the standard deviation of the noise of the samples from the
+/// differences between consecutive samples, so that the estimate is not
+/// affected by a slow drift of the signal.
+fn noise(samples: &[f32]) -> f32 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+
+    let n = (samples.len() - 1) as f32;
+    let diffs = samples.windows(2).map(|w| w[1] - w[0]);
+    let mean = diffs.clone().sum::<f32>() / n;
+    let var = diffs.map(|d| (d - mean).powi(2)).sum::<f32>() / n;
+
+    // The difference of two independent samples has twice the variance.
+    fast_math::sqrt(var / 2.0)
+}
+
+/// Computes the mean of the samples, excluding the ones farther than
+/// `threshold` standard deviations from the mean.
+///
+/// # Returns
+///
+/// The clipped mean of the samples.
+fn clipped_mean(samples: &[f32], threshold: f32) -> f32 {
+    let n = samples.len() as f32;
+    let mean = samples.iter().sum::<f32>() / n;
+    let std = fast_math::sqrt(samples.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / n);
+
+    let limit = threshold * std;
+    let (sum, count) = samples
+        .iter()
+        .filter(|x| (*x - mean).abs() <= limit)
+        .fold((0.0, 0), |(sum, count), x| (sum + x, count + 1));
+
+    if count == 0 {
+        mean
+    } else {
+        sum / count as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMS: FeatureParams = FeatureParams {
+        min_samples: 8,
+        outlier_threshold: 2.0,
+        settle_tolerance: 0.01,
+        settle_window: 3,
+    };
+
+    /// Builds a waveform settling exponentially from `from` to `to`.
+    fn settling<const N: usize>(from: f32, to: f32) -> [f32; N] {
+        let mut waveform = [0.0; N];
+        for (i, x) in waveform.iter_mut().enumerate() {
+            *x = to + (from - to) * 0.5f32.powi(i as i32);
+        }
+        waveform
+    }
+
+    #[test]
+    fn test_steady_state() {
+        let mut segment = settling::<32>(0.0, 1.0);
+        segment[20] = 5.0;
+
+        let value = steady_state(&PARAMS, &segment).unwrap();
+        assert!((value - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_steady_state_errors() {
+        assert_eq!(
+            steady_state(&PARAMS, &[1.0; 4]),
+            Err(FeatureError::TooShort)
+        );
+
+        // The transient lasts until the end of the segment.
+        let segment = settling::<16>(0.0, 1.0);
+        let ramp: [f32; 16] = core::array::from_fn(|i| i as f32);
+        assert!(steady_state(&PARAMS, &segment).is_ok());
+        assert_eq!(steady_state(&PARAMS, &ramp), Err(FeatureError::NotSettled));
+    }
+
+    #[test]
+    fn test_extract_currents() {
+        let mut i_ds = [0.0; 64];
+        let mut i_gs = [0.0; 64];
+        i_ds[..32].copy_from_slice(&settling::<32>(2e-3, 1e-3));
+        i_ds[32..].copy_from_slice(&settling::<32>(1e-3, 5e-4));
+        i_gs[32..].copy_from_slice(&settling::<32>(1e-4, 1e-5));
+        i_ds[50] = 0.0;
+
+        let currents = extract_currents(&PARAMS, &i_ds, &i_gs, 32).unwrap();
+        assert!((currents.i_ds_off - 1e-3).abs() < 1e-6);
+        assert!((currents.i_ds_on - 5e-4).abs() < 1e-6);
+        assert!((currents.i_gs_on - 1e-5).abs() < 1e-7);
+
+        assert_eq!(
+            extract_currents(&PARAMS, &i_ds, &i_gs[1..], 32),
+            Err(FeatureError::InvalidWaveform)
+        );
+    }
+}
@@ -0,0 +1,640 @@
+use core::ptr;
+
+use crate::algorithms::{
+    Adaptive2Equation, Adaptive2Params, AdaptiveEquation, AdaptiveParams, Algorithm,
+    BruteForceEquation, BruteForceParams, GradientDescentEquation, GradientDescentParams,
+    NeuralNetworkEquation, NeuralNetworkParams, NewtonEquation, NewtonParams, SaturationPolicy,
+};
+use crate::losses::Absolute;
+use crate::models::{Equation, Model};
+use crate::params::{Currents, ModelParams, Variables};
+use crate::utils::FloatRange;
+
+/// The number of minima averaged by the adaptive algorithms.
+const MINIMA: usize = 10;
+
+/// The status codes returned by the functions of the C interface.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BioristorStatus {
+    /// The operation succeeded.
+    Ok = 0,
+
+    /// A required pointer is null.
+    NullPointer = 1,
+
+    /// The algorithm is not one of the [`BioristorAlgorithm`] values.
+    InvalidAlgorithm = 2,
+
+    /// The algorithm did not find a solution.
+    NoSolution = 3,
+
+    /// The model has not been solved since it was initialized or its
+    /// currents were updated.
+    NotSolved = 4,
+}
+
+/// The algorithms available through the C interface, all solving the
+/// equation model.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BioristorAlgorithm {
+    /// See [`AdaptiveEquation`].
+    Adaptive = 0,
+
+    /// See [`Adaptive2Equation`].
+    Adaptive2 = 1,
+
+    /// See [`BruteForceEquation`].
+    BruteForce = 2,
+
+    /// See [`GradientDescentEquation`].
+    GradientDescent = 3,
+
+    /// See [`NewtonEquation`].
+    Newton = 4,
+
+    /// See [`NeuralNetworkEquation`], with one hidden layer.
+    NeuralNetwork = 5,
+}
+
+impl BioristorAlgorithm {
+    /// Converts the value received from C, which may not be a valid variant.
+    fn from_raw(value: u32) -> Option<Self> {
+        Some(match value {
+            0 => Self::Adaptive,
+            1 => Self::Adaptive2,
+            2 => Self::BruteForce,
+            3 => Self::GradientDescent,
+            4 => Self::Newton,
+            5 => Self::NeuralNetwork,
+            _ => return None,
+        })
+    }
+}
+
+/// The parameters of the algorithms, flattened in a single structure. Each
+/// algorithm uses only the relevant ones.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BioristorSolverParams {
+    /// The initial guess of the concentration [Molarity].
+    pub concentration_init: f32,
+
+    /// The upper bound of the concentrations to search [Molarity].
+    pub concentration_max: f32,
+
+    /// The lower bound of the concentrations to search [Molarity].
+    pub concentration_min: f32,
+
+    /// The number of concentrations to search.
+    pub concentration_steps: usize,
+
+    /// The tolerance on the gradient at which the iterative algorithms stop.
+    pub grad_tolerance: f32,
+
+    /// The initial learning rate of the gradient descent.
+    pub learning_rate_init: f32,
+
+    /// The maximum number of iterations.
+    pub max_iterations: usize,
+
+    /// The width of the range of concentrations below which the adaptive
+    /// algorithm v2 stops, zero to disable the criterion.
+    pub min_range_width: f32,
+
+    /// The factor by which the range of concentrations is reduced after each
+    /// iteration of the adaptive algorithm v2.
+    pub reduction_factor: f32,
+
+    /// The upper bound of the resistances to search [Ohm].
+    pub resistance_max: f32,
+
+    /// The lower bound of the resistances to search [Ohm].
+    pub resistance_min: f32,
+
+    /// The number of resistances to search.
+    pub resistance_steps: usize,
+
+    /// The upper bound of the saturations to search.
+    pub saturation_max: f32,
+
+    /// The lower bound of the saturations to search.
+    pub saturation_min: f32,
+
+    /// The number of saturations to search.
+    pub saturation_steps: usize,
+
+    /// The error tolerance at which the algorithms stop.
+    pub tolerance: f32,
+}
+
+impl Default for BioristorSolverParams {
+    /// Returns the parameters used by the example firmware.
+    fn default() -> Self {
+        Self {
+            concentration_init: 1e-2,
+            concentration_max: 1e-1,
+            concentration_min: 1e-4,
+            concentration_steps: 1_000,
+            grad_tolerance: 1e-9,
+            learning_rate_init: 0.1,
+            max_iterations: 10,
+            min_range_width: 0.0,
+            reduction_factor: 0.2,
+            resistance_max: 100.0,
+            resistance_min: 10.0,
+            resistance_steps: 100,
+            saturation_max: 1.0,
+            saturation_min: 0.0,
+            saturation_steps: 100,
+            tolerance: 1e-15,
+        }
+    }
+}
+
+impl BioristorSolverParams {
+    fn concentration_range(&self) -> FloatRange {
+        FloatRange::new(
+            self.concentration_min,
+            self.concentration_max,
+            self.concentration_steps,
+        )
+    }
+
+    fn resistance_range(&self) -> FloatRange {
+        FloatRange::new(
+            self.resistance_min,
+            self.resistance_max,
+            self.resistance_steps,
+        )
+    }
+
+    fn saturation_range(&self) -> FloatRange {
+        FloatRange::new(
+            self.saturation_min,
+            self.saturation_max,
+            self.saturation_steps,
+        )
+    }
+}
+
+/// The state of a model: its parameters, the measured currents and the
+/// result of the last solve.
+///
+/// The structure is allocated by the caller, e.g. as a static variable, so
+/// that the library does not need an allocator; its fields must only be
+/// accessed through the functions of the C interface.
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BioristorModel {
+    /// The measured currents.
+    currents: Currents,
+
+    /// The loss of the last solution.
+    loss: f32,
+
+    /// The parameters of the mathematical model.
+    params: ModelParams,
+
+    /// The last solution.
+    result: Variables,
+
+    /// The status of the last solve.
+    status: BioristorStatus,
+}
+
+/// Initializes a model.
+///
+/// # Arguments
+///
+/// * `model` - The model to be initialized.
+/// * `params` - The parameters of the mathematical model.
+/// * `currents` - The measured currents.
+///
+/// # Returns
+///
+/// * `BioristorStatus::Ok` - If the model has been initialized.
+/// * `BioristorStatus::NullPointer` - If any pointer is null.
+///
+/// # Safety
+///
+/// The pointers must be either null or valid and properly aligned.
+#[no_mangle]
+pub unsafe extern "C" fn bioristor_model_init(
+    model: *mut BioristorModel,
+    params: *const ModelParams,
+    currents: *const Currents,
+) -> BioristorStatus {
+    if model.is_null() || params.is_null() || currents.is_null() {
+        return BioristorStatus::NullPointer;
+    }
+
+    ptr::write(
+        model,
+        BioristorModel {
+            currents: *currents,
+            loss: f32::NAN,
+            params: (*params).clone(),
+            result: Variables {
+                concentration: f32::NAN,
+                resistance: f32::NAN,
+                saturation: f32::NAN,
+            },
+            status: BioristorStatus::NotSolved,
+        },
+    );
+    BioristorStatus::Ok
+}
+
+/// Updates the currents of a model after a new measurement, discarding the
+/// last solution.
+///
+/// # Arguments
+///
+/// * `model` - The initialized model.
+/// * `currents` - The measured currents.
+///
+/// # Returns
+///
+/// * `BioristorStatus::Ok` - If the currents have been updated.
+/// * `BioristorStatus::NullPointer` - If any pointer is null.
+///
+/// # Safety
+///
+/// The pointers must be either null or valid and properly aligned, and the
+/// model must have been initialized with [`bioristor_model_init`].
+#[no_mangle]
+pub unsafe extern "C" fn bioristor_model_set_currents(
+    model: *mut BioristorModel,
+    currents: *const Currents,
+) -> BioristorStatus {
+    let (Some(model), Some(currents)) = (model.as_mut(), currents.as_ref()) else {
+        return BioristorStatus::NullPointer;
+    };
+
+    model.currents = *currents;
+    model.status = BioristorStatus::NotSolved;
+    BioristorStatus::Ok
+}
+
+/// Fills the parameters of the algorithms with the ones used by the example
+/// firmware, as a starting point for the configuration.
+///
+/// # Arguments
+///
+/// * `params` - The parameters to be filled.
+///
+/// # Returns
+///
+/// * `BioristorStatus::Ok` - If the parameters have been filled.
+/// * `BioristorStatus::NullPointer` - If the pointer is null.
+///
+/// # Safety
+///
+/// The pointer must be either null or valid and properly aligned.
+#[no_mangle]
+pub unsafe extern "C" fn bioristor_solver_params_default(
+    params: *mut BioristorSolverParams,
+) -> BioristorStatus {
+    if params.is_null() {
+        return BioristorStatus::NullPointer;
+    }
+
+    ptr::write(params, BioristorSolverParams::default());
+    BioristorStatus::Ok
+}
+
+/// Solves a model and stores the solution in it, to be read with
+/// [`bioristor_result`].
+///
+/// # Arguments
+///
+/// * `model` - The initialized model.
+/// * `algorithm` - The algorithm, one of the [`BioristorAlgorithm`] values.
+/// * `params` - The parameters of the algorithm, or null for the defaults of
+///   [`bioristor_solver_params_default`].
+///
+/// # Returns
+///
+/// * `BioristorStatus::Ok` - If a solution has been found.
+/// * `BioristorStatus::NoSolution` - If the algorithm did not find a solution.
+/// * `BioristorStatus::InvalidAlgorithm` - If the algorithm is not valid.
+/// * `BioristorStatus::NullPointer` - If the model is null.
+///
+/// # Safety
+///
+/// The pointers must be either null or valid and properly aligned, and the
+/// model must have been initialized with [`bioristor_model_init`].
+#[no_mangle]
+pub unsafe extern "C" fn bioristor_solve(
+    model: *mut BioristorModel,
+    algorithm: u32,
+    params: *const BioristorSolverParams,
+) -> BioristorStatus {
+    let Some(model) = model.as_mut() else {
+        return BioristorStatus::NullPointer;
+    };
+    let Some(algorithm) = BioristorAlgorithm::from_raw(algorithm) else {
+        return BioristorStatus::InvalidAlgorithm;
+    };
+    let params = params.as_ref().copied().unwrap_or_default();
+
+    let equation = Equation::new(model.params.clone(), model.currents);
+    let solution = match algorithm {
+        BioristorAlgorithm::Adaptive => solve::<AdaptiveEquation<Equation, Absolute, MINIMA>, _>(
+            AdaptiveParams {
+                concentration_init: params.concentration_init,
+                concentration_steps: params.concentration_steps,
+                max_iterations: params.max_iterations,
+                saturation_policy: SaturationPolicy::Keep,
+                saturation_range: params.saturation_range(),
+                resistance_range: params.resistance_range(),
+            },
+            equation,
+        ),
+        BioristorAlgorithm::Adaptive2 => solve::<Adaptive2Equation<Equation, Absolute, MINIMA>, _>(
+            Adaptive2Params {
+                concentration_range: params.concentration_range(),
+                max_iterations: params.max_iterations,
+                min_range_width: params.min_range_width,
+                reduction_factor: params.reduction_factor,
+                resistance_range: params.resistance_range(),
+                saturation_policy: SaturationPolicy::Keep,
+                saturation_range: params.saturation_range(),
+                tolerance: params.tolerance,
+            },
+            equation,
+        ),
+        BioristorAlgorithm::BruteForce => solve::<BruteForceEquation<Equation, Absolute>, _>(
+            BruteForceParams {
+                concentration_range: params.concentration_range(),
+                resistance_range: params.resistance_range(),
+                saturation_policy: SaturationPolicy::Keep,
+                saturation_range: params.saturation_range(),
+            },
+            equation,
+        ),
+        BioristorAlgorithm::GradientDescent => {
+            solve::<GradientDescentEquation<Equation, Absolute>, _>(
+                GradientDescentParams {
+                    concentration_init: params.concentration_init,
+                    grad_tolerance: params.grad_tolerance,
+                    learning_rate_init: params.learning_rate_init,
+                    max_iterations: params.max_iterations,
+                    saturation_policy: SaturationPolicy::Keep,
+                    tolerance: params.tolerance,
+                },
+                equation,
+            )
+        }
+        BioristorAlgorithm::Newton => solve::<NewtonEquation<Equation, Absolute>, _>(
+            NewtonParams {
+                concentration_init: params.concentration_init,
+                grad_tolerance: params.grad_tolerance,
+                max_iterations: params.max_iterations,
+                saturation_policy: SaturationPolicy::Keep,
+                tolerance: params.tolerance,
+            },
+            equation,
+        ),
+        BioristorAlgorithm::NeuralNetwork => {
+            solve::<NeuralNetworkEquation<Equation, Absolute, 0>, _>(
+                NeuralNetworkParams::default(),
+                equation,
+            )
+        }
+    };
+
+    model.status = match solution {
+        Some((result, loss)) => {
+            model.loss = loss;
+            model.result = result;
+            BioristorStatus::Ok
+        }
+        None => BioristorStatus::NoSolution,
+    };
+    model.status
+}
+
+/// Reads the solution found by the last call to [`bioristor_solve`].
+///
+/// # Arguments
+///
+/// * `model` - The initialized model.
+/// * `result` - The variables of the solution.
+/// * `loss` - The loss of the solution, or null if not needed.
+///
+/// # Returns
+///
+/// * `BioristorStatus::Ok` - If the solution has been read.
+/// * `BioristorStatus::NullPointer` - If the model or the result are null.
+/// * Otherwise, the status of the last solve, see [`bioristor_error`].
+///
+/// # Safety
+///
+/// The pointers must be either null or valid and properly aligned, and the
+/// model must have been initialized with [`bioristor_model_init`].
+#[no_mangle]
+pub unsafe extern "C" fn bioristor_result(
+    model: *const BioristorModel,
+    result: *mut Variables,
+    loss: *mut f32,
+) -> BioristorStatus {
+    let Some(model) = model.as_ref() else {
+        return BioristorStatus::NullPointer;
+    };
+    if result.is_null() {
+        return BioristorStatus::NullPointer;
+    }
+    if model.status != BioristorStatus::Ok {
+        return model.status;
+    }
+
+    ptr::write(result, model.result);
+    if !loss.is_null() {
+        ptr::write(loss, model.loss);
+    }
+    BioristorStatus::Ok
+}
+
+/// Returns the status of the last call to [`bioristor_solve`].
+///
+/// # Arguments
+///
+/// * `model` - The initialized model.
+///
+/// # Returns
+///
+/// The status of the last solve, `BioristorStatus::NotSolved` if the model
+/// has not been solved yet, or `BioristorStatus::NullPointer` if the model is
+/// null.
+///
+/// # Safety
+///
+/// The pointer must be either null or valid and properly aligned, and the
+/// model must have been initialized with [`bioristor_model_init`].
+#[no_mangle]
+pub unsafe extern "C" fn bioristor_error(model: *const BioristorModel) -> BioristorStatus {
+    match model.as_ref() {
+        Some(model) => model.status,
+        None => BioristorStatus::NullPointer,
+    }
+}
+
+/// Runs an algorithm on a model.
+fn solve<A: Algorithm<P, Equation>, P>(params: P, model: Equation) -> Option<(Variables, f32)> {
+    A::new(params, model).run()
+}
+
+/// Panic handler of the static library, which has no other crate to provide
+/// one: the panic is forwarded to the `bioristor_panic` function, that must
+/// be defined by the C firmware, e.g. to log the fault and reset the device.
+///
+/// The handler is enabled by the `ffi-panic-handler` feature, which must not
+/// be used when the library is linked in a Rust application.
+#[cfg(all(feature = "ffi-panic-handler", not(feature = "std"), not(test)))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    extern "C" {
+        fn bioristor_panic() -> !;
+    }
+    unsafe { bioristor_panic() }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::MaybeUninit;
+
+    use super::*;
+    use crate::params::{ModulationParams, StemResistanceInvParams, Voltages};
+
+    const PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    const CURRENTS: Currents = Currents {
+        i_ds_off: -0.0030365,
+        i_ds_on: -0.0026829,
+        i_gs_on: 1.169828e-6,
+    };
+
+    fn init() -> BioristorModel {
+        let mut model = MaybeUninit::uninit();
+        unsafe {
+            assert_eq!(
+                bioristor_model_init(model.as_mut_ptr(), &PARAMS, &CURRENTS),
+                BioristorStatus::Ok
+            );
+            model.assume_init()
+        }
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut model = init();
+        let mut result = MaybeUninit::uninit();
+        let mut loss = 0.0;
+        unsafe {
+            assert_eq!(bioristor_error(&model), BioristorStatus::NotSolved);
+            assert_eq!(
+                bioristor_result(&model, result.as_mut_ptr(), ptr::null_mut()),
+                BioristorStatus::NotSolved
+            );
+
+            assert_eq!(
+                bioristor_solve(
+                    &mut model,
+                    BioristorAlgorithm::Adaptive2 as u32,
+                    ptr::null()
+                ),
+                BioristorStatus::Ok
+            );
+            assert_eq!(
+                bioristor_result(&model, result.as_mut_ptr(), &mut loss),
+                BioristorStatus::Ok
+            );
+        }
+
+        let algorithm: Adaptive2Equation<Equation, Absolute, MINIMA> = Adaptive2Equation::new(
+            Adaptive2Params {
+                concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+                max_iterations: 10,
+                min_range_width: 0.0,
+                reduction_factor: 0.2,
+                resistance_range: FloatRange::new(10.0, 100.0, 100),
+                saturation_policy: SaturationPolicy::Keep,
+                saturation_range: FloatRange::new(0.0, 1.0, 100),
+                tolerance: 1e-15,
+            },
+            Equation::new(PARAMS, CURRENTS),
+        );
+        assert_eq!(
+            Some((unsafe { result.assume_init() }, loss)),
+            algorithm.run()
+        );
+
+        // New currents invalidate the solution.
+        unsafe {
+            assert_eq!(
+                bioristor_model_set_currents(&mut model, &CURRENTS),
+                BioristorStatus::Ok
+            );
+            assert_eq!(bioristor_error(&model), BioristorStatus::NotSolved);
+        }
+    }
+
+    #[test]
+    fn test_solve_errors() {
+        let mut model = init();
+        let params = BioristorSolverParams {
+            concentration_steps: 0,
+            ..Default::default()
+        };
+        unsafe {
+            assert_eq!(
+                bioristor_solve(&mut model, 42, ptr::null()),
+                BioristorStatus::InvalidAlgorithm
+            );
+            assert_eq!(
+                bioristor_solve(&mut model, BioristorAlgorithm::BruteForce as u32, &params),
+                BioristorStatus::NoSolution
+            );
+            assert_eq!(bioristor_error(&model), BioristorStatus::NoSolution);
+
+            assert_eq!(
+                bioristor_solve(ptr::null_mut(), 0, ptr::null()),
+                BioristorStatus::NullPointer
+            );
+            assert_eq!(
+                bioristor_model_init(ptr::null_mut(), &PARAMS, &CURRENTS),
+                BioristorStatus::NullPointer
+            );
+            assert_eq!(
+                bioristor_result(&model, ptr::null_mut(), ptr::null_mut()),
+                BioristorStatus::NullPointer
+            );
+        }
+    }
+
+    #[test]
+    fn test_solver_params_default() {
+        let mut params = MaybeUninit::uninit();
+        unsafe {
+            assert_eq!(
+                bioristor_solver_params_default(params.as_mut_ptr()),
+                BioristorStatus::Ok
+            );
+            assert_eq!(params.assume_init(), BioristorSolverParams::default());
+        }
+    }
+}
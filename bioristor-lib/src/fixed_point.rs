@@ -0,0 +1,674 @@
+use fixed::types::I16F16;
+
+use crate::params::{Currents, ModelParams};
+
+/// Fixed-point number with 16 integer bits and 16 fractional bits.
+pub type Q16 = I16F16;
+
+/// Number of bits of the fraction used to index the tables.
+const TABLE_BITS: u32 = 6;
+
+/// Number of intervals of the tables.
+const TABLE_SIZE: usize = 1 << TABLE_BITS;
+
+/// Natural logarithm of `1 + i / TABLE_SIZE` as the bits of a [`Q16`].
+static LN_TABLE: [i32; TABLE_SIZE + 1] = ln_table();
+
+/// Power of two `2^(i / TABLE_SIZE)` as the bits of a [`Q16`].
+static EXP2_TABLE: [i32; TABLE_SIZE + 1] = exp2_table();
+
+/// Natural logarithm of 2 as the bits of a [`Q16`].
+const LN_2: i64 = 45_426;
+
+/// Base 2 logarithm of e with 32 fractional bits.
+const LOG2_E: i64 = 6_196_328_019;
+
+/// Builds the logarithm table at compile time using the series
+/// `ln(x) = 2 * atanh((x - 1) / (x + 1))`.
+const fn ln_table() -> [i32; TABLE_SIZE + 1] {
+    let mut table = [0; TABLE_SIZE + 1];
+    let mut i = 0;
+    while i <= TABLE_SIZE {
+        let x = 1.0 + i as f64 / TABLE_SIZE as f64;
+        let z = (x - 1.0) / (x + 1.0);
+        let mut term = z;
+        let mut sum = 0.0;
+        let mut k = 1;
+        while k < 40 {
+            sum += term / k as f64;
+            term *= z * z;
+            k += 2;
+        }
+        table[i] = (2.0 * sum * 65_536.0 + 0.5) as i32;
+        i += 1;
+    }
+    table
+}
+
+/// Builds the power of two table at compile time using the series of
+/// `exp(x * ln(2))`.
+const fn exp2_table() -> [i32; TABLE_SIZE + 1] {
+    let mut table = [0; TABLE_SIZE + 1];
+    let mut i = 0;
+    while i <= TABLE_SIZE {
+        let x = i as f64 / TABLE_SIZE as f64 * core::f64::consts::LN_2;
+        let mut term = 1.0;
+        let mut sum = 0.0;
+        let mut k = 1;
+        while k < 30 {
+            sum += term;
+            term *= x / k as f64;
+            k += 1;
+        }
+        table[i] = (sum * 65_536.0 + 0.5) as i32;
+        i += 1;
+    }
+    table
+}
+
+/// Interpolates linearly a table at the given fraction.
+///
+/// # Arguments
+///
+/// * `table` - The table of the function.
+/// * `fraction` - The fraction in `[0, 1)` with 16 bits.
+///
+/// # Returns
+///
+/// The bits of the interpolated value.
+#[inline]
+fn interpolate(table: &[i32; TABLE_SIZE + 1], fraction: u32) -> i32 {
+    const WEIGHT_BITS: u32 = 16 - TABLE_BITS;
+
+    let index = (fraction >> WEIGHT_BITS) as usize;
+    let weight = (fraction & ((1 << WEIGHT_BITS) - 1)) as i32;
+    let (low, high) = (table[index], table[index + 1]);
+    low + (((high - low) * weight) >> WEIGHT_BITS)
+}
+
+/// Computes the natural logarithm in fixed point.
+///
+/// The absolute error is below `1e-4`.
+///
+/// # Arguments
+///
+/// * `x` - The argument of the logarithm.
+///
+/// # Returns
+///
+/// The natural logarithm of `x`, saturated to [`Q16::MIN`] if `x` is not
+/// positive.
+pub fn ln(x: Q16) -> Q16 {
+    if x <= Q16::ZERO {
+        return Q16::MIN;
+    }
+
+    let bits = x.to_bits() as u32;
+    let msb = 31 - bits.leading_zeros();
+    // The 16 bits following the most significant one.
+    let fraction = ((bits << (31 - msb)) >> 15) & 0xffff;
+
+    let exponent = msb as i64 - 16;
+    Q16::from_bits((exponent * LN_2) as i32 + interpolate(&LN_TABLE, fraction))
+}
+
+/// Computes the exponential function in fixed point as `2^(x * log2(e))`.
+///
+/// The relative error is below `1e-4`, plus the resolution of [`Q16`].
+///
+/// # Arguments
+///
+/// * `x` - The exponent.
+///
+/// # Returns
+///
+/// The exponential of `x`, saturated to [`Q16::MAX`].
+pub fn exp(x: Q16) -> Q16 {
+    let t = (x.to_bits() as i64 * LOG2_E) >> 32;
+    let k = t >> 16;
+    let power = interpolate(&EXP2_TABLE, (t & 0xffff) as u32);
+
+    if k >= 15 {
+        Q16::MAX
+    } else if k >= 0 {
+        Q16::from_bits(power << k)
+    } else if k > -18 {
+        Q16::from_bits(power >> -k)
+    } else {
+        Q16::ZERO
+    }
+}
+
+/// Raises a positive number to a fixed point power as `exp(n * ln(x))`.
+///
+/// # Arguments
+///
+/// * `x` - The base.
+/// * `n` - The exponent.
+///
+/// # Returns
+///
+/// The power `x^n`.
+#[inline]
+pub fn powf(x: Q16, n: Q16) -> Q16 {
+    exp(n.saturating_mul(ln(x)))
+}
+
+/// Implementation of the equation model in fixed point, to solve the model
+/// on microcontrollers without a floating point unit.
+///
+/// The quantities are scaled so that they are representable in [`Q16`]: the
+/// concentration is in millimolar [mM], the currents and the output value of
+/// the model are in microampere [uA] and the inverse of the stem resistance
+/// is in microsiemens [uS].
+///
+/// The coefficients are computed in floating point once, when the model is
+/// created, while every evaluation only uses integer arithmetic.
+#[derive(Debug, Clone)]
+pub struct FixedEquation {
+    /// Coefficients of the error function `i_gs_on + r * (a / m + b)`:
+    /// the gate-source current, `a` and `b`.
+    func_coeffs: (Q16, Q16, Q16),
+
+    /// Coefficients of the modulation `m0 * c + m1 * ln(c) + m2` for the
+    /// concentration in millimolar.
+    mod_coeffs: (Q16, Q16, Q16),
+
+    /// Coefficients of the inverse of the stem resistance `r0 + r1 * c^0.955`
+    /// for the concentration in millimolar.
+    res_coeffs: (Q16, Q16),
+
+    /// Coefficients of the resistance `r_dry * (m + 1) / (1 + g * m)`:
+    /// `r_dry` and `g`.
+    resistance_coeffs: (Q16, Q16),
+
+    /// Coefficients of the saturation `s0 / m + s1`.
+    saturation_coeffs: (Q16, Q16),
+}
+
+impl FixedEquation {
+    /// Exponent of the concentration in the inverse of the stem resistance.
+    const RES_EXPONENT: Q16 = Q16::lit("0.955");
+
+    /// Creates a new instance of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the mathematical model.
+    /// * `currents` - The output currents of the devices.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(model)` - The new instance of the model.
+    /// * `None` - If a coefficient is not finite or not representable in
+    ///   [`Q16`].
+    pub fn new(params: &ModelParams, currents: &Currents) -> Option<Self> {
+        let q = Q16::checked_from_num::<f32>;
+        let v = &params.voltages;
+
+        let k = v.v_ds * (currents.i_ds_off - currents.i_ds_on + currents.i_gs_on);
+        let l = currents.i_ds_off * (v.v_ds - params.r_dry * (currents.i_ds_on - currents.i_gs_on));
+        let d = currents.i_ds_off * params.r_dry * (currents.i_ds_on - currents.i_gs_on);
+
+        // ln(c) = ln(1000 * c) - ln(1000) and c^0.955 = (1000 * c)^0.955 / 1000^0.955,
+        // with the inverse of the stem resistance in microsiemens.
+        let ln_scale = 6.907_755;
+        let res_scale = 1_364.583_1;
+
+        Some(Self {
+            func_coeffs: (
+                q(currents.i_gs_on * 1e6)?,
+                q(v.v_gs * k / d)?,
+                q(v.v_gs * l / d)?,
+            ),
+            mod_coeffs: (
+                q(params.mod_params.0 * 1e-3)?,
+                q(params.mod_params.1)?,
+                q(params.mod_params.2 - params.mod_params.1 * ln_scale)?,
+            ),
+            res_coeffs: (
+                q(params.res_params.0 * 1e6)?,
+                q(params.res_params.1 * res_scale)?,
+            ),
+            resistance_coeffs: (q(params.r_dry)?, q(l / k)?),
+            saturation_coeffs: (q(-k / d)?, q(-l / d)?),
+        })
+    }
+
+    /// Calculates the modulation of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentration` - The concentration of ions in the electrolyte [mM].
+    ///
+    /// # Returns
+    ///
+    /// The modulation of the channel [dimensionless].
+    pub fn modulation(&self, concentration: Q16) -> Q16 {
+        let (m0, m1, m2) = self.mod_coeffs;
+        m0.saturating_mul(concentration)
+            .saturating_add(m1.saturating_mul(ln(concentration)))
+            .saturating_add(m2)
+    }
+
+    /// Calculates the inverse of the stem resistance.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentration` - The concentration of ions in the electrolyte [mM].
+    ///
+    /// # Returns
+    ///
+    /// The inverse of the stem resistance [uS].
+    pub fn stem_resistance_inv(&self, concentration: Q16) -> Q16 {
+        let (r0, r1) = self.res_coeffs;
+        r0.saturating_add(r1.saturating_mul(powf(concentration, Self::RES_EXPONENT)))
+    }
+
+    /// Calculates the output value of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentration` - The concentration of ions in the electrolyte [mM].
+    ///
+    /// # Returns
+    ///
+    /// The output value of the model [uA].
+    pub fn value(&self, concentration: Q16) -> Q16 {
+        let (i_gs_on, a, b) = self.func_coeffs;
+        let m = self.modulation(concentration);
+        let r = self.stem_resistance_inv(concentration);
+        if m == Q16::ZERO {
+            return Q16::MAX;
+        }
+
+        i_gs_on.saturating_add(r.saturating_mul(a.saturating_div(m).saturating_add(b)))
+    }
+
+    /// Calculates the resistance given the concentration.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentration` - The concentration of ions in the electrolyte [mM].
+    ///
+    /// # Returns
+    ///
+    /// The eletrical resistance of the wet PEDOT channel [Ohm].
+    pub fn resistance(&self, concentration: Q16) -> Q16 {
+        let (r_dry, g) = self.resistance_coeffs;
+        let m = self.modulation(concentration);
+        let denominator = Q16::ONE.saturating_add(g.saturating_mul(m));
+        if denominator == Q16::ZERO {
+            return Q16::MAX;
+        }
+
+        r_dry
+            .saturating_mul(m.saturating_add(Q16::ONE))
+            .saturating_div(denominator)
+    }
+
+    /// Calculates the water saturation given the concentration.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentration` - The concentration of ions in the electrolyte [mM].
+    ///
+    /// # Returns
+    ///
+    /// The saturation of the water [dimensionless].
+    pub fn saturation(&self, concentration: Q16) -> Q16 {
+        let (s0, s1) = self.saturation_coeffs;
+        let m = self.modulation(concentration);
+        if m == Q16::ZERO {
+            return Q16::MAX;
+        }
+
+        s0.saturating_div(m).saturating_add(s1)
+    }
+}
+
+/// The loss function used to evaluate the fixed-point model.
+pub trait FixedLoss {
+    /// Evaluates the loss of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The output value of the model.
+    ///
+    /// # Returns
+    ///
+    /// The loss of the model.
+    fn evaluate(value: Q16) -> Q16;
+}
+
+/// Fixed-point version of the [`Absolute`](crate::losses::Absolute) loss.
+pub struct FixedAbsolute;
+
+impl FixedLoss for FixedAbsolute {
+    #[inline]
+    fn evaluate(value: Q16) -> Q16 {
+        value.saturating_abs()
+    }
+}
+
+/// A solution of the fixed-point model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedSolution {
+    /// The concentration of ions in the electrolyte [mM].
+    pub concentration: Q16,
+
+    /// The loss of the solution.
+    pub loss: Q16,
+
+    /// The resistance of the wet PEDOT channel [Ohm].
+    pub resistance: Q16,
+
+    /// The saturation of the water [dimensionless].
+    pub saturation: Q16,
+}
+
+impl FixedSolution {
+    /// Creates the solution for the given concentration.
+    fn new<L: FixedLoss>(model: &FixedEquation, concentration: Q16) -> Self {
+        Self {
+            concentration,
+            loss: L::evaluate(model.value(concentration)),
+            resistance: model.resistance(concentration),
+            saturation: model.saturation(concentration),
+        }
+    }
+}
+
+/// The parameters of the fixed-point brute force algorithm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedBruteForceParams {
+    /// The lower bound of the concentrations to search (inclusive) [mM].
+    pub concentration_start: Q16,
+
+    /// The upper bound of the concentrations to search (exclusive) [mM].
+    pub concentration_end: Q16,
+
+    /// The number of steps in which the range of concentrations is divided.
+    pub concentration_steps: u16,
+}
+
+/// Implementation of the brute force algorithm for the fixed-point model.
+///
+/// # Type parameters
+///
+/// * `L` - The loss function to be used.
+pub struct FixedBruteForce<L: FixedLoss> {
+    /// The parameters of the algorithm.
+    params: FixedBruteForceParams,
+
+    /// The model to be solved.
+    model: FixedEquation,
+
+    _t: core::marker::PhantomData<L>,
+}
+
+impl<L: FixedLoss> FixedBruteForce<L> {
+    /// Create a new instance of the brute force algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the algorithm.
+    /// * `model` - The model to be solved by the algorithm.
+    pub fn new(params: FixedBruteForceParams, model: FixedEquation) -> Self {
+        Self {
+            params,
+            model,
+            _t: core::marker::PhantomData,
+        }
+    }
+
+    /// Evaluates the model on the grid of concentrations and returns the one
+    /// with the lowest loss.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(solution)` - The best solution found.
+    /// * `None` - If the range of concentrations is empty.
+    pub fn run(&self) -> Option<FixedSolution> {
+        let steps = self.params.concentration_steps;
+        if steps == 0 {
+            return None;
+        }
+        let increment = (self.params.concentration_end - self.params.concentration_start)
+            / Q16::from_num(steps);
+
+        let mut best: Option<(Q16, Q16)> = None;
+        let mut concentration = self.params.concentration_start;
+        for _ in 0..steps {
+            let loss = L::evaluate(self.model.value(concentration));
+            if best.is_none_or(|(_, best_loss)| loss < best_loss) {
+                best = Some((concentration, loss));
+            }
+            concentration = concentration.saturating_add(increment);
+        }
+
+        best.map(|(concentration, _)| FixedSolution::new::<L>(&self.model, concentration))
+    }
+}
+
+/// The parameters of the fixed-point bisection algorithm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedBisectionParams {
+    /// The lower bound of the bracket of concentrations [mM].
+    pub concentration_start: Q16,
+
+    /// The upper bound of the bracket of concentrations [mM].
+    pub concentration_end: Q16,
+
+    /// The maximum number of iterations.
+    pub max_iterations: usize,
+
+    /// The width of the bracket at which the algorithm stops [mM].
+    pub tolerance: Q16,
+}
+
+/// Implementation of the bisection algorithm for the fixed-point model,
+/// which halves a bracket of concentrations in which the output value of the
+/// model changes sign.
+///
+/// # Type parameters
+///
+/// * `L` - The loss function used to evaluate the solution.
+pub struct FixedBisection<L: FixedLoss> {
+    /// The parameters of the algorithm.
+    params: FixedBisectionParams,
+
+    /// The model to be solved.
+    model: FixedEquation,
+
+    _t: core::marker::PhantomData<L>,
+}
+
+impl<L: FixedLoss> FixedBisection<L> {
+    /// Create a new instance of the bisection algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the algorithm.
+    /// * `model` - The model to be solved by the algorithm.
+    pub fn new(params: FixedBisectionParams, model: FixedEquation) -> Self {
+        Self {
+            params,
+            model,
+            _t: core::marker::PhantomData,
+        }
+    }
+
+    /// Halves the bracket until its width is below the tolerance or the
+    /// maximum number of iterations is reached.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(solution)` - The middle of the last bracket.
+    /// * `None` - If the output value of the model has the same sign at the
+    ///   bounds of the initial bracket.
+    pub fn run(&self) -> Option<FixedSolution> {
+        let mut low = self.params.concentration_start;
+        let mut high = self.params.concentration_end;
+        let low_negative = self.model.value(low).is_negative();
+        if low_negative == self.model.value(high).is_negative() {
+            return None;
+        }
+
+        let mut iteration = 0;
+        while iteration < self.params.max_iterations && high - low > self.params.tolerance {
+            let middle = low.mean(high);
+            if self.model.value(middle).is_negative() == low_negative {
+                low = middle;
+            } else {
+                high = middle;
+            }
+            iteration += 1;
+        }
+
+        Some(FixedSolution::new::<L>(&self.model, low.mean(high)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{Equation, EquationModel, Model},
+        params::{ModulationParams, StemResistanceInvParams, Voltages},
+    };
+
+    const PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    const CURRENTS: Currents = Currents {
+        i_ds_off: -0.003_036_5,
+        i_ds_on: -0.002_682_9,
+        i_gs_on: 1.169_828e-6,
+    };
+
+    /// Solution of the model computed with the accurate mathematical
+    /// functions [mM].
+    const SOLUTION: f32 = 5.171_65;
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(LN_TABLE[0], 0);
+        assert_eq!(LN_TABLE[TABLE_SIZE] as i64, LN_2);
+        assert_eq!(EXP2_TABLE[0], 1 << 16);
+        assert_eq!(EXP2_TABLE[TABLE_SIZE], 2 << 16);
+    }
+
+    #[test]
+    fn test_ln() {
+        let mut x = 1e-3f32;
+        while x < 3e4 {
+            let value: f32 = ln(Q16::from_num(x)).to_num();
+            let expected = Q16::from_num(x).to_num::<f32>().ln();
+            assert!((value - expected).abs() < 1e-4, "ln({x})");
+            x *= 1.1;
+        }
+        assert_eq!(ln(Q16::ZERO), Q16::MIN);
+        assert_eq!(ln(Q16::NEG_ONE), Q16::MIN);
+    }
+
+    #[test]
+    fn test_exp() {
+        let mut x = -10.0f32;
+        while x < 10.0 {
+            let value: f32 = exp(Q16::from_num(x)).to_num();
+            let expected = Q16::from_num(x).to_num::<f32>().exp();
+            assert!(
+                (value - expected).abs() < 1e-4 * expected + 2e-5,
+                "exp({x})"
+            );
+            x += 0.07;
+        }
+        assert_eq!(exp(Q16::from_num(11)), Q16::MAX);
+        assert_eq!(exp(Q16::from_num(-13)), Q16::ZERO);
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "deterministic",
+        ignore = "expected values of the accurate mathematical functions"
+    )]
+    fn test_model() {
+        let model = FixedEquation::new(&PARAMS, &CURRENTS).unwrap();
+        let reference = Equation::new(PARAMS, CURRENTS);
+
+        for concentration in [1e-3f32, 5e-3, 1e-2, 5e-2] {
+            let c = Q16::from_num(concentration * 1e3);
+            let value: f32 = model.value(c).to_num();
+            let resistance: f32 = model.resistance(c).to_num();
+            let saturation: f32 = model.saturation(c).to_num();
+
+            assert!((value - reference.value(concentration) * 1e6).abs() < 5e-3);
+            assert!((resistance - reference.resistance(concentration)).abs() < 1e-2);
+            assert!((saturation - reference.saturation(concentration)).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_new_not_representable() {
+        let currents = Currents {
+            i_ds_off: 0.0,
+            ..CURRENTS
+        };
+        assert!(FixedEquation::new(&PARAMS, &currents).is_none());
+    }
+
+    #[test]
+    fn test_brute_force() {
+        let params = FixedBruteForceParams {
+            concentration_start: Q16::from_num(0.1),
+            concentration_end: Q16::from_num(100),
+            concentration_steps: 1000,
+        };
+        let model = FixedEquation::new(&PARAMS, &CURRENTS).unwrap();
+        let solution = FixedBruteForce::<FixedAbsolute>::new(params, model)
+            .run()
+            .unwrap();
+
+        let concentration: f32 = solution.concentration.to_num();
+        assert!((concentration - SOLUTION).abs() < 0.1);
+        assert!(solution.loss < Q16::from_num(0.01));
+    }
+
+    #[test]
+    fn test_bisection() {
+        let params = FixedBisectionParams {
+            concentration_start: Q16::from_num(0.1),
+            concentration_end: Q16::from_num(100),
+            max_iterations: 100,
+            tolerance: Q16::from_num(1e-3),
+        };
+        let model = FixedEquation::new(&PARAMS, &CURRENTS).unwrap();
+        let solution = FixedBisection::<FixedAbsolute>::new(params.clone(), model.clone())
+            .run()
+            .unwrap();
+
+        let concentration: f32 = solution.concentration.to_num();
+        assert!((concentration - SOLUTION).abs() < 1e-2);
+        assert!(solution.loss < Q16::from_num(1e-3));
+        assert_eq!(
+            solution.resistance,
+            model.resistance(solution.concentration)
+        );
+
+        let params = FixedBisectionParams {
+            concentration_start: Q16::from_num(50),
+            ..params
+        };
+        assert!(FixedBisection::<FixedAbsolute>::new(params, model)
+            .run()
+            .is_none());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::params::{
+    Currents, ModelParams, ModulationParams, StemResistanceInvParams, Variables, Voltages,
+};
+
+/// The base of the UUIDs of the Bioristor service, with the 16-bit short
+/// identifier in bits 96 to 111.
+const BASE_UUID: u128 = 0x6b3e_0000_5d2f_4c1a_9a38_7f2c_1b0e_4a10;
+
+/// Returns the 128-bit UUID with the given short identifier.
+const fn uuid(short: u16) -> u128 {
+    BASE_UUID | (short as u128) << 96
+}
+
+/// The UUID of the Bioristor GATT service.
+pub const SERVICE_UUID: u128 = uuid(0x0001);
+
+/// The maximum size of the value of a characteristic.
+///
+/// The parameters of the model exceed the 20 bytes allowed by the default
+/// ATT MTU, hence they require a larger MTU or long reads and writes.
+pub const MAX_VALUE_SIZE: usize = 32;
+
+/// The errors of the serialization of the characteristics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GattError {
+    /// The value has not the size of the characteristic.
+    InvalidLength,
+
+    /// The value contains a field that is not a finite number.
+    InvalidValue,
+}
+
+/// A characteristic of the Bioristor GATT service.
+///
+/// Every characteristic has a fixed size and is serialized as a sequence of
+/// `f32` in little endian, in the order of the fields documented by every
+/// implementation, so that all the ports expose identical characteristics.
+pub trait Characteristic: Sized {
+    /// The UUID of the characteristic.
+    const UUID: u128;
+
+    /// The size of the value of the characteristic.
+    const SIZE: usize;
+
+    /// Whether the characteristic can be written by the client.
+    const WRITABLE: bool;
+
+    /// Serializes the value of the characteristic.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The buffer where the value is written.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(value)` - The serialized value, a prefix of `buf`.
+    /// * `Err(error)` - If the buffer is shorter than [`Self::SIZE`].
+    fn serialize<'a>(&self, buf: &'a mut [u8]) -> Result<&'a [u8], GattError>;
+
+    /// Deserializes the value of the characteristic.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The serialized value.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(value)` - The deserialized value.
+    /// * `Err(error)` - If the value is not valid.
+    fn deserialize(value: &[u8]) -> Result<Self, GattError>;
+}
+
+/// The latest solution: `concentration`, `resistance`, `saturation`.
+impl Characteristic for Variables {
+    const UUID: u128 = uuid(0x0002);
+    const SIZE: usize = 12;
+    const WRITABLE: bool = false;
+
+    fn serialize<'a>(&self, buf: &'a mut [u8]) -> Result<&'a [u8], GattError> {
+        write_floats(buf, &[self.concentration, self.resistance, self.saturation])
+    }
+
+    fn deserialize(value: &[u8]) -> Result<Self, GattError> {
+        let [concentration, resistance, saturation] = read_floats(value)?;
+        Ok(Self {
+            concentration,
+            resistance,
+            saturation,
+        })
+    }
+}
+
+/// The raw currents of the latest measurement: `i_ds_off`, `i_ds_on`,
+/// `i_gs_on`.
+impl Characteristic for Currents {
+    const UUID: u128 = uuid(0x0003);
+    const SIZE: usize = 12;
+    const WRITABLE: bool = false;
+
+    fn serialize<'a>(&self, buf: &'a mut [u8]) -> Result<&'a [u8], GattError> {
+        write_floats(buf, &[self.i_ds_off, self.i_ds_on, self.i_gs_on])
+    }
+
+    fn deserialize(value: &[u8]) -> Result<Self, GattError> {
+        let [i_ds_off, i_ds_on, i_gs_on] = read_floats(value)?;
+        Ok(Self {
+            i_ds_off,
+            i_ds_on,
+            i_gs_on,
+        })
+    }
+}
+
+/// The parameters of the model: the three `mod_params`, `r_dry`, the two
+/// `res_params`, `v_ds` and `v_gs`.
+impl Characteristic for ModelParams {
+    const UUID: u128 = uuid(0x0004);
+    const SIZE: usize = 32;
+    const WRITABLE: bool = true;
+
+    fn serialize<'a>(&self, buf: &'a mut [u8]) -> Result<&'a [u8], GattError> {
+        write_floats(
+            buf,
+            &[
+                self.mod_params.0,
+                self.mod_params.1,
+                self.mod_params.2,
+                self.r_dry,
+                self.res_params.0,
+                self.res_params.1,
+                self.voltages.v_ds,
+                self.voltages.v_gs,
+            ],
+        )
+    }
+
+    fn deserialize(value: &[u8]) -> Result<Self, GattError> {
+        let [a, b, c, r_dry, k0, k1, v_ds, v_gs] = read_floats(value)?;
+        Ok(Self {
+            mod_params: ModulationParams(a, b, c),
+            r_dry,
+            res_params: StemResistanceInvParams(k0, k1),
+            voltages: Voltages { v_ds, v_gs },
+        })
+    }
+}
+
+/// Writes a sequence of `f32` in little endian.
+fn write_floats<'a>(buf: &'a mut [u8], values: &[f32]) -> Result<&'a [u8], GattError> {
+    let buf = buf
+        .get_mut(..values.len() * 4)
+        .ok_or(GattError::InvalidLength)?;
+    for (chunk, value) in buf.chunks_exact_mut(4).zip(values) {
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+    Ok(buf)
+}
+
+/// Reads a sequence of finite `f32` in little endian.
+fn read_floats<const N: usize>(value: &[u8]) -> Result<[f32; N], GattError> {
+    if value.len() != N * 4 {
+        return Err(GattError::InvalidLength);
+    }
+
+    let mut values = [0.0; N];
+    for (value, chunk) in values.iter_mut().zip(value.chunks_exact(4)) {
+        *value = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        if !value.is_finite() {
+            return Err(GattError::InvalidValue);
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    fn round_trip<C: Characteristic + PartialEq + core::fmt::Debug>(value: C) {
+        let mut buf = [0; MAX_VALUE_SIZE];
+        let serialized = value.serialize(&mut buf).unwrap();
+        assert_eq!(serialized.len(), C::SIZE);
+        assert_eq!(C::deserialize(serialized), Ok(value));
+    }
+
+    #[test]
+    fn test_uuids() {
+        assert_eq!(SERVICE_UUID, 0x6b3e_0001_5d2f_4c1a_9a38_7f2c_1b0e_4a10);
+        assert_ne!(Variables::UUID, Currents::UUID);
+        assert_ne!(Currents::UUID, ModelParams::UUID);
+    }
+
+    #[test]
+    fn test_characteristics() {
+        round_trip(Variables {
+            concentration: 2.5e-3,
+            resistance: 42.0,
+            saturation: 0.5,
+        });
+        round_trip(Currents {
+            i_ds_off: -0.0030365,
+            i_ds_on: -0.0026829,
+            i_gs_on: 1.169828e-6,
+        });
+        round_trip(PARAMS);
+
+        let mut buf = [0; MAX_VALUE_SIZE];
+        let value = Variables {
+            concentration: 1.0,
+            resistance: 0.0,
+            saturation: 0.0,
+        };
+        assert_eq!(
+            value.serialize(&mut buf).unwrap(),
+            &[0, 0, 0x80, 0x3F, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut buf = [0; 8];
+        assert_eq!(PARAMS.serialize(&mut buf), Err(GattError::InvalidLength));
+        assert_eq!(
+            ModelParams::deserialize(&[0; 28]),
+            Err(GattError::InvalidLength)
+        );
+
+        let mut value = [0; 32];
+        value[12..16].copy_from_slice(&f32::NAN.to_le_bytes());
+        assert_eq!(
+            ModelParams::deserialize(&value),
+            Err(GattError::InvalidValue)
+        );
+    }
+}
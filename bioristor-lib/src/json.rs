@@ -0,0 +1,574 @@
+use core::fmt::{self, Write};
+
+use crate::algorithms::{Diagnostics, SolveReport};
+use crate::params::{Currents, Variables};
+
+/// The error returned when the buffer is too small for the JSON document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BufferTooSmall;
+
+/// Writer of a JSON document into a caller-provided buffer, without
+/// allocations.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::json::JsonWriter;
+///
+/// let mut buf = [0; 64];
+/// let mut writer = JsonWriter::new(&mut buf);
+/// writer.begin_object().unwrap();
+/// writer.field_str("status", "ok").unwrap();
+/// writer.field_f32("loss", 0.5).unwrap();
+/// writer.end_object().unwrap();
+/// assert_eq!(writer.finish(), r#"{"status":"ok","loss":0.5}"#);
+/// ```
+pub struct JsonWriter<'a> {
+    /// The buffer where the document is written.
+    buf: &'a mut [u8],
+
+    /// Whether the next value is the first of its object.
+    first: bool,
+
+    /// The length of the document written so far.
+    len: usize,
+}
+
+impl<'a> JsonWriter<'a> {
+    /// Creates a new writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The buffer where the document is written.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self {
+            buf,
+            first: true,
+            len: 0,
+        }
+    }
+
+    /// Returns the document written so far.
+    pub fn finish(self) -> &'a str {
+        // Only strings and formatted numbers are written, always valid UTF-8.
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+
+    /// Starts an object.
+    pub fn begin_object(&mut self) -> Result<(), BufferTooSmall> {
+        self.raw("{")?;
+        self.first = true;
+        Ok(())
+    }
+
+    /// Ends the current object.
+    pub fn end_object(&mut self) -> Result<(), BufferTooSmall> {
+        self.raw("}")?;
+        self.first = false;
+        Ok(())
+    }
+
+    /// Writes the name of a field of the current object, followed by the
+    /// value written by `f`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the field.
+    /// * `f` - The function writing the value.
+    pub fn field(
+        &mut self,
+        name: &str,
+        f: impl FnOnce(&mut Self) -> Result<(), BufferTooSmall>,
+    ) -> Result<(), BufferTooSmall> {
+        if !self.first {
+            self.raw(",")?;
+        }
+        self.string(name)?;
+        self.raw(":")?;
+        f(self)?;
+        self.first = false;
+        Ok(())
+    }
+
+    /// Writes a number field, or `null` if the value is not finite.
+    pub fn field_f32(&mut self, name: &str, value: f32) -> Result<(), BufferTooSmall> {
+        self.field(name, |w| w.f32(value))
+    }
+
+    /// Writes an integer field.
+    pub fn field_u64(&mut self, name: &str, value: u64) -> Result<(), BufferTooSmall> {
+        self.field(name, |w| w.format(format_args!("{value}")))
+    }
+
+    /// Writes a string field.
+    pub fn field_str(&mut self, name: &str, value: &str) -> Result<(), BufferTooSmall> {
+        self.field(name, |w| w.string(value))
+    }
+
+    /// Writes an object field, or `null` if the value is `None`.
+    pub fn field_object<T: ToJson>(
+        &mut self,
+        name: &str,
+        value: Option<&T>,
+    ) -> Result<(), BufferTooSmall> {
+        self.field(name, |w| match value {
+            Some(value) => value.write_json(w),
+            None => w.raw("null"),
+        })
+    }
+
+    /// Writes a number, or `null` if the value is not finite.
+    fn f32(&mut self, value: f32) -> Result<(), BufferTooSmall> {
+        if value.is_finite() {
+            // The debug format uses the exponent notation for very small and
+            // very large values, which keeps the document short.
+            self.format(format_args!("{value:?}"))
+        } else {
+            self.raw("null")
+        }
+    }
+
+    /// Writes an escaped string.
+    fn string(&mut self, value: &str) -> Result<(), BufferTooSmall> {
+        self.raw("\"")?;
+        for c in value.chars() {
+            match c {
+                '"' => self.raw("\\\"")?,
+                '\\' => self.raw("\\\\")?,
+                c if (c as u32) < 0x20 => self.format(format_args!("\\u{:04x}", c as u32))?,
+                c => self.raw(c.encode_utf8(&mut [0; 4]))?,
+            }
+        }
+        self.raw("\"")
+    }
+
+    /// Writes formatted text.
+    fn format(&mut self, args: fmt::Arguments) -> Result<(), BufferTooSmall> {
+        self.write_fmt(args).map_err(|_| BufferTooSmall)
+    }
+
+    /// Writes text as is.
+    fn raw(&mut self, s: &str) -> Result<(), BufferTooSmall> {
+        let end = self.len + s.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(BufferTooSmall)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl Write for JsonWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.raw(s).map_err(|_| fmt::Error)
+    }
+}
+
+/// Common interface for the values that can be written as JSON objects.
+pub trait ToJson {
+    /// Writes the value as a JSON object.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The writer of the document.
+    fn write_json(&self, writer: &mut JsonWriter) -> Result<(), BufferTooSmall>;
+
+    /// Writes the value as a JSON document.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The buffer where the document is written.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(json)` - The document, a prefix of `buf`.
+    /// * `Err(error)` - If the buffer is too small.
+    fn to_json<'a>(&self, buf: &'a mut [u8]) -> Result<&'a str, BufferTooSmall> {
+        let mut writer = JsonWriter::new(buf);
+        self.write_json(&mut writer)?;
+        Ok(writer.finish())
+    }
+}
+
+impl ToJson for Variables {
+    fn write_json(&self, writer: &mut JsonWriter) -> Result<(), BufferTooSmall> {
+        writer.begin_object()?;
+        writer.field_f32("concentration", self.concentration)?;
+        writer.field_f32("resistance", self.resistance)?;
+        writer.field_f32("saturation", self.saturation)?;
+        writer.end_object()
+    }
+}
+
+impl ToJson for Currents {
+    fn write_json(&self, writer: &mut JsonWriter) -> Result<(), BufferTooSmall> {
+        writer.begin_object()?;
+        writer.field_f32("i_ds_off", self.i_ds_off)?;
+        writer.field_f32("i_ds_on", self.i_ds_on)?;
+        writer.field_f32("i_gs_on", self.i_gs_on)?;
+        writer.end_object()
+    }
+}
+
+/// A measurement published to a dashboard, with its status.
+///
+/// The measurement is written as:
+/// ```text
+/// {"timestamp":1700000000,"status":"ok","currents":{...},"variables":{...},"loss":1e-6}
+/// ```
+/// where `variables` and `loss` are `null` if no solution has been found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Measurement<'s> {
+    /// The measured currents.
+    pub currents: Currents,
+
+    /// The result returned by the algorithm.
+    pub result: Option<(Variables, f32)>,
+
+    /// The status of the device, e.g. `"ok"`.
+    pub status: &'s str,
+
+    /// The time of the measurement, in seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl ToJson for Measurement<'_> {
+    fn write_json(&self, writer: &mut JsonWriter) -> Result<(), BufferTooSmall> {
+        writer.begin_object()?;
+        writer.field_u64("timestamp", self.timestamp)?;
+        writer.field_str("status", self.status)?;
+        writer.field_object("currents", Some(&self.currents))?;
+        writer.field_object("variables", self.result.as_ref().map(|(vars, _)| vars))?;
+        writer.field_f32("loss", self.result.map_or(f32::NAN, |(_, loss)| loss))?;
+        writer.end_object()
+    }
+}
+
+impl ToJson for Diagnostics {
+    fn write_json(&self, writer: &mut JsonWriter) -> Result<(), BufferTooSmall> {
+        writer.begin_object()?;
+        writer.field_f32("condition", self.condition.unwrap_or(f32::NAN))?;
+        writer.field_f32("gradient", self.gradient.unwrap_or(f32::NAN))?;
+        writer.field_u64("losses", self.losses as u64)?;
+        writer.field_f32("step", self.step.unwrap_or(f32::NAN))?;
+        writer.field("termination", |w| match self.termination {
+            Some(termination) => w.string(termination.as_str()),
+            None => w.raw("null"),
+        })?;
+        writer.end_object()
+    }
+}
+
+impl ToJson for SolveReport {
+    fn write_json(&self, writer: &mut JsonWriter) -> Result<(), BufferTooSmall> {
+        writer.begin_object()?;
+        writer.field_u64("iterations", self.iterations as u64)?;
+        writer.field_u64("evaluations", self.evaluations as u64)?;
+        writer.field_u64(
+            "learning_rate_fallbacks",
+            self.learning_rate_fallbacks as u64,
+        )?;
+        writer.field_u64("non_finite_candidates", self.non_finite_candidates as u64)?;
+        writer.field("non_finite_solution", |w| {
+            w.raw(if self.non_finite_solution {
+                "true"
+            } else {
+                "false"
+            })
+        })?;
+        writer.field_u64("bracketing_steps", self.bracketing_steps as u64)?;
+        writer.field("diverged", |w| {
+            w.raw(if self.diverged { "true" } else { "false" })
+        })?;
+        writer.field("init_corrected", |w| {
+            w.raw(if self.init_corrected { "true" } else { "false" })
+        })?;
+        writer.field("saturation_out_of_range", |w| {
+            w.raw(if self.saturation_out_of_range {
+                "true"
+            } else {
+                "false"
+            })
+        })?;
+        writer.field("boundary_hit", |w| {
+            w.raw(if self.boundary_hit { "true" } else { "false" })
+        })?;
+        writer.field_object("diagnostics", Some(&self.diagnostics))?;
+        #[cfg(feature = "instrument")]
+        {
+            writer.field_u64("evaluation_cycles", self.evaluation_cycles)?;
+            writer.field_u64("total_cycles", self.total_cycles)?;
+        }
+        writer.end_object()
+    }
+}
+
+/// The result of a solve, with the report of its execution, e.g. of a batch
+/// simulation.
+///
+/// The result is written as:
+/// ```text
+/// {"timestamp":0,"currents":{...},"variables":{...},"loss":1e-6,"report":{...}}
+/// ```
+/// where `variables` and `loss` are `null` if no solution has been found.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SolveResult {
+    /// The solved currents.
+    pub currents: Currents,
+
+    /// The report of the execution of the algorithm.
+    pub report: SolveReport,
+
+    /// The result returned by the algorithm.
+    pub result: Option<(Variables, f32)>,
+
+    /// The time of the measurement, or the index of the row of a dataset.
+    pub timestamp: u64,
+}
+
+impl ToJson for SolveResult {
+    fn write_json(&self, writer: &mut JsonWriter) -> Result<(), BufferTooSmall> {
+        writer.begin_object()?;
+        writer.field_u64("timestamp", self.timestamp)?;
+        writer.field_object("currents", Some(&self.currents))?;
+        writer.field_object("variables", self.result.as_ref().map(|(vars, _)| vars))?;
+        writer.field_f32("loss", self.result.map_or(f32::NAN, |(_, loss)| loss))?;
+        writer.field_object("report", Some(&self.report))?;
+        writer.end_object()
+    }
+}
+
+/// Writer of newline-delimited JSON (NDJSON), one document per line, so that
+/// the results of long simulations can be streamed to the analysis tools.
+///
+/// Available with the `std` feature.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::json::NdjsonWriter;
+/// use bioristor_lib::params::Variables;
+///
+/// let mut writer = NdjsonWriter::new(Vec::new());
+/// let vars = Variables {
+///     concentration: 0.01,
+///     resistance: 40.0,
+///     saturation: 0.5,
+/// };
+/// writer.write(&vars).unwrap();
+/// writer.write(&vars).unwrap();
+/// assert_eq!(writer.lines(), 2);
+/// let output = String::from_utf8(writer.into_inner()).unwrap();
+/// assert_eq!(output.lines().count(), 2);
+/// ```
+#[cfg(feature = "std")]
+pub struct NdjsonWriter<W: std::io::Write> {
+    /// The buffer where the documents are formatted, grown as needed.
+    buf: Vec<u8>,
+
+    /// The number of lines written.
+    lines: usize,
+
+    /// The destination of the lines.
+    writer: W,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> NdjsonWriter<W> {
+    /// The initial size of the formatting buffer, enough for a
+    /// [`SolveResult`].
+    const INITIAL_CAPACITY: usize = 512;
+
+    /// Creates a new writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The destination of the lines, e.g. a buffered file.
+    pub fn new(writer: W) -> Self {
+        Self {
+            buf: vec![0; Self::INITIAL_CAPACITY],
+            lines: 0,
+            writer,
+        }
+    }
+
+    /// Returns the number of lines written.
+    #[inline]
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// Writes a value as a line.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to be written.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the line has been written.
+    /// * `Err(error)` - If the destination failed.
+    pub fn write<T: ToJson>(&mut self, value: &T) -> std::io::Result<()> {
+        let len = loop {
+            match value.to_json(&mut self.buf) {
+                Ok(json) => break json.len(),
+                Err(BufferTooSmall) => {
+                    let len = self.buf.len() * 2;
+                    self.buf.resize(len, 0);
+                }
+            }
+        };
+        self.writer.write_all(&self.buf[..len])?;
+        self.writer.write_all(b"\n")?;
+        self.lines += 1;
+        Ok(())
+    }
+
+    /// Flushes the destination.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Releases the destination.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURRENTS: Currents = Currents {
+        i_ds_off: -0.5,
+        i_ds_on: 0.25,
+        i_gs_on: 1e-6,
+    };
+
+    #[test]
+    fn test_variables() {
+        let mut buf = [0; 128];
+        let vars = Variables {
+            concentration: 2.5e-3,
+            resistance: 42.0,
+            saturation: f32::NAN,
+        };
+        assert_eq!(
+            vars.to_json(&mut buf),
+            Ok(r#"{"concentration":0.0025,"resistance":42.0,"saturation":null}"#)
+        );
+    }
+
+    #[test]
+    fn test_measurement() {
+        let mut buf = [0; 256];
+        let measurement = Measurement {
+            currents: CURRENTS,
+            result: Some((
+                Variables {
+                    concentration: 1e-4,
+                    resistance: 10.0,
+                    saturation: 0.5,
+                },
+                1e-9,
+            )),
+            status: "ok",
+            timestamp: 1_700_000_000,
+        };
+        assert_eq!(
+            measurement.to_json(&mut buf),
+            Ok(concat!(
+                r#"{"timestamp":1700000000,"status":"ok","#,
+                r#""currents":{"i_ds_off":-0.5,"i_ds_on":0.25,"i_gs_on":1e-6},"#,
+                r#""variables":{"concentration":0.0001,"resistance":10.0,"saturation":0.5},"#,
+                r#""loss":1e-9}"#
+            ))
+        );
+
+        let measurement = Measurement {
+            result: None,
+            status: "no \"solution\"",
+            ..measurement
+        };
+        let json = measurement.to_json(&mut buf).unwrap();
+        assert!(json.ends_with(r#""status":"no \"solution\"","currents":{"i_ds_off":-0.5,"i_ds_on":0.25,"i_gs_on":1e-6},"variables":null,"loss":null}"#));
+    }
+
+    #[test]
+    fn test_solve_result() {
+        let mut buf = [0; 512];
+        let result = SolveResult {
+            currents: CURRENTS,
+            report: SolveReport {
+                evaluations: 42,
+                iterations: 3,
+                ..SolveReport::default()
+            },
+            result: None,
+            timestamp: 7,
+        };
+        let json = result.to_json(&mut buf).unwrap();
+        assert!(json.starts_with(r#"{"timestamp":7,"currents":{"#));
+        assert!(json
+            .contains(r#""variables":null,"loss":null,"report":{"iterations":3,"evaluations":42"#));
+        assert!(json.contains(
+            r#""diagnostics":{"condition":null,"gradient":null,"losses":0,"step":null,"termination":null}"#
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_ndjson_writer() {
+        let mut writer = NdjsonWriter::new(Vec::new());
+        let first = SolveResult {
+            currents: CURRENTS,
+            report: SolveReport::default(),
+            result: Some((
+                Variables {
+                    concentration: 1e-4,
+                    resistance: 10.0,
+                    saturation: 0.5,
+                },
+                1e-9,
+            )),
+            timestamp: 0,
+        };
+        let results = [
+            first.clone(),
+            SolveResult {
+                timestamp: 1,
+                ..first
+            },
+        ];
+        for result in &results {
+            writer.write(result).unwrap();
+        }
+        // A document larger than the initial buffer.
+        let status = "x".repeat(1000);
+        let measurement = Measurement {
+            currents: CURRENTS,
+            result: None,
+            status: &status,
+            timestamp: 2,
+        };
+        writer.write(&measurement).unwrap();
+        assert_eq!(writer.lines(), 3);
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        let lines: Vec<&str> = output.split_terminator('\n').collect();
+        assert_eq!(lines.len(), 3);
+        let mut buf = [0; 2048];
+        assert_eq!(lines[1], results[1].to_json(&mut buf).unwrap());
+        assert_eq!(lines[2], measurement.to_json(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn test_buffer_too_small() {
+        let mut buf = [0; 16];
+        assert_eq!(CURRENTS.to_json(&mut buf), Err(BufferTooSmall));
+    }
+}
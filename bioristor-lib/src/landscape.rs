@@ -0,0 +1,222 @@
+use crate::{losses::Loss, models::EquationModel, utils::FloatRange};
+#[cfg(feature = "system")]
+use crate::{models::SystemModel, params::Variables};
+
+/// Number of concentrations evaluated together by [`export_landscape`], see
+/// [`EquationModel::value_batch`].
+const BATCH: usize = 8;
+
+/// Samples the loss of the equation model over a range of concentrations,
+/// so that host tools can plot why a measurement failed to converge.
+///
+/// # Arguments
+///
+/// * `model` - The model, with the currents of the measurement.
+/// * `range` - The range of concentrations.
+/// * `buffer` - The buffer receiving the losses, in the order of the range.
+///
+/// # Returns
+///
+/// The number of losses written, the minimum between the steps of the range
+/// and the length of the buffer.
+///
+/// # Type parameters
+///
+/// * `M` - The type of the model.
+/// * `L` - The type of the loss.
+pub fn export_landscape<M, L>(model: &M, range: &FloatRange, buffer: &mut [f32]) -> usize
+where
+    M: EquationModel,
+    L: Loss<ModelOutput = f32>,
+{
+    let len = range.steps.min(buffer.len());
+    let mut concentrations = range.clone().into_iter();
+    for losses in buffer[..len].chunks_mut(BATCH) {
+        if let Ok(losses) = <&mut [f32; BATCH]>::try_from(&mut *losses) {
+            let mut batch = [0.0; BATCH];
+            for (slot, concentration) in batch.iter_mut().zip(&mut concentrations) {
+                *slot = concentration;
+            }
+            for (loss, value) in losses.iter_mut().zip(model.value_batch(&batch)) {
+                *loss = L::evaluate(value);
+            }
+        } else {
+            for (loss, concentration) in losses.iter_mut().zip(&mut concentrations) {
+                *loss = L::evaluate(model.value(concentration));
+            }
+        }
+    }
+    len
+}
+
+/// Samples the loss of the system model over a grid of variables, in
+/// chunks, so that grids larger than the memory of the device can be
+/// streamed to the host.
+///
+/// The grid is visited in the same order of the brute force algorithm: the
+/// concentration in the outer loop and the saturation in the inner one.
+/// Every time the buffer is full, and at the end with the remaining losses,
+/// the callback receives the index in the grid of the first loss of the
+/// chunk and the losses.
+///
+/// # Arguments
+///
+/// * `model` - The model, with the currents of the measurement.
+/// * `concentration_range` - The range of concentrations.
+/// * `resistance_range` - The range of wet drain-source resistances.
+/// * `saturation_range` - The range of water saturations.
+/// * `buffer` - The buffer used for the chunks, not empty.
+/// * `chunk` - The callback receiving the chunks.
+///
+/// # Returns
+///
+/// The number of losses in the grid.
+///
+/// # Type parameters
+///
+/// * `M` - The type of the model.
+/// * `L` - The type of the loss.
+///
+/// # Panics
+///
+/// If the buffer is empty.
+#[cfg(feature = "system")]
+pub fn export_landscape_system<M, L>(
+    model: &M,
+    concentration_range: &FloatRange,
+    resistance_range: &FloatRange,
+    saturation_range: &FloatRange,
+    buffer: &mut [f32],
+    mut chunk: impl FnMut(usize, &[f32]),
+) -> usize
+where
+    M: SystemModel,
+    L: Loss<ModelOutput = [(f32, f32); 3]>,
+{
+    assert!(!buffer.is_empty(), "the buffer must not be empty");
+    let mut start = 0;
+    let mut len = 0;
+
+    for concentration in concentration_range.clone() {
+        for resistance in resistance_range.clone() {
+            for saturation in saturation_range.clone() {
+                buffer[len] = L::evaluate(model.value(Variables {
+                    concentration,
+                    resistance,
+                    saturation,
+                }));
+                len += 1;
+
+                if len == buffer.len() {
+                    chunk(start, buffer);
+                    start += len;
+                    len = 0;
+                }
+            }
+        }
+    }
+
+    if len > 0 {
+        chunk(start, &buffer[..len]);
+    }
+    start + len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::losses::Absolute;
+    #[cfg(feature = "system")]
+    use crate::losses::MaxRelative;
+    #[cfg(feature = "system")]
+    use crate::models::System;
+    use crate::models::{Equation, Model};
+    use crate::params::{
+        Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages,
+    };
+
+    const MODEL_PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    const CURRENTS: Currents = Currents {
+        i_ds_off: -0.00125,
+        i_ds_on: -0.00085,
+        i_gs_on: 2.4e-6,
+    };
+
+    #[test]
+    fn test_export_landscape() {
+        let model = Equation::new(MODEL_PARAMS, CURRENTS);
+        let range = FloatRange::new(1e-3, 1e-1, 10);
+
+        let mut buffer = [f32::NAN; 12];
+        assert_eq!(
+            export_landscape::<_, Absolute>(&model, &range, &mut buffer),
+            10
+        );
+        // The full batches are evaluated together, the rest one at a time.
+        let mut concentrations = [0.0; BATCH];
+        for (slot, concentration) in concentrations.iter_mut().zip(range.clone()) {
+            *slot = concentration;
+        }
+        for (loss, value) in buffer.iter().zip(model.value_batch(&concentrations)) {
+            assert_eq!(*loss, value.abs());
+        }
+        for (loss, concentration) in buffer[BATCH..]
+            .iter()
+            .zip(range.clone().into_iter().skip(BATCH))
+        {
+            assert_eq!(*loss, model.value(concentration).abs());
+        }
+        assert!(buffer[10].is_nan());
+
+        let mut buffer = [0.0; 4];
+        assert_eq!(
+            export_landscape::<_, Absolute>(&model, &range, &mut buffer),
+            4
+        );
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn test_export_landscape_system() {
+        let model = System::new(MODEL_PARAMS, CURRENTS);
+        let concentration_range = FloatRange::new(1e-3, 1e-1, 3);
+        let resistance_range = FloatRange::new(30.0, 50.0, 2);
+        let saturation_range = FloatRange::new(0.5, 1.0, 2);
+
+        let mut chunks = [(0, 0); 4];
+        let mut count = 0;
+        let mut landscape = [0.0; 12];
+        let mut buffer = [0.0; 5];
+        let total = export_landscape_system::<_, MaxRelative>(
+            &model,
+            &concentration_range,
+            &resistance_range,
+            &saturation_range,
+            &mut buffer,
+            |start, losses| {
+                chunks[count] = (start, losses.len());
+                count += 1;
+                landscape[start..start + losses.len()].copy_from_slice(losses);
+            },
+        );
+        assert_eq!(total, 12);
+        assert_eq!(chunks[..count], [(0, 5), (5, 5), (10, 2)]);
+
+        // The last loss of the grid.
+        let vars = Variables {
+            concentration: concentration_range.split(3, 2).start,
+            resistance: 40.0,
+            saturation: 0.75,
+        };
+        assert_eq!(landscape[11], MaxRelative::evaluate(model.value(vars)));
+    }
+}
@@ -1,7 +1,54 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod acquisition;
+pub mod alarms;
 pub mod algorithms;
+#[cfg(feature = "calibration")]
+pub mod calibration;
+#[cfg(feature = "can")]
+pub mod can;
+pub mod console;
+#[cfg(feature = "datalog")]
+pub mod datalog;
+#[cfg(feature = "std")]
+pub mod dataset;
+#[cfg(feature = "display")]
+pub mod display;
+pub mod env;
+pub mod features;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fixed-point")]
+pub mod fixed_point;
+#[cfg(feature = "gatt")]
+pub mod gatt;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod landscape;
+#[cfg(feature = "lorawan")]
+pub mod lorawan;
 pub mod losses;
+#[cfg(feature = "modbus")]
+pub mod modbus;
 pub mod models;
+#[cfg(feature = "noise")]
+pub mod noise;
+#[cfg(feature = "param-store")]
+pub mod param_store;
 pub mod params;
+pub mod pipeline;
+#[cfg(feature = "system")]
+pub mod recalibration;
+#[cfg(feature = "record")]
+pub mod record;
+pub mod schedule;
+#[cfg(feature = "sdcard")]
+pub mod sdcard;
+#[cfg(feature = "system")]
+pub mod selftest;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+pub mod timestamp;
 pub mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
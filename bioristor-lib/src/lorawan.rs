@@ -0,0 +1,176 @@
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::{params::Variables, utils::scale};
+
+/// The size of the payload.
+pub const PAYLOAD_SIZE: usize = 11;
+
+/// The version of the layout of the payload.
+pub const PAYLOAD_VERSION: u8 = 1;
+
+/// The decades of the concentration covered by the encoding, from 1 nM to
+/// 1 M.
+const CONCENTRATION_DECADES: f32 = 9.0;
+
+/// The resolution of the logarithmic encodings [1/decade].
+const STEPS_PER_DECADE: f32 = 7000.0;
+
+/// The flag set when the payload carries a solution.
+const FLAG_SOLUTION: u8 = 0x01;
+
+/// The uplink payload of a measurement, sent over LoRaWAN.
+///
+/// The payload is made of 11 bytes, with the scaled integers in big endian
+/// as usual for the payload decoders of the network servers:
+///
+/// | Bytes | Field           | Encoding                                      |
+/// |-------|-----------------|-----------------------------------------------|
+/// | 0     | version         | [`PAYLOAD_VERSION`]                           |
+/// | 1-2   | `concentration` | `u16`, `7000 * (log10(c) + 9)`, 1 nM to 1 M   |
+/// | 3-4   | `resistance`    | `u16`, `100 * r`, from 0 to 655.35 Ohm        |
+/// | 5     | `saturation`    | `u8`, `250 * s`, from 0 to 1                  |
+/// | 6-7   | `loss`          | `u16`, `-7000 * log10(loss)`, 1 to 4.4e-10    |
+/// | 8-9   | `battery`       | `u16`, `1000 * v`, from 0 to 65.535 V         |
+/// | 10    | flags           | bit 0 set if the payload carries a solution   |
+///
+/// Values out of range are clamped to the nearest representable value. When
+/// no solution has been found, bytes 1 to 7 are zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LoraPayload {
+    /// The voltage of the battery [Volt].
+    pub battery: f32,
+
+    /// The result of the algorithm.
+    pub result: Option<(Variables, f32)>,
+}
+
+/// The errors of the decoding of a payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PayloadError {
+    /// The payload is not [`PAYLOAD_SIZE`] bytes long.
+    InvalidLength,
+
+    /// The version of the payload is not supported.
+    UnsupportedVersion(u8),
+}
+
+impl LoraPayload {
+    /// Encodes the payload.
+    ///
+    /// # Returns
+    ///
+    /// The encoded payload.
+    pub fn encode(&self) -> [u8; PAYLOAD_SIZE] {
+        let mut payload = [0; PAYLOAD_SIZE];
+        payload[0] = PAYLOAD_VERSION;
+
+        if let Some((vars, loss)) = self.result {
+            let concentration =
+                scale((vars.concentration.log10() + CONCENTRATION_DECADES) * STEPS_PER_DECADE);
+            payload[1..3].copy_from_slice(&concentration.to_be_bytes());
+            payload[3..5].copy_from_slice(&scale(vars.resistance * 100.0).to_be_bytes());
+            payload[5] = scale(vars.saturation * 250.0).min(250) as u8;
+            payload[6..8].copy_from_slice(&scale(-loss.log10() * STEPS_PER_DECADE).to_be_bytes());
+            payload[10] |= FLAG_SOLUTION;
+        }
+        payload[8..10].copy_from_slice(&scale(self.battery * 1000.0).to_be_bytes());
+
+        payload
+    }
+
+    /// Decodes a payload received by the host.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The received payload.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(payload)` - The decoded payload, with the precision of the
+    ///   encoding.
+    /// * `Err(error)` - If the payload is not valid.
+    #[cfg(feature = "std")]
+    pub fn decode(payload: &[u8]) -> Result<Self, PayloadError> {
+        let payload: &[u8; PAYLOAD_SIZE] = payload
+            .try_into()
+            .map_err(|_| PayloadError::InvalidLength)?;
+        if payload[0] != PAYLOAD_VERSION {
+            return Err(PayloadError::UnsupportedVersion(payload[0]));
+        }
+
+        let word = |i: usize| u16::from_be_bytes([payload[i], payload[i + 1]]) as f32;
+        let result = (payload[10] & FLAG_SOLUTION != 0).then(|| {
+            let variables = Variables {
+                concentration: 10.0f32.powf(word(1) / STEPS_PER_DECADE - CONCENTRATION_DECADES),
+                resistance: word(3) / 100.0,
+                saturation: payload[5] as f32 / 250.0,
+            };
+            (variables, 10.0f32.powf(-word(6) / STEPS_PER_DECADE))
+        });
+
+        Ok(Self {
+            battery: word(8) / 1000.0,
+            result,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAYLOAD: LoraPayload = LoraPayload {
+        battery: 3.712,
+        result: Some((
+            Variables {
+                concentration: 2.5e-3,
+                resistance: 42.37,
+                saturation: 0.62,
+            },
+            1e-6,
+        )),
+    };
+
+    #[test]
+    fn test_encode() {
+        let payload = PAYLOAD.encode();
+        assert_eq!(payload[0], PAYLOAD_VERSION);
+        assert_eq!(&payload[3..6], &[0x10, 0x8D, 155]);
+        assert_eq!(&payload[8..], &[0x0E, 0x80, FLAG_SOLUTION]);
+
+        let empty = LoraPayload {
+            battery: 3.3,
+            result: None,
+        };
+        assert_eq!(
+            empty.encode(),
+            [PAYLOAD_VERSION, 0, 0, 0, 0, 0, 0, 0, 0x0C, 0xE4, 0]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode() {
+        let decoded = LoraPayload::decode(&PAYLOAD.encode()).unwrap();
+        assert!((decoded.battery - PAYLOAD.battery).abs() < 1e-6);
+
+        let ((vars, loss), (expected, expected_loss)) =
+            (decoded.result.unwrap(), PAYLOAD.result.unwrap());
+        assert!((vars.concentration / expected.concentration - 1.0).abs() < 1e-3);
+        assert!((vars.resistance - expected.resistance).abs() < 1e-4);
+        assert!((vars.saturation - expected.saturation).abs() < 1e-6);
+        assert!((loss / expected_loss - 1.0).abs() < 1e-3);
+
+        assert_eq!(
+            LoraPayload::decode(&[PAYLOAD_VERSION; 4]),
+            Err(PayloadError::InvalidLength)
+        );
+        assert_eq!(
+            LoraPayload::decode(&[2; PAYLOAD_SIZE]),
+            Err(PayloadError::UnsupportedVersion(2))
+        );
+    }
+}
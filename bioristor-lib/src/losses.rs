@@ -1,3 +1,5 @@
+use core::fmt;
+
 #[allow(unused_imports)]
 use micromath::F32Ext;
 
@@ -23,6 +25,12 @@ pub trait Loss {
 /// for example when using the equation model.
 pub struct Absolute;
 
+impl fmt::Display for Absolute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("absolute")
+    }
+}
+
 impl Loss for Absolute {
     type ModelOutput = f32;
 
@@ -40,6 +48,12 @@ impl Loss for Absolute {
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MaxRelative;
 
+impl fmt::Display for MaxRelative {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("max-relative")
+    }
+}
+
 impl Loss for MaxRelative {
     type ModelOutput = [(f32, f32); 3];
 
@@ -63,6 +77,12 @@ impl Loss for MaxRelative {
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MeanRelative;
 
+impl fmt::Display for MeanRelative {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("mean-relative")
+    }
+}
+
 impl Loss for MeanRelative {
     type ModelOutput = [(f32, f32); 3];
 
@@ -86,6 +106,12 @@ impl Loss for MeanRelative {
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SumRelative;
 
+impl fmt::Display for SumRelative {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sum-relative")
+    }
+}
+
 impl Loss for SumRelative {
     type ModelOutput = [(f32, f32); 3];
 
@@ -136,4 +162,14 @@ mod tests {
         let value = [(-1.0, 2.0), (-3.0, 4.0), (5.0, -6.0)];
         assert!((SumRelative::evaluate(value) - 3.0).abs() < 1e-9);
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_display() {
+        // The names are the ones accepted by the simulator.
+        assert_eq!(Absolute.to_string(), "absolute");
+        assert_eq!(MaxRelative.to_string(), "max-relative");
+        assert_eq!(MeanRelative.to_string(), "mean-relative");
+        assert_eq!(SumRelative.to_string(), "sum-relative");
+    }
 }
@@ -0,0 +1,378 @@
+use crate::params::{
+    Currents, ModelParams, ModulationParams, StemResistanceInvParams, Variables, Voltages,
+};
+
+/// The number of input registers.
+pub const INPUT_REGISTERS: usize = 16;
+
+/// The number of holding registers.
+pub const HOLDING_REGISTERS: usize = 16;
+
+/// The status reported when the last measurement has been solved.
+pub const STATUS_OK: u16 = 0;
+
+/// The status reported when the algorithm did not find a solution for the
+/// last measurement.
+pub const STATUS_NO_SOLUTION: u16 = 1;
+
+/// The exceptions returned to the Modbus master.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ModbusError {
+    /// The registers are out of the map, or split a 32-bit value.
+    IllegalDataAddress,
+
+    /// The written value is not a finite number.
+    IllegalDataValue,
+}
+
+impl ModbusError {
+    /// Returns the Modbus exception code.
+    pub fn code(self) -> u8 {
+        match self {
+            Self::IllegalDataAddress => 0x02,
+            Self::IllegalDataValue => 0x03,
+        }
+    }
+}
+
+/// Converts a `f32` to two registers, most significant word first.
+///
+/// # Arguments
+///
+/// * `value` - The value to be converted.
+///
+/// # Returns
+///
+/// The registers.
+#[inline]
+pub fn f32_to_registers(value: f32) -> [u16; 2] {
+    let bits = value.to_bits();
+    [(bits >> 16) as u16, bits as u16]
+}
+
+/// Converts two registers, most significant word first, to a `f32`.
+///
+/// # Arguments
+///
+/// * `registers` - The registers to be converted.
+///
+/// # Returns
+///
+/// The value.
+#[inline]
+pub fn registers_to_f32(registers: [u16; 2]) -> f32 {
+    f32::from_bits(((registers[0] as u32) << 16) | registers[1] as u32)
+}
+
+/// The register map of the device, polled by a Modbus RTU master.
+///
+/// The 32-bit values are IEEE 754 floats stored in two consecutive
+/// registers, most significant word first.
+///
+/// Input registers (function code 4):
+///
+/// | Address | Value                                              |
+/// |---------|----------------------------------------------------|
+/// | 0-1     | `concentration` [Molarity]                         |
+/// | 2-3     | `resistance` [Ohm]                                 |
+/// | 4-5     | `saturation`                                       |
+/// | 6-7     | `loss`                                             |
+/// | 8-9     | `i_ds_off` [Ampere]                                |
+/// | 10-11   | `i_ds_on` [Ampere]                                 |
+/// | 12-13   | `i_gs_on` [Ampere]                                 |
+/// | 14      | status, [`STATUS_OK`] or [`STATUS_NO_SOLUTION`]    |
+/// | 15      | number of measurements, wrapping                   |
+///
+/// Holding registers (function codes 3, 6 and 16), the parameters of the
+/// model:
+///
+/// | Address | Value                                              |
+/// |---------|----------------------------------------------------|
+/// | 0-5     | `mod_params.0`, `mod_params.1`, `mod_params.2`     |
+/// | 6-7     | `r_dry` [Ohm]                                      |
+/// | 8-11    | `res_params.0`, `res_params.1`                     |
+/// | 12-13   | `v_ds` [Volt]                                      |
+/// | 14-15   | `v_gs` [Volt]                                      |
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegisterMap {
+    /// The currents of the last measurement.
+    pub currents: Currents,
+
+    /// The loss of the last solution.
+    pub loss: f32,
+
+    /// The number of measurements.
+    pub measurements: u16,
+
+    /// The parameters of the model.
+    pub params: ModelParams,
+
+    /// The status of the last measurement.
+    pub status: u16,
+
+    /// The variables of the last solution.
+    pub variables: Variables,
+}
+
+impl RegisterMap {
+    /// Creates a new register map, without measurements.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the model.
+    pub fn new(params: ModelParams) -> Self {
+        Self {
+            currents: Currents {
+                i_ds_off: f32::NAN,
+                i_ds_on: f32::NAN,
+                i_gs_on: f32::NAN,
+            },
+            loss: f32::NAN,
+            measurements: 0,
+            params,
+            status: STATUS_NO_SOLUTION,
+            variables: Variables {
+                concentration: f32::NAN,
+                resistance: f32::NAN,
+                saturation: f32::NAN,
+            },
+        }
+    }
+
+    /// Updates the input registers with a new measurement.
+    ///
+    /// # Arguments
+    ///
+    /// * `currents` - The measured currents.
+    /// * `result` - The result returned by the algorithm; if no solution has
+    ///   been found, the variables and the loss are set to NaN.
+    pub fn update(&mut self, currents: Currents, result: Option<(Variables, f32)>) {
+        self.currents = currents;
+        self.measurements = self.measurements.wrapping_add(1);
+        match result {
+            Some((variables, loss)) => {
+                self.variables = variables;
+                self.loss = loss;
+                self.status = STATUS_OK;
+            }
+            None => {
+                self.variables = Variables {
+                    concentration: f32::NAN,
+                    resistance: f32::NAN,
+                    saturation: f32::NAN,
+                };
+                self.loss = f32::NAN;
+                self.status = STATUS_NO_SOLUTION;
+            }
+        }
+    }
+
+    /// Reads consecutive input registers.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the first register.
+    /// * `registers` - The buffer where the registers are written.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the registers have been read.
+    /// * `Err(error)` - If the registers are out of the map.
+    pub fn read_input(&self, address: u16, registers: &mut [u16]) -> Result<(), ModbusError> {
+        let mut map = [0; INPUT_REGISTERS];
+        let values = [
+            self.variables.concentration,
+            self.variables.resistance,
+            self.variables.saturation,
+            self.loss,
+            self.currents.i_ds_off,
+            self.currents.i_ds_on,
+            self.currents.i_gs_on,
+        ];
+        for (pair, value) in map.chunks_exact_mut(2).zip(values) {
+            pair.copy_from_slice(&f32_to_registers(value));
+        }
+        map[14] = self.status;
+        map[15] = self.measurements;
+
+        registers.copy_from_slice(range(&map, address, registers.len())?);
+        Ok(())
+    }
+
+    /// Reads consecutive holding registers.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the first register.
+    /// * `registers` - The buffer where the registers are written.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the registers have been read.
+    /// * `Err(error)` - If the registers are out of the map.
+    pub fn read_holding(&self, address: u16, registers: &mut [u16]) -> Result<(), ModbusError> {
+        let map = self.holding_registers();
+        registers.copy_from_slice(range(&map, address, registers.len())?);
+        Ok(())
+    }
+
+    /// Writes consecutive holding registers, updating the parameters of the
+    /// model.
+    ///
+    /// Every parameter must be written as a whole: the write is rejected if
+    /// it starts or ends in the middle of a parameter.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the first register.
+    /// * `registers` - The values of the registers.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the parameters have been updated.
+    /// * `Err(error)` - If the registers are out of the map or a value is not
+    ///   a finite number; the parameters are left unchanged.
+    pub fn write_holding(&mut self, address: u16, registers: &[u16]) -> Result<(), ModbusError> {
+        if !address.is_multiple_of(2) || !registers.len().is_multiple_of(2) {
+            return Err(ModbusError::IllegalDataAddress);
+        }
+
+        let mut map = self.holding_registers();
+        let start = address as usize;
+        map.get_mut(start..start + registers.len())
+            .ok_or(ModbusError::IllegalDataAddress)?
+            .copy_from_slice(registers);
+
+        let mut values = [0.0; HOLDING_REGISTERS / 2];
+        for (value, pair) in values.iter_mut().zip(map.chunks_exact(2)) {
+            *value = registers_to_f32([pair[0], pair[1]]);
+            if !value.is_finite() {
+                return Err(ModbusError::IllegalDataValue);
+            }
+        }
+
+        let [a, b, c, r_dry, k0, k1, v_ds, v_gs] = values;
+        self.params = ModelParams {
+            mod_params: ModulationParams(a, b, c),
+            r_dry,
+            res_params: StemResistanceInvParams(k0, k1),
+            voltages: Voltages { v_ds, v_gs },
+        };
+        Ok(())
+    }
+
+    /// Returns the values of all the holding registers.
+    fn holding_registers(&self) -> [u16; HOLDING_REGISTERS] {
+        let params = &self.params;
+        let values = [
+            params.mod_params.0,
+            params.mod_params.1,
+            params.mod_params.2,
+            params.r_dry,
+            params.res_params.0,
+            params.res_params.1,
+            params.voltages.v_ds,
+            params.voltages.v_gs,
+        ];
+
+        let mut map = [0; HOLDING_REGISTERS];
+        for (pair, value) in map.chunks_exact_mut(2).zip(values) {
+            pair.copy_from_slice(&f32_to_registers(value));
+        }
+        map
+    }
+}
+
+/// Returns a range of registers of a map.
+fn range(map: &[u16], address: u16, len: usize) -> Result<&[u16], ModbusError> {
+    let start = address as usize;
+    map.get(start..start + len)
+        .ok_or(ModbusError::IllegalDataAddress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    const CURRENTS: Currents = Currents {
+        i_ds_off: -0.0030365,
+        i_ds_on: -0.0026829,
+        i_gs_on: 1.169828e-6,
+    };
+
+    #[test]
+    fn test_f32_registers() {
+        assert_eq!(f32_to_registers(1.0), [0x3F80, 0x0000]);
+        assert_eq!(registers_to_f32([0xC020, 0x0000]), -2.5);
+    }
+
+    #[test]
+    fn test_input_registers() {
+        let mut map = RegisterMap::new(PARAMS);
+        let variables = Variables {
+            concentration: 2.5e-3,
+            resistance: 42.0,
+            saturation: 0.5,
+        };
+        map.update(CURRENTS, Some((variables, 1e-6)));
+
+        let mut registers = [0; INPUT_REGISTERS];
+        map.read_input(0, &mut registers).unwrap();
+        assert_eq!(registers_to_f32([registers[2], registers[3]]), 42.0);
+        assert_eq!(registers_to_f32([registers[8], registers[9]]), -0.0030365);
+        assert_eq!(&registers[14..], &[STATUS_OK, 1]);
+
+        map.update(CURRENTS, None);
+        let mut registers = [0; 4];
+        map.read_input(4, &mut registers).unwrap();
+        assert!(registers_to_f32([registers[0], registers[1]]).is_nan());
+        assert!(registers_to_f32([registers[2], registers[3]]).is_nan());
+
+        let mut registers = [0; 2];
+        map.read_input(14, &mut registers).unwrap();
+        assert_eq!(registers, [STATUS_NO_SOLUTION, 2]);
+        assert_eq!(
+            map.read_input(15, &mut registers),
+            Err(ModbusError::IllegalDataAddress)
+        );
+    }
+
+    #[test]
+    fn test_holding_registers() {
+        let mut map = RegisterMap::new(PARAMS);
+        let mut registers = [0; 2];
+        map.read_holding(6, &mut registers).unwrap();
+        assert_eq!(registers_to_f32(registers), 38.2);
+
+        map.write_holding(6, &f32_to_registers(40.0)).unwrap();
+        assert_eq!(map.params.r_dry, 40.0);
+        assert_eq!(map.params.voltages, PARAMS.voltages);
+
+        assert_eq!(
+            map.write_holding(7, &f32_to_registers(1.0)),
+            Err(ModbusError::IllegalDataAddress)
+        );
+        assert_eq!(
+            map.write_holding(16, &f32_to_registers(1.0)),
+            Err(ModbusError::IllegalDataAddress)
+        );
+        assert_eq!(
+            map.write_holding(14, &f32_to_registers(f32::INFINITY)),
+            Err(ModbusError::IllegalDataValue)
+        );
+        assert_eq!(map.params.voltages.v_gs, 0.5);
+        assert_eq!(ModbusError::IllegalDataValue.code(), 0x03);
+    }
+}
@@ -0,0 +1,282 @@
+use core::cell::Cell;
+
+#[cfg(feature = "system")]
+use nalgebra::Matrix3;
+
+#[cfg(feature = "system")]
+use crate::{models::SystemModel, params::Variables};
+use crate::{
+    models::{EquationModel, Model},
+    params::{Currents, ModelParams},
+};
+
+/// Decorator of a model that counts the evaluations of its value, gradient
+/// and Jacobian matrix.
+///
+/// The counters allow to compare the algorithms by the number of evaluations
+/// of the model they need, which is the dominant cost of a solve.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::models::{CountingModel, Equation, EquationModel, Model};
+/// use bioristor_lib::params::{
+///     Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages,
+/// };
+///
+/// const PARAMS: ModelParams = ModelParams {
+///     mod_params: ModulationParams(1.0, 2.0, 3.0),
+///     r_dry: 4.0,
+///     res_params: StemResistanceInvParams(5.0, 6.0),
+///     voltages: Voltages {
+///         v_ds: 7.0,
+///         v_gs: 8.0,
+///     },
+/// };
+/// let currents = Currents {
+///     i_ds_off: 9.0,
+///     i_ds_on: 10.0,
+///     i_gs_on: 11.0,
+/// };
+///
+/// let model = CountingModel::<Equation>::new(PARAMS, currents);
+/// model.value(1e-2);
+/// model.gradient(1e-2);
+/// assert_eq!(model.values(), 1);
+/// assert_eq!(model.gradients(), 1);
+/// assert_eq!(model.evaluations(), 2);
+/// ```
+#[derive(Debug)]
+pub struct CountingModel<M: Model> {
+    /// The number of evaluations of the gradient.
+    gradients: Cell<usize>,
+
+    /// The number of evaluations of the Jacobian matrix.
+    jacobians: Cell<usize>,
+
+    /// The decorated model.
+    model: M,
+
+    /// The number of evaluations of the value.
+    values: Cell<usize>,
+}
+
+impl<M: Model> CountingModel<M> {
+    /// Wraps a model, with all the counters set to zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The model to be decorated.
+    pub fn wrap(model: M) -> Self {
+        Self {
+            gradients: Cell::new(0),
+            jacobians: Cell::new(0),
+            model,
+            values: Cell::new(0),
+        }
+    }
+
+    /// Returns the number of evaluations of the value of the model.
+    #[inline]
+    pub fn values(&self) -> usize {
+        self.values.get()
+    }
+
+    /// Returns the number of evaluations of the gradient of the model.
+    #[inline]
+    pub fn gradients(&self) -> usize {
+        self.gradients.get()
+    }
+
+    /// Returns the number of evaluations of the Jacobian matrix of the model.
+    #[inline]
+    pub fn jacobians(&self) -> usize {
+        self.jacobians.get()
+    }
+
+    /// Returns the total number of evaluations of the model.
+    #[inline]
+    pub fn evaluations(&self) -> usize {
+        self.values() + self.gradients() + self.jacobians()
+    }
+
+    /// Sets all the counters to zero.
+    pub fn reset(&self) {
+        self.gradients.set(0);
+        self.jacobians.set(0);
+        self.values.set(0);
+    }
+
+    /// Returns a reference to the decorated model.
+    #[inline]
+    pub fn inner(&self) -> &M {
+        &self.model
+    }
+
+    /// Consumes the decorator and returns the decorated model.
+    #[inline]
+    pub fn into_inner(self) -> M {
+        self.model
+    }
+}
+
+impl<M: Model> Model for CountingModel<M> {
+    type Params = M::Params;
+
+    fn new(params: M::Params, currents: Currents) -> Self {
+        Self::wrap(M::new(params, currents))
+    }
+
+    #[inline]
+    fn params(&self) -> &ModelParams {
+        self.model.params()
+    }
+
+    #[inline]
+    fn currents(&self) -> &Currents {
+        self.model.currents()
+    }
+}
+
+impl<M: EquationModel> EquationModel for CountingModel<M> {
+    fn value(&self, concentration: f32) -> f32 {
+        self.values.set(self.values.get() + 1);
+        self.model.value(concentration)
+    }
+
+    fn value_batch<const K: usize>(&self, concentrations: &[f32; K]) -> [f32; K] {
+        self.values.set(self.values.get() + K);
+        self.model.value_batch(concentrations)
+    }
+
+    fn gradient(&self, concentration: f32) -> f32 {
+        self.gradients.set(self.gradients.get() + 1);
+        self.model.gradient(concentration)
+    }
+
+    #[inline]
+    fn resistance(&self, concentration: f32) -> f32 {
+        self.model.resistance(concentration)
+    }
+
+    #[inline]
+    fn saturation(&self, concentration: f32) -> f32 {
+        self.model.saturation(concentration)
+    }
+}
+
+#[cfg(feature = "system")]
+impl<M: SystemModel> SystemModel for CountingModel<M> {
+    fn value(&self, variables: Variables) -> [(f32, f32); 3] {
+        self.values.set(self.values.get() + 1);
+        self.model.value(variables)
+    }
+
+    fn value_at(&self, concentration: f32) -> impl Fn(f32, f32) -> [(f32, f32); 3] + '_ {
+        let value = self.model.value_at(concentration);
+        move |resistance, saturation| {
+            self.values.set(self.values.get() + 1);
+            value(resistance, saturation)
+        }
+    }
+
+    fn jacobian(&self, variables: Variables) -> Matrix3<f32> {
+        self.jacobians.set(self.jacobians.get() + 1);
+        self.model.jacobian(variables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "system")]
+    use crate::models::System;
+    #[cfg(feature = "newton")]
+    use crate::{
+        algorithms::{Algorithm, NewtonEquation, NewtonParams, SaturationPolicy},
+        losses::Absolute,
+    };
+    use crate::{
+        models::Equation,
+        params::{ModulationParams, StemResistanceInvParams, Voltages},
+    };
+
+    const PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    const CURRENTS: Currents = Currents {
+        i_ds_off: -0.003_036_5,
+        i_ds_on: -0.002_682_9,
+        i_gs_on: 1.169_828e-6,
+    };
+
+    #[test]
+    fn test_counting_model_equation() {
+        let model = CountingModel::<Equation>::new(PARAMS, CURRENTS);
+        let inner = Equation::new(PARAMS, CURRENTS);
+
+        assert_eq!(model.value(1e-2), inner.value(1e-2));
+        assert_eq!(model.value(2e-2), inner.value(2e-2));
+        assert_eq!(model.gradient(1e-2), inner.gradient(1e-2));
+        assert_eq!(model.resistance(1e-2), inner.resistance(1e-2));
+        assert_eq!(model.saturation(1e-2), inner.saturation(1e-2));
+
+        assert_eq!(model.values(), 2);
+        assert_eq!(model.gradients(), 1);
+        assert_eq!(model.jacobians(), 0);
+        assert_eq!(model.evaluations(), 3);
+
+        model.reset();
+        assert_eq!(model.evaluations(), 0);
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn test_counting_model_system() {
+        let model = CountingModel::wrap(System::new(PARAMS, CURRENTS));
+        let vars = Variables {
+            concentration: 1e-2,
+            resistance: 9.0,
+            saturation: 0.7,
+        };
+
+        assert_eq!(model.value(vars), model.inner().value(vars));
+        assert_eq!(model.jacobian(vars), model.inner().jacobian(vars));
+        let value = model.value_at(vars.concentration);
+        assert_eq!(value(9.0, 0.7), model.inner().value(vars));
+        assert_eq!(value(9.0, 0.7), model.inner().value(vars));
+
+        assert_eq!(model.values(), 3);
+        assert_eq!(model.gradients(), 0);
+        assert_eq!(model.jacobians(), 1);
+    }
+
+    #[cfg(feature = "newton")]
+    #[test]
+    fn test_counting_model_algorithm() {
+        let params = NewtonParams {
+            concentration_init: 1e-2,
+            grad_tolerance: 1e-9,
+            max_iterations: 10,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-15,
+        };
+        let model = CountingModel::<Equation>::new(PARAMS, CURRENTS);
+        let algorithm = NewtonEquation::<_, Absolute>::new(params, model);
+        algorithm.run().unwrap();
+
+        // Every iteration of the method evaluates both the value and the
+        // gradient of the model.
+        let model = algorithm.model();
+        assert!(model.values() > 0);
+        assert!(model.gradients() > 0);
+        assert_eq!(model.jacobians(), 0);
+    }
+}
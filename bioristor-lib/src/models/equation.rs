@@ -1,3 +1,8 @@
+#[cfg(feature = "std")]
+use wide::f32x8;
+
+use core::borrow::Borrow;
+
 use crate::{
     models::Model,
     params::{Currents, ModelParams},
@@ -17,6 +22,25 @@ pub trait EquationModel: Model {
     /// The output value of the model.
     fn value(&self, concentration: f32) -> f32;
 
+    /// Calculates the output values of the model for a batch of
+    /// concentrations.
+    ///
+    /// The evaluations are independent, so that they can be interleaved by
+    /// the compiler to keep the pipeline of the FPU full, instead of
+    /// stalling on the dependencies of a single evaluation.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentrations` - Concentrations of ions in the electrolyte [Molarity].
+    ///
+    /// # Returns
+    ///
+    /// The output values of the model, in the same order of the concentrations.
+    #[inline]
+    fn value_batch<const K: usize>(&self, concentrations: &[f32; K]) -> [f32; K] {
+        concentrations.map(|concentration| self.value(concentration))
+    }
+
     /// Calculates the gradient of the error function.
     ///
     /// # Arguments
@@ -86,8 +110,37 @@ pub trait EquationModel: Model {
 /// let resistance = model.resistance(concentration);
 /// let saturation = model.saturation(concentration);
 /// ```
+///
+/// The parameters can also be borrowed, e.g. from a `const` in flash or from
+/// a block shared by the models of several channels, so that they are not
+/// copied in every model:
+///
+/// ```
+/// # use bioristor_lib::models::{Model, Equation, EquationModel};
+/// # use bioristor_lib::params::{
+/// #     Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages,
+/// # };
+/// #
+/// # const PARAMS: ModelParams = ModelParams {
+/// #     mod_params: ModulationParams(1.0, 2.0, 3.0),
+/// #     r_dry: 4.0,
+/// #     res_params: StemResistanceInvParams(5.0, 6.0),
+/// #     voltages: Voltages {
+/// #         v_ds: 7.0,
+/// #         v_gs: 8.0,
+/// #     },
+/// # };
+/// # let currents = Currents {
+/// #     i_ds_off: 9.0,
+/// #     i_ds_on: 10.0,
+/// #     i_gs_on: 11.0,
+/// # };
+/// let model: Equation<&ModelParams> = Equation::new(&PARAMS, currents);
+///
+/// let value = model.value(10.0);
+/// ```
 #[derive(Debug)]
-pub struct Equation {
+pub struct Equation<P = ModelParams> {
     /// Pre-calculated coefficients to compute the error function.
     func_coeffs: FuncCoeffs,
 
@@ -100,8 +153,195 @@ pub struct Equation {
     /// The output currents of the device.
     currents: Currents,
 
-    /// The parameters of the mathematical model.
-    params: ModelParams,
+    /// The parameters of the mathematical model, owned or borrowed.
+    params: P,
+}
+
+/// The degenerate configurations of the [`Equation`], in which the
+/// denominator of the error function and of the saturation is zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EquationError {
+    /// A current is not finite.
+    NonFiniteCurrent,
+
+    /// The drain-source current with the gate on is equal to the gate-source
+    /// current: `i_ds_on - i_gs_on = 0`.
+    OnCurrentsEqual,
+
+    /// The dry resistance of the channel is zero: `r_dry = 0`.
+    ZeroDryResistance,
+
+    /// The drain-source current with the gate off is zero: `i_ds_off = 0`.
+    ZeroOffCurrent,
+}
+
+impl<P: Borrow<ModelParams>> Equation<P> {
+    /// Creates a new instance of the model, checking that the parameters and
+    /// the currents do not make the denominator of the equation
+    /// `i_ds_off * r_dry * (i_ds_on - i_gs_on)` zero, in which case every
+    /// evaluation of the model would divide by zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the mathematical model, owned or
+    ///   borrowed.
+    /// * `currents` - The output currents of the devices,
+    ///   i.e. the independent variables of the model.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(model)` - The new instance of the model.
+    /// * `Err(error)` - The relation that makes the model degenerate.
+    pub fn try_new(params: P, currents: Currents) -> Result<Self, EquationError> {
+        if !(currents.i_ds_off.is_finite()
+            && currents.i_ds_on.is_finite()
+            && currents.i_gs_on.is_finite())
+        {
+            return Err(EquationError::NonFiniteCurrent);
+        }
+        if currents.i_ds_off == 0.0 {
+            return Err(EquationError::ZeroOffCurrent);
+        }
+        if params.borrow().r_dry == 0.0 {
+            return Err(EquationError::ZeroDryResistance);
+        }
+        if currents.i_ds_on - currents.i_gs_on == 0.0 {
+            return Err(EquationError::OnCurrentsEqual);
+        }
+
+        Ok(Self::new(params, currents))
+    }
+}
+
+impl Equation {
+    /// Creates a new instance of the model in a constant context, so that
+    /// the coefficients of the model can be computed at compile time and
+    /// stored in flash when the parameters and the currents are known in
+    /// advance, e.g. in test fixtures or in firmwares for a fixed scenario.
+    ///
+    /// It is equivalent to [`Model::new`].
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the mathematical model.
+    /// * `currents` - The output currents of the devices,
+    ///   i.e. the independent variables of the model.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of the model.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bioristor_lib::models::{Equation, EquationModel};
+    /// use bioristor_lib::params::{
+    ///     Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages,
+    /// };
+    ///
+    /// const MODEL: Equation = Equation::precomputed(
+    ///     ModelParams {
+    ///         mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    ///         r_dry: 38.2,
+    ///         res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    ///         voltages: Voltages {
+    ///             v_ds: -0.05,
+    ///             v_gs: 0.5,
+    ///         },
+    ///     },
+    ///     Currents {
+    ///         i_ds_off: -0.003_036_5,
+    ///         i_ds_on: -0.002_682_9,
+    ///         i_gs_on: 1.169_828e-6,
+    ///     },
+    /// );
+    ///
+    /// let value = MODEL.value(5e-3);
+    /// ```
+    pub const fn precomputed(params: ModelParams, currents: Currents) -> Self {
+        let (func_coeffs, resistance_coeffs, saturation_coeffs) = coefficients(&params, &currents);
+        Self {
+            func_coeffs,
+            resistance_coeffs,
+            saturation_coeffs,
+            currents,
+            params,
+        }
+    }
+}
+
+impl<'a> Equation<&'a ModelParams> {
+    /// Creates a new instance of the model borrowing the parameters in a
+    /// constant context, see [`Equation::precomputed`].
+    ///
+    /// It is equivalent to [`Model::new`] with borrowed parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the mathematical model.
+    /// * `currents` - The output currents of the devices,
+    ///   i.e. the independent variables of the model.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of the model.
+    pub const fn precomputed_ref(params: &'a ModelParams, currents: Currents) -> Self {
+        let (func_coeffs, resistance_coeffs, saturation_coeffs) = coefficients(params, &currents);
+        Self {
+            func_coeffs,
+            resistance_coeffs,
+            saturation_coeffs,
+            currents,
+            params,
+        }
+    }
+}
+
+/// Calculates the coefficients of the model.
+///
+/// # Arguments
+///
+/// * `params` - The parameters of the mathematical model.
+/// * `currents` - The output currents of the devices.
+///
+/// # Returns
+///
+/// The coefficients of the error function, of the resistance and of the
+/// saturation.
+const fn coefficients(
+    params: &ModelParams,
+    currents: &Currents,
+) -> (FuncCoeffs, ResistanceCoeffs, SaturationCoeffs) {
+    (
+        FuncCoeffs(
+            currents.i_gs_on,
+            params.voltages.v_gs
+                * params.voltages.v_ds
+                * (currents.i_ds_off - currents.i_ds_on + currents.i_gs_on),
+            params.voltages.v_gs
+                * currents.i_ds_off
+                * (params.voltages.v_ds - currents.i_ds_on * params.r_dry
+                    + currents.i_gs_on * params.r_dry),
+            currents.i_ds_off * params.r_dry * (currents.i_ds_on - currents.i_gs_on),
+        ),
+        ResistanceCoeffs(
+            params.r_dry
+                * params.voltages.v_ds
+                * (currents.i_ds_off - currents.i_ds_on + currents.i_gs_on),
+            params.voltages.v_ds * (currents.i_ds_off - currents.i_ds_on + currents.i_gs_on),
+            currents.i_ds_off
+                * (params.voltages.v_ds - currents.i_ds_on * params.r_dry
+                    + currents.i_gs_on * params.r_dry),
+        ),
+        SaturationCoeffs(
+            params.voltages.v_ds * (currents.i_ds_off - currents.i_ds_on + currents.i_gs_on),
+            currents.i_ds_off
+                * (params.voltages.v_ds - currents.i_ds_on * params.r_dry
+                    + currents.i_gs_on * params.r_dry),
+            currents.i_ds_off * params.r_dry * (currents.i_gs_on - currents.i_ds_on),
+        ),
+    )
 }
 
 /// Pre-calculated coefficients to compute the error function.
@@ -116,36 +356,16 @@ struct ResistanceCoeffs(f32, f32, f32);
 #[derive(Debug)]
 struct SaturationCoeffs(f32, f32, f32);
 
-impl Model for Equation {
-    fn new(params: ModelParams, currents: Currents) -> Self {
-        Equation {
-            func_coeffs: FuncCoeffs(
-                currents.i_gs_on,
-                params.voltages.v_gs
-                    * params.voltages.v_ds
-                    * (currents.i_ds_off - currents.i_ds_on + currents.i_gs_on),
-                params.voltages.v_gs
-                    * currents.i_ds_off
-                    * (params.voltages.v_ds - currents.i_ds_on * params.r_dry
-                        + currents.i_gs_on * params.r_dry),
-                currents.i_ds_off * params.r_dry * (currents.i_ds_on - currents.i_gs_on),
-            ),
-            resistance_coeffs: ResistanceCoeffs(
-                params.r_dry
-                    * params.voltages.v_ds
-                    * (currents.i_ds_off - currents.i_ds_on + currents.i_gs_on),
-                params.voltages.v_ds * (currents.i_ds_off - currents.i_ds_on + currents.i_gs_on),
-                currents.i_ds_off
-                    * (params.voltages.v_ds - currents.i_ds_on * params.r_dry
-                        + currents.i_gs_on * params.r_dry),
-            ),
-            saturation_coeffs: SaturationCoeffs(
-                params.voltages.v_ds * (currents.i_ds_off - currents.i_ds_on + currents.i_gs_on),
-                currents.i_ds_off
-                    * (params.voltages.v_ds - currents.i_ds_on * params.r_dry
-                        + currents.i_gs_on * params.r_dry),
-                currents.i_ds_off * params.r_dry * (currents.i_gs_on - currents.i_ds_on),
-            ),
+impl<P: Borrow<ModelParams>> Model for Equation<P> {
+    type Params = P;
+
+    fn new(params: P, currents: Currents) -> Self {
+        let (func_coeffs, resistance_coeffs, saturation_coeffs) =
+            coefficients(params.borrow(), &currents);
+        Self {
+            func_coeffs,
+            resistance_coeffs,
+            saturation_coeffs,
             currents,
             params,
         }
@@ -156,11 +376,11 @@ impl Model for Equation {
     }
 
     fn params(&self) -> &ModelParams {
-        &self.params
+        self.params.borrow()
     }
 }
 
-impl EquationModel for Equation {
+impl<P: Borrow<ModelParams>> EquationModel for Equation<P> {
     fn value(&self, concentration: f32) -> f32 {
         let m = self.modulation(concentration);
         let r = self.stem_resistance_inv(concentration);
@@ -169,6 +389,15 @@ impl EquationModel for Equation {
             + (self.func_coeffs.1 * r + self.func_coeffs.2 * r * m) / (self.func_coeffs.3 * m)
     }
 
+    /// On the host, the batch is evaluated with [`Equation::value_slice`].
+    #[cfg(feature = "std")]
+    #[inline]
+    fn value_batch<const K: usize>(&self, concentrations: &[f32; K]) -> [f32; K] {
+        let mut values = [0.0; K];
+        self.value_slice(concentrations, &mut values);
+        values
+    }
+
     fn gradient(&self, concentration: f32) -> f32 {
         let m = self.modulation(concentration);
         let r = self.stem_resistance_inv(concentration);
@@ -195,6 +424,77 @@ impl EquationModel for Equation {
     }
 }
 
+/// The number of concentrations evaluated together by
+/// [`Equation::value_slice`].
+#[cfg(feature = "std")]
+const LANES: usize = 8;
+
+#[cfg(feature = "std")]
+impl<P: Borrow<ModelParams>> Equation<P> {
+    /// Calculates the output values of the model for a slice of
+    /// concentrations, evaluating eight of them at a time with the SIMD
+    /// instructions of the host, for large offline sweeps. The grids of the
+    /// algorithms use it through [`EquationModel::value_batch`].
+    ///
+    /// The lanes compute the logarithm and the power with the routines of
+    /// the `wide` crate, whose relative error is below `1e-6` with respect
+    /// to the standard library. With the `deterministic`, `fast-math` or
+    /// `libm` features the concentrations are evaluated one at a time with
+    /// [`EquationModel::value`], to keep the selected mathematical functions.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentrations` - Concentrations of ions in the electrolyte [Molarity].
+    /// * `values` - The buffer receiving the output values of the model, in
+    ///   the same order of the concentrations.
+    ///
+    /// # Panics
+    ///
+    /// If the concentrations and the values have different lengths.
+    pub fn value_slice(&self, concentrations: &[f32], values: &mut [f32]) {
+        assert_eq!(
+            concentrations.len(),
+            values.len(),
+            "a value is needed for every concentration"
+        );
+
+        if cfg!(any(
+            feature = "deterministic",
+            feature = "fast-math",
+            feature = "libm"
+        )) {
+            for (value, &concentration) in values.iter_mut().zip(concentrations) {
+                *value = self.value(concentration);
+            }
+            return;
+        }
+
+        for (values, concentrations) in values.chunks_mut(LANES).zip(concentrations.chunks(LANES)) {
+            // The last chunk is padded with a valid concentration.
+            let mut lanes = [1.0; LANES];
+            lanes[..concentrations.len()].copy_from_slice(concentrations);
+            let lanes = self.value_lanes(f32x8::from(lanes)).to_array();
+            values.copy_from_slice(&lanes[..values.len()]);
+        }
+    }
+
+    /// Calculates the output values of the model for eight concentrations.
+    #[inline]
+    fn value_lanes(&self, concentration: f32x8) -> f32x8 {
+        let mod_params = &self.params().mod_params;
+        let res_params = &self.params().res_params;
+
+        let m = f32x8::splat(mod_params.0) * concentration
+            + f32x8::splat(mod_params.1) * concentration.ln()
+            + f32x8::splat(mod_params.2);
+        let r = f32x8::splat(res_params.0) + f32x8::splat(res_params.1) * concentration.powf(0.955);
+
+        f32x8::splat(self.func_coeffs.0)
+            + (f32x8::splat(self.func_coeffs.1) * r + f32x8::splat(self.func_coeffs.2) * r * m)
+                / (f32x8::splat(self.func_coeffs.3) * m)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -291,4 +591,177 @@ mod tests {
 
         assert!((model.saturation(1.0) - 3.236_111_1).abs() < 1e-6);
     }
+
+    #[test]
+    fn test_precomputed() {
+        const MODEL: Equation = Equation::precomputed(
+            ModelParams {
+                mod_params: ModulationParams(1.0, 2.0, 3.0),
+                r_dry: 4.0,
+                res_params: StemResistanceInvParams(5.0, 6.0),
+                voltages: Voltages {
+                    v_ds: 7.0,
+                    v_gs: 8.0,
+                },
+            },
+            Currents {
+                i_ds_off: 9.0,
+                i_ds_on: 10.0,
+                i_gs_on: 11.0,
+            },
+        );
+        let (params, currents) = mock_params();
+        let model = Equation::new(params, currents);
+
+        let coeffs = |m: &Equation| {
+            [
+                m.func_coeffs.0,
+                m.func_coeffs.1,
+                m.func_coeffs.2,
+                m.func_coeffs.3,
+                m.resistance_coeffs.0,
+                m.resistance_coeffs.1,
+                m.resistance_coeffs.2,
+                m.saturation_coeffs.0,
+                m.saturation_coeffs.1,
+                m.saturation_coeffs.2,
+            ]
+        };
+        assert_eq!(coeffs(&MODEL), coeffs(&model));
+        assert_eq!(MODEL.params(), model.params());
+        assert_eq!(MODEL.currents(), model.currents());
+    }
+
+    #[test]
+    fn test_borrowed() {
+        const PARAMS: ModelParams = ModelParams {
+            mod_params: ModulationParams(1.0, 2.0, 3.0),
+            r_dry: 4.0,
+            res_params: StemResistanceInvParams(5.0, 6.0),
+            voltages: Voltages {
+                v_ds: 7.0,
+                v_gs: 8.0,
+            },
+        };
+        const MODEL: Equation<&ModelParams> = Equation::precomputed_ref(
+            &PARAMS,
+            Currents {
+                i_ds_off: 9.0,
+                i_ds_on: 10.0,
+                i_gs_on: 11.0,
+            },
+        );
+        let (params, currents) = mock_params();
+        let owned = Equation::new(params.clone(), currents);
+        let borrowed = Equation::new(&params, currents);
+
+        // The borrowed parameters are not copied in the model.
+        assert!(core::ptr::eq(borrowed.params(), &params));
+        assert!(size_of::<Equation<&ModelParams>>() < size_of::<Equation>());
+        for concentration in [1e-3, 1e-2, 1.0] {
+            assert_eq!(borrowed.value(concentration), owned.value(concentration));
+            assert_eq!(MODEL.value(concentration), owned.value(concentration));
+            assert_eq!(
+                borrowed.gradient(concentration),
+                owned.gradient(concentration)
+            );
+        }
+        assert_eq!(
+            Equation::try_new(
+                &params,
+                Currents {
+                    i_ds_off: 0.0,
+                    ..currents
+                }
+            )
+            .err(),
+            Some(EquationError::ZeroOffCurrent)
+        );
+    }
+
+    #[test]
+    fn test_try_new() {
+        let (params, currents) = mock_params();
+        assert!(Equation::try_new(params.clone(), currents).is_ok());
+
+        let zero_off = Currents {
+            i_ds_off: 0.0,
+            ..currents
+        };
+        assert_eq!(
+            Equation::try_new(params.clone(), zero_off).unwrap_err(),
+            EquationError::ZeroOffCurrent
+        );
+
+        let equal_on = Currents {
+            i_ds_on: 11.0,
+            ..currents
+        };
+        assert_eq!(
+            Equation::try_new(params.clone(), equal_on).unwrap_err(),
+            EquationError::OnCurrentsEqual
+        );
+
+        let non_finite = Currents {
+            i_gs_on: f32::NAN,
+            ..currents
+        };
+        assert_eq!(
+            Equation::try_new(params.clone(), non_finite).unwrap_err(),
+            EquationError::NonFiniteCurrent
+        );
+
+        let dry = ModelParams {
+            r_dry: 0.0,
+            ..params
+        };
+        assert_eq!(
+            Equation::try_new(dry, currents).unwrap_err(),
+            EquationError::ZeroDryResistance
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_value_slice() {
+        let params = ModelParams {
+            mod_params: ModulationParams(0.0, -0.01463, -0.32),
+            r_dry: 38.2,
+            res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+            voltages: Voltages {
+                v_ds: -0.05,
+                v_gs: 0.5,
+            },
+        };
+        let currents = Currents {
+            i_ds_off: -0.0030365,
+            i_ds_on: -0.0026829,
+            i_gs_on: 1.169828e-6,
+        };
+        let model = Equation::new(params, currents);
+
+        // The length is not a multiple of the lanes.
+        let concentrations: std::vec::Vec<f32> =
+            (0..1_003).map(|i| 1e-4 + i as f32 * 1e-4).collect();
+        let mut values = std::vec![0.0; concentrations.len()];
+        model.value_slice(&concentrations, &mut values);
+
+        // The terms of the value cancel out close to the solution, so the
+        // error is relative to the gate-source current.
+        for (&concentration, &value) in concentrations.iter().zip(&values) {
+            let expected = model.value(concentration);
+            assert!(
+                (value - expected).abs() <= 1e-5 * currents.i_gs_on,
+                "{concentration}: {value} instead of {expected}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[should_panic(expected = "a value is needed for every concentration")]
+    fn test_value_slice_length() {
+        let (params, currents) = mock_params();
+        Equation::new(params, currents).value_slice(&[1.0; 3], &mut [0.0; 2]);
+    }
 }
@@ -1,12 +1,76 @@
+pub use counting::*;
 pub use equation::*;
+#[cfg(feature = "system")]
 pub use system::*;
+#[cfg(feature = "system")]
+pub use verification::*;
 
+mod counting;
 mod equation;
+#[cfg(feature = "system")]
 mod system;
+#[cfg(feature = "system")]
+mod verification;
 
 #[allow(unused_imports)]
 use micromath::F32Ext;
 
+#[cfg(all(feature = "deterministic", feature = "libm"))]
+compile_error!("the `deterministic` and `libm` features are mutually exclusive");
+#[cfg(all(feature = "deterministic", feature = "fast-math"))]
+compile_error!("the `deterministic` and `fast-math` features are mutually exclusive");
+#[cfg(all(feature = "libm", feature = "fast-math"))]
+compile_error!("the `libm` and `fast-math` features are mutually exclusive");
+
+/// Computes the natural logarithm for the models.
+///
+/// Without the standard library, e.g. on the firmware, the approximation of
+/// `micromath` is used, while the host uses the accurate implementation of
+/// the standard library as soon as it is linked, even by a dependency. With
+/// the `deterministic` feature, the approximation is used on every target,
+/// so that the host computes the same bits of the firmware.
+///
+/// With the `libm` feature, the implementation of `libm`, accurate to about
+/// one ulp, is used on every target, trading cycles for accuracy. With the
+/// `fast-math` feature, the logarithm is interpolated from a lookup table.
+/// The features are mutually exclusive.
+#[inline]
+fn ln(x: f32) -> f32 {
+    #[cfg(feature = "deterministic")]
+    return F32Ext::ln(x);
+    #[cfg(all(feature = "libm", not(feature = "deterministic")))]
+    return libm::logf(x);
+    #[cfg(all(
+        feature = "fast-math",
+        not(any(feature = "deterministic", feature = "libm"))
+    ))]
+    return crate::utils::fast_math::ln(x);
+    #[cfg(not(any(feature = "deterministic", feature = "libm", feature = "fast-math")))]
+    return x.ln();
+}
+
+/// Raises a number to a floating point power for the models, see [`ln`] for
+/// the features selecting the implementation.
+///
+/// With the `fast-math` feature, the power is computed as `exp(n * ln(x))`
+/// from the logarithm table and a polynomial approximation of `exp`.
+#[inline]
+fn powf(x: f32, n: f32) -> f32 {
+    #[cfg(feature = "deterministic")]
+    return F32Ext::powf(x, n);
+    #[cfg(all(feature = "libm", not(feature = "deterministic")))]
+    return libm::powf(x, n);
+    #[cfg(all(
+        feature = "fast-math",
+        not(any(feature = "deterministic", feature = "libm"))
+    ))]
+    return crate::utils::fast_math::powf(x, n);
+    #[cfg(not(any(feature = "deterministic", feature = "libm", feature = "fast-math")))]
+    return x.powf(n);
+}
+
+use core::borrow::Borrow;
+
 use crate::params::{Currents, ModelParams};
 
 /// Common trait for all the formulations of the mathematical model
@@ -15,18 +79,23 @@ use crate::params::{Currents, ModelParams};
 /// This trait is implemented by the [`Equation`] and [`System`] structs, that
 /// provide a formulation of the mathematical model of the Bioristor device.
 pub trait Model {
+    /// The parameters of the mathematical model taken by [`Model::new`],
+    /// either owned, i.e. [`ModelParams`], or borrowed, i.e.
+    /// `&ModelParams`.
+    type Params: Borrow<ModelParams>;
+
     /// Creates a new instance of the model.
     ///
     /// # Arguments
     ///
     /// * `params` - The parameters of the mathematical model.
     /// * `currents` - The output currents of the devices,
-    ///     i.e. the independent variables of the model.
+    ///   i.e. the independent variables of the model.
     ///
     /// # Returns
     ///
     /// A new instance of the model.
-    fn new(params: ModelParams, currents: Currents) -> Self;
+    fn new(params: Self::Params, currents: Currents) -> Self;
 
     /// Returns a reference to the parameters of the mathematical model.
     ///
@@ -54,7 +123,7 @@ pub trait Model {
     #[inline]
     fn modulation(&self, concentration: f32) -> f32 {
         let params = self.params().mod_params;
-        params.0 * concentration + params.1 * concentration.ln() + params.2
+        params.0 * concentration + params.1 * ln(concentration) + params.2
     }
 
     /// Calculates the gradient of the modulation of the channel.
@@ -84,7 +153,7 @@ pub trait Model {
     #[inline]
     fn stem_resistance_inv(&self, concentration: f32) -> f32 {
         let params = self.params().res_params;
-        params.0 + params.1 * concentration.powf(0.955)
+        params.0 + params.1 * powf(concentration, 0.955)
     }
 
     /// Calculates the gradient of the inverse of the stem resistance.
@@ -99,7 +168,7 @@ pub trait Model {
     #[inline]
     fn stem_resistance_inv_gradient(&self, concentration: f32) -> f32 {
         let params = self.params().res_params;
-        params.1 * 0.955 * concentration.powf(-0.045)
+        params.1 * 0.955 * powf(concentration, -0.045)
     }
 }
 
@@ -135,6 +204,8 @@ mod tests {
     }
 
     impl Model for ModelMock {
+        type Params = ModelParams;
+
         fn new(params: ModelParams, currents: Currents) -> Self {
             ModelMock { params, currents }
         }
@@ -158,6 +229,10 @@ mod tests {
     }
 
     #[test]
+    #[cfg_attr(
+        feature = "deterministic",
+        ignore = "expected values of the accurate mathematical functions"
+    )]
     fn test_resistivity() {
         let (params, currents) = mock_params();
         let model = ModelMock::new(params, currents);
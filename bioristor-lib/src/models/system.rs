@@ -2,6 +2,8 @@
 use micromath::F32Ext;
 use nalgebra::Matrix3;
 
+use core::borrow::Borrow;
+
 use crate::{
     models::Model,
     params::{Currents, ModelParams, Variables},
@@ -22,6 +24,32 @@ pub trait SystemModel: Model {
     /// The output value of the model.
     fn value(&self, variables: Variables) -> [(f32, f32); 3];
 
+    /// Fixes the concentration and returns a function that calculates the
+    /// output value of the model for the given resistance and saturation.
+    ///
+    /// Implementations compute the terms of the model that only depend on
+    /// the concentration once, so that the returned function is cheaper to
+    /// evaluate over a grid of resistances and saturations than
+    /// [`SystemModel::value`].
+    ///
+    /// # Arguments
+    ///
+    /// * `concentration` - The concentration of ions in the electrolyte [Molarity].
+    ///
+    /// # Returns
+    ///
+    /// A function of the resistance and the saturation returning the same
+    /// output value of [`SystemModel::value`].
+    fn value_at(&self, concentration: f32) -> impl Fn(f32, f32) -> [(f32, f32); 3] + '_ {
+        move |resistance, saturation| {
+            self.value(Variables {
+                concentration,
+                resistance,
+                saturation,
+            })
+        }
+    }
+
     /// Calculates the Jacobian matrix of the model for the given variables.
     ///
     /// # Arguments
@@ -72,23 +100,28 @@ pub trait SystemModel: Model {
 /// let value = model.value(variables);
 /// let error = MeanRelative::evaluate(value);
 /// ```
+///
+/// Like the [`Equation`](crate::models::Equation), the model can borrow the
+/// parameters instead of owning a copy, with `System::new(&PARAMS, currents)`.
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct System {
-    /// The parameters of the mathematical model.
-    params: ModelParams,
+pub struct System<P = ModelParams> {
+    /// The parameters of the mathematical model, owned or borrowed.
+    params: P,
 
     /// The output currents of the devices.
     currents: Currents,
 }
 
-impl Model for System {
-    fn new(params: ModelParams, currents: Currents) -> Self {
+impl<P: Borrow<ModelParams>> Model for System<P> {
+    type Params = P;
+
+    fn new(params: P, currents: Currents) -> Self {
         Self { params, currents }
     }
 
     fn params(&self) -> &ModelParams {
-        &self.params
+        self.params.borrow()
     }
 
     fn currents(&self) -> &Currents {
@@ -96,59 +129,103 @@ impl Model for System {
     }
 }
 
-impl SystemModel for System {
-    fn value(&self, variables: Variables) -> [(f32, f32); 3] {
+impl<P: Borrow<ModelParams>> System<P> {
+    /// Calculates the output value of the model once the terms that only
+    /// depend on the concentration are known.
+    ///
+    /// The denominators of the two drain-source currents share a single
+    /// division, which is by far the most expensive operation of the model
+    /// on microcontrollers without a fast divider.
+    ///
+    /// # Arguments
+    ///
+    /// * `modulation_inv` - The reciprocal of the modulation plus one.
+    /// * `stem_resistance_inv` - The inverse of the stem resistance.
+    /// * `resistance` - The resistance of the wet channel when the gate is off.
+    /// * `saturation` - The water saturation.
+    ///
+    /// # Returns
+    ///
+    /// The output value of the model.
+    #[inline]
+    fn value_with(
+        &self,
+        modulation_inv: f32,
+        stem_resistance_inv: f32,
+        resistance: f32,
+        saturation: f32,
+    ) -> [(f32, f32); 3] {
+        let r_dry = self.params().r_dry;
+        let denominator_on = r_dry + saturation * (resistance * modulation_inv - r_dry);
+        let denominator_off = r_dry + saturation * (resistance - r_dry);
+        let scale = self.params().voltages.v_ds / (denominator_on * denominator_off);
+
         [
             (
                 self.currents.i_ds_on,
-                self.currents.i_gs_on
-                    + self.params.voltages.v_ds
-                        / (self.params.r_dry
-                            + variables.saturation
-                                * (variables.resistance
-                                    / (self.modulation(variables.concentration) + 1.0)
-                                    - self.params.r_dry)),
-            ),
-            (
-                self.currents.i_ds_off,
-                self.params.voltages.v_ds
-                    / (self.params.r_dry
-                        + variables.saturation * (variables.resistance - self.params.r_dry)),
+                self.currents.i_gs_on + denominator_off * scale,
             ),
+            (self.currents.i_ds_off, denominator_on * scale),
             (
                 self.currents.i_gs_on,
-                self.params.voltages.v_gs
-                    * variables.saturation
-                    * self.stem_resistance_inv(variables.concentration),
+                self.params().voltages.v_gs * saturation * stem_resistance_inv,
             ),
         ]
     }
+}
+
+impl<P: Borrow<ModelParams>> SystemModel for System<P> {
+    fn value(&self, variables: Variables) -> [(f32, f32); 3] {
+        self.value_with(
+            1.0 / (self.modulation(variables.concentration) + 1.0),
+            self.stem_resistance_inv(variables.concentration),
+            variables.resistance,
+            variables.saturation,
+        )
+    }
+
+    fn value_at(&self, concentration: f32) -> impl Fn(f32, f32) -> [(f32, f32); 3] + '_ {
+        let modulation_inv = 1.0 / (self.modulation(concentration) + 1.0);
+        let stem_resistance_inv = self.stem_resistance_inv(concentration);
+        move |resistance, saturation| {
+            self.value_with(modulation_inv, stem_resistance_inv, resistance, saturation)
+        }
+    }
 
     fn jacobian(&self, variables: Variables) -> Matrix3<f32> {
-        let m = self.modulation(variables.concentration);
+        let m_inv = 1.0 / (self.modulation(variables.concentration) + 1.0);
         let dm = self.modulation_gradient(variables.concentration);
         let r = self.stem_resistance_inv(variables.concentration);
         let dr = self.stem_resistance_inv_gradient(variables.concentration);
 
-        let denominator1 = (self.params.r_dry
-            - variables.saturation * (self.params.r_dry - variables.resistance / (m + 1.0)))
-            .powi(2);
-        let denominator2 = (self.params.r_dry
-            + variables.saturation * (variables.resistance - self.params.r_dry))
-            .powi(2);
+        let Variables {
+            resistance,
+            saturation,
+            ..
+        } = variables;
+        let r_dry = self.params().r_dry;
+        let v_ds = self.params().voltages.v_ds;
+        let v_gs = self.params().voltages.v_gs;
+
+        // A single division gives the reciprocals of both denominators.
+        let denominator_on = r_dry + saturation * (resistance * m_inv - r_dry);
+        let denominator_off = r_dry + saturation * (resistance - r_dry);
+        let product_inv = 1.0 / (denominator_on * denominator_off);
+        let on_inv = denominator_off * product_inv;
+        let off_inv = denominator_on * product_inv;
+        let on_inv2 = v_ds * on_inv * on_inv;
+        let off_inv2 = v_ds * off_inv * off_inv;
 
         Matrix3::new(
-            -(variables.resistance * variables.saturation * self.params.voltages.v_ds * dm)
-                / ((m + 1.0).powi(2) * denominator1),
-            (variables.saturation * self.params.voltages.v_ds) / ((m + 1.0) * denominator1),
-            -(self.params.voltages.v_ds * (self.params.r_dry - variables.resistance / (m + 1.0)))
-                / denominator1,
+            -resistance * saturation * dm * m_inv * m_inv * on_inv2,
+            saturation * m_inv * on_inv2,
+            (resistance * m_inv - r_dry) * on_inv2,
             0.0,
-            (variables.saturation * self.params.voltages.v_ds) / denominator2,
-            (self.params.voltages.v_ds * (variables.resistance - self.params.r_dry)) / denominator2,
-            -variables.saturation * self.params.voltages.v_gs * dr,
+            saturation * off_inv2,
+            (resistance - r_dry) * off_inv2,
+            -saturation * v_gs * dr,
             0.0,
-            -self.params.voltages.v_gs * r,
+            -v_gs * r,
         )
     }
 }
@@ -216,6 +293,42 @@ mod tests {
     }
 
     #[test]
+    fn test_borrowed() {
+        let (params, currents) = mock_params();
+        let owned = System::new(params.clone(), currents);
+        let borrowed = System::new(&params, currents);
+
+        let variables = Variables {
+            concentration: 0.1,
+            resistance: 0.2,
+            saturation: 0.3,
+        };
+        assert!(core::ptr::eq(borrowed.params(), &params));
+        assert_eq!(borrowed.value(variables), owned.value(variables));
+        assert_eq!(borrowed.jacobian(variables), owned.jacobian(variables));
+    }
+
+    #[test]
+    fn test_value_at() {
+        let (params, currents) = mock_params();
+        let model = System::new(params, currents);
+
+        let value = model.value_at(0.1);
+        for (resistance, saturation) in [(0.2, 0.3), (5.0, 0.9), (40.0, 0.0)] {
+            let variables = Variables {
+                concentration: 0.1,
+                resistance,
+                saturation,
+            };
+            assert_eq!(value(resistance, saturation), model.value(variables));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(
+        any(feature = "deterministic", feature = "fast-math"),
+        ignore = "expected values of the accurate mathematical functions"
+    )]
     fn test_jacobian() {
         let (params, currents) = mock_params();
         let model = System::new(params, currents);
@@ -0,0 +1,277 @@
+use nalgebra::Matrix3;
+
+use crate::models::{Model, System, SystemModel};
+use crate::params::{Currents, ModelParams, Variables};
+
+/// The residuals of the three equations of the system model at a solution,
+/// see [`verify_solution`].
+///
+/// The residual of an equation is its relative error:
+/// `|measured - predicted| / (|measured| + |predicted|)`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Verification {
+    /// The residual of the drain-source current with the gate off.
+    pub i_ds_off: f32,
+
+    /// The residual of the drain-source current with the gate on.
+    pub i_ds_on: f32,
+
+    /// The residual of the gate-source current with the gate on.
+    pub i_gs_on: f32,
+
+    /// The tolerance of the residuals.
+    pub tolerance: f32,
+}
+
+impl Verification {
+    /// Returns the residuals, in the order of the equations of the
+    /// [`SystemModel`]: `i_ds_on`, `i_ds_off` and `i_gs_on`.
+    #[inline]
+    pub fn residuals(&self) -> [f32; 3] {
+        [self.i_ds_on, self.i_ds_off, self.i_gs_on]
+    }
+
+    /// Returns the largest residual.
+    pub fn max_residual(&self) -> f32 {
+        self.residuals().into_iter().fold(0.0, f32::max)
+    }
+
+    /// Returns whether all the residuals are within the tolerance.
+    ///
+    /// A residual that is not finite is never within the tolerance.
+    pub fn is_consistent(&self) -> bool {
+        self.residuals()
+            .into_iter()
+            .all(|residual| residual <= self.tolerance)
+    }
+}
+
+/// Substitutes a solution into the three equations of the system model and
+/// computes their residuals.
+///
+/// The equation model reduces the system to a single equation in the
+/// concentration, assuming that the measured currents are consistent with
+/// each other. When they are not, e.g. because of a leakage of the gate, the
+/// reduced equation can still have a root while the system has no solution:
+/// the residuals tell these cases apart.
+///
+/// # Arguments
+///
+/// * `model_params` - The parameters of the model.
+/// * `currents` - The measured currents.
+/// * `vars` - The solution, e.g. found with the equation model.
+/// * `tolerance` - The maximum residual of a consistent solution.
+///
+/// # Returns
+///
+/// The residuals of the equations.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::models::{verify_solution, Equation, EquationModel, Model};
+/// use bioristor_lib::params::{
+///     Currents, ModelParams, ModulationParams, StemResistanceInvParams, Variables, Voltages,
+/// };
+///
+/// let params = ModelParams {
+///     mod_params: ModulationParams(0.0, -0.01463, -0.32),
+///     r_dry: 38.2,
+///     res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+///     voltages: Voltages {
+///         v_ds: -0.05,
+///         v_gs: 0.5,
+///     },
+/// };
+/// let currents = Currents {
+///     i_ds_off: -0.0030365,
+///     i_ds_on: -0.0026829,
+///     i_gs_on: 1.169828e-6,
+/// };
+///
+/// let model = Equation::new(params.clone(), currents);
+/// let concentration = 1e-2;
+/// let vars = Variables {
+///     concentration,
+///     resistance: model.resistance(concentration),
+///     saturation: model.saturation(concentration),
+/// };
+/// let verification = verify_solution(&params, &currents, &vars, 1e-3);
+/// let consistent = verification.is_consistent();
+/// ```
+pub fn verify_solution(
+    model_params: &ModelParams,
+    currents: &Currents,
+    vars: &Variables,
+    tolerance: f32,
+) -> Verification {
+    let model = System::new(model_params, *currents);
+    let [i_ds_on, i_ds_off, i_gs_on] = model.value(*vars).map(|(measured, predicted)| {
+        (measured - predicted).abs() / (measured.abs() + predicted.abs() + f32::EPSILON)
+    });
+
+    Verification {
+        i_ds_off,
+        i_ds_on,
+        i_gs_on,
+        tolerance,
+    }
+}
+
+/// Computes the condition number of the Jacobian matrix of the system model
+/// at a solution.
+///
+/// The Jacobian matrix is taken in relative terms, i.e. its columns are
+/// scaled by the variables and its rows by the predicted currents, so that
+/// the condition number bounds how much a relative error of the currents,
+/// e.g. the noise of the measurement, is amplified in the relative error of
+/// the variables. The condition number is computed in the 1-norm.
+///
+/// # Arguments
+///
+/// * `model_params` - The parameters of the model.
+/// * `currents` - The measured currents.
+/// * `vars` - The solution, e.g. found with the equation model.
+///
+/// # Returns
+///
+/// The condition number, infinite if the Jacobian matrix is singular or not
+/// finite.
+pub fn condition_number(model_params: &ModelParams, currents: &Currents, vars: &Variables) -> f32 {
+    let model = System::new(model_params, *currents);
+    let predicted = model.value(*vars).map(|(_, predicted)| predicted);
+    let scale = [vars.concentration, vars.resistance, vars.saturation];
+    let jacobian = model.jacobian(*vars);
+    let jacobian = Matrix3::from_fn(|i, j| jacobian[(i, j)] * scale[j] / predicted[i]);
+
+    match inverse(&jacobian) {
+        Some(inverse) => {
+            let condition = norm_1(&jacobian) * norm_1(&inverse);
+            if condition.is_finite() {
+                condition
+            } else {
+                f32::INFINITY
+            }
+        }
+        None => f32::INFINITY,
+    }
+}
+
+/// Computes the inverse of a 3x3 matrix from its cofactors.
+///
+/// With cyclic indices, the cofactors of a 3x3 matrix already have the sign
+/// of their position.
+fn inverse(m: &Matrix3<f32>) -> Option<Matrix3<f32>> {
+    let cofactor = |i: usize, j: usize| {
+        let (i1, i2, j1, j2) = ((i + 1) % 3, (i + 2) % 3, (j + 1) % 3, (j + 2) % 3);
+        m[(i1, j1)] * m[(i2, j2)] - m[(i1, j2)] * m[(i2, j1)]
+    };
+
+    let det = (0..3).map(|j| m[(0, j)] * cofactor(0, j)).sum::<f32>();
+    if det == 0.0 || !det.is_finite() {
+        return None;
+    }
+    Some(Matrix3::from_fn(|i, j| cofactor(j, i) / det))
+}
+
+/// Computes the 1-norm of a matrix, i.e. the largest sum of the absolute
+/// values of a column.
+fn norm_1(m: &Matrix3<f32>) -> f32 {
+    m.column_iter()
+        .map(|column| column.iter().map(|x| x.abs()).sum::<f32>())
+        .fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Equation, EquationModel};
+    use crate::params::{ModulationParams, StemResistanceInvParams, Voltages};
+
+    const MODEL_PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    const VARS: Variables = Variables {
+        concentration: 1e-2,
+        resistance: 40.0,
+        saturation: 0.8,
+    };
+
+    /// Currents of a device in the state of [`VARS`].
+    fn currents() -> Currents {
+        let zero = Currents {
+            i_ds_off: 0.0,
+            i_ds_on: 0.0,
+            i_gs_on: 0.0,
+        };
+        let [(_, i_ds_on), (_, i_ds_off), (_, i_gs_on)] =
+            System::new(MODEL_PARAMS, zero).value(VARS);
+        // The model adds the measured gate-source current, zero here.
+        Currents {
+            i_ds_off,
+            i_ds_on: i_ds_on + i_gs_on,
+            i_gs_on,
+        }
+    }
+
+    #[test]
+    fn test_verify_solution() {
+        let currents = currents();
+        let model = Equation::new(MODEL_PARAMS, currents);
+        let solution = |concentration| Variables {
+            concentration,
+            resistance: model.resistance(concentration),
+            saturation: model.saturation(concentration),
+        };
+
+        let verification = verify_solution(&MODEL_PARAMS, &currents, &solution(1e-2), 1e-3);
+        assert!(verification.is_consistent(), "{verification:?}");
+
+        // The back-substitution satisfies the drain-source equations for any
+        // concentration, but not the gate-source one.
+        let verification = verify_solution(&MODEL_PARAMS, &currents, &solution(5e-2), 1e-3);
+        assert!(!verification.is_consistent());
+        assert!(verification.i_gs_on > 1e-3);
+        assert_eq!(verification.max_residual(), verification.i_gs_on);
+
+        let vars = Variables {
+            saturation: f32::NAN,
+            ..VARS
+        };
+        assert!(!verify_solution(&MODEL_PARAMS, &currents, &vars, 1e-3).is_consistent());
+    }
+
+    #[test]
+    fn test_inverse() {
+        let m = Matrix3::new(2.0, 0.0, 1.0, 1.0, 3.0, 0.0, 0.0, 1.0, 4.0);
+        let product = m * inverse(&m).unwrap();
+        assert!((product - Matrix3::identity()).abs().max() < 1e-6);
+        assert_eq!(norm_1(&m), 5.0);
+
+        assert_eq!(inverse(&Matrix3::zeros()), None);
+    }
+
+    #[test]
+    fn test_condition_number() {
+        let condition = condition_number(&MODEL_PARAMS, &currents(), &VARS);
+        assert!(condition.is_finite() && condition >= 1.0, "{condition}");
+
+        // The currents do not depend on the other variables without water.
+        let vars = Variables {
+            saturation: 0.0,
+            ..VARS
+        };
+        assert_eq!(
+            condition_number(&MODEL_PARAMS, &currents(), &vars),
+            f32::INFINITY
+        );
+    }
+}
@@ -0,0 +1,295 @@
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::{params::Currents, utils::fast_math};
+
+/// Seedable pseudo-random number generator (xorshift64*).
+///
+/// It is not suitable for cryptography, but it is fast, small and gives the
+/// same sequence on every target, so that the evaluations are reproducible.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    /// The state of the generator, never zero.
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a new generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed of the sequence, any value.
+    pub const fn new(seed: u64) -> Self {
+        // Spread the seed, which must not be zero.
+        let state = (seed ^ 0x9E37_79B9_7F4A_7C15).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        Self {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+
+    /// Returns the next random integer.
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 32) as u32
+    }
+
+    /// Returns a random number uniformly distributed in `[0, 1)`.
+    pub fn uniform(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Returns a random number with the standard normal distribution, with
+    /// the Box-Muller transform.
+    pub fn gaussian(&mut self) -> f32 {
+        let u1 = 1.0 - self.uniform();
+        let u2 = self.uniform();
+        fast_math::sqrt(-2.0 * u1.ln()) * (2.0 * core::f32::consts::PI * u2).cos()
+    }
+}
+
+/// The models of the noise affecting the currents.
+///
+/// The amplitudes are expressed in Ampere.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NoiseModel {
+    /// White gaussian noise, e.g. thermal noise of the front-end.
+    Gaussian {
+        /// The standard deviation of the noise.
+        std_dev: f32,
+    },
+
+    /// Quantization of the ADC, rounding to the nearest multiple of the step.
+    Quantization {
+        /// The current corresponding to one LSB of the ADC.
+        step: f32,
+    },
+
+    /// Bursts of stronger gaussian noise, e.g. caused by the switching of
+    /// other loads, affecting several consecutive samples.
+    Burst {
+        /// The number of samples affected by a burst.
+        length: u32,
+
+        /// The probability that a burst starts at every sample.
+        probability: f32,
+
+        /// The standard deviation of the noise during a burst.
+        std_dev: f32,
+    },
+
+    /// Isolated spikes with random sign.
+    Outlier {
+        /// The amplitude of the spikes.
+        magnitude: f32,
+
+        /// The probability of a spike on every current.
+        probability: f32,
+    },
+}
+
+/// Perturbs the currents with a combination of noise models, to evaluate the
+/// sensitivity of the algorithms to the noise of the acquisition.
+///
+/// The models are applied in order to every current, so e.g. the
+/// quantization should be the last one.
+///
+/// # Type parameters
+///
+/// * `N` - The number of noise models.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::noise::{Noise, NoiseModel};
+/// use bioristor_lib::params::Currents;
+///
+/// let mut noise = Noise::new(
+///     [
+///         NoiseModel::Gaussian { std_dev: 1e-7 },
+///         NoiseModel::Quantization { step: 1e-6 },
+///     ],
+///     42,
+/// );
+/// let currents = Currents {
+///     i_ds_off: -1.25e-3,
+///     i_ds_on: -0.85e-3,
+///     i_gs_on: 2.4e-6,
+/// };
+/// let noisy = noise.perturb(&currents);
+/// assert!((noisy.i_ds_off - currents.i_ds_off).abs() < 2e-6);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Noise<const N: usize> {
+    /// The remaining samples of the burst of every model.
+    bursts: [u32; N],
+
+    /// The noise models.
+    models: [NoiseModel; N],
+
+    /// The generator of the noise.
+    rng: Rng,
+}
+
+impl<const N: usize> Noise<N> {
+    /// Creates a new noise generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `models` - The noise models, applied in order.
+    /// * `seed` - The seed of the generator, giving a reproducible sequence.
+    pub fn new(models: [NoiseModel; N], seed: u64) -> Self {
+        Self {
+            bursts: [0; N],
+            models,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Returns the noise models.
+    #[inline]
+    pub fn models(&self) -> &[NoiseModel; N] {
+        &self.models
+    }
+
+    /// Perturbs the currents of a sample.
+    ///
+    /// # Arguments
+    ///
+    /// * `currents` - The exact currents.
+    ///
+    /// # Returns
+    ///
+    /// The noisy currents.
+    pub fn perturb(&mut self, currents: &Currents) -> Currents {
+        let mut values = [currents.i_ds_off, currents.i_ds_on, currents.i_gs_on];
+        for (model, burst) in self.models.iter().zip(self.bursts.iter_mut()) {
+            if let NoiseModel::Burst {
+                length,
+                probability,
+                ..
+            } = *model
+            {
+                if *burst == 0 && self.rng.uniform() < probability {
+                    *burst = length;
+                }
+            }
+            for value in values.iter_mut() {
+                *value = match *model {
+                    NoiseModel::Gaussian { std_dev } => *value + std_dev * self.rng.gaussian(),
+                    NoiseModel::Quantization { step } => (*value / step).round() * step,
+                    NoiseModel::Burst { std_dev, .. } if *burst > 0 => {
+                        *value + std_dev * self.rng.gaussian()
+                    }
+                    NoiseModel::Burst { .. } => *value,
+                    NoiseModel::Outlier {
+                        magnitude,
+                        probability,
+                    } => {
+                        if self.rng.uniform() < probability {
+                            let sign = if self.rng.next_u32() & 1 == 0 {
+                                1.0
+                            } else {
+                                -1.0
+                            };
+                            *value + sign * magnitude
+                        } else {
+                            *value
+                        }
+                    }
+                };
+            }
+            *burst = burst.saturating_sub(1);
+        }
+
+        let [i_ds_off, i_ds_on, i_gs_on] = values;
+        Currents {
+            i_ds_off,
+            i_ds_on,
+            i_gs_on,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURRENTS: Currents = Currents {
+        i_ds_off: -1.25e-3,
+        i_ds_on: -0.85e-3,
+        i_gs_on: 2.4e-6,
+    };
+
+    #[test]
+    fn test_rng() {
+        let mut a = Rng::new(1);
+        let mut b = Rng::new(1);
+        assert!((0..100).all(|_| a.next_u32() == b.next_u32()));
+        assert_ne!(Rng::new(1).next_u32(), Rng::new(2).next_u32());
+
+        // Moments of the standard normal distribution.
+        let mut rng = Rng::new(7);
+        let samples = 10_000;
+        let (mut sum, mut sum_sq) = (0.0, 0.0);
+        for _ in 0..samples {
+            let x = rng.gaussian();
+            sum += x;
+            sum_sq += x * x;
+        }
+        let mean = sum / samples as f32;
+        assert!(mean.abs() < 0.05, "{mean}");
+        assert!((sum_sq / samples as f32 - mean * mean - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_quantization() {
+        let mut noise = Noise::new([NoiseModel::Quantization { step: 1e-4 }], 0);
+        let noisy = noise.perturb(&CURRENTS);
+        assert!((noisy.i_ds_off - -1.3e-3).abs() < 1e-9);
+        assert!((noisy.i_ds_on - -0.9e-3).abs() < 1e-9);
+        assert_eq!(noisy.i_gs_on, 0.0);
+    }
+
+    #[test]
+    fn test_burst() {
+        let mut noise = Noise::new(
+            [NoiseModel::Burst {
+                length: 3,
+                probability: 1.0,
+                std_dev: 1e-4,
+            }],
+            0,
+        );
+        // A new burst starts as soon as the previous one ends.
+        assert!((0..10).all(|_| noise.perturb(&CURRENTS) != CURRENTS));
+
+        let mut noise = Noise::new(
+            [NoiseModel::Burst {
+                length: 3,
+                probability: 0.0,
+                std_dev: 1e-4,
+            }],
+            0,
+        );
+        assert_eq!(noise.perturb(&CURRENTS), CURRENTS);
+    }
+
+    #[test]
+    fn test_outlier() {
+        let mut noise = Noise::new(
+            [NoiseModel::Outlier {
+                magnitude: 1.0,
+                probability: 0.1,
+            }],
+            3,
+        );
+        let outliers = (0..1000)
+            .map(|_| noise.perturb(&CURRENTS))
+            .filter(|noisy| (noisy.i_ds_off - CURRENTS.i_ds_off).abs() > 0.5)
+            .count();
+        assert!((50..150).contains(&outliers), "{outliers}");
+    }
+}
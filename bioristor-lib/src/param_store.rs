@@ -0,0 +1,941 @@
+use embedded_storage::nor_flash::NorFlash;
+
+#[cfg(feature = "adaptive2")]
+use crate::algorithms::Adaptive2Params;
+#[cfg(feature = "adaptive")]
+use crate::algorithms::AdaptiveParams;
+#[cfg(feature = "brute-force")]
+use crate::algorithms::BruteForceParams;
+#[cfg(feature = "gradient-descent")]
+use crate::algorithms::GradientDescentParams;
+#[cfg(feature = "newton")]
+use crate::algorithms::NewtonParams;
+use crate::algorithms::SaturationPolicy;
+use crate::params::{ModelParams, ModulationParams, StemResistanceInvParams, Voltages};
+use crate::utils::{FloatRange, CRC16};
+
+/// The size of a slot in flash, holding a single record.
+pub const SLOT_SIZE: usize = 64;
+
+/// The size of the header of a slot: kind, version, length of the payload,
+/// a reserved byte and the sequence number.
+const HEADER_SIZE: usize = 8;
+
+/// The maximum size of the payload of a record.
+pub const MAX_PAYLOAD: usize = SLOT_SIZE - HEADER_SIZE - 2;
+
+/// A value that can be persisted in a [`ParamStore`].
+///
+/// Every type of record is identified by its kind, and its serialized layout
+/// by its version: when the layout changes the version must be increased, so
+/// that the records written by an older firmware are either migrated by
+/// [`decode`](Record::decode) or ignored.
+pub trait Record: Sized {
+    /// The identifier of the type of record, unique in the store.
+    const KIND: u8;
+
+    /// The version of the serialized layout.
+    const VERSION: u8;
+
+    /// Serializes the value.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The writer of the payload.
+    fn encode(&self, writer: &mut PayloadWriter);
+
+    /// Deserializes a value.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The version of the layout of the payload.
+    /// * `reader` - The reader of the payload.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(value)` - The value.
+    /// * `None` - If the version is not supported or the payload is too short.
+    fn decode(version: u8, reader: &mut PayloadReader) -> Option<Self>;
+}
+
+/// Common trait for the non-volatile storages of parameters.
+pub trait ParamStore {
+    /// The error type of the storage.
+    type Error;
+
+    /// Loads the latest stored value of a record.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(value))` - The value.
+    /// * `Ok(None)` - If the record has never been stored, or it has been
+    ///   stored with an unsupported version.
+    /// * `Err(error)` - If the storage failed.
+    fn load<R: Record>(&mut self) -> Result<Option<R>, Self::Error>;
+
+    /// Stores a new value of a record, replacing the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The value to be stored.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the value has been stored.
+    /// * `Err(error)` - If the storage failed.
+    fn store<R: Record>(&mut self, record: &R) -> Result<(), Self::Error>;
+}
+
+/// Writer of the little-endian payload of a [`Record`].
+#[derive(Debug)]
+pub struct PayloadWriter {
+    /// The payload.
+    buf: [u8; MAX_PAYLOAD],
+
+    /// The number of bytes written.
+    len: usize,
+
+    /// Whether the payload did not fit in the buffer.
+    overflow: bool,
+}
+
+impl PayloadWriter {
+    fn new() -> Self {
+        Self {
+            buf: [0; MAX_PAYLOAD],
+            len: 0,
+            overflow: false,
+        }
+    }
+
+    /// Appends raw bytes to the payload.
+    pub fn put_bytes(&mut self, bytes: &[u8]) {
+        match self.buf.get_mut(self.len..self.len + bytes.len()) {
+            Some(dst) => {
+                dst.copy_from_slice(bytes);
+                self.len += bytes.len();
+            }
+            None => self.overflow = true,
+        }
+    }
+
+    /// Appends a `u8` to the payload.
+    pub fn put_u8(&mut self, value: u8) {
+        self.put_bytes(&[value]);
+    }
+
+    /// Appends a `f32` to the payload.
+    pub fn put_f32(&mut self, value: f32) {
+        self.put_bytes(&value.to_le_bytes());
+    }
+
+    /// Appends a `u32` to the payload.
+    pub fn put_u32(&mut self, value: u32) {
+        self.put_bytes(&value.to_le_bytes());
+    }
+
+    /// Appends a `usize` to the payload, as a `u32`.
+    pub fn put_usize(&mut self, value: usize) {
+        self.put_u32(value as u32);
+    }
+
+    /// Appends a range to the payload.
+    pub fn put_range(&mut self, range: &FloatRange) {
+        self.put_f32(range.start);
+        self.put_f32(range.end);
+        self.put_usize(range.steps);
+    }
+
+    /// Appends a saturation policy to the payload, as the index of the
+    /// variant.
+    pub fn put_saturation_policy(&mut self, policy: SaturationPolicy) {
+        self.put_u8(policy as u8);
+    }
+}
+
+/// Reader of the little-endian payload of a [`Record`].
+#[derive(Debug)]
+pub struct PayloadReader<'a> {
+    /// The remaining payload.
+    bytes: &'a [u8],
+}
+
+impl<'a> PayloadReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Reads the next `N` raw bytes of the payload, if available.
+    pub fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, tail) = self.bytes.split_first_chunk::<N>()?;
+        self.bytes = tail;
+        Some(*head)
+    }
+
+    /// Reads the next `u8` of the payload, if available.
+    pub fn u8(&mut self) -> Option<u8> {
+        self.bytes().map(u8::from_le_bytes)
+    }
+
+    /// Reads the next `f32` of the payload, if available.
+    pub fn f32(&mut self) -> Option<f32> {
+        self.bytes().map(f32::from_le_bytes)
+    }
+
+    /// Reads the next `u32` of the payload, if available.
+    pub fn u32(&mut self) -> Option<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    /// Reads the next `usize` of the payload, stored as a `u32`, if available.
+    pub fn usize(&mut self) -> Option<usize> {
+        self.u32().map(|value| value as usize)
+    }
+
+    /// Reads the next range of the payload, if available.
+    pub fn range(&mut self) -> Option<FloatRange> {
+        Some(FloatRange::new(self.f32()?, self.f32()?, self.usize()?))
+    }
+
+    /// Reads the next saturation policy of the payload, if available and
+    /// valid.
+    pub fn saturation_policy(&mut self) -> Option<SaturationPolicy> {
+        match self.u8()? {
+            0 => Some(SaturationPolicy::Keep),
+            1 => Some(SaturationPolicy::Clamp),
+            2 => Some(SaturationPolicy::Reject),
+            _ => None,
+        }
+    }
+}
+
+impl Record for ModelParams {
+    const KIND: u8 = 1;
+    const VERSION: u8 = 1;
+
+    fn encode(&self, writer: &mut PayloadWriter) {
+        writer.put_f32(self.mod_params.0);
+        writer.put_f32(self.mod_params.1);
+        writer.put_f32(self.mod_params.2);
+        writer.put_f32(self.r_dry);
+        writer.put_f32(self.res_params.0);
+        writer.put_f32(self.res_params.1);
+        writer.put_f32(self.voltages.v_ds);
+        writer.put_f32(self.voltages.v_gs);
+    }
+
+    fn decode(version: u8, reader: &mut PayloadReader) -> Option<Self> {
+        if version != Self::VERSION {
+            return None;
+        }
+        Some(Self {
+            mod_params: ModulationParams(reader.f32()?, reader.f32()?, reader.f32()?),
+            r_dry: reader.f32()?,
+            res_params: StemResistanceInvParams(reader.f32()?, reader.f32()?),
+            voltages: Voltages {
+                v_ds: reader.f32()?,
+                v_gs: reader.f32()?,
+            },
+        })
+    }
+}
+
+#[cfg(feature = "adaptive")]
+impl Record for AdaptiveParams {
+    const KIND: u8 = 2;
+    const VERSION: u8 = 2;
+
+    fn encode(&self, writer: &mut PayloadWriter) {
+        writer.put_f32(self.concentration_init);
+        writer.put_usize(self.concentration_steps);
+        writer.put_usize(self.max_iterations);
+        writer.put_saturation_policy(self.saturation_policy);
+        writer.put_range(&self.saturation_range);
+        writer.put_range(&self.resistance_range);
+    }
+
+    fn decode(version: u8, reader: &mut PayloadReader) -> Option<Self> {
+        // Version 1 did not have the saturation policy, which keeps the
+        // solutions when migrating.
+        if version != 1 && version != Self::VERSION {
+            return None;
+        }
+        Some(Self {
+            concentration_init: reader.f32()?,
+            concentration_steps: reader.usize()?,
+            max_iterations: reader.usize()?,
+            saturation_policy: if version == 1 {
+                SaturationPolicy::Keep
+            } else {
+                reader.saturation_policy()?
+            },
+            saturation_range: reader.range()?,
+            resistance_range: reader.range()?,
+        })
+    }
+}
+
+#[cfg(feature = "adaptive2")]
+impl Record for Adaptive2Params {
+    const KIND: u8 = 3;
+    const VERSION: u8 = 3;
+
+    fn encode(&self, writer: &mut PayloadWriter) {
+        writer.put_range(&self.concentration_range);
+        writer.put_usize(self.max_iterations);
+        writer.put_f32(self.min_range_width);
+        writer.put_f32(self.reduction_factor);
+        writer.put_range(&self.resistance_range);
+        writer.put_saturation_policy(self.saturation_policy);
+        writer.put_range(&self.saturation_range);
+        writer.put_f32(self.tolerance);
+    }
+
+    fn decode(version: u8, reader: &mut PayloadReader) -> Option<Self> {
+        // Version 1 did not have the minimum width of the range, which is
+        // disabled when migrating, and versions 1 and 2 did not have the
+        // saturation policy, which keeps the solutions.
+        if !(1..=Self::VERSION).contains(&version) {
+            return None;
+        }
+        Some(Self {
+            concentration_range: reader.range()?,
+            max_iterations: reader.usize()?,
+            min_range_width: if version == 1 { 0.0 } else { reader.f32()? },
+            reduction_factor: reader.f32()?,
+            resistance_range: reader.range()?,
+            saturation_policy: if version < 3 {
+                SaturationPolicy::Keep
+            } else {
+                reader.saturation_policy()?
+            },
+            saturation_range: reader.range()?,
+            tolerance: reader.f32()?,
+        })
+    }
+}
+
+#[cfg(feature = "brute-force")]
+impl Record for BruteForceParams {
+    const KIND: u8 = 4;
+    const VERSION: u8 = 2;
+
+    fn encode(&self, writer: &mut PayloadWriter) {
+        writer.put_range(&self.concentration_range);
+        writer.put_range(&self.resistance_range);
+        writer.put_saturation_policy(self.saturation_policy);
+        writer.put_range(&self.saturation_range);
+    }
+
+    fn decode(version: u8, reader: &mut PayloadReader) -> Option<Self> {
+        // Version 1 did not have the saturation policy, which keeps the
+        // solutions when migrating.
+        if version != 1 && version != Self::VERSION {
+            return None;
+        }
+        Some(Self {
+            concentration_range: reader.range()?,
+            resistance_range: reader.range()?,
+            saturation_policy: if version == 1 {
+                SaturationPolicy::Keep
+            } else {
+                reader.saturation_policy()?
+            },
+            saturation_range: reader.range()?,
+        })
+    }
+}
+
+#[cfg(feature = "gradient-descent")]
+impl Record for GradientDescentParams {
+    const KIND: u8 = 5;
+    const VERSION: u8 = 2;
+
+    fn encode(&self, writer: &mut PayloadWriter) {
+        writer.put_f32(self.concentration_init);
+        writer.put_f32(self.grad_tolerance);
+        writer.put_f32(self.learning_rate_init);
+        writer.put_usize(self.max_iterations);
+        writer.put_saturation_policy(self.saturation_policy);
+        writer.put_f32(self.tolerance);
+    }
+
+    fn decode(version: u8, reader: &mut PayloadReader) -> Option<Self> {
+        // Version 1 did not have the saturation policy, which keeps the
+        // solutions when migrating.
+        if version != 1 && version != Self::VERSION {
+            return None;
+        }
+        Some(Self {
+            concentration_init: reader.f32()?,
+            grad_tolerance: reader.f32()?,
+            learning_rate_init: reader.f32()?,
+            max_iterations: reader.usize()?,
+            saturation_policy: if version == 1 {
+                SaturationPolicy::Keep
+            } else {
+                reader.saturation_policy()?
+            },
+            tolerance: reader.f32()?,
+        })
+    }
+}
+
+#[cfg(feature = "newton")]
+impl Record for NewtonParams {
+    const KIND: u8 = 6;
+    const VERSION: u8 = 2;
+
+    fn encode(&self, writer: &mut PayloadWriter) {
+        writer.put_f32(self.concentration_init);
+        writer.put_f32(self.grad_tolerance);
+        writer.put_usize(self.max_iterations);
+        writer.put_saturation_policy(self.saturation_policy);
+        writer.put_f32(self.tolerance);
+    }
+
+    fn decode(version: u8, reader: &mut PayloadReader) -> Option<Self> {
+        // Version 1 did not have the saturation policy, which keeps the
+        // solutions when migrating.
+        if version != 1 && version != Self::VERSION {
+            return None;
+        }
+        Some(Self {
+            concentration_init: reader.f32()?,
+            grad_tolerance: reader.f32()?,
+            max_iterations: reader.usize()?,
+            saturation_policy: if version == 1 {
+                SaturationPolicy::Keep
+            } else {
+                reader.saturation_policy()?
+            },
+            tolerance: reader.f32()?,
+        })
+    }
+}
+
+/// A slot of the [`FlashParamStore`].
+struct Slot([u8; SLOT_SIZE]);
+
+impl Slot {
+    /// Serializes a record in a slot.
+    fn new(kind: u8, version: u8, payload: &[u8], sequence: u32) -> Self {
+        let mut bytes = [0xFF; SLOT_SIZE];
+        bytes[0] = kind;
+        bytes[1] = version;
+        bytes[2] = payload.len() as u8;
+        bytes[4..8].copy_from_slice(&sequence.to_le_bytes());
+        bytes[HEADER_SIZE..HEADER_SIZE + payload.len()].copy_from_slice(payload);
+        let crc = CRC16.checksum(&bytes[..SLOT_SIZE - 2]);
+        bytes[SLOT_SIZE - 2..].copy_from_slice(&crc.to_le_bytes());
+        Self(bytes)
+    }
+
+    fn is_erased(&self) -> bool {
+        self.0.iter().all(|&b| b == 0xFF)
+    }
+
+    /// Returns whether the slot contains a record, i.e. its CRC matches.
+    fn is_valid(&self) -> bool {
+        let crc = u16::from_le_bytes([self.0[SLOT_SIZE - 2], self.0[SLOT_SIZE - 1]]);
+        self.len() <= MAX_PAYLOAD && CRC16.checksum(&self.0[..SLOT_SIZE - 2]) == crc
+    }
+
+    fn kind(&self) -> u8 {
+        self.0[0]
+    }
+
+    fn version(&self) -> u8 {
+        self.0[1]
+    }
+
+    fn len(&self) -> usize {
+        self.0[2] as usize
+    }
+
+    fn sequence(&self) -> u32 {
+        u32::from_le_bytes([self.0[4], self.0[5], self.0[6], self.0[7]])
+    }
+
+    fn payload(&self) -> &[u8] {
+        &self.0[HEADER_SIZE..HEADER_SIZE + self.len()]
+    }
+}
+
+/// The errors of the [`FlashParamStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlashParamStoreError<E> {
+    /// The flash memory failed.
+    Flash(E),
+
+    /// There are more types of records than slots in a sector.
+    Full,
+
+    /// The region is not aligned to the sectors, or a sector cannot fit two
+    /// slots.
+    InvalidRegion,
+
+    /// The serialized record is larger than [`MAX_PAYLOAD`].
+    TooLarge,
+}
+
+/// Reference [`ParamStore`] over two sectors of flash memory.
+///
+/// The records are appended to the active sector, each with a sequence
+/// number and a CRC, so that the latest valid value of every kind is found
+/// after a reset and a write interrupted by a power loss leaves the previous
+/// value in place. When the active sector is full, the latest value of every
+/// kind is copied to the other sector, which becomes the active one, and the
+/// old sector is erased; a copy interrupted by a power loss is completed
+/// when the store is mounted.
+pub struct FlashParamStore<F: NorFlash> {
+    /// The index of the active sector.
+    active: u32,
+
+    /// The flash memory.
+    flash: F,
+
+    /// The address of the slot where the next record is written.
+    next: u32,
+
+    /// The address of the first of the two sectors.
+    offset: u32,
+
+    /// The sequence number of the next record.
+    sequence: u32,
+}
+
+impl<F: NorFlash> FlashParamStore<F> {
+    /// The number of slots that fit in a sector.
+    const SLOTS_PER_SECTOR: u32 = (F::ERASE_SIZE / SLOT_SIZE) as u32;
+
+    /// Opens the store in the two sectors starting at the given address.
+    ///
+    /// # Arguments
+    ///
+    /// * `flash` - The flash memory.
+    /// * `offset` - The address of the first sector of the region.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(store)` - The store.
+    /// * `Err(error)` - If the region is invalid or the flash memory failed.
+    pub fn mount(flash: F, offset: u32) -> Result<Self, FlashParamStoreError<F::Error>> {
+        if !(offset as usize).is_multiple_of(F::ERASE_SIZE)
+            || !SLOT_SIZE.is_multiple_of(F::WRITE_SIZE)
+            || Self::SLOTS_PER_SECTOR < 2
+            || offset as usize + 2 * F::ERASE_SIZE > flash.capacity()
+        {
+            return Err(FlashParamStoreError::InvalidRegion);
+        }
+
+        let mut store = Self {
+            active: 0,
+            flash,
+            next: offset,
+            offset,
+            sequence: 0,
+        };
+
+        // The active sector contains the newest record.
+        let mut newest: Option<(u32, u32)> = None;
+        for sector in 0..2 {
+            for slot in 0..Self::SLOTS_PER_SECTOR {
+                let record = store.read_slot(store.slot_address(sector, slot))?;
+                if record.is_valid() && newest.is_none_or(|(_, seq)| record.sequence() > seq) {
+                    newest = Some((sector, record.sequence()));
+                }
+            }
+        }
+        let Some((active, sequence)) = newest else {
+            return Ok(store);
+        };
+        store.active = active;
+        store.sequence = sequence.wrapping_add(1);
+        store.next = store.first_free(active)?;
+
+        // Records in the other sector are left over by an interrupted
+        // compaction: complete it.
+        let other = 1 - active;
+        for slot in 0..Self::SLOTS_PER_SECTOR {
+            let record = store.read_slot(store.slot_address(other, slot))?;
+            if record.is_valid()
+                && store.latest(other, record.kind())?.map(|r| r.sequence())
+                    == Some(record.sequence())
+                && store.latest(active, record.kind())?.is_none()
+            {
+                store.append(record.kind(), record.version(), record.payload())?;
+            }
+        }
+        if !store.is_sector_erased(other)? {
+            store.erase_sector(other)?;
+        }
+
+        Ok(store)
+    }
+
+    /// Releases the flash memory.
+    pub fn release(self) -> F {
+        self.flash
+    }
+
+    /// Returns the address of a slot.
+    #[inline]
+    fn slot_address(&self, sector: u32, slot: u32) -> u32 {
+        self.offset + sector * F::ERASE_SIZE as u32 + slot * SLOT_SIZE as u32
+    }
+
+    fn read_slot(&mut self, address: u32) -> Result<Slot, FlashParamStoreError<F::Error>> {
+        let mut bytes = [0; SLOT_SIZE];
+        self.flash
+            .read(address, &mut bytes)
+            .map_err(FlashParamStoreError::Flash)?;
+        Ok(Slot(bytes))
+    }
+
+    /// Returns the address after the last written slot of a sector, which is
+    /// the start of the next sector if the sector is full.
+    fn first_free(&mut self, sector: u32) -> Result<u32, FlashParamStoreError<F::Error>> {
+        for slot in (0..Self::SLOTS_PER_SECTOR).rev() {
+            let address = self.slot_address(sector, slot);
+            if !self.read_slot(address)?.is_erased() {
+                return Ok(address + SLOT_SIZE as u32);
+            }
+        }
+        Ok(self.slot_address(sector, 0))
+    }
+
+    fn is_sector_erased(&mut self, sector: u32) -> Result<bool, FlashParamStoreError<F::Error>> {
+        for slot in 0..Self::SLOTS_PER_SECTOR {
+            if !self.read_slot(self.slot_address(sector, slot))?.is_erased() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn erase_sector(&mut self, sector: u32) -> Result<(), FlashParamStoreError<F::Error>> {
+        let from = self.slot_address(sector, 0);
+        self.flash
+            .erase(from, from + F::ERASE_SIZE as u32)
+            .map_err(FlashParamStoreError::Flash)
+    }
+
+    /// Returns the latest valid record of the given kind in a sector.
+    fn latest(
+        &mut self,
+        sector: u32,
+        kind: u8,
+    ) -> Result<Option<Slot>, FlashParamStoreError<F::Error>> {
+        let mut latest: Option<Slot> = None;
+        for slot in 0..Self::SLOTS_PER_SECTOR {
+            let record = self.read_slot(self.slot_address(sector, slot))?;
+            if record.is_valid()
+                && record.kind() == kind
+                && latest
+                    .as_ref()
+                    .is_none_or(|r| record.sequence() > r.sequence())
+            {
+                latest = Some(record);
+            }
+        }
+        Ok(latest)
+    }
+
+    /// Writes a record in the next slot, which must be free.
+    fn append(
+        &mut self,
+        kind: u8,
+        version: u8,
+        payload: &[u8],
+    ) -> Result<(), FlashParamStoreError<F::Error>> {
+        if self.next == self.slot_address(self.active, Self::SLOTS_PER_SECTOR) {
+            return Err(FlashParamStoreError::Full);
+        }
+
+        let slot = Slot::new(kind, version, payload, self.sequence);
+        self.flash
+            .write(self.next, &slot.0)
+            .map_err(FlashParamStoreError::Flash)?;
+        self.sequence = self.sequence.wrapping_add(1);
+        self.next += SLOT_SIZE as u32;
+        Ok(())
+    }
+
+    /// Copies the latest record of every kind to the other sector, and erases
+    /// the active one.
+    fn compact(&mut self) -> Result<(), FlashParamStoreError<F::Error>> {
+        let src = self.active;
+        let dst = 1 - src;
+        self.erase_sector(dst)?;
+        self.active = dst;
+        self.next = self.slot_address(dst, 0);
+
+        for slot in 0..Self::SLOTS_PER_SECTOR {
+            let record = self.read_slot(self.slot_address(src, slot))?;
+            if record.is_valid()
+                && self.latest(src, record.kind())?.map(|r| r.sequence()) == Some(record.sequence())
+            {
+                self.append(record.kind(), record.version(), record.payload())?;
+            }
+        }
+        self.erase_sector(src)
+    }
+}
+
+impl<F: NorFlash> ParamStore for FlashParamStore<F> {
+    type Error = FlashParamStoreError<F::Error>;
+
+    fn load<R: Record>(&mut self) -> Result<Option<R>, Self::Error> {
+        Ok(self
+            .latest(self.active, R::KIND)?
+            .and_then(|slot| R::decode(slot.version(), &mut PayloadReader::new(slot.payload()))))
+    }
+
+    fn store<R: Record>(&mut self, record: &R) -> Result<(), Self::Error> {
+        let mut writer = PayloadWriter::new();
+        record.encode(&mut writer);
+        if writer.overflow {
+            return Err(FlashParamStoreError::TooLarge);
+        }
+
+        if self.next == self.slot_address(self.active, Self::SLOTS_PER_SECTOR) {
+            self.compact()?;
+        }
+        self.append(R::KIND, R::VERSION, &writer.buf[..writer.len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_storage::nor_flash::{ErrorType, NorFlashErrorKind, ReadNorFlash};
+
+    use super::*;
+
+    const SECTOR_SIZE: usize = 4 * SLOT_SIZE;
+
+    /// Flash memory of two sectors, counting the erase cycles.
+    struct FlashMock {
+        data: [u8; 2 * SECTOR_SIZE],
+        erases: u32,
+    }
+
+    impl FlashMock {
+        fn new() -> Self {
+            Self {
+                data: [0xFF; 2 * SECTOR_SIZE],
+                erases: 0,
+            }
+        }
+    }
+
+    impl ErrorType for FlashMock {
+        type Error = NorFlashErrorKind;
+    }
+
+    impl ReadNorFlash for FlashMock {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    impl NorFlash for FlashMock {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = SECTOR_SIZE;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            self.data[from as usize..to as usize].fill(0xFF);
+            self.erases += 1;
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            for (cell, byte) in self.data[offset..offset + bytes.len()]
+                .iter_mut()
+                .zip(bytes)
+            {
+                // Programming can only clear bits.
+                *cell &= byte;
+            }
+            Ok(())
+        }
+    }
+
+    fn model_params(r_dry: f32) -> ModelParams {
+        ModelParams {
+            mod_params: ModulationParams(0.0, -0.01463, -0.32),
+            r_dry,
+            res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+            voltages: Voltages {
+                v_ds: -0.05,
+                v_gs: 0.5,
+            },
+        }
+    }
+
+    #[cfg(feature = "adaptive2")]
+    const ALG_PARAMS: Adaptive2Params = Adaptive2Params {
+        concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+        max_iterations: 10,
+        min_range_width: 1e-5,
+        reduction_factor: 0.2,
+        resistance_range: FloatRange::new(10.0, 100.0, 100),
+        saturation_policy: SaturationPolicy::Clamp,
+        saturation_range: FloatRange::new(0.0, 1.0, 100),
+        tolerance: 1e-15,
+    };
+
+    /// A record too large for a slot.
+    #[cfg(feature = "adaptive2")]
+    struct Large;
+
+    #[cfg(feature = "adaptive2")]
+    impl Record for Large {
+        const KIND: u8 = 100;
+        const VERSION: u8 = 1;
+
+        fn encode(&self, writer: &mut PayloadWriter) {
+            writer.put_bytes(&[0; MAX_PAYLOAD + 1]);
+        }
+
+        fn decode(_version: u8, _reader: &mut PayloadReader) -> Option<Self> {
+            Some(Large)
+        }
+    }
+
+    #[cfg(feature = "adaptive2")]
+    #[test]
+    fn test_store_load() {
+        let mut store = FlashParamStore::mount(FlashMock::new(), 0).unwrap();
+        assert_eq!(store.load::<ModelParams>(), Ok(None));
+
+        store.store(&model_params(38.2)).unwrap();
+        store.store(&ALG_PARAMS).unwrap();
+        store.store(&model_params(40.0)).unwrap();
+        assert_eq!(store.load(), Ok(Some(model_params(40.0))));
+        assert_eq!(store.load(), Ok(Some(ALG_PARAMS)));
+
+        // The values are recovered after a reset.
+        let mut store = FlashParamStore::mount(store.release(), 0).unwrap();
+        assert_eq!(store.load(), Ok(Some(model_params(40.0))));
+        assert_eq!(store.load(), Ok(Some(ALG_PARAMS)));
+        #[cfg(feature = "newton")]
+        assert_eq!(store.load::<NewtonParams>(), Ok(None));
+
+        assert_eq!(store.store(&Large), Err(FlashParamStoreError::TooLarge));
+    }
+
+    #[cfg(feature = "adaptive2")]
+    #[test]
+    fn test_compaction() {
+        let mut store = FlashParamStore::mount(FlashMock::new(), 0).unwrap();
+        store.store(&ALG_PARAMS).unwrap();
+        for i in 0..10 {
+            store.store(&model_params(i as f32)).unwrap();
+        }
+        assert_eq!(store.load(), Ok(Some(model_params(9.0))));
+        assert_eq!(store.load(), Ok(Some(ALG_PARAMS)));
+
+        let mut store = FlashParamStore::mount(store.release(), 0).unwrap();
+        assert_eq!(store.load(), Ok(Some(model_params(9.0))));
+        assert_eq!(store.load(), Ok(Some(ALG_PARAMS)));
+        assert!(store.release().erases > 0);
+    }
+
+    #[cfg(feature = "adaptive2")]
+    #[test]
+    fn test_interrupted_compaction() {
+        let mut store = FlashParamStore::mount(FlashMock::new(), 0).unwrap();
+        store.store(&ALG_PARAMS).unwrap();
+        for i in 0..3 {
+            store.store(&model_params(i as f32)).unwrap();
+        }
+
+        // Simulate a power loss after copying only the latest model
+        // parameters to the second sector.
+        let mut flash = store.release();
+        let latest = Slot(flash.data[3 * SLOT_SIZE..4 * SLOT_SIZE].try_into().unwrap());
+        let copy = Slot::new(latest.kind(), latest.version(), latest.payload(), 4);
+        flash.data[SECTOR_SIZE..SECTOR_SIZE + SLOT_SIZE].copy_from_slice(&copy.0);
+
+        // The copy is completed and the old sector erased.
+        let mut store = FlashParamStore::mount(flash, 0).unwrap();
+        assert_eq!(store.load(), Ok(Some(model_params(2.0))));
+        assert_eq!(store.load(), Ok(Some(ALG_PARAMS)));
+        let flash = store.release();
+        assert!(flash.data[..SECTOR_SIZE].iter().all(|&b| b == 0xFF));
+
+        let mut store = FlashParamStore::mount(flash, 0).unwrap();
+        assert_eq!(store.load(), Ok(Some(ALG_PARAMS)));
+    }
+
+    #[test]
+    fn test_corrupted_record() {
+        let mut store = FlashParamStore::mount(FlashMock::new(), 0).unwrap();
+        store.store(&model_params(1.0)).unwrap();
+        store.store(&model_params(2.0)).unwrap();
+
+        // Simulate a power loss while writing the second record.
+        let mut flash = store.release();
+        flash.data[SLOT_SIZE + 23] = 0x00;
+        let mut store = FlashParamStore::mount(flash, 0).unwrap();
+        assert_eq!(store.load(), Ok(Some(model_params(1.0))));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut flash = FlashMock::new();
+        let mut writer = PayloadWriter::new();
+        model_params(1.0).encode(&mut writer);
+        let slot = Slot::new(ModelParams::KIND, 2, &writer.buf[..writer.len], 0);
+        flash.data[..SLOT_SIZE].copy_from_slice(&slot.0);
+
+        let mut store = FlashParamStore::mount(flash, 0).unwrap();
+        assert_eq!(store.load::<ModelParams>(), Ok(None));
+    }
+
+    #[cfg(feature = "adaptive2")]
+    #[test]
+    fn test_migration() {
+        // Layout of the version 1, without the minimum width of the range.
+        let mut writer = PayloadWriter::new();
+        writer.put_range(&ALG_PARAMS.concentration_range);
+        writer.put_usize(ALG_PARAMS.max_iterations);
+        writer.put_f32(ALG_PARAMS.reduction_factor);
+        writer.put_range(&ALG_PARAMS.resistance_range);
+        writer.put_range(&ALG_PARAMS.saturation_range);
+        writer.put_f32(ALG_PARAMS.tolerance);
+
+        let mut flash = FlashMock::new();
+        let slot = Slot::new(Adaptive2Params::KIND, 1, &writer.buf[..writer.len], 0);
+        flash.data[..SLOT_SIZE].copy_from_slice(&slot.0);
+
+        let mut store = FlashParamStore::mount(flash, 0).unwrap();
+        assert_eq!(
+            store.load(),
+            Ok(Some(Adaptive2Params {
+                min_range_width: 0.0,
+                saturation_policy: SaturationPolicy::Keep,
+                ..ALG_PARAMS
+            }))
+        );
+    }
+
+    #[test]
+    fn test_invalid_region() {
+        assert!(matches!(
+            FlashParamStore::mount(FlashMock::new(), SECTOR_SIZE as u32),
+            Err(FlashParamStoreError::InvalidRegion)
+        ));
+    }
+}
@@ -1,6 +1,10 @@
+use core::fmt;
+
 /// The parameters of the mathematical model.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "ffi", repr(C))]
 pub struct ModelParams {
     /// The parameters of the modulation function.
     pub mod_params: ModulationParams,
@@ -16,9 +20,24 @@ pub struct ModelParams {
     pub voltages: Voltages,
 }
 
+impl fmt::Display for ModelParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("modulation: ")?;
+        fmt::Display::fmt(&self.mod_params, f)?;
+        f.write_str("; r_dry: ")?;
+        write_f32(f, self.r_dry)?;
+        f.write_str(" Ohm; stem resistance inv: ")?;
+        fmt::Display::fmt(&self.res_params, f)?;
+        f.write_str("; ")?;
+        fmt::Display::fmt(&self.voltages, f)
+    }
+}
+
 /// The output currents of the device.
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "ffi", repr(C))]
 pub struct Currents {
     /// Current measured between drain and source when the gate is off [Ampere].
     pub i_ds_off: f32,
@@ -30,6 +49,18 @@ pub struct Currents {
     pub i_gs_on: f32,
 }
 
+impl fmt::Display for Currents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("i_ds_off: ")?;
+        write_f32(f, self.i_ds_off)?;
+        f.write_str(" A, i_ds_on: ")?;
+        write_f32(f, self.i_ds_on)?;
+        f.write_str(" A, i_gs_on: ")?;
+        write_f32(f, self.i_gs_on)?;
+        f.write_str(" A")
+    }
+}
+
 /// The parameters of the modulation function.
 /// The function is defined as:
 /// ```text
@@ -38,8 +69,20 @@ pub struct Currents {
 /// where `x` is the ion concentration, `a`, `b` and `c` are the parameters.
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "ffi", repr(C))]
 pub struct ModulationParams(pub f32, pub f32, pub f32);
 
+impl fmt::Display for ModulationParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_f32(f, self.0)?;
+        f.write_str(" * x + ")?;
+        write_f32(f, self.1)?;
+        f.write_str(" * ln(x) + ")?;
+        write_f32(f, self.2)
+    }
+}
+
 /// The parameters of the inverse of stem resistance function.
 /// The function is defined as:
 /// ```text
@@ -48,11 +91,24 @@ pub struct ModulationParams(pub f32, pub f32, pub f32);
 /// where `x` is the ion concentration, `a` and `b` are the parameters.
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "ffi", repr(C))]
 pub struct StemResistanceInvParams(pub f32, pub f32);
 
+impl fmt::Display for StemResistanceInvParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_f32(f, self.0)?;
+        f.write_str(" + ")?;
+        write_f32(f, self.1)?;
+        f.write_str(" * x^0.955")
+    }
+}
+
 /// The dependent variables of the model.
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "ffi", repr(C))]
 pub struct Variables {
     /// Concentration of ions in the electrolyte [Molarity].
     pub concentration: f32,
@@ -65,9 +121,22 @@ pub struct Variables {
     pub saturation: f32,
 }
 
+impl fmt::Display for Variables {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("concentration: ")?;
+        write_f32(f, self.concentration)?;
+        f.write_str(" M, resistance: ")?;
+        write_f32(f, self.resistance)?;
+        f.write_str(" Ohm, saturation: ")?;
+        write_f32(f, self.saturation)
+    }
+}
+
 /// The input voltages of the device.
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "ffi", repr(C))]
 pub struct Voltages {
     /// Voltage applied between drain and source [Volt].
     pub v_ds: f32,
@@ -75,3 +144,85 @@ pub struct Voltages {
     /// Voltage applied between gate and source [Volt].
     pub v_gs: f32,
 }
+
+impl fmt::Display for Voltages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("v_ds: ")?;
+        write_f32(f, self.v_ds)?;
+        f.write_str(" V, v_gs: ")?;
+        write_f32(f, self.v_gs)?;
+        f.write_str(" V")
+    }
+}
+
+/// Writes a number with the precision of the formatter, if any, so that
+/// e.g. `{:.3}` applies to every field of the parameters.
+///
+/// # Arguments
+///
+/// * `f` - The formatter.
+/// * `value` - The number.
+fn write_f32(f: &mut fmt::Formatter<'_>, value: f32) -> fmt::Result {
+    match f.precision() {
+        Some(precision) => write!(f, "{value:.precision$}"),
+        None => write!(f, "{value}"),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    const VARIABLES: Variables = Variables {
+        concentration: 0.0125,
+        resistance: 42.5,
+        saturation: 0.5,
+    };
+
+    #[test]
+    fn test_display_variables() {
+        assert_eq!(
+            VARIABLES.to_string(),
+            "concentration: 0.0125 M, resistance: 42.5 Ohm, saturation: 0.5"
+        );
+        assert_eq!(
+            format!("{VARIABLES:.2}"),
+            "concentration: 0.01 M, resistance: 42.50 Ohm, saturation: 0.50"
+        );
+    }
+
+    #[test]
+    fn test_display_currents() {
+        let currents = Currents {
+            i_ds_off: -0.5,
+            i_ds_on: 0.25,
+            i_gs_on: 1e-6,
+        };
+        assert_eq!(
+            currents.to_string(),
+            "i_ds_off: -0.5 A, i_ds_on: 0.25 A, i_gs_on: 0.000001 A"
+        );
+    }
+
+    #[test]
+    fn test_display_model_params() {
+        let params = ModelParams {
+            mod_params: ModulationParams(1.5, -0.25, 2.0),
+            r_dry: 100.0,
+            res_params: StemResistanceInvParams(1e-3, 0.5),
+            voltages: Voltages {
+                v_ds: 0.05,
+                v_gs: 0.5,
+            },
+        };
+        assert_eq!(
+            params.to_string(),
+            "modulation: 1.5 * x + -0.25 * ln(x) + 2; r_dry: 100 Ohm; \
+             stem resistance inv: 0.001 + 0.5 * x^0.955; v_ds: 0.05 V, v_gs: 0.5 V"
+        );
+        assert_eq!(
+            format!("{:.1}", params.voltages),
+            "v_ds: 0.1 V, v_gs: 0.5 V"
+        );
+    }
+}
@@ -0,0 +1,817 @@
+use core::borrow::Borrow;
+use core::marker::PhantomData;
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+use crate::alarms::{AlarmEvent, Alarms};
+#[cfg(feature = "adaptive2")]
+use crate::algorithms::Adaptive2Params;
+#[cfg(feature = "adaptive")]
+use crate::algorithms::AdaptiveParams;
+#[cfg(feature = "brute-force")]
+use crate::algorithms::BruteForceParams;
+#[cfg(feature = "gradient-descent")]
+use crate::algorithms::GradientDescentParams;
+#[cfg(feature = "newton")]
+use crate::algorithms::NewtonParams;
+use crate::algorithms::{Algorithm, SolveReport};
+use crate::models::Model;
+use crate::params::{Currents, ModelParams, Variables};
+#[cfg(any(feature = "adaptive2", feature = "brute-force"))]
+use crate::utils::FloatRange;
+
+/// Common interface for the filters of the [`Pipeline`].
+///
+/// The unit type is the identity filter.
+///
+/// # Type parameters
+///
+/// * `T` - The type of the filtered values.
+pub trait Filter<T> {
+    /// Filters a new value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The new value.
+    ///
+    /// # Returns
+    ///
+    /// The filtered value.
+    fn apply(&mut self, value: T) -> T;
+
+    /// Forgets the previous values.
+    fn reset(&mut self);
+}
+
+impl<T> Filter<T> for () {
+    #[inline]
+    fn apply(&mut self, value: T) -> T {
+        value
+    }
+
+    #[inline]
+    fn reset(&mut self) {}
+}
+
+/// Exponential moving average, applied to every field of the values.
+///
+/// The filtered value is computed as:
+/// ```text
+/// y[n] = alpha * x[n] + (1 - alpha) * y[n - 1]
+/// ```
+/// where the first filtered value is the first value. Non-finite fields are
+/// replaced by the previous filtered value, so that a failed conversion does
+/// not poison the filter.
+///
+/// # Type parameters
+///
+/// * `T` - The type of the filtered values, [`Currents`] or [`Variables`].
+#[derive(Debug, Clone)]
+pub struct ExponentialFilter<T> {
+    /// The weight of the new value, between zero (excluded) and one.
+    alpha: f32,
+
+    /// The last filtered value.
+    state: Option<T>,
+}
+
+impl<T> ExponentialFilter<T> {
+    /// Creates a new filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `alpha` - The weight of the new value, between zero (excluded) and
+    ///   one, where one disables the filter.
+    pub const fn new(alpha: f32) -> Self {
+        Self { alpha, state: None }
+    }
+
+    /// Filters a field of the values.
+    #[inline]
+    fn smooth(&self, previous: f32, value: f32) -> f32 {
+        if value.is_finite() {
+            self.alpha * value + (1.0 - self.alpha) * previous
+        } else {
+            previous
+        }
+    }
+}
+
+impl Filter<Currents> for ExponentialFilter<Currents> {
+    fn apply(&mut self, value: Currents) -> Currents {
+        let filtered = match &self.state {
+            Some(previous) => Currents {
+                i_ds_off: self.smooth(previous.i_ds_off, value.i_ds_off),
+                i_ds_on: self.smooth(previous.i_ds_on, value.i_ds_on),
+                i_gs_on: self.smooth(previous.i_gs_on, value.i_gs_on),
+            },
+            None => value,
+        };
+        self.state = Some(filtered);
+        filtered
+    }
+
+    fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+impl Filter<Variables> for ExponentialFilter<Variables> {
+    fn apply(&mut self, value: Variables) -> Variables {
+        let filtered = match &self.state {
+            Some(previous) => Variables {
+                concentration: self.smooth(previous.concentration, value.concentration),
+                resistance: self.smooth(previous.resistance, value.resistance),
+                saturation: self.smooth(previous.saturation, value.saturation),
+            },
+            None => value,
+        };
+        self.state = Some(filtered);
+        filtered
+    }
+
+    fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+/// Parameters of the algorithms that can start from the previous solution,
+/// which is usually close to the new one since the variables change slowly.
+///
+/// The grid-based algorithms search the whole range anyway, so their
+/// parameters are used unchanged, unless the pipeline is incremental, see
+/// [`WarmStart::narrow`].
+pub trait WarmStart: Clone {
+    /// Returns the parameters starting from the given solution.
+    ///
+    /// # Arguments
+    ///
+    /// * `previous` - The previous solution.
+    ///
+    /// # Returns
+    ///
+    /// The parameters of the algorithm.
+    #[inline]
+    fn warm_start(&self, previous: &Variables) -> Self {
+        let _ = previous;
+        self.clone()
+    }
+
+    /// Returns the parameters searching only the neighborhood of the given
+    /// solution, used by an incremental [`Pipeline`] when the currents have
+    /// barely changed.
+    ///
+    /// The grid-based algorithms search the concentrations within `span`
+    /// times the previous one, with the step of the whole grid, so that the
+    /// resolution of the solution is the same with a fraction of the
+    /// evaluations. The other algorithms start from the solution, as with
+    /// [`WarmStart::warm_start`].
+    ///
+    /// # Arguments
+    ///
+    /// * `previous` - The previous solution.
+    /// * `span` - The ratio between the bounds of the neighborhood and the
+    ///   previous concentration.
+    ///
+    /// # Returns
+    ///
+    /// The parameters of the algorithm.
+    #[inline]
+    fn narrow(&self, previous: &Variables, span: f32) -> Self {
+        let _ = span;
+        self.warm_start(previous)
+    }
+}
+
+/// Returns the points of a grid of concentrations within `span` times the
+/// given concentration, extended to the closest points outside it.
+///
+/// The grid is returned unchanged if the neighborhood does not overlap it
+/// or the concentration is NaN, which is ignored by the bounds.
+#[cfg(any(feature = "adaptive2", feature = "brute-force"))]
+fn narrow_range(range: &FloatRange, concentration: f32, span: f32) -> FloatRange {
+    let increment = (range.end - range.start) / range.steps as f32;
+    let start = (concentration / span).max(range.start);
+    let end = (concentration * span).min(range.end);
+    if start >= end {
+        return range.clone();
+    }
+    let first = ((start - range.start) / increment).floor() as usize;
+    let last = (((end - range.start) / increment).ceil() as usize).min(range.steps);
+    FloatRange::new(
+        range.start + increment * first as f32,
+        range.start + increment * last as f32,
+        (last - first).max(1),
+    )
+}
+
+impl WarmStart for () {}
+
+#[cfg(feature = "adaptive")]
+impl WarmStart for AdaptiveParams {}
+
+#[cfg(feature = "adaptive2")]
+impl WarmStart for Adaptive2Params {
+    fn narrow(&self, previous: &Variables, span: f32) -> Self {
+        Self {
+            concentration_range: narrow_range(
+                &self.concentration_range,
+                previous.concentration,
+                span,
+            ),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(feature = "brute-force")]
+impl WarmStart for BruteForceParams {
+    fn narrow(&self, previous: &Variables, span: f32) -> Self {
+        Self {
+            concentration_range: narrow_range(
+                &self.concentration_range,
+                previous.concentration,
+                span,
+            ),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(feature = "gradient-descent")]
+impl WarmStart for GradientDescentParams {
+    fn warm_start(&self, previous: &Variables) -> Self {
+        Self {
+            concentration_init: previous.concentration,
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(feature = "newton")]
+impl WarmStart for NewtonParams {
+    fn warm_start(&self, previous: &Variables) -> Self {
+        Self {
+            concentration_init: previous.concentration,
+            ..self.clone()
+        }
+    }
+}
+
+/// The parameters of an incremental [`Pipeline`], which searches only the
+/// neighborhood of the previous solution when the currents have barely
+/// changed, see [`Pipeline::with_incremental`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IncrementalParams {
+    /// The maximum relative change of every current since the previous
+    /// measurement for which the neighborhood is searched, e.g. `0.01`.
+    pub max_change: f32,
+
+    /// The ratio between the bounds of the neighborhood and the previous
+    /// concentration, e.g. `1.5`, see [`WarmStart::narrow`].
+    pub span: f32,
+}
+
+/// Returns the largest relative change of the currents.
+fn relative_change(previous: &Currents, currents: &Currents) -> f32 {
+    let change = |previous: f32, current: f32| ((current - previous) / previous).abs();
+    change(previous.i_ds_off, currents.i_ds_off)
+        .max(change(previous.i_ds_on, currents.i_ds_on))
+        .max(change(previous.i_gs_on, currents.i_gs_on))
+}
+
+/// The output of the [`Pipeline`] for every measurement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Estimate {
+    /// The active alarms, as returned by [`Alarms::flags`].
+    pub alarms: u32,
+
+    /// The alarms cleared by this measurement, as bit flags.
+    pub cleared: u32,
+
+    /// Whether the solution was found in the neighborhood of the previous
+    /// one, see [`Pipeline::with_incremental`].
+    pub incremental: bool,
+
+    /// The loss of the solution.
+    pub loss: f32,
+
+    /// The alarms raised by this measurement, as bit flags.
+    pub raised: u32,
+
+    /// The solution of the algorithm, before the output filter.
+    pub solution: Variables,
+
+    /// The time of the measurement.
+    pub timestamp: u64,
+
+    /// The filtered variables.
+    pub variables: Variables,
+}
+
+/// End-to-end processing of the measurements, from the currents to the
+/// filtered variables and the alarms.
+///
+/// Every measurement goes through the following steps:
+/// 1. the currents are filtered by the input filter;
+/// 2. the model is updated with the filtered currents;
+/// 3. the algorithm is run, starting from the previous solution if its
+///    parameters support it, see [`WarmStart`], or only in its neighborhood
+///    if the pipeline is incremental and the currents have barely changed;
+/// 4. the solution is filtered by the output filter;
+/// 5. the alarms are updated with the filtered variables.
+///
+/// # Type parameters
+///
+/// * `A` - The type of the algorithm.
+/// * `P` - The type of the parameters of the algorithm.
+/// * `M` - The type of the model.
+/// * `I` - The type of the input filter.
+/// * `O` - The type of the output filter.
+/// * `N` - The number of alarms.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::alarms::Alarms;
+/// use bioristor_lib::algorithms::{NewtonEquation, NewtonParams, SaturationPolicy};
+/// use bioristor_lib::losses::Absolute;
+/// use bioristor_lib::models::Equation;
+/// use bioristor_lib::params::{
+///     Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages,
+/// };
+/// use bioristor_lib::pipeline::{ExponentialFilter, Pipeline};
+///
+/// let model_params = ModelParams {
+///     mod_params: ModulationParams(0.0, -0.01463, -0.32),
+///     r_dry: 38.2,
+///     res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+///     voltages: Voltages {
+///         v_ds: -0.05,
+///         v_gs: 0.5,
+///     },
+/// };
+/// let alg_params = NewtonParams {
+///     concentration_init: 1e-2,
+///     grad_tolerance: 1e-9,
+///     max_iterations: 100,
+///     saturation_policy: SaturationPolicy::Keep,
+///     tolerance: 1e-9,
+/// };
+///
+/// let mut pipeline = Pipeline::<NewtonEquation<Equation, Absolute>, _, _, _, _, 0>::new(
+///     alg_params,
+///     model_params,
+///     ExponentialFilter::new(0.5),
+///     (),
+///     Alarms::new([]),
+/// );
+/// let currents = Currents {
+///     i_ds_off: -0.00125,
+///     i_ds_on: -0.00085,
+///     i_gs_on: 2.4e-6,
+/// };
+/// let estimate = pipeline.push(currents, 0);
+/// ```
+pub struct Pipeline<A, P, M, I, O, const N: usize>
+where
+    A: Algorithm<P, M>,
+    P: WarmStart,
+    M: Model,
+    I: Filter<Currents>,
+    O: Filter<Variables>,
+{
+    /// The alarms.
+    alarms: Alarms<N>,
+
+    /// The parameters of the algorithm.
+    alg_params: P,
+
+    /// The parameters of the incremental solve, if enabled.
+    incremental: Option<IncrementalParams>,
+
+    /// The input filter.
+    input: I,
+
+    /// The parameters of the model, owned or borrowed.
+    model_params: M::Params,
+
+    /// The output filter.
+    output: O,
+
+    /// The filtered currents and the solution of the last measurement.
+    previous: Option<(Currents, Variables)>,
+
+    _t: PhantomData<(A, M)>,
+}
+
+impl<A, P, M, I, O, const N: usize> Pipeline<A, P, M, I, O, N>
+where
+    A: Algorithm<P, M>,
+    P: WarmStart,
+    M: Model,
+    M::Params: Clone,
+    I: Filter<Currents>,
+    O: Filter<Variables>,
+{
+    /// Creates a new pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// * `alg_params` - The parameters of the algorithm.
+    /// * `model_params` - The parameters of the model, borrowed when the
+    ///   model borrows them, e.g. to share them among the pipelines of
+    ///   several channels.
+    /// * `input` - The filter of the currents.
+    /// * `output` - The filter of the solutions.
+    /// * `alarms` - The alarms checked on the filtered variables.
+    pub fn new(
+        alg_params: P,
+        model_params: M::Params,
+        input: I,
+        output: O,
+        alarms: Alarms<N>,
+    ) -> Self {
+        Self {
+            alarms,
+            alg_params,
+            incremental: None,
+            input,
+            model_params,
+            output,
+            previous: None,
+            _t: PhantomData,
+        }
+    }
+
+    /// Makes the pipeline incremental: when every current changes by less
+    /// than [`IncrementalParams::max_change`] since the previous measurement,
+    /// the algorithm searches only the neighborhood of the previous solution,
+    /// see [`WarmStart::narrow`].
+    ///
+    /// The whole range is searched again if the solution of the neighborhood
+    /// is on its boundary, i.e. the concentration has moved out of it, or if
+    /// no solution is found there.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the incremental solve.
+    ///
+    /// # Panics
+    ///
+    /// If the span of the neighborhood is not greater than one.
+    pub fn with_incremental(mut self, params: IncrementalParams) -> Self {
+        assert!(
+            params.span > 1.0,
+            "the span of the neighborhood must be greater than one"
+        );
+        self.incremental = Some(params);
+        self
+    }
+
+    /// Returns the alarms.
+    #[inline]
+    pub fn alarms(&self) -> &Alarms<N> {
+        &self.alarms
+    }
+
+    /// Returns the parameters of the model.
+    #[inline]
+    pub fn model_params(&self) -> &ModelParams {
+        self.model_params.borrow()
+    }
+
+    /// Replaces the parameters of the model, e.g. after a calibration.
+    ///
+    /// The filters and the warm start are reset, since the previous values
+    /// refer to the old parameters.
+    pub fn set_model_params(&mut self, model_params: M::Params) {
+        self.model_params = model_params;
+        self.reset();
+    }
+
+    /// Resets the filters, the warm start and the alarms, e.g. after the
+    /// device has been moved.
+    pub fn reset(&mut self) {
+        self.input.reset();
+        self.output.reset();
+        self.previous = None;
+        self.alarms.reset();
+    }
+
+    /// Processes a measurement.
+    ///
+    /// # Arguments
+    ///
+    /// * `currents` - The measured currents.
+    /// * `timestamp` - The time of the measurement, in the unit of the hold
+    ///   time of the alarms.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(estimate)` - The estimate of the variables.
+    /// * `None` - If the algorithm did not find a solution; the output
+    ///   filter and the alarms are not updated.
+    pub fn push(&mut self, currents: Currents, timestamp: u64) -> Option<Estimate> {
+        let currents = self.input.apply(currents);
+        let narrowed = match (&self.previous, &self.incremental) {
+            (Some((previous_currents, previous)), Some(incremental))
+                if relative_change(previous_currents, &currents) <= incremental.max_change =>
+            {
+                let params = self.alg_params.narrow(previous, incremental.span);
+                let model = M::new(self.model_params.clone(), currents);
+                let mut report = SolveReport::new();
+                A::new(params, model)
+                    .run_with(&mut report)
+                    .filter(|_| !report.boundary_hit)
+            }
+            _ => None,
+        };
+        let incremental = narrowed.is_some();
+        let (solution, loss) = match narrowed {
+            Some(narrowed) => narrowed,
+            None => {
+                let params = match &self.previous {
+                    Some((_, previous)) => self.alg_params.warm_start(previous),
+                    None => self.alg_params.clone(),
+                };
+                let model = M::new(self.model_params.clone(), currents);
+                A::new(params, model).run()?
+            }
+        };
+        self.previous = Some((currents, solution));
+
+        let variables = self.output.apply(solution);
+        let (mut raised, mut cleared) = (0, 0);
+        self.alarms
+            .update(timestamp, &variables, |event| match event {
+                AlarmEvent::Raised { index, .. } => raised |= 1 << index,
+                AlarmEvent::Cleared { index, .. } => cleared |= 1 << index,
+            });
+
+        Some(Estimate {
+            alarms: self.alarms.flags(),
+            cleared,
+            incremental,
+            loss,
+            raised,
+            solution,
+            timestamp,
+            variables,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "newton")]
+    use crate::alarms::{AlarmParams, Direction, Quantity};
+    #[cfg(feature = "brute-force")]
+    use crate::algorithms::BruteForceEquation;
+    #[cfg(feature = "newton")]
+    use crate::algorithms::NewtonEquation;
+    #[cfg(any(feature = "brute-force", feature = "newton"))]
+    use crate::{
+        algorithms::SaturationPolicy,
+        losses::Absolute,
+        models::Equation,
+        params::{ModulationParams, StemResistanceInvParams, Voltages},
+    };
+
+    #[cfg(any(feature = "brute-force", feature = "newton"))]
+    const MODEL_PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    #[cfg(feature = "newton")]
+    const ALG_PARAMS: NewtonParams = NewtonParams {
+        concentration_init: 1e-2,
+        grad_tolerance: 1e-9,
+        max_iterations: 100,
+        saturation_policy: SaturationPolicy::Keep,
+        tolerance: 1e-12,
+    };
+
+    /// Currents of a device immersed in a solution with the given
+    /// concentration and with wet resistance of 40 Ohm.
+    #[cfg(any(feature = "brute-force", feature = "newton"))]
+    fn currents(concentration: f32) -> Currents {
+        let zero = Currents {
+            i_ds_off: 0.0,
+            i_ds_on: 0.0,
+            i_gs_on: 0.0,
+        };
+        let model = Equation::new(MODEL_PARAMS, zero);
+        let m = model.modulation(concentration);
+        let r = model.stem_resistance_inv(concentration);
+        let i_gs_on = 0.5 * r;
+        Currents {
+            i_ds_off: -0.05 / 40.0,
+            i_ds_on: i_gs_on - 0.05 * (m + 1.0) / 40.0,
+            i_gs_on,
+        }
+    }
+
+    #[test]
+    fn test_exponential_filter() {
+        let mut filter = ExponentialFilter::new(0.25);
+        let vars = |concentration| Variables {
+            concentration,
+            resistance: 40.0,
+            saturation: 1.0,
+        };
+        assert_eq!(filter.apply(vars(1.0)), vars(1.0));
+        assert_eq!(filter.apply(vars(2.0)), vars(1.25));
+        assert_eq!(filter.apply(vars(f32::NAN)), vars(1.25));
+        filter.reset();
+        assert_eq!(filter.apply(vars(3.0)), vars(3.0));
+    }
+
+    #[cfg(feature = "newton")]
+    #[test]
+    fn test_warm_start() {
+        let previous = Variables {
+            concentration: 0.05,
+            resistance: 40.0,
+            saturation: 1.0,
+        };
+        assert_eq!(ALG_PARAMS.warm_start(&previous).concentration_init, 0.05);
+    }
+
+    #[cfg(feature = "brute-force")]
+    #[test]
+    fn test_narrow() {
+        let params = BruteForceParams {
+            concentration_range: FloatRange::new(1e-4, 1e-1, 100_000),
+            resistance_range: FloatRange::new(10.0, 100.0, 10),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 10),
+        };
+        let vars = |concentration| Variables {
+            concentration,
+            resistance: 40.0,
+            saturation: 1.0,
+        };
+
+        // The neighborhood is made of points of the whole grid.
+        let range = params.narrow(&vars(5e-3), 1.5).concentration_range;
+        assert!(range.start <= 5e-3 / 1.5 && range.start > 5e-3 / 1.5 - 0.999e-6);
+        assert!(range.end >= 7.5e-3 && range.end < 7.5e-3 + 0.999e-6);
+        let step = (range.end - range.start) / range.steps as f32;
+        assert!((step - 0.999e-6).abs() < 1e-10);
+        let index = (range.start - 1e-4) / 0.999e-6;
+        assert!((index - index.round()).abs() < 1e-2);
+
+        // The neighborhood is clipped to the grid.
+        let range = params.narrow(&vars(0.09), 1.5).concentration_range;
+        assert!(range.start <= 0.06 && range.start > 0.06 - 0.999e-6);
+        assert_eq!(range.end, 1e-1);
+
+        let range = params.narrow(&vars(f32::NAN), 1.5).concentration_range;
+        assert_eq!(range, params.concentration_range);
+        let range = params.narrow(&vars(1.0), 1.5).concentration_range;
+        assert_eq!(range, params.concentration_range);
+
+        // The iterative algorithms start from the solution.
+        #[cfg(feature = "newton")]
+        assert_eq!(ALG_PARAMS.narrow(&vars(5e-3), 1.5).concentration_init, 5e-3);
+    }
+
+    #[cfg(feature = "brute-force")]
+    #[test]
+    fn test_pipeline_incremental() {
+        let alg_params = BruteForceParams {
+            concentration_range: FloatRange::new(1e-4, 1e-1, 10_000),
+            resistance_range: FloatRange::new(10.0, 100.0, 1),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: FloatRange::new(0.0, 1.0, 1),
+        };
+        let incremental = IncrementalParams {
+            max_change: 0.01,
+            span: 1.5,
+        };
+        let mut pipeline = Pipeline::<BruteForceEquation<Equation, Absolute>, _, _, _, _, 0>::new(
+            alg_params.clone(),
+            MODEL_PARAMS,
+            (),
+            (),
+            Alarms::new([]),
+        )
+        .with_incremental(incremental);
+
+        let estimate = pipeline.push(currents(20e-3), 0).unwrap();
+        assert!(!estimate.incremental);
+
+        // The currents change by less than 1%, so only the neighborhood of
+        // the previous solution is searched.
+        let estimate = pipeline.push(currents(20.1e-3), 1).unwrap();
+        assert!(estimate.incremental);
+        let (expected, _) = BruteForceEquation::<_, Absolute>::new(
+            alg_params.clone(),
+            Equation::new(MODEL_PARAMS, currents(20.1e-3)),
+        )
+        .run()
+        .unwrap();
+        // The same point of the grid, up to the rounding of the iteration.
+        assert!((estimate.solution.concentration - expected.concentration).abs() < 1e-6);
+
+        let estimate = pipeline.push(currents(80e-3), 2).unwrap();
+        assert!(!estimate.incremental);
+
+        // The solution is on the boundary of a narrow neighborhood, so the
+        // whole grid is searched again.
+        let mut pipeline = Pipeline::<BruteForceEquation<Equation, Absolute>, _, _, _, _, 0>::new(
+            alg_params,
+            MODEL_PARAMS,
+            (),
+            (),
+            Alarms::new([]),
+        )
+        .with_incremental(IncrementalParams {
+            max_change: 0.01,
+            span: 1.001,
+        });
+        pipeline.push(currents(20e-3), 0).unwrap();
+        let estimate = pipeline.push(currents(20.1e-3), 1).unwrap();
+        assert!(!estimate.incremental);
+        assert!((estimate.solution.concentration - 20.1e-3).abs() < 1e-5);
+    }
+
+    #[cfg(feature = "newton")]
+    #[test]
+    fn test_pipeline() {
+        let alarm = AlarmParams {
+            direction: Direction::Above,
+            hold_time: 0,
+            hysteresis: 1e-3,
+            quantity: Quantity::Concentration,
+            threshold: 50e-3,
+        };
+        let mut pipeline = Pipeline::<NewtonEquation<Equation, Absolute>, _, _, _, _, 1>::new(
+            ALG_PARAMS,
+            MODEL_PARAMS,
+            (),
+            ExponentialFilter::new(0.5),
+            Alarms::new([alarm]),
+        );
+
+        let estimate = pipeline.push(currents(20e-3), 0).unwrap();
+        assert!((estimate.solution.concentration - 20e-3).abs() < 1e-4);
+        assert_eq!(estimate.variables, estimate.solution);
+        assert_eq!((estimate.alarms, estimate.raised), (0, 0));
+
+        // The output filter delays the alarm by one measurement.
+        let estimate = pipeline.push(currents(100e-3), 1).unwrap();
+        assert!((estimate.solution.concentration - 100e-3).abs() < 1e-3);
+        assert!((estimate.variables.concentration - 60e-3).abs() < 1e-3);
+        assert_eq!((estimate.alarms, estimate.raised), (1, 1));
+
+        let estimate = pipeline.push(currents(100e-3), 2).unwrap();
+        assert_eq!((estimate.alarms, estimate.raised), (1, 0));
+
+        pipeline.reset();
+        let estimate = pipeline.push(currents(20e-3), 3).unwrap();
+        assert_eq!((estimate.alarms, estimate.cleared), (0, 0));
+    }
+    #[cfg(feature = "newton")]
+    #[test]
+    fn test_pipeline_borrowed() {
+        // The pipelines of two channels share the parameters of the model.
+        let channel = || {
+            Pipeline::<NewtonEquation<Equation<&ModelParams>, Absolute>, _, _, _, _, 0>::new(
+                ALG_PARAMS,
+                &MODEL_PARAMS,
+                (),
+                (),
+                Alarms::new([]),
+            )
+        };
+        let mut owned = Pipeline::<NewtonEquation<Equation, Absolute>, _, _, _, _, 0>::new(
+            ALG_PARAMS,
+            MODEL_PARAMS,
+            (),
+            (),
+            Alarms::new([]),
+        );
+        let (mut first, mut second) = (channel(), channel());
+        assert!(core::ptr::eq(first.model_params(), second.model_params()));
+
+        for concentration in [20e-3, 50e-3] {
+            let expected = owned.push(currents(concentration), 0).unwrap();
+            let first = first.push(currents(concentration), 0).unwrap();
+            let second = second.push(currents(concentration * 2.0), 0).unwrap();
+            assert_eq!(first.solution, expected.solution);
+            assert!((second.solution.concentration - concentration * 2.0).abs() < 1e-3);
+        }
+    }
+}
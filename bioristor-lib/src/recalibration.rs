@@ -0,0 +1,270 @@
+use crate::models::{Model, System};
+use crate::params::{Currents, ModelParams, ModulationParams};
+
+/// The parameters of the [`Recalibration`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RecalibrationParams {
+    /// The maximum change of the bias in a single update.
+    pub bias_step_max: f32,
+
+    /// The maximum change of the gain in a single update.
+    pub gain_step_max: f32,
+
+    /// The fraction of the error corrected by a single update, between zero
+    /// and one. Small values average out the error of the references.
+    pub learning_rate: f32,
+}
+
+/// Computes the modulation of the channel from the currents measured when the
+/// concentration is known, e.g. from a sample of sap analyzed in the lab.
+///
+/// The saturation is given by the gate-source current and the stem
+/// resistance function, after which the resistance of the electrolyte and
+/// the modulation follow from the drain-source currents.
+///
+/// # Arguments
+///
+/// * `concentration` - The reference concentration [Molarity].
+/// * `currents` - The currents measured at the time of the sample.
+/// * `params` - The parameters of the model.
+///
+/// # Returns
+///
+/// * `Some(modulation)` - The modulation of the channel.
+/// * `None` - If the concentration is not positive, or the currents are not
+///   consistent with the model.
+pub fn measured_modulation(
+    concentration: f32,
+    currents: &Currents,
+    params: &ModelParams,
+) -> Option<f32> {
+    if !(concentration > 0.0 && concentration.is_finite()) {
+        return None;
+    }
+
+    let model = System::new(params.clone(), *currents);
+    let r_dry = params.r_dry;
+    let saturation =
+        currents.i_gs_on / (params.voltages.v_gs * model.stem_resistance_inv(concentration));
+    // Resistance of the electrolyte with the gate off and on.
+    let off = (params.voltages.v_ds / currents.i_ds_off - r_dry) / saturation + r_dry;
+    let on =
+        (params.voltages.v_ds / (currents.i_ds_on - currents.i_gs_on) - r_dry) / saturation + r_dry;
+    let modulation = off / on - 1.0;
+
+    (saturation > 0.0 && modulation.is_finite()).then_some(modulation)
+}
+
+/// Slow correction of the modulation function, compensating the ageing of
+/// the sensor in long deployments.
+///
+/// The modulation of the calibrated parameters is corrected with a gain and a
+/// bias:
+/// ```text
+/// gain * (a * x + b * ln(x) + c) + bias
+/// ```
+/// which are updated with the normalized least mean squares method every
+/// time a reference concentration is available. Every update is bounded, so
+/// that a wrong reference cannot disrupt the estimates.
+#[derive(Debug, Clone)]
+pub struct Recalibration {
+    /// The parameters of the modulation function from the calibration.
+    base: ModulationParams,
+
+    /// The additive correction.
+    bias: f32,
+
+    /// The multiplicative correction.
+    gain: f32,
+
+    /// The parameters of the recalibration.
+    params: RecalibrationParams,
+
+    /// The number of updates applied.
+    updates: u32,
+}
+
+impl Recalibration {
+    /// Creates a new recalibration, without any correction.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the recalibration.
+    /// * `base` - The parameters of the modulation function from the
+    ///   calibration.
+    pub fn new(params: RecalibrationParams, base: ModulationParams) -> Self {
+        Self {
+            base,
+            bias: 0.0,
+            gain: 1.0,
+            params,
+            updates: 0,
+        }
+    }
+
+    /// Returns the additive correction.
+    #[inline]
+    pub fn bias(&self) -> f32 {
+        self.bias
+    }
+
+    /// Returns the multiplicative correction.
+    #[inline]
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Returns the number of updates applied.
+    #[inline]
+    pub fn updates(&self) -> u32 {
+        self.updates
+    }
+
+    /// Removes the correction, e.g. after a new calibration.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The parameters of the modulation function from the
+    ///   calibration.
+    pub fn reset(&mut self, base: ModulationParams) {
+        *self = Self::new(self.params.clone(), base);
+    }
+
+    /// Returns the corrected parameters of the modulation function.
+    pub fn modulation_params(&self) -> ModulationParams {
+        ModulationParams(
+            self.gain * self.base.0,
+            self.gain * self.base.1,
+            self.gain * self.base.2 + self.bias,
+        )
+    }
+
+    /// Updates the correction with a reference concentration and applies it
+    /// to the parameters of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `concentration` - The reference concentration [Molarity].
+    /// * `currents` - The currents measured at the time of the sample.
+    /// * `params` - The parameters of the model, whose modulation function is
+    ///   replaced by the corrected one.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(error)` - The error of the modulation before the update.
+    /// * `None` - If the reference is not valid, see
+    ///   [`measured_modulation`]; the correction is left unchanged.
+    pub fn update(
+        &mut self,
+        concentration: f32,
+        currents: &Currents,
+        params: &mut ModelParams,
+    ) -> Option<f32> {
+        let measured = measured_modulation(concentration, currents, params)?;
+        let model = System::new(
+            ModelParams {
+                mod_params: self.base,
+                ..params.clone()
+            },
+            *currents,
+        );
+        let predicted = model.modulation(concentration);
+
+        let error = measured - (self.gain * predicted + self.bias);
+        let step = self.params.learning_rate * error / (1.0 + predicted * predicted);
+        self.gain +=
+            (step * predicted).clamp(-self.params.gain_step_max, self.params.gain_step_max);
+        self.bias += step.clamp(-self.params.bias_step_max, self.params.bias_step_max);
+        self.updates += 1;
+
+        params.mod_params = self.modulation_params();
+        Some(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SystemModel;
+    use crate::params::{StemResistanceInvParams, Variables, Voltages};
+
+    const MODEL_PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    const PARAMS: RecalibrationParams = RecalibrationParams {
+        bias_step_max: 0.01,
+        gain_step_max: 0.01,
+        learning_rate: 0.5,
+    };
+
+    /// Currents of a device with the given modulation function.
+    fn currents(mod_params: ModulationParams, concentration: f32) -> Currents {
+        let zero = Currents {
+            i_ds_off: 0.0,
+            i_ds_on: 0.0,
+            i_gs_on: 0.0,
+        };
+        let params = ModelParams {
+            mod_params,
+            ..MODEL_PARAMS
+        };
+        let [(_, i_ds_on), (_, i_ds_off), (_, i_gs_on)] =
+            System::new(params, zero).value(Variables {
+                concentration,
+                resistance: 40.0,
+                saturation: 0.8,
+            });
+        // The model adds the measured gate-source current, zero here.
+        Currents {
+            i_ds_off,
+            i_ds_on: i_ds_on + i_gs_on,
+            i_gs_on,
+        }
+    }
+
+    #[test]
+    fn test_measured_modulation() {
+        let currents = currents(MODEL_PARAMS.mod_params, 1e-2);
+        let model = System::new(MODEL_PARAMS, currents);
+        let modulation = measured_modulation(1e-2, &currents, &MODEL_PARAMS).unwrap();
+        assert!((modulation - model.modulation(1e-2)).abs() < 1e-4);
+        assert_eq!(measured_modulation(0.0, &currents, &MODEL_PARAMS), None);
+    }
+
+    #[test]
+    fn test_recalibration() {
+        let base = MODEL_PARAMS.mod_params;
+        let aged = ModulationParams(0.0, 1.1 * base.1, 1.1 * base.2 + 0.02);
+        let mut recalibration = Recalibration::new(PARAMS, base);
+        let mut params = MODEL_PARAMS;
+
+        let first = recalibration
+            .update(1e-2, &currents(aged, 1e-2), &mut params)
+            .unwrap();
+        // The first update is bounded.
+        assert!((recalibration.gain() - 1.0).abs() <= PARAMS.gain_step_max);
+        assert!(recalibration.bias().abs() <= PARAMS.bias_step_max);
+        assert_eq!(params.mod_params, recalibration.modulation_params());
+
+        for i in 0..2000 {
+            let concentration = [1e-3, 1e-2, 1e-1][i % 3];
+            recalibration.update(concentration, &currents(aged, concentration), &mut params);
+        }
+        assert_eq!(recalibration.updates(), 2001);
+        let last = recalibration
+            .update(1e-2, &currents(aged, 1e-2), &mut params)
+            .unwrap();
+        assert!(last.abs() < first.abs() / 10.0, "{first} {last}");
+
+        recalibration.reset(base);
+        assert_eq!(recalibration.modulation_params(), base);
+    }
+}
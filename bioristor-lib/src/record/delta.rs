@@ -0,0 +1,415 @@
+use super::{MeasurementRecord, RecordError, RECORD_SIZE};
+
+/// The tag of a record encoded in full.
+const TAG_FULL: u8 = 0x00;
+
+/// The flag of the tag of a record encoded as a delta, whose lower bits tell
+/// which fields are present.
+const TAG_DELTA: u8 = 0x80;
+
+/// The maximum size of an encoded record, that is a record encoded in full.
+pub const MAX_DELTA_SIZE: usize = RECORD_SIZE + 1;
+
+/// The errors of the delta encoding and decoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeltaError {
+    /// The buffer is too small for the encoded record.
+    BufferTooSmall,
+
+    /// The encoded record is truncated.
+    Truncated,
+
+    /// The tag of the encoded record is not valid.
+    InvalidTag,
+
+    /// A delta has been received before any record encoded in full.
+    NoReference,
+
+    /// The record encoded in full is not valid.
+    Record(RecordError),
+}
+
+/// The fields of a delta, in the order they are encoded.
+///
+/// A field is present only if its delta differs from the expected one, that
+/// is one for the sequence number and zero for the others.
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Sequence,
+    Timestamp,
+    Concentration,
+    Resistance,
+    Saturation,
+    Loss,
+}
+
+impl Field {
+    const ALL: [Field; 6] = [
+        Field::Sequence,
+        Field::Timestamp,
+        Field::Concentration,
+        Field::Resistance,
+        Field::Saturation,
+        Field::Loss,
+    ];
+
+    /// Returns the zigzag-encoded delta of the field between two records,
+    /// zero if the field changed as expected.
+    fn delta(self, previous: &MeasurementRecord, record: &MeasurementRecord) -> u64 {
+        let float = |previous: f32, value: f32| {
+            // The bit patterns of close floats are close integers, so their
+            // difference is small and the encoding is lossless.
+            zigzag(value.to_bits().wrapping_sub(previous.to_bits()) as i32 as i64)
+        };
+        match self {
+            Field::Sequence => record
+                .sequence
+                .wrapping_sub(previous.sequence)
+                .wrapping_sub(1) as u64,
+            Field::Timestamp => zigzag(record.timestamp.wrapping_sub(previous.timestamp) as i64),
+            Field::Concentration => float(
+                previous.variables.concentration,
+                record.variables.concentration,
+            ),
+            Field::Resistance => float(previous.variables.resistance, record.variables.resistance),
+            Field::Saturation => float(previous.variables.saturation, record.variables.saturation),
+            Field::Loss => float(previous.loss, record.loss),
+        }
+    }
+
+    /// Applies the zigzag-encoded delta of the field to a record.
+    fn apply(self, record: &mut MeasurementRecord, delta: u64) {
+        let float = |value: &mut f32| {
+            *value = f32::from_bits(value.to_bits().wrapping_add(unzigzag(delta) as u32));
+        };
+        match self {
+            Field::Sequence => record.sequence = record.sequence.wrapping_add(delta as u32),
+            Field::Timestamp => {
+                record.timestamp = record.timestamp.wrapping_add(unzigzag(delta) as u64)
+            }
+            Field::Concentration => float(&mut record.variables.concentration),
+            Field::Resistance => float(&mut record.variables.resistance),
+            Field::Saturation => float(&mut record.variables.saturation),
+            Field::Loss => float(&mut record.loss),
+        }
+    }
+}
+
+/// Encoder of a sequence of [`MeasurementRecord`]s, where every record is
+/// encoded as the difference from the previous one.
+///
+/// The first record, and the first one after [`DeltaEncoder::reset`], is
+/// encoded in full, with its CRC. The following ones are encoded as a tag
+/// telling which fields changed, followed by their deltas as LEB128 varints:
+/// the sequence number and the timestamp as integers, the floats as the
+/// difference of their bit patterns, so that the encoding is lossless. A
+/// record of a slowly changing solution taken at a regular interval takes
+/// about 10 bytes instead of [`RECORD_SIZE`].
+///
+/// The deltas are not protected by a CRC: the encoded sequence must be
+/// protected by the medium, e.g. the MIC of a LoRaWAN uplink, and the encoder
+/// must be reset at the start of every independent unit, e.g. every uplink,
+/// so that a lost unit does not corrupt the following ones.
+///
+/// # Example
+///
+/// ```
+/// use bioristor_lib::params::Variables;
+/// use bioristor_lib::record::{DeltaDecoder, DeltaEncoder, MeasurementRecord, RECORD_SIZE};
+///
+/// let records = [0, 1, 2].map(|i| MeasurementRecord {
+///     loss: 1e-6,
+///     sequence: i,
+///     timestamp: 1_700_000_000 + 60 * i as u64,
+///     variables: Variables {
+///         concentration: 1e-2 + 1e-6 * i as f32,
+///         resistance: 40.0,
+///         saturation: 1.0,
+///     },
+/// });
+///
+/// let mut buf = [0; 64];
+/// let mut encoder = DeltaEncoder::new();
+/// let mut len = 0;
+/// for record in &records {
+///     len += encoder.encode(record, &mut buf[len..]).unwrap();
+/// }
+/// assert!(len < records.len() * RECORD_SIZE / 2);
+///
+/// let mut decoder = DeltaDecoder::new();
+/// let mut pos = 0;
+/// for record in &records {
+///     let (decoded, n) = decoder.decode(&buf[pos..len]).unwrap();
+///     assert_eq!(&decoded, record);
+///     pos += n;
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeltaEncoder {
+    /// The last encoded record.
+    previous: Option<MeasurementRecord>,
+}
+
+impl DeltaEncoder {
+    /// Creates a new encoder.
+    pub const fn new() -> Self {
+        Self { previous: None }
+    }
+
+    /// Resets the encoder, so that the next record is encoded in full.
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    /// Encodes a record.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The record to be encoded.
+    /// * `buf` - The buffer where the encoded record is written, at least
+    ///   [`MAX_DELTA_SIZE`] bytes long to fit any record.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(len)` - The length of the encoded record, written at the start
+    ///   of `buf`.
+    /// * `Err(error)` - If the buffer is too small; the state of the encoder
+    ///   is not changed, so that the record can be encoded in the next unit
+    ///   after a reset.
+    pub fn encode(
+        &mut self,
+        record: &MeasurementRecord,
+        buf: &mut [u8],
+    ) -> Result<usize, DeltaError> {
+        let len = match &self.previous {
+            None => {
+                let dst = buf
+                    .get_mut(..MAX_DELTA_SIZE)
+                    .ok_or(DeltaError::BufferTooSmall)?;
+                dst[0] = TAG_FULL;
+                dst[1..].copy_from_slice(&record.to_bytes());
+                MAX_DELTA_SIZE
+            }
+            Some(previous) => {
+                let mut tag = TAG_DELTA;
+                let mut len = 1;
+                for (i, field) in Field::ALL.into_iter().enumerate() {
+                    let delta = field.delta(previous, record);
+                    if delta != 0 {
+                        tag |= 1 << i;
+                        len += write_varint(delta, buf.get_mut(len..).unwrap_or_default())?;
+                    }
+                }
+                *buf.first_mut().ok_or(DeltaError::BufferTooSmall)? = tag;
+                len
+            }
+        };
+
+        self.previous = Some(*record);
+        Ok(len)
+    }
+}
+
+/// Decoder of a sequence of [`MeasurementRecord`]s encoded by a
+/// [`DeltaEncoder`].
+#[derive(Debug, Clone, Default)]
+pub struct DeltaDecoder {
+    /// The last decoded record.
+    previous: Option<MeasurementRecord>,
+}
+
+impl DeltaDecoder {
+    /// Creates a new decoder.
+    pub const fn new() -> Self {
+        Self { previous: None }
+    }
+
+    /// Resets the decoder, at the start of an independent unit.
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    /// Decodes a record.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The encoded records, starting with the one to decode.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((record, len))` - The decoded record and the length of its
+    ///   encoding.
+    /// * `Err(error)` - If the record cannot be decoded.
+    pub fn decode(&mut self, bytes: &[u8]) -> Result<(MeasurementRecord, usize), DeltaError> {
+        let (&tag, rest) = bytes.split_first().ok_or(DeltaError::Truncated)?;
+        let (record, len) = match tag {
+            TAG_FULL => {
+                let record = MeasurementRecord::from_bytes(rest).map_err(|error| match error {
+                    RecordError::Truncated => DeltaError::Truncated,
+                    error => DeltaError::Record(error),
+                })?;
+                (record, MAX_DELTA_SIZE)
+            }
+            tag if tag & TAG_DELTA != 0 && tag & !TAG_DELTA < 1 << Field::ALL.len() => {
+                let mut record = self.previous.ok_or(DeltaError::NoReference)?;
+                record.sequence = record.sequence.wrapping_add(1);
+                let mut len = 1;
+                for (i, field) in Field::ALL.into_iter().enumerate() {
+                    if tag & 1 << i != 0 {
+                        let (delta, n) = read_varint(&bytes[len..])?;
+                        field.apply(&mut record, delta);
+                        len += n;
+                    }
+                }
+                (record, len)
+            }
+            _ => return Err(DeltaError::InvalidTag),
+        };
+
+        self.previous = Some(record);
+        Ok((record, len))
+    }
+}
+
+/// Encodes a signed integer so that small magnitudes give small values.
+#[inline]
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Decodes a value encoded by [`zigzag`].
+#[inline]
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Writes a LEB128 varint.
+fn write_varint(mut value: u64, buf: &mut [u8]) -> Result<usize, DeltaError> {
+    let mut len = 0;
+    loop {
+        let byte = buf.get_mut(len).ok_or(DeltaError::BufferTooSmall)?;
+        len += 1;
+        if value < 0x80 {
+            *byte = value as u8;
+            return Ok(len);
+        }
+        *byte = value as u8 | 0x80;
+        value >>= 7;
+    }
+}
+
+/// Reads a LEB128 varint.
+fn read_varint(bytes: &[u8]) -> Result<(u64, usize), DeltaError> {
+    let mut value = 0;
+    for (i, &byte) in bytes.iter().enumerate().take(10) {
+        value |= ((byte & 0x7F) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    Err(DeltaError::Truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::Variables;
+
+    fn record(i: u32) -> MeasurementRecord {
+        MeasurementRecord {
+            loss: 1e-6 * (1 + i % 3) as f32,
+            sequence: i,
+            timestamp: 1_700_000_000 + 60 * i as u64,
+            variables: Variables {
+                concentration: 1e-2 * (1.0 + 1e-3 * i as f32),
+                resistance: 40.0 - 0.01 * i as f32,
+                saturation: 1.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_varint() {
+        let mut buf = [0; 10];
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let len = write_varint(value, &mut buf).unwrap();
+            assert_eq!(read_varint(&buf[..len]), Ok((value, len)));
+        }
+        assert_eq!(
+            write_varint(300, &mut buf[..1]),
+            Err(DeltaError::BufferTooSmall)
+        );
+        assert_eq!(read_varint(&[0x80]), Err(DeltaError::Truncated));
+
+        for value in [0, 1, -1, i32::MIN as i64, i64::MAX, i64::MIN] {
+            assert_eq!(unzigzag(zigzag(value)), value);
+        }
+        assert_eq!(zigzag(-1), 1);
+    }
+
+    #[test]
+    fn test_delta() {
+        let mut buf = [0; 256];
+        let mut encoder = DeltaEncoder::new();
+        let mut len = 0;
+        for i in 0..10 {
+            len += encoder.encode(&record(i), &mut buf[len..]).unwrap();
+        }
+        // A record with a gap in the sequence and a NaN.
+        let gap = MeasurementRecord {
+            sequence: 20,
+            variables: Variables {
+                concentration: f32::NAN,
+                ..record(20).variables
+            },
+            ..record(20)
+        };
+        len += encoder.encode(&gap, &mut buf[len..]).unwrap();
+        // The 11 full records would take 352 bytes.
+        assert!(len < 160, "{len}");
+
+        let mut decoder = DeltaDecoder::new();
+        let mut pos = 0;
+        for i in 0..10 {
+            let (decoded, n) = decoder.decode(&buf[pos..len]).unwrap();
+            assert_eq!(decoded, record(i));
+            pos += n;
+        }
+        let (decoded, n) = decoder.decode(&buf[pos..len]).unwrap();
+        assert_eq!(decoded.sequence, 20);
+        assert!(decoded.variables.concentration.is_nan());
+        assert_eq!(pos + n, len);
+    }
+
+    #[test]
+    fn test_delta_errors() {
+        let mut buf = [0; 64];
+        let mut encoder = DeltaEncoder::new();
+        assert_eq!(
+            encoder.encode(&record(0), &mut buf[..RECORD_SIZE]),
+            Err(DeltaError::BufferTooSmall)
+        );
+        let full = encoder.encode(&record(0), &mut buf).unwrap();
+        let delta = encoder.encode(&record(1), &mut buf[full..]).unwrap();
+
+        // A delta needs the previous record.
+        let mut decoder = DeltaDecoder::new();
+        assert_eq!(
+            decoder.decode(&buf[full..full + delta]),
+            Err(DeltaError::NoReference)
+        );
+
+        assert_eq!(decoder.decode(&buf[..full - 1]), Err(DeltaError::Truncated));
+        buf[5] ^= 0x01;
+        assert_eq!(
+            decoder.decode(&buf[..full]),
+            Err(DeltaError::Record(RecordError::Crc))
+        );
+        assert_eq!(decoder.decode(&[0x40]), Err(DeltaError::InvalidTag));
+
+        // Encoding again after a reset restarts with a full record.
+        encoder.reset();
+        assert_eq!(encoder.encode(&record(2), &mut buf), Ok(MAX_DELTA_SIZE));
+    }
+}
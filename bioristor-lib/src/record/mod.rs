@@ -0,0 +1,299 @@
+mod delta;
+
+pub use delta::*;
+
+use crate::params::Variables;
+use crate::utils::CRC16;
+
+/// The size of a serialized [`MeasurementRecord`], a multiple of the write
+/// size of most internal flash memories.
+pub const RECORD_SIZE: usize = 32;
+
+/// The first byte of every serialized [`MeasurementRecord`], used to find the
+/// start of the records in a byte stream.
+pub const RECORD_MAGIC: u8 = 0xB5;
+
+/// The version of the layout of the serialized [`MeasurementRecord`].
+pub const RECORD_VERSION: u8 = 1;
+
+/// The errors of the deserialization of a [`MeasurementRecord`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecordError {
+    /// The bytes are less than [`RECORD_SIZE`].
+    Truncated,
+
+    /// The first byte is not [`RECORD_MAGIC`].
+    Magic,
+
+    /// The layout version is not supported.
+    Version(u8),
+
+    /// The CRC does not match, e.g. for a corrupted record or an erased slot.
+    Crc,
+}
+
+/// A solution of the model, with the same fixed-size serialization in the
+/// flash log and in the telemetry, so that the host tools share a single
+/// parser.
+///
+/// The record is serialized in little endian as:
+///
+/// | Offset | Size | Field                  |
+/// |--------|------|------------------------|
+/// | 0      | 1    | [`RECORD_MAGIC`]       |
+/// | 1      | 1    | [`RECORD_VERSION`]     |
+/// | 2      | 4    | `sequence`             |
+/// | 6      | 8    | `timestamp`            |
+/// | 14     | 4    | `concentration`        |
+/// | 18     | 4    | `resistance`           |
+/// | 22     | 4    | `saturation`           |
+/// | 26     | 4    | `loss`                 |
+/// | 30     | 2    | CRC16 of bytes 0 to 29 |
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MeasurementRecord {
+    /// The loss of the solution.
+    pub loss: f32,
+
+    /// The sequence number of the record, increasing by one for every record.
+    pub sequence: u32,
+
+    /// The time of the measurement, in seconds since the Unix epoch.
+    pub timestamp: u64,
+
+    /// The variables of the solution.
+    pub variables: Variables,
+}
+
+impl MeasurementRecord {
+    /// Serializes the record, followed by its CRC.
+    ///
+    /// # Returns
+    ///
+    /// The serialized record.
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut bytes = [0; RECORD_SIZE];
+        bytes[0] = RECORD_MAGIC;
+        bytes[1] = RECORD_VERSION;
+        bytes[2..6].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes[6..14].copy_from_slice(&self.timestamp.to_le_bytes());
+        bytes[14..18].copy_from_slice(&self.variables.concentration.to_le_bytes());
+        bytes[18..22].copy_from_slice(&self.variables.resistance.to_le_bytes());
+        bytes[22..26].copy_from_slice(&self.variables.saturation.to_le_bytes());
+        bytes[26..30].copy_from_slice(&self.loss.to_le_bytes());
+        let crc = CRC16.checksum(&bytes[..30]);
+        bytes[30..32].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// Deserializes a record, checking its CRC.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The serialized record, only the first [`RECORD_SIZE`]
+    ///   bytes are used.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(record)` - The record.
+    /// * `Err(error)` - If the bytes are not a valid record.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RecordError> {
+        let bytes = bytes.get(..RECORD_SIZE).ok_or(RecordError::Truncated)?;
+        if bytes[0] != RECORD_MAGIC {
+            return Err(RecordError::Magic);
+        }
+        let crc = u16::from_le_bytes([bytes[30], bytes[31]]);
+        if CRC16.checksum(&bytes[..30]) != crc {
+            return Err(RecordError::Crc);
+        }
+        if bytes[1] != RECORD_VERSION {
+            return Err(RecordError::Version(bytes[1]));
+        }
+
+        let word = |i: usize| [bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]];
+        let mut timestamp = [0; 8];
+        timestamp.copy_from_slice(&bytes[6..14]);
+        Ok(Self {
+            loss: f32::from_le_bytes(word(26)),
+            sequence: u32::from_le_bytes(word(2)),
+            timestamp: u64::from_le_bytes(timestamp),
+            variables: Variables {
+                concentration: f32::from_le_bytes(word(14)),
+                resistance: f32::from_le_bytes(word(18)),
+                saturation: f32::from_le_bytes(word(22)),
+            },
+        })
+    }
+}
+
+/// The record is serialized as its fixed-size bytes, so that it keeps its CRC
+/// also when it is embedded in other messages.
+#[cfg(feature = "serde")]
+impl serde::Serialize for MeasurementRecord {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_bytes().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for MeasurementRecord {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = <[u8; RECORD_SIZE]>::deserialize(deserializer)?;
+        Self::from_bytes(&bytes).map_err(|_| serde::de::Error::custom("invalid measurement record"))
+    }
+}
+
+/// Decoder of a stream of back-to-back serialized records, received one byte
+/// at a time.
+///
+/// The decoder can start in the middle of the stream and recovers from
+/// truncated or corrupted records: whenever the buffered bytes are not a
+/// valid record, they are skipped up to the next [`RECORD_MAGIC`].
+#[derive(Debug, Clone)]
+pub struct RecordDecoder {
+    /// The bytes of the record being received.
+    buf: [u8; RECORD_SIZE],
+
+    /// The number of bytes in `buf`.
+    len: usize,
+
+    /// The number of bytes skipped since the last valid record.
+    skipped: usize,
+}
+
+impl RecordDecoder {
+    /// Creates a new decoder.
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; RECORD_SIZE],
+            len: 0,
+            skipped: 0,
+        }
+    }
+
+    /// Returns the number of bytes skipped since the last valid record, that
+    /// is non-zero if the stream has been corrupted.
+    #[inline]
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Feeds a byte to the decoder.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte` - The received byte.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(record)` - If a valid record has been completed.
+    /// * `None` - If the record is not completed yet.
+    pub fn push(&mut self, byte: u8) -> Option<MeasurementRecord> {
+        if self.len == 0 && byte != RECORD_MAGIC {
+            self.skipped += 1;
+            return None;
+        }
+        self.buf[self.len] = byte;
+        self.len += 1;
+        if self.len < RECORD_SIZE {
+            return None;
+        }
+
+        if let Ok(record) = MeasurementRecord::from_bytes(&self.buf) {
+            self.len = 0;
+            self.skipped = 0;
+            return Some(record);
+        }
+
+        // Resynchronize on the next magic byte, which may be the start of a
+        // record following a truncated one.
+        let next = self.buf[1..]
+            .iter()
+            .position(|&byte| byte == RECORD_MAGIC)
+            .map_or(RECORD_SIZE, |i| i + 1);
+        self.buf.copy_within(next.., 0);
+        self.len = RECORD_SIZE - next;
+        self.skipped += next;
+        None
+    }
+}
+
+impl Default for RecordDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(sequence: u32) -> MeasurementRecord {
+        MeasurementRecord {
+            loss: 1e-3,
+            sequence,
+            timestamp: 1_700_000_000 + sequence as u64,
+            variables: Variables {
+                concentration: 0.1,
+                resistance: 1000.0,
+                saturation: 0.5,
+            },
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let mut bytes = record(7).to_bytes();
+        assert_eq!(bytes[0], RECORD_MAGIC);
+        assert_eq!(MeasurementRecord::from_bytes(&bytes), Ok(record(7)));
+        assert_eq!(
+            MeasurementRecord::from_bytes(&bytes[..RECORD_SIZE - 1]),
+            Err(RecordError::Truncated)
+        );
+
+        bytes[5] ^= 0x10;
+        assert_eq!(MeasurementRecord::from_bytes(&bytes), Err(RecordError::Crc));
+        assert_eq!(
+            MeasurementRecord::from_bytes(&[0xFF; RECORD_SIZE]),
+            Err(RecordError::Magic)
+        );
+
+        let mut bytes = record(7).to_bytes();
+        bytes[1] = 2;
+        let crc = CRC16.checksum(&bytes[..30]);
+        bytes[30..32].copy_from_slice(&crc.to_le_bytes());
+        assert_eq!(
+            MeasurementRecord::from_bytes(&bytes),
+            Err(RecordError::Version(2))
+        );
+    }
+
+    #[test]
+    fn test_record_decoder() {
+        let mut stream = [0; 5 * RECORD_SIZE];
+        for (i, chunk) in stream.chunks_mut(RECORD_SIZE).enumerate() {
+            chunk.copy_from_slice(&record(i as u32).to_bytes());
+        }
+        // Corrupt the second record.
+        stream[RECORD_SIZE + 10] ^= 0xFF;
+
+        let mut decoder = RecordDecoder::new();
+        let mut sequences = [0; 5];
+        let mut n = 0;
+        // Start in the middle of the first record, and truncate the fourth one.
+        let truncated = 3 * RECORD_SIZE..3 * RECORD_SIZE + 20;
+        for (i, &byte) in stream.iter().enumerate().skip(10) {
+            if truncated.contains(&i) {
+                continue;
+            }
+            if let Some(record) = decoder.push(byte) {
+                assert_eq!(record, self::record(record.sequence));
+                sequences[n] = record.sequence;
+                n += 1;
+            }
+        }
+        assert_eq!(&sequences[..n], &[2, 4]);
+        assert_eq!(decoder.skipped(), 0);
+    }
+}
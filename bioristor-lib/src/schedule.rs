@@ -0,0 +1,286 @@
+use crate::params::Variables;
+use crate::timestamp::SECONDS_PER_DAY;
+
+/// The policy deciding when the next measurement is taken.
+///
+/// The times are expressed in seconds, the times of the day in seconds since
+/// midnight UTC, see [`Timestamp`](crate::timestamp::Timestamp).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SchedulePolicy {
+    /// Measurements at a fixed interval.
+    Fixed {
+        /// The time between two measurements.
+        interval: u64,
+    },
+
+    /// Dense measurements during the day, when the transpiration drives the
+    /// dynamics of the plant, and sparse ones during the night.
+    DayNight {
+        /// The time of the day at which the day starts.
+        day_start: u32,
+
+        /// The time between two measurements during the day.
+        day_interval: u64,
+
+        /// The time of the day at which the night starts.
+        night_start: u32,
+
+        /// The time between two measurements during the night.
+        night_interval: u64,
+    },
+
+    /// Measurements whose interval adapts to the changes of the variables:
+    /// the interval is doubled every time the concentration is stable, and
+    /// brought back to the minimum as soon as it changes.
+    ChangeDetection {
+        /// The maximum time between two measurements.
+        max_interval: u64,
+
+        /// The minimum time between two measurements.
+        min_interval: u64,
+
+        /// The relative change of the concentration considered significant.
+        threshold: f32,
+    },
+}
+
+impl SchedulePolicy {
+    /// Returns whether the given time is during the day.
+    ///
+    /// # Arguments
+    ///
+    /// * `day_start` - The time of the day at which the day starts.
+    /// * `night_start` - The time of the day at which the night starts.
+    /// * `now` - The current time.
+    fn is_day(day_start: u32, night_start: u32, now: u64) -> bool {
+        let time = (now % SECONDS_PER_DAY) as u32;
+        if day_start <= night_start {
+            (day_start..night_start).contains(&time)
+        } else {
+            // The day spans midnight UTC, e.g. in the eastern hemisphere.
+            !(night_start..day_start).contains(&time)
+        }
+    }
+}
+
+/// Scheduler of the measurements, driven by a [`SchedulePolicy`].
+///
+/// The scheduler never blocks: the application checks
+/// [`is_due`](Scheduler::is_due) periodically, or sleeps until
+/// [`next`](Scheduler::next), and reports every completed measurement with
+/// [`record`](Scheduler::record).
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    /// The current interval of the change detection policy.
+    interval: u64,
+
+    /// The time of the last measurement.
+    last: Option<u64>,
+
+    /// The policy of the scheduler.
+    policy: SchedulePolicy,
+
+    /// The concentration of the last measurement that changed the interval.
+    reference: Option<f32>,
+}
+
+impl Scheduler {
+    /// Creates a new scheduler, with the first measurement due immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy of the scheduler.
+    pub fn new(policy: SchedulePolicy) -> Self {
+        let interval = match policy {
+            SchedulePolicy::ChangeDetection { min_interval, .. } => min_interval,
+            _ => 0,
+        };
+        Self {
+            interval,
+            last: None,
+            policy,
+            reference: None,
+        }
+    }
+
+    /// Returns the policy of the scheduler.
+    #[inline]
+    pub fn policy(&self) -> &SchedulePolicy {
+        &self.policy
+    }
+
+    /// Returns the time between two measurements at the given time.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time.
+    pub fn interval(&self, now: u64) -> u64 {
+        match self.policy {
+            SchedulePolicy::Fixed { interval } => interval,
+            SchedulePolicy::DayNight {
+                day_start,
+                day_interval,
+                night_start,
+                night_interval,
+            } => {
+                if SchedulePolicy::is_day(day_start, night_start, now) {
+                    day_interval
+                } else {
+                    night_interval
+                }
+            }
+            SchedulePolicy::ChangeDetection { .. } => self.interval,
+        }
+    }
+
+    /// Returns the time of the next measurement.
+    ///
+    /// The interval is evaluated at the given time, so that a shorter
+    /// interval takes effect as soon as the day starts.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time.
+    pub fn next(&self, now: u64) -> u64 {
+        match self.last {
+            Some(last) => last.saturating_add(self.interval(now)),
+            None => now,
+        }
+    }
+
+    /// Returns whether a measurement is due.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time.
+    #[inline]
+    pub fn is_due(&self, now: u64) -> bool {
+        now >= self.next(now)
+    }
+
+    /// Records a completed measurement.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The time of the measurement.
+    /// * `variables` - The solution of the measurement, used by the change
+    ///   detection policy.
+    pub fn record(&mut self, now: u64, variables: &Variables) {
+        self.last = Some(now);
+
+        if let SchedulePolicy::ChangeDetection {
+            max_interval,
+            min_interval,
+            threshold,
+        } = self.policy
+        {
+            let concentration = variables.concentration;
+            if !concentration.is_finite() {
+                // A failed solve says nothing about the dynamics.
+                return;
+            }
+            match self.reference {
+                Some(reference)
+                    if (concentration - reference).abs() <= threshold * reference.abs() =>
+                {
+                    self.interval = self
+                        .interval
+                        .saturating_mul(2)
+                        .clamp(min_interval, max_interval);
+                }
+                _ => {
+                    self.interval = min_interval;
+                    self.reference = Some(concentration);
+                }
+            }
+        }
+    }
+
+    /// Forgets the previous measurements, so that the next one is due
+    /// immediately.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.policy.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(concentration: f32) -> Variables {
+        Variables {
+            concentration,
+            resistance: 40.0,
+            saturation: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_fixed() {
+        let mut scheduler = Scheduler::new(SchedulePolicy::Fixed { interval: 60 });
+        assert!(scheduler.is_due(1000));
+        scheduler.record(1000, &vars(1e-2));
+        assert!(!scheduler.is_due(1059));
+        assert!(scheduler.is_due(1060));
+        assert_eq!(scheduler.next(1059), 1060);
+    }
+
+    #[test]
+    fn test_day_night() {
+        let hour = 3600;
+        let policy = SchedulePolicy::DayNight {
+            day_start: 6 * hour,
+            day_interval: 600,
+            night_start: 20 * hour,
+            night_interval: 3600,
+        };
+        let day = 10 * SECONDS_PER_DAY;
+        let mut scheduler = Scheduler::new(policy);
+        assert_eq!(scheduler.interval(day + 12 * hour as u64), 600);
+        assert_eq!(scheduler.interval(day + 22 * hour as u64), 3600);
+        assert_eq!(scheduler.interval(day + 2 * hour as u64), 3600);
+
+        // The day interval takes effect as soon as the day starts.
+        scheduler.record(day + 5 * hour as u64 + 1800, &vars(1e-2));
+        assert!(!scheduler.is_due(day + 6 * hour as u64 - 1));
+        assert!(scheduler.is_due(day + 6 * hour as u64));
+
+        // Day spanning midnight UTC.
+        let scheduler = Scheduler::new(SchedulePolicy::DayNight {
+            day_start: 22 * hour,
+            day_interval: 600,
+            night_start: 10 * hour,
+            night_interval: 3600,
+        });
+        assert_eq!(scheduler.interval(day + 2 * hour as u64), 600);
+        assert_eq!(scheduler.interval(day + 12 * hour as u64), 3600);
+    }
+
+    #[test]
+    fn test_change_detection() {
+        let mut scheduler = Scheduler::new(SchedulePolicy::ChangeDetection {
+            max_interval: 400,
+            min_interval: 100,
+            threshold: 0.05,
+        });
+        scheduler.record(0, &vars(1e-2));
+        assert_eq!(scheduler.interval(0), 100);
+        scheduler.record(100, &vars(1.01e-2));
+        assert_eq!(scheduler.interval(100), 200);
+        scheduler.record(300, &vars(1.02e-2));
+        assert_eq!(scheduler.interval(300), 400);
+        scheduler.record(700, &vars(0.99e-2));
+        assert_eq!(scheduler.interval(700), 400);
+        scheduler.record(1100, &vars(f32::NAN));
+        assert_eq!(scheduler.interval(1100), 400);
+        assert_eq!(scheduler.next(1100), 1500);
+
+        // Significant change.
+        scheduler.record(1500, &vars(2e-2));
+        assert_eq!(scheduler.interval(1500), 100);
+
+        scheduler.reset();
+        assert!(scheduler.is_due(1501));
+    }
+}
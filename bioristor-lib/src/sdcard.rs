@@ -0,0 +1,613 @@
+use core::fmt;
+
+use embedded_io::{ErrorKind, ErrorType, Write, WriteFmtError};
+use embedded_sdmmc::{BlockDevice, Mode, RawDirectory, RawFile, TimeSource, VolumeManager};
+
+use crate::params::{Currents, Variables};
+
+/// The header of the CSV files.
+pub const CSV_HEADER: &[u8] =
+    b"timestamp,i_ds_off,i_ds_on,i_gs_on,concentration,resistance,saturation,loss\r\n";
+
+/// A directory of a FAT filesystem where the log files are created.
+///
+/// At most one file is open at a time: the bytes written through the
+/// [`Write`] implementation are appended to the file opened by the last call
+/// to [`create`](LogStorage::create).
+///
+/// With `embedded-sdmmc`, this is implemented by [`SdCardStorage`].
+pub trait LogStorage: Write {
+    /// Creates a file, truncating it if it already exists, and opens it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the file, in 8.3 format.
+    fn create(&mut self, name: &str) -> Result<(), Self::Error>;
+
+    /// Closes the open file, updating its directory entry.
+    fn close(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Logger writing measurements and solutions as rows of CSV files.
+///
+/// The rows are written to the files `LOG00000.CSV`, `LOG00001.CSV`, ...
+/// and a new file is started every time the current one reaches the
+/// maximum number of rows, so that a long trial is split into files of
+/// manageable size.
+pub struct CsvLogger<S: LogStorage> {
+    /// The index of the current file.
+    index: u16,
+
+    /// The maximum number of rows of a file.
+    max_rows: u32,
+
+    /// Whether a file is open.
+    open: bool,
+
+    /// The number of rows written to the current file.
+    rows: u32,
+
+    /// The filesystem.
+    storage: S,
+}
+
+impl<S: LogStorage> CsvLogger<S> {
+    /// Creates a new logger. No file is created until the first row is logged.
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - The directory where the files are created.
+    /// * `max_rows` - The maximum number of rows of a file, at least one.
+    /// * `first_index` - The index of the first file, e.g. to avoid
+    ///   overwriting the files of a previous trial.
+    pub fn new(storage: S, max_rows: u32, first_index: u16) -> Self {
+        Self {
+            index: first_index,
+            max_rows: max_rows.max(1),
+            open: false,
+            rows: 0,
+            storage,
+        }
+    }
+
+    /// Returns the index of the file where the next row is written.
+    #[inline]
+    pub fn file_index(&self) -> u16 {
+        self.index
+    }
+
+    /// Returns the name of a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the file.
+    ///
+    /// # Returns
+    ///
+    /// The name of the file, in 8.3 format.
+    pub fn file_name(index: u16) -> [u8; 12] {
+        let mut name = *b"LOG00000.CSV";
+        let mut value = index;
+        for digit in name[3..8].iter_mut().rev() {
+            *digit = b'0' + (value % 10) as u8;
+            value /= 10;
+        }
+        name
+    }
+
+    /// Logs a measurement and the corresponding solution.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - The time of the measurement, in seconds since the Unix
+    ///   epoch, e.g. from a [`Timestamp`](crate::timestamp::Timestamp).
+    /// * `currents` - The measured currents.
+    /// * `result` - The result returned by the algorithm, whose fields are
+    ///   left empty if no solution has been found.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the row has been written.
+    /// * `Err(error)` - If the filesystem failed.
+    pub fn log(
+        &mut self,
+        timestamp: u64,
+        currents: &Currents,
+        result: Option<(Variables, f32)>,
+    ) -> Result<(), S::Error> {
+        if !self.open {
+            self.start()?;
+        }
+
+        self.print(format_args!(
+            "{},{},{},{}",
+            timestamp, currents.i_ds_off, currents.i_ds_on, currents.i_gs_on
+        ))?;
+        match result {
+            Some((vars, loss)) => self.print(format_args!(
+                ",{},{},{},{}\r\n",
+                vars.concentration, vars.resistance, vars.saturation, loss
+            ))?,
+            None => self.storage.write_all(b",,,,\r\n")?,
+        }
+        self.storage.flush()?;
+
+        self.rows += 1;
+        if self.rows == self.max_rows {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    /// Closes the current file, so that the next row starts a new one.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the file has been closed.
+    /// * `Err(error)` - If the filesystem failed.
+    pub fn rotate(&mut self) -> Result<(), S::Error> {
+        if self.open {
+            self.open = false;
+            self.rows = 0;
+            self.index = self.index.wrapping_add(1);
+            self.storage.close()?;
+        }
+        Ok(())
+    }
+
+    /// Closes the current file and releases the filesystem.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(storage)` - The filesystem.
+    /// * `Err(error)` - If the file cannot be closed.
+    pub fn release(mut self) -> Result<S, S::Error> {
+        self.rotate()?;
+        Ok(self.storage)
+    }
+
+    /// Creates the current file and writes the header.
+    fn start(&mut self) -> Result<(), S::Error> {
+        let name = Self::file_name(self.index);
+        // The name is always made of ASCII characters.
+        self.storage
+            .create(core::str::from_utf8(&name).unwrap_or_default())?;
+        self.open = true;
+        self.storage.write_all(CSV_HEADER)
+    }
+
+    /// Writes formatted text to the current file.
+    fn print(&mut self, args: fmt::Arguments) -> Result<(), S::Error> {
+        match self.storage.write_fmt(args) {
+            Ok(()) => Ok(()),
+            Err(WriteFmtError::Other(error)) => Err(error),
+            // Formatting numbers cannot fail.
+            Err(WriteFmtError::FmtError) => Ok(()),
+        }
+    }
+}
+
+/// The errors of the [`SdCardStorage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdCardStorageError<E: core::error::Error> {
+    /// The filesystem or the card failed.
+    Filesystem(embedded_sdmmc::Error<E>),
+
+    /// Bytes have been written while no file was open.
+    NotOpen,
+}
+
+impl<E: core::error::Error> From<embedded_sdmmc::Error<E>> for SdCardStorageError<E> {
+    fn from(error: embedded_sdmmc::Error<E>) -> Self {
+        Self::Filesystem(error)
+    }
+}
+
+impl<E: core::error::Error> embedded_io::Error for SdCardStorageError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Filesystem(embedded_sdmmc::Error::DiskFull) => ErrorKind::OutOfMemory,
+            Self::Filesystem(_) => ErrorKind::Other,
+            Self::NotOpen => ErrorKind::NotConnected,
+        }
+    }
+}
+
+/// [`LogStorage`] over a directory of a FAT filesystem on an SD card, opened
+/// with the `VolumeManager` of `embedded-sdmmc`.
+///
+/// The directory stays open, and is still owned by the caller, who closes it
+/// after releasing the storage. The files are created with
+/// `Mode::ReadWriteCreateOrTruncate`, so an existing file with the same name
+/// is overwritten.
+///
+/// # Type parameters
+///
+/// * `D` - The type of the block device, e.g. an `SdCard`.
+/// * `T` - The type of the source of the timestamps of the files.
+/// * `MAX_DIRS`, `MAX_FILES`, `MAX_VOLUMES` - The limits of the
+///   `VolumeManager`.
+pub struct SdCardStorage<
+    'a,
+    D: BlockDevice,
+    T: TimeSource,
+    const MAX_DIRS: usize,
+    const MAX_FILES: usize,
+    const MAX_VOLUMES: usize,
+> {
+    /// The directory where the files are created.
+    directory: RawDirectory,
+
+    /// The open file, if any.
+    file: Option<RawFile>,
+
+    /// The filesystem.
+    volume_mgr: &'a VolumeManager<D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>,
+}
+
+impl<'a, D, T, const MAX_DIRS: usize, const MAX_FILES: usize, const MAX_VOLUMES: usize>
+    SdCardStorage<'a, D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>
+where
+    D: BlockDevice,
+    T: TimeSource,
+{
+    /// Creates a new storage. No file is opened until the first call to
+    /// [`create`](LogStorage::create).
+    ///
+    /// # Arguments
+    ///
+    /// * `volume_mgr` - The filesystem.
+    /// * `directory` - The directory where the files are created, e.g. the
+    ///   root directory of the first volume.
+    pub fn new(
+        volume_mgr: &'a VolumeManager<D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>,
+        directory: RawDirectory,
+    ) -> Self {
+        Self {
+            directory,
+            file: None,
+            volume_mgr,
+        }
+    }
+
+    /// Returns the directory where the files are created.
+    #[inline]
+    pub fn directory(&self) -> RawDirectory {
+        self.directory
+    }
+}
+
+impl<D, T, const MAX_DIRS: usize, const MAX_FILES: usize, const MAX_VOLUMES: usize> ErrorType
+    for SdCardStorage<'_, D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>
+where
+    D: BlockDevice,
+    T: TimeSource,
+{
+    type Error = SdCardStorageError<D::Error>;
+}
+
+impl<D, T, const MAX_DIRS: usize, const MAX_FILES: usize, const MAX_VOLUMES: usize> Write
+    for SdCardStorage<'_, D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>
+where
+    D: BlockDevice,
+    T: TimeSource,
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let file = self.file.ok_or(SdCardStorageError::NotOpen)?;
+        self.volume_mgr.write(file, buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        if let Some(file) = self.file {
+            self.volume_mgr.flush_file(file)?;
+        }
+        Ok(())
+    }
+}
+
+impl<D, T, const MAX_DIRS: usize, const MAX_FILES: usize, const MAX_VOLUMES: usize> LogStorage
+    for SdCardStorage<'_, D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>
+where
+    D: BlockDevice,
+    T: TimeSource,
+{
+    fn create(&mut self, name: &str) -> Result<(), Self::Error> {
+        self.close()?;
+        let file = self.volume_mgr.open_file_in_dir(
+            self.directory,
+            name,
+            Mode::ReadWriteCreateOrTruncate,
+        )?;
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Self::Error> {
+        if let Some(file) = self.file.take() {
+            self.volume_mgr.close_file(file)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+    use core::convert::Infallible;
+
+    use embedded_sdmmc::{Block, BlockCount, BlockIdx, Timestamp, VolumeIdx};
+
+    use super::*;
+
+    /// Directory holding up to four files in memory.
+    struct StorageMock {
+        closed: usize,
+        current: Option<usize>,
+        files: [([u8; 12], [u8; 256], usize); 4],
+    }
+
+    impl StorageMock {
+        fn new() -> Self {
+            Self {
+                closed: 0,
+                current: None,
+                files: [([0; 12], [0; 256], 0); 4],
+            }
+        }
+
+        fn content(&self, name: &str) -> Option<&str> {
+            self.files
+                .iter()
+                .find(|(n, _, _)| n == name.as_bytes())
+                .map(|(_, data, len)| core::str::from_utf8(&data[..*len]).unwrap())
+        }
+    }
+
+    impl ErrorType for StorageMock {
+        type Error = Infallible;
+    }
+
+    impl Write for StorageMock {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            let (_, data, len) = &mut self.files[self.current.unwrap()];
+            data[*len..*len + buf.len()].copy_from_slice(buf);
+            *len += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    impl LogStorage for StorageMock {
+        fn create(&mut self, name: &str) -> Result<(), Self::Error> {
+            assert!(self.current.is_none());
+            let slot = self.files.iter().position(|(_, _, len)| *len == 0).unwrap();
+            self.files[slot].0.copy_from_slice(name.as_bytes());
+            self.current = Some(slot);
+            Ok(())
+        }
+
+        fn close(&mut self) -> Result<(), Self::Error> {
+            assert!(self.current.take().is_some());
+            self.closed += 1;
+            Ok(())
+        }
+    }
+
+    /// The number of blocks of the FAT16 volume, the smallest with a single
+    /// block per cluster.
+    const VOLUME_BLOCKS: u32 = 4250;
+
+    /// The number of blocks of the file allocation table.
+    const FAT_BLOCKS: u16 = 17;
+
+    /// SD card holding only the blocks that are not zero, up to 32.
+    struct CardMock {
+        blocks: RefCell<[(u32, [u8; Block::LEN]); 32]>,
+    }
+
+    impl CardMock {
+        /// Creates a card with a partition table and an empty FAT16 volume.
+        fn formatted() -> Self {
+            let card = Self {
+                blocks: RefCell::new([(0, [0; Block::LEN]); 32]),
+            };
+
+            // Master boot record, with the volume after it.
+            let mut mbr = Block::new();
+            mbr[446 + 4] = 0x06;
+            mbr[446 + 8..446 + 12].copy_from_slice(&1u32.to_le_bytes());
+            mbr[446 + 12..446 + 16].copy_from_slice(&VOLUME_BLOCKS.to_le_bytes());
+            mbr[510..].copy_from_slice(&[0x55, 0xAA]);
+
+            // Boot sector: 512 bytes per block, one block per cluster, one
+            // reserved block, one table and 512 entries in the root.
+            let mut bpb = Block::new();
+            bpb[11..13].copy_from_slice(&512u16.to_le_bytes());
+            bpb[13] = 1;
+            bpb[14..16].copy_from_slice(&1u16.to_le_bytes());
+            bpb[16] = 1;
+            bpb[17..19].copy_from_slice(&512u16.to_le_bytes());
+            bpb[19..21].copy_from_slice(&(VOLUME_BLOCKS as u16).to_le_bytes());
+            bpb[21] = 0xF8;
+            bpb[22..24].copy_from_slice(&FAT_BLOCKS.to_le_bytes());
+            bpb[510..].copy_from_slice(&[0x55, 0xAA]);
+
+            // The first two entries of the table are reserved.
+            let mut fat = Block::new();
+            fat[..4].copy_from_slice(&[0xF8, 0xFF, 0xFF, 0xFF]);
+
+            card.write(&[mbr, bpb, fat], BlockIdx(0)).unwrap();
+            card
+        }
+    }
+
+    impl BlockDevice for CardMock {
+        type Error = Infallible;
+
+        fn read(&self, blocks: &mut [Block], start_block_idx: BlockIdx) -> Result<(), Infallible> {
+            let stored = self.blocks.borrow();
+            for (block, idx) in blocks.iter_mut().zip(start_block_idx.0..) {
+                block.contents = stored
+                    .iter()
+                    .find(|(i, data)| *i == idx && data.iter().any(|&b| b != 0))
+                    .map_or([0; Block::LEN], |(_, data)| *data);
+            }
+            Ok(())
+        }
+
+        fn write(&self, blocks: &[Block], start_block_idx: BlockIdx) -> Result<(), Infallible> {
+            let mut stored = self.blocks.borrow_mut();
+            for (block, idx) in blocks.iter().zip(start_block_idx.0..) {
+                let slot = stored
+                    .iter()
+                    .position(|(i, _)| *i == idx)
+                    .or_else(|| {
+                        stored
+                            .iter()
+                            .position(|(_, data)| data.iter().all(|&b| b == 0))
+                    })
+                    .expect("too many blocks written");
+                stored[slot] = (idx, block.contents);
+            }
+            Ok(())
+        }
+
+        fn num_blocks(&self) -> Result<BlockCount, Infallible> {
+            Ok(BlockCount(VOLUME_BLOCKS + 1))
+        }
+    }
+
+    struct ClockMock;
+
+    impl TimeSource for ClockMock {
+        fn get_timestamp(&self) -> Timestamp {
+            Timestamp {
+                year_since_1970: 56,
+                zero_indexed_month: 9,
+                zero_indexed_day: 15,
+                hours: 12,
+                minutes: 0,
+                seconds: 0,
+            }
+        }
+    }
+
+    /// Reads a file of a directory into a buffer.
+    fn read_file<'a>(
+        volume_mgr: &VolumeManager<CardMock, ClockMock>,
+        directory: RawDirectory,
+        name: &str,
+        buf: &'a mut [u8],
+    ) -> &'a str {
+        let file = volume_mgr
+            .open_file_in_dir(directory, name, Mode::ReadOnly)
+            .unwrap();
+        let len = volume_mgr.read(file, buf).unwrap();
+        volume_mgr.close_file(file).unwrap();
+        core::str::from_utf8(&buf[..len]).unwrap()
+    }
+
+    const CURRENTS: Currents = Currents {
+        i_ds_off: 1.5,
+        i_ds_on: 2.0,
+        i_gs_on: 0.25,
+    };
+
+    const VARIABLES: Variables = Variables {
+        concentration: 10.0,
+        resistance: 2.5,
+        saturation: 0.5,
+    };
+
+    #[test]
+    fn test_file_name() {
+        type Logger = CsvLogger<StorageMock>;
+        assert_eq!(&Logger::file_name(0), b"LOG00000.CSV");
+        assert_eq!(&Logger::file_name(42), b"LOG00042.CSV");
+        assert_eq!(&Logger::file_name(u16::MAX), b"LOG65535.CSV");
+    }
+
+    #[test]
+    fn test_logger() {
+        let mut logger = CsvLogger::new(StorageMock::new(), 2, 7);
+        assert_eq!(logger.file_index(), 7);
+
+        logger
+            .log(100, &CURRENTS, Some((VARIABLES, 0.125)))
+            .unwrap();
+        logger.log(200, &CURRENTS, None).unwrap();
+        assert_eq!(logger.file_index(), 8);
+        logger.log(300, &CURRENTS, Some((VARIABLES, 1.0))).unwrap();
+
+        let storage = logger.release().unwrap();
+        assert_eq!(storage.closed, 2);
+        assert_eq!(storage.current, None);
+        assert_eq!(
+            storage.content("LOG00007.CSV"),
+            Some(
+                "timestamp,i_ds_off,i_ds_on,i_gs_on,concentration,resistance,saturation,loss\r\n\
+                 100,1.5,2,0.25,10,2.5,0.5,0.125\r\n\
+                 200,1.5,2,0.25,,,,\r\n"
+            )
+        );
+        assert_eq!(
+            storage.content("LOG00008.CSV"),
+            Some(
+                "timestamp,i_ds_off,i_ds_on,i_gs_on,concentration,resistance,saturation,loss\r\n\
+                 300,1.5,2,0.25,10,2.5,0.5,1\r\n"
+            )
+        );
+        assert_eq!(storage.content("LOG00009.CSV"), None);
+    }
+
+    #[test]
+    fn test_sdcard_storage() {
+        let volume_mgr = VolumeManager::new(CardMock::formatted(), ClockMock);
+        let volume = volume_mgr.open_raw_volume(VolumeIdx(0)).unwrap();
+        let root = volume_mgr.open_root_dir(volume).unwrap();
+
+        // A file of a previous trial, longer than the new one.
+        let file = volume_mgr
+            .open_file_in_dir(root, "LOG00008.CSV", Mode::ReadWriteCreate)
+            .unwrap();
+        volume_mgr.write(file, &[b'x'; 600]).unwrap();
+        volume_mgr.close_file(file).unwrap();
+
+        let mut storage = SdCardStorage::new(&volume_mgr, root);
+        assert_eq!(storage.write(b"row"), Err(SdCardStorageError::NotOpen));
+
+        let mut logger = CsvLogger::new(storage, 2, 7);
+        logger
+            .log(100, &CURRENTS, Some((VARIABLES, 0.125)))
+            .unwrap();
+        logger.log(200, &CURRENTS, None).unwrap();
+        logger.log(300, &CURRENTS, Some((VARIABLES, 1.0))).unwrap();
+        let storage = logger.release().unwrap();
+        assert_eq!(storage.directory(), root);
+        assert!(volume_mgr
+            .open_file_in_dir(root, "LOG00009.CSV", Mode::ReadOnly)
+            .is_err());
+
+        let mut buf = [0; 1024];
+        assert_eq!(
+            read_file(&volume_mgr, root, "LOG00007.CSV", &mut buf),
+            "timestamp,i_ds_off,i_ds_on,i_gs_on,concentration,resistance,saturation,loss\r\n\
+             100,1.5,2,0.25,10,2.5,0.5,0.125\r\n\
+             200,1.5,2,0.25,,,,\r\n"
+        );
+        // The file of the previous trial has been truncated.
+        assert_eq!(
+            read_file(&volume_mgr, root, "LOG00008.CSV", &mut buf),
+            "timestamp,i_ds_off,i_ds_on,i_gs_on,concentration,resistance,saturation,loss\r\n\
+             300,1.5,2,0.25,10,2.5,0.5,1\r\n"
+        );
+
+        volume_mgr.close_dir(root).unwrap();
+        volume_mgr.close_volume(volume).unwrap();
+        assert!(!volume_mgr.has_open_handles());
+    }
+}
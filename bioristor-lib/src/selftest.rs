@@ -0,0 +1,389 @@
+use embedded_hal::delay::DelayNs;
+
+use crate::acquisition::{AveragingSampler, Channel, CurrentSource, GateDriver};
+use crate::models::{Model, System, SystemModel};
+use crate::params::{Currents, ModelParams, Variables};
+
+/// The parameters of the self-test.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SelfTestParams {
+    /// The state of the device during the test, e.g. dry or dipped in a
+    /// reference solution, from which the expected currents are computed
+    /// with the model.
+    pub expected: Variables,
+
+    /// The absolute tolerance on every current, covering the noise and the
+    /// offset of the acquisition [Ampere].
+    pub noise_floor: f32,
+
+    /// The time to wait after switching the gate before sampling [us].
+    pub settle_time_us: u32,
+
+    /// The relative tolerance on the currents, covering the spread of the
+    /// parameters of the devices.
+    pub tolerance: f32,
+}
+
+/// The checks performed by the self-test, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Check {
+    /// The drain-source current with the gate off, which detects a
+    /// disconnected channel or a wrong drain-source voltage.
+    DrainSourceOff,
+
+    /// The gate-source current with the gate off, which detects leakage or
+    /// a gate stuck on.
+    GateSourceOff,
+
+    /// The drain-source current with the gate on, which detects a gate that
+    /// does not switch.
+    DrainSourceOn,
+
+    /// The gate-source current with the gate on, which detects a
+    /// disconnected gate or a wrong gate-source voltage.
+    GateSourceOn,
+
+    /// The drain-source current after switching the gate off again, which
+    /// detects a gate that does not release.
+    GateRelease,
+}
+
+impl Check {
+    /// All the checks, in the order they are performed.
+    pub const ALL: [Check; 5] = [
+        Check::DrainSourceOff,
+        Check::GateSourceOff,
+        Check::DrainSourceOn,
+        Check::GateSourceOn,
+        Check::GateRelease,
+    ];
+
+    /// Returns the name of the check.
+    pub fn name(self) -> &'static str {
+        match self {
+            Check::DrainSourceOff => "drain_source_off",
+            Check::GateSourceOff => "gate_source_off",
+            Check::DrainSourceOn => "drain_source_on",
+            Check::GateSourceOn => "gate_source_on",
+            Check::GateRelease => "gate_release",
+        }
+    }
+}
+
+/// The outcome of a check of the self-test.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CheckResult {
+    /// The check.
+    pub check: Check,
+
+    /// The expected current [Ampere].
+    pub expected: f32,
+
+    /// The measured current [Ampere].
+    pub measured: f32,
+
+    /// Whether the measured current is within the tolerance.
+    pub passed: bool,
+}
+
+/// The report of the self-test, with the outcome of every check.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SelfTestReport {
+    /// The outcome of the checks, in the order of [`Check::ALL`].
+    pub results: [CheckResult; 5],
+}
+
+impl SelfTestReport {
+    /// Returns whether all the checks passed.
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|result| result.passed)
+    }
+
+    /// Returns the checks that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.results.iter().filter(|result| !result.passed)
+    }
+}
+
+/// The errors of the self-test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SelfTestError<S, G> {
+    /// The current source failed.
+    Source(S),
+
+    /// The gate driver failed.
+    Gate(G),
+}
+
+/// Computes the currents expected from the device in the given state.
+///
+/// # Arguments
+///
+/// * `params` - The parameters of the model.
+/// * `variables` - The state of the device.
+///
+/// # Returns
+///
+/// The expected currents.
+pub fn expected_currents(params: &ModelParams, variables: Variables) -> Currents {
+    let zero = Currents {
+        i_ds_off: 0.0,
+        i_ds_on: 0.0,
+        i_gs_on: 0.0,
+    };
+    let [(_, i_ds_on), (_, i_ds_off), (_, i_gs_on)] =
+        System::new(params.clone(), zero).value(variables);
+    // The model adds the measured gate-source current to the drain-source
+    // one, zero here.
+    Currents {
+        i_ds_off,
+        i_ds_on: i_ds_on + i_gs_on,
+        i_gs_on,
+    }
+}
+
+/// Runs the self-test of the wiring of the device, so that installers can
+/// validate it before trusting the readings.
+///
+/// The gate is switched off, on and off again, and after every switch the
+/// currents are compared with the ones expected from the model, see
+/// [`Check`]. The gate is left off, also when the test fails.
+///
+/// # Arguments
+///
+/// * `params` - The parameters of the self-test.
+/// * `model_params` - The parameters of the model.
+/// * `sampler` - The sampler of the currents.
+/// * `gate` - The driver of the gate.
+/// * `delay` - The delay waiting for the currents to settle.
+///
+/// # Returns
+///
+/// * `Ok(report)` - The outcome of the checks.
+/// * `Err(error)` - If the hardware failed.
+pub fn run<S: CurrentSource, G: GateDriver, const N: usize>(
+    params: &SelfTestParams,
+    model_params: &ModelParams,
+    sampler: &mut AveragingSampler<S, N>,
+    gate: &mut G,
+    delay: &mut impl DelayNs,
+) -> Result<SelfTestReport, SelfTestError<S::Error, G::Error>> {
+    let result = measure(params, model_params, sampler, gate, delay);
+    if result.is_err() {
+        // The error of the test takes precedence.
+        let _ = gate.set_gate(false);
+    }
+    result
+}
+
+/// Performs the measurements of the self-test.
+fn measure<S: CurrentSource, G: GateDriver, const N: usize>(
+    params: &SelfTestParams,
+    model_params: &ModelParams,
+    sampler: &mut AveragingSampler<S, N>,
+    gate: &mut G,
+    delay: &mut impl DelayNs,
+) -> Result<SelfTestReport, SelfTestError<S::Error, G::Error>> {
+    let expected = expected_currents(model_params, params.expected);
+    let mut switch = |on: bool| {
+        gate.set_gate(on).map_err(SelfTestError::Gate)?;
+        delay.delay_us(params.settle_time_us);
+        Ok(())
+    };
+    let mut sample = |channel| sampler.sample(channel).map_err(SelfTestError::Source);
+
+    switch(false)?;
+    let i_ds_off = sample(Channel::DrainSource)?;
+    let i_gs_off = sample(Channel::GateSource)?;
+    switch(true)?;
+    let i_ds_on = sample(Channel::DrainSource)?;
+    let i_gs_on = sample(Channel::GateSource)?;
+    switch(false)?;
+    let i_ds_release = sample(Channel::DrainSource)?;
+
+    let check = |check, expected: f32, measured: f32| CheckResult {
+        check,
+        expected,
+        measured,
+        passed: (measured - expected).abs()
+            <= params.tolerance * expected.abs() + params.noise_floor,
+    };
+    Ok(SelfTestReport {
+        results: [
+            check(Check::DrainSourceOff, expected.i_ds_off, i_ds_off),
+            check(Check::GateSourceOff, 0.0, i_gs_off),
+            check(Check::DrainSourceOn, expected.i_ds_on, i_ds_on),
+            check(Check::GateSourceOn, expected.i_gs_on, i_gs_on),
+            check(Check::GateRelease, expected.i_ds_off, i_ds_release),
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use super::*;
+    use crate::acquisition::{ChannelCalibration, SamplerParams};
+    use crate::params::{ModulationParams, StemResistanceInvParams, Voltages};
+
+    /// Current source returning the currents of a device with the given
+    /// parameters, in uA, depending on the state of the gate.
+    struct CurrentSourceMock<'a> {
+        currents: Currents,
+        fail: bool,
+        gate: &'a Cell<bool>,
+    }
+
+    impl CurrentSource for CurrentSourceMock<'_> {
+        type Error = ();
+
+        fn read(&mut self, channel: Channel) -> Result<u16, Self::Error> {
+            if self.fail {
+                return Err(());
+            }
+            let current = match (channel, self.gate.get()) {
+                (Channel::DrainSource, false) => self.currents.i_ds_off,
+                (Channel::DrainSource, true) => self.currents.i_ds_on,
+                (Channel::GateSource, false) => 0.0,
+                (Channel::GateSource, true) => self.currents.i_gs_on,
+            };
+            Ok((current * 1e6 + 2048.0) as u16)
+        }
+    }
+
+    struct DelayMock;
+
+    impl DelayNs for DelayMock {
+        fn delay_ns(&mut self, _: u32) {}
+    }
+
+    const MODEL_PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    const PARAMS: SelfTestParams = SelfTestParams {
+        expected: Variables {
+            concentration: 1e-2,
+            resistance: 40.0,
+            saturation: 1.0,
+        },
+        noise_floor: 2e-6,
+        settle_time_us: 1000,
+        tolerance: 0.1,
+    };
+
+    const SAMPLER_PARAMS: SamplerParams = SamplerParams {
+        drain_source: ChannelCalibration {
+            gain: 1e-6,
+            offset: -2048e-6,
+        },
+        gate_source: ChannelCalibration {
+            gain: 1e-6,
+            offset: -2048e-6,
+        },
+    };
+
+    fn run_with(currents: Currents, stuck: bool) -> SelfTestReport {
+        let gate = Cell::new(false);
+        let mut sampler = AveragingSampler::<_, 4>::new(
+            SAMPLER_PARAMS,
+            CurrentSourceMock {
+                currents,
+                fail: false,
+                gate: &gate,
+            },
+        );
+        let mut driver = |on| gate.set(on || (stuck && gate.get()));
+        run(
+            &PARAMS,
+            &MODEL_PARAMS,
+            &mut sampler,
+            &mut driver,
+            &mut DelayMock,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "deterministic",
+        ignore = "expected values of the accurate mathematical functions"
+    )]
+    fn test_expected_currents() {
+        let currents = expected_currents(&MODEL_PARAMS, PARAMS.expected);
+        assert!((currents.i_ds_off - -0.05 / 40.0).abs() < 1e-9);
+        let modulation = -0.01463 * 1e-2f32.ln() - 0.32;
+        let i_ds_on = currents.i_gs_on - 0.05 * (modulation + 1.0) / 40.0;
+        assert!((currents.i_ds_on - i_ds_on).abs() < 1e-8);
+        assert!((currents.i_gs_on - 0.5 * (1.35e-6 + 2.73e-4 * 1e-2f32.powf(0.955))).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_selftest_passed() {
+        let report = run_with(expected_currents(&MODEL_PARAMS, PARAMS.expected), false);
+        assert!(report.passed(), "{report:?}");
+        assert_eq!(report.failures().count(), 0);
+        for (result, check) in report.results.iter().zip(Check::ALL) {
+            assert_eq!(result.check, check);
+        }
+    }
+
+    #[test]
+    fn test_selftest_failed() {
+        // Drain-source channel disconnected.
+        let currents = Currents {
+            i_ds_off: 0.0,
+            i_ds_on: 0.0,
+            ..expected_currents(&MODEL_PARAMS, PARAMS.expected)
+        };
+        let report = run_with(currents, false);
+        let failures = report.failures().map(|result| result.check);
+        assert!(failures.eq([
+            Check::DrainSourceOff,
+            Check::DrainSourceOn,
+            Check::GateRelease
+        ]));
+
+        // Gate stuck on after the first switch.
+        let report = run_with(expected_currents(&MODEL_PARAMS, PARAMS.expected), true);
+        let failures = report.failures().map(|result| result.check);
+        assert!(failures.eq([Check::GateRelease]));
+    }
+
+    #[test]
+    fn test_selftest_error() {
+        let gate = Cell::new(false);
+        let mut sampler = AveragingSampler::<_, 1>::new(
+            SAMPLER_PARAMS,
+            CurrentSourceMock {
+                currents: expected_currents(&MODEL_PARAMS, PARAMS.expected),
+                fail: true,
+                gate: &gate,
+            },
+        );
+        let mut driver = |on| gate.set(on);
+        assert_eq!(
+            run(
+                &PARAMS,
+                &MODEL_PARAMS,
+                &mut sampler,
+                &mut driver,
+                &mut DelayMock
+            ),
+            Err(SelfTestError::Source(()))
+        );
+        assert!(!gate.get());
+    }
+}
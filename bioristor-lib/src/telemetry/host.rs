@@ -0,0 +1,93 @@
+use std::io::{self, BufRead};
+
+use super::{decode, Message, TelemetryError};
+
+/// Host-side decoder reading frames from a byte stream, e.g. a serial port.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::io::BufReader;
+///
+/// use bioristor_lib::telemetry::HostDecoder;
+///
+/// let port = std::fs::File::open("/dev/ttyACM0").unwrap();
+/// for message in HostDecoder::new(BufReader::new(port)) {
+///     println!("{:?}", message);
+/// }
+/// ```
+pub struct HostDecoder<R: BufRead> {
+    /// The frame being received.
+    buf: Vec<u8>,
+
+    /// The byte stream.
+    reader: R,
+}
+
+impl<R: BufRead> HostDecoder<R> {
+    /// Creates a new decoder.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The byte stream.
+    pub fn new(reader: R) -> Self {
+        Self {
+            buf: Vec::new(),
+            reader,
+        }
+    }
+
+    /// Reads the next frame from the stream.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Ok(message)))` - If a frame has been received.
+    /// * `Ok(Some(Err(error)))` - If a corrupted frame has been received.
+    /// * `Ok(None)` - If the stream has ended.
+    /// * `Err(error)` - If the stream failed.
+    pub fn next_message(&mut self) -> io::Result<Option<Result<Message, TelemetryError>>> {
+        loop {
+            self.buf.clear();
+            if self.reader.read_until(0, &mut self.buf)? == 0 {
+                return Ok(None);
+            }
+            // Skip the empty frames used to resynchronize.
+            if self.buf != [0] {
+                return Ok(Some(decode(&mut self.buf)));
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for HostDecoder<R> {
+    type Item = Result<Message, TelemetryError>;
+
+    /// Returns the next message, stopping at the end of the stream or on the
+    /// first I/O error.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_message().ok().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::telemetry::{encode, MAX_FRAME_SIZE};
+
+    use super::*;
+
+    #[test]
+    fn test_host_decoder() {
+        let mut stream = Vec::new();
+        stream.push(0);
+        for sequence in 0..3 {
+            let mut buf = [0; MAX_FRAME_SIZE];
+            stream.extend_from_slice(encode(&Message::Heartbeat { sequence }, &mut buf).unwrap());
+        }
+
+        let messages: Vec<_> = HostDecoder::new(stream.as_slice()).collect();
+        assert_eq!(
+            messages,
+            [0, 1, 2].map(|sequence| Ok(Message::Heartbeat { sequence }))
+        );
+    }
+}
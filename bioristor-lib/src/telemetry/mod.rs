@@ -0,0 +1,293 @@
+#[cfg(feature = "std")]
+mod host;
+#[cfg(feature = "udp")]
+mod udp;
+#[cfg(feature = "usb")]
+mod usb;
+
+#[cfg(feature = "std")]
+pub use host::*;
+#[cfg(feature = "udp")]
+pub use udp::*;
+#[cfg(feature = "usb")]
+pub use usb::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::record::{MeasurementRecord, RECORD_SIZE};
+use crate::utils::CRC16;
+
+/// The maximum size of a serialized [`Message`], excluding the CRC.
+pub const MAX_MESSAGE_SIZE: usize = RECORD_SIZE + 8;
+
+/// The maximum size of an encoded frame, including the CRC, the COBS overhead
+/// and the frame delimiter.
+pub const MAX_FRAME_SIZE: usize = cobs::max_encoding_length(MAX_MESSAGE_SIZE + 2) + 1;
+
+/// The error codes reported by the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorCode {
+    /// The acquisition of the currents failed.
+    Acquisition,
+    /// The driver of the gate failed.
+    Gate,
+    /// The algorithm did not find a solution.
+    NoSolution,
+    /// The storage failed.
+    Storage,
+    /// An application-specific error.
+    Other(u16),
+}
+
+/// The status of the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status {
+    /// The number of errors since the device started.
+    pub errors: u32,
+
+    /// The number of measurements since the device started.
+    pub measurements: u32,
+
+    /// The time since the device started [second].
+    pub uptime: u32,
+}
+
+/// The messages sent by the device.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Message {
+    /// An error occurred.
+    Error(ErrorCode),
+
+    /// Periodic message notifying that the device is alive.
+    Heartbeat {
+        /// The sequence number of the heartbeat.
+        sequence: u32,
+    },
+
+    /// The result of the algorithm, serialized as in the flash log.
+    Result(MeasurementRecord),
+
+    /// The status of the device.
+    Status(Status),
+}
+
+/// The errors of the encoding and decoding of the telemetry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TelemetryError {
+    /// The buffer is too small for the frame.
+    BufferTooSmall,
+
+    /// The CRC of the frame does not match.
+    Crc,
+
+    /// The frame is not valid COBS.
+    Framing,
+
+    /// The message cannot be serialized or deserialized.
+    Serialization,
+}
+
+/// Encodes a message in a frame.
+///
+/// The message is serialized with `postcard`, followed by its CRC in little
+/// endian, and the whole is COBS encoded and terminated by a zero byte, so
+/// that the receiver can find the frame boundaries in a byte stream.
+///
+/// # Arguments
+///
+/// * `message` - The message to be encoded.
+/// * `buf` - The buffer where the frame is written, at least
+///   [`MAX_FRAME_SIZE`] bytes long to fit any message.
+///
+/// # Returns
+///
+/// * `Ok(frame)` - The encoded frame, a prefix of `buf`.
+/// * `Err(error)` - If the message cannot be encoded.
+pub fn encode<'a>(message: &Message, buf: &'a mut [u8]) -> Result<&'a mut [u8], TelemetryError> {
+    let mut raw = [0; MAX_MESSAGE_SIZE + 2];
+    let len = postcard::to_slice(message, &mut raw[..MAX_MESSAGE_SIZE])
+        .map_err(|_| TelemetryError::Serialization)?
+        .len();
+
+    let crc = CRC16.checksum(&raw[..len]);
+    raw[len..len + 2].copy_from_slice(&crc.to_le_bytes());
+
+    let n = cobs::try_encode(&raw[..len + 2], buf).map_err(|_| TelemetryError::BufferTooSmall)?;
+    *buf.get_mut(n).ok_or(TelemetryError::BufferTooSmall)? = 0;
+
+    Ok(&mut buf[..n + 1])
+}
+
+/// Decodes a frame in place.
+///
+/// # Arguments
+///
+/// * `frame` - The COBS encoded frame, with or without the zero delimiter.
+///
+/// # Returns
+///
+/// * `Ok(message)` - The decoded message.
+/// * `Err(error)` - If the frame is corrupted.
+pub fn decode(frame: &mut [u8]) -> Result<Message, TelemetryError> {
+    let frame = match frame.split_last_mut() {
+        Some((0, frame)) => frame,
+        _ => frame,
+    };
+
+    let len = cobs::decode_in_place(frame).map_err(|_| TelemetryError::Framing)?;
+    if len < 2 {
+        return Err(TelemetryError::Framing);
+    }
+
+    let (payload, crc) = frame[..len].split_at(len - 2);
+    if CRC16.checksum(payload).to_le_bytes() != crc {
+        return Err(TelemetryError::Crc);
+    }
+
+    postcard::from_bytes(payload).map_err(|_| TelemetryError::Serialization)
+}
+
+/// Decoder of a stream of frames, received one byte at a time.
+///
+/// # Type parameters
+///
+/// * `N` - The size of the receive buffer, frames longer than this are
+///   discarded. [`MAX_FRAME_SIZE`] fits any message.
+pub struct FrameDecoder<const N: usize> {
+    /// The frame being received.
+    buf: [u8; N],
+
+    /// The length of the frame being received.
+    len: usize,
+
+    /// Whether the frame being received is too long and must be discarded.
+    overflow: bool,
+}
+
+impl<const N: usize> FrameDecoder<N> {
+    /// Creates a new decoder.
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+            overflow: false,
+        }
+    }
+
+    /// Feeds a byte to the decoder.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte` - The received byte.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Ok(message))` - If a frame has been completed.
+    /// * `Some(Err(error))` - If a corrupted frame has been completed.
+    /// * `None` - If the frame is not completed yet.
+    pub fn push(&mut self, byte: u8) -> Option<Result<Message, TelemetryError>> {
+        if byte != 0 {
+            if self.len < N {
+                self.buf[self.len] = byte;
+                self.len += 1;
+            } else {
+                self.overflow = true;
+            }
+            return None;
+        }
+
+        let (len, overflow) = (self.len, self.overflow);
+        self.len = 0;
+        self.overflow = false;
+
+        match (len, overflow) {
+            // Consecutive delimiters are used to resynchronize.
+            (0, false) => None,
+            (_, true) => Some(Err(TelemetryError::BufferTooSmall)),
+            (len, false) => Some(decode(&mut self.buf[..len])),
+        }
+    }
+}
+
+impl<const N: usize> Default for FrameDecoder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::Variables;
+
+    const MESSAGES: [Message; 4] = [
+        Message::Error(ErrorCode::Other(1234)),
+        Message::Heartbeat { sequence: 42 },
+        Message::Result(MeasurementRecord {
+            loss: 1e-3,
+            sequence: u32::MAX,
+            timestamp: u64::MAX,
+            variables: Variables {
+                concentration: 0.1,
+                resistance: 1000.0,
+                saturation: 0.5,
+            },
+        }),
+        Message::Status(Status {
+            errors: u32::MAX,
+            measurements: u32::MAX,
+            uptime: u32::MAX,
+        }),
+    ];
+
+    #[test]
+    fn test_encode_decode() {
+        for message in MESSAGES {
+            let mut buf = [0; MAX_FRAME_SIZE];
+            let frame = encode(&message, &mut buf).unwrap();
+            assert_eq!(frame.last(), Some(&0));
+            assert!(!frame[..frame.len() - 1].contains(&0));
+            assert_eq!(decode(frame), Ok(message));
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let mut buf = [0; MAX_FRAME_SIZE];
+        let len = encode(&MESSAGES[1], &mut buf).unwrap().len();
+
+        let mut corrupted = buf;
+        corrupted[2] ^= 0x01;
+        assert_eq!(decode(&mut corrupted[..len]), Err(TelemetryError::Crc));
+
+        assert_eq!(
+            encode(&MESSAGES[2], &mut [0; 8]),
+            Err(TelemetryError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_frame_decoder() {
+        let mut decoder = FrameDecoder::<MAX_FRAME_SIZE>::new();
+        let mut received = 0;
+
+        // Garbage before the first delimiter is discarded as a bad frame.
+        assert!(decoder.push(0x55).is_none());
+        assert!(decoder.push(0).unwrap().is_err());
+
+        for message in MESSAGES {
+            let mut buf = [0; MAX_FRAME_SIZE];
+            for &byte in encode(&message, &mut buf).unwrap().iter() {
+                if let Some(res) = decoder.push(byte) {
+                    assert_eq!(res, Ok(message));
+                    received += 1;
+                }
+            }
+        }
+        assert_eq!(received, MESSAGES.len());
+    }
+}
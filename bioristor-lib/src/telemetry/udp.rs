@@ -0,0 +1,233 @@
+use smoltcp::{
+    socket::udp::{self, SendError},
+    time::{Duration, Instant},
+    wire::IpEndpoint,
+};
+
+use super::{encode, Message, TelemetryError, MAX_FRAME_SIZE};
+
+/// The errors of the UDP transport of the telemetry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UdpSinkError {
+    /// The message cannot be encoded.
+    Telemetry(TelemetryError),
+
+    /// The datagram cannot be queued, e.g. with [`SendError::BufferFull`] if
+    /// the interface has not been polled since the last datagrams.
+    Send(SendError),
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for UdpSinkError {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            UdpSinkError::Telemetry(error) => defmt::write!(fmt, "Telemetry({})", error),
+            UdpSinkError::Send(error) => {
+                defmt::write!(fmt, "Send({})", defmt::Debug2Format(error))
+            }
+        }
+    }
+}
+
+impl From<TelemetryError> for UdpSinkError {
+    fn from(error: TelemetryError) -> Self {
+        UdpSinkError::Telemetry(error)
+    }
+}
+
+impl From<SendError> for UdpSinkError {
+    fn from(error: SendError) -> Self {
+        UdpSinkError::Send(error)
+    }
+}
+
+/// Telemetry sink pushing the frames to a collector over UDP, one frame per
+/// datagram, and sending a periodic heartbeat.
+///
+/// The datagrams contain the same frames of the serial transports, zero
+/// delimiter included, so the collector can decode them with [`decode`]
+/// or feed them to the same stream decoder.
+///
+/// The sink does not own the socket: it must be bound by the application and
+/// the interface must be polled for the datagrams to be sent.
+///
+/// # Example
+///
+/// ```ignore
+/// let handle = sockets.add(socket);
+/// sockets.get_mut::<udp::Socket>(handle).bind(LOCAL_PORT).unwrap();
+/// let mut sink = UdpSink::new(COLLECTOR, Duration::from_secs(10));
+///
+/// loop {
+///     let now = Instant::from_millis(millis());
+///     iface.poll(now, &mut device, &mut sockets);
+///
+///     let socket = sockets.get_mut::<udp::Socket>(handle);
+///     sink.poll(socket, now).ok();
+///     if let Some(message) = next_measurement() {
+///         sink.send(socket, &message).ok();
+///     }
+/// }
+/// ```
+///
+/// [`decode`]: super::decode
+#[derive(Debug, Clone)]
+pub struct UdpSink {
+    /// The time between two heartbeats.
+    heartbeat_period: Duration,
+
+    /// The time of the next heartbeat.
+    next_heartbeat: Instant,
+
+    /// The endpoint of the collector.
+    remote: IpEndpoint,
+
+    /// The sequence number of the next heartbeat.
+    sequence: u32,
+}
+
+impl UdpSink {
+    /// Creates a new sink. The first heartbeat is sent at the first poll.
+    ///
+    /// # Arguments
+    ///
+    /// * `remote` - The endpoint of the collector.
+    /// * `heartbeat_period` - The time between two heartbeats.
+    pub fn new(remote: IpEndpoint, heartbeat_period: Duration) -> Self {
+        Self {
+            heartbeat_period,
+            next_heartbeat: Instant::ZERO,
+            remote,
+            sequence: 0,
+        }
+    }
+
+    /// Returns the endpoint of the collector.
+    #[inline]
+    pub fn remote(&self) -> IpEndpoint {
+        self.remote
+    }
+
+    /// Encodes a message in a frame and queues it as a datagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - The bound UDP socket.
+    /// * `message` - The message to be sent.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the datagram has been queued.
+    /// * `Err(error)` - If the message cannot be encoded or queued.
+    pub fn send(&self, socket: &mut udp::Socket, message: &Message) -> Result<(), UdpSinkError> {
+        let mut buf = [0; MAX_FRAME_SIZE];
+        let frame = encode(message, &mut buf)?;
+        socket.send_slice(frame, self.remote)?;
+        Ok(())
+    }
+
+    /// Sends the heartbeat if it is due.
+    ///
+    /// A heartbeat that cannot be queued is retried at the next poll, and
+    /// the heartbeats missed while the sink was not polled are not recovered.
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - The bound UDP socket.
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - If a heartbeat has been queued.
+    /// * `Ok(false)` - If the heartbeat is not due yet.
+    /// * `Err(error)` - If the heartbeat cannot be queued.
+    pub fn poll(&mut self, socket: &mut udp::Socket, now: Instant) -> Result<bool, UdpSinkError> {
+        if now < self.next_heartbeat {
+            return Ok(false);
+        }
+
+        self.send(
+            socket,
+            &Message::Heartbeat {
+                sequence: self.sequence,
+            },
+        )?;
+        self.sequence = self.sequence.wrapping_add(1);
+        self.next_heartbeat = now + self.heartbeat_period;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smoltcp::wire::{IpAddress, Ipv4Address};
+
+    use super::*;
+    use crate::telemetry::Status;
+
+    const COLLECTOR: IpEndpoint = IpEndpoint {
+        addr: IpAddress::Ipv4(Ipv4Address([192, 168, 1, 10])),
+        port: 4242,
+    };
+
+    const MESSAGE: Message = Message::Status(Status {
+        errors: 1,
+        measurements: 2,
+        uptime: 3,
+    });
+
+    /// Runs the test with a bound socket with room for two datagrams.
+    fn with_socket(f: impl FnOnce(&mut udp::Socket)) {
+        let mut rx_meta = [udp::PacketMetadata::EMPTY; 1];
+        let mut rx_payload = [0; MAX_FRAME_SIZE];
+        let mut tx_meta = [udp::PacketMetadata::EMPTY; 2];
+        let mut tx_payload = [0; 2 * MAX_FRAME_SIZE];
+        let mut socket = udp::Socket::new(
+            udp::PacketBuffer::new(&mut rx_meta[..], &mut rx_payload[..]),
+            udp::PacketBuffer::new(&mut tx_meta[..], &mut tx_payload[..]),
+        );
+        socket.bind(4242).unwrap();
+        f(&mut socket);
+    }
+
+    #[test]
+    fn test_udp_sink_send() {
+        with_socket(|socket| {
+            let sink = UdpSink::new(COLLECTOR, Duration::from_secs(10));
+            assert_eq!(sink.send(socket, &MESSAGE), Ok(()));
+            assert_eq!(sink.send(socket, &MESSAGE), Ok(()));
+            assert_eq!(
+                sink.send(socket, &MESSAGE),
+                Err(UdpSinkError::Send(SendError::BufferFull))
+            );
+        });
+
+        let mut rx_meta = [udp::PacketMetadata::EMPTY; 1];
+        let mut tx_meta = [udp::PacketMetadata::EMPTY; 1];
+        let mut unbound = udp::Socket::new(
+            udp::PacketBuffer::new(&mut rx_meta[..], &mut [][..]),
+            udp::PacketBuffer::new(&mut tx_meta[..], &mut [][..]),
+        );
+        let sink = UdpSink::new(COLLECTOR, Duration::from_secs(10));
+        assert_eq!(
+            sink.send(&mut unbound, &MESSAGE),
+            Err(UdpSinkError::Send(SendError::Unaddressable))
+        );
+    }
+
+    #[test]
+    fn test_udp_sink_heartbeat() {
+        with_socket(|socket| {
+            let mut sink = UdpSink::new(COLLECTOR, Duration::from_secs(10));
+            assert_eq!(sink.poll(socket, Instant::from_secs(1)), Ok(true));
+            assert_eq!(sink.poll(socket, Instant::from_secs(5)), Ok(false));
+            assert_eq!(sink.poll(socket, Instant::from_secs(11)), Ok(true));
+            assert_eq!(sink.sequence, 2);
+
+            // The socket is full, so the heartbeat is retried.
+            assert!(sink.poll(socket, Instant::from_secs(21)).is_err());
+            assert_eq!(sink.sequence, 2);
+            assert_eq!(sink.next_heartbeat, Instant::from_secs(21));
+        });
+    }
+}
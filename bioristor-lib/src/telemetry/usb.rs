@@ -0,0 +1,208 @@
+use usb_device::UsbError;
+
+use super::{encode, Message, TelemetryError, MAX_FRAME_SIZE};
+
+/// Common interface for the USB classes sending data over a bulk IN
+/// endpoint, such as the `CdcAcmClass` of `usbd-serial`.
+///
+/// The trait only wraps the two methods needed to stream the frames, so that
+/// it can be implemented for any class without depending on its crate.
+pub trait UsbPacketWriter {
+    /// Returns the maximum packet size of the IN endpoint.
+    fn max_packet_size(&self) -> usize;
+
+    /// Writes a single packet to the IN endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet` - The packet, at most [`max_packet_size`] bytes long.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(len)` - The number of bytes written.
+    /// * `Err(UsbError::WouldBlock)` - If the previous packet has not been
+    ///   read by the host yet.
+    /// * `Err(error)` - If the endpoint failed.
+    ///
+    /// [`max_packet_size`]: UsbPacketWriter::max_packet_size
+    fn write_packet(&mut self, packet: &[u8]) -> usb_device::Result<usize>;
+}
+
+/// The errors of the USB transport of the telemetry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsbTransportError {
+    /// The message cannot be encoded.
+    Telemetry(TelemetryError),
+
+    /// The USB class failed, e.g. with [`UsbError::WouldBlock`] if the host
+    /// is not reading the port.
+    Usb(UsbError),
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for UsbTransportError {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            UsbTransportError::Telemetry(error) => defmt::write!(fmt, "Telemetry({})", error),
+            UsbTransportError::Usb(error) => {
+                defmt::write!(fmt, "Usb({})", defmt::Debug2Format(error))
+            }
+        }
+    }
+}
+
+impl From<TelemetryError> for UsbTransportError {
+    fn from(error: TelemetryError) -> Self {
+        UsbTransportError::Telemetry(error)
+    }
+}
+
+impl From<UsbError> for UsbTransportError {
+    fn from(error: UsbError) -> Self {
+        UsbTransportError::Usb(error)
+    }
+}
+
+/// Encodes a message in a frame and sends it over a USB class.
+///
+/// The frame is split in packets of the maximum packet size of the endpoint,
+/// followed by a zero-length packet if the last one is full, so that the host
+/// receives the frame without waiting for more data.
+///
+/// The function does not block: if the host does not read the packets fast
+/// enough, it fails with [`UsbError::WouldBlock`] and the rest of the frame
+/// is dropped. The receiver discards the truncated frame at the next
+/// delimiter, so the following frames are not affected.
+///
+/// # Arguments
+///
+/// * `class` - The USB class, e.g. a CDC-ACM serial port.
+/// * `message` - The message to be sent.
+///
+/// # Returns
+///
+/// * `Ok(())` - If the whole frame has been sent.
+/// * `Err(error)` - If the message cannot be encoded or sent.
+pub fn send_usb<C: UsbPacketWriter>(
+    class: &mut C,
+    message: &Message,
+) -> Result<(), UsbTransportError> {
+    let mut buf = [0; MAX_FRAME_SIZE];
+    let frame = encode(message, &mut buf)?;
+
+    let max_packet_size = class.max_packet_size().max(1);
+    let mut sent = 0;
+    while sent < frame.len() {
+        let end = frame.len().min(sent + max_packet_size);
+        sent += class.write_packet(&frame[sent..end])?;
+    }
+    if frame.len().is_multiple_of(max_packet_size) {
+        class.write_packet(&[])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::{FrameDecoder, Status};
+
+    /// USB class recording the packets in a buffer.
+    struct UsbClassMock {
+        buf: [u8; 4 * MAX_FRAME_SIZE],
+        len: usize,
+        max_packet_size: usize,
+        packets: usize,
+        zero_length_packets: usize,
+    }
+
+    impl UsbClassMock {
+        fn new(max_packet_size: usize) -> Self {
+            Self {
+                buf: [0; 4 * MAX_FRAME_SIZE],
+                len: 0,
+                max_packet_size,
+                packets: 0,
+                zero_length_packets: 0,
+            }
+        }
+    }
+
+    impl UsbPacketWriter for UsbClassMock {
+        fn max_packet_size(&self) -> usize {
+            self.max_packet_size
+        }
+
+        fn write_packet(&mut self, packet: &[u8]) -> usb_device::Result<usize> {
+            assert!(packet.len() <= self.max_packet_size);
+            if self.len + packet.len() > self.buf.len() {
+                return Err(UsbError::WouldBlock);
+            }
+            self.buf[self.len..self.len + packet.len()].copy_from_slice(packet);
+            self.len += packet.len();
+            self.packets += 1;
+            if packet.is_empty() {
+                self.zero_length_packets += 1;
+            }
+            Ok(packet.len())
+        }
+    }
+
+    const MESSAGE: Message = Message::Status(Status {
+        errors: 1,
+        measurements: 2,
+        uptime: 3,
+    });
+
+    #[test]
+    fn test_send_usb() {
+        let mut class = UsbClassMock::new(64);
+        send_usb(&mut class, &MESSAGE).unwrap();
+        send_usb(&mut class, &Message::Heartbeat { sequence: 7 }).unwrap();
+        assert_eq!(class.packets, 2);
+        assert_eq!(class.zero_length_packets, 0);
+
+        let mut decoder = FrameDecoder::<MAX_FRAME_SIZE>::new();
+        let mut received = class.buf[..class.len]
+            .iter()
+            .filter_map(|&byte| decoder.push(byte));
+        assert_eq!(received.next(), Some(Ok(MESSAGE)));
+        assert_eq!(
+            received.next(),
+            Some(Ok(Message::Heartbeat { sequence: 7 }))
+        );
+        assert_eq!(received.next(), None);
+    }
+
+    #[test]
+    fn test_send_usb_split() {
+        let mut buf = [0; MAX_FRAME_SIZE];
+        let len = encode(&MESSAGE, &mut buf).unwrap().len();
+
+        // A frame filling the last packet is terminated by an empty packet.
+        let mut class = UsbClassMock::new(len / 2);
+        send_usb(&mut class, &MESSAGE).unwrap();
+        assert_eq!(class.len, len);
+        assert_eq!(&class.buf[..len], &buf[..len]);
+        assert_eq!(class.zero_length_packets, len.is_multiple_of(2) as usize);
+
+        let mut class = UsbClassMock::new(len);
+        send_usb(&mut class, &MESSAGE).unwrap();
+        assert_eq!(class.packets, 2);
+        assert_eq!(class.zero_length_packets, 1);
+    }
+
+    #[test]
+    fn test_send_usb_would_block() {
+        let mut class = UsbClassMock::new(8);
+        while class.len < class.buf.len() - MAX_FRAME_SIZE {
+            send_usb(&mut class, &MESSAGE).unwrap();
+        }
+        let mut result = Ok(());
+        while result.is_ok() {
+            result = send_usb(&mut class, &MESSAGE);
+        }
+        assert_eq!(result, Err(UsbTransportError::Usb(UsbError::WouldBlock)));
+    }
+}
@@ -0,0 +1,268 @@
+#[cfg(feature = "profiler")]
+use profiler::Clock;
+
+/// The number of seconds in a day.
+pub(crate) const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Source of the time at which a measurement is taken.
+///
+/// The time is attached to every logged and transmitted solution, so that
+/// the analysis does not depend on when the data is received by the host.
+///
+/// The trait is implemented for every closure returning a [`DateTime`], e.g.
+/// reading the calendar of a real-time clock, and by [`MonotonicTimestamp`]
+/// for devices without a real-time clock.
+pub trait Timestamp {
+    /// Returns the current time.
+    ///
+    /// # Returns
+    ///
+    /// The number of seconds since the Unix epoch.
+    fn unix_seconds(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> DateTime> Timestamp for F {
+    fn unix_seconds(&mut self) -> u64 {
+        self().to_unix_seconds()
+    }
+}
+
+/// A date and a time of the day in UTC, as read from a real-time clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DateTime {
+    /// The day of the month, starting from 1.
+    pub day: u8,
+
+    /// The hour, from 0 to 23.
+    pub hour: u8,
+
+    /// The minute, from 0 to 59.
+    pub minute: u8,
+
+    /// The month, from 1 to 12.
+    pub month: u8,
+
+    /// The second, from 0 to 59.
+    pub second: u8,
+
+    /// The year, starting from 1970.
+    pub year: u16,
+}
+
+impl DateTime {
+    /// Converts a number of seconds since the Unix epoch to a date and time.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds` - The number of seconds since the Unix epoch.
+    ///
+    /// # Returns
+    ///
+    /// The date and time.
+    pub fn from_unix_seconds(seconds: u64) -> Self {
+        // Days since 0000-03-01, so that the leap day is the last of the year.
+        let days = seconds / SECONDS_PER_DAY + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+        let time = seconds % SECONDS_PER_DAY;
+        Self {
+            day: day as u8,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            month: month as u8,
+            second: (time % 60) as u8,
+            year: year as u16,
+        }
+    }
+
+    /// Converts the date and time to a number of seconds since the Unix epoch.
+    ///
+    /// # Returns
+    ///
+    /// The number of seconds since the Unix epoch, or zero if the date is
+    /// before the epoch.
+    pub fn to_unix_seconds(&self) -> u64 {
+        if self.year < 1970 {
+            return 0;
+        }
+
+        let month = u64::from(self.month.clamp(1, 12));
+        let year = u64::from(self.year) - u64::from(month <= 2);
+
+        // Days since 0000-03-01, so that the leap day is the last of the year.
+        let era = year / 400;
+        let year_of_era = year % 400;
+        let day_of_year =
+            (153 * ((month + 9) % 12) + 2) / 5 + u64::from(self.day).saturating_sub(1);
+        let day_of_era = 365 * year_of_era + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era - 719_468;
+
+        days * SECONDS_PER_DAY
+            + u64::from(self.hour) * 3600
+            + u64::from(self.minute) * 60
+            + u64::from(self.second)
+    }
+}
+
+/// Timestamp derived from the monotonic clock of the profiler.
+///
+/// The clock is synchronized with the Unix time once, e.g. by a command from
+/// the host, and then the time is derived by counting the CPU cycles.
+///
+/// # Type parameters
+///
+/// * `FREQ` - The frequency of the CPU in Hz.
+#[cfg(feature = "profiler")]
+pub struct MonotonicTimestamp<C: Clock<FREQ>, const FREQ: u32> {
+    /// The monotonic clock.
+    clock: C,
+
+    /// The Unix time when the clock started counting [second].
+    epoch: u64,
+}
+
+#[cfg(feature = "profiler")]
+impl<C: Clock<FREQ>, const FREQ: u32> MonotonicTimestamp<C, FREQ> {
+    /// Creates a new timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - The monotonic clock.
+    /// * `unix_seconds` - The current number of seconds since the Unix epoch.
+    pub fn new(clock: C, unix_seconds: u64) -> Self {
+        let mut timestamp = Self { clock, epoch: 0 };
+        timestamp.synchronize(unix_seconds);
+        timestamp
+    }
+
+    /// Synchronizes the timestamp with the Unix time.
+    ///
+    /// # Arguments
+    ///
+    /// * `unix_seconds` - The current number of seconds since the Unix epoch.
+    pub fn synchronize(&mut self, unix_seconds: u64) {
+        self.epoch = unix_seconds.saturating_sub(self.uptime());
+    }
+
+    /// Returns the monotonic clock.
+    #[inline]
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Releases the clock.
+    pub fn release(self) -> C {
+        self.clock
+    }
+
+    /// Returns the number of seconds counted by the clock.
+    #[inline]
+    fn uptime(&self) -> u64 {
+        self.clock.now().ticks() / u64::from(FREQ)
+    }
+}
+
+#[cfg(feature = "profiler")]
+impl<C: Clock<FREQ>, const FREQ: u32> Timestamp for MonotonicTimestamp<C, FREQ> {
+    fn unix_seconds(&mut self) -> u64 {
+        self.epoch + self.uptime()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATES: [(u64, DateTime); 4] = [
+        (
+            0,
+            DateTime {
+                day: 1,
+                hour: 0,
+                minute: 0,
+                month: 1,
+                second: 0,
+                year: 1970,
+            },
+        ),
+        (
+            951_868_800,
+            DateTime {
+                day: 1,
+                hour: 0,
+                minute: 0,
+                month: 3,
+                second: 0,
+                year: 2000,
+            },
+        ),
+        (
+            1_709_210_096,
+            DateTime {
+                day: 29,
+                hour: 12,
+                minute: 34,
+                month: 2,
+                second: 56,
+                year: 2024,
+            },
+        ),
+        (
+            4_133_980_799,
+            DateTime {
+                day: 31,
+                hour: 23,
+                minute: 59,
+                month: 12,
+                second: 59,
+                year: 2100,
+            },
+        ),
+    ];
+
+    #[test]
+    fn test_date_time() {
+        for (seconds, date) in DATES {
+            assert_eq!(date.to_unix_seconds(), seconds);
+            assert_eq!(DateTime::from_unix_seconds(seconds), date);
+        }
+
+        let mut before_epoch = DATES[0].1;
+        before_epoch.year = 1969;
+        assert_eq!(before_epoch.to_unix_seconds(), 0);
+    }
+
+    #[test]
+    fn test_rtc_timestamp() {
+        let mut rtc = || DATES[2].1;
+        assert_eq!(rtc.unix_seconds(), DATES[2].0);
+    }
+
+    #[cfg(feature = "profiler")]
+    #[test]
+    fn test_monotonic_timestamp() {
+        use profiler::MockCounter;
+
+        let clock = MockCounter::new(0);
+        clock.advance(5_000_000);
+        let mut timestamp = MonotonicTimestamp::<_, 1_000_000>::new(clock, 1_700_000_000);
+        assert_eq!(timestamp.unix_seconds(), 1_700_000_000);
+
+        timestamp.clock().advance(2_500_000);
+        assert_eq!(timestamp.unix_seconds(), 1_700_000_002);
+
+        timestamp.synchronize(1_800_000_000);
+        assert_eq!(timestamp.unix_seconds(), 1_800_000_000);
+        timestamp.clock().advance(1_000_000);
+        assert_eq!(timestamp.unix_seconds(), 1_800_000_001);
+    }
+}
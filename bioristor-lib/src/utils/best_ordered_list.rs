@@ -5,11 +5,61 @@ use crate::params::Variables;
 /// # Type parameters
 ///
 /// * `S` - The type of a solution.
-/// * `N` - The number of solutions to keep.
+/// * `N` - The number of solutions to keep, which must be greater than zero:
+///   an empty list is rejected at compile time.
+///
+/// ```compile_fail
+/// use bioristor_lib::utils::BestOrderedList;
+///
+/// let list = BestOrderedList::<f32, 0>::new();
+/// ```
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct BestOrderedList<S: Sized, const N: usize> {
+    /// The number of solutions in the list with a finite error.
+    count: usize,
+
     data: [(S, f32); N],
+
+    /// The sum of the concentrations of the solutions with a finite error.
+    sum: f32,
+}
+
+impl<S: Sized, const N: usize> BestOrderedList<S, N> {
+    /// Fails the build when the list is instantiated with no room for
+    /// solutions, which would otherwise panic at the first insertion.
+    const NOT_EMPTY: () = assert!(N > 0, "the list must keep at least one solution");
+
+    /// Inserts the solution in its position if it is better than the worst
+    /// solution in the list, which is dropped.
+    ///
+    /// Since the list is always sorted, the position is found with a binary
+    /// search and only the worse solutions are shifted by one. The sum of the
+    /// concentrations is then recomputed from the list, at the same cost as
+    /// the shift, so that their mean is available in constant time without
+    /// the rounding errors of a running sum.
+    ///
+    /// # Arguments
+    ///
+    /// * `solution` - The solution to insert in the form `(solution, error)`.
+    /// * `concentration` - The function returning the concentration of a
+    ///   solution.
+    #[inline]
+    fn insert(&mut self, solution: (S, f32), concentration: fn(&S) -> f32) {
+        if solution.1 < self.data[N - 1].1 {
+            let index = self.data.partition_point(|(_, error)| *error <= solution.1);
+            self.data[index..].rotate_right(1);
+            self.data[index] = solution;
+
+            (self.count, self.sum) = self
+                .data
+                .iter()
+                .filter(|(_, error)| error.is_finite())
+                .fold((0, 0.0), |(count, sum), (solution, _)| {
+                    (count + 1, sum + concentration(solution))
+                });
+        }
+    }
 }
 
 impl<const N: usize> Default for BestOrderedList<f32, N> {
@@ -22,15 +72,20 @@ impl<const N: usize> BestOrderedList<f32, N> {
     /// Create a new instance of the list.
     #[inline]
     pub fn new() -> Self {
+        let () = Self::NOT_EMPTY;
         BestOrderedList::<f32, N> {
+            count: 0,
             data: [(0.0, f32::INFINITY); N],
+            sum: 0.0,
         }
     }
 
     /// Clear the list.
     #[inline]
     pub fn clear(&mut self) {
+        self.count = 0;
         self.data = [(0.0, f32::INFINITY); N];
+        self.sum = 0.0;
     }
 
     /// Add a new solution to the list if it is better than the worst solution
@@ -41,47 +96,29 @@ impl<const N: usize> BestOrderedList<f32, N> {
     /// * `solution` - The solution to add in the form `(variable, error)`.
     #[inline]
     pub fn add_solution(&mut self, solution: (f32, f32)) {
-        if solution.1 < self.data[N - 1].1 {
-            self.data[N - 1] = solution;
-            self.data
-                .sort_unstable_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-        }
+        self.insert(solution, |concentration| *concentration);
     }
 
     /// Get the mean concentration of the solutions in the list.
     ///
     /// # Returns
     ///
-    /// The mean concentration.
+    /// * `Some(concentration)` - The mean concentration.
+    /// * `None` - If no solution has been added to the list.
     #[inline]
-    pub fn mean_concentration(&self) -> f32 {
-        let n = self.data.iter().filter(|(_, e)| e.is_finite()).count() as f32;
-        return self
-            .data
-            .iter()
-            .filter(|(_, e)| e.is_finite())
-            .map(|(var, _)| var)
-            .sum::<f32>()
-            / n;
+    pub fn mean_concentration(&self) -> Option<f32> {
+        (self.count > 0).then(|| self.sum / self.count as f32)
     }
 
     /// Get the best solution calculated as the mean of the solutions in the list.
     ///
     /// # Returns
     ///
-    /// The best solution.
+    /// * `Some(concentration)` - The best solution.
+    /// * `None` - If no solution has been added to the list.
     #[inline]
-    pub fn best(&self) -> f32 {
-        let mut concentration = 0.0;
-
-        let mut n = 0;
-        for (var, _) in self.data.iter().filter(|(_, e)| e.is_finite()) {
-            concentration += var;
-            n += 1;
-        }
-
-        let n_inv = 1.0 / n as f32;
-        concentration * n_inv
+    pub fn best(&self) -> Option<f32> {
+        self.mean_concentration()
     }
 }
 
@@ -104,15 +141,20 @@ impl<const N: usize> BestOrderedList<Variables, N> {
     /// Create a new instance of the list.
     #[inline]
     pub fn new() -> Self {
+        let () = Self::NOT_EMPTY;
         BestOrderedList::<Variables, N> {
+            count: 0,
             data: [Self::DEFAULT; N],
+            sum: 0.0,
         }
     }
 
     /// Clear the list.
     #[inline]
     pub fn clear(&mut self) {
+        self.count = 0;
         self.data = [Self::DEFAULT; N];
+        self.sum = 0.0;
     }
 
     /// Add a new solution to the list if it is better than the worst solution
@@ -123,58 +165,49 @@ impl<const N: usize> BestOrderedList<Variables, N> {
     /// * `solution` - The solution to add.
     #[inline]
     pub fn add_solution(&mut self, solution: (Variables, f32)) {
-        if solution.1 < self.data[N - 1].1 {
-            self.data[N - 1] = solution;
-            self.data
-                .sort_unstable_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-        }
+        self.insert(solution, |vars| vars.concentration);
     }
 
     /// Get the mean concentration of the solutions in the list.
     ///
     /// # Returns
     ///
-    /// The mean concentration.
+    /// * `Some(concentration)` - The mean concentration.
+    /// * `None` - If no solution has been added to the list.
     #[inline]
-    pub fn mean_concentration(&self) -> f32 {
-        let n = self.data.iter().filter(|(_, e)| e.is_finite()).count() as f32;
-        return self
-            .data
-            .iter()
-            .filter(|(_, e)| e.is_finite())
-            .map(|(v, _)| v.concentration)
-            .sum::<f32>()
-            / n;
+    pub fn mean_concentration(&self) -> Option<f32> {
+        (self.count > 0).then(|| self.sum / self.count as f32)
     }
 
     /// Get the best solution calculated as the mean of the solutions in the list.
     ///
     /// # Returns
     ///
-    /// The best solution.
+    /// * `Some((vars, error))` - The best solution with its error.
+    /// * `None` - If no solution has been added to the list.
     #[inline]
-    pub fn best(&self) -> (Variables, f32) {
-        let mut concentration = 0.0;
+    pub fn best(&self) -> Option<(Variables, f32)> {
+        let concentration = self.mean_concentration()?;
         let mut resistance = 0.0;
         let mut saturation = 0.0;
         let mut error = 0.0;
         let mut n = 0;
         for (vars, err) in self.data.iter().filter(|(_, e)| e.is_finite()) {
-            concentration += vars.concentration;
             resistance += vars.resistance;
             saturation += vars.saturation;
             error += err;
             n += 1;
         }
+
         let n_inv = 1.0 / n as f32;
-        (
+        Some((
             Variables {
-                concentration: concentration * n_inv,
+                concentration,
                 resistance: resistance * n_inv,
                 saturation: saturation * n_inv,
             },
             error * n_inv,
-        )
+        ))
     }
 }
 
@@ -292,6 +325,12 @@ mod tests {
         assert_eq!(list.data[2].0, 1.0);
         assert_eq!(list.data[2].1, 1.0);
 
+        list.add_solution((5.0, 0.5));
+        list.add_solution((6.0, -1.0));
+        assert_eq!(list.data[0], (6.0, -1.0));
+        assert_eq!(list.data[1], (0.0, 0.0));
+        assert_eq!(list.data[2], (4.0, 0.5));
+
         let mut list = BestOrderedList::<Variables, 3>::new();
 
         list.add_solution((
@@ -403,144 +442,138 @@ mod tests {
     #[test]
     fn test_mean_concentration() {
         let mut list = BestOrderedList::<f32, 3>::new();
-        list.data = [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)];
-        assert_eq!(list.mean_concentration(), 1.0);
+        assert_eq!(list.mean_concentration(), None);
+
+        list.add_solution((0.0, 0.0));
+        list.add_solution((1.0, 1.0));
+        assert_eq!(list.mean_concentration(), Some(0.5));
 
-        list.data = [(0.0, 0.0), (1.0, 1.0), (0.0, f32::INFINITY)];
-        assert_eq!(list.mean_concentration(), 0.5);
+        list.add_solution((2.0, 2.0));
+        assert_eq!(list.mean_concentration(), Some(1.0));
+
+        // The worst solution is dropped from the mean.
+        list.add_solution((5.0, 0.5));
+        assert_eq!(list.mean_concentration(), Some(2.0));
+
+        // Solutions worse than the list or with a non-finite error are ignored.
+        list.add_solution((9.0, 3.0));
+        list.add_solution((9.0, f32::NAN));
+        assert_eq!(list.mean_concentration(), Some(2.0));
+
+        list.clear();
+        assert_eq!(list.mean_concentration(), None);
 
         let mut list = BestOrderedList::<Variables, 3>::new();
-        list.data = [
-            (
-                Variables {
-                    concentration: 0.0,
-                    resistance: 0.0,
-                    saturation: 0.0,
-                },
-                0.0,
-            ),
-            (
+        assert_eq!(list.mean_concentration(), None);
+        for (concentration, error) in [(0.0, 0.0), (1.0, 1.0)] {
+            list.add_solution((
                 Variables {
-                    concentration: 1.0,
-                    resistance: 1.0,
-                    saturation: 1.0,
+                    concentration,
+                    resistance: 10.0,
+                    saturation: 10.0,
                 },
-                1.0,
-            ),
-            (
-                Variables {
-                    concentration: 2.0,
-                    resistance: 2.0,
-                    saturation: 2.0,
-                },
-                2.0,
-            ),
-        ];
-        assert_eq!(list.mean_concentration(), 1.0);
+                error,
+            ));
+        }
+        assert_eq!(list.mean_concentration(), Some(0.5));
 
-        list.data = [
-            (
+        for (concentration, error) in [(2.0, 2.0), (5.0, 0.5)] {
+            list.add_solution((
                 Variables {
-                    concentration: 0.0,
-                    resistance: 0.0,
-                    saturation: 0.0,
-                },
-                0.0,
-            ),
-            (
-                Variables {
-                    concentration: 1.0,
-                    resistance: 1.0,
-                    saturation: 1.0,
+                    concentration,
+                    resistance: 10.0,
+                    saturation: 10.0,
                 },
-                1.0,
-            ),
-            (
-                Variables {
-                    concentration: 0.0,
-                    resistance: 0.0,
-                    saturation: 0.0,
-                },
-                f32::INFINITY,
-            ),
-        ];
-        assert_eq!(list.mean_concentration(), 0.5);
+                error,
+            ));
+        }
+        assert_eq!(list.mean_concentration(), Some(2.0));
+
+        list.clear();
+        assert_eq!(list.mean_concentration(), None);
     }
 
     #[test]
     fn test_best() {
         let mut list = BestOrderedList::<f32, 3>::new();
-        list.data = [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)];
-        let best = list.best();
-        assert_eq!(best, 1.0);
+        for solution in [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)] {
+            list.add_solution(solution);
+        }
+        assert_eq!(list.best(), Some(1.0));
 
-        list.data = [(0.0, 0.0), (1.0, 1.0), (0.0, f32::INFINITY)];
-        let best = list.best();
-        assert_eq!(best, 0.5);
+        let mut list = BestOrderedList::<f32, 3>::new();
+        list.add_solution((0.0, 0.0));
+        list.add_solution((1.0, 1.0));
+        assert_eq!(list.best(), Some(0.5));
 
+        let vars = |value: f32| Variables {
+            concentration: value,
+            resistance: value,
+            saturation: value,
+        };
         let mut list = BestOrderedList::<Variables, 3>::new();
-        list.data = [
-            (
-                Variables {
-                    concentration: 0.0,
-                    resistance: 0.0,
-                    saturation: 0.0,
-                },
-                0.0,
-            ),
-            (
-                Variables {
-                    concentration: 1.0,
-                    resistance: 1.0,
-                    saturation: 1.0,
-                },
-                1.0,
-            ),
-            (
-                Variables {
-                    concentration: 2.0,
-                    resistance: 2.0,
-                    saturation: 2.0,
-                },
-                2.0,
-            ),
-        ];
-        let best = list.best();
+        for value in [0.0, 1.0, 2.0] {
+            list.add_solution((vars(value), value));
+        }
+        let best = list.best().unwrap();
         assert_eq!(best.0.concentration, 1.0);
         assert_eq!(best.0.resistance, 1.0);
         assert_eq!(best.0.saturation, 1.0);
         assert_eq!(best.1, 1.0);
 
-        list.data = [
-            (
-                Variables {
-                    concentration: 0.0,
-                    resistance: 0.0,
-                    saturation: 0.0,
-                },
-                0.0,
-            ),
-            (
-                Variables {
-                    concentration: 1.0,
-                    resistance: 1.0,
-                    saturation: 1.0,
-                },
-                1.0,
-            ),
-            (
-                Variables {
-                    concentration: 0.0,
-                    resistance: 0.0,
-                    saturation: 0.0,
-                },
-                f32::INFINITY,
-            ),
-        ];
-        let best = list.best();
+        let mut list = BestOrderedList::<Variables, 3>::new();
+        for value in [0.0, 1.0] {
+            list.add_solution((vars(value), value));
+        }
+        let best = list.best().unwrap();
         assert_eq!(best.0.concentration, 0.5);
         assert_eq!(best.0.resistance, 0.5);
         assert_eq!(best.0.saturation, 0.5);
         assert_eq!(best.1, 0.5);
     }
+
+    #[test]
+    fn test_mean_concentration_many_decades() {
+        // Improving solutions on a descending logarithmic grid, whose running
+        // sum used to drift below zero.
+        let mut list = BestOrderedList::<f32, 10>::new();
+        let mut vars_list = BestOrderedList::<Variables, 10>::new();
+        let steps = 200_000;
+        for i in 0..steps {
+            let concentration = 10f32.powf(-6.0 * i as f32 / (steps - 1) as f32);
+            let error = concentration;
+            list.add_solution((concentration, error));
+            vars_list.add_solution((
+                Variables {
+                    concentration,
+                    resistance: 0.0,
+                    saturation: 0.0,
+                },
+                error,
+            ));
+        }
+
+        let mean = list.mean_concentration().unwrap();
+        assert!(mean > 0.0);
+        assert!((mean - 1e-6).abs() < 1e-8, "{mean}");
+        assert_eq!(list.mean_concentration(), list.best());
+        assert_eq!(
+            vars_list.mean_concentration(),
+            vars_list.best().map(|(vars, _)| vars.concentration)
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut list = BestOrderedList::<f32, 3>::new();
+        assert_eq!(list.mean_concentration(), None);
+        assert_eq!(list.best(), None);
+
+        list.add_solution((1.0, f32::INFINITY));
+        assert_eq!(list.best(), None);
+
+        let list = BestOrderedList::<Variables, 3>::new();
+        assert_eq!(list.mean_concentration(), None);
+        assert_eq!(list.best(), None);
+    }
 }
@@ -0,0 +1,317 @@
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+/// Computes the square root.
+///
+/// On cores with a single precision floating point unit and the hard-float
+/// ABI, e.g. the Cortex-M4F and the Cortex-M7F, the `VSQRT` instruction is
+/// used, which is correctly rounded and takes 14 cycles. Elsewhere the
+/// square root is computed in software: with the `libm` feature by the
+/// correctly rounded routine of `libm`, otherwise by the standard library as
+/// soon as it is linked, or by the approximation of `micromath`, with a
+/// relative error up to 6%, e.g. on the Cortex-M0+.
+///
+/// With the `deterministic` feature, the approximation of `micromath` is
+/// used on every target, so that the host computes the same bits of the
+/// firmware without a floating point unit. Otherwise, since every other
+/// implementation is correctly rounded, the host computes the same bits of
+/// the firmware with a floating point unit.
+///
+/// # Arguments
+///
+/// * `x` - The number, `NaN` if negative.
+///
+/// # Returns
+///
+/// The square root of the number.
+#[inline]
+pub fn sqrt(x: f32) -> f32 {
+    #[cfg(feature = "deterministic")]
+    return F32Ext::sqrt(x);
+    #[cfg(all(
+        target_arch = "arm",
+        target_abi = "eabihf",
+        not(feature = "deterministic")
+    ))]
+    return vsqrt(x);
+    #[cfg(all(
+        feature = "libm",
+        not(any(
+            feature = "deterministic",
+            all(target_arch = "arm", target_abi = "eabihf")
+        ))
+    ))]
+    return libm::sqrtf(x);
+    #[cfg(not(any(
+        feature = "deterministic",
+        feature = "libm",
+        all(target_arch = "arm", target_abi = "eabihf")
+    )))]
+    return x.sqrt();
+}
+
+/// Computes the square root with the `VSQRT` instruction of the floating
+/// point unit.
+///
+/// The `sreg` operands of the inline assembly require the `vfp2` feature,
+/// which implies double precision and is not enabled on the Cortex-M4F, so
+/// the operand is moved through a core register and `s0` is restored after
+/// the instruction.
+#[cfg(all(
+    target_arch = "arm",
+    target_abi = "eabihf",
+    not(feature = "deterministic")
+))]
+#[inline]
+fn vsqrt(x: f32) -> f32 {
+    let bits: u32;
+    // SAFETY: the instructions only use the operands and `s0`, which is
+    // saved and restored, and do not access the memory or the stack.
+    unsafe {
+        core::arch::asm!(
+            "vmov {saved}, s0",
+            "vmov s0, {x}",
+            "vsqrt.f32 s0, s0",
+            "vmov {x}, s0",
+            "vmov s0, {saved}",
+            x = inout(reg) x.to_bits() => bits,
+            saved = out(reg) _,
+            options(pure, nomem, nostack),
+        );
+    }
+    f32::from_bits(bits)
+}
+
+/// Number of bits of the mantissa used to index the logarithm table.
+#[cfg(feature = "fast-math")]
+const LN_TABLE_BITS: u32 = 6;
+
+/// Number of intervals of the logarithm table.
+#[cfg(feature = "fast-math")]
+const LN_TABLE_SIZE: usize = 1 << LN_TABLE_BITS;
+
+/// Natural logarithm of the mantissas `1 + i / LN_TABLE_SIZE`, including
+/// the upper end of the last interval.
+#[cfg(feature = "fast-math")]
+static LN_TABLE: [f32; LN_TABLE_SIZE + 1] = ln_table();
+
+/// Builds the logarithm table at compile time using the series
+/// `ln(x) = 2 * atanh((x - 1) / (x + 1))`, which converges quickly for the
+/// mantissas in `[1, 2]`.
+#[cfg(feature = "fast-math")]
+const fn ln_table() -> [f32; LN_TABLE_SIZE + 1] {
+    let mut table = [0.0; LN_TABLE_SIZE + 1];
+    let mut i = 0;
+    while i <= LN_TABLE_SIZE {
+        let x = 1.0 + i as f64 / LN_TABLE_SIZE as f64;
+        let z = (x - 1.0) / (x + 1.0);
+        let mut term = z;
+        let mut sum = 0.0;
+        let mut k = 1;
+        while k < 40 {
+            sum += term / k as f64;
+            term *= z * z;
+            k += 2;
+        }
+        table[i] = (2.0 * sum) as f32;
+        i += 1;
+    }
+    table
+}
+
+/// Computes the natural logarithm by linear interpolation of a table
+/// indexed by the most significant bits of the mantissa.
+///
+/// The absolute error is below `4e-5` for all positive normal numbers,
+/// with a cost of few integer operations and one multiply-add.
+///
+/// # Arguments
+///
+/// * `x` - The argument of the logarithm.
+///
+/// # Returns
+///
+/// The natural logarithm of `x`, `-inf` for zero and NaN for negative numbers.
+#[cfg(feature = "fast-math")]
+pub(crate) fn ln(x: f32) -> f32 {
+    if x.is_nan() || x < 0.0 {
+        return f32::NAN;
+    } else if x == 0.0 {
+        return f32::NEG_INFINITY;
+    } else if x == f32::INFINITY {
+        return x;
+    }
+
+    // Subnormal numbers are scaled to normal ones.
+    let (x, offset) = if x < f32::MIN_POSITIVE {
+        (x * (1u32 << 23) as f32, -23)
+    } else {
+        (x, 0)
+    };
+
+    let bits = x.to_bits();
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + offset;
+    let mantissa = bits & 0x007f_ffff;
+
+    let index = (mantissa >> (23 - LN_TABLE_BITS)) as usize;
+    let fraction = (mantissa & ((1 << (23 - LN_TABLE_BITS)) - 1)) as f32
+        / (1u32 << (23 - LN_TABLE_BITS)) as f32;
+
+    let (low, high) = (LN_TABLE[index], LN_TABLE[index + 1]);
+    exponent as f32 * core::f32::consts::LN_2 + low + (high - low) * fraction
+}
+
+/// Computes the exponential function as `2^k * e^r`, where `k` is the
+/// nearest integer to `x / ln(2)` and `e^r` is evaluated with a polynomial
+/// of degree six on `|r| <= ln(2) / 2`.
+///
+/// The relative error is below `1e-6`, results below the smallest normal
+/// number are flushed to zero.
+///
+/// # Arguments
+///
+/// * `x` - The exponent.
+///
+/// # Returns
+///
+/// The exponential of `x`.
+#[cfg(feature = "fast-math")]
+pub(crate) fn exp(x: f32) -> f32 {
+    let t = x * core::f32::consts::LOG2_E;
+    if t.is_nan() {
+        return f32::NAN;
+    } else if t >= 128.0 {
+        return f32::INFINITY;
+    } else if t < -126.0 {
+        return 0.0;
+    }
+
+    let k = if t >= 0.0 {
+        (t + 0.5) as i32
+    } else {
+        (t - 0.5) as i32
+    };
+    // ln(2) is split in a part exactly multiplied by k and a correction.
+    let r = (x - k as f32 * 0.693_145_75) - k as f32 * 1.428_606_8e-6;
+    let p = 1.0
+        + r * (1.0
+            + r * (1.0 / 2.0
+                + r * (1.0 / 6.0 + r * (1.0 / 24.0 + r * (1.0 / 120.0 + r * (1.0 / 720.0))))));
+
+    // 2^k is built from its exponent bits, splitting it when k is 128.
+    if k > 127 {
+        p * 2.0 * f32::from_bits(((k - 1 + 127) as u32) << 23)
+    } else {
+        p * f32::from_bits(((k + 127) as u32) << 23)
+    }
+}
+
+/// Raises a positive number to a floating point power as `exp(n * ln(x))`.
+///
+/// # Arguments
+///
+/// * `x` - The base.
+/// * `n` - The exponent.
+///
+/// # Returns
+///
+/// The power `x^n`, with a relative error below `5e-5 * |n|` plus the one of
+/// [`exp`].
+#[cfg(feature = "fast-math")]
+#[inline]
+pub(crate) fn powf(x: f32, n: f32) -> f32 {
+    exp(n * ln(x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqrt() {
+        // The square root is correctly rounded on the host, or the one of
+        // the firmware without a floating point unit.
+        let mut x = 1e-30f32;
+        while x < 1e30 {
+            let expected = if cfg!(feature = "deterministic") {
+                F32Ext::sqrt(x)
+            } else {
+                (x as f64).sqrt() as f32
+            };
+            assert_eq!(sqrt(x).to_bits(), expected.to_bits(), "{x}");
+            x *= 1.37;
+        }
+
+        #[cfg(not(feature = "deterministic"))]
+        {
+            assert_eq!(sqrt(0.0), 0.0);
+            assert_eq!(sqrt(16.0), 4.0);
+            assert_eq!(sqrt(f32::INFINITY), f32::INFINITY);
+            assert!(sqrt(-1.0).is_nan());
+            assert!(sqrt(f32::NAN).is_nan());
+        }
+    }
+
+    #[cfg(feature = "fast-math")]
+    #[test]
+    fn test_ln_table() {
+        assert_eq!(LN_TABLE[0], 0.0);
+        assert_eq!(LN_TABLE[LN_TABLE_SIZE], core::f32::consts::LN_2);
+        for (i, value) in LN_TABLE.iter().enumerate() {
+            let x = 1.0 + i as f64 / LN_TABLE_SIZE as f64;
+            assert!((*value as f64 - x.ln()).abs() < 1e-7);
+        }
+    }
+
+    #[cfg(feature = "fast-math")]
+    #[test]
+    fn test_ln() {
+        let mut x = 1e-6f32;
+        while x < 10.0 {
+            assert!((ln(x) - x.ln()).abs() < 4e-5, "ln({x})");
+            x *= 1.013;
+        }
+        assert!((ln(1e-40) - 1e-40f32.ln()).abs() < 4e-5);
+    }
+
+    #[cfg(feature = "fast-math")]
+    #[test]
+    fn test_exp() {
+        let mut x = -87.0f32;
+        while x < 88.0 {
+            let expected = x.exp();
+            assert!((exp(x) - expected).abs() <= 1e-6 * expected, "exp({x})");
+            x += 0.173;
+        }
+        assert_eq!(exp(0.0), 1.0);
+        assert_eq!(exp(100.0), f32::INFINITY);
+        assert_eq!(exp(-100.0), 0.0);
+        assert_eq!(exp(f32::NEG_INFINITY), 0.0);
+        assert!(exp(f32::NAN).is_nan());
+    }
+
+    #[cfg(feature = "fast-math")]
+    #[test]
+    fn test_powf() {
+        let mut x = 1e-6f32;
+        while x < 10.0 {
+            for n in [0.955, -0.045] {
+                let expected = x.powf(n);
+                assert!((powf(x, n) - expected).abs() <= 5e-5 * expected, "{x}^{n}");
+            }
+            x *= 1.013;
+        }
+        assert_eq!(powf(0.0, 0.955), 0.0);
+        assert_eq!(powf(0.0, -0.045), f32::INFINITY);
+    }
+
+    #[cfg(feature = "fast-math")]
+    #[test]
+    fn test_ln_special() {
+        assert_eq!(ln(1.0), 0.0);
+        assert_eq!(ln(0.0), f32::NEG_INFINITY);
+        assert_eq!(ln(f32::INFINITY), f32::INFINITY);
+        assert!(ln(-1.0).is_nan());
+        assert!(ln(f32::NAN).is_nan());
+    }
+}
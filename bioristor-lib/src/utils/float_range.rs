@@ -36,6 +36,43 @@ impl FloatRange {
     pub const fn new(start: f32, end: f32, steps: usize) -> Self {
         Self { start, end, steps }
     }
+
+    /// Splits the range into `parts` disjoint chunks and returns the chunk
+    /// with the given index.
+    ///
+    /// The chunks cover the same values of the range, up to rounding, and
+    /// have the same number of steps, except for the last ones which can have
+    /// one step less when `steps` is not a multiple of `parts`.
+    ///
+    /// # Arguments
+    ///
+    /// * `parts` - The number of chunks, must be greater than zero.
+    /// * `index` - The index of the chunk, from `0` to `parts - 1`.
+    ///
+    /// # Returns
+    ///
+    /// The chunk of the range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bioristor_lib::utils::FloatRange;
+    ///
+    /// let range = FloatRange::new(0.0, 1.0, 10);
+    /// assert_eq!(range.split(2, 0), FloatRange::new(0.0, 0.5, 5));
+    /// assert_eq!(range.split(2, 1), FloatRange::new(0.5, 1.0, 5));
+    /// ```
+    pub fn split(&self, parts: usize, index: usize) -> Self {
+        let increment = (self.end - self.start) / self.steps as f32;
+        let bound = |i: usize| {
+            let step = (self.steps * i).div_ceil(parts);
+            (step, self.start + increment * step as f32)
+        };
+
+        let (first, start) = bound(index);
+        let (last, end) = bound(index + 1);
+        Self::new(start, end, last - first)
+    }
 }
 
 impl IntoIterator for FloatRange {
@@ -73,7 +110,7 @@ impl IntoIterator for FloatRange {
 /// assert!((iter.next().unwrap() - 0.9).abs() < 1e-6);
 /// assert_eq!(iter.next(), None);
 /// ```
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct FloatRangeIter {
     /// The current value of the iterator.
@@ -128,4 +165,19 @@ mod tests {
         assert!((iter.next().unwrap() - 0.9).abs() < 1e-6);
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn test_float_range_split() {
+        let range = FloatRange::new(0.0, 1.0, 10usize);
+
+        let chunks = [range.split(3, 0), range.split(3, 1), range.split(3, 2)];
+        assert_eq!(chunks.clone().map(|chunk| chunk.steps), [4, 3, 3]);
+        assert_eq!(chunks[2].end, range.end);
+
+        let mut values = chunks.into_iter().flatten();
+        for expected in range {
+            assert!((values.next().unwrap() - expected).abs() < 1e-6);
+        }
+        assert_eq!(values.next(), None);
+    }
 }
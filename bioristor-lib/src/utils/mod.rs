@@ -1,5 +1,20 @@
 mod best_ordered_list;
+pub mod fast_math;
 mod float_range;
 
 pub use best_ordered_list::BestOrderedList;
 pub use float_range::FloatRange;
+
+/// The CRC used to protect the data stored or transmitted by the device
+/// (CRC-16/CCITT-FALSE).
+#[cfg(feature = "crc")]
+pub const CRC16: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_IBM_3740);
+
+/// Rounds a value to the nearest `u16`, clamping values out of range and
+/// mapping NaN to zero, to pack the variables in the payloads of the
+/// fieldbuses.
+#[cfg(any(feature = "can", feature = "lorawan"))]
+#[inline]
+pub(crate) fn scale(value: f32) -> u16 {
+    (value + 0.5) as u16
+}
@@ -0,0 +1,424 @@
+use wasm_bindgen::prelude::*;
+
+use crate::algorithms::{
+    Adaptive2Equation, Adaptive2Params, AdaptiveEquation, AdaptiveParams, Algorithm,
+    BruteForceEquation, BruteForceParams, GradientDescentEquation, GradientDescentParams,
+    NeuralNetworkEquation, NeuralNetworkParams, NewtonEquation, NewtonParams, SaturationPolicy,
+};
+use crate::losses::Absolute;
+use crate::models::{Equation, EquationModel, Model};
+use crate::params::{
+    Currents, ModelParams, ModulationParams, StemResistanceInvParams, Variables, Voltages,
+};
+use crate::utils::FloatRange;
+
+/// The number of minima averaged by the adaptive algorithms.
+const MINIMA: usize = 10;
+
+/// The parameters of the mathematical model, see [`ModelParams`].
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SensorParams {
+    /// The parameter `a` of the modulation function.
+    pub mod_a: f32,
+
+    /// The parameter `b` of the modulation function.
+    pub mod_b: f32,
+
+    /// The parameter `c` of the modulation function.
+    pub mod_c: f32,
+
+    /// Eletrical resistance of the dry PEDOT channel [Ohm].
+    pub r_dry: f32,
+
+    /// The parameter `a` of the inverse of stem resistance function.
+    pub res_a: f32,
+
+    /// The parameter `b` of the inverse of stem resistance function.
+    pub res_b: f32,
+
+    /// The drain-source voltage [Volt].
+    pub v_ds: f32,
+
+    /// The gate-source voltage [Volt].
+    pub v_gs: f32,
+}
+
+#[wasm_bindgen]
+impl SensorParams {
+    /// Creates the parameters of the device characterized in the laboratory,
+    /// the same of the examples.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            mod_a: 0.0,
+            mod_b: -0.01463,
+            mod_c: -0.32,
+            r_dry: 38.2,
+            res_a: 1.35e-6,
+            res_b: 2.73e-4,
+            v_ds: -0.05,
+            v_gs: 0.5,
+        }
+    }
+}
+
+impl Default for SensorParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<SensorParams> for ModelParams {
+    fn from(params: SensorParams) -> Self {
+        ModelParams {
+            mod_params: ModulationParams(params.mod_a, params.mod_b, params.mod_c),
+            r_dry: params.r_dry,
+            res_params: StemResistanceInvParams(params.res_a, params.res_b),
+            voltages: Voltages {
+                v_ds: params.v_ds,
+                v_gs: params.v_gs,
+            },
+        }
+    }
+}
+
+/// The parameters of the solvers, flattened in a single object so that they
+/// can be edited from JavaScript. Each solver uses only the relevant ones.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolverParams {
+    /// The initial guess of the concentration [Molarity].
+    pub concentration_init: f32,
+
+    /// The upper bound of the concentrations to search [Molarity].
+    pub concentration_max: f32,
+
+    /// The lower bound of the concentrations to search [Molarity].
+    pub concentration_min: f32,
+
+    /// The number of concentrations to search.
+    pub concentration_steps: usize,
+
+    /// The tolerance on the gradient at which the iterative solvers stop.
+    pub grad_tolerance: f32,
+
+    /// The initial learning rate of the gradient descent.
+    pub learning_rate_init: f32,
+
+    /// The maximum number of iterations.
+    pub max_iterations: usize,
+
+    /// The width of the range of concentrations below which the adaptive
+    /// algorithm v2 stops, zero to disable the criterion.
+    pub min_range_width: f32,
+
+    /// The factor by which the range of concentrations is reduced after each
+    /// iteration of the adaptive algorithm v2.
+    pub reduction_factor: f32,
+
+    /// The upper bound of the resistances to search [Ohm].
+    pub resistance_max: f32,
+
+    /// The lower bound of the resistances to search [Ohm].
+    pub resistance_min: f32,
+
+    /// The number of resistances to search.
+    pub resistance_steps: usize,
+
+    /// The upper bound of the saturations to search.
+    pub saturation_max: f32,
+
+    /// The lower bound of the saturations to search.
+    pub saturation_min: f32,
+
+    /// The number of saturations to search.
+    pub saturation_steps: usize,
+
+    /// The error tolerance at which the solvers stop.
+    pub tolerance: f32,
+}
+
+#[wasm_bindgen]
+impl SolverParams {
+    /// Creates the parameters used by the firmware.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            concentration_init: 1e-2,
+            concentration_max: 1e-1,
+            concentration_min: 1e-4,
+            concentration_steps: 1_000,
+            grad_tolerance: 1e-9,
+            learning_rate_init: 0.1,
+            max_iterations: 10,
+            min_range_width: 0.0,
+            reduction_factor: 0.2,
+            resistance_max: 100.0,
+            resistance_min: 10.0,
+            resistance_steps: 100,
+            saturation_max: 1.0,
+            saturation_min: 0.0,
+            saturation_steps: 100,
+            tolerance: 1e-15,
+        }
+    }
+}
+
+impl Default for SolverParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SolverParams {
+    fn concentration_range(&self) -> FloatRange {
+        FloatRange::new(
+            self.concentration_min,
+            self.concentration_max,
+            self.concentration_steps,
+        )
+    }
+
+    fn resistance_range(&self) -> FloatRange {
+        FloatRange::new(
+            self.resistance_min,
+            self.resistance_max,
+            self.resistance_steps,
+        )
+    }
+
+    fn saturation_range(&self) -> FloatRange {
+        FloatRange::new(
+            self.saturation_min,
+            self.saturation_max,
+            self.saturation_steps,
+        )
+    }
+}
+
+/// A solution of the model, see [`Variables`].
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Solution {
+    /// The concentration of ions in the electrolyte [Molarity].
+    pub concentration: f32,
+
+    /// The value of the loss function at the solution.
+    pub loss: f32,
+
+    /// The wet drain-source resistance [Ohm].
+    pub resistance: f32,
+
+    /// The water saturation.
+    pub saturation: f32,
+}
+
+impl From<(Variables, f32)> for Solution {
+    fn from((vars, loss): (Variables, f32)) -> Self {
+        Solution {
+            concentration: vars.concentration,
+            loss,
+            resistance: vars.resistance,
+            saturation: vars.saturation,
+        }
+    }
+}
+
+/// The equation model of a device for a set of measured currents, exported
+/// to JavaScript together with its solvers.
+///
+/// The bindings let the web dashboard run what-if solves in the browser with
+/// the same code of the firmware. The `wasm` feature does not enable `std`,
+/// so the mathematical functions are the same approximations used on the
+/// devices and the solutions match the ones computed on the field.
+///
+/// # Example
+///
+/// ```js
+/// import { Sensor, SensorParams, SolverParams } from "bioristor-lib";
+///
+/// const sensor = new Sensor(new SensorParams(), -0.0030365, -0.0026829, 1.169828e-6);
+/// const params = new SolverParams();
+/// params.tolerance = 1e-12;
+/// const solution = sensor.solve_adaptive2(params);
+/// ```
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct Sensor {
+    /// The model.
+    model: Equation,
+}
+
+#[wasm_bindgen]
+impl Sensor {
+    /// Creates the model of a device.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters of the mathematical model.
+    /// * `i_ds_off` - The drain-source current when the gate is off [Ampere].
+    /// * `i_ds_on` - The drain-source current when the gate is on [Ampere].
+    /// * `i_gs_on` - The gate-source current when the gate is on [Ampere].
+    #[wasm_bindgen(constructor)]
+    pub fn new(params: &SensorParams, i_ds_off: f32, i_ds_on: f32, i_gs_on: f32) -> Self {
+        let currents = Currents {
+            i_ds_off,
+            i_ds_on,
+            i_gs_on,
+        };
+        Self {
+            model: Equation::new((*params).into(), currents),
+        }
+    }
+
+    /// Returns the value of the equation at the given concentration.
+    pub fn value(&self, concentration: f32) -> f32 {
+        self.model.value(concentration)
+    }
+
+    /// Returns the resistance at the given concentration [Ohm].
+    pub fn resistance(&self, concentration: f32) -> f32 {
+        self.model.resistance(concentration)
+    }
+
+    /// Returns the saturation at the given concentration.
+    pub fn saturation(&self, concentration: f32) -> f32 {
+        self.model.saturation(concentration)
+    }
+
+    /// Solves the model with the adaptive algorithm.
+    pub fn solve_adaptive(&self, params: &SolverParams) -> Option<Solution> {
+        self.solve::<AdaptiveEquation<Equation, Absolute, MINIMA>, _>(AdaptiveParams {
+            concentration_init: params.concentration_init,
+            concentration_steps: params.concentration_steps,
+            max_iterations: params.max_iterations,
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: params.saturation_range(),
+            resistance_range: params.resistance_range(),
+        })
+    }
+
+    /// Solves the model with the adaptive algorithm v2.
+    pub fn solve_adaptive2(&self, params: &SolverParams) -> Option<Solution> {
+        self.solve::<Adaptive2Equation<Equation, Absolute, MINIMA>, _>(Adaptive2Params {
+            concentration_range: params.concentration_range(),
+            max_iterations: params.max_iterations,
+            min_range_width: params.min_range_width,
+            reduction_factor: params.reduction_factor,
+            resistance_range: params.resistance_range(),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: params.saturation_range(),
+            tolerance: params.tolerance,
+        })
+    }
+
+    /// Solves the model with the brute force algorithm.
+    pub fn solve_brute_force(&self, params: &SolverParams) -> Option<Solution> {
+        self.solve::<BruteForceEquation<Equation, Absolute>, _>(BruteForceParams {
+            concentration_range: params.concentration_range(),
+            resistance_range: params.resistance_range(),
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: params.saturation_range(),
+        })
+    }
+
+    /// Solves the model with the gradient descent algorithm.
+    pub fn solve_gradient_descent(&self, params: &SolverParams) -> Option<Solution> {
+        self.solve::<GradientDescentEquation<Equation, Absolute>, _>(GradientDescentParams {
+            concentration_init: params.concentration_init,
+            grad_tolerance: params.grad_tolerance,
+            learning_rate_init: params.learning_rate_init,
+            max_iterations: params.max_iterations,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: params.tolerance,
+        })
+    }
+
+    /// Solves the model with the Newton's method.
+    pub fn solve_newton(&self, params: &SolverParams) -> Option<Solution> {
+        self.solve::<NewtonEquation<Equation, Absolute>, _>(NewtonParams {
+            concentration_init: params.concentration_init,
+            grad_tolerance: params.grad_tolerance,
+            max_iterations: params.max_iterations,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: params.tolerance,
+        })
+    }
+
+    /// Solves the model with the neural network with one hidden layer.
+    pub fn solve_neural_network(&self) -> Option<Solution> {
+        self.solve::<NeuralNetworkEquation<Equation, Absolute, 0>, _>(NeuralNetworkParams::default())
+    }
+}
+
+impl Sensor {
+    /// Runs an algorithm on a copy of the model.
+    fn solve<A: Algorithm<P, Equation>, P>(&self, params: P) -> Option<Solution> {
+        let model = Equation::new(self.model.params().clone(), *self.model.currents());
+        A::new(params, model).run().map(Solution::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor() -> Sensor {
+        Sensor::new(&SensorParams::new(), -0.0030365, -0.0026829, 1.169828e-6)
+    }
+
+    #[test]
+    fn test_sensor_params() {
+        let params: ModelParams = SensorParams::new().into();
+        assert_eq!(params.mod_params, ModulationParams(0.0, -0.01463, -0.32));
+        assert_eq!(params.res_params, StemResistanceInvParams(1.35e-6, 2.73e-4));
+        assert_eq!(params.voltages.v_gs, 0.5);
+    }
+
+    #[test]
+    fn test_sensor_model() {
+        let model: Equation = Equation::new(
+            SensorParams::new().into(),
+            Currents {
+                i_ds_off: -0.0030365,
+                i_ds_on: -0.0026829,
+                i_gs_on: 1.169828e-6,
+            },
+        );
+        assert_eq!(sensor().value(1e-2), model.value(1e-2));
+        assert_eq!(sensor().resistance(1e-2), model.resistance(1e-2));
+        assert_eq!(sensor().saturation(1e-2), model.saturation(1e-2));
+    }
+
+    #[test]
+    fn test_solve_adaptive2() {
+        let params = SolverParams::new();
+        let model: Equation = Equation::new(SensorParams::new().into(), *sensor().model.currents());
+        let expected: Adaptive2Equation<_, Absolute, MINIMA> = Adaptive2Equation::new(
+            Adaptive2Params {
+                concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+                max_iterations: 10,
+                min_range_width: 0.0,
+                reduction_factor: 0.2,
+                resistance_range: FloatRange::new(10.0, 100.0, 100),
+                saturation_policy: SaturationPolicy::Keep,
+                saturation_range: FloatRange::new(0.0, 1.0, 100),
+                tolerance: 1e-15,
+            },
+            model,
+        );
+
+        assert_eq!(
+            sensor().solve_adaptive2(&params),
+            expected.run().map(Solution::from)
+        );
+    }
+
+    #[test]
+    fn test_solve_empty_range() {
+        let mut params = SolverParams::new();
+        params.concentration_steps = 0;
+        assert_eq!(sensor().solve_brute_force(&params), None);
+    }
+}
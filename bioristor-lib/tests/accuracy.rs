@@ -0,0 +1,222 @@
+//! Accuracy of the mathematical backends over the operating range.
+//!
+//! The modulation, the inverse of the stem resistance and the absolute loss
+//! of the equation model are evaluated with the logarithm and the power of
+//! `micromath`, `libm` and the standard library, and compared with a
+//! reference computed in `f64`. The maximum and mean errors of every
+//! function are printed as a table, so that the choice of a backend is
+//! driven by data:
+//!
+//! ```text
+//! cargo test -p bioristor-lib --features std,libm --test accuracy -- --nocapture
+//! ```
+//!
+//! The tests also fail when a backend gets less accurate than the bounds
+//! below.
+
+mod fixtures;
+
+use bioristor_lib::params::{Currents, ModelParams};
+use fixtures::{MODEL_PARAMS, RECORDINGS};
+
+/// The bounds of the searched concentrations [Molarity].
+const CONCENTRATION_RANGE: (f64, f64) = (1e-4, 1e-1);
+
+/// The number of concentrations, spaced logarithmically over the range.
+const SAMPLES: usize = 10_000;
+
+/// The exponent of the power of the stem resistance.
+const EXPONENT: f32 = 0.955;
+
+/// The mathematical functions of a backend.
+struct Backend {
+    /// The name of the backend in the report.
+    name: &'static str,
+
+    /// The natural logarithm.
+    ln: fn(f32) -> f32,
+
+    /// The power with a floating point exponent.
+    powf: fn(f32, f32) -> f32,
+
+    /// The maximum error of the modulation, relative to the reference.
+    max_modulation_error: f64,
+
+    /// The maximum error of the inverse of the stem resistance, relative to
+    /// the reference.
+    max_stem_resistance_error: f64,
+
+    /// The maximum error of the loss, relative to the gate-source current.
+    max_loss_error: f64,
+}
+
+/// The backends under test.
+const BACKENDS: [Backend; 3] = [
+    Backend {
+        name: "micromath",
+        ln: micromath::F32Ext::ln,
+        powf: micromath::F32Ext::powf,
+        max_modulation_error: 1e-2,
+        max_stem_resistance_error: 1.5e-1,
+        max_loss_error: 1.0,
+    },
+    Backend {
+        name: "libm",
+        ln: libm::logf,
+        powf: libm::powf,
+        max_modulation_error: 2e-7,
+        max_stem_resistance_error: 3e-7,
+        max_loss_error: 2e-6,
+    },
+    Backend {
+        name: "std",
+        ln: f32::ln,
+        powf: f32::powf,
+        max_modulation_error: 2e-7,
+        max_stem_resistance_error: 3e-7,
+        max_loss_error: 2e-6,
+    },
+];
+
+/// The maximum and mean of a set of errors.
+#[derive(Default)]
+struct Stats {
+    max: f64,
+    sum: f64,
+    count: usize,
+}
+
+impl Stats {
+    fn add(&mut self, error: f64) {
+        self.max = self.max.max(error);
+        self.sum += error;
+        self.count += 1;
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+}
+
+/// Returns the concentrations of the operating range.
+fn concentrations() -> impl Iterator<Item = f64> {
+    let (start, end) = CONCENTRATION_RANGE;
+    let ratio = (end / start).ln() / (SAMPLES - 1) as f64;
+    (0..SAMPLES).map(move |i| start * (ratio * i as f64).exp())
+}
+
+fn modulation(params: &ModelParams, concentration: f32, ln: fn(f32) -> f32) -> f32 {
+    let p = &params.mod_params;
+    p.0 * concentration + p.1 * ln(concentration) + p.2
+}
+
+fn modulation_f64(params: &ModelParams, concentration: f64) -> f64 {
+    let p = &params.mod_params;
+    p.0 as f64 * concentration + p.1 as f64 * concentration.ln() + p.2 as f64
+}
+
+fn stem_resistance_inv(params: &ModelParams, concentration: f32, powf: fn(f32, f32) -> f32) -> f32 {
+    let p = &params.res_params;
+    p.0 + p.1 * powf(concentration, EXPONENT)
+}
+
+fn stem_resistance_inv_f64(params: &ModelParams, concentration: f64) -> f64 {
+    let p = &params.res_params;
+    p.0 as f64 + p.1 as f64 * concentration.powf(EXPONENT as f64)
+}
+
+/// Returns the value of the equation model from the modulation and the
+/// inverse of the stem resistance, with the coefficients of the library.
+fn value(params: &ModelParams, currents: &Currents, m: f64, r: f64) -> f64 {
+    let (v_ds, v_gs, r_dry) = (
+        params.voltages.v_ds as f64,
+        params.voltages.v_gs as f64,
+        params.r_dry as f64,
+    );
+    let (i_ds_off, i_ds_on, i_gs_on) = (
+        currents.i_ds_off as f64,
+        currents.i_ds_on as f64,
+        currents.i_gs_on as f64,
+    );
+    let c1 = v_gs * v_ds * (i_ds_off - i_ds_on + i_gs_on);
+    let c2 = v_gs * i_ds_off * (v_ds - i_ds_on * r_dry + i_gs_on * r_dry);
+    let c3 = i_ds_off * r_dry * (i_ds_on - i_gs_on);
+    i_gs_on + (c1 * r + c2 * r * m) / (c3 * m)
+}
+
+/// Prints a row of the report: the function, the backend, the maximum and
+/// the mean error.
+fn report(function: &str, backend: &Backend, stats: &Stats) {
+    println!(
+        "| {function:<20} | {:<9} | {:>10.3e} | {:>10.3e} |",
+        backend.name,
+        stats.max,
+        stats.mean()
+    );
+}
+
+#[test]
+fn test_accuracy_modulation() {
+    for backend in &BACKENDS {
+        let mut stats = Stats::default();
+        for concentration in concentrations() {
+            let expected = modulation_f64(&MODEL_PARAMS, concentration);
+            let actual = modulation(&MODEL_PARAMS, concentration as f32, backend.ln) as f64;
+            stats.add(((actual - expected) / expected).abs());
+        }
+        report("modulation", backend, &stats);
+        assert!(
+            stats.max <= backend.max_modulation_error,
+            "{}",
+            backend.name
+        );
+    }
+}
+
+#[test]
+fn test_accuracy_stem_resistance() {
+    for backend in &BACKENDS {
+        let mut stats = Stats::default();
+        for concentration in concentrations() {
+            let expected = stem_resistance_inv_f64(&MODEL_PARAMS, concentration);
+            let actual =
+                stem_resistance_inv(&MODEL_PARAMS, concentration as f32, backend.powf) as f64;
+            stats.add(((actual - expected) / expected).abs());
+        }
+        report("stem resistance inv", backend, &stats);
+        assert!(
+            stats.max <= backend.max_stem_resistance_error,
+            "{}",
+            backend.name
+        );
+    }
+}
+
+#[test]
+fn test_accuracy_loss() {
+    for backend in &BACKENDS {
+        let mut stats = Stats::default();
+        for recording in &RECORDINGS {
+            for concentration in concentrations() {
+                let expected = value(
+                    &MODEL_PARAMS,
+                    &recording.currents,
+                    modulation_f64(&MODEL_PARAMS, concentration),
+                    stem_resistance_inv_f64(&MODEL_PARAMS, concentration),
+                );
+                let actual = value(
+                    &MODEL_PARAMS,
+                    &recording.currents,
+                    modulation(&MODEL_PARAMS, concentration as f32, backend.ln) as f64,
+                    stem_resistance_inv(&MODEL_PARAMS, concentration as f32, backend.powf) as f64,
+                );
+                // The terms of the value cancel out close to the solution,
+                // so the error is relative to the gate-source current.
+                stats
+                    .add((actual.abs() - expected.abs()).abs() / recording.currents.i_gs_on as f64);
+            }
+        }
+        report("absolute loss", backend, &stats);
+        assert!(stats.max <= backend.max_loss_error, "{}", backend.name);
+    }
+}
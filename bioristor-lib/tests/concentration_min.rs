@@ -0,0 +1,69 @@
+//! Tests of the lower bound of the concentration set at runtime.
+//!
+//! The bound is global, so these tests run in their own binary, where no
+//! other test can observe it while it is changed.
+
+use bioristor_lib::{
+    algorithms::{
+        concentration_min, set_concentration_min, Algorithm, GradientDescentEquation,
+        GradientDescentParams, NewtonEquation, NewtonParams, SaturationPolicy,
+        DEFAULT_CONCENTRATION_MIN,
+    },
+    losses::Absolute,
+    models::{Equation, Model},
+    params::{Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages},
+};
+
+const MODEL_PARAMS: ModelParams = ModelParams {
+    mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    r_dry: 38.2,
+    res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    voltages: Voltages {
+        v_ds: -0.05,
+        v_gs: 0.5,
+    },
+};
+
+const CURRENTS: Currents = Currents {
+    i_ds_off: -0.003_036_5,
+    i_ds_on: -0.002_682_9,
+    i_gs_on: 1.169_828e-6,
+};
+
+#[test]
+fn test_initial_guess_clamped() {
+    set_concentration_min(1e-2);
+
+    // With an unreachable tolerance the solution is the initial guess, which
+    // is below the bound.
+    let newton = NewtonEquation::<_, Absolute>::new(
+        NewtonParams {
+            concentration_init: 1e-4,
+            grad_tolerance: 0.0,
+            max_iterations: 1,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: f32::MAX,
+        },
+        Equation::new(MODEL_PARAMS, CURRENTS),
+    );
+    let newton = newton.run().map(|(vars, _)| vars.concentration);
+
+    let gradient_descent = GradientDescentEquation::<_, Absolute>::new(
+        GradientDescentParams {
+            concentration_init: 1e-4,
+            grad_tolerance: 0.0,
+            learning_rate_init: 1e-3,
+            max_iterations: 1,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: f32::MAX,
+        },
+        Equation::new(MODEL_PARAMS, CURRENTS),
+    );
+    let gradient_descent = gradient_descent.run().map(|(vars, _)| vars.concentration);
+
+    set_concentration_min(DEFAULT_CONCENTRATION_MIN);
+    assert_eq!(concentration_min(), DEFAULT_CONCENTRATION_MIN);
+
+    assert_eq!(newton, Some(1e-2));
+    assert_eq!(gradient_descent, Some(1e-2));
+}
@@ -0,0 +1,173 @@
+//! Property-based consistency tests across the algorithms.
+//!
+//! The currents are generated from random physical states with the forward
+//! system model, then solved with the equation model by every algorithm: the
+//! algorithms must find the concentration of the state within their
+//! tolerance, or report that they did not converge.
+
+use bioristor_lib::{
+    algorithms::{
+        Adaptive2Equation, Adaptive2Params, Algorithm, BruteForceEquation, BruteForceParams,
+        GradientDescentEquation, GradientDescentParams, NewtonEquation, NewtonParams,
+        SaturationPolicy, SolveReport,
+    },
+    losses::Absolute,
+    models::{Equation, Model, System, SystemModel},
+    params::{
+        Currents, ModelParams, ModulationParams, StemResistanceInvParams, Variables, Voltages,
+    },
+    utils::FloatRange,
+};
+use proptest::prelude::*;
+
+const MODEL_PARAMS: ModelParams = ModelParams {
+    mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    r_dry: 38.2,
+    res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    voltages: Voltages {
+        v_ds: -0.05,
+        v_gs: 0.5,
+    },
+};
+
+/// The range of concentrations searched by the grid algorithms.
+const CONCENTRATION_RANGE: FloatRange = FloatRange::new(1e-4, 1e-1, 1_000);
+
+/// The maximum relative error of the iterative algorithms that converged.
+const RELATIVE_TOLERANCE: f32 = 1e-3;
+
+/// The loss, relative to the gate-source current, below which the
+/// approximations of the `deterministic` feature cannot tell the points of
+/// the grid apart.
+const APPROXIMATION_LOSS_TOLERANCE: f32 = 1e-2;
+
+/// Unused ranges of the equation algorithms.
+const UNUSED_RANGE: FloatRange = FloatRange::new(0.0, 1.0, 1);
+
+/// Returns the currents of a device in the given state.
+fn currents(vars: Variables) -> Currents {
+    let zero = Currents {
+        i_ds_off: 0.0,
+        i_ds_on: 0.0,
+        i_gs_on: 0.0,
+    };
+    let [(_, i_ds_on), (_, i_ds_off), (_, i_gs_on)] = System::new(MODEL_PARAMS, zero).value(vars);
+    // The model adds the measured gate-source current, zero here.
+    Currents {
+        i_ds_off,
+        i_ds_on: i_ds_on + i_gs_on,
+        i_gs_on,
+    }
+}
+
+/// Random physical states, with the concentration inside the searched range.
+fn states() -> impl Strategy<Value = Variables> {
+    (-2.7f32..-1.1, 30.0f32..60.0, 0.5f32..1.0).prop_map(|(exponent, resistance, saturation)| {
+        Variables {
+            concentration: 10f32.powf(exponent),
+            resistance,
+            saturation,
+        }
+    })
+}
+
+/// Checks the solution of an iterative algorithm, which is accepted if it is
+/// within the tolerance or the algorithm reported that it did not converge.
+fn check_iterative(
+    name: &str,
+    state: &Variables,
+    result: Option<(Variables, f32)>,
+    report: &SolveReport,
+) -> Result<(), TestCaseError> {
+    if let (Some((vars, _)), true) = (result, report.diagnostics.converged()) {
+        let error = (vars.concentration - state.concentration).abs() / state.concentration;
+        prop_assert!(
+            error < RELATIVE_TOLERANCE,
+            "{name}: {} instead of {}",
+            vars.concentration,
+            state.concentration
+        );
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn test_brute_force(state in states()) {
+        let currents = currents(state);
+        let model = Equation::new(MODEL_PARAMS, currents);
+        let params = BruteForceParams {
+            concentration_range: CONCENTRATION_RANGE,
+            resistance_range: UNUSED_RANGE,
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: UNUSED_RANGE,
+        };
+        let (vars, loss) = BruteForceEquation::<_, Absolute>::new(params, model).run().unwrap();
+
+        // The solution is the closest point of the grid. The approximations
+        // of the logarithm and of the power are not monotonic, so another
+        // point can have a lower loss, below their resolution.
+        let step = (CONCENTRATION_RANGE.end - CONCENTRATION_RANGE.start)
+            / CONCENTRATION_RANGE.steps as f32;
+        let closest = (vars.concentration - state.concentration).abs() <= step;
+        let approximated = cfg!(feature = "deterministic")
+            && loss <= APPROXIMATION_LOSS_TOLERANCE * currents.i_gs_on;
+        prop_assert!(
+            closest || approximated,
+            "{} instead of {}, loss {}",
+            vars.concentration,
+            state.concentration,
+            loss
+        );
+    }
+
+    #[test]
+    fn test_adaptive2(state in states()) {
+        let model = Equation::new(MODEL_PARAMS, currents(state));
+        let params = Adaptive2Params {
+            concentration_range: FloatRange::new(1e-4, 1e-1, 100),
+            max_iterations: 10,
+            min_range_width: 0.0,
+            reduction_factor: 0.2,
+            resistance_range: UNUSED_RANGE,
+            saturation_policy: SaturationPolicy::Keep,
+            saturation_range: UNUSED_RANGE,
+            tolerance: 1e-12,
+        };
+        let mut report = SolveReport::new();
+        let result = Adaptive2Equation::<_, Absolute, 5>::new(params, model).run_with(&mut report);
+        check_iterative("adaptive2", &state, result, &report)?;
+    }
+
+    #[test]
+    fn test_newton(state in states()) {
+        let model = Equation::new(MODEL_PARAMS, currents(state));
+        let params = NewtonParams {
+            concentration_init: 1e-2,
+            grad_tolerance: 1e-12,
+            max_iterations: 30,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-12,
+        };
+        let mut report = SolveReport::new();
+        let result = NewtonEquation::<_, Absolute>::new(params, model).run_with(&mut report);
+        check_iterative("newton", &state, result, &report)?;
+    }
+
+    #[test]
+    fn test_gradient_descent(state in states()) {
+        let model = Equation::new(MODEL_PARAMS, currents(state));
+        let params = GradientDescentParams {
+            concentration_init: 1e-2,
+            grad_tolerance: 1e-20,
+            learning_rate_init: 0.1,
+            max_iterations: 50,
+            saturation_policy: SaturationPolicy::Keep,
+            tolerance: 1e-12,
+        };
+        let mut report = SolveReport::new();
+        let result =
+            GradientDescentEquation::<_, Absolute>::new(params, model).run_with(&mut report);
+        check_iterative("gradient descent", &state, result, &report)?;
+    }
+}
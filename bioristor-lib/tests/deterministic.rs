@@ -0,0 +1,121 @@
+//! Bit-exact tests of the `deterministic` feature on the golden dataset.
+//!
+//! With the feature, the host uses the same mathematical functions of the
+//! firmware, so the solutions below are the ones computed on the targets:
+//! a firmware revision can be certified against the archived recordings by
+//! comparing the bits of its results with these ones.
+
+mod fixtures;
+
+use bioristor_lib::{
+    algorithms::{
+        Adaptive2Equation, Adaptive2Params, Algorithm, BruteForceEquation, BruteForceParams,
+        NewtonEquation, NewtonParams, SaturationPolicy,
+    },
+    losses::Absolute,
+    models::{Equation, EquationModel, Model},
+    params::Variables,
+    utils::FloatRange,
+};
+use fixtures::{Recording, MODEL_PARAMS, RECORDINGS};
+
+/// The range of concentrations searched by the grid algorithms.
+const CONCENTRATION_RANGE: FloatRange = FloatRange::new(1e-4, 1e-1, 1_000);
+
+/// Unused ranges of the equation algorithms.
+const UNUSED_RANGE: FloatRange = FloatRange::new(0.0, 1.0, 1);
+
+/// The bits of a solution: concentration, resistance, saturation and loss.
+type Bits = [u32; 4];
+
+/// Returns the equation model of a recording.
+fn model(recording: &Recording) -> Equation {
+    Equation::new(MODEL_PARAMS, recording.currents)
+}
+
+/// Returns the bits of the result of an algorithm.
+fn bits(result: Option<(Variables, f32)>) -> Bits {
+    let (vars, loss) = result.unwrap();
+    [
+        vars.concentration.to_bits(),
+        vars.resistance.to_bits(),
+        vars.saturation.to_bits(),
+        loss.to_bits(),
+    ]
+}
+
+#[test]
+fn test_deterministic_value() {
+    const EXPECTED: [u32; 2] = [0xb4f6_1278, 0xb402_1430];
+
+    for (recording, expected) in RECORDINGS.iter().zip(EXPECTED) {
+        assert_eq!(
+            model(recording).value(1e-2).to_bits(),
+            expected,
+            "{}",
+            recording.name
+        );
+    }
+}
+
+#[test]
+fn test_deterministic_brute_force() {
+    const EXPECTED: [Bits; 2] = [
+        [0x3bbd_de20, 0x4110_a636, 0x3f3e_ce95, 0x3126_7200],
+        [0x3c02_f152, 0x40a8_6722, 0x3f22_7283, 0x311e_d600],
+    ];
+
+    let params = BruteForceParams {
+        concentration_range: CONCENTRATION_RANGE,
+        resistance_range: UNUSED_RANGE,
+        saturation_policy: SaturationPolicy::Keep,
+        saturation_range: UNUSED_RANGE,
+    };
+    for (recording, expected) in RECORDINGS.iter().zip(EXPECTED) {
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params.clone(), model(recording));
+        assert_eq!(bits(algorithm.run()), expected, "{}", recording.name);
+    }
+}
+
+#[test]
+fn test_deterministic_adaptive2() {
+    const EXPECTED: [Bits; 2] = [
+        [0x3bbe_a1df, 0x4110_9d59, 0x3f3e_caf5, 0x2c48_0000],
+        [0x3c02_336d, 0x40a8_6f99, 0x3f22_73d2, 0x2d0e_0000],
+    ];
+
+    let params = Adaptive2Params {
+        concentration_range: CONCENTRATION_RANGE,
+        max_iterations: 10,
+        min_range_width: 0.0,
+        reduction_factor: 0.2,
+        resistance_range: UNUSED_RANGE,
+        saturation_policy: SaturationPolicy::Keep,
+        saturation_range: UNUSED_RANGE,
+        tolerance: 1e-15,
+    };
+    for (recording, expected) in RECORDINGS.iter().zip(EXPECTED) {
+        let algorithm = Adaptive2Equation::<_, Absolute, 10>::new(params.clone(), model(recording));
+        assert_eq!(bits(algorithm.run()), expected, "{}", recording.name);
+    }
+}
+
+#[test]
+fn test_deterministic_newton() {
+    const EXPECTED: [Bits; 2] = [
+        [0x3bbe_a219, 0x4110_9d56, 0x3f3e_caf4, 0x2a00_0000],
+        [0x3c02_39ba, 0x40a8_6f53, 0x3f22_73c8, 0x2e96_4000],
+    ];
+
+    let params = NewtonParams {
+        concentration_init: 1e-2,
+        grad_tolerance: 1e-9,
+        max_iterations: 10,
+        saturation_policy: SaturationPolicy::Keep,
+        tolerance: 1e-15,
+    };
+    for (recording, expected) in RECORDINGS.iter().zip(EXPECTED) {
+        let algorithm = NewtonEquation::<_, Absolute>::new(params.clone(), model(recording));
+        assert_eq!(bits(algorithm.run()), expected, "{}", recording.name);
+    }
+}
@@ -0,0 +1,64 @@
+//! Anonymized field recordings of the bioristor with their reference
+//! solutions, used as golden dataset by the regression tests.
+
+// Not every test uses every field of the recordings.
+#![allow(dead_code)]
+
+use bioristor_lib::params::{
+    Currents, ModelParams, ModulationParams, StemResistanceInvParams, Variables, Voltages,
+};
+
+/// The parameters of the model calibrated on the device of the recordings.
+pub const MODEL_PARAMS: ModelParams = ModelParams {
+    mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    r_dry: 38.2,
+    res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    voltages: Voltages {
+        v_ds: -0.05,
+        v_gs: 0.5,
+    },
+};
+
+/// A measurement vector recorded in the field with its reference solution.
+pub struct Recording {
+    /// The measured currents.
+    pub currents: Currents,
+
+    /// The name of the recording, reported by the failing tests.
+    pub name: &'static str,
+
+    /// The reference solution of the equation model for the currents,
+    /// computed by brute force over `1e-4..1e-1` with 10^6 steps and
+    /// confirmed by the Newton method to a relative error of 1e-5.
+    pub solution: Variables,
+}
+
+/// The golden dataset.
+pub const RECORDINGS: [Recording; 2] = [
+    Recording {
+        currents: Currents {
+            i_ds_off: -0.003_036_5,
+            i_ds_on: -0.002_682_9,
+            i_gs_on: 1.169_828e-6,
+        },
+        name: "plant-a",
+        solution: Variables {
+            concentration: 5.171_65e-3,
+            resistance: 9.038_41,
+            saturation: 0.745_284,
+        },
+    },
+    Recording {
+        currents: Currents {
+            i_ds_off: -0.002_890_3,
+            i_ds_on: -0.002_715,
+            i_gs_on: 1.277_413_7e-6,
+        },
+        name: "plant-b",
+        solution: Variables {
+            concentration: 7.882_76e-3,
+            resistance: 5.263_52,
+            saturation: 0.634_578,
+        },
+    },
+];
@@ -0,0 +1,238 @@
+//! Regression tests on the golden dataset of field recordings.
+//!
+//! Every algorithm solves the equation model for the recorded currents and
+//! must stay within its documented relative error from the reference
+//! solution, so that a refactor cannot silently shift the results.
+//!
+//! The bounds hold with the accurate mathematical functions of the host.
+//! With the `deterministic` feature the approximations of the firmware are
+//! used instead, whose results are checked bit by bit by the deterministic
+//! tests.
+
+#![cfg(not(feature = "deterministic"))]
+
+mod fixtures;
+
+use bioristor_lib::{
+    algorithms::{
+        Adaptive2Equation, Adaptive2Params, AdaptiveEquation, AdaptiveParams, Algorithm,
+        BruteForceEquation, BruteForceParams, GradientDescentEquation, GradientDescentParams,
+        NeuralNetworkEquation, NeuralNetworkParams, NewtonEquation, NewtonParams, SaturationPolicy,
+        SolveReport,
+    },
+    losses::Absolute,
+    models::{Equation, Model},
+    params::Variables,
+    utils::FloatRange,
+};
+use fixtures::{Recording, MODEL_PARAMS, RECORDINGS};
+
+/// The range of concentrations searched by the grid algorithms.
+const CONCENTRATION_RANGE: FloatRange = FloatRange::new(1e-4, 1e-1, 1_000);
+
+/// Unused ranges of the equation algorithms.
+const UNUSED_RANGE: FloatRange = FloatRange::new(0.0, 1.0, 1);
+
+/// Maximum relative error of the grid search, within one step of the grid.
+const BRUTE_FORCE_BOUND: f32 = 1e-2;
+
+/// Maximum relative error of the adaptive algorithm, which averages the
+/// minima of its last grid.
+const ADAPTIVE_BOUND: f32 = 1e-2;
+
+/// Maximum relative error of the algorithms that converge to the root.
+const CONVERGED_BOUND: f32 = 1e-4;
+
+/// Maximum relative error of the concentration estimated by the networks.
+const NEURAL_NETWORK_BOUND: f32 = 1e-1;
+
+/// Returns the equation model of a recording.
+fn model(recording: &Recording) -> Equation {
+    Equation::new(MODEL_PARAMS, recording.currents)
+}
+
+/// Returns the relative error of `value` from `reference`.
+fn relative_error(value: f32, reference: f32) -> f32 {
+    ((value - reference) / reference).abs()
+}
+
+/// Checks that the solution of an algorithm is within the relative error
+/// `bound` from the reference solution of the recording.
+///
+/// # Arguments
+///
+/// * `name` - The name of the algorithm.
+/// * `recording` - The solved recording.
+/// * `result` - The result of the algorithm.
+/// * `bound` - The maximum relative error.
+/// * `derived` - Whether the resistance and the saturation are derived from
+///   the concentration by the model, and so are checked too.
+fn check(
+    name: &str,
+    recording: &Recording,
+    result: Option<(Variables, f32)>,
+    bound: f32,
+    derived: bool,
+) {
+    let (vars, _) = result.unwrap_or_else(|| panic!("{name} on {}: no solution", recording.name));
+    let reference = &recording.solution;
+
+    let mut checks = vec![("concentration", vars.concentration, reference.concentration)];
+    if derived {
+        checks.push(("resistance", vars.resistance, reference.resistance));
+        checks.push(("saturation", vars.saturation, reference.saturation));
+    }
+    for (variable, value, expected) in checks {
+        let error = relative_error(value, expected);
+        assert!(
+            error <= bound,
+            "{name} on {}: {variable} {value} instead of {expected} (error {error} > {bound})",
+            recording.name
+        );
+    }
+}
+
+#[test]
+fn test_golden_brute_force() {
+    let params = BruteForceParams {
+        concentration_range: CONCENTRATION_RANGE,
+        resistance_range: UNUSED_RANGE,
+        saturation_policy: SaturationPolicy::Keep,
+        saturation_range: UNUSED_RANGE,
+    };
+    for recording in &RECORDINGS {
+        let algorithm = BruteForceEquation::<_, Absolute>::new(params.clone(), model(recording));
+        check(
+            "brute force",
+            recording,
+            algorithm.run(),
+            BRUTE_FORCE_BOUND,
+            true,
+        );
+    }
+}
+
+#[test]
+fn test_golden_adaptive() {
+    let params = AdaptiveParams {
+        concentration_init: 1e-2,
+        concentration_steps: 100,
+        max_iterations: 10,
+        resistance_range: UNUSED_RANGE,
+        saturation_policy: SaturationPolicy::Keep,
+        saturation_range: UNUSED_RANGE,
+    };
+    for recording in &RECORDINGS {
+        let algorithm = AdaptiveEquation::<_, Absolute, 5>::new(params.clone(), model(recording));
+        check("adaptive", recording, algorithm.run(), ADAPTIVE_BOUND, true);
+    }
+}
+
+#[test]
+fn test_golden_adaptive2() {
+    let params = Adaptive2Params {
+        concentration_range: CONCENTRATION_RANGE,
+        max_iterations: 10,
+        min_range_width: 0.0,
+        reduction_factor: 0.2,
+        resistance_range: UNUSED_RANGE,
+        saturation_policy: SaturationPolicy::Keep,
+        saturation_range: UNUSED_RANGE,
+        tolerance: 1e-15,
+    };
+    for recording in &RECORDINGS {
+        let algorithm = Adaptive2Equation::<_, Absolute, 10>::new(params.clone(), model(recording));
+        check(
+            "adaptive2",
+            recording,
+            algorithm.run(),
+            CONVERGED_BOUND,
+            true,
+        );
+    }
+}
+
+#[test]
+fn test_golden_newton() {
+    let params = NewtonParams {
+        concentration_init: 1e-2,
+        grad_tolerance: 1e-9,
+        max_iterations: 10,
+        saturation_policy: SaturationPolicy::Keep,
+        tolerance: 1e-15,
+    };
+    for recording in &RECORDINGS {
+        let algorithm = NewtonEquation::<_, Absolute>::new(params.clone(), model(recording));
+        check("newton", recording, algorithm.run(), CONVERGED_BOUND, true);
+    }
+}
+
+#[test]
+fn test_golden_gradient_descent() {
+    // The gradient of the model is in the order of 1e-4, so the learning
+    // rate must be large for the first step to leave the initial guess.
+    let params = GradientDescentParams {
+        concentration_init: 1e-2,
+        grad_tolerance: 1e-20,
+        learning_rate_init: 100.0,
+        max_iterations: 50,
+        saturation_policy: SaturationPolicy::Keep,
+        tolerance: 1e-15,
+    };
+    for recording in &RECORDINGS {
+        let algorithm =
+            GradientDescentEquation::<_, Absolute>::new(params.clone(), model(recording));
+        check(
+            "gradient descent",
+            recording,
+            algorithm.run(),
+            CONVERGED_BOUND,
+            true,
+        );
+    }
+}
+
+#[test]
+fn test_golden_neural_network() {
+    for recording in &RECORDINGS {
+        let algorithm = NeuralNetworkEquation::<_, Absolute, 0>::new(
+            NeuralNetworkParams::default(),
+            model(recording),
+        );
+        check(
+            "neural network 0",
+            recording,
+            algorithm.run(),
+            NEURAL_NETWORK_BOUND,
+            false,
+        );
+
+        let algorithm = NeuralNetworkEquation::<_, Absolute, 1>::new(
+            NeuralNetworkParams::default(),
+            model(recording),
+        );
+        check(
+            "neural network 1",
+            recording,
+            algorithm.run(),
+            NEURAL_NETWORK_BOUND,
+            false,
+        );
+    }
+}
+
+#[test]
+fn test_golden_condition() {
+    // The reference solutions are well-conditioned, so their error bounds
+    // are not dominated by the noise of the recordings.
+    for recording in &RECORDINGS {
+        let mut report = SolveReport::new();
+        report.record_condition(&MODEL_PARAMS, &recording.currents, &recording.solution);
+        assert!(
+            !report.diagnostics.is_ill_conditioned(),
+            "{}: condition number {:?}",
+            recording.name,
+            report.diagnostics.condition
+        );
+    }
+}
@@ -0,0 +1,16 @@
+/* Memory mapping for nRF52840 chip, without SoftDevice */
+MEMORY
+{
+  /* NOTE K = KiBi = 1024 bytes */
+  FLASH : ORIGIN = 0x00000000, LENGTH = 1024K
+  RAM   : ORIGIN = 0x20000000, LENGTH = 256K
+}
+
+/* This is where the call stack will be allocated. */
+/* The stack is of the full descending type. */
+/* You may want to use this variable to locate the call stack and static
+   variables in different memory regions. Below is shown the default value */
+_stack_start = ORIGIN(RAM) + LENGTH(RAM);
+
+/* Size of the heap (in bytes) */
+/* _heap_size = 1024; */
@@ -0,0 +1,174 @@
+//! Acquisition with the SAADC of the nRF52840 and Adaptive2 solver.
+//!
+//! The drain-source current is converted on AIN0 (P0.02) and the gate-source
+//! current on AIN1 (P0.03), while the gate is switched through P0.28. LED 1
+//! is lit while the device is busy, so the duty cycle of a battery-powered
+//! node can be checked with a scope.
+
+#![no_main]
+#![no_std]
+
+use defmt_rtt as _; // global logger
+use panic_probe as _; // panic handler
+
+use embedded_hal::{delay::DelayNs, digital::OutputPin};
+use nrf52840_hal::{
+    self as hal,
+    gpio::{
+        p0::{self, P0_02, P0_03},
+        Floating, Input, Level,
+    },
+    pac,
+    saadc::{Saadc, SaadcConfig},
+};
+
+use bioristor_lib::{
+    acquisition::{
+        AveragingSampler, Channel, ChannelCalibration, CurrentSource, GateDriver, PinGate,
+        SamplerParams,
+    },
+    algorithms::{Adaptive2Equation, Adaptive2Params, Algorithm, CpuClass},
+    losses::Absolute,
+    models::{Equation, Model},
+    params::{ModelParams, ModulationParams, StemResistanceInvParams, Voltages},
+};
+use profiler::{cycles_to_us, Profiler, StackProfiler};
+
+const ALG_PARAMS: Adaptive2Params = CpuClass::CortexM4.adaptive2_params();
+
+const MODEL_PARAMS: ModelParams = ModelParams {
+    mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    r_dry: 38.2,
+    res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    voltages: Voltages {
+        v_ds: -0.05,
+        v_gs: 0.5,
+    },
+};
+
+const SAMPLER_PARAMS: SamplerParams = SamplerParams {
+    drain_source: ChannelCalibration {
+        gain: -1e-6,
+        offset: 0.0,
+    },
+    gate_source: ChannelCalibration {
+        gain: 1e-9,
+        offset: 0.0,
+    },
+};
+
+/// The nRF52840 always runs at 64 MHz.
+const CORE_FREQ: u32 = 64_000_000;
+
+/// Number of conversions averaged for every sample.
+const OVERSAMPLING: usize = 16;
+
+/// Time for the currents to settle after the gate has been switched.
+const SETTLE_MS: u32 = 10;
+
+/// Time between two measurements.
+const PERIOD_MS: u32 = 5_000;
+
+/// Current source based on the SAADC, in single-ended mode.
+struct SaadcSource {
+    saadc: Saadc,
+    i_ds_pin: P0_02<Input<Floating>>,
+    i_gs_pin: P0_03<Input<Floating>>,
+}
+
+impl CurrentSource for SaadcSource {
+    type Error = ();
+
+    fn read(&mut self, channel: Channel) -> Result<u16, Self::Error> {
+        let raw = match channel {
+            Channel::DrainSource => self.saadc.read_channel(&mut self.i_ds_pin)?,
+            Channel::GateSource => self.saadc.read_channel(&mut self.i_gs_pin)?,
+        };
+        // Single-ended conversions can be slightly negative because of the
+        // offset of the amplifier.
+        Ok(raw.max(0) as u16)
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    // Retrieve core and device peripherals.
+    let cp = pac::CorePeripherals::take().unwrap();
+    let dp = pac::Peripherals::take().unwrap();
+
+    // The external crystal gives a more accurate time base to the profiler.
+    let _clocks = hal::Clocks::new(dp.CLOCK).enable_ext_hfosc();
+
+    // The profiler owns SysTick and is also used for delays.
+    let mut profiler = Profiler::new(cp.SYST, CORE_FREQ);
+    profiler.calibrate_overhead();
+
+    defmt::info!("Bioristor application");
+
+    let port0 = p0::Parts::new(dp.P0);
+    let mut led = port0.p0_13.into_push_pull_output(Level::High);
+    let mut gate = PinGate::new(port0.p0_28.into_push_pull_output(Level::Low));
+
+    let source = SaadcSource {
+        saadc: Saadc::new(dp.SAADC, SaadcConfig::default()),
+        i_ds_pin: port0.p0_02.into_floating_input(),
+        i_gs_pin: port0.p0_03.into_floating_input(),
+    };
+    let mut sampler: AveragingSampler<_, OVERSAMPLING> =
+        AveragingSampler::new(SAMPLER_PARAMS, source);
+
+    // SAFETY: no heap is used, so the memory below the stack is free.
+    let stack = unsafe { StackProfiler::new() };
+
+    loop {
+        // LED 1 is active low.
+        led.set_low().ok();
+
+        // The profiler is also used to wait for the currents to settle, so
+        // the acquisition is timed by hand.
+        let start = profiler.cycles();
+        let currents = sampler.currents(|on| {
+            gate.set_gate(on).ok();
+            profiler.delay_ms(SETTLE_MS);
+        });
+        let acquisition = profiler.cycles() - start;
+        let currents = match currents {
+            Ok(currents) => currents,
+            Err(()) => {
+                defmt::warn!("Acquisition failed");
+                led.set_high().ok();
+                profiler.delay_ms(PERIOD_MS);
+                continue;
+            }
+        };
+        defmt::debug!("{}", currents);
+
+        let model = Equation::new(MODEL_PARAMS, currents);
+        let algorithm: Adaptive2Equation<_, Absolute, 10> =
+            Adaptive2Equation::new(ALG_PARAMS, model);
+        let (res, cycles) = profiler.measure(|| algorithm.run());
+
+        led.set_high().ok();
+
+        match res {
+            Some((variables, error)) => {
+                defmt::info!("Solution found: {}, error: {}", variables, error);
+            }
+            None => {
+                defmt::warn!("No solution found");
+            }
+        }
+        defmt::info!(
+            "Acquisition took {} us, solver {} us",
+            cycles_to_us::<CORE_FREQ>(acquisition),
+            cycles_to_us::<CORE_FREQ>(cycles)
+        );
+        defmt::info!(
+            "Stack usage: {} of {} bytes",
+            stack.high_water_mark(),
+            stack.size()
+        );
+
+        profiler.delay_ms(PERIOD_MS);
+    }
+}
@@ -0,0 +1,230 @@
+//! Comparison of the algorithms on the same currents.
+//!
+//! Every algorithm of the library solves the model for the same `Currents`.
+//! The example measures the execution time with the profiler over
+//! `BENCH_RUNS` runs, together with the number of evaluations of the model
+//! and the relative error of the concentration with respect to a fine
+//! brute-force search, which is taken as the reference. The results are
+//! printed as a table over defmt, one row per algorithm, ready to be copied
+//! into a paper or a bring-up report.
+
+#![no_main]
+#![no_std]
+
+use defmt_rtt as _; // global logger
+use panic_probe as _; // panic handler
+
+use stm32f7xx_hal::{pac, prelude::*};
+
+use bioristor_lib::{
+    algorithms::{
+        Adaptive2Equation, Adaptive2Params, AdaptiveEquation, AdaptiveParams, AdaptiveSystem,
+        Algorithm, BruteForceEquation, BruteForceParams, GradientDescentEquation,
+        GradientDescentParams, NeuralNetworkEquation, NeuralNetworkParams, NewtonEquation,
+        NewtonParams, SaturationPolicy, SolveReport,
+    },
+    losses::{Absolute, MeanRelative},
+    models::{Equation, Model, System},
+    params::{Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages},
+    utils::FloatRange,
+};
+use profiler::{bench, cycles_to_us, Profiler};
+
+const REFERENCE_PARAMS: BruteForceParams = BruteForceParams {
+    concentration_range: FloatRange::new(1e-4, 1e-1, 100_000),
+    resistance_range: FloatRange::new(10.0, 100.0, 100),
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 100),
+};
+
+const BRUTE_FORCE_PARAMS: BruteForceParams = BruteForceParams {
+    concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+    resistance_range: FloatRange::new(10.0, 100.0, 100),
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 100),
+};
+
+const ADAPTIVE_PARAMS: AdaptiveParams = AdaptiveParams {
+    concentration_init: 1e-2,
+    concentration_steps: 100,
+    max_iterations: 10,
+    resistance_range: FloatRange::new(10.0, 100.0, 20),
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 20),
+};
+
+const ADAPTIVE2_PARAMS: Adaptive2Params = Adaptive2Params {
+    concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+    max_iterations: 10,
+    min_range_width: 0.0,
+    reduction_factor: 0.2,
+    resistance_range: FloatRange::new(10.0, 100.0, 100),
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 100),
+    tolerance: 1e-15,
+};
+
+const GRADIENT_DESCENT_PARAMS: GradientDescentParams = GradientDescentParams {
+    concentration_init: 1e-2,
+    grad_tolerance: 1e-20,
+    learning_rate_init: 100.0,
+    max_iterations: 50,
+    saturation_policy: SaturationPolicy::Keep,
+    tolerance: 1e-15,
+};
+
+const NEWTON_PARAMS: NewtonParams = NewtonParams {
+    concentration_init: 1e-2,
+    grad_tolerance: 1e-9,
+    max_iterations: 10,
+    saturation_policy: SaturationPolicy::Keep,
+    tolerance: 1e-15,
+};
+
+const MODEL_PARAMS: ModelParams = ModelParams {
+    mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    r_dry: 38.2,
+    res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    voltages: Voltages {
+        v_ds: -0.05,
+        v_gs: 0.5,
+    },
+};
+
+const CORE_FREQ: u32 = 216_000_000;
+
+/// Number of runs used to benchmark every algorithm.
+const BENCH_RUNS: usize = 10;
+
+/// Runs the algorithm once to collect its solution and report, then
+/// benchmarks it and prints a row of the comparison table.
+///
+/// # Arguments
+///
+/// * `profiler` - The profiler used to measure the execution time.
+/// * `name` - The name of the algorithm in the table.
+/// * `algorithm` - The algorithm to compare.
+/// * `reference` - The reference concentration.
+fn compare<P, M: Model, A: Algorithm<P, M>>(
+    profiler: &Profiler,
+    name: &str,
+    algorithm: A,
+    reference: f32,
+) {
+    let mut report = SolveReport::new();
+    let solution = algorithm.run_with(&mut report);
+
+    let stats = bench::<BENCH_RUNS>(profiler, || {
+        core::hint::black_box(algorithm.run());
+    });
+
+    match solution {
+        Some((variables, loss)) => defmt::info!(
+            "| {=str} | {} | {} | {} | {} | {} | {} |",
+            name,
+            stats.median,
+            cycles_to_us::<CORE_FREQ>(stats.median),
+            report.evaluations,
+            variables.concentration,
+            (variables.concentration - reference).abs() / reference,
+            loss
+        ),
+        None => defmt::info!(
+            "| {=str} | {} | {} | {} | - | - | - |",
+            name,
+            stats.median,
+            cycles_to_us::<CORE_FREQ>(stats.median),
+            report.evaluations
+        ),
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    // Retrieve core and device peripherals.
+    let cp: pac::CorePeripherals = pac::CorePeripherals::take().unwrap();
+    let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+
+    let rcc = dp.RCC.constrain();
+
+    // Configure clocks.
+    rcc.cfgr.sysclk(CORE_FREQ.Hz()).freeze();
+
+    let mut profiler = Profiler::new(cp.SYST, CORE_FREQ);
+    profiler.calibrate_overhead();
+
+    defmt::info!("Bioristor algorithm comparison");
+
+    let currents = core::hint::black_box(Currents {
+        i_ds_on: -0.0026829,
+        i_ds_off: -0.0030365,
+        i_gs_on: 1.169828e-6,
+    });
+    defmt::debug!("{}", currents);
+
+    let equation = || Equation::new(MODEL_PARAMS, currents);
+
+    // The fine brute-force search is the reference of the accuracy.
+    let reference = BruteForceEquation::<_, Absolute>::new(REFERENCE_PARAMS, equation())
+        .run()
+        .map(|(variables, _)| variables.concentration);
+    let Some(reference) = reference else {
+        defmt::panic!("The reference algorithm found no solution");
+    };
+    defmt::info!("Reference concentration: {}", reference);
+
+    defmt::info!(
+        "| algorithm | cycles | us | evaluations | concentration | relative error | loss |"
+    );
+    compare(
+        &profiler,
+        "brute force",
+        BruteForceEquation::<_, Absolute>::new(BRUTE_FORCE_PARAMS, equation()),
+        reference,
+    );
+    compare(
+        &profiler,
+        "adaptive",
+        AdaptiveEquation::<_, Absolute, 10>::new(ADAPTIVE_PARAMS, equation()),
+        reference,
+    );
+    compare(
+        &profiler,
+        "adaptive v2",
+        Adaptive2Equation::<_, Absolute, 10>::new(ADAPTIVE2_PARAMS, equation()),
+        reference,
+    );
+    compare(
+        &profiler,
+        "gradient descent",
+        GradientDescentEquation::<_, Absolute>::new(GRADIENT_DESCENT_PARAMS, equation()),
+        reference,
+    );
+    compare(
+        &profiler,
+        "newton",
+        NewtonEquation::<_, Absolute>::new(NEWTON_PARAMS, equation()),
+        reference,
+    );
+    compare(
+        &profiler,
+        "neural network",
+        NeuralNetworkEquation::<_, Absolute, 0>::new(NeuralNetworkParams::default(), equation()),
+        reference,
+    );
+    compare(
+        &profiler,
+        "adaptive system",
+        AdaptiveSystem::<_, MeanRelative, 10>::new(
+            ADAPTIVE_PARAMS,
+            System::new(MODEL_PARAMS, currents),
+        ),
+        reference,
+    );
+
+    defmt::info!("Comparison completed");
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
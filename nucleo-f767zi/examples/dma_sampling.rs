@@ -0,0 +1,224 @@
+//! Double-buffered acquisition of the current waveforms with ADC1 and DMA2,
+//! overlapping the sampling of the next gate pulse with the solver.
+//!
+//! The drain-source current is sampled on PA3 (A0) and the gate-source
+//! current on PC0 (A1), interleaved by the scan sequence of the ADC. The gate
+//! is switched on by a timer synchronized with the start of every transfer
+//! (not shown), `GATE_ON` samples after the beginning of the buffer.
+
+#![no_main]
+#![no_std]
+
+use defmt_rtt as _; // global logger
+use panic_probe as _; // panic handler
+
+use stm32f7xx_hal::{pac, prelude::*};
+
+use bioristor_lib::{
+    acquisition::{deinterleave, ChannelCalibration, DmaTransfer, DoubleBuffer, SamplerParams},
+    algorithms::{Adaptive2Equation, Adaptive2Params, Algorithm, SaturationPolicy},
+    features::{extract_currents, FeatureParams},
+    losses::Absolute,
+    models::{Equation, Model},
+    params::{ModelParams, ModulationParams, StemResistanceInvParams, Voltages},
+    utils::FloatRange,
+};
+
+const ALG_PARAMS: Adaptive2Params = Adaptive2Params {
+    concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+    max_iterations: 10,
+    min_range_width: 0.0,
+    reduction_factor: 0.2,
+    resistance_range: FloatRange::new(10.0, 100.0, 100),
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 100),
+    tolerance: 1e-15,
+};
+
+const MODEL_PARAMS: ModelParams = ModelParams {
+    mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    r_dry: 38.2,
+    res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    voltages: Voltages {
+        v_ds: -0.05,
+        v_gs: 0.5,
+    },
+};
+
+const FEATURE_PARAMS: FeatureParams = FeatureParams {
+    min_samples: 16,
+    outlier_threshold: 3.0,
+    settle_tolerance: 0.02,
+    settle_window: 4,
+};
+
+const SAMPLER_PARAMS: SamplerParams = SamplerParams {
+    drain_source: ChannelCalibration {
+        gain: -1e-6,
+        offset: 0.0,
+    },
+    gate_source: ChannelCalibration {
+        gain: 1e-9,
+        offset: 0.0,
+    },
+};
+
+const CORE_FREQ: u32 = 216_000_000;
+
+/// Number of samples of every waveform.
+const SAMPLES: usize = 256;
+
+/// Index of the first sample after the gate has been switched on.
+const GATE_ON: usize = SAMPLES / 2;
+
+/// Buffer of the interleaved conversions of the two channels.
+type Buffer = &'static mut [u16; 2 * SAMPLES];
+
+/// Transfer of the conversions of ADC1 through stream 0 of DMA2.
+struct AdcDma {
+    adc: pac::ADC1,
+    dma: pac::DMA2,
+    active: Option<Buffer>,
+}
+
+impl AdcDma {
+    fn new(adc: pac::ADC1, dma: pac::DMA2) -> Self {
+        // Scan PA3 (IN3) then PC0 (IN10) in continuous mode, with DMA
+        // requests issued after every conversion.
+        adc.smpr2.write(|w| unsafe { w.smp3().bits(0b011) });
+        adc.smpr1.write(|w| unsafe { w.smp10().bits(0b011) });
+        adc.sqr1.write(|w| w.l().bits(1));
+        adc.sqr3
+            .write(|w| unsafe { w.sq1().bits(3).sq2().bits(10) });
+        adc.cr1.write(|w| w.scan().set_bit());
+        adc.cr2.write(|w| {
+            w.cont()
+                .set_bit()
+                .dma()
+                .set_bit()
+                .dds()
+                .set_bit()
+                .adon()
+                .set_bit()
+        });
+
+        Self {
+            adc,
+            dma,
+            active: None,
+        }
+    }
+}
+
+impl DmaTransfer<Buffer> for AdcDma {
+    type Error = ();
+
+    fn start(&mut self, buffer: Buffer) {
+        let stream = &self.dma.st[0];
+        stream.cr.modify(|_, w| w.en().clear_bit());
+        while stream.cr.read().en().bit_is_set() {}
+        self.dma
+            .lifcr
+            .write(|w| w.ctcif0().set_bit().cteif0().set_bit());
+
+        stream
+            .par
+            .write(|w| unsafe { w.pa().bits(self.adc.dr.as_ptr() as u32) });
+        stream
+            .m0ar
+            .write(|w| unsafe { w.m0a().bits(buffer.as_mut_ptr() as u32) });
+        stream.ndtr.write(|w| w.ndt().bits(buffer.len() as u16));
+        stream.cr.write(|w| unsafe {
+            w.chsel()
+                .bits(0)
+                .msize()
+                .bits16()
+                .psize()
+                .bits16()
+                .minc()
+                .set_bit()
+                .dir()
+                .peripheral_to_memory()
+                .en()
+                .set_bit()
+        });
+        self.active = Some(buffer);
+
+        // Restart the scan sequence from the first channel.
+        self.adc.sr.modify(|_, w| w.ovr().clear_bit());
+        self.adc.cr2.modify(|_, w| w.swstart().set_bit());
+    }
+
+    fn poll(&mut self) -> Option<(Buffer, Result<(), ()>)> {
+        let status = self.dma.lisr.read();
+        if status.teif0().bit_is_set() {
+            return self.active.take().map(|buffer| (buffer, Err(())));
+        }
+        if status.tcif0().bit_is_set() {
+            return self.active.take().map(|buffer| (buffer, Ok(())));
+        }
+        None
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+
+    let rcc = dp.RCC.constrain();
+    rcc.cfgr.sysclk(CORE_FREQ.Hz()).freeze();
+
+    // SAFETY: the clocks of ADC1 and DMA2 are not used by the HAL.
+    unsafe {
+        let rcc = &*pac::RCC::ptr();
+        rcc.apb2enr.modify(|_, w| w.adc1en().set_bit());
+        rcc.ahb1enr.modify(|_, w| w.dma2en().set_bit());
+    }
+
+    let gpioa = dp.GPIOA.split();
+    let gpioc = dp.GPIOC.split();
+    let _i_ds_pin = gpioa.pa3.into_analog();
+    let _i_gs_pin = gpioc.pc0.into_analog();
+
+    let first = cortex_m::singleton!(: [u16; 2 * SAMPLES] = [0; 2 * SAMPLES]).unwrap();
+    let second = cortex_m::singleton!(: [u16; 2 * SAMPLES] = [0; 2 * SAMPLES]).unwrap();
+    let mut sampler = DoubleBuffer::new(AdcDma::new(dp.ADC1, dp.DMA2), first, second);
+
+    defmt::info!("Bioristor DMA sampling");
+
+    let mut i_ds = [0.0; SAMPLES];
+    let mut i_gs = [0.0; SAMPLES];
+    loop {
+        if !sampler.poll() {
+            continue;
+        }
+
+        // The next waveform is acquired while the solver runs.
+        let currents = sampler.read(|raw| {
+            deinterleave(&SAMPLER_PARAMS, raw, &mut i_ds, &mut i_gs);
+            extract_currents(&FEATURE_PARAMS, &i_ds, &i_gs, GATE_ON)
+        });
+
+        match currents {
+            Some(Ok(currents)) => {
+                let model = Equation::new(MODEL_PARAMS, currents);
+                let algorithm: Adaptive2Equation<_, Absolute, 10> =
+                    Adaptive2Equation::new(ALG_PARAMS, model);
+                match algorithm.run() {
+                    Some((variables, error)) => {
+                        defmt::info!("Solution found: {}, error: {}", variables, error);
+                    }
+                    None => defmt::warn!("No solution found"),
+                }
+            }
+            Some(Err(error)) => defmt::warn!("Invalid waveform: {}", error),
+            None => {}
+        }
+
+        defmt::debug!(
+            "Overruns: {}, errors: {}",
+            sampler.overruns(),
+            sampler.errors()
+        );
+    }
+}
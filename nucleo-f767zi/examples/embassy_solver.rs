@@ -0,0 +1,139 @@
+//! Solver sharing a single-core embassy executor with another task.
+//!
+//! The solver task runs the algorithm with `run_async`, yielding every
+//! `YIELD_EVERY` evaluations of the model, while the heartbeat task blinks the
+//! green LED and keeps track of the longest time it has been kept waiting,
+//! standing in for the radio and sensor tasks of a deployed device.
+
+#![no_main]
+#![no_std]
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use defmt_rtt as _; // global logger
+use panic_probe as _; // panic handler
+
+use cortex_m::peripheral::DWT;
+use embassy_executor::Spawner;
+use stm32f7xx_hal::{
+    gpio::{Output, Pin, PushPull},
+    pac,
+    prelude::*,
+};
+
+use bioristor_lib::{
+    algorithms::{
+        yield_now, Adaptive2Equation, Adaptive2Params, Algorithm, SaturationPolicy, SolveReport,
+    },
+    losses::Absolute,
+    models::{Equation, Model},
+    params::{Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages},
+    utils::FloatRange,
+};
+
+const ALG_PARAMS: Adaptive2Params = Adaptive2Params {
+    concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+    max_iterations: 10,
+    min_range_width: 0.0,
+    reduction_factor: 0.2,
+    resistance_range: FloatRange::new(10.0, 100.0, 100),
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 100),
+    tolerance: 1e-15,
+};
+
+const MODEL_PARAMS: ModelParams = ModelParams {
+    mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    r_dry: 38.2,
+    res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    voltages: Voltages {
+        v_ds: -0.05,
+        v_gs: 0.5,
+    },
+};
+
+const CORE_FREQ: u32 = 216_000_000;
+
+/// Number of evaluations of the model between two yields of the solver.
+const YIELD_EVERY: usize = 100;
+
+/// Period of the heartbeat, in CPU cycles.
+const HEARTBEAT_PERIOD: u32 = CORE_FREQ / 10;
+
+/// Longest time the heartbeat task has been kept waiting, in CPU cycles.
+static MAX_LATENCY: AtomicU32 = AtomicU32::new(0);
+
+#[embassy_executor::task]
+async fn heartbeat(mut led: Pin<'B', 0, Output<PushPull>>) {
+    let mut last_poll = DWT::cycle_count();
+    let mut last_toggle = last_poll;
+    loop {
+        let now = DWT::cycle_count();
+        MAX_LATENCY.fetch_max(now.wrapping_sub(last_poll), Ordering::Relaxed);
+        last_poll = now;
+
+        if now.wrapping_sub(last_toggle) >= HEARTBEAT_PERIOD {
+            led.toggle();
+            last_toggle = now;
+        }
+
+        yield_now().await;
+    }
+}
+
+#[embassy_executor::task]
+async fn solver(currents: Currents) {
+    let model = Equation::new(MODEL_PARAMS, currents);
+    let algorithm: Adaptive2Equation<_, Absolute, 10> = Adaptive2Equation::new(ALG_PARAMS, model);
+
+    loop {
+        MAX_LATENCY.store(0, Ordering::Relaxed);
+
+        let mut report = SolveReport::new();
+        let start = DWT::cycle_count();
+        let res = algorithm.run_async(&mut report, YIELD_EVERY).await;
+        let cycles = DWT::cycle_count().wrapping_sub(start);
+
+        match res {
+            Some((variables, error)) => {
+                defmt::info!("Solution found: {}, error: {}", variables, error);
+            }
+            None => {
+                defmt::warn!("No solution found");
+            }
+        }
+        defmt::info!(
+            "{} evaluations in {} CPU cycles, heartbeat kept waiting at most {} CPU cycles",
+            report.evaluations,
+            cycles,
+            MAX_LATENCY.load(Ordering::Relaxed)
+        );
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let mut cp = cortex_m::Peripherals::take().unwrap();
+    let dp = pac::Peripherals::take().unwrap();
+
+    let rcc = dp.RCC.constrain();
+    rcc.cfgr.sysclk(CORE_FREQ.Hz()).freeze();
+
+    // The cycle counter times the tasks without taking SysTick.
+    cp.DCB.enable_trace();
+    cp.DWT.enable_cycle_counter();
+
+    defmt::info!("Bioristor embassy solver");
+
+    let gpiob = dp.GPIOB.split();
+    let green_led = gpiob.pb0.into_push_pull_output();
+
+    let currents = Currents {
+        i_ds_on: -0.0026829,
+        i_ds_off: -0.0030365,
+        i_gs_on: 1.169828e-6,
+    };
+
+    spawner.spawn(heartbeat(green_led)).unwrap();
+    spawner.spawn(solver(currents)).unwrap();
+}
@@ -0,0 +1,258 @@
+//! RTIC 2 application: acquisition in a hardware task, solver in a
+//! low-priority software task.
+//!
+//! Every second, the `trigger` task starts the sampling of the gate pulse;
+//! the `conversion` hardware task collects the interleaved conversions of
+//! PA3 (A0) and PC0 (A1), extracts the currents and hands them to the
+//! `solver` task, which runs the algorithm with `run_async` and publishes the
+//! best solution in the `latest` shared resource. Acquisition preempts the solver at any
+//! time, and the solver yields every `YIELD_EVERY` evaluations of the model
+//! to the `report` task, which runs at the same priority.
+//!
+//! The profiler owns SysTick and is shared through a `StaticProfiler`, whose
+//! counter can be read from every priority level; the monotonic of the
+//! scheduler is based on TIM2, so that it does not interfere with it.
+
+#![no_main]
+#![no_std]
+
+use defmt_rtt as _; // global logger
+use panic_probe as _; // panic handler
+
+use profiler::StaticProfiler;
+
+/// The profiler shared by all the tasks.
+static PROFILER: StaticProfiler = StaticProfiler::new();
+
+rtic_monotonics::stm32_tim2_monotonic!(Mono, 1_000_000);
+
+#[rtic::app(device = stm32f7xx_hal::pac, dispatchers = [USART1, USART2])]
+mod app {
+    use rtic_monotonics::{fugit::ExtU32, Monotonic};
+    use rtic_sync::{
+        channel::{Receiver, Sender},
+        make_channel,
+    };
+    use stm32f7xx_hal::{pac, prelude::*};
+
+    use bioristor_lib::{
+        acquisition::{deinterleave, ChannelCalibration, SamplerParams},
+        algorithms::{
+            Adaptive2Equation, Adaptive2Params, Algorithm, SaturationPolicy, SolveReport,
+        },
+        features::{extract_currents, FeatureParams},
+        losses::Absolute,
+        models::{Equation, Model},
+        params::{
+            Currents, ModelParams, ModulationParams, StemResistanceInvParams, Variables, Voltages,
+        },
+        utils::FloatRange,
+    };
+    use profiler::{cycles_to_us, Profiler};
+
+    use super::{Mono, PROFILER};
+
+    const ALG_PARAMS: Adaptive2Params = Adaptive2Params {
+        concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+        max_iterations: 10,
+        min_range_width: 0.0,
+        reduction_factor: 0.2,
+        resistance_range: FloatRange::new(10.0, 100.0, 100),
+        saturation_policy: SaturationPolicy::Keep,
+        saturation_range: FloatRange::new(0.0, 1.0, 100),
+        tolerance: 1e-15,
+    };
+
+    const MODEL_PARAMS: ModelParams = ModelParams {
+        mod_params: ModulationParams(0.0, -0.01463, -0.32),
+        r_dry: 38.2,
+        res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+        voltages: Voltages {
+            v_ds: -0.05,
+            v_gs: 0.5,
+        },
+    };
+
+    const FEATURE_PARAMS: FeatureParams = FeatureParams {
+        min_samples: 16,
+        outlier_threshold: 3.0,
+        settle_tolerance: 0.02,
+        settle_window: 4,
+    };
+
+    const SAMPLER_PARAMS: SamplerParams = SamplerParams {
+        drain_source: ChannelCalibration {
+            gain: -1e-6,
+            offset: 0.0,
+        },
+        gate_source: ChannelCalibration {
+            gain: 1e-9,
+            offset: 0.0,
+        },
+    };
+
+    const CORE_FREQ: u32 = 216_000_000;
+
+    /// Frequency of the clock of TIM2, on APB1 with a prescaler of 4.
+    const TIM2_FREQ: u32 = CORE_FREQ / 2;
+
+    /// Number of samples of every waveform.
+    const SAMPLES: usize = 256;
+
+    /// Index of the first sample after the gate has been switched on.
+    const GATE_ON: usize = SAMPLES / 2;
+
+    /// Number of evaluations of the model between two yields of the solver.
+    const YIELD_EVERY: usize = 100;
+
+    #[shared]
+    struct Shared {
+        /// The best solution of the last measurement.
+        latest: Option<(Variables, f32)>,
+    }
+
+    #[local]
+    struct Local {
+        adc: pac::ADC1,
+        index: usize,
+        raw: [u16; 2 * SAMPLES],
+        sender: Sender<'static, Currents, 1>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        let dp = cx.device;
+
+        let rcc = dp.RCC.constrain();
+        rcc.cfgr.sysclk(CORE_FREQ.Hz()).freeze();
+
+        // SAFETY: the clock of ADC1 is not used by the HAL.
+        unsafe {
+            let rcc = &*pac::RCC::ptr();
+            rcc.apb2enr.modify(|_, w| w.adc1en().set_bit());
+        }
+
+        let mut profiler = Profiler::new(cx.core.SYST, CORE_FREQ);
+        profiler.calibrate_overhead();
+        PROFILER.init(profiler).ok().unwrap();
+        Mono::start(TIM2_FREQ);
+
+        let gpioa = dp.GPIOA.split();
+        let gpioc = dp.GPIOC.split();
+        let _i_ds_pin = gpioa.pa3.into_analog();
+        let _i_gs_pin = gpioc.pc0.into_analog();
+
+        // Scan PA3 (IN3) then PC0 (IN10), with an interrupt at the end of
+        // every conversion.
+        let adc = dp.ADC1;
+        adc.smpr2.write(|w| unsafe { w.smp3().bits(0b011) });
+        adc.smpr1.write(|w| unsafe { w.smp10().bits(0b011) });
+        adc.sqr1.write(|w| w.l().bits(1));
+        adc.sqr3
+            .write(|w| unsafe { w.sq1().bits(3).sq2().bits(10) });
+        adc.cr1.write(|w| w.scan().set_bit().eocie().set_bit());
+        adc.cr2.write(|w| w.eocs().set_bit().adon().set_bit());
+
+        defmt::info!("Bioristor RTIC solver");
+
+        let (sender, receiver) = make_channel!(Currents, 1);
+        trigger::spawn().ok();
+        solver::spawn(receiver).ok();
+        report::spawn().ok();
+
+        (
+            Shared { latest: None },
+            Local {
+                adc,
+                index: 0,
+                raw: [0; 2 * SAMPLES],
+                sender,
+            },
+        )
+    }
+
+    /// Starts the acquisition of a waveform every second.
+    #[task(priority = 2)]
+    async fn trigger(_cx: trigger::Context) {
+        loop {
+            // SAFETY: only the start bit is written, the ADC is owned by the
+            // `conversion` task, which is idle between two acquisitions.
+            unsafe { (*pac::ADC1::ptr()).cr2.modify(|_, w| w.swstart().set_bit()) };
+            Mono::delay(1.secs()).await;
+        }
+    }
+
+    /// Collects the conversions and hands the currents to the solver.
+    #[task(binds = ADC, priority = 3, local = [adc, index, raw, sender])]
+    fn conversion(cx: conversion::Context) {
+        let conversion::LocalResources {
+            adc,
+            index,
+            raw,
+            sender,
+            ..
+        } = cx.local;
+
+        raw[*index] = adc.dr.read().data().bits();
+        *index += 1;
+
+        if *index < raw.len() {
+            // Start the next scan after both channels have been converted.
+            if *index % 2 == 0 {
+                adc.cr2.modify(|_, w| w.swstart().set_bit());
+            }
+            return;
+        }
+        *index = 0;
+
+        let mut i_ds = [0.0; SAMPLES];
+        let mut i_gs = [0.0; SAMPLES];
+        deinterleave(&SAMPLER_PARAMS, &raw[..], &mut i_ds, &mut i_gs);
+        match extract_currents(&FEATURE_PARAMS, &i_ds, &i_gs, GATE_ON) {
+            // The measurement is dropped if the solver is still busy.
+            Ok(currents) => {
+                if sender.try_send(currents).is_err() {
+                    defmt::warn!("Solver busy, measurement dropped");
+                }
+            }
+            Err(err) => defmt::warn!("Acquisition failed: {}", err),
+        }
+    }
+
+    /// Solves the model for every measurement.
+    #[task(priority = 1, shared = [latest])]
+    async fn solver(mut cx: solver::Context, mut receiver: Receiver<'static, Currents, 1>) {
+        while let Ok(currents) = receiver.recv().await {
+            let model = Equation::new(MODEL_PARAMS, currents);
+            let algorithm: Adaptive2Equation<_, Absolute, 10> =
+                Adaptive2Equation::new(ALG_PARAMS, model);
+
+            let mut report = SolveReport::new();
+            let start = PROFILER.cycles().unwrap_or_default();
+            let res = algorithm.run_async(&mut report, YIELD_EVERY).await;
+            let cycles = PROFILER.cycles().unwrap_or_default() - start;
+            defmt::debug!(
+                "{} evaluations in {} us",
+                report.evaluations,
+                cycles_to_us::<CORE_FREQ>(cycles)
+            );
+
+            // Hand the solution off to the other tasks.
+            cx.shared.latest.lock(|latest| *latest = res);
+        }
+    }
+
+    /// Reports the latest solution every five seconds.
+    #[task(priority = 1, shared = [latest])]
+    async fn report(mut cx: report::Context) {
+        loop {
+            Mono::delay(5.secs()).await;
+            match cx.shared.latest.lock(|latest| *latest) {
+                Some((variables, error)) => {
+                    defmt::info!("Latest solution: {}, error: {}", variables, error);
+                }
+                None => defmt::warn!("No solution found"),
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Push of the solutions to a collector on the LAN over the on-board
+//! Ethernet port, as UDP datagrams.
+//!
+//! Every `MEASUREMENT_PERIOD_MS` the solution is sent as a telemetry frame to
+//! `COLLECTOR`, and a heartbeat every `HEARTBEAT_PERIOD_S`, so the gateway can
+//! tell a silent node from a dead one. The address of the board is static,
+//! and the device has no real-time clock, so the timestamps are the seconds
+//! since boot.
+
+#![no_main]
+#![no_std]
+
+use defmt_rtt as _; // global logger
+use panic_probe as _; // panic handler
+
+use smoltcp::{
+    iface::{Config, Interface, SocketSet, SocketStorage},
+    socket::udp,
+    time::{Duration, Instant},
+    wire::{EthernetAddress, IpAddress, IpCidr, IpEndpoint},
+};
+use stm32_eth::{
+    dma::{RxRingEntry, TxRingEntry},
+    EthPins, Parts, PartsIn,
+};
+use stm32f7xx_hal::{
+    pac,
+    prelude::*,
+    rcc::{HSEClock, HSEClockMode},
+};
+
+use bioristor_lib::{
+    algorithms::{Adaptive2Equation, Adaptive2Params, Algorithm, SaturationPolicy},
+    losses::Absolute,
+    models::{Equation, Model},
+    params::{Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages},
+    record::MeasurementRecord,
+    telemetry::{ErrorCode, Message, UdpSink},
+    utils::FloatRange,
+};
+use profiler::{cycles_to_ms_u64, Profiler};
+
+const ALG_PARAMS: Adaptive2Params = Adaptive2Params {
+    concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+    max_iterations: 10,
+    min_range_width: 0.0,
+    reduction_factor: 0.2,
+    resistance_range: FloatRange::new(10.0, 100.0, 100),
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 100),
+    tolerance: 1e-15,
+};
+
+const MODEL_PARAMS: ModelParams = ModelParams {
+    mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    r_dry: 38.2,
+    res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    voltages: Voltages {
+        v_ds: -0.05,
+        v_gs: 0.5,
+    },
+};
+
+const CORE_FREQ: u32 = 216_000_000;
+
+/// Frequency of the MCO output of the ST-LINK, used as external clock.
+const HSE_FREQ: u32 = 8_000_000;
+
+/// Locally administered MAC address of the board.
+const MAC: [u8; 6] = [0x02, 0x00, 0x05, 0x06, 0x07, 0x08];
+
+/// Static address of the board.
+const ADDRESS: IpAddress = IpAddress::v4(192, 168, 1, 100);
+
+/// Endpoint of the collector of the gateway.
+const COLLECTOR: IpEndpoint = IpEndpoint::new(IpAddress::v4(192, 168, 1, 1), 4242);
+
+/// Local port of the telemetry socket.
+const LOCAL_PORT: u16 = 4242;
+
+/// Time between two measurements.
+const MEASUREMENT_PERIOD_MS: u64 = 10_000;
+
+/// Time between two heartbeats.
+const HEARTBEAT_PERIOD_S: u64 = 60;
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    // Retrieve core and device peripherals.
+    let cp: pac::CorePeripherals = pac::CorePeripherals::take().unwrap();
+    let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+
+    let rcc = dp.RCC.constrain();
+    let clocks = rcc
+        .cfgr
+        .hse(HSEClock::new(HSE_FREQ.Hz(), HSEClockMode::Bypass))
+        .sysclk(CORE_FREQ.Hz())
+        .freeze();
+
+    let profiler = Profiler::new(cp.SYST, CORE_FREQ);
+    let now = || Instant::from_millis(cycles_to_ms_u64::<CORE_FREQ>(profiler.cycles()) as i64);
+
+    defmt::info!("Bioristor UDP telemetry");
+
+    // RMII pins of the LAN8742A PHY.
+    let gpioa = dp.GPIOA.split();
+    let gpiob = dp.GPIOB.split();
+    let gpioc = dp.GPIOC.split();
+    let gpiog = dp.GPIOG.split();
+    let pins = EthPins {
+        ref_clk: gpioa.pa1,
+        crs: gpioa.pa7,
+        tx_en: gpiog.pg11,
+        tx_d0: gpiog.pg13,
+        tx_d1: gpiob.pb13,
+        rx_d0: gpioc.pc4,
+        rx_d1: gpioc.pc5,
+    };
+
+    let mut rx_ring: [RxRingEntry; 2] = Default::default();
+    let mut tx_ring: [TxRingEntry; 2] = Default::default();
+    let Parts { mut dma, .. } = stm32_eth::new(
+        PartsIn {
+            dma: dp.ETHERNET_DMA,
+            mac: dp.ETHERNET_MAC,
+            mmc: dp.ETHERNET_MMC,
+            ptp: dp.ETHERNET_PTP,
+        },
+        &mut rx_ring[..],
+        &mut tx_ring[..],
+        clocks,
+        pins,
+    )
+    .unwrap();
+
+    let config = Config::new(EthernetAddress(MAC).into());
+    let mut iface = Interface::new(config, &mut &mut dma, now());
+    iface.update_ip_addrs(|addrs| {
+        addrs.push(IpCidr::new(ADDRESS, 24)).ok();
+    });
+
+    let mut rx_meta = [udp::PacketMetadata::EMPTY; 1];
+    let mut rx_payload = [0; 64];
+    let mut tx_meta = [udp::PacketMetadata::EMPTY; 4];
+    let mut tx_payload = [0; 256];
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(&mut rx_meta[..], &mut rx_payload[..]),
+        udp::PacketBuffer::new(&mut tx_meta[..], &mut tx_payload[..]),
+    );
+    socket.bind(LOCAL_PORT).unwrap();
+
+    let mut storage = [SocketStorage::EMPTY; 1];
+    let mut sockets = SocketSet::new(&mut storage[..]);
+    let handle = sockets.add(socket);
+
+    let mut sink = UdpSink::new(COLLECTOR, Duration::from_secs(HEARTBEAT_PERIOD_S));
+
+    let currents = core::hint::black_box(Currents {
+        i_ds_on: -0.0026829,
+        i_ds_off: -0.0030365,
+        i_gs_on: 1.169828e-6,
+    });
+    let model = Equation::new(MODEL_PARAMS, currents);
+    let algorithm: Adaptive2Equation<_, Absolute, 10> = Adaptive2Equation::new(ALG_PARAMS, model);
+
+    let mut next_measurement = Instant::ZERO;
+    let mut sequence = 0;
+    loop {
+        let time = now();
+        iface.poll(time, &mut &mut dma, &mut sockets);
+        let socket = sockets.get_mut::<udp::Socket>(handle);
+
+        if let Err(err) = sink.poll(socket, time) {
+            defmt::warn!("Heartbeat not sent: {}", err);
+        }
+
+        if time >= next_measurement {
+            next_measurement = time + Duration::from_millis(MEASUREMENT_PERIOD_MS);
+
+            let message = match algorithm.run() {
+                Some((variables, loss)) => Message::Result(MeasurementRecord {
+                    loss,
+                    sequence,
+                    timestamp: time.secs() as u64,
+                    variables,
+                }),
+                None => Message::Error(ErrorCode::NoSolution),
+            };
+            sequence += 1;
+            if let Err(err) = sink.send(socket, &message) {
+                defmt::warn!("Measurement not sent: {}", err);
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Streaming of the solutions over the USB OTG FS port (CN13) as a CDC-ACM
+//! serial port, so that the measurements can be logged at full rate without
+//! a debug probe attached.
+//!
+//! Every solution is sent as a telemetry frame, and the status of the device
+//! every `STATUS_EVERY` measurements; the frames can be read on the host with
+//! the `HostDecoder` of `bioristor-lib`. The device has no real-time clock,
+//! so the timestamps are the seconds since boot.
+
+#![no_main]
+#![no_std]
+
+use defmt_rtt as _; // global logger
+use panic_probe as _; // panic handler
+
+use stm32f7xx_hal::{
+    otg_fs::{UsbBus, USB},
+    pac,
+    prelude::*,
+    rcc::{HSEClock, HSEClockMode},
+};
+use usb_device::prelude::*;
+use usbd_serial::{CdcAcmClass, USB_CLASS_CDC};
+
+use bioristor_lib::{
+    algorithms::{Adaptive2Equation, Adaptive2Params, Algorithm, SaturationPolicy},
+    losses::Absolute,
+    models::{Equation, Model},
+    params::{Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages},
+    record::MeasurementRecord,
+    telemetry::{send_usb, ErrorCode, Message, Status, UsbPacketWriter},
+    utils::FloatRange,
+};
+use profiler::{cycles_to_ms_u64, Profiler};
+
+const ALG_PARAMS: Adaptive2Params = Adaptive2Params {
+    concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
+    max_iterations: 10,
+    min_range_width: 0.0,
+    reduction_factor: 0.2,
+    resistance_range: FloatRange::new(10.0, 100.0, 100),
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 100),
+    tolerance: 1e-15,
+};
+
+const MODEL_PARAMS: ModelParams = ModelParams {
+    mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    r_dry: 38.2,
+    res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    voltages: Voltages {
+        v_ds: -0.05,
+        v_gs: 0.5,
+    },
+};
+
+const CORE_FREQ: u32 = 216_000_000;
+
+/// Frequency of the MCO output of the ST-LINK, used as external clock.
+const HSE_FREQ: u32 = 8_000_000;
+
+/// Number of measurements between two status messages.
+const STATUS_EVERY: u32 = 100;
+
+/// The CDC-ACM class of `usbd-serial` as a sink of telemetry packets.
+struct CdcWriter<'a>(CdcAcmClass<'a, UsbBus<USB>>);
+
+impl UsbPacketWriter for CdcWriter<'_> {
+    fn max_packet_size(&self) -> usize {
+        self.0.max_packet_size() as usize
+    }
+
+    fn write_packet(&mut self, packet: &[u8]) -> usb_device::Result<usize> {
+        self.0.write_packet(packet)
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    // Retrieve core and device peripherals.
+    let cp: pac::CorePeripherals = pac::CorePeripherals::take().unwrap();
+    let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
+
+    // The USB peripheral needs the 48 MHz clock of the PLL.
+    let rcc = dp.RCC.constrain();
+    let clocks = rcc
+        .cfgr
+        .hse(HSEClock::new(HSE_FREQ.Hz(), HSEClockMode::Bypass))
+        .use_pll()
+        .use_pll48clk()
+        .sysclk(CORE_FREQ.Hz())
+        .freeze();
+
+    let profiler = Profiler::new(cp.SYST, CORE_FREQ);
+
+    defmt::info!("Bioristor USB telemetry");
+
+    let gpioa = dp.GPIOA.split();
+    let usb = USB::new(
+        dp.OTG_FS_GLOBAL,
+        dp.OTG_FS_DEVICE,
+        dp.OTG_FS_PWRCLK,
+        (gpioa.pa11.into_alternate(), gpioa.pa12.into_alternate()),
+        &clocks,
+    );
+    let ep_memory = cortex_m::singleton!(: [u32; 1024] = [0; 1024]).unwrap();
+    let usb_bus = UsbBus::new(usb, ep_memory);
+
+    let mut serial = CdcWriter(CdcAcmClass::new(&usb_bus, 64));
+    let mut usb_dev = UsbDeviceBuilder::new(&usb_bus, UsbVidPid(0x16c0, 0x27dd))
+        .strings(&[StringDescriptors::default()
+            .manufacturer("University of Parma")
+            .product("Bioristor")
+            .serial_number("0001")])
+        .unwrap()
+        .device_class(USB_CLASS_CDC)
+        .build();
+
+    let currents = core::hint::black_box(Currents {
+        i_ds_on: -0.0026829,
+        i_ds_off: -0.0030365,
+        i_gs_on: 1.169828e-6,
+    });
+    let model = Equation::new(MODEL_PARAMS, currents);
+    let algorithm: Adaptive2Equation<_, Absolute, 10> = Adaptive2Equation::new(ALG_PARAMS, model);
+
+    let mut status = Status {
+        errors: 0,
+        measurements: 0,
+        uptime: 0,
+    };
+    loop {
+        // The device is polled between two measurements, which is enough to
+        // answer the host once it has been enumerated.
+        usb_dev.poll(&mut [&mut serial.0]);
+        if usb_dev.state() != UsbDeviceState::Configured {
+            continue;
+        }
+
+        let uptime = cycles_to_ms_u64::<CORE_FREQ>(profiler.cycles()) / 1_000;
+        let message = match algorithm.run() {
+            Some((variables, loss)) => Message::Result(MeasurementRecord {
+                loss,
+                sequence: status.measurements,
+                timestamp: uptime,
+                variables,
+            }),
+            None => {
+                status.errors += 1;
+                Message::Error(ErrorCode::NoSolution)
+            }
+        };
+        status.measurements += 1;
+        status.uptime = uptime as u32;
+
+        // Frames dropped because the host is not reading are only counted.
+        if let Err(err) = send_usb(&mut serial, &message) {
+            defmt::warn!("Frame dropped: {}", err);
+            status.errors += 1;
+        }
+        if status.measurements.is_multiple_of(STATUS_EVERY) {
+            send_usb(&mut serial, &Message::Status(status)).ok();
+        }
+    }
+}
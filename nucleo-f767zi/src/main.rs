@@ -4,28 +4,32 @@
 use defmt_rtt as _; // global logger
 use panic_probe as _; // panic handler
 
+use embedded_hal::delay::DelayNs;
 use stm32f7xx_hal::{pac, prelude::*};
 
 use bioristor_lib::{
-    algorithms::{Adaptive2Equation, Adaptive2Params, Algorithm},
+    algorithms::{Adaptive2Equation, Adaptive2Params, Algorithm, SaturationPolicy},
     losses::Absolute,
     models::{Equation, Model},
     params::{Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages},
     utils::FloatRange,
 };
-use profiler::{cycles_to_us, Profiler};
+use profiler::{bench, cycles_to_us, Profiler, StackProfiler};
 
 const ALG_PARAMS: Adaptive2Params = Adaptive2Params {
     concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
     max_iterations: 10,
+    min_range_width: 0.0,
     reduction_factor: 0.2,
     resistance_range: FloatRange::new(10.0, 100.0, 100),
+    saturation_policy: SaturationPolicy::Keep,
     saturation_range: FloatRange::new(0.0, 1.0, 100),
     tolerance: 1e-15,
 };
 //const ALG_PARAMS: BruteForceParams = BruteForceParams {
 //    concentration_range: FloatRange::new(1e-4, 1e-1, 100_000),
 //    resistance_range: FloatRange::new(10.0, 100.0, 100),
+//    saturation_policy: SaturationPolicy::Keep,
 //    saturation_range: FloatRange::new(0.0, 1.0, 100),
 //};
 //const ALG_PARAMS: GradientDescentParams = GradientDescentParams {
@@ -33,15 +37,19 @@ const ALG_PARAMS: Adaptive2Params = Adaptive2Params {
 //    grad_tolerance: 1e-9,
 //    learning_rate_init: 0.1,
 //    max_iterations: 10,
+//    saturation_policy: SaturationPolicy::Keep,
 //    tolerance: 1e-15,
 //};
 //const ALG_PARAMS: NewtonParams = NewtonParams {
 //    concentration_init: 1e-2,
 //    grad_tolerance: 1e-9,
 //    max_iterations: 10,
+//    saturation_policy: SaturationPolicy::Keep,
 //    tolerance: 1e-15,
 //};
-//const ALG_PARAMS: () = ();
+//const ALG_PARAMS: NeuralNetworkParams = NeuralNetworkParams {
+//    saturation_policy: SaturationPolicy::Keep,
+//};
 
 const MODEL_PARAMS: ModelParams = ModelParams {
     mod_params: ModulationParams(0.0, -0.01463, -0.32),
@@ -55,6 +63,9 @@ const MODEL_PARAMS: ModelParams = ModelParams {
 
 const CORE_FREQ: u32 = 216_000_000;
 
+/// Number of runs used to benchmark the algorithm.
+const BENCH_RUNS: usize = 10;
+
 #[cortex_m_rt::entry]
 fn main() -> ! {
     // Retrieve core and device peripherals.
@@ -62,10 +73,13 @@ fn main() -> ! {
     let dp: pac::Peripherals = pac::Peripherals::take().unwrap();
 
     let rcc = dp.RCC.constrain();
-    let syst = cp.SYST;
 
     // Configure clocks.
-    let clocks = rcc.cfgr.sysclk(CORE_FREQ.Hz()).freeze();
+    rcc.cfgr.sysclk(CORE_FREQ.Hz()).freeze();
+
+    // The profiler owns SysTick and is also used for delays.
+    let mut profiler = Profiler::new(cp.SYST, CORE_FREQ);
+    profiler.calibrate_overhead();
 
     defmt::info!("Bioristor application");
 
@@ -83,8 +97,7 @@ fn main() -> ! {
     });
     defmt::debug!("{}", currents);
 
-    let mut delay = dp.TIM1.delay_us(&clocks);
-    delay.delay_ms(1000_u32);
+    profiler.delay_ms(1000);
 
     blue_led.set_low();
     defmt::info!("Starting algorithm execution...");
@@ -97,12 +110,11 @@ fn main() -> ! {
     let algorithm: Adaptive2Equation<_, Absolute, 10> = Adaptive2Equation::new(ALG_PARAMS, model);
     defmt::debug!("{}", ALG_PARAMS);
 
-    let profiler = Profiler::new(syst);
+    // SAFETY: no heap is used, so the memory below the stack is free.
+    let stack = unsafe { StackProfiler::new() };
 
     // Run algorithm.
-    let res = algorithm.run();
-
-    let cycles = profiler.cycles();
+    let (res, cycles) = profiler.measure(|| algorithm.run());
 
     match res {
         Some((variables, error)) => {
@@ -122,7 +134,25 @@ fn main() -> ! {
         cycles_to_us::<CORE_FREQ>(cycles)
     );
 
-    delay.delay_ms(1000_u32);
+    // Benchmark the algorithm over several runs.
+    let stats = bench::<BENCH_RUNS>(&profiler, || {
+        core::hint::black_box(algorithm.run());
+    });
+    defmt::info!(
+        "Over {} runs: min {} median {} max {} stddev {} CPU cycles",
+        stats.runs,
+        stats.min,
+        stats.median,
+        stats.max,
+        stats.stddev
+    );
+    defmt::info!(
+        "Stack usage: {} of {} bytes",
+        stack.high_water_mark(),
+        stack.size()
+    );
+
+    profiler.delay_ms(1000);
 
     loop {
         cortex_m::asm::wfi();
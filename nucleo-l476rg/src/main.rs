@@ -4,43 +4,21 @@
 use defmt_rtt as _; // global logger
 use panic_probe as _; // panic handler
 
-use stm32l4xx_hal::{delay::Delay, pac, prelude::*};
+use embedded_hal::delay::DelayNs;
+use stm32l4xx_hal::{pac, prelude::*};
 
 use bioristor_lib::{
-    algorithms::{Adaptive2Equation, Adaptive2Params, Algorithm},
+    algorithms::{Adaptive2Equation, Adaptive2Params, Algorithm, CpuClass},
     losses::Absolute,
     models::{Equation, Model},
     params::{Currents, ModelParams, ModulationParams, StemResistanceInvParams, Voltages},
-    utils::FloatRange,
 };
-use profiler::{cycles_to_us, Profiler};
-
-const ALG_PARAMS: Adaptive2Params = Adaptive2Params {
-    concentration_range: FloatRange::new(1e-4, 1e-1, 1_000),
-    max_iterations: 10,
-    reduction_factor: 0.2,
-    resistance_range: FloatRange::new(10.0, 100.0, 100),
-    saturation_range: FloatRange::new(0.0, 1.0, 100),
-    tolerance: 1e-15,
-};
-//const ALG_PARAMS: BruteForceParams = BruteForceParams {
-//    concentration_range: FloatRange::new(1e-4, 1e-1, 100_000),
-//    resistance_range: FloatRange::new(10.0, 100.0, 100),
-//    saturation_range: FloatRange::new(0.0, 1.0, 100),
-//};
-//const ALG_PARAMS: GradientDescentParams = GradientDescentParams {
-//    concentration_init: 1e-2,
-//    grad_tolerance: 1e-9,
-//    learning_rate_init: 0.1,
-//    max_iterations: 10,
-//    tolerance: 1e-15,
-//};
-//const ALG_PARAMS: NewtonParams = NewtonParams {
-//    concentration_init: 1e-2,
-//    grad_tolerance: 1e-9,
-//    max_iterations: 10,
-//    tolerance: 1e-15,
-//};
+use profiler::{bench, cycles_to_us, Profiler, StackProfiler};
+
+const ALG_PARAMS: Adaptive2Params = CpuClass::CortexM4.adaptive2_params();
+//const ALG_PARAMS: BruteForceParams = CpuClass::CortexM4.brute_force_params();
+//const ALG_PARAMS: GradientDescentParams = CpuClass::CortexM4.gradient_descent_params();
+//const ALG_PARAMS: NewtonParams = CpuClass::CortexM4.newton_params();
 //const ALG_PARAMS: () = ();
 
 const MODEL_PARAMS: ModelParams = ModelParams {
@@ -55,6 +33,9 @@ const MODEL_PARAMS: ModelParams = ModelParams {
 
 const CORE_FREQ: u32 = 80_000_000;
 
+/// Number of runs used to benchmark the algorithm.
+const BENCH_RUNS: usize = 10;
+
 #[cortex_m_rt::entry]
 fn main() -> ! {
     // Retrieve device and core peripherals.
@@ -64,14 +45,16 @@ fn main() -> ! {
     let mut flash = dp.FLASH.constrain();
     let mut rcc = dp.RCC.constrain();
     let mut pwr = dp.PWR.constrain(&mut rcc.apb1r1);
-    let mut syst = cp.SYST;
 
     // Configure clocks.
-    let clocks = rcc
-        .cfgr
+    rcc.cfgr
         .sysclk(CORE_FREQ.Hz())
         .freeze(&mut flash.acr, &mut pwr);
 
+    // The profiler owns SysTick and is also used for delays.
+    let mut profiler = Profiler::new(cp.SYST, CORE_FREQ);
+    profiler.calibrate_overhead();
+
     defmt::info!("Bioristor application");
 
     // Setup LED.
@@ -88,10 +71,7 @@ fn main() -> ! {
     });
     defmt::debug!("{}", currents);
 
-    // Setup delay.
-    let mut delay = Delay::new(syst, clocks);
-    delay.delay_ms(1000_u32);
-    syst = delay.free();
+    profiler.delay_ms(1000);
 
     led.set_low();
     defmt::info!("Starting algorithm execution...");
@@ -103,13 +83,11 @@ fn main() -> ! {
     let algorithm: Adaptive2Equation<_, Absolute, 10> = Adaptive2Equation::new(ALG_PARAMS, model);
     defmt::debug!("{}", ALG_PARAMS);
 
-    let profiler = Profiler::new(syst);
+    // SAFETY: no heap is used, so the memory below the stack is free.
+    let stack = unsafe { StackProfiler::new() };
 
     // Run algorithm.
-    let res = algorithm.run();
-
-    let cycles = profiler.cycles();
-    syst = profiler.free();
+    let (res, cycles) = profiler.measure(|| algorithm.run());
 
     match res {
         Some((variables, error)) => {
@@ -126,8 +104,25 @@ fn main() -> ! {
         cycles_to_us::<CORE_FREQ>(cycles)
     );
 
-    let mut delay = Delay::new(syst, clocks);
-    delay.delay_ms(1000_u32);
+    // Benchmark the algorithm over several runs.
+    let stats = bench::<BENCH_RUNS>(&profiler, || {
+        core::hint::black_box(algorithm.run());
+    });
+    defmt::info!(
+        "Over {} runs: min {} median {} max {} stddev {} CPU cycles",
+        stats.runs,
+        stats.min,
+        stats.median,
+        stats.max,
+        stats.stddev
+    );
+    defmt::info!(
+        "Stack usage: {} of {} bytes",
+        stack.high_water_mark(),
+        stack.size()
+    );
+
+    profiler.delay_ms(1000);
 
     loop {
         cortex_m::asm::wfi();
@@ -0,0 +1,143 @@
+use crate::CycleCounter;
+
+/// Statistics of the execution time of a benchmarked closure, in cycles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BenchStats {
+    /// The number of runs.
+    pub runs: usize,
+
+    /// The minimum number of cycles.
+    pub min: u64,
+
+    /// The median number of cycles.
+    pub median: u64,
+
+    /// The maximum number of cycles.
+    pub max: u64,
+
+    /// The mean number of cycles.
+    pub mean: u64,
+
+    /// The standard deviation of the number of cycles.
+    pub stddev: u64,
+}
+
+/// Runs a closure `N` times and returns the statistics of its execution time.
+///
+/// The cycle counter is 64 bits wide, so runs spanning a rollover of the
+/// underlying hardware timer are measured correctly.
+///
+/// # Parameters
+///
+/// * `counter`: The counter used to measure the time.
+/// * `f`: The closure to be benchmarked.
+///
+/// # Returns
+///
+/// The statistics of the execution time.
+///
+/// # Type parameters
+///
+/// * `N`: The number of runs, must be greater than zero.
+///
+/// # Example
+///
+/// ```
+/// use core::cell::Cell;
+///
+/// use profiler::{bench, CycleCounter};
+///
+/// /// Counter advancing by 100 cycles on every read.
+/// struct Counter(Cell<u64>);
+///
+/// impl CycleCounter for Counter {
+///     fn cycles(&self) -> u64 {
+///         self.0.replace(self.0.get() + 100)
+///     }
+/// }
+///
+/// let counter = Counter(Cell::new(0));
+/// let stats = bench::<10>(&counter, || {
+///     // Do some work.
+/// });
+/// assert_eq!(stats.median, 100);
+/// ```
+pub fn bench<const N: usize>(counter: &impl CycleCounter, mut f: impl FnMut()) -> BenchStats {
+    assert!(N > 0, "at least one run is required");
+
+    let mut samples = [0u64; N];
+    for sample in samples.iter_mut() {
+        let start = counter.cycles();
+        f();
+        *sample = counter.cycles().wrapping_sub(start);
+    }
+
+    stats(&mut samples)
+}
+
+/// Computes the statistics of the given samples, sorting them in place.
+fn stats(samples: &mut [u64]) -> BenchStats {
+    samples.sort_unstable();
+
+    let n = samples.len();
+    let median = if n.is_multiple_of(2) {
+        (samples[n / 2 - 1] + samples[n / 2]) / 2
+    } else {
+        samples[n / 2]
+    };
+
+    let sum: u128 = samples.iter().map(|&s| s as u128).sum();
+    let mean = sum / n as u128;
+    let variance = samples
+        .iter()
+        .map(|&s| (s as u128).abs_diff(mean).pow(2))
+        .sum::<u128>()
+        / n as u128;
+
+    BenchStats {
+        runs: n,
+        min: samples[0],
+        median,
+        max: samples[n - 1],
+        mean: mean as u64,
+        stddev: variance.isqrt() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockCounter;
+
+    #[test]
+    fn test_bench() {
+        let counter = MockCounter::new(0);
+        let mut run = 0;
+        let stats = bench::<5>(&counter, || {
+            run += 1;
+            counter.advance([30, 10, 50, 20, 40][run - 1]);
+        });
+
+        assert_eq!(stats.runs, 5);
+        assert_eq!(stats.min, 10);
+        assert_eq!(stats.median, 30);
+        assert_eq!(stats.max, 50);
+        assert_eq!(stats.mean, 30);
+        assert_eq!(stats.stddev, 14);
+    }
+
+    #[test]
+    fn test_stats_even() {
+        let stats = stats(&mut [4, 1, 3, 2]);
+        assert_eq!(stats.min, 1);
+        assert_eq!(stats.median, 2);
+        assert_eq!(stats.max, 4);
+        assert_eq!(stats.mean, 2);
+        assert_eq!(stats.stddev, 1);
+
+        let stats = super::stats(&mut [7]);
+        assert_eq!(stats.median, 7);
+        assert_eq!(stats.stddev, 0);
+    }
+}
@@ -0,0 +1,154 @@
+use fugit::{Duration, Instant};
+
+use crate::CycleCounter;
+
+/// Monotonic clock based on a free-running cycle counter.
+///
+/// This exposes the extended 64-bit counter as a [`fugit`] time base, so that
+/// other drivers (e.g. debouncers and timeouts) can consume the profiler's
+/// time base directly. The clock is implemented for every [`CycleCounter`]
+/// that counts CPU cycles, with `FREQ` being the frequency of the CPU in Hz.
+///
+/// # Type parameters
+///
+/// * `FREQ`: The frequency of the CPU in Hz.
+///
+/// # Example
+///
+/// ```
+/// use core::cell::Cell;
+///
+/// use fugit::ExtU64;
+/// use profiler::{Clock, CycleCounter};
+///
+/// /// Counter advanced by hand.
+/// struct Counter(Cell<u64>);
+///
+/// impl CycleCounter for Counter {
+///     fn cycles(&self) -> u64 {
+///         self.0.get()
+///     }
+/// }
+///
+/// let clock = Counter(Cell::new(0));
+/// let deadline = Clock::<1_000_000>::deadline(&clock, 10.millis());
+///
+/// clock.0.set(5_000);
+/// assert!(!Clock::<1_000_000>::is_expired(&clock, deadline));
+/// clock.0.set(10_000);
+/// assert!(Clock::<1_000_000>::is_expired(&clock, deadline));
+/// ```
+pub trait Clock<const FREQ: u32> {
+    /// Returns the current instant.
+    ///
+    /// # Returns
+    ///
+    /// The current instant.
+    fn now(&self) -> Instant<u64, 1, FREQ>;
+
+    /// Returns the time elapsed since the given instant.
+    ///
+    /// # Parameters
+    ///
+    /// * `since`: An instant previously returned by [`Clock::now`].
+    ///
+    /// # Returns
+    ///
+    /// The time elapsed since `since`, or zero if `since` is in the future.
+    fn elapsed(&self, since: Instant<u64, 1, FREQ>) -> Duration<u64, 1, FREQ> {
+        self.now()
+            .checked_duration_since(since)
+            .unwrap_or(Duration::<u64, 1, FREQ>::from_ticks(0))
+    }
+
+    /// Returns the instant at which the given timeout expires.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout`: The timeout, starting from now.
+    ///
+    /// # Returns
+    ///
+    /// The instant at which the timeout expires.
+    fn deadline(&self, timeout: Duration<u64, 1, FREQ>) -> Instant<u64, 1, FREQ> {
+        self.now() + timeout
+    }
+
+    /// Returns whether the given deadline has expired.
+    ///
+    /// # Parameters
+    ///
+    /// * `deadline`: An instant previously returned by [`Clock::deadline`].
+    ///
+    /// # Returns
+    ///
+    /// `true` if the deadline has expired, `false` otherwise.
+    fn is_expired(&self, deadline: Instant<u64, 1, FREQ>) -> bool {
+        self.now() >= deadline
+    }
+}
+
+impl<C: CycleCounter, const FREQ: u32> Clock<FREQ> for C {
+    #[inline]
+    fn now(&self) -> Instant<u64, 1, FREQ> {
+        Instant::<u64, 1, FREQ>::from_ticks(self.cycles())
+    }
+}
+
+/// Handle to the time base of the [`Profiler`](crate::Profiler) that can be
+/// freely copied and shared between drivers.
+///
+/// It reads the same extended counter as the profiler without holding it,
+/// so it must only be used while a [`Profiler`](crate::Profiler) is running.
+///
+/// # Example
+///
+/// ```no_run
+/// use profiler::{Clock, SysTickClock};
+///
+/// fn debounce(clock: &impl Clock<1_000_000>) {
+///     let start = clock.now();
+///     // ...
+/// }
+///
+/// debounce(&SysTickClock);
+/// ```
+#[cfg(feature = "cortex-m")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SysTickClock;
+
+#[cfg(feature = "cortex-m")]
+impl CycleCounter for SysTickClock {
+    #[inline]
+    fn cycles(&self) -> u64 {
+        crate::systick::read_cycles()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockCounter;
+
+    use fugit::ExtU64;
+
+    #[test]
+    fn test_clock() {
+        let clock = MockCounter::new(0);
+        clock.advance(1_000);
+
+        let start: Instant<u64, 1, 1_000_000> = clock.now();
+        assert_eq!(start.ticks(), 1_000);
+
+        clock.advance(2_500);
+        assert_eq!(clock.elapsed(start), 2_500.micros::<1, 1_000_000>());
+        assert_eq!(
+            Clock::<1_000_000>::elapsed(&clock, start + 1.secs()),
+            0.micros::<1, 1_000_000>()
+        );
+
+        let deadline = Clock::<1_000_000>::deadline(&clock, 1.millis());
+        assert_eq!(deadline.ticks(), 4_500);
+        assert!(!Clock::<1_000_000>::is_expired(&clock, deadline));
+    }
+}
@@ -0,0 +1,245 @@
+#[cfg(any(feature = "cortex-m", test, feature = "mock"))]
+use core::cell::Cell;
+
+#[cfg(feature = "cortex-m")]
+use cortex_m::peripheral::{DCB, DWT};
+
+#[cfg(feature = "cortex-m")]
+use crate::Profiler;
+
+/// Common interface for free-running cycle counters.
+///
+/// This is the minimal abstraction needed to measure elapsed time, so that
+/// instrumented code is not hard-wired to a specific hardware timer.
+pub trait CycleCounter {
+    /// Returns the number of cycles elapsed since the counter was started.
+    ///
+    /// # Returns
+    ///
+    /// The number of cycles elapsed since the counter was started.
+    fn cycles(&self) -> u64;
+}
+
+#[cfg(feature = "cortex-m")]
+impl CycleCounter for Profiler {
+    #[inline]
+    fn cycles(&self) -> u64 {
+        Profiler::cycles(self)
+    }
+}
+
+/// Cycle counter based on the [`DWT`] cycle counter, available on Cortex-M3
+/// and above.
+///
+/// The hardware counter is only 32 bits wide, so it is extended to 64 bits in
+/// software: the counter must be read at least once every 2^32 cycles (about
+/// 20 seconds at 216 MHz) for the extension to be correct. Unlike the
+/// [`Profiler`], it does not use any exception.
+#[cfg(feature = "cortex-m")]
+pub struct DwtCounter {
+    dwt: DWT,
+
+    /// The last value read from the hardware counter.
+    last: Cell<u32>,
+
+    /// The number of times the hardware counter wrapped around.
+    wraps: Cell<u32>,
+}
+
+#[cfg(feature = "cortex-m")]
+impl DwtCounter {
+    /// Enables the DWT cycle counter and starts counting CPU cycles.
+    ///
+    /// # Parameters
+    ///
+    /// * `dcb`: The [`DCB`] peripheral, used to enable the trace unit.
+    /// * `dwt`: The [`DWT`] peripheral.
+    pub fn new(dcb: &mut DCB, mut dwt: DWT) -> Self {
+        dcb.enable_trace();
+        DWT::unlock();
+        dwt.set_cycle_count(0);
+        dwt.enable_cycle_counter();
+
+        Self {
+            dwt,
+            last: Cell::new(0),
+            wraps: Cell::new(0),
+        }
+    }
+
+    /// Releases the [`DWT`] peripheral.
+    pub fn free(self) -> DWT {
+        self.dwt
+    }
+}
+
+#[cfg(feature = "cortex-m")]
+impl CycleCounter for DwtCounter {
+    #[inline]
+    fn cycles(&self) -> u64 {
+        let current = DWT::cycle_count();
+        if current < self.last.get() {
+            self.wraps.set(self.wraps.get() + 1);
+        }
+        self.last.set(current);
+
+        ((self.wraps.get() as u64) << 32) | current as u64
+    }
+}
+
+/// Cycle counter based on the `mcycle` CSR of RISC-V cores.
+///
+/// The hardware counter is 64 bits wide on both RV32 and RV64, so neither a
+/// software extension nor an exception is needed: on RV32 the two halves are
+/// read from `mcycle` and `mcycleh`, and the read is retried if the low half
+/// wrapped around in between.
+///
+/// The CSR is only accessible in machine mode, and the counter must not be
+/// inhibited through `mcountinhibit`; both hold after reset on most
+/// bare-metal RISC-V microcontrollers.
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+#[derive(Debug, Clone, Copy)]
+pub struct McycleCounter {
+    /// The value of the hardware counter when the counter was started.
+    start: u64,
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+impl McycleCounter {
+    /// Starts counting CPU cycles from the current value of `mcycle`.
+    pub fn new() -> Self {
+        Self {
+            start: read_mcycle(),
+        }
+    }
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+impl Default for McycleCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+impl CycleCounter for McycleCounter {
+    #[inline]
+    fn cycles(&self) -> u64 {
+        read_mcycle().wrapping_sub(self.start)
+    }
+}
+
+/// Reads the 64-bit `mcycle` CSR.
+#[cfg(target_arch = "riscv64")]
+#[inline]
+fn read_mcycle() -> u64 {
+    let cycles: u64;
+    // SAFETY: reading the counter has no side effects.
+    unsafe {
+        core::arch::asm!("csrr {0}, mcycle", out(reg) cycles, options(nomem, nostack));
+    }
+    cycles
+}
+
+/// Reads the 64-bit `mcycle` CSR from its two halves.
+///
+/// The high half is read before and after the low half, and the read is
+/// retried if it changed in between, i.e. if the low half wrapped around.
+#[cfg(target_arch = "riscv32")]
+#[inline]
+fn read_mcycle() -> u64 {
+    loop {
+        let (high, low, again): (u32, u32, u32);
+        // SAFETY: reading the counter has no side effects.
+        unsafe {
+            core::arch::asm!(
+                "csrr {0}, mcycleh",
+                "csrr {1}, mcycle",
+                "csrr {2}, mcycleh",
+                out(reg) high,
+                out(reg) low,
+                out(reg) again,
+                options(nomem, nostack),
+            );
+        }
+        if high == again {
+            return (high as u64) << 32 | low as u64;
+        }
+    }
+}
+
+/// Software cycle counter for host tests: every read advances the counter by
+/// a fixed number of cycles.
+///
+/// # Example
+///
+/// ```
+/// use profiler::{CycleCounter, MockCounter};
+///
+/// let counter = MockCounter::new(10);
+/// assert_eq!(counter.cycles(), 0);
+/// assert_eq!(counter.cycles(), 10);
+///
+/// counter.advance(100);
+/// assert_eq!(counter.cycles(), 120);
+/// ```
+#[cfg(any(test, feature = "mock"))]
+#[derive(Debug, Default)]
+pub struct MockCounter {
+    /// The current value of the counter.
+    now: Cell<u64>,
+
+    /// The number of cycles added on every read.
+    step: u64,
+}
+
+#[cfg(any(test, feature = "mock"))]
+impl MockCounter {
+    /// Creates a new counter starting from zero.
+    ///
+    /// # Parameters
+    ///
+    /// * `step`: The number of cycles added on every read.
+    pub const fn new(step: u64) -> Self {
+        Self {
+            now: Cell::new(0),
+            step,
+        }
+    }
+
+    /// Advances the counter by the given number of cycles.
+    ///
+    /// # Parameters
+    ///
+    /// * `cycles`: The number of cycles to add.
+    pub fn advance(&self, cycles: u64) {
+        self.now.set(self.now.get() + cycles);
+    }
+}
+
+#[cfg(any(test, feature = "mock"))]
+impl CycleCounter for MockCounter {
+    fn cycles(&self) -> u64 {
+        let now = self.now.get();
+        self.now.set(now + self.step);
+        now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_counter() {
+        let counter = MockCounter::new(3);
+        assert_eq!(counter.cycles(), 0);
+        assert_eq!(counter.cycles(), 3);
+        counter.advance(10);
+        assert_eq!(counter.cycles(), 16);
+
+        let counter = MockCounter::default();
+        assert_eq!(counter.cycles(), 0);
+        assert_eq!(counter.cycles(), 0);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::CycleCounter;
+
+/// Number of buckets of the [`Histogram`], one for each power of two of a [`u64`].
+pub const HISTOGRAM_BUCKETS: usize = 64;
+
+/// Histogram of cycle counts with logarithmic (base 2) buckets.
+///
+/// Bucket `i` counts the samples in the range `[2^i, 2^(i+1))`, except for
+/// bucket 0 which also counts the samples equal to zero. The buckets are
+/// fixed, so the histogram has a constant size and recording a sample takes
+/// constant time, which makes it suitable for long soak tests where the tail
+/// latencies matter more than the average.
+///
+/// # Example
+///
+/// ```
+/// use core::cell::Cell;
+///
+/// use profiler::{CycleCounter, Histogram};
+///
+/// /// Counter advanced by hand.
+/// struct Counter(Cell<u64>);
+///
+/// impl CycleCounter for Counter {
+///     fn cycles(&self) -> u64 {
+///         self.0.get()
+///     }
+/// }
+///
+/// let counter = Counter(Cell::new(0));
+/// let mut histogram = Histogram::new();
+///
+/// for _ in 0..10 {
+///     histogram.measure(&counter, || counter.0.set(counter.0.get() + 1000));
+/// }
+/// assert_eq!(histogram.count(), 10);
+/// assert_eq!(histogram.bucket(9), 10);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    /// The number of samples in each bucket.
+    buckets: [u32; HISTOGRAM_BUCKETS],
+
+    /// The total number of samples.
+    count: u64,
+
+    /// The minimum sample.
+    min: u64,
+
+    /// The maximum sample.
+    max: u64,
+}
+
+impl Histogram {
+    /// Creates a new empty histogram.
+    pub const fn new() -> Self {
+        Self {
+            buckets: [0; HISTOGRAM_BUCKETS],
+            count: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    /// Returns the index of the bucket the given sample falls into.
+    ///
+    /// # Parameters
+    ///
+    /// * `cycles`: The sample.
+    ///
+    /// # Returns
+    ///
+    /// The index of the bucket.
+    pub const fn bucket_index(cycles: u64) -> usize {
+        match cycles.checked_ilog2() {
+            Some(i) => i as usize,
+            None => 0,
+        }
+    }
+
+    /// Adds a sample to the histogram.
+    ///
+    /// The bucket counters saturate instead of overflowing.
+    ///
+    /// # Parameters
+    ///
+    /// * `cycles`: The sample.
+    pub fn record(&mut self, cycles: u64) {
+        let bucket = &mut self.buckets[Self::bucket_index(cycles)];
+        *bucket = bucket.saturating_add(1);
+        self.count += 1;
+        self.min = self.min.min(cycles);
+        self.max = self.max.max(cycles);
+    }
+
+    /// Executes a closure and records its execution time in the histogram.
+    ///
+    /// # Parameters
+    ///
+    /// * `counter`: The counter used to measure the time.
+    /// * `f`: The closure to be measured.
+    ///
+    /// # Returns
+    ///
+    /// The result of the closure.
+    pub fn measure<R>(&mut self, counter: &impl CycleCounter, f: impl FnOnce() -> R) -> R {
+        let start = counter.cycles();
+        let res = f();
+        self.record(counter.cycles().wrapping_sub(start));
+        res
+    }
+
+    /// Returns the number of samples in the given bucket.
+    ///
+    /// # Parameters
+    ///
+    /// * `index`: The index of the bucket, less than [`HISTOGRAM_BUCKETS`].
+    ///
+    /// # Returns
+    ///
+    /// The number of samples in the bucket.
+    pub fn bucket(&self, index: usize) -> u32 {
+        self.buckets[index]
+    }
+
+    /// Returns the number of samples in each bucket.
+    pub fn buckets(&self) -> &[u32; HISTOGRAM_BUCKETS] {
+        &self.buckets
+    }
+
+    /// Returns the total number of samples.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the minimum sample, if any.
+    pub fn min(&self) -> Option<u64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    /// Returns the maximum sample, if any.
+    pub fn max(&self) -> Option<u64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// Returns an upper bound of the given percentile of the samples.
+    ///
+    /// Since the buckets are logarithmic, the result is the upper bound of
+    /// the bucket containing the percentile, capped at the maximum sample.
+    ///
+    /// # Parameters
+    ///
+    /// * `percent`: The percentile, between 0 and 100.
+    ///
+    /// # Returns
+    ///
+    /// The upper bound of the percentile, or `None` if the histogram is empty.
+    pub fn percentile(&self, percent: u8) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let target = (self.count * percent.min(100) as u64).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n as u64;
+            if seen >= target {
+                let upper = 1u64.checked_shl(i as u32 + 1).map_or(u64::MAX, |b| b - 1);
+                return Some(upper.min(self.max));
+            }
+        }
+
+        Some(self.max)
+    }
+
+    /// Removes all the samples from the histogram.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Logs the non-empty buckets of the histogram with `defmt`.
+    #[cfg(feature = "defmt")]
+    pub fn dump(&self) {
+        defmt::info!(
+            "Histogram: {} samples, min {} max {} cycles",
+            self.count,
+            self.min().unwrap_or(0),
+            self.max
+        );
+        for (i, &n) in self.buckets.iter().enumerate().filter(|(_, &n)| n > 0) {
+            defmt::info!("  [2^{=usize}, 2^{=usize}): {=u32}", i, i + 1, n);
+        }
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_index() {
+        assert_eq!(Histogram::bucket_index(0), 0);
+        assert_eq!(Histogram::bucket_index(1), 0);
+        assert_eq!(Histogram::bucket_index(2), 1);
+        assert_eq!(Histogram::bucket_index(3), 1);
+        assert_eq!(Histogram::bucket_index(1024), 10);
+        assert_eq!(Histogram::bucket_index(u64::MAX), 63);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::new();
+        assert_eq!(histogram.min(), None);
+        assert_eq!(histogram.percentile(50), None);
+
+        for _ in 0..98 {
+            histogram.record(100);
+        }
+        histogram.record(5000);
+        histogram.record(u64::MAX);
+
+        assert_eq!(histogram.count(), 100);
+        assert_eq!(histogram.bucket(6), 98);
+        assert_eq!(histogram.bucket(12), 1);
+        assert_eq!(histogram.bucket(63), 1);
+        assert_eq!(histogram.min(), Some(100));
+        assert_eq!(histogram.max(), Some(u64::MAX));
+        assert_eq!(histogram.percentile(50), Some(127));
+        assert_eq!(histogram.percentile(99), Some(8191));
+        assert_eq!(histogram.percentile(100), Some(u64::MAX));
+
+        histogram.reset();
+        assert_eq!(histogram, Histogram::new());
+    }
+}
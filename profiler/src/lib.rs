@@ -1,125 +1,340 @@
-//! Profiler for Cortex-M microcontrollers.
+//! Profiler for Cortex-M and RISC-V microcontrollers.
 //!
 //! This implementation is strongly inspired by the [`ep-systick`] crate.
 //!
 //! This profiler depends on the [`SYST`] hardware, common to most Cortex-M devices.
 //!
-//! The profiler's configured resolution is the same as the core clock.
+//! The profiler's configured resolution is the same as the core clock, unless the
+//! SysTick is clocked by the external reference clock (see
+//! [`Profiler::new_with_source`]).
 //!
 //! The cycle count clock is free-running, so overflows are likely if you have
 //! long running functions to profile.
 //! To mitigate this, this profiler uses a [`u64`] counter and the [`SysTick`] exception.
-//! You can expect an exception to fire every 2^24 clock cycles.
+//! You can expect an exception to fire every 2^24 clock cycles, or more often if
+//! a custom reload value is configured with [`Profiler::with_reload`].
+//! The number of rollovers is tracked in 64 bits as well, so the counter covers
+//! the full [`u64`] range of cycles (thousands of years at hundreds of MHz)
+//! regardless of the reload value.
+//!
+//! The profiler also implements the [`DelayNs`] trait of `embedded-hal`, so the
+//! same SysTick owner can be used both to measure execution time and to
+//! busy-wait.
+//!
+//! All the counters implement the [`CycleCounter`] trait, so that instrumented
+//! code does not depend on a specific hardware timer: besides the SysTick-based
+//! [`Profiler`], a [`DwtCounter`] backend is provided, and a `MockCounter` is
+//! available for host tests with the `mock` feature.
+//!
+//! The Cortex-M backends, including the `SysTick` exception handler, are
+//! enabled by the default `cortex-m` feature. Without it, only the
+//! architecture-independent parts are built ([`CycleCounter`], [`Clock`],
+//! [`bench`], [`Histogram`] and the conversion functions), so that the
+//! instrumented code also runs on other cores: on RISC-V targets, the
+//! `McycleCounter` backend reads the standard `mcycle` CSR.
+//!
+//! The [`bench`] function runs a closure several times and reports the
+//! statistics of its execution time, which is more reliable than single-shot
+//! measurements. For long soak tests, the [`Histogram`] collects the cycle
+//! counts in logarithmic buckets to reveal the tail latencies.
+//!
+//! The extended counter is also exposed as a monotonic [`Clock`] based on
+//! [`fugit`], so that other drivers can share the profiler's time base through
+//! the [`SysTickClock`] handle.
+//!
+//! The [`StaticProfiler`] wraps the profiler in a critical-section protected
+//! handle that can be placed in a `static` and read from interrupt handlers.
+//!
+//! The [`StackProfiler`] complements the cycle profiler by reporting the
+//! high-water mark of the stack, since RAM usage is as important as execution
+//! time when choosing an algorithm for small parts.
+//!
+//! With the `defmt-timestamp` feature enabled, the extended counter is also
+//! installed as the `defmt` global timestamp with microsecond resolution, so log
+//! timestamps and profiler measurements share the same time base.
 //!
 //! [`ep-systick`]: https://crates.io/crates/ep-systick
+//! [`DelayNs`]: `embedded_hal::delay::DelayNs`
 //! [`SYST`]: `cortex_m::peripheral::SYST`
 //! [`SysTick`]: `cortex_m::peripheral::scb::Exception::SysTick`
 
 #![no_std]
 
-use core::sync::atomic::{AtomicU32, Ordering};
+mod bench;
+mod clock;
+mod counter;
+mod histogram;
+#[cfg(feature = "cortex-m")]
+mod shared;
+mod stack;
+#[cfg(feature = "cortex-m")]
+mod systick;
+#[cfg(feature = "defmt-timestamp")]
+mod timestamp;
 
-use cortex_m::peripheral::{syst::SystClkSource, SYST};
-use cortex_m_rt::exception;
+pub use bench::{bench, BenchStats};
+pub use clock::Clock;
+#[cfg(feature = "cortex-m")]
+pub use clock::SysTickClock;
+pub use counter::CycleCounter;
+#[cfg(feature = "cortex-m")]
+pub use counter::DwtCounter;
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+pub use counter::McycleCounter;
+#[cfg(any(test, feature = "mock"))]
+pub use counter::MockCounter;
+pub use histogram::{Histogram, HISTOGRAM_BUCKETS};
+#[cfg(feature = "cortex-m")]
+pub use shared::StaticProfiler;
+pub use stack::StackProfiler;
+#[cfg(feature = "cortex-m")]
+pub use systick::*;
 
-/// Tracker of `systick` cycle count overflows to extend systick's 24 bit timer.
-static ROLLOVER_COUNT: AtomicU32 = AtomicU32::new(0);
+use fugit::{Duration, MicrosDurationU64, MillisDurationU64};
 
-/// The reload value of the [`systick`](cortex_m::peripheral::SYST) peripheral.
-/// Also is the max it can go: 2^24.
-const SYSTICK_RELOAD: u32 = 0x00FF_FFFF;
+/// Converts the number of CPU cycles to milliseconds.
+///
+/// # Parameters
+///
+/// * `cycles`: The number of CPU cycles.
+///
+/// # Returns
+///
+/// The number of milliseconds, saturated to [`u32::MAX`].
+/// Use [`cycles_to_ms_u64`] for long captures.
+///
+/// # Type parameters
+///
+/// * `FREQ`: The frequency of the CPU in Hz.
+#[inline]
+pub fn cycles_to_ms<const FREQ: u32>(cycles: u64) -> u32 {
+    (cycles as f32 * (1_000_f32 / FREQ as f32)) as u32
+}
 
-/// The resolution of [`systick`](cortex_m::peripheral::SYST): 2^24.
-const SYSTICK_RESOLUTION: u64 = 0x0100_0000;
+/// Converts the number of CPU cycles to microseconds.
+///
+/// # Parameters
+///
+/// * `cycles`: The number of CPU cycles.
+///
+/// # Returns
+///
+/// The number of microseconds, saturated to [`u32::MAX`].
+/// Use [`cycles_to_us_u64`] for long captures.
+///
+/// # Type parameters
+///
+/// * `FREQ`: The frequency of the CPU in Hz.
+#[inline]
+pub fn cycles_to_us<const FREQ: u32>(cycles: u64) -> u32 {
+    (cycles as f32 * (1_000_000_f32 / FREQ as f32)) as u32
+}
 
-/// Profiler based on [`SysTick`](cortex_m::peripheral::SYST)
-/// for Cortex-M microcontrollers.
+/// Converts the number of CPU cycles to milliseconds without overflowing.
 ///
-/// # Example
+/// # Parameters
+///
+/// * `cycles`: The number of CPU cycles.
+///
+/// # Returns
+///
+/// The number of milliseconds, truncated.
+///
+/// # Type parameters
+///
+/// * `FREQ`: The frequency of the CPU in Hz.
+#[inline]
+pub const fn cycles_to_ms_u64<const FREQ: u32>(cycles: u64) -> u64 {
+    scale_cycles(cycles, FREQ, 1_000)
+}
+
+/// Converts the number of CPU cycles to microseconds without overflowing.
+///
+/// # Parameters
+///
+/// * `cycles`: The number of CPU cycles.
+///
+/// # Returns
+///
+/// The number of microseconds, truncated.
+///
+/// # Type parameters
+///
+/// * `FREQ`: The frequency of the CPU in Hz.
+#[inline]
+pub const fn cycles_to_us_u64<const FREQ: u32>(cycles: u64) -> u64 {
+    scale_cycles(cycles, FREQ, 1_000_000)
+}
+
+/// Converts the number of CPU cycles to nanoseconds.
+///
+/// # Parameters
+///
+/// * `cycles`: The number of CPU cycles.
+///
+/// # Returns
+///
+/// The number of nanoseconds, truncated.
+///
+/// # Type parameters
+///
+/// * `FREQ`: The frequency of the CPU in Hz.
+#[inline]
+pub const fn cycles_to_ns<const FREQ: u32>(cycles: u64) -> u64 {
+    scale_cycles(cycles, FREQ, 1_000_000_000)
+}
+
+/// Converts the number of CPU cycles to milliseconds, for a CPU frequency
+/// known only at runtime.
+///
+/// # Parameters
+///
+/// * `cycles`: The number of CPU cycles.
+/// * `freq`: The frequency of the CPU in Hz.
+///
+/// # Returns
+///
+/// The number of milliseconds, truncated.
+#[inline]
+pub const fn cycles_to_ms_rt(cycles: u64, freq: u32) -> u64 {
+    scale_cycles(cycles, freq, 1_000)
+}
+
+/// Converts the number of CPU cycles to microseconds, for a CPU frequency
+/// known only at runtime.
+///
+/// # Parameters
+///
+/// * `cycles`: The number of CPU cycles.
+/// * `freq`: The frequency of the CPU in Hz.
+///
+/// # Returns
 ///
-/// ```no_run
-/// use cortex_m::peripheral::Peripherals;
-/// use cortex_m_rt::entry;
+/// The number of microseconds, truncated.
+#[inline]
+pub const fn cycles_to_us_rt(cycles: u64, freq: u32) -> u64 {
+    scale_cycles(cycles, freq, 1_000_000)
+}
+
+/// Converts the number of CPU cycles to nanoseconds, for a CPU frequency
+/// known only at runtime.
+///
+/// # Parameters
+///
+/// * `cycles`: The number of CPU cycles.
+/// * `freq`: The frequency of the CPU in Hz.
+///
+/// # Returns
+///
+/// The number of nanoseconds, truncated.
+#[inline]
+pub const fn cycles_to_ns_rt(cycles: u64, freq: u32) -> u64 {
+    scale_cycles(cycles, freq, 1_000_000_000)
+}
+
+/// Scales a number of CPU cycles to the given number of units per second,
+/// using a 128-bit intermediate product so that it never overflows.
+#[inline]
+const fn scale_cycles(cycles: u64, freq: u32, units_per_sec: u64) -> u64 {
+    (cycles as u128 * units_per_sec as u128 / freq as u128) as u64
+}
+
+/// A span of time measured by the profiler, split into whole seconds and
+/// nanoseconds, similar to [`core::time::Duration`] but built from CPU cycles.
 ///
-/// use profiler::{cycles_to_ms, Profiler};
+/// The full 64-bit cycle count is preserved, so multi-minute (or multi-day)
+/// profiling sessions do not wrap.
 ///
-/// let cp = Peripherals::take().unwrap();
-/// let mut syst = cp.SYST;
-/// let profiler = Profiler::new(syst);
+/// # Example
 ///
-/// // Do some work.
+/// ```
+/// use profiler::CycleDuration;
 ///
-/// let cycles = profiler.cycles();
-/// let duration_ms = cycles_to_ms::<1_000_000>(cycles);
+/// let duration = CycleDuration::from_cycles::<216_000_000>(324_000_000);
+/// assert_eq!(duration.as_secs(), 1);
+/// assert_eq!(duration.subsec_nanos(), 500_000_000);
+/// assert_eq!(duration.as_millis(), 1_500);
 /// ```
-pub struct Profiler {
-    systick: SYST,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct CycleDuration {
+    /// The number of whole seconds.
+    secs: u64,
+
+    /// The fractional part of the duration in nanoseconds.
+    nanos: u32,
 }
 
-impl Profiler {
-    /// Setup the SysTick counter and start counting CPU cycles.
+impl CycleDuration {
+    /// Creates a new duration from a number of CPU cycles.
     ///
     /// # Parameters
     ///
-    /// * `systick`: The [`SysTick`] peripheral.
-    pub fn new(mut systick: SYST) -> Self {
-        // Reset the rollover count.
-        ROLLOVER_COUNT.store(0, Ordering::Relaxed);
-
-        // Configure SysTick counter.
-        systick.disable_counter();
-        systick.set_clock_source(SystClkSource::Core);
-        systick.clear_current();
-        systick.set_reload(SYSTICK_RELOAD);
-        systick.enable_counter();
-
-        // Enable SysTick interrupt.
-        systick.enable_interrupt();
-
-        Self { systick }
-    }
-
-    /// Releases the system timer (SysTick) resource
-    pub fn free(mut self) -> SYST {
-        // Disable SysTick interrupt.
-        self.systick.disable_interrupt();
-
-        self.systick
+    /// * `cycles`: The number of CPU cycles.
+    ///
+    /// # Type parameters
+    ///
+    /// * `FREQ`: The frequency of the CPU in Hz.
+    #[inline]
+    pub const fn from_cycles<const FREQ: u32>(cycles: u64) -> Self {
+        Self::from_cycles_rt(cycles, FREQ)
     }
 
-    /// Returns the number of CPU cycles since the profiler was started.
+    /// Creates a new duration from a number of CPU cycles, for a CPU frequency
+    /// known only at runtime.
     ///
-    /// # Returns
+    /// # Parameters
     ///
-    /// The number of CPU cycles since the profiler was started.
+    /// * `cycles`: The number of CPU cycles.
+    /// * `freq`: The frequency of the CPU in Hz.
     #[inline]
-    pub fn cycles(&self) -> u64 {
-        // Read the clock & ROLLOVER_COUNT. We read `SYST` twice because we need to detect
-        // if we've rolled over, and if we have make sure we have the right value for ROLLOVER_COUNT.
-        let first = SYST::get_current();
-        let rollover_count = ROLLOVER_COUNT.load(Ordering::Acquire) as u64;
-        let second = SYST::get_current();
-
-        // Since the SYSTICK counter is a count down timer, check if first is larger than second.
-        if first > second {
-            // The usual case: we did not roll over between the first and second reading,
-            // and because of that, we also know we got a valid read on ROLLOVER_COUNT.
-            rollover_count * SYSTICK_RESOLUTION + (SYSTICK_RELOAD - first) as u64
-        } else {
-            // We rolled over sometime between the first and second read. We may or may not have
-            // caught the right ROLLOVER_COUNT, so grab that again and then use the second reading.
-            let rollover_count = ROLLOVER_COUNT.load(Ordering::Acquire) as u64;
-            rollover_count * SYSTICK_RESOLUTION + (SYSTICK_RELOAD - second) as u64
+    pub const fn from_cycles_rt(cycles: u64, freq: u32) -> Self {
+        let secs = cycles / freq as u64;
+        let rem = cycles % freq as u64;
+        Self {
+            secs,
+            nanos: (rem * 1_000_000_000 / freq as u64) as u32,
         }
     }
+
+    /// Returns the number of whole seconds.
+    #[inline]
+    pub const fn as_secs(&self) -> u64 {
+        self.secs
+    }
+
+    /// Returns the fractional part of the duration in nanoseconds.
+    #[inline]
+    pub const fn subsec_nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    /// Returns the total number of whole milliseconds.
+    #[inline]
+    pub const fn as_millis(&self) -> u64 {
+        self.secs * 1_000 + (self.nanos / 1_000_000) as u64
+    }
+
+    /// Returns the total number of whole microseconds.
+    #[inline]
+    pub const fn as_micros(&self) -> u64 {
+        self.secs * 1_000_000 + (self.nanos / 1_000) as u64
+    }
+
+    /// Returns the total number of nanoseconds.
+    #[inline]
+    pub const fn as_nanos(&self) -> u128 {
+        self.secs as u128 * 1_000_000_000 + self.nanos as u128
+    }
 }
 
-#[exception]
-fn SysTick() {
-    ROLLOVER_COUNT.fetch_add(1, Ordering::Release);
+impl From<CycleDuration> for core::time::Duration {
+    fn from(duration: CycleDuration) -> Self {
+        core::time::Duration::new(duration.secs, duration.nanos)
+    }
 }
 
-/// Converts the number of CPU cycles to milliseconds.
+/// Converts the number of CPU cycles to a typed duration whose tick period is
+/// one CPU cycle.
 ///
 /// # Parameters
 ///
@@ -127,17 +342,17 @@ fn SysTick() {
 ///
 /// # Returns
 ///
-/// The number of milliseconds.
+/// The duration corresponding to the given number of cycles.
 ///
 /// # Type parameters
 ///
 /// * `FREQ`: The frequency of the CPU in Hz.
 #[inline]
-pub fn cycles_to_ms<const FREQ: u32>(cycles: u64) -> u32 {
-    (cycles as f32 * (1_000_f32 / FREQ as f32)) as u32
+pub const fn cycles_to_duration<const FREQ: u32>(cycles: u64) -> Duration<u64, 1, FREQ> {
+    Duration::<u64, 1, FREQ>::from_ticks(cycles)
 }
 
-/// Converts the number of CPU cycles to microseconds.
+/// Converts the number of CPU cycles to a typed duration in milliseconds.
 ///
 /// # Parameters
 ///
@@ -145,14 +360,32 @@ pub fn cycles_to_ms<const FREQ: u32>(cycles: u64) -> u32 {
 ///
 /// # Returns
 ///
-/// The number of microseconds.
+/// The duration in milliseconds, truncated.
 ///
 /// # Type parameters
 ///
 /// * `FREQ`: The frequency of the CPU in Hz.
 #[inline]
-pub fn cycles_to_us<const FREQ: u32>(cycles: u64) -> u32 {
-    (cycles as f32 * (1_000_000_f32 / FREQ as f32)) as u32
+pub const fn cycles_to_millis<const FREQ: u32>(cycles: u64) -> MillisDurationU64 {
+    cycles_to_duration::<FREQ>(cycles).convert()
+}
+
+/// Converts the number of CPU cycles to a typed duration in microseconds.
+///
+/// # Parameters
+///
+/// * `cycles`: The number of CPU cycles.
+///
+/// # Returns
+///
+/// The duration in microseconds, truncated.
+///
+/// # Type parameters
+///
+/// * `FREQ`: The frequency of the CPU in Hz.
+#[inline]
+pub const fn cycles_to_micros<const FREQ: u32>(cycles: u64) -> MicrosDurationU64 {
+    cycles_to_duration::<FREQ>(cycles).convert()
 }
 
 #[cfg(test)]
@@ -170,4 +403,68 @@ mod tests {
         assert_eq!(cycles_to_us::<1_000_000>(1_000), 1_000);
         assert_eq!(cycles_to_us::<1_000_000>(1_000_000), 1_000_000);
     }
+
+    #[test]
+    fn test_cycles_to_duration() {
+        let duration = cycles_to_duration::<216_000_000>(432_000_000);
+        assert_eq!(duration.ticks(), 432_000_000);
+        assert_eq!(duration.to_secs(), 2);
+
+        assert_eq!(cycles_to_millis::<1_000_000>(1_000_000).ticks(), 1_000);
+        assert_eq!(
+            cycles_to_millis::<216_000_000>(216_000_000 * 3_600).ticks(),
+            3_600_000
+        );
+        assert_eq!(cycles_to_micros::<1_000_000>(1_000).ticks(), 1_000);
+        assert_eq!(
+            cycles_to_micros::<216_000_000>(216_000_000 * 3_600).ticks(),
+            3_600_000_000
+        );
+    }
+
+    #[test]
+    fn test_cycles_to_u64() {
+        // One hour at 216 MHz overflows the `u32` microseconds.
+        let cycles = 216_000_000 * 3_600;
+        assert_eq!(cycles_to_ms_u64::<216_000_000>(cycles), 3_600_000);
+        assert_eq!(cycles_to_us_u64::<216_000_000>(cycles), 3_600_000_000);
+        assert_eq!(cycles_to_ns::<216_000_000>(cycles), 3_600_000_000_000);
+        assert_eq!(cycles_to_ns::<216_000_000>(216), 1_000);
+        assert_eq!(
+            cycles_to_ns::<1_000_000>(u64::MAX / 1_000),
+            u64::MAX / 1_000 * 1_000
+        );
+    }
+
+    #[test]
+    fn test_cycle_duration() {
+        let duration = CycleDuration::from_cycles::<216_000_000>(216_000_000 * 600 + 108);
+        assert_eq!(duration.as_secs(), 600);
+        assert_eq!(duration.subsec_nanos(), 500);
+        assert_eq!(duration.as_millis(), 600_000);
+        assert_eq!(duration.as_micros(), 600_000_000);
+        assert_eq!(duration.as_nanos(), 600_000_000_500);
+        assert_eq!(
+            core::time::Duration::from(duration),
+            core::time::Duration::new(600, 500)
+        );
+
+        let duration = CycleDuration::from_cycles::<1_000_000>(u64::MAX);
+        assert_eq!(duration.as_secs(), u64::MAX / 1_000_000);
+    }
+
+    #[test]
+    fn test_cycles_to_rt() {
+        assert_eq!(cycles_to_ms_rt(48_000_000, 48_000_000), 1_000);
+        assert_eq!(cycles_to_us_rt(48_000_000, 48_000_000), 1_000_000);
+        assert_eq!(cycles_to_ns_rt(48, 48_000_000), 1_000);
+        assert_eq!(
+            cycles_to_us_rt(216_000_000 * 3_600, 216_000_000),
+            cycles_to_us_u64::<216_000_000>(216_000_000 * 3_600)
+        );
+        assert_eq!(
+            CycleDuration::from_cycles_rt(72_000_000, 48_000_000),
+            CycleDuration::from_cycles::<48_000_000>(72_000_000)
+        );
+    }
 }
@@ -0,0 +1,124 @@
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+
+use crate::Profiler;
+
+/// Profiler handle that can be shared between tasks and interrupt handlers.
+///
+/// The handle is meant to be placed in a `static` and initialized once with
+/// a [`Profiler`]; afterwards, every context can read the cycle counter, e.g.
+/// to timestamp events from an interrupt handler. Access to the inner profiler
+/// is guarded by a critical section.
+///
+/// # Example
+///
+/// ```no_run
+/// use cortex_m::peripheral::Peripherals;
+///
+/// use profiler::{Profiler, StaticProfiler};
+///
+/// static PROFILER: StaticProfiler = StaticProfiler::new();
+///
+/// let cp = Peripherals::take().unwrap();
+/// PROFILER.init(Profiler::new(cp.SYST, 1_000_000)).ok().unwrap();
+///
+/// // From any task or interrupt handler.
+/// let cycles = PROFILER.cycles();
+/// ```
+pub struct StaticProfiler {
+    inner: Mutex<RefCell<Option<Profiler>>>,
+}
+
+impl Default for StaticProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StaticProfiler {
+    /// Creates a new, uninitialized handle.
+    pub const fn new() -> Self {
+        Self {
+            inner: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// Stores the profiler in the handle.
+    ///
+    /// # Parameters
+    ///
+    /// * `profiler`: The profiler to be shared.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the handle has been initialized.
+    /// * `Err(profiler)` - If the handle was already initialized, giving the
+    ///   profiler back to the caller.
+    pub fn init(&self, profiler: Profiler) -> Result<(), Profiler> {
+        critical_section::with(|cs| {
+            let mut inner = self.inner.borrow_ref_mut(cs);
+            if inner.is_some() {
+                return Err(profiler);
+            }
+            *inner = Some(profiler);
+            Ok(())
+        })
+    }
+
+    /// Returns whether the handle has been initialized.
+    pub fn is_initialized(&self) -> bool {
+        critical_section::with(|cs| self.inner.borrow_ref(cs).is_some())
+    }
+
+    /// Returns the number of CPU cycles since the profiler was started.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(cycles)` - The number of CPU cycles since the profiler was started.
+    /// * `None` - If the handle has not been initialized yet.
+    #[inline]
+    pub fn cycles(&self) -> Option<u64> {
+        self.with(|profiler| profiler.cycles())
+    }
+
+    /// Executes the given closure with exclusive access to the profiler.
+    ///
+    /// The closure runs inside a critical section, so it should be short.
+    ///
+    /// # Parameters
+    ///
+    /// * `f`: The closure to be executed.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(res)` - The value returned by the closure.
+    /// * `None` - If the handle has not been initialized yet.
+    pub fn with<R>(&self, f: impl FnOnce(&mut Profiler) -> R) -> Option<R> {
+        critical_section::with(|cs| self.inner.borrow_ref_mut(cs).as_mut().map(f))
+    }
+
+    /// Takes the profiler out of the handle, leaving it uninitialized.
+    ///
+    /// # Returns
+    ///
+    /// The profiler, if the handle was initialized.
+    pub fn take(&self) -> Option<Profiler> {
+        critical_section::with(|cs| self.inner.borrow_ref_mut(cs).take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static PROFILER: StaticProfiler = StaticProfiler::new();
+
+    #[test]
+    fn test_uninitialized() {
+        assert!(!PROFILER.is_initialized());
+        assert_eq!(PROFILER.cycles(), None);
+        assert_eq!(PROFILER.with(|profiler| profiler.frequency()), None);
+        assert!(PROFILER.take().is_none());
+    }
+}
@@ -0,0 +1,175 @@
+use core::ptr;
+
+/// The pattern written on the unused stack at initialization.
+const STACK_PAINT: u32 = 0xCDCD_CDCD;
+
+/// The number of bytes below the current stack pointer that are left
+/// untouched when painting the stack, to avoid overwriting the frame of the
+/// painting function itself.
+#[cfg(all(target_arch = "arm", target_os = "none"))]
+const STACK_SAFETY_MARGIN: usize = 256;
+
+/// Stack usage profiler.
+///
+/// At initialization, the free part of the stack is painted with a known
+/// pattern; the high-water mark is then found by looking for the lowest word
+/// that does not hold the pattern anymore.
+///
+/// # Example
+///
+/// Only available on Cortex-M targets:
+///
+/// ```ignore
+/// use profiler::StackProfiler;
+///
+/// let stack = unsafe { StackProfiler::new() };
+///
+/// // Do some work, e.g. solve the model.
+///
+/// let used_bytes = stack.high_water_mark();
+/// ```
+#[derive(Debug)]
+pub struct StackProfiler {
+    /// The lowest address of the painted region.
+    bottom: *mut u32,
+
+    /// The highest address (exclusive) of the painted region.
+    painted_top: *mut u32,
+
+    /// The highest address (exclusive) of the stack.
+    top: *const u32,
+}
+
+impl StackProfiler {
+    /// Paints the free part of the stack and returns a profiler for it.
+    ///
+    /// The stack is assumed to span from the end of the static data
+    /// (`__sheap`) to the initial stack pointer (`_stack_start`), as laid
+    /// out by `cortex-m-rt`.
+    ///
+    /// # Safety
+    ///
+    /// The memory between the end of the static data and the current stack
+    /// pointer must not be in use, i.e. no heap must be placed there.
+    #[cfg(all(target_arch = "arm", target_os = "none"))]
+    pub unsafe fn new() -> Self {
+        extern "C" {
+            static mut __sheap: u32;
+            static _stack_start: u32;
+        }
+
+        let marker = 0u32;
+        let sp = ptr::addr_of!(marker) as usize - STACK_SAFETY_MARGIN;
+
+        let bottom = ptr::addr_of_mut!(__sheap);
+        let painted_top = (sp & !0b11) as *mut u32;
+        Self::from_region(bottom, painted_top, ptr::addr_of!(_stack_start))
+    }
+
+    /// Paints the given region and returns a profiler for a stack growing
+    /// downwards from `top`.
+    ///
+    /// # Parameters
+    ///
+    /// * `bottom`: The lowest address of the region to paint.
+    /// * `painted_top`: The highest address (exclusive) of the region to paint.
+    /// * `top`: The highest address (exclusive) of the stack.
+    ///
+    /// # Safety
+    ///
+    /// The region `[bottom, painted_top)` must be valid for writes and not in
+    /// use, and `painted_top` must not be above `top`.
+    pub unsafe fn from_region(bottom: *mut u32, painted_top: *mut u32, top: *const u32) -> Self {
+        let profiler = Self {
+            bottom,
+            painted_top,
+            top,
+        };
+        profiler.paint();
+        profiler
+    }
+
+    /// Paints the region again, resetting the high-water mark.
+    ///
+    /// # Safety
+    ///
+    /// The painted region must not be in use.
+    pub unsafe fn repaint(&mut self) {
+        self.paint();
+    }
+
+    /// Returns the size of the stack.
+    ///
+    /// # Returns
+    ///
+    /// The size of the stack in bytes.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.top as usize - self.bottom as usize
+    }
+
+    /// Returns the maximum stack usage since the stack was painted.
+    ///
+    /// # Returns
+    ///
+    /// The maximum number of bytes used on the stack.
+    pub fn high_water_mark(&self) -> usize {
+        let mut addr = self.bottom;
+        while addr < self.painted_top {
+            // SAFETY: the address lies in the painted region.
+            if unsafe { ptr::read_volatile(addr) } != STACK_PAINT {
+                break;
+            }
+            addr = addr.wrapping_add(1);
+        }
+        self.top as usize - addr as usize
+    }
+
+    /// Writes the pattern on the whole painted region.
+    unsafe fn paint(&self) {
+        let mut addr = self.bottom;
+        while addr < self.painted_top {
+            ptr::write_volatile(addr, STACK_PAINT);
+            addr = addr.add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_high_water_mark() {
+        let mut memory = [0u32; 64];
+        let bottom = memory.as_mut_ptr();
+        let top = bottom.wrapping_add(64);
+
+        let mut stack = unsafe { StackProfiler::from_region(bottom, top, top) };
+        assert_eq!(stack.size(), 64 * 4);
+        assert_eq!(stack.high_water_mark(), 0);
+
+        // Simulate a stack growing downwards.
+        for i in 54..64 {
+            unsafe { ptr::write_volatile(bottom.add(i), i as u32) };
+        }
+        assert_eq!(stack.high_water_mark(), 10 * 4);
+
+        unsafe { stack.repaint() };
+        assert_eq!(stack.high_water_mark(), 0);
+    }
+
+    #[test]
+    fn test_partially_painted() {
+        let mut memory = [0u32; 64];
+        let bottom = memory.as_mut_ptr();
+        let painted_top = bottom.wrapping_add(48);
+        let top = bottom.wrapping_add(64);
+
+        let stack = unsafe { StackProfiler::from_region(bottom, painted_top, top) };
+        assert_eq!(stack.high_water_mark(), 16 * 4);
+
+        unsafe { ptr::write_volatile(bottom.add(40), 0) };
+        assert_eq!(stack.high_water_mark(), 24 * 4);
+    }
+}
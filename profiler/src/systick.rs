@@ -0,0 +1,607 @@
+pub use cortex_m::peripheral::syst::SystClkSource;
+
+use core::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
+
+use cortex_m::peripheral::SYST;
+use cortex_m_rt::exception;
+use embedded_hal::delay::DelayNs;
+use fugit::Duration;
+
+use crate::{cycles_to_duration, CycleDuration};
+
+/// Tracker of `systick` cycle count overflows to extend systick's 24 bit timer.
+///
+/// The count is 64 bits wide, but 64-bit atomics are not available on
+/// Cortex-M, so it is split in a low and a high half. The halves are only
+/// written by the [`SysTick`](cortex_m::peripheral::scb::Exception::SysTick)
+/// exception, see [`increment_rollover_count`] and [`read_rollover_count`].
+static ROLLOVER_LOW: AtomicU32 = AtomicU32::new(0);
+
+/// High half of the rollover count, see [`ROLLOVER_LOW`].
+static ROLLOVER_HIGH: AtomicU32 = AtomicU32::new(0);
+
+/// The frequency of the core clock in Hz, shared with the code that reads the
+/// counter without holding the [`Profiler`] (e.g. the `defmt` timestamp).
+pub(crate) static CORE_FREQ: AtomicU32 = AtomicU32::new(0);
+
+/// The default reload value of the [`systick`](cortex_m::peripheral::SYST)
+/// peripheral. Also is the max it can go: 2^24 - 1.
+pub const SYSTICK_MAX_RELOAD: u32 = 0x00FF_FFFF;
+
+/// The reload value currently configured in the [`systick`](cortex_m::peripheral::SYST)
+/// peripheral: the counter rolls over every `reload + 1` cycles.
+static RELOAD: AtomicU32 = AtomicU32::new(SYSTICK_MAX_RELOAD);
+
+/// The divider between the core clock and the SysTick clock source: each tick
+/// of the SysTick counter accounts for this many CPU cycles.
+static DIVIDER: AtomicU32 = AtomicU32::new(1);
+
+/// The divider applied to the core clock when the SysTick uses the
+/// [`External`](SystClkSource::External) clock source.
+///
+/// This is the AHB/8 reference clock of STM32 devices; other vendors may use a
+/// different reference clock.
+pub const EXTERNAL_CLOCK_DIVIDER: u32 = 8;
+
+/// The function invoked on every SysTick exception, stored as a type-erased
+/// pointer (null if no hook is installed).
+static TICK_HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// The number of empty measurements performed to calibrate the overhead.
+const CALIBRATION_ROUNDS: usize = 16;
+
+/// Profiler based on [`SysTick`](cortex_m::peripheral::SYST)
+/// for Cortex-M microcontrollers.
+///
+/// # Example
+///
+/// ```no_run
+/// use cortex_m::peripheral::Peripherals;
+/// use cortex_m_rt::entry;
+/// use embedded_hal::delay::DelayNs;
+///
+/// use profiler::{cycles_to_ms, Profiler};
+///
+/// let cp = Peripherals::take().unwrap();
+/// let mut syst = cp.SYST;
+/// let mut profiler = Profiler::new(syst, 1_000_000);
+///
+/// // Wait for some time.
+/// profiler.delay_ms(10);
+///
+/// // Measure the cost of the measurement itself.
+/// profiler.calibrate_overhead();
+///
+/// // Do some work.
+/// let ((), cycles) = profiler.measure(|| {
+///     // ...
+/// });
+/// let duration_ms = cycles_to_ms::<1_000_000>(cycles);
+/// ```
+pub struct Profiler {
+    systick: SYST,
+
+    /// The frequency of the core clock in Hz.
+    freq: u32,
+
+    /// The number of CPU cycles spent by the measurement itself, subtracted
+    /// from every measurement.
+    overhead: u64,
+
+    /// The configuration of the SysTick before the profiler took it over,
+    /// restored by [`Profiler::free`].
+    previous: SysTickConfig,
+}
+
+impl Profiler {
+    /// Setup the SysTick counter and start counting CPU cycles.
+    ///
+    /// # Parameters
+    ///
+    /// * `systick`: The [`SysTick`] peripheral.
+    /// * `freq`: The frequency of the core clock in Hz, used to implement delays
+    ///   and runtime conversions. See [`Profiler::set_frequency`].
+    pub fn new(systick: SYST, freq: u32) -> Self {
+        Self::with_reload(systick, freq, SYSTICK_MAX_RELOAD, None)
+    }
+
+    /// Setup the SysTick counter with the given clock source and start counting
+    /// CPU cycles.
+    ///
+    /// With the [`External`](SystClkSource::External) source the SysTick is
+    /// clocked at the core frequency divided by [`EXTERNAL_CLOCK_DIVIDER`]:
+    /// this is needed on parts whose clock gating options make the core clock
+    /// source unreliable. The counter is still expressed in CPU cycles, so all
+    /// conversions are unaffected, but the resolution is reduced to
+    /// [`Profiler::resolution`] cycles.
+    ///
+    /// # Parameters
+    ///
+    /// * `systick`: The [`SysTick`] peripheral.
+    /// * `source`: The clock source of the SysTick counter.
+    /// * `freq`: The frequency of the core clock in Hz.
+    pub fn new_with_source(systick: SYST, source: SystClkSource, freq: u32) -> Self {
+        Self::configure(systick, source, freq, SYSTICK_MAX_RELOAD, None)
+    }
+
+    /// Setup the SysTick counter with a custom reload value and start counting
+    /// CPU cycles.
+    ///
+    /// This allows the profiler to coexist with an RTOS that needs a periodic
+    /// tick: e.g., a reload of `freq / 1_000 - 1` fires the SysTick exception
+    /// every millisecond, and the RTOS tick handler can be installed as `hook`.
+    /// The counter is still extended to 64 bits and all conversions remain
+    /// expressed in CPU cycles.
+    ///
+    /// # Parameters
+    ///
+    /// * `systick`: The [`SysTick`] peripheral.
+    /// * `freq`: The frequency of the core clock in Hz.
+    /// * `reload`: The reload value, between 1 and [`SYSTICK_MAX_RELOAD`].
+    /// * `hook`: An optional function invoked on every SysTick exception.
+    ///
+    /// # Panics
+    ///
+    /// If `reload` is zero or greater than [`SYSTICK_MAX_RELOAD`].
+    pub fn with_reload(systick: SYST, freq: u32, reload: u32, hook: Option<fn()>) -> Self {
+        Self::configure(systick, SystClkSource::Core, freq, reload, hook)
+    }
+
+    /// Configures the SysTick counter and the shared state of the profiler.
+    fn configure(
+        mut systick: SYST,
+        source: SystClkSource,
+        freq: u32,
+        reload: u32,
+        hook: Option<fn()>,
+    ) -> Self {
+        assert!(
+            reload > 0 && reload <= SYSTICK_MAX_RELOAD,
+            "invalid SysTick reload value"
+        );
+
+        // Reset the rollover count.
+        ROLLOVER_HIGH.store(0, Ordering::Relaxed);
+        ROLLOVER_LOW.store(0, Ordering::Relaxed);
+        CORE_FREQ.store(freq, Ordering::Relaxed);
+        RELOAD.store(reload, Ordering::Relaxed);
+        DIVIDER.store(clock_divider(source), Ordering::Relaxed);
+        TICK_HOOK.store(
+            hook.map_or(core::ptr::null_mut(), |hook| hook as *mut ()),
+            Ordering::Release,
+        );
+
+        // Save the current configuration, so that it can be restored.
+        let previous = SysTickConfig::read(&mut systick);
+
+        // Configure SysTick counter.
+        systick.disable_counter();
+        systick.set_clock_source(source);
+        systick.clear_current();
+        systick.set_reload(reload);
+        systick.enable_counter();
+
+        // Enable SysTick interrupt.
+        systick.enable_interrupt();
+
+        Self {
+            systick,
+            freq,
+            overhead: 0,
+            previous,
+        }
+    }
+
+    /// Releases the system timer (SysTick) resource, restoring the
+    /// configuration it had before the profiler was created.
+    pub fn free(mut self) -> SYST {
+        // Disable SysTick interrupt.
+        self.systick.disable_interrupt();
+        CORE_FREQ.store(0, Ordering::Relaxed);
+        TICK_HOOK.store(core::ptr::null_mut(), Ordering::Release);
+
+        self.previous.apply(&mut self.systick);
+        self.systick
+    }
+
+    /// Returns the configuration of the SysTick before the profiler took it
+    /// over, if the SysTick was already in use.
+    ///
+    /// The profiler reconfigures the SysTick regardless, e.g. clobbering the
+    /// settings of a HAL delay timer, and restores them in [`Profiler::free`];
+    /// this can be used to detect and report the conflict.
+    ///
+    /// # Returns
+    ///
+    /// The previous configuration if the SysTick counter was enabled, `None`
+    /// otherwise.
+    pub fn previous_config(&self) -> Option<SysTickConfig> {
+        self.previous.counter_enabled.then_some(self.previous)
+    }
+
+    /// Returns the resolution of the profiler, i.e. the number of CPU cycles
+    /// per tick of the SysTick counter.
+    ///
+    /// # Returns
+    ///
+    /// The resolution of the profiler in CPU cycles.
+    pub fn resolution(&self) -> u32 {
+        DIVIDER.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of CPU cycles since the profiler was started.
+    ///
+    /// # Returns
+    ///
+    /// The number of CPU cycles since the profiler was started.
+    #[inline]
+    pub fn cycles(&self) -> u64 {
+        read_cycles()
+    }
+
+    /// Returns the number of CPU cycles elapsed since `start`, net of the
+    /// calibrated measurement overhead.
+    ///
+    /// # Parameters
+    ///
+    /// * `start`: A value previously returned by [`Profiler::cycles`].
+    ///
+    /// # Returns
+    ///
+    /// The number of CPU cycles elapsed since `start`.
+    #[inline]
+    pub fn cycles_since(&self, start: u64) -> u64 {
+        (self.cycles() - start).saturating_sub(self.overhead)
+    }
+
+    /// Executes the given closure and measures the number of CPU cycles it
+    /// took, net of the calibrated measurement overhead.
+    ///
+    /// # Parameters
+    ///
+    /// * `f`: The closure to be measured.
+    ///
+    /// # Returns
+    ///
+    /// The value returned by the closure and the number of CPU cycles it took.
+    #[inline]
+    pub fn measure<R>(&self, f: impl FnOnce() -> R) -> (R, u64) {
+        let start = self.cycles();
+        let res = f();
+        (res, self.cycles_since(start))
+    }
+
+    /// Measures the cost of reading the cycle counter and timing an empty
+    /// closure, and subtracts it from all subsequent measurements.
+    ///
+    /// The overhead is taken as the minimum over several runs, so that the
+    /// calibration is not affected by a SysTick exception firing in between.
+    ///
+    /// # Returns
+    ///
+    /// The calibrated overhead in CPU cycles.
+    pub fn calibrate_overhead(&mut self) -> u64 {
+        self.overhead = 0;
+
+        let mut overhead = u64::MAX;
+        for _ in 0..CALIBRATION_ROUNDS {
+            let (_, cycles) = self.measure(|| core::hint::black_box(()));
+            overhead = overhead.min(cycles);
+        }
+
+        self.overhead = overhead;
+        overhead
+    }
+
+    /// Returns the calibrated measurement overhead.
+    ///
+    /// # Returns
+    ///
+    /// The number of CPU cycles subtracted from every measurement, zero if
+    /// [`Profiler::calibrate_overhead`] has never been called.
+    #[inline]
+    pub fn overhead(&self) -> u64 {
+        self.overhead
+    }
+
+    /// Returns the frequency of the core clock currently assumed by the
+    /// profiler.
+    ///
+    /// # Returns
+    ///
+    /// The frequency of the core clock in Hz.
+    #[inline]
+    pub fn frequency(&self) -> u32 {
+        self.freq
+    }
+
+    /// Updates the frequency of the core clock, e.g. after switching between
+    /// low-power and burst modes at runtime.
+    ///
+    /// Cycles counted before the change are not rescaled: measurements that
+    /// span a frequency change must be converted by the caller.
+    ///
+    /// # Parameters
+    ///
+    /// * `freq`: The new frequency of the core clock in Hz.
+    #[inline]
+    pub fn set_frequency(&mut self, freq: u32) {
+        self.freq = freq;
+        CORE_FREQ.store(freq, Ordering::Relaxed);
+    }
+
+    /// Converts the number of CPU cycles to a duration using the current
+    /// frequency of the core clock.
+    ///
+    /// # Parameters
+    ///
+    /// * `cycles`: The number of CPU cycles.
+    ///
+    /// # Returns
+    ///
+    /// The duration corresponding to the given number of cycles.
+    #[inline]
+    pub fn duration(&self, cycles: u64) -> CycleDuration {
+        CycleDuration::from_cycles_rt(cycles, self.freq)
+    }
+
+    /// Returns the time elapsed since the profiler was started as a typed
+    /// duration whose tick period is one CPU cycle.
+    ///
+    /// # Returns
+    ///
+    /// The elapsed time since the profiler was started.
+    ///
+    /// # Type parameters
+    ///
+    /// * `FREQ`: The frequency of the CPU in Hz.
+    #[inline]
+    pub fn elapsed<const FREQ: u32>(&self) -> Duration<u64, 1, FREQ> {
+        cycles_to_duration::<FREQ>(self.cycles())
+    }
+}
+
+/// Snapshot of the configuration of the [`SysTick`](cortex_m::peripheral::SYST)
+/// peripheral.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SysTickConfig {
+    /// The clock source of the counter.
+    pub clock_source: SystClkSource,
+
+    /// Whether the counter is enabled.
+    pub counter_enabled: bool,
+
+    /// Whether the SysTick exception is enabled.
+    pub interrupt_enabled: bool,
+
+    /// The reload value of the counter.
+    pub reload: u32,
+}
+
+impl SysTickConfig {
+    /// Reads the current configuration of the SysTick.
+    ///
+    /// # Parameters
+    ///
+    /// * `systick`: The [`SysTick`](cortex_m::peripheral::SYST) peripheral.
+    pub fn read(systick: &mut SYST) -> Self {
+        Self {
+            clock_source: systick.get_clock_source(),
+            counter_enabled: systick.is_counter_enabled(),
+            interrupt_enabled: systick.is_interrupt_enabled(),
+            reload: SYST::get_reload(),
+        }
+    }
+
+    /// Applies the configuration to the SysTick, restarting the counter from
+    /// the reload value.
+    ///
+    /// # Parameters
+    ///
+    /// * `systick`: The [`SysTick`](cortex_m::peripheral::SYST) peripheral.
+    pub fn apply(&self, systick: &mut SYST) {
+        systick.disable_interrupt();
+        systick.disable_counter();
+        systick.set_clock_source(self.clock_source);
+        systick.set_reload(self.reload);
+        systick.clear_current();
+
+        if self.counter_enabled {
+            systick.enable_counter();
+        }
+        if self.interrupt_enabled {
+            systick.enable_interrupt();
+        }
+    }
+}
+
+/// Reads the extended 64-bit cycle counter.
+///
+/// This only relies on the SysTick current value register and on the rollover
+/// count, so it can be used without holding the [`Profiler`].
+#[inline]
+pub(crate) fn read_cycles() -> u64 {
+    // Read the clock & rollover count. We read `SYST` twice because we need to detect
+    // if we've rolled over, and if we have make sure we have the right value for the rollover count.
+    let reload = RELOAD.load(Ordering::Relaxed);
+    let divider = DIVIDER.load(Ordering::Relaxed) as u64;
+    let first = SYST::get_current();
+    let rollover_count = read_rollover_count();
+    let second = SYST::get_current();
+
+    // Since the SYSTICK counter is a count down timer, check if first is larger than second.
+    if first > second {
+        // The usual case: we did not roll over between the first and second reading,
+        // and because of that, we also know we got a valid read on the rollover count.
+        extend_cycles(rollover_count, reload, first) * divider
+    } else {
+        // We rolled over sometime between the first and second read. We may or may not have
+        // caught the right rollover count, so grab that again and then use the second reading.
+        let rollover_count = read_rollover_count();
+        extend_cycles(rollover_count, reload, second) * divider
+    }
+}
+
+/// Reads the 64-bit rollover count.
+///
+/// The high half is read before and after the low half, and the read is
+/// retried if it changed in between, i.e. if the low half wrapped around.
+/// The two halves are updated atomically by [`increment_rollover_count`], so
+/// a reader preempting the update never observes a torn count.
+#[inline]
+fn read_rollover_count() -> u64 {
+    loop {
+        let high = ROLLOVER_HIGH.load(Ordering::Acquire);
+        let low = ROLLOVER_LOW.load(Ordering::Acquire);
+        if ROLLOVER_HIGH.load(Ordering::Acquire) == high {
+            return join_rollover_count(high, low);
+        }
+    }
+}
+
+/// Increments the 64-bit rollover count.
+///
+/// This must only be called by the SysTick exception, which is the only writer
+/// of the count: this allows to use plain loads and stores, which are also
+/// available on Armv6-M. When the low half wraps around, both halves are
+/// updated in a critical section, so that a reader in a higher priority
+/// interrupt, e.g. the `defmt` timestamp, cannot observe the new high half
+/// with the old low half, i.e. a count ahead by 2^32.
+#[inline]
+fn increment_rollover_count() {
+    let low = ROLLOVER_LOW.load(Ordering::Relaxed).wrapping_add(1);
+    if low == 0 {
+        critical_section::with(|_| {
+            let high = ROLLOVER_HIGH.load(Ordering::Relaxed);
+            ROLLOVER_HIGH.store(high.wrapping_add(1), Ordering::Release);
+            ROLLOVER_LOW.store(low, Ordering::Release);
+        });
+    } else {
+        ROLLOVER_LOW.store(low, Ordering::Release);
+    }
+}
+
+/// Combines the two halves of the rollover count.
+#[inline]
+fn join_rollover_count(high: u32, low: u32) -> u64 {
+    (high as u64) << 32 | low as u64
+}
+
+/// Returns the number of CPU cycles per tick of the given SysTick clock source.
+#[inline]
+fn clock_divider(source: SystClkSource) -> u32 {
+    match source {
+        SystClkSource::Core => 1,
+        SystClkSource::External => EXTERNAL_CLOCK_DIVIDER,
+    }
+}
+
+/// Combines the rollover count and the current value of the SysTick counter
+/// into the number of cycles elapsed since the counter was started.
+///
+/// # Parameters
+///
+/// * `rollover_count`: The number of times the counter rolled over.
+/// * `reload`: The reload value of the counter.
+/// * `current`: The current value of the count down counter.
+#[inline]
+fn extend_cycles(rollover_count: u64, reload: u32, current: u32) -> u64 {
+    rollover_count
+        .wrapping_mul(reload as u64 + 1)
+        .wrapping_add((reload - current) as u64)
+}
+
+impl DelayNs for Profiler {
+    /// Pauses execution for at least `ns` nanoseconds by busy-waiting on the
+    /// extended cycle counter.
+    #[inline]
+    fn delay_ns(&mut self, ns: u32) {
+        let start = self.cycles();
+        let cycles = ns_to_cycles(ns, self.freq);
+        while self.cycles() - start < cycles {}
+    }
+}
+
+#[exception]
+fn SysTick() {
+    increment_rollover_count();
+
+    let hook = TICK_HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: the pointer has been obtained from a `fn()` in `with_reload`.
+        let hook = unsafe { core::mem::transmute::<*mut (), fn()>(hook) };
+        hook();
+    }
+}
+
+/// Converts a number of nanoseconds to the number of CPU cycles needed to
+/// elapse at least that amount of time.
+///
+/// # Parameters
+///
+/// * `ns`: The number of nanoseconds.
+/// * `freq`: The frequency of the CPU in Hz.
+///
+/// # Returns
+///
+/// The number of CPU cycles, rounded up.
+#[inline]
+fn ns_to_cycles(ns: u32, freq: u32) -> u64 {
+    (ns as u64 * freq as u64).div_ceil(1_000_000_000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ns_to_cycles() {
+        assert_eq!(ns_to_cycles(0, 216_000_000), 0);
+        assert_eq!(ns_to_cycles(1_000, 1_000_000), 1);
+        assert_eq!(ns_to_cycles(1, 1_000_000), 1);
+        assert_eq!(ns_to_cycles(1_000_000_000, 216_000_000), 216_000_000);
+        assert_eq!(ns_to_cycles(u32::MAX, 216_000_000), 927_712_936);
+    }
+
+    #[test]
+    fn test_extend_cycles() {
+        assert_eq!(extend_cycles(0, SYSTICK_MAX_RELOAD, SYSTICK_MAX_RELOAD), 0);
+        assert_eq!(extend_cycles(0, SYSTICK_MAX_RELOAD, 0), 0x00FF_FFFF);
+        assert_eq!(
+            extend_cycles(1, SYSTICK_MAX_RELOAD, SYSTICK_MAX_RELOAD),
+            0x0100_0000
+        );
+
+        // 1 ms tick at 216 MHz.
+        let reload = 216_000_000 / 1_000 - 1;
+        assert_eq!(extend_cycles(1_000, reload, reload), 216_000_000);
+        assert_eq!(extend_cycles(1_000, reload, reload - 10), 216_000_010);
+
+        // Beyond 2^32 rollovers.
+        let rollover_count = join_rollover_count(1, 0);
+        assert_eq!(rollover_count, 1 << 32);
+        assert_eq!(extend_cycles(rollover_count, reload, reload), 216_000 << 32);
+    }
+
+    #[test]
+    fn test_rollover_count() {
+        ROLLOVER_HIGH.store(0, Ordering::Relaxed);
+        ROLLOVER_LOW.store(u32::MAX, Ordering::Relaxed);
+        assert_eq!(read_rollover_count(), u32::MAX as u64);
+
+        increment_rollover_count();
+        assert_eq!(read_rollover_count(), 1 << 32);
+
+        increment_rollover_count();
+        assert_eq!(read_rollover_count(), (1 << 32) + 1);
+    }
+
+    #[test]
+    fn test_clock_divider() {
+        assert_eq!(clock_divider(SystClkSource::Core), 1);
+        assert_eq!(
+            clock_divider(SystClkSource::External),
+            EXTERNAL_CLOCK_DIVIDER
+        );
+    }
+}
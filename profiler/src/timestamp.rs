@@ -0,0 +1,22 @@
+//! Global `defmt` timestamp based on the extended SysTick counter.
+//!
+//! Only one timestamp can be defined in a firmware image, so this module must
+//! not be enabled if the application already provides one.
+
+use core::sync::atomic::Ordering;
+
+use crate::{
+    cycles_to_us_rt,
+    systick::{read_cycles, CORE_FREQ},
+};
+
+/// Returns the time elapsed since the profiler was started in microseconds,
+/// or zero if the profiler has not been started yet.
+fn timestamp_us() -> u64 {
+    match CORE_FREQ.load(Ordering::Relaxed) {
+        0 => 0,
+        freq => cycles_to_us_rt(read_cycles(), freq),
+    }
+}
+
+defmt::timestamp!("{=u64:us}", timestamp_us());
@@ -0,0 +1,21 @@
+/* Memory mapping for RP2040 chip with 2 MiB of external flash */
+MEMORY
+{
+  /* NOTE K = KiBi = 1024 bytes */
+  BOOT2 : ORIGIN = 0x10000000, LENGTH = 0x100
+  FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100
+  RAM   : ORIGIN = 0x20000000, LENGTH = 256K
+}
+
+/* The second stage bootloader must be placed at the start of the flash. */
+SECTIONS
+{
+  .boot2 ORIGIN(BOOT2) :
+  {
+    KEEP(*(.boot2));
+  } > BOOT2
+} INSERT BEFORE .text;
+
+/* This is where the call stack will be allocated. */
+/* The stack is of the full descending type. */
+_stack_start = ORIGIN(RAM) + LENGTH(RAM);
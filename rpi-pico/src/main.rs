@@ -0,0 +1,192 @@
+//! Brute force algorithm split between the two cores of the RP2040.
+//!
+//! The range of concentrations is split in two chunks: core 0 solves the
+//! first one while core 1 solves the second one, then the results are merged.
+//! The currents and the results are exchanged through the inter-core FIFOs,
+//! one `f32` per word.
+
+#![no_main]
+#![no_std]
+
+use defmt_rtt as _; // global logger
+use panic_probe as _; // panic handler
+
+use rp2040_hal::{
+    self as hal,
+    clocks::init_clocks_and_plls,
+    multicore::{Multicore, Stack},
+    pac,
+    sio::SioFifo,
+    Clock, Sio, Timer, Watchdog,
+};
+
+use bioristor_lib::{
+    algorithms::{merge, Algorithm, BruteForceEquation, BruteForceParams, SaturationPolicy},
+    losses::Absolute,
+    models::{Equation, Model},
+    params::{
+        Currents, ModelParams, ModulationParams, StemResistanceInvParams, Variables, Voltages,
+    },
+    utils::FloatRange,
+};
+
+/// The second stage bootloader for the W25Q080 flash of the Pico.
+#[link_section = ".boot2"]
+#[used]
+pub static BOOT2: [u8; 256] = rp2040_boot2::BOOT_LOADER_W25Q080;
+
+const ALG_PARAMS: BruteForceParams = BruteForceParams {
+    concentration_range: FloatRange::new(1e-4, 1e-1, 10_000),
+    resistance_range: FloatRange::new(10.0, 100.0, 100),
+    saturation_policy: SaturationPolicy::Keep,
+    saturation_range: FloatRange::new(0.0, 1.0, 100),
+};
+
+const MODEL_PARAMS: ModelParams = ModelParams {
+    mod_params: ModulationParams(0.0, -0.01463, -0.32),
+    r_dry: 38.2,
+    res_params: StemResistanceInvParams(1.35e-6, 2.73e-4),
+    voltages: Voltages {
+        v_ds: -0.05,
+        v_gs: 0.5,
+    },
+};
+
+/// Frequency of the crystal oscillator of the Pico.
+const XTAL_FREQ_HZ: u32 = 12_000_000;
+
+/// Number of chunks of the range of concentrations, one per core.
+const CORES: usize = 2;
+
+/// Stack of core 1.
+static CORE1_STACK: Stack<4096> = Stack::new();
+
+/// Solves a chunk of the range of concentrations.
+fn solve(currents: Currents, index: usize) -> Option<(Variables, f32)> {
+    let model = Equation::new(MODEL_PARAMS, currents);
+    let algorithm: BruteForceEquation<_, Absolute> =
+        BruteForceEquation::new(ALG_PARAMS.split(CORES, index), model);
+    algorithm.run()
+}
+
+/// Writes a sequence of `f32` to the FIFO.
+fn write_floats(fifo: &mut SioFifo, values: &[f32]) {
+    for value in values {
+        fifo.write_blocking(value.to_bits());
+    }
+}
+
+/// Reads a sequence of `f32` from the FIFO.
+fn read_floats<const N: usize>(fifo: &mut SioFifo) -> [f32; N] {
+    [(); N].map(|_| f32::from_bits(fifo.read_blocking()))
+}
+
+/// Solves the second chunk for every currents received from core 0.
+fn core1_task() -> ! {
+    // SAFETY: core 1 only uses its own side of the FIFO.
+    let pac = unsafe { pac::Peripherals::steal() };
+    let mut fifo = Sio::new(pac.SIO).fifo;
+
+    loop {
+        let [i_ds_off, i_ds_on, i_gs_on] = read_floats(&mut fifo);
+        let currents = Currents {
+            i_ds_off,
+            i_ds_on,
+            i_gs_on,
+        };
+
+        // A NaN loss means that no solution has been found.
+        let (vars, loss) = solve(currents, 1).unwrap_or((
+            Variables {
+                concentration: f32::NAN,
+                resistance: f32::NAN,
+                saturation: f32::NAN,
+            },
+            f32::NAN,
+        ));
+        write_floats(
+            &mut fifo,
+            &[vars.concentration, vars.resistance, vars.saturation, loss],
+        );
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    let mut pac = pac::Peripherals::take().unwrap();
+
+    let mut watchdog = Watchdog::new(pac.WATCHDOG);
+    let clocks = init_clocks_and_plls(
+        XTAL_FREQ_HZ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .ok()
+    .unwrap();
+    let timer = Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
+
+    let mut sio = Sio::new(pac.SIO);
+    let mut mc = Multicore::new(&mut pac.PSM, &mut pac.PPB, &mut sio.fifo);
+    let cores = mc.cores();
+    cores[1]
+        .spawn(CORE1_STACK.take().unwrap(), || core1_task())
+        .unwrap();
+
+    defmt::info!(
+        "Bioristor dual-core brute force at {} Hz",
+        clocks.system_clock.freq().to_Hz()
+    );
+
+    let currents = core::hint::black_box(Currents {
+        i_ds_on: -0.0026829,
+        i_ds_off: -0.0030365,
+        i_gs_on: 1.169828e-6,
+    });
+
+    // Solve the whole grid on core 0, as a reference.
+    let start = timer.get_counter();
+    let reference =
+        BruteForceEquation::<_, Absolute>::new(ALG_PARAMS, Equation::new(MODEL_PARAMS, currents))
+            .run();
+    let single = (timer.get_counter() - start).to_micros();
+
+    // Solve one chunk per core.
+    let start = timer.get_counter();
+    write_floats(
+        &mut sio.fifo,
+        &[currents.i_ds_off, currents.i_ds_on, currents.i_gs_on],
+    );
+    let first = solve(currents, 0);
+    let [concentration, resistance, saturation, loss] = read_floats(&mut sio.fifo);
+    let second = (!loss.is_nan()).then_some((
+        Variables {
+            concentration,
+            resistance,
+            saturation,
+        },
+        loss,
+    ));
+    let res = merge([first, second]);
+    let dual = (timer.get_counter() - start).to_micros();
+
+    match res {
+        Some((variables, error)) => {
+            defmt::info!("Solution found: {}, error: {}", variables, error);
+        }
+        None => {
+            defmt::warn!("No solution found");
+        }
+    }
+    if let Some((variables, error)) = reference {
+        defmt::debug!("Single core solution: {}, error: {}", variables, error);
+    }
+    defmt::info!("Single core: {} us, dual core: {} us", single, dual);
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}